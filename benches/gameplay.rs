@@ -0,0 +1,192 @@
+//! Hot gameplay paths, only the gameplay core is used
+//! so it runs without a GPU or a sound card:
+//!
+//! ```text
+//! cargo bench --no-default-features --bench gameplay
+//! ```
+//!
+//! Fixtures are shared with `tests/`, nothing bench-only is bundled.
+//! Baseline numbers are kept in the table below, update it along with
+//! changes that are expected to move them and mention the machine.
+//!
+//! | benchmark                          | baseline |
+//! |------------------------------------|----------|
+//! | convert/marathon                   |          |
+//! | process_all/gin_no_kaze            |          |
+//! | prepare_window/marathon            |          |
+//! | slider/checkpoint_times            |          |
+//! | slider/curve_lut                   |          |
+//! | slider/bounding_box                |          |
+
+use std::{hint::black_box, path::Path};
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use osu_replay_parser::replay::Replay;
+use rosu::{hit_objects::{converter::checkpoint_times, hit_window::HitWindow, slider::CurveLut, Object, ObjectKind}, math::{calc_circle_approach, calc_hidden_alpha, calc_hitcircle_diameter, calc_slider_body_alpha, calculate_preempt_fadein}, processor::{rules::GameplayRules, OsuProcessor}};
+use rosu_map::Beatmap;
+
+/// ~10 minutes of streams and long sliders
+const MARATHON: &str = "tests/data/songs_folder/953303 Our Stolen Theory - United (LAOS Remix)/Our Stolen Theory - United (L.A.O.S Remix) (Sotarks) [Eternity].osu";
+
+const GAMEPLAY_DATA: &str = "tests/data/gameplay";
+
+/// Length of the window `prepare_window` is measured on, ms
+const DENSE_WINDOW: f64 = 1000.0;
+
+fn open_beatmap(path: impl AsRef<Path>) -> Beatmap {
+    Beatmap::from_path(path.as_ref())
+        .unwrap_or_else(|e| panic!("failed to open {}: {e}", path.as_ref().display()))
+}
+
+fn bench_convert(c: &mut Criterion) {
+    let beatmap = open_beatmap(MARATHON);
+
+    c.bench_function("convert/marathon", |b| {
+        b.iter(|| Object::from_rosu(black_box(&beatmap)).unwrap())
+    });
+}
+
+/// Whole replay judged at once, same as the gameplay tests do it
+fn bench_process_all(c: &mut Criterion) {
+    let base = Path::new(GAMEPLAY_DATA);
+    let beatmap = open_beatmap(base.join("gin_no_kaze.osu"));
+    let replay_path = base.join("gin_no_kaze.osr");
+
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+    let circle_diameter = calc_hitcircle_diameter(beatmap.circle_size);
+    let rules = GameplayRules::default();
+
+    c.bench_function("process_all/gin_no_kaze", |b| {
+        b.iter_batched(
+            || {
+                let processor: OsuProcessor = Replay::open(&replay_path).unwrap().into();
+                (processor, Object::from_rosu(&beatmap).unwrap())
+            },
+            |(mut processor, mut objects)| {
+                processor.process_all(&mut objects, &hit_window, circle_diameter, &rules);
+                processor
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+/// Start of the [`DENSE_WINDOW`] with the most objects starting in it
+fn densest_window(objects: &[Object]) -> f64 {
+    let mut best = (0, 0.0);
+    let mut first = 0;
+
+    for (i, object) in objects.iter().enumerate() {
+        while object.start_time - objects[first].start_time > DENSE_WINDOW {
+            first += 1;
+        }
+
+        if i + 1 - first > best.0 {
+            best = (i + 1 - first, objects[first].start_time);
+        }
+    }
+
+    best.1
+}
+
+/// CPU side of `OsuRenderer::prepare_objects` over the densest second:
+/// visibility scan of the whole map and every per-object animation value
+/// instance data is built from. Buffers themselves need the renderer
+fn bench_prepare_window(c: &mut Criterion) {
+    let beatmap = open_beatmap(MARATHON);
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+    objects.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    let (preempt, fadein) = calculate_preempt_fadein(beatmap.approach_rate);
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+
+    let start = densest_window(&objects);
+    let frames: Vec<f64> = (0..DENSE_WINDOW as usize / 4)
+        .map(|i| start + i as f64 * 4.0)
+        .collect();
+
+    let mut queue = Vec::with_capacity(objects.len());
+
+    c.bench_function("prepare_window/marathon", |b| {
+        b.iter(|| {
+            let mut acc = 0.0;
+
+            for &time in &frames {
+                queue.clear();
+                queue.extend(
+                    objects.iter()
+                        .enumerate()
+                        .rev()
+                        .filter(|(_, x)| x.is_visible(time, preempt, &hit_window))
+                        .map(|(i, _)| i),
+                );
+
+                for &i in &queue {
+                    match &objects[i].kind {
+                        ObjectKind::Circle(circle) => {
+                            let approach = calc_circle_approach(
+                                time, circle.start_time, preempt, fadein, None, hit_window.x50,
+                            );
+
+                            acc += approach.map(|x| x.0 + x.1).unwrap_or(0.0);
+                            acc += calc_hidden_alpha(time, circle.start_time, preempt);
+                        },
+                        ObjectKind::Slider(slider) => {
+                            acc += calc_slider_body_alpha(time, slider.start_time, slider.end_time(), preempt, fadein);
+
+                            let follow = slider.position_at(slider.get_slider_progress(time));
+                            acc += (follow.x + follow.y) as f64;
+                        },
+                    }
+                }
+            }
+
+            black_box(acc)
+        })
+    });
+}
+
+fn bench_slider(c: &mut Criterion) {
+    let beatmap = open_beatmap(MARATHON);
+    let objects = Object::from_rosu(&beatmap).unwrap();
+    let radius = calc_hitcircle_diameter(beatmap.circle_size) / 2.0;
+
+    let sliders: Vec<_> = objects.iter()
+        .filter_map(|x| match &x.kind {
+            ObjectKind::Slider(slider) => Some(slider),
+            _ => None,
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("slider");
+
+    group.bench_function("checkpoint_times", |b| {
+        b.iter(|| {
+            for slider in &sliders {
+                let span_duration = slider.duration / slider.repeats as f64;
+                black_box(checkpoint_times(slider.start_time, span_duration, slider.repeats as usize, span_duration / 4.0));
+            }
+        })
+    });
+
+    group.bench_function("curve_lut", |b| {
+        b.iter(|| {
+            for slider in &sliders {
+                black_box(CurveLut::new(&slider.curve));
+            }
+        })
+    });
+
+    group.bench_function("bounding_box", |b| {
+        b.iter(|| {
+            for slider in &sliders {
+                black_box(slider.bounding_box(radius));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_convert, bench_process_all, bench_prepare_window, bench_slider);
+criterion_main!(benches);
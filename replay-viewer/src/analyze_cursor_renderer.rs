@@ -49,8 +49,8 @@ impl PointsInstance {
 }
 
 
-pub struct AnalyzeCursorRenderer<'acr> {
-    graphics: Arc<Graphics<'acr>>,
+pub struct AnalyzeCursorRenderer {
+    graphics: Arc<Graphics>,
 
     /// Points
     pub points_pipeline: RenderPipeline,
@@ -63,9 +63,9 @@ pub struct AnalyzeCursorRenderer<'acr> {
     points_instance_data: Vec<PointsInstance>,
 }
 
-impl<'acr> AnalyzeCursorRenderer<'acr> {
-    pub fn new(graphics: Arc<Graphics<'acr>>) -> Self {
-        let surface_config = graphics.get_surface_config();
+impl AnalyzeCursorRenderer {
+    pub fn new(graphics: Arc<Graphics>) -> Self {
+        let format = graphics.format();
         
 
         let vertex_line = vec![Vertex {
@@ -147,7 +147,7 @@ impl<'acr> AnalyzeCursorRenderer<'acr> {
                         module: &lines_shader,
                         entry_point: Some("fs_main"),
                         targets: &[Some(wgpu::ColorTargetState {
-                            format: surface_config.format,
+                            format,
                             blend: Some(wgpu::BlendState {
                                 color: wgpu::BlendComponent {
                                     src_factor: wgpu::BlendFactor::SrcAlpha,
@@ -210,7 +210,7 @@ impl<'acr> AnalyzeCursorRenderer<'acr> {
                         module: &point_shader,
                         entry_point: Some("fs_main"),
                         targets: &[Some(wgpu::ColorTargetState {
-                            format: surface_config.format,
+                            format,
                             blend: Some(wgpu::BlendState {
                                 color: wgpu::BlendComponent {
                                     src_factor: wgpu::BlendFactor::SrcAlpha,
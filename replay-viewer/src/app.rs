@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use egui_wgpu::wgpu::RequestAdapterOptionsBase;
-use rosu::{egui_state::EguiState, graphics::{Graphics, GraphicsInitialized}};
+use rosu::{egui_state::EguiState, graphics::{Graphics, GraphicsInitialized, DEFAULT_WINDOW_SIZE}};
 use wgpu::{InstanceDescriptor, PowerPreference, Surface};
 use winit::{application::ApplicationHandler, dpi::PhysicalSize, event_loop::EventLoopProxy, window::{Theme, Window}};
 
@@ -9,19 +9,19 @@ use crate::state::ReplayViewerState;
 
 
 pub enum AppEvents {
-    GraphicsInitialized(Arc<Graphics<'static>>),
+    GraphicsInitialized(Arc<Graphics>),
 }
 
-pub struct App<'app> {
+pub struct App {
     window: Option<Arc<Window>>,
     // Used as oneshot channel to initialize graphics in async manner
     proxy: Option<EventLoopProxy<AppEvents>>,
-    graphics: Option<Arc<Graphics<'app>>>,
-    replay_state: Option<ReplayViewerState<'app>>,
+    graphics: Option<Arc<Graphics>>,
+    replay_state: Option<ReplayViewerState>,
     egui_state: Option<EguiState>,
 }
 
-impl<'app> App<'app> {
+impl App {
     pub fn new(proxy: EventLoopProxy<AppEvents>) -> Self {
         Self {
             window: None,
@@ -33,9 +33,10 @@ impl<'app> App<'app> {
     }
 }
 
-impl<'app> ApplicationHandler<AppEvents> for App<'app> {
+impl ApplicationHandler<AppEvents> for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        let attrs = Window::default_attributes();
+        let attrs = Window::default_attributes()
+            .with_inner_size(DEFAULT_WINDOW_SIZE);
 
         let window = Arc::new(event_loop.create_window(attrs).unwrap());
 
@@ -55,7 +56,18 @@ impl<'app> ApplicationHandler<AppEvents> for App<'app> {
         };
 
         if let Some(egui_state) = &mut self.egui_state {
-            if egui_state.on_window_event(&event, &window).consumed {
+            let consumed = egui_state.on_window_event(&event, &window).consumed;
+
+            // Releases and cursor positions always go through, otherwise
+            // a drag released over the side panel never ends and camera
+            // jumps by the distance travelled over it
+            let passthrough = matches!(
+                event,
+                winit::event::WindowEvent::CursorMoved { .. }
+                | winit::event::WindowEvent::MouseInput { state: winit::event::ElementState::Released, .. }
+            );
+
+            if consumed && !passthrough {
                 return;
             };
         }
@@ -110,6 +122,7 @@ impl<'app> ApplicationHandler<AppEvents> for App<'app> {
 
 
                         let graphics_initialized = GraphicsInitialized {
+                            instance,
                             surface,
                             device,
                             queue,
@@ -133,6 +146,20 @@ impl<'app> ApplicationHandler<AppEvents> for App<'app> {
                     replay_state.on_resize(&physical_size)
                 }
             },
+            // Egui already picked up the new pixels per point above,
+            // surface and camera still have the old physical size
+            winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                let new_size = window.inner_size();
+                tracing::info!("Scale factor changed to {scale_factor}, new size: {}x{}", new_size.width, new_size.height);
+
+                if let Some(graphics) = &self.graphics {
+                    graphics.resize(&new_size)
+                }
+
+                if let Some(replay_state) = &mut self.replay_state {
+                    replay_state.on_resize(&new_size)
+                }
+            },
             winit::event::WindowEvent::RedrawRequested => {
                 let (Some(graphics), Some(egui_state), Some(replay_state)) = (
                     self.graphics.as_ref(),
@@ -172,9 +199,19 @@ impl<'app> ApplicationHandler<AppEvents> for App<'app> {
 
                 output.present();
             },
+            winit::event::WindowEvent::HoveredFile(_) => {
+                if let Some(state) = &mut self.replay_state {
+                    state.on_file_hovered(true)
+                }
+            },
+            winit::event::WindowEvent::HoveredFileCancelled => {
+                if let Some(state) = &mut self.replay_state {
+                    state.on_file_hovered(false)
+                }
+            },
             winit::event::WindowEvent::DroppedFile(path) => {
                 if let Some(state) = &mut self.replay_state {
-                    state.open_replay(path)
+                    state.on_file_dropped(path)
                 }
             },
             winit::event::WindowEvent::KeyboardInput { event, .. } => {
@@ -9,18 +9,38 @@ use std::{path::{Path, PathBuf}, sync::{mpsc::{Receiver, Sender}, Arc}};
 use cgmath::Vector2;
 use egui::Modal;
 use osu_replay_parser::replay::Replay;
-use rosu::{camera::Camera, config::Config, graphics::Graphics, hit_objects::{hit_window::HitWindow, Hit, Object, ObjectKind}, math::{calc_hitcircle_diameter, calc_playfield, calculate_preempt_fadein}, osu_db::{OsuDatabase, DEFAULT_DB_PATH}, osu_renderer::{OsuRenderer, QUAD_INDECIES}, processor::OsuProcessor, rgb::{mix_colors_linear, Rgb}, skin_manager::SkinManager, timer::Timer, vertex::Vertex};
-use rosu_map::Beatmap;
+#[cfg(feature = "render-stats")]
+use rosu::render_stats::{render_stats_ui, RenderStats};
+use rosu::{camera::Camera, config::Config, gameplay_renderer::{GameplayFrame, GameplayRenderer}, graphics::Graphics, hit_objects::{breaks::Break, hit_window::HitWindow, Hit, Object, ObjectKind}, math::{angle_between, calc_hitcircle_diameter, calc_playfield, calculate_preempt_fadein}, osu_db::{OsuDatabase, DEFAULT_DB_PATH}, osu_renderer::{OsuRenderer, QUAD_INDECIES}, processor::{rules::GameplayRules, OsuProcessor}, accuracy_graph::accuracy_graph, aim_scatter::aim_scatter, dropped_file::{route_dropped_file, DroppedFileKind}, score::{Score, GRAPH_POINTS}, rgb::{mix_colors_linear, Rgb}, skin_manager::SkinManager, timer::Timer, vertex::Vertex};
+use rosu_map::{util::Pos, Beatmap};
 use wgpu::{util::DeviceExt, BindGroup, BufferUsages, TextureView};
 use winit::{dpi::{PhysicalPosition, PhysicalSize}, event::MouseButton, keyboard::KeyCode};
 
-use crate::{analyze_cursor_renderer::{AnalyzeCursorRenderer, PointsInstance}, judgements_list::JudgementPoint, replay_log::ReplayLog};
+use crate::{analyze_cursor_renderer::{AnalyzeCursorRenderer, PointsInstance}, judgements_list::JudgementPoint, replay_log::ReplayLog, waveform::{waveform_strip, Waveform, WaveformCache}};
 
 enum ReplayViewerEvents {
     OpenReplay(PathBuf),
+    LocateBeatmap(PathBuf),
     ScanBeatmaps(PathBuf),
     ResetModal,
     UpdateReplayPositionByTime(f64),
+    WaveformLoaded(PathBuf, Arc<Waveform>),
+}
+
+/// Playback rates available as quick buttons
+const PLAYBACK_RATES: [f64; 6] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0];
+
+/// Annotation label height in osu!px, so labels grow with the zoom
+const ANNOTATION_FONT_SIZE: f32 = 9.0;
+/// Label height limits in egui points
+const ANNOTATION_FONT_RANGE: std::ops::RangeInclusive<f32> = 10.0..=48.0;
+
+/// Which objects get spacing and angle labels
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnnotationDensity {
+    Every,
+    /// Only the object under the cursor and its jumps
+    Selected,
 }
 
 pub struct ReplayViewerSettings {
@@ -32,15 +52,32 @@ pub struct ReplayViewerSettings {
     k2_color: [u8; 3],
     m1_color: [u8; 3],
     m2_color: [u8; 3],
+
+    /// Lines between consecutive visible objects
+    /// labeled with spacing and angles
+    show_annotations: bool,
+    annotation_density: AnnotationDensity,
 }
 
-pub struct ReplayViewerState<'rvs> {
+pub struct ReplayViewerState {
     db: OsuDatabase,
 
-    graphics: Arc<Graphics<'rvs>>,
+    graphics: Arc<Graphics>,
     replay: Option<ReplayLog>,
+    /// Opened replay file, it's judged again once its beatmap is located
+    replay_path: Option<PathBuf>,
+    /// Beatmap hash of the opened replay that is not in the database,
+    /// only cursor data is shown until it's located
+    missing_beatmap: Option<String>,
     judgements_list: Option<Vec<JudgementPoint>>,
-    cursor_renderer: AnalyzeCursorRenderer<'rvs>,
+    accuracy_points: Vec<(f64, f64)>,
+    /// Score of the opened replay, used for aim analysis
+    score: Score,
+    /// Audio of the opened beatmap, `None` while loading or if it failed
+    waveform: Option<Arc<Waveform>>,
+    waveform_audio_path: Option<PathBuf>,
+    waveform_cache: WaveformCache,
+    cursor_renderer: AnalyzeCursorRenderer,
     
     playing: bool,
     slider_time: f64,
@@ -50,7 +87,7 @@ pub struct ReplayViewerState<'rvs> {
     gameplay_config: Config,
     skin_manager: SkinManager,
 
-    osu_renderer: OsuRenderer<'rvs>,
+    osu_renderer: OsuRenderer,
 
     /// TODO
     camera: Camera,
@@ -68,7 +105,8 @@ pub struct ReplayViewerState<'rvs> {
     fadein: f32,
     hit_window: HitWindow,
     objects: Option<Vec<Object>>,
-    objects_render_queue: Vec<usize>,
+    breaks: Vec<Break>,
+    gameplay_renderer: GameplayRenderer,
 
     zoom: f32,
     offsets: Vector2<f32>,
@@ -78,6 +116,11 @@ pub struct ReplayViewerState<'rvs> {
 
     // Stupid egui handling lmao
     modal_text: Option<String>,
+    is_file_hovered: bool,
+
+    /// Renderer counters of the last rendered frame
+    #[cfg(feature = "render-stats")]
+    render_stats: RenderStats,
 
     // Events
     tx: Sender<ReplayViewerEvents>,
@@ -86,8 +129,8 @@ pub struct ReplayViewerState<'rvs> {
     circle_diameter: f32,
 }
 
-impl<'rvs> ReplayViewerState<'rvs> {
-    pub fn new(graphics: Arc<Graphics<'rvs>>) -> Self {
+impl ReplayViewerState {
+    pub fn new(graphics: Arc<Graphics>) -> Self {
         let (graphics_width, graphics_height) = graphics.get_surface_size();
         let camera = Camera::new(
             &graphics,
@@ -158,6 +201,8 @@ impl<'rvs> ReplayViewerState<'rvs> {
         Self {
             time: Timer::new(),
             replay: None,
+            replay_path: None,
+            missing_beatmap: None,
             camera,
             cursor_renderer: AnalyzeCursorRenderer::new(graphics.clone()),
             osu_renderer: OsuRenderer::new(graphics.clone(), &Config::default()),
@@ -173,6 +218,8 @@ impl<'rvs> ReplayViewerState<'rvs> {
                 k2_color: [252, 12, 12],
                 m1_color: [51, 255, 255],
                 m2_color: [255, 51, 255],
+                show_annotations: false,
+                annotation_density: AnnotationDensity::Every,
             },
             replay_frame_end_idx: 0,
             replay_frame_start_idx: 0,
@@ -180,10 +227,16 @@ impl<'rvs> ReplayViewerState<'rvs> {
             fadein: 0.0,
             hit_window: HitWindow::default(),
             objects: None,
+            breaks: Vec::new(),
             gameplay_config: config,
             skin_manager,
-            objects_render_queue: Vec::with_capacity(10),
+            gameplay_renderer: GameplayRenderer::new(false),
             slider_time: 0.0,
+            accuracy_points: Vec::new(),
+            score: Score::default(),
+            waveform: None,
+            waveform_audio_path: None,
+            waveform_cache: WaveformCache::default(),
             playing: false,
             zoom: 1.0,
             offsets: Vector2::new(1.0, 1.0),
@@ -191,6 +244,9 @@ impl<'rvs> ReplayViewerState<'rvs> {
             left_mouse_holding: false,
             db: OsuDatabase::new_from_path(DEFAULT_DB_PATH).unwrap(),
             modal_text: None,
+            is_file_hovered: false,
+            #[cfg(feature = "render-stats")]
+            render_stats: RenderStats::default(),
             tx,
             rx,
             circle_diameter: 4.0,
@@ -215,30 +271,80 @@ impl<'rvs> ReplayViewerState<'rvs> {
 
         let (preempt, fadein) = calculate_preempt_fadein(map.approach_rate);
         let hit_window = HitWindow::from_od(map.overall_difficulty);
-        let out_objects = Object::from_rosu(&map);
+        let out_objects = match Object::from_rosu(&map) {
+            Ok(objects) => objects,
+            Err(e) => {
+                tracing::error!("Failed to convert beatmap objects: {e}");
+                self.modal_text = Some(format!("Can't open beatmap: {e}"));
+                return;
+            }
+        };
 
         self.preempt = preempt;
         self.fadein = fadein;
         self.hit_window = hit_window;
         self.circle_diameter = calc_hitcircle_diameter(cs);
+
+        // Sized once so the queue doesn't regrow on dense parts
+        self.gameplay_renderer.reserve(out_objects.len());
         self.objects = Some(out_objects);
+        self.breaks = Break::from_rosu(&map);
+
+        let audio_path = beatmap_path.parent()
+            .map(|dir| dir.join(&map.audio_file));
+
+        self.spawn_waveform_loader(audio_path);
     }
 
-    pub fn open_replay(&mut self, replay_path: impl AsRef<Path>) {
-        let Ok(replay) = Replay::open(&replay_path.as_ref()) else {
-            self.modal_text = Some("Can't open replay file".to_owned());
+    fn spawn_waveform_loader(&mut self, audio_path: Option<PathBuf>) {
+        self.waveform = None;
+        self.waveform_audio_path = audio_path.clone();
+
+        let Some(audio_path) = audio_path else {
             return;
         };
 
-        let mut processor: OsuProcessor = Replay::open(&replay_path.as_ref()).unwrap().into();
+        let tx = self.tx.clone();
+        let cache = self.waveform_cache.clone();
+
+        std::thread::spawn(move || {
+            match cache.load(&audio_path) {
+                Ok(waveform) => {
+                    let _ = tx.send(ReplayViewerEvents::WaveformLoaded(audio_path, waveform));
+                },
+                // Strip is simply not shown
+                Err(e) => tracing::warn!("Failed to load waveform of {}: {e}", audio_path.display()),
+            }
+        });
+    }
 
-        let Some(beatmap_entry) = self.db.get_beatmap_by_hash(&replay.map_hash) else {
-            self.modal_text = Some("Can't find a beatmap for that replay".to_owned());
+    /// Cursor data is shown even if the beatmap is not found,
+    /// it can be located later without reopening the replay
+    pub fn open_replay(&mut self, replay_path: impl AsRef<Path>) {
+        let Ok(replay) = Replay::open(&replay_path.as_ref()) else {
+            self.modal_text = Some("Can't open replay file".to_owned());
             return;
         };
 
-        self.open_beatmap(beatmap_entry.path);
+        let beatmap_entry = self.db.get_beatmap_by_hash(&replay.map_hash);
+
+        // Leftovers of the previous replay
+        self.objects = None;
+        self.breaks.clear();
+        self.judgements_list = None;
+        self.accuracy_points.clear();
+        self.score = Score::default();
+        self.spawn_waveform_loader(None);
+
+        match &beatmap_entry {
+            Some(_) => self.missing_beatmap = None,
+            None => {
+                tracing::warn!("No beatmap with hash {}, showing cursor data only", replay.map_hash);
+                self.missing_beatmap = Some(replay.map_hash.clone());
+            },
+        }
 
+        self.replay_path = Some(replay_path.as_ref().to_path_buf());
         self.replay = Some(replay.into());
 
         self.time.reset_time();
@@ -247,35 +353,104 @@ impl<'rvs> ReplayViewerState<'rvs> {
         self.update_replay_position_by_time();
         self.playing = false;
 
-        if let Some(objects) = &mut self.objects {
-            processor.process_all(
-                objects,
-                &self.hit_window,
-                self.circle_diameter
-            );
+        if let Some(beatmap_entry) = beatmap_entry {
+            self.open_beatmap(beatmap_entry.path);
+            self.analyze_replay();
+        }
+    }
 
-            let mut judgements_list: Vec<JudgementPoint> = Vec::new();
-
-            for obj in objects.iter() {
-                match &obj.kind {
-                    ObjectKind::Circle(circle) => {
-                        if let Some(result) = &circle.hit_result {
-                            if result.result != Hit::X300 {
-                                judgements_list.push(JudgementPoint {
-                                    ts: circle.start_time,
-                                    kind: crate::judgements_list::JudgementObjectKind::Circle,
-                                    hit: result.result,
-                                })
-                            } else { continue; }
-                        }
-                    },
-                    ObjectKind::Slider(_) => continue,
-                }
-            };
+    /// Upgrades beatmap-less session in place, camera
+    /// and current time stay where they were
+    fn locate_beatmap(&mut self, beatmap_path: PathBuf) {
+        let _span = tracy_client::span!("state::locate_beatmap");
 
-            self.judgements_list = Some(judgements_list);
+        let Some(hash) = self.missing_beatmap.clone() else {
+            return;
+        };
+
+        let matches = std::fs::read(&beatmap_path)
+            .map(|bytes| format!("{:x}", md5::compute(bytes)) == hash)
+            .unwrap_or(false);
+
+        self.open_beatmap(beatmap_path);
+
+        if self.objects.is_none() {
+            return;
         }
 
+        // Re-saved beatmaps have a different hash but are
+        // usually fine, so it's still loaded with a warning
+        if !matches {
+            self.modal_text = Some("Beatmap doesn't match the replay, judgements may be off".to_owned());
+        }
+
+        self.missing_beatmap = None;
+        self.analyze_replay();
+    }
+
+    /// Judges opened replay on the opened beatmap
+    fn analyze_replay(&mut self) {
+        let _span = tracy_client::span!("state::analyze_replay");
+
+        let (Some(objects), Some(replay_path)) = (&mut self.objects, &self.replay_path) else {
+            return;
+        };
+
+        let Ok(replay) = Replay::open(replay_path) else {
+            self.modal_text = Some("Can't open replay file".to_owned());
+            return;
+        };
+
+        let mut processor: OsuProcessor = replay.into();
+
+        processor.process_all(
+            objects,
+            &self.hit_window,
+            self.circle_diameter,
+            &GameplayRules::default(),
+        );
+
+        self.accuracy_points = processor.score().downsampled_accuracy(GRAPH_POINTS);
+        self.score = processor.take_score();
+
+        let mut judgements_list: Vec<JudgementPoint> = Vec::new();
+
+        for obj in objects.iter() {
+            match &obj.kind {
+                ObjectKind::Circle(circle) => {
+                    if let Some(result) = &circle.hit_result {
+                        if result.result != Hit::X300 {
+                            judgements_list.push(JudgementPoint {
+                                ts: circle.start_time,
+                                kind: crate::judgements_list::JudgementObjectKind::Circle,
+                                hit: result.result,
+                            })
+                        } else { continue; }
+                    }
+                },
+                ObjectKind::Slider(_) => continue,
+            }
+        };
+
+        self.judgements_list = Some(judgements_list);
+    }
+
+    pub fn on_file_hovered(&mut self, is_hovered: bool) {
+        self.is_file_hovered = is_hovered;
+    }
+
+    /// Only replays can be opened here, each dropped
+    /// file arrives separately so the last one wins
+    pub fn on_file_dropped(&mut self, path: impl AsRef<Path>) {
+        self.is_file_hovered = false;
+
+        match route_dropped_file(&path) {
+            DroppedFileKind::Replay => self.open_replay(path),
+            kind => {
+                tracing::warn!("Ignoring dropped {kind:?} file: {}", path.as_ref().display());
+                self.modal_text = Some("Only .osr replays can be opened here".to_owned());
+            },
+        }
     }
 
     pub fn sync_cursor(&mut self) {
@@ -350,10 +525,11 @@ impl<'rvs> ReplayViewerState<'rvs> {
 
         self.handle_events();
 
-        if self.objects.is_none() || self.replay.is_none() {
+        if self.replay.is_none() {
             return
         }
 
+        // Does nothing without a beatmap, cursor data is still drawn
         self.render_gameplay_objects(view);
 
         let mut encoder =
@@ -494,56 +670,155 @@ impl<'rvs> ReplayViewerState<'rvs> {
     fn render_gameplay_objects(&mut self,  view: &TextureView) {
         let _span = tracy_client::span!("state::render_gameplay_objects");
 
-        // 1. Prepare all objects
         let Some(objects) = &mut self.objects else {
             return;
         };
 
-        for (i, obj) in objects.iter_mut().enumerate().rev() {
-            if !obj.is_visible(self.time.get_time(), self.preempt, &self.hit_window) {
+        let frame = GameplayFrame {
+            time: self.time.get_time(),
+            preempt: self.preempt,
+            fadein: self.fadein,
+            hit_window: &self.hit_window,
+        };
+
+        self.gameplay_renderer.prepare(&mut self.osu_renderer, &frame, objects);
+        self.gameplay_renderer.render(&mut self.osu_renderer, view, objects).unwrap();
+
+        #[cfg(feature = "render-stats")]
+        {
+            self.render_stats = self.osu_renderer.finish_frame_stats();
+        }
+    }
+
+    /// Draws jumps between consecutive visible objects with their
+    /// spacing in osu!px and the angle at the middle object
+    fn render_annotations(&self, ctx: &egui::Context) {
+        let _span = tracy_client::span!("state::render_annotations");
+
+        if !self.settings.show_annotations {
+            return;
+        }
+
+        let Some(objects) = &self.objects else {
+            return;
+        };
+
+        let time = self.time.get_time();
+        let visible: Vec<usize> = objects.iter()
+            .enumerate()
+            .filter(|(_, obj)| obj.is_visible(time, self.preempt, &self.hit_window))
+            .map(|(i, _)| i)
+            .collect();
+
+        if visible.is_empty() {
+            return;
+        }
+
+        let pixels_per_point = ctx.pixels_per_point();
+        let to_screen = |pos: Pos| {
+            let pos = Vector2::new(pos.x, pos.y) * self.camera.scale + self.camera.offsets;
+            egui::pos2(pos.x / pixels_per_point, pos.y / pixels_per_point)
+        };
+        let to_vector = |pos: Pos| Vector2::new(pos.x, pos.y);
+
+        let density = self.settings.annotation_density;
+        let hovered = (density == AnnotationDensity::Selected)
+            .then(|| {
+                let cursor = self.camera.screen_to_world(self.mouse_pos);
+                let radius = self.circle_diameter / 2.0;
+
+                visible.iter().copied().find(|&i| {
+                    let pos = objects[i].position();
+                    (pos.x - cursor.x).hypot(pos.y - cursor.y) <= radius
+                })
+            })
+            .flatten();
+        let is_labeled = |i: usize| density == AnnotationDensity::Every || hovered == Some(i);
+
+        let font = egui::FontId::proportional(
+            (ANNOTATION_FONT_SIZE * self.camera.scale / pixels_per_point)
+                .clamp(*ANNOTATION_FONT_RANGE.start(), *ANNOTATION_FONT_RANGE.end())
+        );
+        let radius = self.circle_diameter / 2.0 * self.camera.scale / pixels_per_point;
+
+        // Clipped so panels stay on top of the annotations
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Background,
+            egui::Id::new("annotations"),
+        )).with_clip_rect(ctx.available_rect());
+
+        let line = egui::Stroke::new(2.0, egui::Color32::from_white_alpha(120));
+
+        for &i in &visible {
+            if !visible.contains(&(i + 1)) {
                 continue;
             }
 
-            match &mut obj.kind {
-                ObjectKind::Slider(slider) => {
-                    self.osu_renderer.prepare_and_render_slider_texture(
-                        slider, 
-                        &self.skin_manager, 
-                        &self.gameplay_config
-                    );
-                }
-                _ => {},
-            }
+            let from = objects[i].position();
+            let to = objects[i + 1].position();
+            let (start, end) = (to_screen(from), to_screen(to));
 
-            self.objects_render_queue.push(i);
+            painter.line_segment([start, end], line);
+
+            if is_labeled(i) || is_labeled(i + 1) {
+                let distance = (to.x - from.x).hypot(to.y - from.y);
+
+                painter.text(
+                    start.lerp(end, 0.5),
+                    egui::Align2::CENTER_CENTER,
+                    format!("{distance:.0}"),
+                    font.clone(),
+                    egui::Color32::WHITE,
+                );
+            }
         }
 
-        self.osu_renderer.prepare_objects(
-            self.time.get_time(), self.preempt, self.fadein,
-            &self.objects_render_queue, &objects,
-            &self.skin_manager,
-            &self.gameplay_config
-        );
+        for &i in &visible {
+            if i == 0 || !is_labeled(i) {
+                continue;
+            }
 
-        self.osu_renderer.prepare(
-            &self.gameplay_config
-        );
+            let Some(next) = objects.get(i + 1) else {
+                continue;
+            };
 
-        self.osu_renderer.write_buffers();
+            let prev = objects[i - 1].position();
+            let cur = objects[i].position();
 
-        // 2. Rendering
-        self.osu_renderer.render_objects(
-            &view,
-            &self.objects_render_queue, &objects,
-            &self.skin_manager,
-        ).unwrap();
+            // Stacked objects have no angle
+            let Some(angle) = angle_between(to_vector(prev), to_vector(cur), to_vector(next.position())) else {
+                continue;
+            };
 
-        self.objects_render_queue.clear();
+            painter.text(
+                to_screen(cur) - egui::vec2(0.0, radius),
+                egui::Align2::CENTER_BOTTOM,
+                format!("{angle:.0}°"),
+                font.clone(),
+                egui::Color32::from_rgb(255, 220, 90),
+            );
+        }
     }
 
     pub fn render_ui(&mut self, ctx: &egui::Context) {
         let _span = tracy_client::span!("state::render_ui");
 
+        if self.is_file_hovered {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("drop_overlay"),
+            ));
+
+            painter.rect_filled(ctx.screen_rect(), 0.0, egui::Color32::from_black_alpha(120));
+            painter.text(
+                ctx.screen_rect().center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop to open replay",
+                egui::FontId::proportional(32.0),
+                egui::Color32::from_white_alpha(200),
+            );
+        }
+
         if let Some(judgements_list) = &self.judgements_list {
             egui::Window::new("Hit Results").show(ctx, |ui| {
                 egui::ScrollArea::vertical().show(ui, |ui| {
@@ -567,6 +842,18 @@ impl<'rvs> ReplayViewerState<'rvs> {
             });
         };
 
+        if self.missing_beatmap.is_some() {
+            egui::TopBottomPanel::top("missing_beatmap").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Beatmap of this replay is not in the database, only cursor data is shown");
+
+                    if ui.button("Locate beatmap...").clicked() {
+                        self.spawn_beatmap_chooser();
+                    }
+                });
+            });
+        }
+
         if let Some(modal_text) = &self.modal_text {
             Modal::new(egui::Id::new("Modal")).show(ctx, |ui| {
                 ui.label(modal_text);
@@ -591,6 +878,28 @@ impl<'rvs> ReplayViewerState<'rvs> {
                             self.update_replay_position_by_time()
                         }
 
+                        ui.separator();
+                        ui.label("Speed:");
+
+                        for rate in PLAYBACK_RATES {
+                            if ui.selectable_label(self.time.rate() == rate, format!("{rate}x")).clicked() {
+                                self.time.set_rate(rate);
+                            }
+                        }
+
+                        let mut rate = self.time.rate();
+                        let response = ui.add(
+                            egui::Slider::new(&mut rate, 0.25..=2.0)
+                                .step_by(0.05)
+                                .suffix("x")
+                        );
+
+                        if response.changed() {
+                            self.time.set_rate(rate);
+                        }
+
+                        ui.separator();
+
                         if let Some(replay) = &self.replay {
                             let idx = self.replay_frame_end_idx;
                             ui.label(&format!("Frame ms: {}", replay.frames[idx].ts));
@@ -623,6 +932,39 @@ impl<'rvs> ReplayViewerState<'rvs> {
                         self.time.set_time(self.slider_time);
                         self.update_replay_position_by_time()
                     }
+
+                    if !self.accuracy_points.is_empty() {
+                        let clicked = accuracy_graph(
+                            ui,
+                            &self.accuracy_points,
+                            (min, max),
+                            &self.breaks,
+                            Some(self.slider_time),
+                            egui::Vec2::new(slider_width, 60.0),
+                        );
+
+                        if let Some(time) = clicked {
+                            self.slider_time = time;
+                            self.time.set_time(time);
+                            self.update_replay_position_by_time()
+                        }
+                    }
+
+                    if let Some(waveform) = &self.waveform {
+                        let clicked = waveform_strip(
+                            ui,
+                            waveform,
+                            (min, max),
+                            Some(self.slider_time),
+                            egui::Vec2::new(slider_width, 40.0),
+                        );
+
+                        if let Some(time) = clicked {
+                            self.slider_time = time;
+                            self.time.set_time(time);
+                            self.update_replay_position_by_time()
+                        }
+                    }
                 });
             });
 
@@ -635,6 +977,17 @@ impl<'rvs> ReplayViewerState<'rvs> {
                 self.spawn_beatmaps_directory_chooser();
             }
 
+            #[cfg(feature = "render-stats")]
+            render_stats_ui(ui, &self.render_stats);
+
+            ui.collapsing("Aim error", |ui| {
+                aim_scatter(ui, &self.score, 180.0);
+
+                if let Some(mean) = self.score.mean_hit_offset() {
+                    ui.label(format!("Mean offset: {:.1}, {:.1} osu!px", mean.x, mean.y));
+                }
+            });
+
             ui.heading("Settings");
             let resp = ui.add(
                 egui::Slider::new(
@@ -684,6 +1037,16 @@ impl<'rvs> ReplayViewerState<'rvs> {
                 };
             });
 
+            ui.checkbox(&mut self.settings.show_annotations, "Spacing and angles");
+
+            ui.add_enabled_ui(self.settings.show_annotations, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Labels");
+                    ui.selectable_value(&mut self.settings.annotation_density, AnnotationDensity::Every, "Every object");
+                    ui.selectable_value(&mut self.settings.annotation_density, AnnotationDensity::Selected, "Hovered object");
+                });
+            });
+
             ui.collapsing("Gameplay Visuals", |ui| {
                 if ui.add(
                     egui::Slider::new(
@@ -711,36 +1074,29 @@ impl<'rvs> ReplayViewerState<'rvs> {
                     }
                 };
 
-                if ui.add(
+                ui.add(
                     egui::Slider::new(
                         &mut self.gameplay_config.slider.body_color_saturation, 
                         0.01..=2.0
                     ).step_by(0.01).text("Body color saturation")
-                ).changed() {
-                    if let Some(objects) = &mut self.objects {
-                        self.osu_renderer.clear_cached_slider_textures(
-                            objects
-                        );
-                    }
-                };
+                );
 
-                if ui.add(
+                ui.add(
                     egui::Slider::new(
                         &mut self.gameplay_config.slider.body_alpha_multiplier, 
                         0.01..=2.0
                     ).step_by(0.01).text("Body alpha multiplier")
-                ).changed() {
-                    if let Some(objects) = &mut self.objects {
-                        self.osu_renderer.clear_cached_slider_textures(
-                            objects
-                        );
-                    }
-                };
+                );
 
                 ui.checkbox(
                     &mut self.gameplay_config.debug_use_judgements_as_colors, 
                     "Judgements as colors"
                 );
+
+                ui.checkbox(
+                    &mut self.gameplay_config.hidden, 
+                    "Hidden"
+                );
             });
 
             ui.collapsing("Current Frame Info", |ui| {
@@ -757,6 +1113,9 @@ impl<'rvs> ReplayViewerState<'rvs> {
                 }
             });
         });
+
+        // After the panels, so only the area left for the playfield is annotated
+        self.render_annotations(ctx);
     }
 
     pub fn on_pressed_down(&mut self, key_code: KeyCode) {
@@ -861,6 +1220,9 @@ impl<'rvs> ReplayViewerState<'rvs> {
                 ReplayViewerEvents::OpenReplay(path_buf) => {
                     self.open_replay(&path_buf);
                 },
+                ReplayViewerEvents::LocateBeatmap(path_buf) => {
+                    self.locate_beatmap(path_buf);
+                },
                 ReplayViewerEvents::ResetModal => self.modal_text = None,
                 ReplayViewerEvents::ScanBeatmaps(path_buf) => {
                     let (_tx, rx) = oneshot::channel();
@@ -870,6 +1232,12 @@ impl<'rvs> ReplayViewerState<'rvs> {
                     self.time.set_time(ts);
                     self.update_replay_position_by_time();
                 },
+                ReplayViewerEvents::WaveformLoaded(path, waveform) => {
+                    // Ignoring waveforms of previously opened beatmaps
+                    if self.waveform_audio_path.as_ref() == Some(&path) {
+                        self.waveform = Some(waveform);
+                    }
+                },
             },
             Err(e) => {
                 // TODO
@@ -891,6 +1259,19 @@ impl<'rvs> ReplayViewerState<'rvs> {
         });
     }
 
+    fn spawn_beatmap_chooser(&self) {
+        let tx = self.tx.clone();
+        std::thread::spawn(move || {
+            let file = rfd::FileDialog::new()
+                .add_filter("osu", &["osu"])
+                .pick_file();
+
+            if let Some(file) = file {
+                let _ = tx.send(ReplayViewerEvents::LocateBeatmap(file));
+            }
+        });
+    }
+
     fn spawn_beatmaps_directory_chooser(&self) {
         let tx = self.tx.clone();
         std::thread::spawn(move || {
@@ -0,0 +1,217 @@
+use std::{collections::HashMap, io::Cursor, path::Path, sync::{Arc, RwLock}};
+
+use egui::{Color32, Pos2, Rect, Sense, Stroke, Vec2};
+use symphonia::core::{audio::SampleBuffer, codecs::DecoderOptions, errors::Error as SymphoniaError, formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint};
+
+/// Amount of min/max buckets audio is downsampled to
+pub const WAVEFORM_BUCKETS: usize = 2000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WaveformError {
+    #[error("failed to read audio file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to decode audio: {0}")]
+    Decode(#[from] SymphoniaError),
+    #[error("audio file has no playable track")]
+    NoTrack,
+}
+
+/// Downsampled audio amplitudes
+pub struct Waveform {
+    /// Min and max amplitude of every bucket
+    pub buckets: Vec<(f32, f32)>,
+    pub duration_ms: f64,
+}
+
+impl Waveform {
+    /// Splits mono `samples` into `buckets` equal parts
+    /// and keeps min/max amplitude of every part
+    pub fn from_samples(samples: &[f32], sample_rate: u32, buckets: usize) -> Self {
+        let duration_ms = samples.len() as f64 / sample_rate.max(1) as f64 * 1000.0;
+
+        if samples.is_empty() || buckets == 0 {
+            return Self {
+                buckets: Vec::new(),
+                duration_ms,
+            };
+        }
+
+        let bucket_size = samples.len().div_ceil(buckets);
+
+        let buckets = samples
+            .chunks(bucket_size)
+            .map(|chunk| {
+                chunk.iter().fold((0.0f32, 0.0f32), |(min, max), x| (min.min(*x), max.max(*x)))
+            })
+            .collect();
+
+        Self {
+            buckets,
+            duration_ms,
+        }
+    }
+
+    /// Decodes whole file into mono samples, expected to be called from a worker thread
+    pub fn decode(bytes: Vec<u8>, extension: Option<&str>) -> Result<Self, WaveformError> {
+        let _span = tracy_client::span!("waveform::decode");
+
+        let mss = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(extension) = extension {
+            hint.with_extension(extension);
+        }
+
+        let probed = symphonia::default::get_probe().format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?;
+
+        let mut format = probed.format;
+
+        let track = format.default_track().ok_or(WaveformError::NoTrack)?;
+        let track_id = track.id;
+        let sample_rate = track.codec_params.sample_rate.ok_or(WaveformError::NoTrack)?;
+
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+
+        let mut samples = Vec::new();
+
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e.into()),
+            };
+
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // Broken packet, skipping it is fine for a preview
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            let spec = *decoded.spec();
+            let channels = spec.channels.count().max(1);
+
+            let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+            buffer.copy_interleaved_ref(decoded);
+
+            samples.extend(
+                buffer.samples()
+                    .chunks(channels)
+                    .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            );
+        }
+
+        Ok(Self::from_samples(&samples, sample_rate, WAVEFORM_BUCKETS))
+    }
+}
+
+/// Computed waveforms by audio md5, so the same song
+/// isn't decoded again for every replay
+#[derive(Default, Clone)]
+pub struct WaveformCache {
+    inner: Arc<RwLock<HashMap<md5::Digest, Arc<Waveform>>>>,
+}
+
+impl WaveformCache {
+    pub fn load(&self, path: &Path) -> Result<Arc<Waveform>, WaveformError> {
+        let bytes = std::fs::read(path)?;
+        let hash = md5::compute(&bytes);
+
+        if let Some(waveform) = self.inner.read().expect("failed to acquire read lock").get(&hash) {
+            return Ok(waveform.clone());
+        }
+
+        let extension = path.extension().and_then(|x| x.to_str());
+        let waveform = Arc::new(Waveform::decode(bytes, extension)?);
+
+        self.inner
+            .write()
+            .expect("failed to acquire write lock")
+            .insert(hash, waveform.clone());
+
+        Ok(waveform)
+    }
+}
+
+/// Draws waveform strip aligned with the timeline.
+///
+/// `time_range` is the same span timeline uses. Returns time of the clicked point if any
+pub fn waveform_strip(
+    ui: &mut egui::Ui,
+    waveform: &Waveform,
+    time_range: (f64, f64),
+    current_time: Option<f64>,
+    size: Vec2,
+) -> Option<f64> {
+    let _span = tracy_client::span!("waveform::waveform_strip");
+
+    let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::from_black_alpha(150));
+
+    let (start, end) = time_range;
+    let duration = (end - start).max(1.0);
+    let bucket_ms = waveform.duration_ms / waveform.buckets.len().max(1) as f64;
+
+    let center = rect.center().y;
+    let half_height = rect.height() / 2.0;
+    let stroke = Stroke::new(1.0, Color32::from_rgb(100, 160, 255));
+
+    for (i, (min, max)) in waveform.buckets.iter().enumerate() {
+        let time = i as f64 * bucket_ms;
+
+        if time < start || time > end {
+            continue;
+        }
+
+        let x = rect.min.x + ((time - start) / duration) as f32 * rect.width();
+
+        painter.vline(
+            x,
+            (center - max.clamp(0.0, 1.0) * half_height)..=(center - min.clamp(-1.0, 0.0) * half_height),
+            stroke,
+        );
+    }
+
+    if let Some(time) = current_time {
+        let x = rect.min.x + ((time - start) / duration).clamp(0.0, 1.0) as f32 * rect.width();
+        painter.vline(x, rect.y_range(), Stroke::new(1.0, Color32::WHITE));
+    }
+
+    if response.clicked() {
+        if let Some(pos) = response.interact_pointer_pos() {
+            return Some(time_at(rect, pos, time_range));
+        }
+    }
+
+    None
+}
+
+fn time_at(rect: Rect, pos: Pos2, (start, end): (f64, f64)) -> f64 {
+    let progress = ((pos.x - rect.min.x) / rect.width()).clamp(0.0, 1.0) as f64;
+
+    start + (end - start) * progress
+}
+
+#[test]
+fn test_waveform_from_samples() {
+    let samples: Vec<f32> = (0..1000).map(|i| if i < 500 { 0.5 } else { -0.25 }).collect();
+
+    let waveform = Waveform::from_samples(&samples, 1000, 4);
+
+    assert_eq!(waveform.duration_ms, 1000.0);
+    assert_eq!(waveform.buckets, [(0.0, 0.5), (0.0, 0.5), (-0.25, 0.0), (-0.25, 0.0)]);
+
+    assert!(Waveform::from_samples(&[], 44100, 4).buckets.is_empty());
+}
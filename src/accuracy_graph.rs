@@ -0,0 +1,89 @@
+use egui::{Color32, Pos2, Rect, Sense, Stroke, Vec2};
+
+use crate::hit_objects::breaks::Break;
+
+/// Lowest accuracy shown on graph, everything below is clamped
+const MIN_ACCURACY: f64 = 0.8;
+
+const BREAK_COLOR: Color32 = Color32::from_rgba_premultiplied(40, 40, 60, 120);
+
+/// Draws running accuracy line graph.
+///
+/// `time_range` is a full time span of the play, so graphs
+/// line up with timelines. `breaks` are shown as gaps.
+/// Returns time of the clicked point if any
+pub fn accuracy_graph(
+    ui: &mut egui::Ui,
+    points: &[(f64, f64)],
+    time_range: (f64, f64),
+    breaks: &[Break],
+    current_time: Option<f64>,
+    size: Vec2,
+) -> Option<f64> {
+    let _span = tracy_client::span!("accuracy_graph::accuracy_graph");
+
+    let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::from_black_alpha(150));
+
+    let (start, end) = time_range;
+    let duration = (end - start).max(1.0);
+
+    let to_screen = |time: f64, accuracy: f64| -> Pos2 {
+        let x = ((time - start) / duration).clamp(0.0, 1.0) as f32;
+        let y = ((accuracy - MIN_ACCURACY) / (1.0 - MIN_ACCURACY)).clamp(0.0, 1.0) as f32;
+
+        Pos2::new(
+            rect.min.x + x * rect.width(),
+            rect.max.y - y * rect.height(),
+        )
+    };
+
+    for brk in breaks {
+        let from = to_screen(brk.start_time, 1.0).x;
+        let to = to_screen(brk.end_time, 1.0).x;
+
+        painter.rect_filled(
+            Rect::from_x_y_ranges(from..=to, rect.y_range()),
+            0.0,
+            BREAK_COLOR,
+        );
+    }
+
+    let line: Vec<Pos2> = points
+        .iter()
+        .map(|(time, accuracy)| to_screen(*time, *accuracy))
+        .collect();
+
+    painter.add(egui::Shape::line(line, Stroke::new(1.5, Color32::LIGHT_GREEN)));
+
+    if let Some(time) = current_time {
+        let x = to_screen(time, 1.0).x;
+        painter.vline(x, rect.y_range(), Stroke::new(1.0, Color32::WHITE));
+    }
+
+    if let Some(point) = points.last() {
+        painter.text(
+            rect.left_top() + Vec2::new(4.0, 2.0),
+            egui::Align2::LEFT_TOP,
+            format!("{:.2}%", point.1 * 100.0),
+            egui::FontId::default(),
+            Color32::WHITE,
+        );
+    }
+
+    if response.clicked() {
+        if let Some(pos) = response.interact_pointer_pos() {
+            return Some(time_at(rect, pos, time_range));
+        }
+    }
+
+    None
+}
+
+fn time_at(rect: Rect, pos: Pos2, (start, end): (f64, f64)) -> f64 {
+    let progress = ((pos.x - rect.min.x) / rect.width()).clamp(0.0, 1.0) as f64;
+
+    start + (end - start) * progress
+}
@@ -0,0 +1,373 @@
+use std::collections::VecDeque;
+
+use crate::config::JudgementsConfig;
+
+/// Frames the rolling average frame time is taken over
+const AVERAGE_FRAMES: usize = 30;
+
+/// Single hitch (beatmap loading, window dragging) is capped
+/// to this, so it alone doesn't push the average over, in ms
+const MAX_FRAME_TIME: f64 = 100.0;
+
+/// How eagerly visuals are dropped when frames get slow
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum AdaptiveQualityMode {
+    #[default]
+    Off,
+    /// Only drops visuals under sustained slowdowns
+    Conservative,
+    /// Reacts quickly and keeps visuals off for longer
+    Aggressive,
+}
+
+/// Thresholds of a mode. Restore threshold is lower than the degrade
+/// one and takes longer to reach, so the level doesn't flap between two
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Thresholds {
+    /// Average frame time above which visuals are dropped, in ms
+    degrade_above: f64,
+    /// Frames in a row the average has to stay above `degrade_above`
+    degrade_frames: u32,
+    /// Average frame time below which visuals come back, in ms
+    restore_below: f64,
+    /// Frames in a row the average has to stay below `restore_below`
+    restore_frames: u32,
+}
+
+impl AdaptiveQualityMode {
+    pub const ALL: [AdaptiveQualityMode; 3] = [
+        AdaptiveQualityMode::Off,
+        AdaptiveQualityMode::Conservative,
+        AdaptiveQualityMode::Aggressive,
+    ];
+
+    /// Code stored in the settings file
+    pub fn code(&self) -> &'static str {
+        match self {
+            AdaptiveQualityMode::Off => "off",
+            AdaptiveQualityMode::Conservative => "conservative",
+            AdaptiveQualityMode::Aggressive => "aggressive",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.code() == code)
+    }
+
+    /// i18n key of the name shown in settings
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            AdaptiveQualityMode::Off => "settings.renderer.adaptive_quality.off",
+            AdaptiveQualityMode::Conservative => "settings.renderer.adaptive_quality.conservative",
+            AdaptiveQualityMode::Aggressive => "settings.renderer.adaptive_quality.aggressive",
+        }
+    }
+
+    fn thresholds(&self) -> Option<Thresholds> {
+        match self {
+            AdaptiveQualityMode::Off => None,
+            // Below 50 fps for two seconds
+            AdaptiveQualityMode::Conservative => Some(Thresholds {
+                degrade_above: 20.0,
+                degrade_frames: 120,
+                restore_below: 14.0,
+                restore_frames: 300,
+            }),
+            // Below 57 fps for half a second
+            AdaptiveQualityMode::Aggressive => Some(Thresholds {
+                degrade_above: 17.5,
+                degrade_frames: 30,
+                restore_below: 12.0,
+                restore_frames: 600,
+            }),
+        }
+    }
+}
+
+/// Optional visuals that are currently dropped. Every level
+/// keeps everything the previous ones have dropped
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum QualityLevel {
+    #[default]
+    Full,
+    /// Nothing draws hit lighting yet, so this step is free
+    NoHitLighting,
+    /// Cursor trail is updated at half the rate
+    ReducedCursorTrail,
+    /// Judgements pop in and out without fades and outlines
+    SimpleJudgements,
+    /// Sliders are not baked ahead, only when they show up
+    NoSliderBakeAhead,
+}
+
+impl QualityLevel {
+    pub const ALL: [QualityLevel; 5] = [
+        QualityLevel::Full,
+        QualityLevel::NoHitLighting,
+        QualityLevel::ReducedCursorTrail,
+        QualityLevel::SimpleJudgements,
+        QualityLevel::NoSliderBakeAhead,
+    ];
+
+    /// 0 is full quality
+    #[inline]
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    fn degraded(&self) -> Self {
+        Self::ALL[(self.index() + 1).min(Self::ALL.len() - 1)]
+    }
+
+    fn restored(&self) -> Self {
+        Self::ALL[self.index().saturating_sub(1)]
+    }
+
+    #[inline]
+    pub fn hit_lighting(&self) -> bool {
+        *self < QualityLevel::NoHitLighting
+    }
+
+    /// Multiplier of the cursor trail update rate
+    #[inline]
+    pub fn cursor_trail_density(&self) -> f64 {
+        if *self < QualityLevel::ReducedCursorTrail {
+            1.0
+        } else {
+            0.5
+        }
+    }
+
+    /// Judgement settings to draw with, with simple judgements
+    /// fades are folded into the time they stay on screen
+    pub fn judgements(&self, config: &JudgementsConfig) -> JudgementsConfig {
+        if *self < QualityLevel::SimpleJudgements {
+            return *config;
+        }
+
+        JudgementsConfig {
+            fade_in_ms: 0.0,
+            stay_on_screen_ms: config.total_time(),
+            fade_out_ms: 0.0,
+            high_contrast: false,
+            ..*config
+        }
+    }
+
+    /// Sliders baked ahead per frame, `configured` is the config value
+    #[inline]
+    pub fn bake_ahead_per_frame(&self, configured: u32) -> u32 {
+        if *self < QualityLevel::NoSliderBakeAhead {
+            configured
+        } else {
+            0
+        }
+    }
+}
+
+/// Drops optional visuals one by one while frames are too
+/// slow and brings them back once there's headroom again
+#[derive(Debug, Default)]
+pub struct AdaptiveQuality {
+    mode: AdaptiveQualityMode,
+    level: QualityLevel,
+
+    /// Last [`AVERAGE_FRAMES`] frame times, in ms
+    frame_times: VecDeque<f64>,
+    sum: f64,
+
+    frames_above: u32,
+    frames_below: u32,
+}
+
+impl AdaptiveQuality {
+    #[inline]
+    pub fn level(&self) -> QualityLevel {
+        self.level
+    }
+
+    #[inline]
+    pub fn mode(&self) -> AdaptiveQualityMode {
+        self.mode
+    }
+
+    /// Switching modes starts over from full quality
+    pub fn set_mode(&mut self, mode: AdaptiveQualityMode) {
+        if self.mode == mode {
+            return;
+        }
+
+        self.mode = mode;
+        self.level = QualityLevel::Full;
+        self.frames_above = 0;
+        self.frames_below = 0;
+    }
+
+    /// Rolling average of the last frames, in ms
+    pub fn average_frame_time(&self) -> f64 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        self.sum / self.frame_times.len() as f64
+    }
+
+    /// Accounts a finished frame, returns the new level when it changes
+    pub fn push_frame_time(&mut self, frame_time: f64) -> Option<QualityLevel> {
+        let frame_time = frame_time.clamp(0.0, MAX_FRAME_TIME);
+
+        if self.frame_times.len() >= AVERAGE_FRAMES {
+            self.sum -= self.frame_times.pop_front().unwrap_or(0.0);
+        }
+
+        self.frame_times.push_back(frame_time);
+        self.sum += frame_time;
+
+        let thresholds = self.mode.thresholds()?;
+
+        // Not enough frames for a meaningful average yet
+        if self.frame_times.len() < AVERAGE_FRAMES {
+            return None;
+        }
+
+        let average = self.average_frame_time();
+
+        self.frames_above = if average > thresholds.degrade_above { self.frames_above + 1 } else { 0 };
+        self.frames_below = if average < thresholds.restore_below { self.frames_below + 1 } else { 0 };
+
+        let new_level = if self.frames_above >= thresholds.degrade_frames {
+            self.level.degraded()
+        } else if self.frames_below >= thresholds.restore_frames {
+            self.level.restored()
+        } else {
+            return None;
+        };
+
+        // Each step gets its own full wait, so
+        // visuals go away and come back one at a time
+        self.frames_above = 0;
+        self.frames_below = 0;
+
+        if new_level == self.level {
+            return None;
+        }
+
+        tracing::info!("Adaptive quality {:?} -> {new_level:?}, average frame time {average:.2}ms", self.level);
+        self.level = new_level;
+
+        Some(new_level)
+    }
+}
+
+#[cfg(test)]
+fn push_frames(quality: &mut AdaptiveQuality, frame_time: f64, frames: u32) -> Vec<QualityLevel> {
+    (0..frames)
+        .filter_map(|_| quality.push_frame_time(frame_time))
+        .collect()
+}
+
+#[test]
+fn test_adaptive_quality_degrade_and_restore() {
+    let mut quality = AdaptiveQuality::default();
+    quality.set_mode(AdaptiveQualityMode::Aggressive);
+
+    // Average is taken only once the window is full
+    assert!(push_frames(&mut quality, 30.0, AVERAGE_FRAMES as u32 - 1).is_empty());
+
+    // Every 30 slow frames drop one more visual, in order
+    assert_eq!(push_frames(&mut quality, 30.0, 29), vec![]);
+    assert_eq!(push_frames(&mut quality, 30.0, 1), vec![QualityLevel::NoHitLighting]);
+    assert_eq!(push_frames(&mut quality, 30.0, 90), vec![
+        QualityLevel::ReducedCursorTrail,
+        QualityLevel::SimpleJudgements,
+        QualityLevel::NoSliderBakeAhead,
+    ]);
+
+    // Nothing is left to drop
+    assert!(push_frames(&mut quality, 30.0, 300).is_empty());
+    assert_eq!(quality.level(), QualityLevel::NoSliderBakeAhead);
+
+    // Headroom brings them back one at a time in reverse order,
+    // it takes a while until the average goes down first
+    let restored = push_frames(&mut quality, 5.0, 600 * 4 + AVERAGE_FRAMES as u32);
+    assert_eq!(restored, vec![
+        QualityLevel::SimpleJudgements,
+        QualityLevel::ReducedCursorTrail,
+        QualityLevel::NoHitLighting,
+        QualityLevel::Full,
+    ]);
+}
+
+#[test]
+fn test_adaptive_quality_hysteresis() {
+    let mut quality = AdaptiveQuality::default();
+    quality.set_mode(AdaptiveQualityMode::Conservative);
+
+    push_frames(&mut quality, 25.0, AVERAGE_FRAMES as u32 + 120);
+    assert_eq!(quality.level(), QualityLevel::NoHitLighting);
+
+    // Between the thresholds nothing changes in either direction
+    assert!(push_frames(&mut quality, 16.0, 2000).is_empty());
+
+    // Slow frames that don't last long enough are forgiven
+    for _ in 0..10 {
+        assert!(push_frames(&mut quality, 25.0, 100).is_empty());
+        assert!(push_frames(&mut quality, 16.0, 100).is_empty());
+    }
+
+    // Fast bursts shorter than the restore wait are forgiven too
+    for _ in 0..10 {
+        assert!(push_frames(&mut quality, 5.0, 250).is_empty());
+        assert!(push_frames(&mut quality, 16.0, 100).is_empty());
+    }
+
+    assert_eq!(quality.level(), QualityLevel::NoHitLighting);
+
+    // Single long hitch is capped and doesn't drop anything on its own
+    let mut quality = AdaptiveQuality::default();
+    quality.set_mode(AdaptiveQualityMode::Aggressive);
+    push_frames(&mut quality, 10.0, AVERAGE_FRAMES as u32);
+    assert!(push_frames(&mut quality, 5000.0, 1).is_empty());
+    assert!(push_frames(&mut quality, 10.0, 300).is_empty());
+}
+
+#[test]
+fn test_adaptive_quality_off() {
+    let mut quality = AdaptiveQuality::default();
+
+    assert!(push_frames(&mut quality, 100.0, 1000).is_empty());
+    assert_eq!(quality.level(), QualityLevel::Full);
+
+    quality.set_mode(AdaptiveQualityMode::Aggressive);
+    push_frames(&mut quality, 100.0, 100);
+    assert_ne!(quality.level(), QualityLevel::Full);
+
+    // Turning it off brings everything back right away
+    quality.set_mode(AdaptiveQualityMode::Off);
+    assert_eq!(quality.level(), QualityLevel::Full);
+}
+
+#[test]
+fn test_quality_level_visuals() {
+    let judgements = JudgementsConfig {
+        fade_in_ms: 100.0,
+        stay_on_screen_ms: 200.0,
+        fade_out_ms: 100.0,
+        high_contrast: true,
+        hit_position_nudge: 0.5,
+    };
+
+    assert_eq!(QualityLevel::Full.judgements(&judgements), judgements);
+    assert!(QualityLevel::Full.hit_lighting());
+    assert_eq!(QualityLevel::NoHitLighting.cursor_trail_density(), 1.0);
+
+    let simple = QualityLevel::SimpleJudgements.judgements(&judgements);
+    assert_eq!(simple.total_time(), judgements.total_time());
+    assert_eq!(simple.fade_in_ms, 0.0);
+    assert!(!simple.high_contrast);
+    assert_eq!(simple.hit_position_nudge, 0.5);
+
+    assert_eq!(QualityLevel::SimpleJudgements.bake_ahead_per_frame(2), 2);
+    assert_eq!(QualityLevel::NoSliderBakeAhead.bake_ahead_per_frame(2), 0);
+    assert_eq!(QualityLevel::NoSliderBakeAhead.cursor_trail_density(), 0.5);
+}
@@ -0,0 +1,60 @@
+use cgmath::Vector2;
+use egui::{Color32, Sense, Stroke, Vec2};
+
+use crate::score::Score;
+
+/// Plot spans this many circle radii from the center,
+/// points further away are clamped to the edge
+const MAX_RADII: f64 = 1.5;
+
+const POINT_COLOR: Color32 = Color32::from_rgba_premultiplied(90, 150, 190, 160);
+const MEAN_COLOR: Color32 = Color32::from_rgb(255, 200, 60);
+
+/// Draws click positions of the score relative to the object center,
+/// hit circle is shown as a unit circle in the middle and the mean
+/// offset as a cross
+pub fn aim_scatter(ui: &mut egui::Ui, score: &Score, size: f32) {
+    let _span = tracy_client::span!("aim_scatter::aim_scatter");
+
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(size), Sense::hover());
+
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::from_black_alpha(150));
+
+    let center = rect.center();
+    let radius = size / 2.0 / MAX_RADII as f32;
+
+    painter.circle_stroke(center, radius, Stroke::new(1.0, Color32::GRAY));
+    painter.hline(rect.x_range(), center.y, Stroke::new(1.0, Color32::from_gray(60)));
+    painter.vline(center.x, rect.y_range(), Stroke::new(1.0, Color32::from_gray(60)));
+
+    let circle_radius = score.circle_radius;
+
+    if circle_radius <= 0.0 {
+        return;
+    }
+
+    let to_screen = |offset: Vector2<f64>| {
+        let mut normalized = offset / circle_radius;
+        let length = (normalized.x * normalized.x + normalized.y * normalized.y).sqrt();
+
+        if length > MAX_RADII {
+            normalized = normalized * (MAX_RADII / length);
+        }
+
+        center + Vec2::new(normalized.x as f32, normalized.y as f32) * radius
+    };
+
+    for offset in &score.hit_offsets {
+        painter.circle_filled(to_screen(*offset), 1.5, POINT_COLOR);
+    }
+
+    let Some(mean) = score.mean_hit_offset() else {
+        return;
+    };
+
+    let mean = to_screen(mean);
+
+    painter.line_segment([mean - Vec2::new(4.0, 0.0), mean + Vec2::new(4.0, 0.0)], Stroke::new(2.0, MEAN_COLOR));
+    painter.line_segment([mean - Vec2::new(0.0, 4.0), mean + Vec2::new(0.0, 4.0)], Stroke::new(2.0, MEAN_COLOR));
+}
@@ -0,0 +1,51 @@
+use std::{alloc::{GlobalAlloc, Layout, System}, cell::Cell};
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// System allocator that counts allocations of every thread.
+///
+/// Has to be installed with `#[global_allocator]` by the binary,
+/// otherwise [`allocations`] always stays at zero
+pub struct CountingAllocator;
+
+#[inline]
+fn count() {
+    // Thread locals can already be destroyed when thread exits
+    let _ = ALLOCATIONS.try_with(|x| x.set(x.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Allocations made by the current thread so far
+pub fn allocations() -> usize {
+    ALLOCATIONS.try_with(Cell::get).unwrap_or(0)
+}
+
+/// Runs `f` and returns amount of allocations it made on the current thread
+pub fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = allocations();
+    let out = f();
+
+    (out, allocations() - before)
+}
@@ -0,0 +1,124 @@
+use soloud::{AudioExt, Backend, Handle, Soloud, SoloudFlag};
+
+use crate::sound_scheduler::{SoundScheduler, SCHEDULE_LOOKAHEAD_MS};
+
+/// Backend used when none is picked in settings
+pub const DEFAULT_BACKEND: &str = "Default";
+
+/// Backends user can choose from. Not every one of them
+/// is compiled into soloud, unavailable ones fall back to default
+pub fn available_backends() -> &'static [(&'static str, Backend)] {
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "windows")] {
+            &[
+                (DEFAULT_BACKEND, Backend::Auto),
+                ("Miniaudio", Backend::MiniAudio),
+                ("WASAPI", Backend::Wasapi),
+                ("WinMM", Backend::WinMm),
+                ("XAudio2", Backend::XAudio2),
+            ]
+        } else if #[cfg(target_os = "macos")] {
+            &[
+                (DEFAULT_BACKEND, Backend::Auto),
+                ("Miniaudio", Backend::MiniAudio),
+                ("CoreAudio", Backend::CoreAudio),
+            ]
+        } else {
+            &[
+                (DEFAULT_BACKEND, Backend::Auto),
+                ("Miniaudio", Backend::MiniAudio),
+                ("ALSA", Backend::Alsa),
+                ("JACK", Backend::Jack),
+                ("OSS", Backend::Oss),
+            ]
+        }
+    }
+}
+
+/// Information reported by currently running backend
+#[derive(Debug, Clone, Default)]
+pub struct AudioInfo {
+    pub name: String,
+    pub backend: String,
+    pub samplerate: u32,
+    pub buffer_size: u32,
+    pub channels: u32,
+}
+
+impl AudioInfo {
+    pub fn new(name: &str, sl: &Soloud) -> Self {
+        Self {
+            name: name.to_owned(),
+            backend: sl.backend_string(),
+            samplerate: sl.backend_samplerate(),
+            buffer_size: sl.backend_buffer_size(),
+            channels: sl.backend_channels(),
+        }
+    }
+
+    /// Latency introduced by the output buffer
+    pub fn buffer_latency_ms(&self) -> f64 {
+        if self.samplerate == 0 {
+            return 0.0;
+        }
+
+        self.buffer_size as f64 / self.samplerate as f64 * 1000.0
+    }
+}
+
+/// Initializes soloud with a backend by its name, falls back to
+/// the default one if backend is missing or failed to initialize.
+///
+/// Returns name of the backend that was actually initialized
+pub fn init_soloud(name: Option<&str>) -> (Soloud, &'static str) {
+    let _span = tracy_client::span!("audio::init_soloud");
+
+    let requested = name.and_then(|name| {
+        available_backends().iter().find(|(backend_name, _)| *backend_name == name)
+    });
+
+    if let Some((backend_name, backend)) = requested {
+        match Soloud::new(SoloudFlag::ClipRoundoff, *backend, 0, 0, 2) {
+            Ok(sl) => {
+                tracing::info!("Initialized audio backend: {}", sl.backend_string());
+                return (sl, backend_name);
+            },
+            Err(e) => tracing::warn!("Failed to initialize {backend_name} audio backend, falling back to default: {e:?}"),
+        }
+    } else if let Some(name) = name {
+        tracing::warn!("Audio backend {name} is not available, falling back to default");
+    }
+
+    let sl = Soloud::default().expect("failed to initialize default audio backend");
+    tracing::info!("Initialized audio backend: {}", sl.backend_string());
+
+    (sl, DEFAULT_BACKEND)
+}
+
+/// Starts every sound due within the lookahead. Ones that are still
+/// ahead of `now` are started paused and delayed by the exact amount of
+/// samples, so their onset doesn't depend on when the frame happened.
+/// `source` maps scheduled sounds to loaded samples, missing ones are skipped
+pub fn dispatch_sounds<'a, T, S: AudioExt + 'a>(
+    sl: &mut Soloud,
+    scheduler: &mut SoundScheduler<T>,
+    now: f64,
+    volume: f32,
+    source: impl Fn(&T) -> Option<&'a S>,
+) {
+    let _span = tracy_client::span!("audio::dispatch_sounds");
+
+    let samplerate = sl.backend_samplerate() as f64;
+
+    for scheduled in scheduler.drain_due(now, SCHEDULE_LOOKAHEAD_MS) {
+        let Some(sound) = source(&scheduled.sound) else {
+            continue;
+        };
+
+        let delay_ms = (scheduled.at - now).max(0.0);
+        let handle = sl.play_ex(sound, volume, 0.0, true, Handle::PRIMARY);
+
+        sl.set_delay_samples(handle, (delay_ms / 1000.0 * samplerate) as u32);
+        sl.set_pause(handle, false);
+    }
+}
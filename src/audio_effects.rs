@@ -0,0 +1,358 @@
+use std::time::Instant;
+
+use crate::config::VolumeConfig;
+
+use soloud::{filter::{BiquadResonantFilter, BiquadResonantFilterAttr, BiquadResonantFilterType}, AudioExt, Handle, Soloud, Wav};
+
+/// How long it takes to move from one state to another, in ms
+pub const EFFECT_RAMP_MS: f64 = 300.0;
+
+/// Failing is slower, so the fail overlay isn't jarring
+pub const FAIL_RAMP_MS: f64 = 500.0;
+
+/// Longer gaps between updates, e.g. a stalled frame,
+/// don't skip the ramps to their ends at once
+const MAX_UPDATE_DT_MS: f64 = 100.0;
+
+/// Filter slot used on music sources
+const FILTER_ID: u32 = 0;
+
+/// Close to the top of the audible range, so filter is
+/// unnoticeable in the middle of the ramp back to normal
+const OPEN_CUTOFF: f32 = 20000.0;
+const RESONANCE: f32 = 0.7;
+
+/// What is happening to gameplay, music is muffled accordingly
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AudioState {
+    Normal,
+    Paused,
+    /// Nothing can fail a play yet, meant for when HP is implemented
+    Failed,
+}
+
+impl AudioState {
+    /// Low-pass cutoff in Hz
+    pub fn cutoff(self) -> f32 {
+        match self {
+            AudioState::Normal => OPEN_CUTOFF,
+            AudioState::Paused => 600.0,
+            AudioState::Failed => 300.0,
+        }
+    }
+
+    /// Music volume multiplier
+    pub fn volume(self, config: &VolumeConfig) -> f32 {
+        match self {
+            AudioState::Normal => 1.0,
+            AudioState::Paused => config.pause,
+            AudioState::Failed => config.fail,
+        }
+    }
+
+    /// How long it takes to get into this state, in ms
+    pub fn ramp_ms(self) -> f64 {
+        match self {
+            AudioState::Failed => FAIL_RAMP_MS,
+            _ => EFFECT_RAMP_MS,
+        }
+    }
+}
+
+/// Value moving linearly to its target, advanced by frame time
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ramp {
+    from: f32,
+    to: f32,
+    elapsed: f64,
+    duration: f64,
+}
+
+impl Ramp {
+    pub fn new(value: f32) -> Self {
+        Self {
+            from: value,
+            to: value,
+            elapsed: 0.0,
+            duration: 0.0,
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        match self.duration > 0.0 {
+            true => self.from + (self.to - self.from) * (self.elapsed / self.duration).clamp(0.0, 1.0) as f32,
+            false => self.to,
+        }
+    }
+
+    #[inline]
+    pub fn target(&self) -> f32 {
+        self.to
+    }
+
+    /// Starts moving from wherever the ramp currently is
+    pub fn set_target(&mut self, to: f32, duration: f64) {
+        self.from = self.value();
+        self.to = to;
+        self.elapsed = 0.0;
+        self.duration = duration;
+    }
+
+    /// Jumps to `value` without ramping
+    pub fn set(&mut self, value: f32) {
+        *self = Self::new(value);
+    }
+
+    /// `dt` is in ms
+    pub fn advance(&mut self, dt: f64) {
+        self.elapsed += dt.max(0.0);
+    }
+
+    #[inline]
+    pub fn is_settled(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// Cutoff `elapsed` ms into a ramp from `from` to `to`.
+/// Interpolated on a log scale, so the sweep sounds even
+pub fn ramp_cutoff(from: f32, to: f32, elapsed: f64) -> f32 {
+    let t = ramp_progress(elapsed);
+
+    (from.ln() + (to.ln() - from.ln()) * t).exp()
+}
+
+pub fn ramp_volume(from: f32, to: f32, elapsed: f64) -> f32 {
+    from + (to - from) * ramp_progress(elapsed)
+}
+
+#[inline]
+fn ramp_progress(elapsed: f64) -> f32 {
+    (elapsed / EFFECT_RAMP_MS).clamp(0.0, 1.0) as f32
+}
+
+/// Low-pass "underwater" effect and volume duck on the music.
+///
+/// Filter is attached only to music sources through [`AudioEffects::attach`]
+/// so other sounds stay clean. Sources keep a pointer to the filter,
+/// so `AudioEffects` has to outlive every `Wav` it was attached to.
+///
+/// Music volume is the only thing setting volume of the music voice,
+/// it's `master × music × state duck`, so the duck stays proportional
+/// whatever the other two are
+pub struct AudioEffects {
+    filter: BiquadResonantFilter,
+    enabled: bool,
+
+    state: AudioState,
+    /// Natural log of the cutoff, so the sweep sounds even
+    cutoff: Ramp,
+    /// State multiplier of the music volume
+    duck: Ramp,
+    volume: VolumeConfig,
+
+    /// Ramps are advanced by time between updates
+    last_update: Option<Instant>,
+}
+
+impl AudioEffects {
+    pub fn new(enabled: bool, volume: VolumeConfig) -> Self {
+        let mut filter = BiquadResonantFilter::default();
+
+        if let Err(e) = filter.set_params(BiquadResonantFilterType::LowPass, OPEN_CUTOFF, RESONANCE) {
+            tracing::error!("Failed to configure low-pass filter: {e:?}");
+        }
+
+        Self {
+            filter,
+            enabled,
+            state: AudioState::Normal,
+            cutoff: Ramp::new(OPEN_CUTOFF.ln()),
+            duck: Ramp::new(1.0),
+            volume,
+            last_update: None,
+        }
+    }
+
+    /// Must be called before the source is played
+    pub fn attach(&self, wav: &mut Wav) {
+        wav.set_filter(FILTER_ID, Some(&self.filter));
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Disabled effects snap back to normal
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+
+        if !enabled {
+            self.state = AudioState::Normal;
+            self.cutoff.set(OPEN_CUTOFF.ln());
+            self.duck.set(1.0);
+        }
+    }
+
+    /// Volume settings are applied right away, ducks in
+    /// progress keep going relative to the new values
+    pub fn set_volume(&mut self, volume: VolumeConfig) {
+        if self.volume == volume {
+            return;
+        }
+
+        // Changed multiplier of the current state moves like a state change
+        if self.duck.target() != self.state.volume(&volume) {
+            self.duck.set_target(self.state.volume(&volume), EFFECT_RAMP_MS);
+        }
+
+        self.volume = volume;
+    }
+
+    /// `master × music`, without the state duck. Used
+    /// for music outside of gameplay, e.g. previews
+    pub fn music_volume(&self) -> f32 {
+        self.volume.master * self.volume.music
+    }
+
+    #[inline]
+    pub fn state(&self) -> AudioState {
+        self.state
+    }
+
+    /// Starts a ramp from wherever the previous one currently is
+    pub fn set_state(&mut self, state: AudioState) {
+        if self.state == state || !self.enabled {
+            return;
+        }
+
+        self.state = state;
+        self.cutoff.set_target(state.cutoff().ln(), state.ramp_ms());
+        self.duck.set_target(state.volume(&self.volume), state.ramp_ms());
+    }
+
+    /// Moves ramps `dt` ms forward
+    pub fn advance(&mut self, dt: f64) {
+        self.cutoff.advance(dt);
+        self.duck.advance(dt);
+    }
+
+    /// Cutoff and final music volume right now
+    pub fn current(&self) -> (f32, f32) {
+        (self.cutoff.value().exp(), self.music_volume() * self.duck.value())
+    }
+
+    /// Advances ramps and applies them to the music voice, every frame
+    pub fn update(&mut self, sl: &mut Soloud, handle: Handle) {
+        let _span = tracy_client::span!("audio_effects::update");
+
+        let now = Instant::now();
+        let dt = self.last_update
+            .map(|x| (now - x).as_secs_f64() * 1000.0)
+            .unwrap_or(0.0);
+
+        self.last_update = Some(now);
+        self.advance(dt.min(MAX_UPDATE_DT_MS));
+
+        let (cutoff, volume) = self.current();
+
+        // Fully bypassed once back to normal
+        let is_settled = self.state == AudioState::Normal && self.cutoff.is_settled();
+        let wet = if is_settled || !self.enabled { 0.0 } else { 1.0 };
+
+        sl.set_filter_parameter(handle, FILTER_ID, BiquadResonantFilterAttr::Wet, wet);
+        sl.set_filter_parameter(handle, FILTER_ID, BiquadResonantFilterAttr::Frequency, cutoff);
+        sl.set_volume(handle, volume);
+    }
+}
+
+#[test]
+fn test_ramp_cutoff() {
+    let (open, paused) = (AudioState::Normal.cutoff(), AudioState::Paused.cutoff());
+
+    assert!((ramp_cutoff(open, paused, 0.0) - open).abs() < 0.01);
+    assert!((ramp_cutoff(open, paused, -50.0) - open).abs() < 0.01);
+    assert!((ramp_cutoff(open, paused, EFFECT_RAMP_MS) - paused).abs() < 0.01);
+    assert!((ramp_cutoff(open, paused, 1000.0) - paused).abs() < 0.01);
+
+    // Geometric mean in the middle of a log scale ramp
+    let middle = ramp_cutoff(open, paused, EFFECT_RAMP_MS / 2.0);
+    assert!((middle - (open * paused).sqrt()).abs() < 1.0);
+
+    // Monotonic
+    let mut last = ramp_cutoff(open, paused, 0.0);
+    for ms in (10..=300).step_by(10) {
+        let cutoff = ramp_cutoff(open, paused, ms as f64);
+        assert!(cutoff < last);
+        last = cutoff;
+    }
+
+    assert!(ramp_cutoff(paused, open, 100.0) > paused);
+    assert_eq!(ramp_volume(1.0, 0.5, EFFECT_RAMP_MS / 2.0), 0.75);
+}
+
+#[test]
+fn test_ramp() {
+    let mut ramp = Ramp::new(1.0);
+    assert_eq!(ramp.value(), 1.0);
+    assert!(ramp.is_settled());
+
+    ramp.set_target(0.5, 500.0);
+    ramp.advance(250.0);
+    assert_eq!(ramp.value(), 0.75);
+    assert!(!ramp.is_settled());
+
+    // Going back starts from the middle
+    ramp.set_target(1.0, 500.0);
+    assert_eq!(ramp.value(), 0.75);
+    ramp.advance(250.0);
+    assert_eq!(ramp.value(), 0.875);
+
+    ramp.advance(1000.0);
+    assert_eq!(ramp.value(), 1.0);
+    assert!(ramp.is_settled());
+
+    // Time never goes backwards
+    ramp.set_target(0.0, 100.0);
+    ramp.advance(-50.0);
+    assert_eq!(ramp.value(), 1.0);
+}
+
+#[test]
+fn test_music_volume_composition() {
+    let volume = VolumeConfig { master: 0.8, music: 0.5, pause: 0.5, fail: 0.2 };
+    let mut effects = AudioEffects::new(true, volume);
+
+    assert_eq!(effects.current().1, 0.4);
+
+    effects.set_state(AudioState::Paused);
+    effects.advance(EFFECT_RAMP_MS / 2.0);
+    assert!((effects.current().1 - 0.4 * 0.75).abs() < 1e-6);
+
+    effects.advance(EFFECT_RAMP_MS);
+    assert!((effects.current().1 - 0.2).abs() < 1e-6);
+    assert!((effects.current().0 - AudioState::Paused.cutoff()).abs() < 0.1);
+
+    // Master changed while paused, duck stays at 50% of the new volume
+    effects.set_volume(VolumeConfig { master: 0.4, ..volume });
+    assert!((effects.current().1 - 0.1).abs() < 1e-6);
+    assert_eq!(effects.music_volume(), 0.2);
+
+    // Fail fades slower than pause
+    effects.set_state(AudioState::Failed);
+    effects.advance(EFFECT_RAMP_MS);
+    assert!(effects.current().1 > 0.2 * 0.2);
+    effects.advance(FAIL_RAMP_MS);
+    assert!((effects.current().1 - 0.2 * 0.2).abs() < 1e-6);
+
+    // Resume restores everything
+    effects.set_state(AudioState::Normal);
+    effects.advance(EFFECT_RAMP_MS);
+    assert!((effects.current().1 - 0.2).abs() < 1e-6);
+
+    // Disabled effects don't duck
+    effects.set_enabled(false);
+    effects.set_state(AudioState::Paused);
+    assert!((effects.current().1 - 0.2).abs() < 1e-6);
+}
@@ -0,0 +1,144 @@
+use std::{collections::VecDeque, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use rosu_map::Beatmap;
+
+/// How many parsed beatmaps are kept around, song select
+/// only needs the recently selected ones
+pub const BEATMAP_CACHE_CAPACITY: usize = 8;
+
+struct CachedBeatmap {
+    path: PathBuf,
+    /// md5 of the file contents at the moment of parsing
+    hash: String,
+    beatmap: Arc<Beatmap>,
+}
+
+/// Small in-memory cache of parsed beatmaps, filled by the song select
+/// loader thread so starting a map doesn't parse the same file again
+pub struct BeatmapCache {
+    entries: Mutex<VecDeque<CachedBeatmap>>,
+    capacity: usize,
+}
+
+impl Default for BeatmapCache {
+    fn default() -> Self {
+        Self::new(BEATMAP_CACHE_CAPACITY)
+    }
+}
+
+impl BeatmapCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    pub fn insert(&self, path: impl Into<PathBuf>, hash: impl Into<String>, beatmap: Arc<Beatmap>) {
+        let _span = tracy_client::span!("beatmap_cache::insert");
+
+        if self.capacity == 0 {
+            return;
+        }
+
+        let path = path.into();
+        let mut entries = self.entries.lock().expect("failed to acquire lock");
+
+        entries.retain(|x| x.path != path);
+
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+
+        entries.push_back(CachedBeatmap {
+            path,
+            hash: hash.into(),
+            beatmap,
+        });
+    }
+
+    /// Returns parsed beatmap only if it was parsed from the file
+    /// with the same `hash`, entries for changed files are dropped
+    pub fn get(&self, path: impl AsRef<Path>, hash: &str) -> Option<Arc<Beatmap>> {
+        let _span = tracy_client::span!("beatmap_cache::get");
+
+        let path = path.as_ref();
+        let mut entries = self.entries.lock().expect("failed to acquire lock");
+
+        let index = entries.iter().position(|x| x.path == path)?;
+
+        if entries[index].hash != hash {
+            tracing::info!("Cached beatmap is outdated: {}", path.display());
+            entries.remove(index);
+            return None;
+        }
+
+        // Moving to the back so recently used entries are evicted last
+        let entry = entries.remove(index)?;
+        let beatmap = entry.beatmap.clone();
+        entries.push_back(entry);
+
+        Some(beatmap)
+    }
+
+    /// Removes every entry located inside of `path`, works both
+    /// for a single beatmap file and the whole beatmapset directory
+    pub fn invalidate(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+
+        self.entries
+            .lock()
+            .expect("failed to acquire lock")
+            .retain(|x| !x.path.starts_with(path));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("failed to acquire lock").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[test]
+fn test_beatmap_cache_hash_mismatch() {
+    let cache = BeatmapCache::default();
+    cache.insert("songs/a/a.osu", "aaaa", Arc::new(Beatmap::default()));
+
+    assert!(cache.get("songs/a/a.osu", "aaaa").is_some());
+
+    // File changed on disk since it was parsed
+    assert!(cache.get("songs/a/a.osu", "bbbb").is_none());
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_beatmap_cache_eviction() {
+    let cache = BeatmapCache::new(2);
+    cache.insert("a.osu", "a", Arc::new(Beatmap::default()));
+    cache.insert("b.osu", "b", Arc::new(Beatmap::default()));
+
+    // Touching `a` so `b` becomes the oldest one
+    assert!(cache.get("a.osu", "a").is_some());
+
+    cache.insert("c.osu", "c", Arc::new(Beatmap::default()));
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get("a.osu", "a").is_some());
+    assert!(cache.get("b.osu", "b").is_none());
+    assert!(cache.get("c.osu", "c").is_some());
+}
+
+#[test]
+fn test_beatmap_cache_invalidate_directory() {
+    let cache = BeatmapCache::default();
+    cache.insert("songs/a/1.osu", "1", Arc::new(Beatmap::default()));
+    cache.insert("songs/a/2.osu", "2", Arc::new(Beatmap::default()));
+    cache.insert("songs/b/1.osu", "3", Arc::new(Beatmap::default()));
+
+    cache.invalidate("songs/a");
+
+    assert_eq!(cache.len(), 1);
+    assert!(cache.get("songs/b/1.osu", "3").is_some());
+}
@@ -1,19 +1,30 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
-use rosu::{graphics::Graphics, osu_state::OsuState};
-use soloud::Soloud;
+use rosu::{diagnostics, graphics::{Graphics, DEFAULT_WINDOW_SIZE}, osu_state::OsuState};
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt};
 use winit::{application::ApplicationHandler, event_loop::{ControlFlow, EventLoop}, keyboard::KeyCode, window::Window};
 
-pub struct OsuApp<'a> {
+#[cfg(feature = "alloc-counter")]
+#[global_allocator]
+static ALLOCATOR: rosu::alloc_counter::CountingAllocator = rosu::alloc_counter::CountingAllocator;
+
+pub struct OsuApp {
     window: Option<Arc<Window>>,
-    state: Option<OsuState<'a>>,
+    state: Option<OsuState>,
 
     is_cntrl_pressed: bool,
+    is_shift_pressed: bool,
+
+    /// Passed with `--replay`, watched once state is created
+    replay_to_watch: Option<PathBuf>,
 }
 
-impl<'a> ApplicationHandler for OsuApp<'a> {
+impl ApplicationHandler for OsuApp {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        let attrs = Window::default_attributes();
+        // Logical size, so window takes the same part
+        // of the screen regardless of its scale factor
+        let attrs = Window::default_attributes()
+            .with_inner_size(DEFAULT_WINDOW_SIZE);
         let window_orig = Arc::new(event_loop.create_window(attrs).unwrap());
 
         self.window = Some(window_orig.clone());
@@ -25,12 +36,14 @@ impl<'a> ApplicationHandler for OsuApp<'a> {
 
         let window = window_orig.clone();
 
-        let sl = Soloud::default().unwrap();
-
-        let state = pollster::block_on(async move {
-            OsuState::new(window, graphics, sl)
+        let mut state = pollster::block_on(async move {
+            OsuState::new(window, graphics)
         });
 
+        if let Some(path) = self.replay_to_watch.take() {
+            state.watch_replay(path);
+        }
+
         self.state = Some(state);
     }
 
@@ -40,6 +53,13 @@ impl<'a> ApplicationHandler for OsuApp<'a> {
         _window_id: winit::window::WindowId,
         event: winit::event::WindowEvent,
     ) {
+        // Egui goes first, so a keypress meant for a focused widget
+        // doesn't also reach gameplay or song select navigation
+        let egui_consumed = match (&mut self.state, &self.window) {
+            (Some(state), Some(window)) => state.egui.on_window_event(&event, window).consumed,
+            _ => false,
+        };
+
         match &event {
             winit::event::WindowEvent::CloseRequested => {
                 event_loop.exit();
@@ -49,6 +69,12 @@ impl<'a> ApplicationHandler for OsuApp<'a> {
                     state.resize(&new_size);
                 }
             },
+            // Egui already picked up the new pixels per point above
+            winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                if let Some(state) = &mut self.state {
+                    state.on_scale_factor_changed(*scale_factor);
+                }
+            },
             winit::event::WindowEvent::KeyboardInput { event, .. } => {
                 if let Some(state) = &mut self.state {
                     match event.physical_key {
@@ -59,13 +85,25 @@ impl<'a> ApplicationHandler for OsuApp<'a> {
                                         self.is_cntrl_pressed = true;
                                     }
 
-                                    state.on_pressed_down(key_code, self.is_cntrl_pressed);
+                                    if key_code == KeyCode::ShiftLeft {
+                                        self.is_shift_pressed = true;
+                                    }
+
+                                    if !egui_consumed {
+                                        state.on_pressed_down(key_code, self.is_cntrl_pressed, self.is_shift_pressed);
+                                    }
                                 },
                                 winit::event::ElementState::Released => {
                                     if key_code == KeyCode::ControlLeft {
                                         self.is_cntrl_pressed = false;
                                     }
 
+                                    if key_code == KeyCode::ShiftLeft {
+                                        self.is_shift_pressed = false;
+                                    }
+
+                                    // Releases always go through, otherwise
+                                    // a key pressed before focus would stay held
                                     state.on_pressed_release(key_code);
                                 },
                             }
@@ -80,15 +118,41 @@ impl<'a> ApplicationHandler for OsuApp<'a> {
                     state.on_cursor_moved(*position);
                 }
             },
+            winit::event::WindowEvent::HoveredFile(_) => {
+                if let Some(state) = &mut self.state {
+                    state.on_file_hovered(true);
+                }
+            },
+            winit::event::WindowEvent::HoveredFileCancelled => {
+                if let Some(state) = &mut self.state {
+                    state.on_file_hovered(false);
+                }
+            },
+            winit::event::WindowEvent::DroppedFile(path) => {
+                if let Some(state) = &mut self.state {
+                    state.on_file_dropped(path.clone());
+                }
+            },
             winit::event::WindowEvent::RedrawRequested => {
                 if let Some(state) = &mut self.state {
-                    'blk: loop {
-                        match state.render() {
-                            Ok(_) => break 'blk,
-                            Err(wgpu::SurfaceError::Lost) => tracing::warn!("Surface Lost"),
-                            Err(wgpu::SurfaceError::OutOfMemory) => tracing::error!("Render out of memory!"),
-                            Err(e) => {} //tracing::error!("Error during render: {e}"),
-                        }
+                    // Failed frames are retried on the next redraw, so a
+                    // surface that keeps getting lost can't spin the loop
+                    match state.render() {
+                        Ok(_) => {},
+                        Err(wgpu::SurfaceError::Lost) => {
+                            tracing::warn!("Surface Lost, recreating");
+
+                            if let Err(e) = state.recreate_surface() {
+                                tracing::error!("Failed to recreate surface: {e}");
+                            }
+                        },
+                        // Minimized window stays outdated until it's restored
+                        Err(wgpu::SurfaceError::Outdated) => {
+                            tracing::warn!("Surface Outdated, reconfiguring");
+                            state.on_surface_outdated();
+                        },
+                        Err(wgpu::SurfaceError::OutOfMemory) => tracing::error!("Render out of memory!"),
+                        Err(e) => {} //tracing::error!("Error during render: {e}"),
                     }
                 }
 
@@ -100,14 +164,13 @@ impl<'a> ApplicationHandler for OsuApp<'a> {
             _ => {},
         }
 
-        if let (Some(state), Some(window)) = (&mut self.state, &self.window) {
-            let _ = state.egui.on_window_event(&event, &window);
-        };
     }
 
 
     fn about_to_wait(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
         if let Some(state) = &mut self.state {
+            // Resized events of this iteration are coalesced into one
+            state.apply_pending_resize();
             state.update();
         }
     }
@@ -116,12 +179,18 @@ impl<'a> ApplicationHandler for OsuApp<'a> {
 fn main() {
     let _client = tracy_client::Client::start();
     
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .with_target(false)
-        .with_thread_names(true)
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_thread_names(true)
+        )
+        .with(diagnostics::RingBufferLayer::new(diagnostics::log_buffer().clone()))
         .init();
 
+    diagnostics::install_panic_hook();
+
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(ControlFlow::Poll);
 
@@ -129,7 +198,22 @@ fn main() {
         window: None,
         state: None,
         is_cntrl_pressed: false,
+        is_shift_pressed: false,
+        replay_to_watch: replay_arg(),
     };
 
     event_loop.run_app(&mut app).unwrap();
 }
+
+/// `--replay <path>`
+fn replay_arg() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "--replay" {
+            return args.next().map(PathBuf::from);
+        }
+    }
+
+    None
+}
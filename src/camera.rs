@@ -1,6 +1,6 @@
 use std::num::NonZero;
 
-use cgmath::{ortho, Matrix4, SquareMatrix, Vector2, Vector3};
+use cgmath::{ortho, Matrix3, Matrix4, SquareMatrix, Vector2, Vector3};
 use wgpu::{util::DeviceExt, BufferUsages};
 use winit::dpi::PhysicalSize;
 
@@ -21,6 +21,9 @@ pub struct Camera {
     pub screen: Vector2<f32>,
     pub scale: f32,
     pub offsets: Vector2<f32>,
+    /// Applied in playfield space before scale and offsets,
+    /// see [`crate::math::playfield_matrix`]
+    pub playfield: Matrix4<f32>,
     buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
     bind_group_layout: wgpu::BindGroupLayout,
@@ -80,6 +83,7 @@ impl Camera {
             screen: Vector2::new(width, height),
             scale,
             offsets: Vector2::new(0.0, 0.0),
+            playfield: Matrix4::identity(),
             buffer,
             bind_group,
             bind_group_layout,
@@ -139,6 +143,7 @@ impl Camera {
             screen: Vector2::new(right, bottom),
             scale: 1.0,
             offsets: Vector2::new(0.0, 0.0),
+            playfield: Matrix4::identity(),
             buffer,
             bind_group,
             bind_group_layout,
@@ -205,7 +210,21 @@ impl Camera {
 
         self.gpu.view = Matrix4::identity()
             * Matrix4::from_translation(Vector3::new(offsets.x, offsets.y, 0.0))
-            * Matrix4::from_nonuniform_scale(scale, scale, 1.0);
+            * Matrix4::from_nonuniform_scale(scale, scale, 1.0)
+            * self.playfield;
+    }
+
+    /// Takes homogeneous 2D playfield matrix, buffers
+    /// have to be written afterwards
+    pub fn set_playfield_matrix(&mut self, matrix: Matrix3<f64>) {
+        self.playfield = Matrix4::new(
+            matrix.x.x as f32, matrix.x.y as f32, 0.0, 0.0,
+            matrix.y.x as f32, matrix.y.y as f32, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            matrix.z.x as f32, matrix.z.y as f32, 0.0, 1.0,
+        );
+
+        self.transform(self.scale, self.offsets);
     }
 
     #[inline]
@@ -0,0 +1,117 @@
+use crate::rgb::Rgb;
+
+/// Okabe-Ito based, no red/green pairs
+const DEUTERANOPIA: [[u8; 3]; 4] = [
+    [0, 114, 178],
+    [230, 159, 0],
+    [86, 180, 233],
+    [240, 228, 66],
+];
+
+/// Same idea as deuteranopia, reds are avoided
+/// completely since they look dark to protans
+const PROTANOPIA: [[u8; 3]; 4] = [
+    [0, 114, 178],
+    [240, 228, 66],
+    [86, 180, 233],
+    [255, 255, 255],
+];
+
+/// No blue/yellow pairs
+const TRITANOPIA: [[u8; 3]; 4] = [
+    [213, 94, 0],
+    [0, 158, 115],
+    [204, 121, 167],
+    [255, 255, 255],
+];
+
+/// Built-in combo colours for colour vision deficiencies,
+/// override skin colours when anything but `Off` is selected
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ColorPreset {
+    #[default]
+    Off,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl ColorPreset {
+    pub const ALL: [ColorPreset; 4] = [
+        ColorPreset::Off,
+        ColorPreset::Deuteranopia,
+        ColorPreset::Protanopia,
+        ColorPreset::Tritanopia,
+    ];
+
+    /// Code stored in the settings file
+    pub fn code(&self) -> &'static str {
+        match self {
+            ColorPreset::Off => "off",
+            ColorPreset::Deuteranopia => "deuteranopia",
+            ColorPreset::Protanopia => "protanopia",
+            ColorPreset::Tritanopia => "tritanopia",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.code() == code)
+    }
+
+    /// i18n key of the name shown in settings
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            ColorPreset::Off => "settings.skin.color_preset.off",
+            ColorPreset::Deuteranopia => "settings.skin.color_preset.deuteranopia",
+            ColorPreset::Protanopia => "settings.skin.color_preset.protanopia",
+            ColorPreset::Tritanopia => "settings.skin.color_preset.tritanopia",
+        }
+    }
+
+    fn palette(&self) -> Option<&'static [[u8; 3]]> {
+        match self {
+            ColorPreset::Off => None,
+            ColorPreset::Deuteranopia => Some(&DEUTERANOPIA),
+            ColorPreset::Protanopia => Some(&PROTANOPIA),
+            ColorPreset::Tritanopia => Some(&TRITANOPIA),
+        }
+    }
+
+    /// Combo colour of the object with `index`, preset
+    /// goes first and skin colours are used when it's off
+    pub fn combo_color(&self, skin_colors: &[Rgb], index: usize) -> Rgb {
+        match self.palette() {
+            Some(palette) => palette[index % palette.len()].into(),
+            None if skin_colors.is_empty() => Rgb::default(),
+            None => skin_colors[index % skin_colors.len()],
+        }
+    }
+
+    /// Stroke of the selected song select row
+    pub fn selection_color(&self) -> Rgb {
+        match self {
+            ColorPreset::Off => Rgb::new(255, 0, 0),
+            ColorPreset::Deuteranopia | ColorPreset::Protanopia => Rgb::new(240, 228, 66),
+            ColorPreset::Tritanopia => Rgb::new(213, 94, 0),
+        }
+    }
+}
+
+#[test]
+fn test_color_preset_resolution() {
+    let skin = [Rgb::new(1, 2, 3), Rgb::new(4, 5, 6)];
+
+    // Skin colours cycle when preset is off
+    assert_eq!(ColorPreset::Off.combo_color(&skin, 3).b(), 6);
+    assert_eq!(ColorPreset::Off.combo_color(&[], 3).r(), 255);
+
+    // Preset ignores skin colours
+    let color = ColorPreset::Deuteranopia.combo_color(&skin, 5);
+    assert_eq!((color.r(), color.g(), color.b()), (230, 159, 0));
+
+    for preset in ColorPreset::ALL {
+        assert_eq!(ColorPreset::from_code(preset.code()), Some(preset));
+    }
+
+    assert_eq!(ColorPreset::from_code("rainbow"), None);
+}
@@ -1,5 +1,17 @@
+use std::{ops::RangeInclusive, path::Path};
+
+use ini::Ini;
+
+use crate::{adaptive_quality::{AdaptiveQualityMode, QualityLevel}, color_preset::ColorPreset, hud_layout::{HudElement, HudLayout, HudPlacement, HUD_SCALE_RANGE}, i18n::Lang, math::PlayfieldLayout, processor::rules::GameplayRules};
+
+/// Settings file, shared with other persisted settings
+pub const CONFIG_PATH: &str = "./rosu.ini";
+
+/// Where replays are saved unless changed in settings
+pub const DEFAULT_REPLAYS_PATH: &str = "./Replays";
+
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct SliderConfig {
     pub border_feather: f32,
     pub border_size_multiplier: f32,
@@ -7,11 +19,26 @@ pub struct SliderConfig {
     pub body_alpha_multiplier: f32,
 }
 
-#[derive(Copy, Clone, Debug)]
+impl SliderConfig {
+    /// Border is baked into slider textures, so changing it needs
+    /// a re-bake. Body ones are applied when textures are drawn
+    pub fn needs_rebake(&self, other: &SliderConfig) -> bool {
+        self.border_feather != other.border_feather
+        || self.border_size_multiplier != other.border_size_multiplier
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct JudgementsConfig {
     pub fade_in_ms: f32,
     pub stay_on_screen_ms: f32,
     pub fade_out_ms: f32,
+    /// Dark outline behind judgements and a ring around 50s,
+    /// so results can be told apart without relying on colour
+    pub high_contrast: bool,
+    /// Fraction of the way from the object center to the
+    /// actual hit position judgements are drawn at
+    pub hit_position_nudge: f32,
 }
 
 impl JudgementsConfig {
@@ -22,27 +49,109 @@ impl JudgementsConfig {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct CursorConfig {
     pub size: f32,
+    /// Ring expanding from the cursor on every key press
+    pub ripples: bool,
+    /// Size multiplier of the ripples, on top of the cursor size
+    pub ripple_scale: f32,
+}
+
+/// Multipliers from 0 to 1, music ends up at `master × music × state`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VolumeConfig {
+    pub master: f32,
+    pub music: f32,
+    /// Music while gameplay is paused
+    pub pause: f32,
+    /// Music once the play is failed
+    pub fail: f32,
+}
+
+impl Default for VolumeConfig {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            music: 1.0,
+            pause: 0.5,
+            fail: 0.3,
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Config {
     /// Toggle storing slider textures in the gpu for future reuse
     pub store_slider_textures: bool,
+    /// Sliders appearing within this many ms are baked ahead of
+    /// time, only matters when slider textures are stored
+    pub bake_ahead_ms: f32,
+    /// Max amount of sliders baked ahead of time per frame
+    pub bake_ahead_per_frame: u32,
+    /// Drops optional visuals when frames get too slow
+    pub adaptive_quality: AdaptiveQualityMode,
+    /// Visuals currently dropped by the adaptive quality controller,
+    /// renderers read it from here. Runtime state, not saved
+    pub quality: QualityLevel,
     /// Will use judgements colors instead of skin colors
     /// for drawing hit objects, useful for debugging
     pub debug_use_judgements_as_colors: bool,
+    /// Draws every approach circle in one batch above all objects,
+    /// less draw calls but later objects' approach circles end up
+    /// over earlier objects
+    pub debug_batch_approach_circles: bool,
+    /// Hidden-style visuals: no approach circles
+    /// and objects fade out before their hit time
+    pub hidden: bool,
+    /// Playfield is rotated clockwise by this many degrees,
+    /// inputs are rotated back so aim stays the same
+    pub playfield_rotation: f32,
+    /// Multiplier of the default playfield size
+    pub playfield_scale: f32,
+    /// Playfield is moved down by this many pixels,
+    /// negative values move it up
+    pub playfield_vertical_offset_px: f32,
     pub slider: SliderConfig,
     pub judgements: JudgementsConfig,
     pub cursor: CursorConfig,
+    /// Practice toggles applied to the next play
+    pub rules: GameplayRules,
+    /// UI language
+    pub lang: Lang,
+    /// Fixed seed of the app RNG for testing and debugging,
+    /// a random one is used when it's not set
+    pub rng_seed: Option<u64>,
+    /// Muffles music while gameplay is paused
+    pub audio_effects: bool,
+    pub volume: VolumeConfig,
+    /// Audio backend picked in settings, the default
+    /// one is used when it's not set
+    pub audio_backend: Option<String>,
+    /// Combo colours used instead of the skin ones
+    pub color_preset: ColorPreset,
+    /// Skin elements shipped inside beatmap folders are not used
+    pub ignore_beatmap_skin: bool,
+    /// Positions and scales of gameplay HUD elements
+    pub hud_layout: HudLayout,
+    /// Directory exported replays are written to
+    pub replays_dir: String,
+    /// Replay of every completed play is saved
+    /// without pressing the button on results
+    pub always_save_replays: bool,
+    /// HUD elements are shown over song select and can be
+    /// dragged around. Runtime state, not saved
+    pub hud_edit: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             store_slider_textures: true,
+            bake_ahead_ms: 500.0,
+            bake_ahead_per_frame: 2,
+            adaptive_quality: AdaptiveQualityMode::default(),
+            quality: QualityLevel::default(),
             slider: SliderConfig {
                 border_feather: 0.1,
                 border_size_multiplier: 0.65,
@@ -50,14 +159,512 @@ impl Default for Config {
                 body_alpha_multiplier: 0.65,
             },
             debug_use_judgements_as_colors: false,
+            debug_batch_approach_circles: false,
+            hidden: false,
+            playfield_rotation: 0.0,
+            playfield_scale: 1.0,
+            playfield_vertical_offset_px: 0.0,
             judgements: JudgementsConfig {
                 fade_in_ms: 100.0,
                 stay_on_screen_ms: 100.0,
                 fade_out_ms: 100.0,
+                high_contrast: false,
+                hit_position_nudge: 0.0,
             },
             cursor: CursorConfig {
-                size: 1.0
+                size: 1.0,
+                ripples: true,
+                ripple_scale: 1.0,
             },
+            rules: GameplayRules::default(),
+            lang: Lang::default(),
+            rng_seed: None,
+            audio_effects: true,
+            volume: VolumeConfig::default(),
+            audio_backend: None,
+            color_preset: ColorPreset::default(),
+            ignore_beatmap_skin: false,
+            hud_layout: HudLayout::default(),
+            replays_dir: DEFAULT_REPLAYS_PATH.to_owned(),
+            always_save_replays: false,
+            hud_edit: false,
+        }
+    }
+}
+
+/// Field of the settings file that was not applied
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ConfigFieldError {
+    #[error("{section}.{key}: can't parse {value:?}")]
+    Parse {
+        section: &'static str,
+        key: &'static str,
+        value: String,
+    },
+    #[error("{section}.{key}: {value} is outside of {min}..={max}")]
+    OutOfRange {
+        section: &'static str,
+        key: &'static str,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+}
+
+// Same ranges as settings UI allows
+const SLIDER_RANGE: RangeInclusive<f32> = 0.0..=2.0;
+const JUDGEMENTS_RANGE: RangeInclusive<f32> = 0.0..=1000.0;
+const HIT_POSITION_NUDGE_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+const CURSOR_SIZE_RANGE: RangeInclusive<f32> = 1.0..=10.0;
+pub const RIPPLE_SCALE_RANGE: RangeInclusive<f32> = 0.5..=3.0;
+const BAKE_AHEAD_MS_RANGE: RangeInclusive<f32> = 0.0..=2000.0;
+const BAKE_AHEAD_PER_FRAME_RANGE: RangeInclusive<f32> = 0.0..=16.0;
+pub const PLAYFIELD_ROTATION_RANGE: RangeInclusive<f32> = 0.0..=360.0;
+pub const PLAYFIELD_SCALE_RANGE: RangeInclusive<f32> = 0.8..=1.0;
+/// Further limited by the window size, see [`crate::math::calc_adjusted_playfield`]
+pub const PLAYFIELD_OFFSET_RANGE: RangeInclusive<f32> = -300.0..=300.0;
+const HUD_POSITION_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+pub const VOLUME_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+pub const DIFFICULTY_RANGE: RangeInclusive<f32> = 0.0..=10.0;
+
+fn read_f32(
+    ini: &Ini,
+    section: &'static str,
+    key: &'static str,
+    range: RangeInclusive<f32>,
+    out: &mut f32,
+    errors: &mut Vec<ConfigFieldError>,
+) {
+    let Some(value) = ini.get_from(Some(section), key) else {
+        return;
+    };
+
+    let Ok(parsed) = value.trim().parse::<f32>() else {
+        errors.push(ConfigFieldError::Parse { section, key, value: value.to_owned() });
+        return;
+    };
+
+    if !range.contains(&parsed) {
+        errors.push(ConfigFieldError::OutOfRange {
+            section,
+            key,
+            value: parsed,
+            min: *range.start(),
+            max: *range.end(),
+        });
+        return;
+    }
+
+    *out = parsed;
+}
+
+/// Empty value means `None`
+fn read_opt_f32(
+    ini: &Ini,
+    section: &'static str,
+    key: &'static str,
+    range: RangeInclusive<f32>,
+    out: &mut Option<f32>,
+    errors: &mut Vec<ConfigFieldError>,
+) {
+    let Some(value) = ini.get_from(Some(section), key) else {
+        return;
+    };
+
+    if value.trim().is_empty() {
+        *out = None;
+        return;
+    }
+
+    let mut parsed = out.unwrap_or_default();
+    let errors_before = errors.len();
+    read_f32(ini, section, key, range, &mut parsed, errors);
+
+    if errors.len() == errors_before {
+        *out = Some(parsed);
+    }
+}
+
+/// Empty value means `None`
+fn read_opt_u64(
+    ini: &Ini,
+    section: &'static str,
+    key: &'static str,
+    out: &mut Option<u64>,
+    errors: &mut Vec<ConfigFieldError>,
+) {
+    let Some(value) = ini.get_from(Some(section), key) else {
+        return;
+    };
+
+    if value.trim().is_empty() {
+        *out = None;
+        return;
+    }
+
+    match value.trim().parse() {
+        Ok(parsed) => *out = Some(parsed),
+        Err(_) => errors.push(ConfigFieldError::Parse { section, key, value: value.to_owned() }),
+    }
+}
+
+fn opt_f32_to_string(value: Option<f32>) -> String {
+    value.map(|x| x.to_string()).unwrap_or_default()
+}
+
+fn read_bool(
+    ini: &Ini,
+    section: &'static str,
+    key: &'static str,
+    out: &mut bool,
+    errors: &mut Vec<ConfigFieldError>,
+) {
+    let Some(value) = ini.get_from(Some(section), key) else {
+        return;
+    };
+
+    match value.trim().parse::<bool>() {
+        Ok(parsed) => *out = parsed,
+        Err(_) => errors.push(ConfigFieldError::Parse { section, key, value: value.to_owned() }),
+    }
+}
+
+fn read_lang(
+    ini: &Ini,
+    section: &'static str,
+    key: &'static str,
+    out: &mut Lang,
+    errors: &mut Vec<ConfigFieldError>,
+) {
+    let Some(value) = ini.get_from(Some(section), key) else {
+        return;
+    };
+
+    match Lang::from_code(value.trim()) {
+        Some(lang) => *out = lang,
+        None => errors.push(ConfigFieldError::Parse { section, key, value: value.to_owned() }),
+    }
+}
+
+fn read_adaptive_quality(
+    ini: &Ini,
+    section: &'static str,
+    key: &'static str,
+    out: &mut AdaptiveQualityMode,
+    errors: &mut Vec<ConfigFieldError>,
+) {
+    let Some(value) = ini.get_from(Some(section), key) else {
+        return;
+    };
+
+    match AdaptiveQualityMode::from_code(value.trim()) {
+        Some(mode) => *out = mode,
+        None => errors.push(ConfigFieldError::Parse { section, key, value: value.to_owned() }),
+    }
+}
+
+fn read_color_preset(
+    ini: &Ini,
+    section: &'static str,
+    key: &'static str,
+    out: &mut ColorPreset,
+    errors: &mut Vec<ConfigFieldError>,
+) {
+    let Some(value) = ini.get_from(Some(section), key) else {
+        return;
+    };
+
+    match ColorPreset::from_code(value.trim()) {
+        Some(preset) => *out = preset,
+        None => errors.push(ConfigFieldError::Parse { section, key, value: value.to_owned() }),
+    }
+}
+
+impl Config {
+    /// Writes every field into `ini`, other sections are kept untouched
+    pub fn write_to_ini(&self, ini: &mut Ini) {
+        ini.with_section(Some("Renderer"))
+            .set("StoreSliderTextures", self.store_slider_textures.to_string())
+            .set("BakeAheadMs", self.bake_ahead_ms.to_string())
+            .set("BakeAheadPerFrame", self.bake_ahead_per_frame.to_string())
+            .set("AdaptiveQuality", self.adaptive_quality.code())
+            .set("DebugUseJudgementsAsColors", self.debug_use_judgements_as_colors.to_string())
+            .set("DebugBatchApproachCircles", self.debug_batch_approach_circles.to_string())
+            .set("Hidden", self.hidden.to_string())
+            .set("PlayfieldRotation", self.playfield_rotation.to_string())
+            .set("PlayfieldScale", self.playfield_scale.to_string())
+            .set("PlayfieldVerticalOffset", self.playfield_vertical_offset_px.to_string());
+
+        ini.with_section(Some("Slider"))
+            .set("BorderFeather", self.slider.border_feather.to_string())
+            .set("BorderSizeMultiplier", self.slider.border_size_multiplier.to_string())
+            .set("BodyColorSaturation", self.slider.body_color_saturation.to_string())
+            .set("BodyAlphaMultiplier", self.slider.body_alpha_multiplier.to_string());
+
+        ini.with_section(Some("Judgements"))
+            .set("FadeInMs", self.judgements.fade_in_ms.to_string())
+            .set("StayOnScreenMs", self.judgements.stay_on_screen_ms.to_string())
+            .set("FadeOutMs", self.judgements.fade_out_ms.to_string())
+            .set("HighContrast", self.judgements.high_contrast.to_string())
+            .set("HitPositionNudge", self.judgements.hit_position_nudge.to_string());
+
+        ini.with_section(Some("Skin"))
+            .set("ColorPreset", self.color_preset.code())
+            .set("IgnoreBeatmapSkin", self.ignore_beatmap_skin.to_string());
+
+        ini.with_section(Some("Cursor"))
+            .set("Size", self.cursor.size.to_string())
+            .set("Ripples", self.cursor.ripples.to_string())
+            .set("RippleScale", self.cursor.ripple_scale.to_string());
+
+        ini.with_section(Some("Gameplay"))
+            .set("Relax", self.rules.relax.to_string())
+            .set("NoFail", self.rules.no_fail.to_string())
+            .set("HardRock", self.rules.hard_rock.to_string())
+            .set("OverrideCS", opt_f32_to_string(self.rules.difficulty.cs))
+            .set("OverrideAR", opt_f32_to_string(self.rules.difficulty.ar))
+            .set("OverrideOD", opt_f32_to_string(self.rules.difficulty.od))
+            .set("OverrideHP", opt_f32_to_string(self.rules.difficulty.hp));
+
+        ini.with_section(Some("General"))
+            .set("Language", self.lang.code())
+            .set("RngSeed", self.rng_seed.map(|x| x.to_string()).unwrap_or_default());
+
+        ini.with_section(Some("Audio"))
+            .set("Backend", self.audio_backend.clone().unwrap_or_default())
+            .set("Effects", self.audio_effects.to_string())
+            .set("MasterVolume", self.volume.master.to_string())
+            .set("MusicVolume", self.volume.music.to_string())
+            .set("PauseVolume", self.volume.pause.to_string())
+            .set("FailVolume", self.volume.fail.to_string());
+
+        ini.with_section(Some("Replays"))
+            .set("Directory", &self.replays_dir)
+            .set("AlwaysSave", self.always_save_replays.to_string());
+
+        for element in HudElement::ALL {
+            let placement = self.hud_layout.get(element);
+            let [x, y, scale] = element.ini_keys();
+
+            ini.with_section(Some("Hud"))
+                .set(x, placement.x.to_string())
+                .set(y, placement.y.to_string())
+                .set(scale, placement.scale.to_string());
         }
     }
+
+    /// Applies every valid field from `ini`. Invalid fields keep
+    /// their current values and are returned, missing ones are skipped
+    pub fn read_from_ini(&mut self, ini: &Ini) -> Vec<ConfigFieldError> {
+        let mut errors = Vec::new();
+
+        read_bool(ini, "Renderer", "StoreSliderTextures", &mut self.store_slider_textures, &mut errors);
+        read_f32(ini, "Renderer", "BakeAheadMs", BAKE_AHEAD_MS_RANGE, &mut self.bake_ahead_ms, &mut errors);
+
+        let mut per_frame = self.bake_ahead_per_frame as f32;
+        read_f32(ini, "Renderer", "BakeAheadPerFrame", BAKE_AHEAD_PER_FRAME_RANGE, &mut per_frame, &mut errors);
+        self.bake_ahead_per_frame = per_frame as u32;
+        read_adaptive_quality(ini, "Renderer", "AdaptiveQuality", &mut self.adaptive_quality, &mut errors);
+        read_bool(ini, "Renderer", "DebugUseJudgementsAsColors", &mut self.debug_use_judgements_as_colors, &mut errors);
+        read_bool(ini, "Renderer", "DebugBatchApproachCircles", &mut self.debug_batch_approach_circles, &mut errors);
+        read_bool(ini, "Renderer", "Hidden", &mut self.hidden, &mut errors);
+        read_f32(ini, "Renderer", "PlayfieldRotation", PLAYFIELD_ROTATION_RANGE, &mut self.playfield_rotation, &mut errors);
+        read_f32(ini, "Renderer", "PlayfieldScale", PLAYFIELD_SCALE_RANGE, &mut self.playfield_scale, &mut errors);
+        read_f32(ini, "Renderer", "PlayfieldVerticalOffset", PLAYFIELD_OFFSET_RANGE, &mut self.playfield_vertical_offset_px, &mut errors);
+
+        read_f32(ini, "Slider", "BorderFeather", SLIDER_RANGE, &mut self.slider.border_feather, &mut errors);
+        read_f32(ini, "Slider", "BorderSizeMultiplier", SLIDER_RANGE, &mut self.slider.border_size_multiplier, &mut errors);
+        read_f32(ini, "Slider", "BodyColorSaturation", SLIDER_RANGE, &mut self.slider.body_color_saturation, &mut errors);
+        read_f32(ini, "Slider", "BodyAlphaMultiplier", SLIDER_RANGE, &mut self.slider.body_alpha_multiplier, &mut errors);
+
+        read_f32(ini, "Judgements", "FadeInMs", JUDGEMENTS_RANGE, &mut self.judgements.fade_in_ms, &mut errors);
+        read_f32(ini, "Judgements", "StayOnScreenMs", JUDGEMENTS_RANGE, &mut self.judgements.stay_on_screen_ms, &mut errors);
+        read_f32(ini, "Judgements", "FadeOutMs", JUDGEMENTS_RANGE, &mut self.judgements.fade_out_ms, &mut errors);
+        read_bool(ini, "Judgements", "HighContrast", &mut self.judgements.high_contrast, &mut errors);
+        read_f32(ini, "Judgements", "HitPositionNudge", HIT_POSITION_NUDGE_RANGE, &mut self.judgements.hit_position_nudge, &mut errors);
+
+        read_color_preset(ini, "Skin", "ColorPreset", &mut self.color_preset, &mut errors);
+        read_bool(ini, "Skin", "IgnoreBeatmapSkin", &mut self.ignore_beatmap_skin, &mut errors);
+
+        read_f32(ini, "Cursor", "Size", CURSOR_SIZE_RANGE, &mut self.cursor.size, &mut errors);
+        read_bool(ini, "Cursor", "Ripples", &mut self.cursor.ripples, &mut errors);
+        read_f32(ini, "Cursor", "RippleScale", RIPPLE_SCALE_RANGE, &mut self.cursor.ripple_scale, &mut errors);
+
+        read_bool(ini, "Gameplay", "Relax", &mut self.rules.relax, &mut errors);
+        read_bool(ini, "Gameplay", "NoFail", &mut self.rules.no_fail, &mut errors);
+        read_bool(ini, "Gameplay", "HardRock", &mut self.rules.hard_rock, &mut errors);
+        read_opt_f32(ini, "Gameplay", "OverrideCS", DIFFICULTY_RANGE, &mut self.rules.difficulty.cs, &mut errors);
+        read_opt_f32(ini, "Gameplay", "OverrideAR", DIFFICULTY_RANGE, &mut self.rules.difficulty.ar, &mut errors);
+        read_opt_f32(ini, "Gameplay", "OverrideOD", DIFFICULTY_RANGE, &mut self.rules.difficulty.od, &mut errors);
+        read_opt_f32(ini, "Gameplay", "OverrideHP", DIFFICULTY_RANGE, &mut self.rules.difficulty.hp, &mut errors);
+
+        read_lang(ini, "General", "Language", &mut self.lang, &mut errors);
+        read_opt_u64(ini, "General", "RngSeed", &mut self.rng_seed, &mut errors);
+
+        read_bool(ini, "Audio", "Effects", &mut self.audio_effects, &mut errors);
+        read_f32(ini, "Audio", "MasterVolume", VOLUME_RANGE, &mut self.volume.master, &mut errors);
+        read_f32(ini, "Audio", "MusicVolume", VOLUME_RANGE, &mut self.volume.music, &mut errors);
+        read_f32(ini, "Audio", "PauseVolume", VOLUME_RANGE, &mut self.volume.pause, &mut errors);
+        read_f32(ini, "Audio", "FailVolume", VOLUME_RANGE, &mut self.volume.fail, &mut errors);
+
+        if let Some(backend) = ini.get_from(Some("Audio"), "Backend").filter(|x| !x.is_empty()) {
+            self.audio_backend = Some(backend.to_owned());
+        }
+
+        if let Some(dir) = ini.get_from(Some("Replays"), "Directory").filter(|x| !x.is_empty()) {
+            self.replays_dir = dir.to_owned();
+        }
+
+        read_bool(ini, "Replays", "AlwaysSave", &mut self.always_save_replays, &mut errors);
+
+        for element in HudElement::ALL {
+            let HudPlacement { mut x, mut y, mut scale } = self.hud_layout.get(element);
+            let [x_key, y_key, scale_key] = element.ini_keys();
+
+            read_f32(ini, "Hud", x_key, HUD_POSITION_RANGE, &mut x, &mut errors);
+            read_f32(ini, "Hud", y_key, HUD_POSITION_RANGE, &mut y, &mut errors);
+            read_f32(ini, "Hud", scale_key, HUD_SCALE_RANGE, &mut scale, &mut errors);
+
+            self.hud_layout.set(element, HudPlacement { x, y, scale });
+        }
+
+        errors
+    }
+
+    /// Loads config from the settings file, defaults are used for
+    /// anything that is missing or invalid
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let mut config = Self::default();
+
+        let Ok(ini) = Ini::load_from_file(path.as_ref()) else {
+            return config;
+        };
+
+        for e in config.read_from_ini(&ini) {
+            tracing::warn!("Skipping invalid setting {e}");
+        }
+
+        config
+    }
+
+    /// Saves config into the settings file, keeping other sections
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut ini = Ini::load_from_file(path.as_ref()).unwrap_or_default();
+        self.write_to_ini(&mut ini);
+
+        ini.write_to_file(path)
+    }
+
+    /// Writes only config sections to a standalone file
+    pub fn export(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut ini = Ini::new();
+        self.write_to_ini(&mut ini);
+
+        ini.write_to_file(path)
+    }
+
+    #[inline]
+    pub fn playfield_layout(&self) -> PlayfieldLayout {
+        PlayfieldLayout {
+            scale: self.playfield_scale,
+            vertical_offset: self.playfield_vertical_offset_px,
+        }
+    }
+}
+
+#[test]
+fn test_slider_config_needs_rebake() {
+    let slider = Config::default().slider;
+
+    let body = SliderConfig {
+        body_color_saturation: 1.5,
+        body_alpha_multiplier: 0.1,
+        ..slider
+    };
+
+    let border = SliderConfig {
+        border_feather: 0.5,
+        ..slider
+    };
+
+    assert!(!slider.needs_rebake(&body));
+    assert!(slider.needs_rebake(&border));
+}
+
+#[test]
+fn test_config_ini_roundtrip() {
+    let mut config = Config::default();
+    config.hidden = true;
+    config.slider.border_feather = 0.5;
+    config.judgements.fade_out_ms = 250.0;
+    config.judgements.hit_position_nudge = 0.5;
+    config.rules.relax = true;
+    config.rules.hard_rock = true;
+    config.rules.difficulty.ar = Some(9.5);
+    config.playfield_rotation = 90.0;
+    config.playfield_scale = 0.9;
+    config.playfield_vertical_offset_px = -40.0;
+    config.adaptive_quality = AdaptiveQualityMode::Conservative;
+    config.lang = Lang::Russian;
+    config.audio_effects = false;
+    config.volume.master = 0.75;
+    config.volume.pause = 0.25;
+    config.audio_backend = Some("ALSA".to_owned());
+    config.judgements.high_contrast = true;
+    config.color_preset = ColorPreset::Tritanopia;
+    config.ignore_beatmap_skin = true;
+    config.hud_layout.set(HudElement::KeyOverlay, HudPlacement { x: 0.25, y: 0.875, scale: 1.5 });
+    config.hud_layout.zoom(HudElement::Combo, 2.0);
+    config.debug_batch_approach_circles = true;
+    config.replays_dir = "D:/osu!/Replays".to_owned();
+    config.always_save_replays = true;
+    config.cursor.ripples = false;
+    config.cursor.ripple_scale = 1.5;
+    config.rng_seed = Some(727);
+
+    let mut ini = Ini::new();
+    config.write_to_ini(&mut ini);
+
+    let mut read = Config::default();
+    assert!(read.read_from_ini(&ini).is_empty());
+    assert_eq!(read, config);
+}
+
+#[test]
+fn test_config_ini_invalid_fields() {
+    let ini = Ini::load_from_str("
+[Slider]
+BorderFeather=5.0
+BodyAlphaMultiplier=0.3
+
+[Cursor]
+Size=big
+
+[Renderer]
+Hidden=true
+").unwrap();
+
+    let mut config = Config::default();
+    let errors = config.read_from_ini(&ini);
+
+    // Valid fields are still applied
+    assert!(config.hidden);
+    assert_eq!(config.slider.body_alpha_multiplier, 0.3);
+
+    // Invalid ones are listed and keep their values
+    assert_eq!(config.slider.border_feather, Config::default().slider.border_feather);
+    assert_eq!(config.cursor.size, Config::default().cursor.size);
+    assert_eq!(errors.len(), 2);
+    assert!(errors.contains(&ConfigFieldError::OutOfRange {
+        section: "Slider",
+        key: "BorderFeather",
+        value: 5.0,
+        min: 0.0,
+        max: 2.0,
+    }));
+    assert!(errors.contains(&ConfigFieldError::Parse {
+        section: "Cursor",
+        key: "Size",
+        value: "big".to_owned(),
+    }));
 }
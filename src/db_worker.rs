@@ -0,0 +1,436 @@
+use std::{sync::{atomic::{AtomicU64, AtomicUsize, Ordering}, mpsc::{Receiver, Sender, TryRecvError}, Arc}, time::{Duration, Instant}};
+
+use crate::{osu_db::{BeatmapFilter, DbBeatmapEntry, DbCollection, OsuDatabase, SavedSelection}, search_query::parse_search_query};
+
+/// How often visible window is refetched even if it didn't change,
+/// picks up beatmaps added by a scan that is still running
+pub const WINDOW_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub enum DbRequest {
+    /// Rows `min..max` of the `ORDER BY id` list
+    Range { min: usize, max: usize },
+    Index(usize),
+    Hash(String),
+    /// Position of the beatmap inside of the filtered list
+    HashIndex(String),
+    Search(String),
+    /// Following ranges and indexes are inside of the collection,
+    /// `None` goes back to the whole list
+    SetCollection(Option<i64>),
+    /// Same as `SetCollection` but for the search box text,
+    /// empty one shows every beatmap of the collection
+    SetSearch(String),
+    Collections,
+    /// Following requests modify collections and are
+    /// answered with the updated `Collections`
+    CreateCollection { name: String, hash: Option<String> },
+    RenameCollection { id: i64, name: String },
+    DeleteCollection(i64),
+    AddToCollection { id: i64, hash: String },
+    RemoveFromCollection { id: i64, hash: String },
+    /// Not answered
+    SaveSelection(SavedSelection),
+}
+
+pub enum DbResponse {
+    Index(usize, Option<Arc<DbBeatmapEntry>>),
+    Hash(String, Option<Arc<DbBeatmapEntry>>),
+    HashIndex(String, Option<usize>),
+    /// Indexes of the matched beatmaps
+    Search(String, Vec<usize>),
+    Collections(Vec<DbCollection>),
+    /// New beatmaps were committed, e.g. by a running scan. Window is
+    /// already invalidated, indexes held by the UI may be outdated
+    Changed,
+}
+
+/// Snapshot of the rows that were visible at the time of the request
+#[derive(Debug, Default)]
+pub struct BeatmapWindow {
+    pub min: usize,
+    pub max: usize,
+    /// Amount of beatmaps in the DB when window was fetched
+    pub total: usize,
+    pub entries: Vec<Arc<DbBeatmapEntry>>,
+}
+
+impl BeatmapWindow {
+    /// Entry at the global `index`, if it's inside the window
+    pub fn get(&self, index: usize) -> Option<&Arc<DbBeatmapEntry>> {
+        index.checked_sub(self.min)
+            .and_then(|i| self.entries.get(i))
+    }
+
+    #[inline]
+    pub fn contains(&self, min: usize, max: usize) -> bool {
+        self.min == min && self.max == max
+    }
+}
+
+enum WorkerResponse {
+    Window(Arc<BeatmapWindow>),
+    Other(DbResponse),
+}
+
+/// Runs `OsuDatabase` queries on a dedicated thread so UI never
+/// waits on SQL.
+///
+/// Only one range request is in flight at a time, ranges requested
+/// in the meantime replace each other and the newest one is sent
+/// once the previous one is answered
+pub struct DbWorker {
+    tx: Sender<DbRequest>,
+    rx: Receiver<WorkerResponse>,
+
+    window: Arc<BeatmapWindow>,
+
+    range_in_flight: bool,
+    pending_range: Option<(usize, usize)>,
+    last_range: Option<(usize, usize)>,
+    last_range_sent: Instant,
+    need_refetch: bool,
+
+    /// Collection the list is filtered by
+    collection: Option<i64>,
+
+    /// Search box text the list is filtered by
+    search: String,
+
+    /// DB generation and the value of it that was last reported
+    generation: Arc<AtomicU64>,
+    seen_generation: u64,
+
+    /// Beatmaps with greater ids were added during this session
+    session_start_id: u64,
+
+    /// Requests sent by this handle
+    requests_sent: usize,
+    /// Queries executed by the worker thread
+    queries: Arc<AtomicUsize>,
+}
+
+impl DbWorker {
+    pub fn spawn(db: Arc<OsuDatabase>) -> Self {
+        let (tx, worker_rx) = std::sync::mpsc::channel();
+        let (worker_tx, rx) = std::sync::mpsc::channel();
+
+        let queries = Arc::new(AtomicUsize::new(0));
+
+        let generation = db.generation_counter();
+        let seen_generation = db.generation();
+        let session_start_id = db.session_start_id();
+
+        spawn_db_worker(db, worker_rx, worker_tx, queries.clone());
+
+        Self {
+            tx,
+            rx,
+            window: Arc::new(BeatmapWindow::default()),
+            range_in_flight: false,
+            pending_range: None,
+            last_range: None,
+            last_range_sent: Instant::now(),
+            need_refetch: false,
+            collection: None,
+            search: String::new(),
+            generation,
+            seen_generation,
+            session_start_id,
+            requests_sent: 0,
+            queries,
+        }
+    }
+
+    /// Latest fetched window, never blocks
+    #[inline]
+    pub fn window(&self) -> &Arc<BeatmapWindow> {
+        &self.window
+    }
+
+    #[inline]
+    pub fn is_range_in_flight(&self) -> bool {
+        self.range_in_flight
+    }
+
+    #[inline]
+    pub fn requests_sent(&self) -> usize {
+        self.requests_sent
+    }
+
+    pub fn queries_issued(&self) -> usize {
+        self.queries.load(Ordering::Relaxed)
+    }
+
+    /// Forces next `request_range` to query the DB even
+    /// if the range is the same, e.g. after rows were deleted
+    pub fn invalidate(&mut self) {
+        self.need_refetch = true;
+    }
+
+    /// Can be called every frame, DB is queried only if range
+    /// changed, window was invalidated or it's time to refresh
+    pub fn request_range(&mut self, min: usize, max: usize) {
+        let _span = tracy_client::span!("db_worker::request_range");
+
+        let is_stale = self.last_range_sent.elapsed() >= WINDOW_REFRESH_INTERVAL;
+
+        if self.last_range == Some((min, max)) && !self.need_refetch && !is_stale {
+            return;
+        }
+
+        if self.range_in_flight {
+            self.pending_range = Some((min, max));
+            return;
+        }
+
+        self.send_range(min, max);
+    }
+
+    fn send_range(&mut self, min: usize, max: usize) {
+        self.need_refetch = false;
+        self.range_in_flight = true;
+        self.last_range = Some((min, max));
+        self.last_range_sent = Instant::now();
+
+        self.send(DbRequest::Range { min, max });
+    }
+
+    pub fn request_index(&mut self, index: usize) {
+        self.send(DbRequest::Index(index));
+    }
+
+    pub fn request_hash(&mut self, hash: impl Into<String>) {
+        self.send(DbRequest::Hash(hash.into()));
+    }
+
+    pub fn request_hash_index(&mut self, hash: impl Into<String>) {
+        self.send(DbRequest::HashIndex(hash.into()));
+    }
+
+    pub fn request_search(&mut self, query: impl Into<String>) {
+        self.send(DbRequest::Search(query.into()));
+    }
+
+    pub fn request_collections(&mut self) {
+        self.send(DbRequest::Collections);
+    }
+
+    /// Added after the DB was opened, e.g. by an import that is still running
+    #[inline]
+    pub fn is_new(&self, entry: &DbBeatmapEntry) -> bool {
+        entry.id > self.session_start_id
+    }
+
+    #[inline]
+    pub fn collection(&self) -> Option<i64> {
+        self.collection
+    }
+
+    /// Filters the list by `collection`, window is refetched on
+    /// the next `request_range` since indexes are different now
+    pub fn set_collection(&mut self, collection: Option<i64>) {
+        if self.collection == collection {
+            return;
+        }
+
+        self.collection = collection;
+        self.invalidate();
+        self.send(DbRequest::SetCollection(collection));
+    }
+
+    #[inline]
+    pub fn search(&self) -> &str {
+        &self.search
+    }
+
+    /// Filters the list by the search box `query`, works
+    /// on top of the collection filter
+    pub fn set_search(&mut self, query: impl Into<String>) {
+        let query = query.into();
+
+        if self.search == query {
+            return;
+        }
+
+        self.search = query.clone();
+        self.invalidate();
+        self.send(DbRequest::SetSearch(query));
+    }
+
+    /// Creates a collection, `hash` is added to it right away
+    pub fn create_collection(&mut self, name: impl Into<String>, hash: Option<String>) {
+        self.send(DbRequest::CreateCollection { name: name.into(), hash });
+    }
+
+    pub fn rename_collection(&mut self, id: i64, name: impl Into<String>) {
+        self.send(DbRequest::RenameCollection { id, name: name.into() });
+    }
+
+    /// Filter is reset once `Collections` without it arrive
+    pub fn delete_collection(&mut self, id: i64) {
+        self.send(DbRequest::DeleteCollection(id));
+    }
+
+    pub fn add_to_collection(&mut self, id: i64, hash: impl Into<String>) {
+        if self.collection == Some(id) {
+            self.invalidate();
+        }
+
+        self.send(DbRequest::AddToCollection { id, hash: hash.into() });
+    }
+
+    pub fn remove_from_collection(&mut self, id: i64, hash: impl Into<String>) {
+        if self.collection == Some(id) {
+            self.invalidate();
+        }
+
+        self.send(DbRequest::RemoveFromCollection { id, hash: hash.into() });
+    }
+
+    /// Remembers the selection for the next launch
+    pub fn save_selection(&mut self, selection: SavedSelection) {
+        self.send(DbRequest::SaveSelection(selection));
+    }
+
+    fn send(&mut self, request: DbRequest) {
+        self.requests_sent += 1;
+
+        if self.tx.send(request).is_err() {
+            tracing::error!("DB worker is gone");
+        }
+    }
+
+    /// Applies fetched windows and returns other responses one by one,
+    /// meant to be called until it returns `None`
+    pub fn poll(&mut self) -> Option<DbResponse> {
+        let _span = tracy_client::span!("db_worker::poll");
+
+        let generation = self.generation.load(Ordering::Acquire);
+
+        if generation != self.seen_generation {
+            self.seen_generation = generation;
+            self.invalidate();
+
+            return Some(DbResponse::Changed);
+        }
+
+        loop {
+            match self.rx.try_recv() {
+                Ok(WorkerResponse::Window(window)) => {
+                    self.window = window;
+                    self.range_in_flight = false;
+
+                    if let Some((min, max)) = self.pending_range.take() {
+                        if !self.window.contains(min, max) || self.need_refetch {
+                            self.send_range(min, max);
+                        }
+                    }
+                },
+                Ok(WorkerResponse::Other(response)) => return Some(response),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    self.range_in_flight = false;
+                    return None;
+                },
+            }
+        }
+    }
+}
+
+fn spawn_db_worker(
+    db: Arc<OsuDatabase>,
+    rx: Receiver<DbRequest>,
+    tx: Sender<WorkerResponse>,
+    queries: Arc<AtomicUsize>,
+) {
+    std::thread::spawn(move || {
+        let mut filter = BeatmapFilter::default();
+
+        // Exits once `DbWorker` is dropped
+        while let Ok(request) = rx.recv() {
+            let _span = tracy_client::span!("db_worker::query");
+            queries.fetch_add(1, Ordering::Relaxed);
+
+            let response = match request {
+                DbRequest::Range { min, max } => {
+                    WorkerResponse::Window(Arc::new(BeatmapWindow {
+                        min,
+                        max,
+                        total: db.beatmaps_amount_matching(&filter),
+                        entries: db.beatmaps_range_matching(&filter, min, max),
+                    }))
+                },
+                DbRequest::Index(index) => WorkerResponse::Other(DbResponse::Index(
+                    index,
+                    db.get_beatmap_by_index_matching(&filter, index).map(Arc::new),
+                )),
+                DbRequest::Hash(hash) => {
+                    let entry = db.get_beatmap_by_hash(&hash).map(Arc::new);
+                    WorkerResponse::Other(DbResponse::Hash(hash, entry))
+                },
+                DbRequest::HashIndex(hash) => {
+                    let index = db.get_beatmap_index_matching(&filter, &hash);
+                    WorkerResponse::Other(DbResponse::HashIndex(hash, index))
+                },
+                DbRequest::Search(query) => {
+                    let indexes = db.search_beatmaps_in(filter.collection, &query);
+                    WorkerResponse::Other(DbResponse::Search(query, indexes))
+                },
+                DbRequest::SetCollection(new) => {
+                    filter.collection = new;
+                    continue;
+                },
+                DbRequest::SetSearch(query) => {
+                    filter.query = parse_search_query(&query);
+                    continue;
+                },
+                DbRequest::Collections => {
+                    WorkerResponse::Other(DbResponse::Collections(db.collections()))
+                },
+                DbRequest::CreateCollection { name, hash } => {
+                    match db.create_collection(&name) {
+                        Ok(id) => if let Some(hash) = hash {
+                            log_collection_error(db.add_to_collection(id, &hash));
+                        },
+                        Err(e) => tracing::error!("Failed to create collection {name}: {e}"),
+                    }
+
+                    WorkerResponse::Other(DbResponse::Collections(db.collections()))
+                },
+                DbRequest::RenameCollection { id, name } => {
+                    log_collection_error(db.rename_collection(id, &name));
+                    WorkerResponse::Other(DbResponse::Collections(db.collections()))
+                },
+                DbRequest::DeleteCollection(id) => {
+                    log_collection_error(db.delete_collection(id));
+                    WorkerResponse::Other(DbResponse::Collections(db.collections()))
+                },
+                DbRequest::AddToCollection { id, hash } => {
+                    log_collection_error(db.add_to_collection(id, &hash));
+                    WorkerResponse::Other(DbResponse::Collections(db.collections()))
+                },
+                DbRequest::RemoveFromCollection { id, hash } => {
+                    log_collection_error(db.remove_from_collection(id, &hash));
+                    WorkerResponse::Other(DbResponse::Collections(db.collections()))
+                },
+                DbRequest::SaveSelection(selection) => {
+                    if let Err(e) = db.save_selection(&selection) {
+                        tracing::error!("Failed to save selection: {e}");
+                    }
+
+                    continue;
+                },
+            };
+
+            if tx.send(response).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn log_collection_error<T>(result: Result<T, rusqlite::Error>) {
+    if let Err(e) = result {
+        tracing::error!("Failed to update collection: {e}");
+    }
+}
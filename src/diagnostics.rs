@@ -0,0 +1,237 @@
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fmt::{self, Write as _},
+    panic::PanicHookInfo,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{field::{Field, Visit}, Event, Subscriber};
+use tracing_subscriber::{layer::Context, Layer};
+
+/// How many recent log lines are kept for crash reports
+pub const LOG_BUFFER_CAPACITY: usize = 200;
+
+/// Directory where crash reports are written
+pub const CRASH_DIR: &str = "./crashes";
+
+/// Bounded buffer of the most recent log lines, oldest are dropped first
+pub struct LogRingBuffer {
+    lines: Mutex<VecDeque<String>>,
+    capacity: usize,
+    started_at: Instant,
+}
+
+impl LogRingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn push(&self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        // Buffer is also read from the panic hook, so a poisoned
+        // lock shouldn't make us lose the logs
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+
+        if lines.len() >= self.capacity {
+            lines.pop_front();
+        }
+
+        lines.push_back(line);
+    }
+
+    /// Returns lines from the oldest to the newest
+    pub fn lines(&self) -> Vec<String> {
+        self.lines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Buffer that is filled by [`RingBufferLayer`] installed in `main`
+pub fn log_buffer() -> &'static Arc<LogRingBuffer> {
+    static LOG_BUFFER: OnceLock<Arc<LogRingBuffer>> = OnceLock::new();
+
+    LOG_BUFFER.get_or_init(|| Arc::new(LogRingBuffer::new(LOG_BUFFER_CAPACITY)))
+}
+
+struct MessageVisitor<'a>(&'a mut String);
+
+impl Visit for MessageVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+/// Tracing layer that copies every event into a [`LogRingBuffer`]
+pub struct RingBufferLayer {
+    buffer: Arc<LogRingBuffer>,
+}
+
+impl RingBufferLayer {
+    pub fn new(buffer: Arc<LogRingBuffer>) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for RingBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let elapsed = self.buffer.started_at.elapsed().as_secs_f64();
+        let thread = std::thread::current();
+
+        let mut line = format!(
+            "[{elapsed:>10.3}s] {:>5} {}: ",
+            event.metadata().level(),
+            thread.name().unwrap_or("unnamed"),
+        );
+
+        event.record(&mut MessageVisitor(&mut line));
+
+        self.buffer.push(line);
+    }
+}
+
+#[derive(Default)]
+struct CrashContext {
+    state: Option<String>,
+    beatmap: Option<PathBuf>,
+}
+
+fn crash_context() -> &'static Mutex<CrashContext> {
+    static CONTEXT: OnceLock<Mutex<CrashContext>> = OnceLock::new();
+
+    CONTEXT.get_or_init(Default::default)
+}
+
+/// Records current game state for crash reports
+pub fn set_state(state: impl Into<String>) {
+    crash_context().lock().unwrap_or_else(|e| e.into_inner()).state = Some(state.into());
+}
+
+/// Records currently played beatmap for crash reports
+pub fn set_beatmap(path: Option<&Path>) {
+    crash_context().lock().unwrap_or_else(|e| e.into_inner()).beatmap = path.map(Path::to_path_buf);
+}
+
+/// Current state, active beatmap and recent log lines.
+/// Used both for crash reports and "Copy diagnostics" button
+pub fn report() -> String {
+    let mut out = String::new();
+
+    {
+        let context = crash_context().lock().unwrap_or_else(|e| e.into_inner());
+
+        let _ = writeln!(out, "Version: {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(out, "OS: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+        let _ = writeln!(out, "State: {}", context.state.as_deref().unwrap_or("unknown"));
+        let _ = writeln!(
+            out,
+            "Beatmap: {}",
+            context.beatmap.as_ref().map(|x| x.display().to_string()).unwrap_or_else(|| "none".to_string())
+        );
+    }
+
+    let lines = log_buffer().lines();
+    let _ = writeln!(out, "\nLast {} log lines:", lines.len());
+
+    for line in lines {
+        let _ = writeln!(out, "{line}");
+    }
+
+    out
+}
+
+fn write_crash_report(info: &PanicHookInfo) -> std::io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default();
+
+    let thread = std::thread::current();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "Thread '{}' {info}", thread.name().unwrap_or("unnamed"));
+    let _ = writeln!(out, "\nBacktrace:\n{}", Backtrace::force_capture());
+    let _ = writeln!(out, "{}", report());
+
+    std::fs::create_dir_all(CRASH_DIR)?;
+
+    let path = Path::new(CRASH_DIR).join(format!("crash-{timestamp}.txt"));
+    std::fs::write(&path, out)?;
+
+    Ok(path)
+}
+
+/// Writes crash report to the [`CRASH_DIR`] and shows
+/// a message box pointing to it, then runs the default hook
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        let description = match write_crash_report(info) {
+            Ok(path) => format!(
+                "rosu crashed, crash report was saved to:\n{}",
+                std::path::absolute(&path).unwrap_or(path).display()
+            ),
+            Err(e) => format!("rosu crashed and failed to save crash report: {e}"),
+        };
+
+        rfd::MessageDialog::new()
+            .set_level(rfd::MessageLevel::Error)
+            .set_title("rosu crashed")
+            .set_description(description)
+            .set_buttons(rfd::MessageButtons::Ok)
+            .show();
+    }));
+}
+
+#[test]
+fn test_log_ring_buffer_bounded() {
+    let buffer = LogRingBuffer::new(3);
+
+    for i in 0..5 {
+        buffer.push(i.to_string());
+    }
+
+    // Oldest lines are dropped, order is kept
+    assert_eq!(buffer.lines(), ["2", "3", "4"]);
+}
+
+#[test]
+fn test_ring_buffer_layer() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let buffer = Arc::new(LogRingBuffer::new(2));
+    let subscriber = tracing_subscriber::registry()
+        .with(RingBufferLayer::new(buffer.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("first");
+        tracing::warn!("second");
+        tracing::error!(id = 3, "third");
+    });
+
+    let lines = buffer.lines();
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("WARN") && lines[0].ends_with("second"));
+    assert!(lines[1].contains("ERROR") && lines[1].ends_with("third id=3"));
+}
@@ -0,0 +1,117 @@
+use rosu_map::Beatmap;
+
+use crate::{hit_objects::hit_window::HitWindow, math::{calc_hitcircle_diameter, calculate_preempt_fadein}, processor::rules::{DifficultyOverrides, GameplayRules}};
+
+/// Difficulty settings of a beatmap
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Difficulty {
+    pub cs: f32,
+    pub ar: f32,
+    pub od: f32,
+    pub hp: f32,
+}
+
+impl Difficulty {
+    pub fn from_beatmap(map: &Beatmap) -> Self {
+        Self {
+            cs: map.circle_size,
+            ar: map.approach_rate,
+            od: map.overall_difficulty,
+            hp: map.hp_drain_rate,
+        }
+    }
+
+    /// Values that are actually played with
+    pub fn with_overrides(self, overrides: &DifficultyOverrides) -> Self {
+        Self {
+            cs: overrides.cs.unwrap_or(self.cs),
+            ar: overrides.ar.unwrap_or(self.ar),
+            od: overrides.od.unwrap_or(self.od),
+            hp: overrides.hp.unwrap_or(self.hp),
+        }
+    }
+
+    /// Hard Rock multipliers, same as stable
+    pub fn with_hard_rock(self) -> Self {
+        Self {
+            cs: (self.cs * 1.3).min(10.0),
+            ar: (self.ar * 1.4).min(10.0),
+            od: (self.od * 1.4).min(10.0),
+            hp: (self.hp * 1.4).min(10.0),
+        }
+    }
+
+    /// Values a play with `rules` is judged with,
+    /// explicit overrides win over Hard Rock
+    pub fn with_rules(self, rules: &GameplayRules) -> Self {
+        let difficulty = if rules.hard_rock {
+            self.with_hard_rock()
+        } else {
+            self
+        };
+
+        difficulty.with_overrides(&rules.difficulty)
+    }
+
+    /// osu!standard values computed from the settings
+    pub fn derive(&self) -> DerivedDifficulty {
+        let (preempt, fadein) = calculate_preempt_fadein(self.ar);
+
+        DerivedDifficulty {
+            preempt,
+            fadein,
+            circle_diameter: calc_hitcircle_diameter(self.cs),
+            hit_window: HitWindow::from_od(self.od),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DerivedDifficulty {
+    /// In ms
+    pub preempt: f32,
+    /// In ms
+    pub fadein: f32,
+    /// In osu!pixels
+    pub circle_diameter: f32,
+    pub hit_window: HitWindow,
+}
+
+#[test]
+fn test_derived_difficulty() {
+    let difficulty = Difficulty { cs: 4.0, ar: 9.0, od: 8.0, hp: 5.0 };
+    let derived = difficulty.derive();
+
+    assert_eq!(derived.preempt, 600.0);
+    assert_eq!(derived.fadein, 400.0);
+    assert!((derived.circle_diameter - 72.99).abs() < 0.01);
+    assert_eq!(derived.hit_window, HitWindow { x300: 32.0, x100: 76.0, x50: 120.0 });
+
+    let overrides = DifficultyOverrides { ar: Some(10.0), ..Default::default() };
+    let overridden = difficulty.with_overrides(&overrides);
+
+    assert_eq!(overridden.ar, 10.0);
+    assert_eq!(overridden.od, 8.0);
+    assert_eq!(overridden.derive().preempt, 450.0);
+}
+
+#[test]
+fn test_hard_rock_difficulty() {
+    let difficulty = Difficulty { cs: 4.0, ar: 9.0, od: 5.0, hp: 5.0 };
+
+    let rules = GameplayRules { hard_rock: true, ..Default::default() };
+    let hard_rock = difficulty.with_rules(&rules);
+
+    assert!((hard_rock.cs - 5.2).abs() < 1e-6);
+    assert_eq!(hard_rock.ar, 10.0);
+    assert_eq!(hard_rock.od, 7.0);
+
+    // Overrides are applied on top
+    let rules = GameplayRules {
+        difficulty: DifficultyOverrides { ar: Some(8.0), ..Default::default() },
+        ..rules
+    };
+
+    assert_eq!(difficulty.with_rules(&rules).ar, 8.0);
+    assert_eq!(difficulty.with_rules(&GameplayRules::default()), difficulty);
+}
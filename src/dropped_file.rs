@@ -0,0 +1,77 @@
+use std::{fs::File, path::{Path, PathBuf}};
+
+use thiserror::Error;
+
+/// Directory where dropped beatmap archives are extracted to
+pub const DEFAULT_SONGS_PATH: &str = "./Songs";
+
+/// What to do with a file dropped onto the window
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DroppedFileKind {
+    /// .osu, started right away without going through the DB
+    Beatmap,
+    /// .osz, extracted and imported into the DB
+    BeatmapArchive,
+    /// .osr
+    Replay,
+    Unknown,
+}
+
+/// Routes dropped file by its extension, case insensitive
+pub fn route_dropped_file(path: impl AsRef<Path>) -> DroppedFileKind {
+    let Some(ext) = path.as_ref().extension().and_then(|x| x.to_str()) else {
+        return DroppedFileKind::Unknown;
+    };
+
+    match ext.to_ascii_lowercase().as_str() {
+        "osu" => DroppedFileKind::Beatmap,
+        "osz" => DroppedFileKind::BeatmapArchive,
+        "osr" => DroppedFileKind::Replay,
+        _ => DroppedFileKind::Unknown,
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("broken archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("archive has no file name")]
+    NoFileName,
+}
+
+/// Extracts .osz into its own directory inside `songs_dir`,
+/// returns path to that directory
+pub fn extract_beatmap_archive(
+    archive: impl AsRef<Path>,
+    songs_dir: impl AsRef<Path>,
+) -> Result<PathBuf, ArchiveError> {
+    let _span = tracy_client::span!("dropped_file::extract_beatmap_archive");
+
+    let archive = archive.as_ref();
+    let name = archive.file_stem().ok_or(ArchiveError::NoFileName)?;
+
+    let out_dir = songs_dir.as_ref().join(name);
+    std::fs::create_dir_all(&out_dir)?;
+
+    let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+
+    // Entries with paths escaping `out_dir` are rejected by the zip crate
+    zip.extract(&out_dir)?;
+
+    tracing::info!("Extracted {} to {}", archive.display(), out_dir.display());
+
+    Ok(out_dir)
+}
+
+#[test]
+fn test_route_dropped_file() {
+    assert_eq!(route_dropped_file("map.osu"), DroppedFileKind::Beatmap);
+    assert_eq!(route_dropped_file("/songs/1 set/map [Hard].OSU"), DroppedFileKind::Beatmap);
+    assert_eq!(route_dropped_file("set.osz"), DroppedFileKind::BeatmapArchive);
+    assert_eq!(route_dropped_file("replay.Osr"), DroppedFileKind::Replay);
+    assert_eq!(route_dropped_file("skin.osk"), DroppedFileKind::Unknown);
+    assert_eq!(route_dropped_file("osu"), DroppedFileKind::Unknown);
+    assert_eq!(route_dropped_file("directory/"), DroppedFileKind::Unknown);
+}
@@ -26,12 +26,12 @@ impl EguiState {
             None,
         );
 
-        let surface_config = graphics.get_surface_config();
+        let format = graphics.format();
 
 
         let egui_renderer = Renderer::new(
             &graphics.device, 
-            surface_config.format,
+            format,
             None, 
             1,
             false
@@ -0,0 +1,274 @@
+use std::{collections::VecDeque, time::{Duration, Instant}};
+
+use egui::{Color32, Pos2, Stroke};
+
+use crate::adaptive_quality::QualityLevel;
+
+#[cfg(feature = "render-stats")]
+use crate::render_stats::{render_stats_ui, RenderStats, RenderStatsLogger};
+
+/// Amount of frames kept for the graph
+const FRAMES_TO_KEEP: usize = 240;
+
+/// How often rolling median gets recalculated, in frames.
+/// Sorting every frame is not free so doing it once in a while
+const MEDIAN_UPDATE_RATE: usize = 60;
+
+/// Frame considered as a spike if it takes this much longer than median
+const SPIKE_MULTIPLIER: f64 = 3.0;
+
+const SPIKE_LOG_FRAMES: usize = 10;
+
+/// Window in which input latency gets averaged
+const LATENCY_WINDOW: Duration = Duration::from_secs(1);
+
+const GRAPH_WIDTH: f32 = 240.0;
+const GRAPH_HEIGHT: f32 = 80.0;
+
+/// Collects frame times and input latencies for
+/// the performance overlay.
+///
+/// Collecting is always on (it's just a push into ring buffer)
+/// but everything expensive happens only when overlay is visible
+pub struct FrameHistory {
+    last_frame: Instant,
+
+    /// Frame times in milliseconds
+    frame_times: VecDeque<f64>,
+
+    /// (when result was assigned, latency in milliseconds)
+    latencies: VecDeque<(Instant, f64)>,
+
+    rolling_median: f64,
+    frames_since_median: usize,
+
+    is_visible: bool,
+
+    /// Visuals dropped by adaptive quality, only shown
+    quality: QualityLevel,
+
+    // Reused for percentile calculations
+    sorted: Vec<f64>,
+
+    /// Renderer counters of the last finished frame
+    #[cfg(feature = "render-stats")]
+    render_stats: RenderStats,
+    #[cfg(feature = "render-stats")]
+    render_stats_logger: RenderStatsLogger,
+}
+
+impl Default for FrameHistory {
+    fn default() -> Self {
+        Self {
+            last_frame: Instant::now(),
+            frame_times: VecDeque::with_capacity(FRAMES_TO_KEEP),
+            latencies: VecDeque::with_capacity(64),
+            rolling_median: 0.0,
+            frames_since_median: 0,
+            is_visible: false,
+            quality: QualityLevel::default(),
+            sorted: Vec::with_capacity(FRAMES_TO_KEEP),
+            #[cfg(feature = "render-stats")]
+            render_stats: RenderStats::default(),
+            #[cfg(feature = "render-stats")]
+            render_stats_logger: RenderStatsLogger::default(),
+        }
+    }
+}
+
+impl FrameHistory {
+    pub fn toggle(&mut self) {
+        self.is_visible = !self.is_visible;
+    }
+
+    /// Should be called once at the beginning of every frame
+    pub fn on_new_frame(&mut self) {
+        let _span = tracy_client::span!("frame_history::on_new_frame");
+        let now = Instant::now();
+        let frame_time = now.duration_since(self.last_frame).as_secs_f64() * 1000.0;
+        self.last_frame = now;
+
+        self.push_frame_time(frame_time);
+    }
+
+    pub fn push_frame_time(&mut self, frame_time: f64) {
+        if self.frame_times.len() >= FRAMES_TO_KEEP {
+            self.frame_times.pop_front();
+        }
+
+        self.frame_times.push_back(frame_time);
+
+        self.frames_since_median += 1;
+        if self.frames_since_median >= MEDIAN_UPDATE_RATE {
+            self.frames_since_median = 0;
+            self.rolling_median = self.percentile(0.5);
+        }
+
+        if self.rolling_median > 0.0
+        && frame_time > self.rolling_median * SPIKE_MULTIPLIER {
+            let last: Vec<String> = self.frame_times
+                .iter()
+                .rev()
+                .take(SPIKE_LOG_FRAMES)
+                .rev()
+                .map(|x| format!("{x:.2}"))
+                .collect();
+
+            tracing::warn!(
+                "Frame took {:.2}ms (median {:.2}ms), last frames: [{}]",
+                frame_time, self.rolling_median, last.join(", ")
+            );
+        }
+    }
+
+    /// Time of the last finished frame, in ms
+    #[inline]
+    pub fn last_frame_time(&self) -> Option<f64> {
+        self.frame_times.back().copied()
+    }
+
+    #[inline]
+    pub fn set_quality_level(&mut self, quality: QualityLevel) {
+        self.quality = quality;
+    }
+
+    #[cfg(feature = "render-stats")]
+    pub fn set_render_stats(&mut self, stats: RenderStats) {
+        self.render_stats_logger.on_frame(&stats);
+        self.render_stats = stats;
+    }
+
+    /// Stores latency between input timestamp and time when
+    /// the judgement was assigned
+    pub fn push_input_latency(&mut self, latency: f64) {
+        let now = Instant::now();
+
+        self.latencies.push_back((now, latency));
+
+        while let Some((at, _)) = self.latencies.front() {
+            if now.duration_since(*at) > LATENCY_WINDOW {
+                self.latencies.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Average input latency over the last second
+    pub fn average_input_latency(&self) -> Option<f64> {
+        let now = Instant::now();
+
+        let (sum, count) = self.latencies
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) <= LATENCY_WINDOW)
+            .fold((0.0, 0), |(sum, count), (_, latency)| (sum + latency, count + 1));
+
+        if count == 0 {
+            return None;
+        }
+
+        Some(sum / count as f64)
+    }
+
+    /// Returns `p` percentile of stored frame times, `p` is in 0.0..=1.0 range
+    pub fn percentile(&mut self, p: f64) -> f64 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        self.sorted.clear();
+        self.sorted.extend(self.frame_times.iter());
+        self.sorted.sort_by(|a, b| a.partial_cmp(b).expect("failed to compare"));
+
+        let index = ((self.sorted.len() - 1) as f64 * p).round() as usize;
+
+        self.sorted[index]
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) {
+        if !self.is_visible {
+            return;
+        }
+
+        let _span = tracy_client::span!("frame_history::render");
+
+        let p95 = self.percentile(0.95);
+        let p99 = self.percentile(0.99);
+        let last = self.frame_times.back().copied().unwrap_or(0.0);
+        let latency = self.average_input_latency();
+
+        egui::Area::new(egui::Id::new("frame_history_overlay"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            // Renderer stats header has to be clickable
+            .interactable(cfg!(feature = "render-stats"))
+            .show(ctx, |ui| {
+                egui::Frame::default()
+                    .fill(Color32::from_rgba_unmultiplied(0, 0, 0, 200))
+                    .inner_margin(5.0)
+                    .show(ui, |ui| {
+                        ui.label(format!("Frame: {last:.2}ms p95: {p95:.2}ms p99: {p99:.2}ms"));
+
+                        match latency {
+                            Some(latency) => ui.label(format!("Input latency: {latency:.2}ms")),
+                            None => ui.label("Input latency: -"),
+                        };
+
+                        ui.label(format!("Quality: {} ({:?})", self.quality.index(), self.quality));
+
+                        let (rect, _) = ui.allocate_exact_size(
+                            egui::Vec2::new(GRAPH_WIDTH, GRAPH_HEIGHT),
+                            egui::Sense::hover(),
+                        );
+
+                        // Scaling graph to the p99 so single spike doesn't
+                        // squash everything else
+                        let max = (p99 * 1.5).max(1.0);
+                        let step = GRAPH_WIDTH / FRAMES_TO_KEEP as f32;
+
+                        let points: Vec<Pos2> = self.frame_times
+                            .iter()
+                            .enumerate()
+                            .map(|(i, time)| {
+                                let y = (*time / max).min(1.0) as f32 * GRAPH_HEIGHT;
+                                Pos2::new(rect.min.x + i as f32 * step, rect.max.y - y)
+                            })
+                            .collect();
+
+                        let painter = ui.painter_at(rect);
+
+                        let p95_y = rect.max.y - (p95 / max).min(1.0) as f32 * GRAPH_HEIGHT;
+                        painter.hline(rect.x_range(), p95_y, Stroke::new(1.0, Color32::YELLOW));
+
+                        painter.add(egui::Shape::line(points, Stroke::new(1.0, Color32::GREEN)));
+
+                        #[cfg(feature = "render-stats")]
+                        render_stats_ui(ui, &self.render_stats);
+                    });
+            });
+    }
+}
+
+#[test]
+fn test_frame_history_percentile() {
+    let mut history = FrameHistory::default();
+
+    for i in 1..=100 {
+        history.push_frame_time(i as f64);
+    }
+
+    assert_eq!(history.percentile(0.5), 51.0);
+    assert_eq!(history.percentile(0.99), 99.0);
+    assert_eq!(history.percentile(1.0), 100.0);
+}
+
+#[test]
+fn test_frame_history_keeps_last_frames() {
+    let mut history = FrameHistory::default();
+
+    for i in 0..FRAMES_TO_KEEP * 2 {
+        history.push_frame_time(i as f64);
+    }
+
+    assert_eq!(history.frame_times.len(), FRAMES_TO_KEEP);
+    assert_eq!(*history.frame_times.front().unwrap(), FRAMES_TO_KEEP as f64);
+}
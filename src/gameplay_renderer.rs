@@ -0,0 +1,110 @@
+use wgpu::TextureView;
+
+use crate::{hit_objects::{hit_window::HitWindow, Object, ObjectKind}, osu_renderer::OsuRenderer};
+
+/// Timings of the beatmap at the drawn frame
+#[derive(Debug, Clone, Copy)]
+pub struct GameplayFrame<'a> {
+    pub time: f64,
+    pub preempt: f32,
+    pub fadein: f32,
+    pub hit_window: &'a HitWindow,
+}
+
+/// Per-frame gameplay drawing shared by the game, the replay viewer
+/// and wasm: builds queues of visible objects, bakes their sliders,
+/// prepares instances and draws them with an [`OsuRenderer`]
+#[derive(Default)]
+pub struct GameplayRenderer {
+    objects_queue: Vec<usize>,
+    judgements_queue: Vec<usize>,
+    /// Judgements are drawn from the processor results,
+    /// off for views that only show the beatmap
+    judgements: bool,
+}
+
+impl GameplayRenderer {
+    pub fn new(judgements: bool) -> Self {
+        Self {
+            judgements,
+            ..Default::default()
+        }
+    }
+
+    /// Judgements queue holds every object every frame, sizing
+    /// queues once avoids regrowing them during the play
+    pub fn reserve(&mut self, objects: usize) {
+        self.objects_queue.clear();
+        self.objects_queue.reserve(objects);
+        self.judgements_queue.clear();
+
+        if self.judgements {
+            self.judgements_queue.reserve(objects);
+        }
+    }
+
+    // Going through every object on beatmap and preparing it to
+    // assigned buffers
+    pub fn prepare(&mut self, renderer: &mut OsuRenderer, frame: &GameplayFrame, objects: &mut [Object]) {
+        let _span = tracy_client::span!("gameplay_renderer::prepare");
+
+        self.objects_queue.clear();
+        self.judgements_queue.clear();
+
+        for (i, obj) in objects.iter_mut().enumerate().rev() {
+            if self.judgements {
+                self.judgements_queue.push(i);
+            }
+
+            if !obj.is_visible(frame.time, frame.preempt, frame.hit_window) {
+                continue;
+            }
+
+            if let ObjectKind::Slider(slider) = &mut obj.kind {
+                renderer.prepare_and_render_slider_texture(slider);
+            }
+
+            self.objects_queue.push(i);
+        }
+
+        // Visible sliders are baked above, upcoming ones
+        // are baked a few at a time to avoid spikes
+        renderer.bake_ahead(frame.time, frame.preempt, objects);
+
+        if self.judgements {
+            renderer.prepare_judgements(frame.time, &self.judgements_queue, objects);
+        }
+
+        renderer.prepare_objects(
+            frame.time, frame.preempt, frame.fadein,
+            &self.objects_queue,
+            objects,
+            frame.hit_window,
+        );
+
+        // Syncing settings with the renderer
+        renderer.prepare();
+
+        // When we are done preparing all objects for rendering
+        // we should not forget to upload all that to gpu
+        renderer.write_buffers();
+    }
+
+    /// Draws objects from the last [`Self::prepare`]
+    pub fn render(
+        &mut self,
+        renderer: &mut OsuRenderer,
+        view: &TextureView,
+        objects: &[Object],
+    ) -> Result<(), wgpu::SurfaceError> {
+        let _span = tracy_client::span!("gameplay_renderer::render");
+
+        renderer.render_objects(view, &self.objects_queue, objects)?;
+
+        // Clearing queues only after they successfully rendered
+        self.objects_queue.clear();
+        self.judgements_queue.clear();
+
+        Ok(())
+    }
+}
@@ -1,25 +1,59 @@
-use std::sync::{Arc, Mutex};
+use std::sync::{atomic::{AtomicU32, Ordering}, Arc, Mutex, RwLock};
 
+use thiserror::Error;
 use wgpu::{BackendOptions, Instance, InstanceDescriptor, MemoryHints, PresentMode, RequestAdapterOptions, SurfaceTexture};
-use winit::window::Window;
+use winit::{dpi::{LogicalSize, PhysicalSize}, window::Window};
 
-pub struct GraphicsInitialized<'gi> {
-    pub surface: wgpu::Surface<'gi>,
+use crate::texture_upload::TextureUploader;
+
+/// Initial window size, scaled by the monitor scale factor
+pub const DEFAULT_WINDOW_SIZE: LogicalSize<f64> = LogicalSize::new(1280.0, 720.0);
+
+#[derive(Debug, Error)]
+pub enum PresentError {
+    #[error("graphics has no present target")]
+    Headless,
+    #[error(transparent)]
+    CreateSurface(#[from] wgpu::CreateSurfaceError),
+    #[error("surface doesn't support {format:?}, available formats: {available:?}")]
+    UnsupportedFormat {
+        format: wgpu::TextureFormat,
+        available: Vec<wgpu::TextureFormat>,
+    },
+}
+
+pub struct GraphicsInitialized {
+    pub instance: wgpu::Instance,
+    pub surface: wgpu::Surface<'static>,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub adapter: wgpu::Adapter,
     pub size: winit::dpi::PhysicalSize<u32>,
 }
 
-pub struct Graphics<'g> {
-    pub surface: wgpu::Surface<'g>,
+/// Everything that is needed to present frames to a window
+struct PresentTarget {
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    surface: wgpu::Surface<'static>,
+}
+
+pub struct Graphics {
+    /// Behind a lock so the surface can be recreated
+    /// without rebuilding the whole renderer.
+    /// `None` when rendering headless
+    present: RwLock<Option<PresentTarget>>,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub config: Mutex<wgpu::SurfaceConfiguration>,
     pub size: winit::dpi::PhysicalSize<u32>,
+    /// How many times surface was reconfigured by [`Self::resize`]
+    configure_count: AtomicU32,
+    /// Staged texture uploads, see [`Self::flush_uploads`]
+    pub uploads: Mutex<TextureUploader>,
 }
 
-impl<'g> Graphics<'g> {
+impl Graphics {
     pub async fn new(window: Arc<Window>) -> Self {
         let _span = tracy_client::span!("wgpu init");
 
@@ -58,6 +92,7 @@ impl<'g> Graphics<'g> {
         let (device, queue) = adapter.request_device(&device_descriptor, None).await.unwrap();
 
         let graphics = GraphicsInitialized {
+            instance,
             surface,
             device,
             queue,
@@ -68,7 +103,81 @@ impl<'g> Graphics<'g> {
         Self::from_initialized(graphics)
     }
 
-    pub fn from_initialized(graphics: GraphicsInitialized<'g>) -> Self {
+    /// Creates graphics from already existing device without any window,
+    /// everything should be rendered to the offscreen textures of `format`.
+    ///
+    /// Methods that require a surface return errors
+    pub fn headless(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        size: PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: size.width,
+            height: size.height,
+            present_mode: PresentMode::AutoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 1,
+        };
+
+        Graphics {
+            config: Mutex::new(config),
+            device,
+            queue,
+            size,
+            present: RwLock::new(None),
+            configure_count: AtomicU32::new(0),
+            uploads: Mutex::new(TextureUploader::default()),
+        }
+    }
+
+    /// Same as [`Self::headless`] but also requests a device.
+    ///
+    /// Adapter can be picked with `WGPU_ADAPTER_NAME` and `WGPU_BACKEND`
+    /// env variables, otherwise default one is used with
+    /// a fallback (software) adapter as the last resort.
+    /// Returns `None` if there are no adapters at all
+    pub async fn headless_from_env(
+        width: u32, 
+        height: u32, 
+        format: wgpu::TextureFormat
+    ) -> Option<Self> {
+        let _span = tracy_client::span!("wgpu headless init");
+
+        let instance = Instance::new(&InstanceDescriptor {
+            backends: wgpu::Backends::from_env().unwrap_or(wgpu::Backends::all()),
+            ..Default::default()
+        });
+
+        let adapter = match wgpu::util::initialize_adapter_from_env_or_default(&instance, None).await {
+            Some(adapter) => adapter,
+            None => instance.request_adapter(&RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::None,
+                force_fallback_adapter: true,
+                compatible_surface: None,
+            }).await?,
+        };
+
+        tracing::info!("Initialized headless adapter: {:?}", adapter.get_info());
+
+        let (device, queue) = adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: wgpu::Features::default(),
+                required_limits: wgpu::Limits::default(),
+                memory_hints: MemoryHints::default(),
+            },
+            None
+        ).await.ok()?;
+
+        Some(Self::headless(device, queue, PhysicalSize::new(width, height), format))
+    }
+
+    pub fn from_initialized(graphics: GraphicsInitialized) -> Self {
         let present_mode = PresentMode::AutoVsync;
         let surface_caps = graphics.surface.get_capabilities(&graphics.adapter);
 
@@ -116,10 +225,54 @@ impl<'g> Graphics<'g> {
             device: graphics.device,
             queue: graphics.queue,
             size: graphics.size,
-            surface: graphics.surface,
+            present: RwLock::new(Some(PresentTarget {
+                instance: graphics.instance,
+                adapter: graphics.adapter,
+                surface: graphics.surface,
+            })),
+            configure_count: AtomicU32::new(0),
+            uploads: Mutex::new(TextureUploader::default()),
         };
     }
 
+    /// Creates a new surface for the provided window and configures it
+    /// with the current surface configuration.
+    ///
+    /// Used to recover from [`wgpu::SurfaceError::Lost`] or when the window
+    /// moved somewhere the old surface is no longer valid. The old surface
+    /// is kept when the new one doesn't support the current format.
+    pub fn recreate_surface(&self, window: Arc<Window>) -> Result<(), PresentError> {
+        let _span = tracy_client::span!("wgpu recreate_surface");
+
+        let mut present = self.present.write().unwrap();
+        let Some(present) = present.as_mut() else {
+            return Err(PresentError::Headless);
+        };
+
+        let surface = present.instance.create_surface(window)?;
+        let surface_caps = surface.get_capabilities(&present.adapter);
+
+        let lock = self.config.lock().unwrap();
+
+        // All pipelines are built against the initial format so we can't
+        // just switch to another one here, configuring the surface with
+        // it anyway is a validation error
+        if !surface_caps.formats.contains(&lock.format) {
+            return Err(PresentError::UnsupportedFormat {
+                format: lock.format,
+                available: surface_caps.formats,
+            });
+        }
+
+        surface.configure(&self.device, &lock);
+
+        present.surface = surface;
+
+        tracing::info!("Recreated surface with config: {:#?}", &lock);
+
+        Ok(())
+    }
+
     pub fn resize(&self, new_size: &winit::dpi::PhysicalSize<u32>) {
         let _span = tracy_client::span!("wgpu resize");
         if new_size.width > 0 && new_size.height > 0 {
@@ -128,7 +281,12 @@ impl<'g> Graphics<'g> {
             lock.width = new_size.width;
             lock.height = new_size.height;
 
-            self.surface.configure(&self.device, &lock);
+            if let Some(present) = self.present.read().unwrap().as_ref() {
+                present.surface.configure(&self.device, &lock);
+
+                let count = self.configure_count.fetch_add(1, Ordering::Relaxed) + 1;
+                tracing::debug!("Reconfigured surface to {}x{}, {count} times so far", lock.width, lock.height);
+            }
         }
     }
 
@@ -144,7 +302,29 @@ impl<'g> Graphics<'g> {
         lock.clone()
     }
 
+    /// Format of the surface or offscreen target,
+    /// every pipeline should be built against it
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.config.lock().unwrap().format
+    }
+
+    /// Copies the next chunk of staged textures,
+    /// called once per frame before rendering
+    pub fn flush_uploads(&self) {
+        self.uploads.lock().unwrap().flush(&self.device, &self.queue);
+    }
+
+    #[inline]
+    pub fn is_headless(&self) -> bool {
+        self.present.read().unwrap().is_none()
+    }
+
+    /// Headless graphics have nothing to present
+    /// so [`wgpu::SurfaceError::Lost`] is returned
     pub fn get_current_texture(&self) -> Result<SurfaceTexture, wgpu::SurfaceError> {
-        self.surface.get_current_texture()
+        match self.present.read().unwrap().as_ref() {
+            Some(present) => present.surface.get_current_texture(),
+            None => Err(wgpu::SurfaceError::Lost),
+        }
     }
 }
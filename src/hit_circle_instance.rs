@@ -1,6 +1,6 @@
 use cgmath::Vector3;
 
-use crate::rgb::Rgb;
+use crate::{math::debug_assert_finite, rgb::Rgb};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Zeroable, bytemuck::Pod)]
@@ -9,15 +9,18 @@ pub struct HitCircleInstance {
     pub color: [f32; 3], 
     pub alpha: f32,
     pub scale: f32,
+    /// Degrees, quad is rotated around its center
+    pub rotation: f32,
 }
 
 impl HitCircleInstance {
-    const ATTRIBS: [wgpu::VertexAttribute; 4] = 
+    const ATTRIBS: [wgpu::VertexAttribute; 5] = 
         wgpu::vertex_attr_array![
             2 => Float32x3,
             3 => Float32x3,
             4 => Float32,
             5 => Float32,
+            6 => Float32,
         ];
 
     pub fn new(
@@ -26,8 +29,11 @@ impl HitCircleInstance {
         z: f32, 
         alpha: f32,
         scale: f32,
-        color: &Rgb
+        color: &Rgb,
+        rotation: f32,
     ) -> HitCircleInstance {
+        debug_assert_finite(x, y);
+
         let mat = Vector3::new(x, y, z);
 
         Self {
@@ -35,6 +41,7 @@ impl HitCircleInstance {
             color: color.to_gpu_values(),
             alpha,
             scale,
+            rotation,
         }
     }
 
@@ -66,6 +73,8 @@ impl ApproachCircleInstance {
     pub fn new(
         x: f32, y: f32, z: f32, alpha: f32, scale: f32
     ) -> Self {
+        debug_assert_finite(x, y);
+
         let mat = Vector3::new(x, y, z);
 
         Self {
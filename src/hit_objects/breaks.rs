@@ -0,0 +1,81 @@
+use rosu_map::Beatmap;
+
+/// How long break overlay takes to fade in and out, in ms
+pub const BREAK_FADE_TIME: f64 = 300.0;
+
+/// Break period of the beatmap, there are no objects
+/// between `start_time` and `end_time`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Break {
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+impl Break {
+    /// Breaks sorted by start time
+    pub fn from_rosu(map: &Beatmap) -> Vec<Self> {
+        let mut breaks: Vec<Self> = map.breaks
+            .iter()
+            .filter(|x| x.end_time > x.start_time)
+            .map(|x| Self {
+                start_time: x.start_time,
+                end_time: x.end_time,
+            })
+            .collect();
+
+        breaks.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+        breaks
+    }
+
+    #[inline]
+    pub fn contains(&self, time: f64) -> bool {
+        (self.start_time..self.end_time).contains(&time)
+    }
+
+    #[inline]
+    pub fn duration(&self) -> f64 {
+        self.end_time - self.start_time
+    }
+
+    /// Alpha of the break overlay, fades in after
+    /// the start and fades out before the end
+    pub fn overlay_alpha(&self, time: f64) -> f64 {
+        if !self.contains(time) {
+            return 0.0;
+        }
+
+        let fade_in = (time - self.start_time) / BREAK_FADE_TIME;
+        let fade_out = (self.end_time - time) / BREAK_FADE_TIME;
+
+        fade_in.min(fade_out).clamp(0.0, 1.0)
+    }
+}
+
+/// Break that contains `time`, `breaks` are expected to be sorted
+pub fn break_at(breaks: &[Break], time: f64) -> Option<&Break> {
+    let index = breaks.partition_point(|x| x.start_time <= time);
+
+    index.checked_sub(1)
+        .map(|i| &breaks[i])
+        .filter(|x| x.contains(time))
+}
+
+#[test]
+fn test_break_at() {
+    let breaks = [
+        Break { start_time: 1000.0, end_time: 2000.0 },
+        Break { start_time: 5000.0, end_time: 15000.0 },
+    ];
+
+    assert_eq!(break_at(&breaks, 500.0), None);
+    assert_eq!(break_at(&breaks, 1000.0), Some(&breaks[0]));
+    assert_eq!(break_at(&breaks, 2000.0), None);
+    assert_eq!(break_at(&breaks, 10000.0), Some(&breaks[1]));
+    assert_eq!(break_at(&[], 10000.0), None);
+
+    assert_eq!(breaks[1].overlay_alpha(5000.0), 0.0);
+    assert_eq!(breaks[1].overlay_alpha(5000.0 + BREAK_FADE_TIME / 2.0), 0.5);
+    assert_eq!(breaks[1].overlay_alpha(10000.0), 1.0);
+    assert_eq!(breaks[1].overlay_alpha(15000.0), 0.0);
+}
@@ -8,10 +8,26 @@ use super::{hit_window::HitWindow, Hit, CIRCLE_FADEOUT_TIME, JUDGMENTS_FADEOUT_T
 #[derive(Debug)]
 pub struct CircleHitResult {
     pub at: f64,
+    /// Cursor position of the input that got judged
     pub pos: Vector2<f64>,
     pub result: Hit
 }
 
+impl CircleHitResult {
+    /// Where the click landed relative to `center`, in osu!px.
+    /// Misses don't have a click to measure
+    pub fn offset_from(&self, center: Pos) -> Option<Vector2<f64>> {
+        if self.result == Hit::MISS {
+            return None;
+        }
+
+        Some(Vector2::new(
+            self.pos.x - center.x as f64,
+            self.pos.y - center.y as f64,
+        ))
+    }
+}
+
 pub struct Circle {
     pub start_time: f64,
     pub pos: Pos,
@@ -20,6 +36,11 @@ pub struct Circle {
 }
 
 impl Circle {
+    #[inline]
+    pub fn hit_offset(&self) -> Option<Vector2<f64>> {
+        self.hit_result.as_ref().and_then(|x| x.offset_from(self.pos))
+    }
+
     pub fn is_visible(&self, time: f64, preempt: f32, hit_window: &HitWindow) -> bool {
         time > self.start_time - preempt as f64 && time < self.start_time + CIRCLE_FADEOUT_TIME + hit_window.x50
     }
@@ -0,0 +1,433 @@
+use std::sync::OnceLock;
+
+use cgmath::Vector2;
+use rosu_map::{section::{general::GameMode, hit_objects::{Curve, PathControlPoint, SplineType}}, util::Pos, Beatmap};
+
+use crate::{difficulty::Difficulty, math::{calc_hitcircle_diameter, calc_opposite_direction_degree}};
+
+use super::{
+    circle::Circle,
+    hit_window::HitWindow,
+    slider::{self, ControlPoint, SegmentKind, Slider, Tick},
+    Object, ObjectKind, DEFAULT_BEAT_LEN,
+};
+
+/// Mode specific part of turning parsed beatmap into playable objects.
+///
+/// Only osu!standard is implemented, other modes can be
+/// prototyped by adding a converter and returning it from [`converter_for`]
+pub trait ModeConverter {
+    fn mode(&self) -> GameMode;
+
+    /// Broken objects are not a reason to fail the whole beatmap,
+    /// they are either fixed up or skipped with a warning
+    fn convert(&self, map: &Beatmap) -> Vec<Object>;
+
+    fn hit_window(&self, difficulty: &Difficulty) -> HitWindow;
+
+    /// Diameter of hit objects in osu!pixels
+    fn hit_circle_diameter(&self, difficulty: &Difficulty) -> f32;
+}
+
+/// Converter for the `mode`, `None` if mode isn't supported
+pub fn converter_for(mode: GameMode) -> Option<&'static dyn ModeConverter> {
+    match mode {
+        GameMode::Osu => Some(&StdConverter),
+        _ => None,
+    }
+}
+
+/// Mode stored in the database as u8, same values as in .osu files
+pub fn mode_from_u8(mode: u8) -> Option<GameMode> {
+    match mode {
+        0 => Some(GameMode::Osu),
+        1 => Some(GameMode::Taiko),
+        2 => Some(GameMode::Catch),
+        3 => Some(GameMode::Mania),
+        _ => None,
+    }
+}
+
+/// B-splines of any degree are drawn as bezier segments
+fn control_points(points: &[PathControlPoint]) -> Vec<ControlPoint> {
+    points.iter()
+        .map(|point| ControlPoint {
+            pos: point.pos,
+            kind: point.path_type.map(|x| match x.kind {
+                SplineType::BSpline => SegmentKind::Bezier,
+                SplineType::Linear => SegmentKind::Linear,
+                SplineType::PerfectCurve => SegmentKind::PerfectCurve,
+                SplineType::Catmull => SegmentKind::Catmull,
+            }),
+        })
+        .collect()
+}
+
+/// Points sampled when checking that a slider curve is usable
+const CURVE_CHECK_SAMPLES: usize = 64;
+
+#[inline]
+fn is_finite_pos(pos: Pos) -> bool {
+    pos.x.is_finite() && pos.y.is_finite()
+}
+
+/// Degenerate curves (collinear perfect circles, duplicate or
+/// overlapping control points) may end up with NaN positions
+/// that would poison instance buffers
+fn is_finite_curve(curve: &Curve) -> bool {
+    (0..=CURVE_CHECK_SAMPLES)
+        .map(|i| curve.position_at(i as f64 / CURVE_CHECK_SAMPLES as f64))
+        .all(is_finite_pos)
+}
+
+/// Ticks closer than this to the end of a span are dropped, same as stable
+const TICK_MIN_DISTANCE_FROM_END: f64 = 10.0;
+
+/// Slack for tick times derived from BPM and tick rate, a tick that
+/// is meant to land exactly on a limit stays on the same side of it
+const TICK_EPSILON: f64 = 1e-6;
+
+/// Where and when a slider tick or repeat happens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CheckpointTime {
+    pub time: f64,
+    /// Progress along the curve, 0.0 is the head
+    pub progress: f64,
+    /// 1 based span, repeats belong to the span they end
+    pub slide: usize,
+    pub is_reverse: bool,
+}
+
+/// Ticks and repeats of a slider sorted by time.
+///
+/// Ticks are placed `k * tick_interval` from the start of the path in every
+/// span independently, so the error doesn't build up over long sliders.
+/// Reversed spans go along the path backwards, so their ticks are mirrored
+/// in time and land on the same positions as in forward spans, like in stable.
+/// Repeats sit exactly on span boundaries, ticks never get within
+/// [`TICK_MIN_DISTANCE_FROM_END`] of them so both are never at the same time
+pub fn checkpoint_times(
+    start_time: f64,
+    span_duration: f64,
+    span_count: usize,
+    tick_interval: f64,
+) -> Vec<CheckpointTime> {
+    // Same for every span
+    let mut tick_offsets = Vec::new();
+
+    if tick_interval.is_finite() && tick_interval > TICK_EPSILON {
+        let limit = span_duration - TICK_MIN_DISTANCE_FROM_END - TICK_EPSILON;
+
+        let mut k = 1;
+
+        while k as f64 * tick_interval < limit {
+            tick_offsets.push(k as f64 * tick_interval);
+            k += 1;
+        }
+    }
+
+    let mut checkpoints = Vec::with_capacity((tick_offsets.len() + 1) * span_count);
+
+    for span in 0..span_count {
+        let span_start = start_time + span as f64 * span_duration;
+        let span_end = start_time + (span + 1) as f64 * span_duration;
+        let is_reversed = span % 2 == 1;
+
+        let ticks = tick_offsets.iter().map(|offset| CheckpointTime {
+            time: match is_reversed {
+                true => span_end - offset,
+                false => span_start + offset,
+            },
+            progress: offset / span_duration,
+            slide: span + 1,
+            is_reverse: false,
+        });
+
+        match is_reversed {
+            true => checkpoints.extend(ticks.rev()),
+            false => checkpoints.extend(ticks),
+        }
+
+        if span + 1 < span_count {
+            checkpoints.push(CheckpointTime {
+                time: span_end,
+                progress: if is_reversed { 0.0 } else { 1.0 },
+                slide: span + 1,
+                is_reverse: true,
+            });
+        }
+    }
+
+    checkpoints
+}
+
+/// osu!standard
+pub struct StdConverter;
+
+impl ModeConverter for StdConverter {
+    fn mode(&self) -> GameMode {
+        GameMode::Osu
+    }
+
+    fn hit_window(&self, difficulty: &Difficulty) -> HitWindow {
+        HitWindow::from_od(difficulty.od)
+    }
+
+    fn hit_circle_diameter(&self, difficulty: &Difficulty) -> f32 {
+        calc_hitcircle_diameter(difficulty.cs)
+    }
+
+    fn convert(&self, map: &Beatmap) -> Vec<Object> {
+            let mut color_index = 1;
+            let tick_rate = map.slider_tick_rate;
+
+
+            let values = &map.hit_objects;
+            let mut objects = Vec::with_capacity(values.len());
+
+            for value in values {
+                if value.new_combo() {
+                    color_index += 1;
+                }
+
+                if color_index > 8 {
+                    color_index = 0;
+                }
+
+                match &value.kind {
+                    rosu_map::section::hit_objects::HitObjectKind::Slider(slider) if !is_finite_pos(slider.pos) => {
+                        tracing::warn!("Slider at {} has invalid position ({}, {}), skipping", value.start_time, slider.pos.x, slider.pos.y);
+                    },
+                    rosu_map::section::hit_objects::HitObjectKind::Slider(slider) => {
+                        //dbg!("====++=========");
+                        let beat_len = match map.control_points.timing_point_at(value.start_time) {
+                            Some(timing) if timing.beat_len.is_finite() && timing.beat_len > 0.0 => timing.beat_len,
+                            _ => {
+                                tracing::warn!("No valid timing point for slider at {}, using default beat length", value.start_time);
+                                DEFAULT_BEAT_LEN
+                            },
+                        };
+                        // Broken tick rate would never get past the slider end
+                        let tick_every_ms = match beat_len / tick_rate {
+                            x if x.is_finite() && x > 0.0 => x,
+                            _ => f64::INFINITY,
+                        };
+
+                        let mut slider = slider.clone();
+
+                        let pos = slider.pos;
+                        let duration = slider.duration();
+
+                        // Zero length sliders or sliders with broken velocity
+                        // are not playable as sliders, treating them as circles
+                        if !duration.is_finite() || duration <= 0.0 || slider.span_count() < 1 {
+                            tracing::warn!(
+                                "Slider at {} has invalid duration ({duration}) or spans ({}), converting to circle",
+                                value.start_time,
+                                slider.span_count(),
+                            );
+
+                            objects.push(Object {
+                                start_time: value.start_time,
+                                color: color_index,
+                                kind: ObjectKind::Circle(Circle {
+                                    start_time: value.start_time,
+                                    pos,
+                                    hit_result: None,
+                                }),
+                            });
+
+                            continue;
+                        }
+                        let curve = slider.path.curve().clone();
+
+                        if !is_finite_curve(&curve) {
+                            tracing::warn!("Slider at {} has a degenerate curve, skipping", value.start_time);
+                            continue;
+                        }
+
+                        let span_duration = duration / f64::from(slider.span_count());
+
+                        let mut ticks = Vec::new();
+                        let mut checkpoints = Vec::new();
+                        let mut reverse_arrows = Vec::new();
+
+                        let times = checkpoint_times(
+                            value.start_time,
+                            span_duration,
+                            slider.span_count() as usize,
+                            tick_every_ms,
+                        );
+
+                        for checkpoint in times {
+                            let curve_pos = curve.position_at(checkpoint.progress);
+
+                            let pos = Vector2::new(
+                                slider.pos.x + curve_pos.x,
+                                slider.pos.y + curve_pos.y
+                            );
+
+                            let tick = Tick {
+                                pos,
+                                time: checkpoint.time,
+                                slide: checkpoint.slide,
+                                is_reverse: checkpoint.is_reverse,
+                            };
+
+                            if !checkpoint.is_reverse {
+                                ticks.push(tick);
+                                checkpoints.push(tick);
+                                continue;
+                            }
+
+                            // Arrow points back along the curve, even
+                            // repeats are the ones at the slider head
+                            let towards = match checkpoint.slide % 2 == 0 {
+                                true => curve.position_at(0.05),
+                                false => curve.position_at(0.95),
+                            };
+
+                            let towards = Vector2::new(slider.pos.x + towards.x, slider.pos.y + towards.y);
+
+                            reverse_arrows.push(
+                                slider::ReverseArrow {
+                                    time: checkpoint.time,
+                                    angle: -calc_opposite_direction_degree(towards, pos),
+                                }
+                            );
+
+                            checkpoints.push(tick);
+                        }
+
+                        objects.push(Object {
+                            start_time: value.start_time,
+                            color: color_index,
+                            kind: ObjectKind::Slider(Slider {
+                                repeats: slider.span_count(),
+                                start_time: value.start_time,
+                                pos,
+                                duration,
+                                curve,
+                                control_points: control_points(slider.path.control_points()),
+                                curve_lut: OnceLock::new(),
+                                lazy_path: OnceLock::new(),
+                                ticks,
+                                #[cfg(feature = "render")]
+                                render: None,
+                                reverse_arrows,
+                                hit_result: None,
+                                checkpoints,
+                            }),
+                        })
+                    }
+                    rosu_map::section::hit_objects::HitObjectKind::Circle(circle) if !is_finite_pos(circle.pos) => {
+                        tracing::warn!("Circle at {} has invalid position ({}, {}), skipping", value.start_time, circle.pos.x, circle.pos.y);
+                    },
+                    rosu_map::section::hit_objects::HitObjectKind::Circle(circle) => objects.push(Object {
+                        start_time: value.start_time,
+                        color: color_index,
+                        kind: ObjectKind::Circle(Circle {
+                            start_time: value.start_time,
+                            pos: circle.pos,
+                            hit_result: None,
+                        }),
+                    }),
+                    _ => {},
+                };
+            };
+
+        objects
+    }
+}
+
+#[test]
+fn test_converter_for() {
+    assert!(converter_for(GameMode::Osu).is_some_and(|x| x.mode() == GameMode::Osu));
+    assert!(converter_for(GameMode::Taiko).is_none());
+    assert!(converter_for(GameMode::Mania).is_none());
+
+    assert_eq!(mode_from_u8(0), Some(GameMode::Osu));
+    assert_eq!(mode_from_u8(3), Some(GameMode::Mania));
+    assert_eq!(mode_from_u8(4), None);
+}
+
+#[test]
+fn test_is_finite_pos() {
+    assert!(is_finite_pos(Pos { x: 0.0, y: 384.0 }));
+    assert!(!is_finite_pos(Pos { x: f32::NAN, y: 0.0 }));
+    assert!(!is_finite_pos(Pos { x: 0.0, y: f32::INFINITY }));
+}
+
+#[cfg(test)]
+fn assert_checkpoint_times(actual: &[CheckpointTime], expected: &[(f64, bool)]) {
+    let actual: Vec<_> = actual.iter().map(|x| (x.time, x.is_reverse)).collect();
+
+    assert_eq!(actual.len(), expected.len(), "{actual:?} != {expected:?}");
+
+    for (a, b) in actual.iter().zip(expected) {
+        assert!((a.0 - b.0).abs() < 1e-6 && a.1 == b.1, "{actual:?} != {expected:?}");
+    }
+}
+
+#[test]
+fn test_checkpoint_times_bpm_222() {
+    // 1/3 snapped spans of 4/3 beats, one tick per beat
+    let beat_len = 60000.0 / 222.0;
+    let span_duration = beat_len * 4.0 / 3.0;
+
+    let checkpoints = checkpoint_times(1000.0, span_duration, 3, beat_len);
+
+    // Tick of the reversed span is mirrored from its end
+    assert_checkpoint_times(&checkpoints, &[
+        (1000.0 + beat_len, false),
+        (1000.0 + span_duration, true),
+        (1000.0 + span_duration * 2.0 - beat_len, false),
+        (1000.0 + span_duration * 2.0, true),
+        (1000.0 + span_duration * 2.0 + beat_len, false),
+    ]);
+
+    // Same point of the path in every span
+    assert!(checkpoints.iter().filter(|x| !x.is_reverse).all(|x| (x.progress - 0.75).abs() < 1e-9));
+    assert_eq!(checkpoints[1].progress, 1.0);
+    assert_eq!(checkpoints[3].progress, 0.0);
+
+    // Tick rate 3 over a long span, the last tick is a full tick
+    // away from the end and must survive the rounding
+    let tick = beat_len / 3.0;
+    let checkpoints = checkpoint_times(1000.0, tick * 16.0, 2, tick);
+
+    let ticks: Vec<_> = checkpoints.iter().filter(|x| !x.is_reverse).collect();
+    assert_eq!(ticks.len(), 30);
+    assert!((ticks[14].time - (1000.0 + tick * 15.0)).abs() < 1e-6);
+    assert!((ticks[15].time - (1000.0 + tick * 17.0)).abs() < 1e-6);
+
+    assert!(checkpoints.windows(2).all(|x| x[0].time < x[1].time));
+}
+
+#[test]
+fn test_checkpoint_times_tick_near_span_end() {
+    // 540ms beat with tick rate 6.75 ticks every 80ms,
+    // third tick is exactly 10ms before the end and is dropped
+    let tick = 540.0 / 6.75;
+
+    assert_checkpoint_times(&checkpoint_times(0.1, 250.0, 2, tick), &[
+        (80.1, false),
+        (160.1, false),
+        (250.1, true),
+        (340.1, false),
+        (420.1, false),
+    ]);
+
+    // A bit further from the end it's kept
+    assert_eq!(checkpoint_times(0.1, 250.01, 1, tick).len(), 3);
+
+    // Tick is longer than a span
+    assert_checkpoint_times(&checkpoint_times(0.0, 50.0, 3, tick), &[
+        (50.0, true),
+        (100.0, true),
+    ]);
+
+    // Broken tick rate still leaves repeats
+    assert_eq!(checkpoint_times(0.0, 50.0, 2, f64::INFINITY).len(), 1);
+    assert!(checkpoint_times(0.0, 50.0, 1, 0.0).is_empty());
+}
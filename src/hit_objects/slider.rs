@@ -1,9 +1,13 @@
+use std::sync::OnceLock;
+#[cfg(feature = "render")]
 use std::sync::Arc;
 
-use cgmath::Vector2;
+use cgmath::{InnerSpace, Vector2};
 use rosu_map::{section::hit_objects::Curve, util::Pos};
 
-use crate::{osu_input::OsuInput, texture::Texture};
+use crate::osu_input::OsuInput;
+#[cfg(feature = "render")]
+use crate::texture::Texture;
 
 use super::{circle::CircleHitResult, hit_window::HitWindow, Hit, Rectangle, SLIDER_FADEOUT_TIME};
 
@@ -13,7 +17,7 @@ pub struct ReverseArrow {
     pub angle: f32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Tick {
     pub time: f64,
     pub pos: Vector2<f32>,
@@ -21,9 +25,138 @@ pub struct Tick {
     pub is_reverse: bool,
 }
 
+/// Shortest path the cursor has to take to stay within the follow
+/// radius of every tick, repeat and the tail, same as the lazy slider
+/// cursor of stable and lazer difficulty calculation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LazyPath {
+    /// Where the cursor is at the end of the slider, in playfield coordinates
+    pub end_pos: Pos,
+    /// Distance the cursor had to move, in osu!px
+    pub travel_distance: f32,
+}
+
+/// Curve type of a path segment, same ones as in the .osu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    Bezier,
+    Linear,
+    PerfectCurve,
+    Catmull,
+}
+
+/// Control point of the slider path, kept after conversion
+/// to show why the curve goes where it does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlPoint {
+    /// Relative to the slider head
+    pub pos: Pos,
+    /// Set on points that start a segment. Besides the head,
+    /// these are red anchors, where the path has a corner
+    pub kind: Option<SegmentKind>,
+}
+
+/// Kind of the segment every control point belongs to,
+/// red anchors belong to the segment they start
+pub fn segment_kinds(points: &[ControlPoint]) -> impl Iterator<Item = SegmentKind> + '_ {
+    points.iter().scan(SegmentKind::Bezier, |kind, point| {
+        if let Some(new) = point.kind {
+            *kind = new;
+        }
+
+        Some(*kind)
+    })
+}
+
+/// Minimal amount of samples in `CurveLut`
+pub const CURVE_LUT_MIN_SAMPLES: usize = 512;
+
+/// Upper limit of samples, curves longer than
+/// `CURVE_LUT_MAX_SAMPLES * CURVE_LUT_MAX_STEP` get sparser samples
+pub const CURVE_LUT_MAX_SAMPLES: usize = 32768;
+
+/// Max distance between neighbouring samples, in osu!pixels.
+///
+/// Interpolated position can't be further than half of the step
+/// from the curve, even at the sharp corners of red anchors,
+/// so the error stays below 0.5 osu!px
+pub const CURVE_LUT_MAX_STEP: f32 = 0.9;
+
+/// Positions along the curve sampled at equal distances,
+/// replaces evaluating the curve for every input frame
+#[derive(Debug)]
+pub struct CurveLut {
+    points: Vec<Pos>,
+}
+
+impl CurveLut {
+    pub fn new(curve: &Curve) -> Self {
+        let _span = tracy_client::span!("hit_objects::slider::CurveLut::new");
+
+        let mut lut = Self::sample(curve, CURVE_LUT_MIN_SAMPLES);
+
+        let step = lut.length() / (CURVE_LUT_MIN_SAMPLES - 1) as f32;
+
+        if step > CURVE_LUT_MAX_STEP {
+            let samples = (lut.length() / CURVE_LUT_MAX_STEP).ceil() as usize + 1;
+            lut = Self::sample(curve, samples.min(CURVE_LUT_MAX_SAMPLES));
+        }
+
+        lut
+    }
+
+    fn sample(curve: &Curve, samples: usize) -> Self {
+        // `position_at` takes progress along the curve length,
+        // so equal progress steps are equal distances
+        let points = (0..samples)
+            .map(|i| curve.position_at(i as f64 / (samples - 1) as f64))
+            .collect();
+
+        Self { points }
+    }
+
+    /// Sum of the distances between samples
+    fn length(&self) -> f32 {
+        self.points
+            .windows(2)
+            .map(|x| ((x[1].x - x[0].x).powi(2) + (x[1].y - x[0].y).powi(2)).sqrt())
+            .sum()
+    }
+
+    /// Same as `Curve::position_at`, `progress` is clamped to 0.0..=1.0
+    pub fn position_at(&self, progress: f64) -> Pos {
+        let last = self.points.len() - 1;
+        let index = progress.clamp(0.0, 1.0) * last as f64;
+
+        let i = (index.floor() as usize).min(last.saturating_sub(1));
+        let t = (index - i as f64) as f32;
+
+        let a = self.points[i];
+        let Some(b) = self.points.get(i + 1) else {
+            return a;
+        };
+
+        Pos {
+            x: a.x + (b.x - a.x) * t,
+            y: a.y + (b.y - a.y) * t,
+        }
+    }
+}
+
+#[cfg(feature = "render")]
 pub struct SliderRender {
     pub texture: Arc<Texture>,
     pub quad: Arc<wgpu::Buffer>,
+    /// Size of the texture, counted in render stats
+    #[cfg(feature = "render-stats")]
+    pub bytes: u64,
+}
+
+#[cfg(feature = "render-stats")]
+impl Drop for SliderRender {
+    fn drop(&mut self) {
+        crate::render_stats::SLIDER_TEXTURE_BYTES.fetch_sub(self.bytes, std::sync::atomic::Ordering::Relaxed);
+    }
 }
 
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
@@ -41,11 +174,53 @@ pub enum SliderResultState {
     Passed(Hit)
 }
 
+/// Parts of a slider judged as they happen, combo follows these
+/// while the final `Hit` is only known once the slider is over
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SliderEvent {
+    Head { time: f64, hit: bool },
+    /// Tick or repeat, `time` is the time of the checkpoint
+    /// itself, not of the frame it was judged at
+    Checkpoint { time: f64, index: usize, passed: bool },
+    End { time: f64, passed: bool },
+}
+
+impl SliderEvent {
+    #[inline]
+    pub fn time(&self) -> f64 {
+        match self {
+            SliderEvent::Head { time, .. }
+            | SliderEvent::Checkpoint { time, .. }
+            | SliderEvent::End { time, .. } => *time,
+        }
+    }
+
+    /// Missed heads and checkpoints break combo, missed end doesn't
+    #[inline]
+    pub fn is_combo_break(&self) -> bool {
+        match self {
+            SliderEvent::Head { hit, .. } => !hit,
+            SliderEvent::Checkpoint { passed, .. } => !passed,
+            SliderEvent::End { .. } => false,
+        }
+    }
+
+    #[inline]
+    pub fn is_combo_hit(&self) -> bool {
+        match self {
+            SliderEvent::Head { hit, .. } => *hit,
+            SliderEvent::Checkpoint { passed, .. } | SliderEvent::End { passed, .. } => *passed,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SliderResult {
     pub state: SliderResultState,
     pub head: CircleHitResult,
     pub passed_checkpoints: Vec<usize>,
+    /// Checkpoints that already have a `SliderEvent`
+    pub resolved_checkpoints: usize,
     pub lenience_passed: bool,
     pub holding_since: Option<f64>,
     pub in_radius_since: Option<f64>,
@@ -86,6 +261,24 @@ impl SliderResult {
             Some(Hit::MISS)
         }
     }
+
+    /// Emits events of checkpoints before `until`, the ones that
+    /// are not in `passed_checkpoints` by then are failed
+    fn resolve_checkpoints(&mut self, checkpoints: &[Tick], until: f64, events: &mut Vec<SliderEvent>) {
+        while let Some(checkpoint) = checkpoints.get(self.resolved_checkpoints) {
+            if checkpoint.time >= until {
+                break;
+            }
+
+            events.push(SliderEvent::Checkpoint {
+                time: checkpoint.time,
+                index: self.resolved_checkpoints,
+                passed: self.passed_checkpoints.contains(&self.resolved_checkpoints),
+            });
+
+            self.resolved_checkpoints += 1;
+        }
+    }
 }
 
 pub struct Slider {
@@ -93,6 +286,13 @@ pub struct Slider {
     pub duration: f64,
 
     pub curve: Curve,
+    /// Path the curve was calculated from, first point is the head
+    pub control_points: Vec<ControlPoint>,
+    /// Built from `curve` on the first `position_at` call
+    pub curve_lut: OnceLock<CurveLut>,
+    /// Built on the first `lazy_travel` call along
+    /// with the follow radius it was built for
+    pub lazy_path: OnceLock<(f32, LazyPath)>,
     pub pos: Pos, // TODO: Make the same as in circle
 
     /// Total repeats
@@ -112,6 +312,8 @@ pub struct Slider {
     /// Should contain both ticks and slider reverses
     pub checkpoints: Vec<Tick>,
 
+    /// Baked slider body, only exists with the renderer compiled in
+    #[cfg(feature = "render")]
     pub render: Option<SliderRender>,
 
     pub hit_result: Option<SliderResult>,
@@ -122,7 +324,86 @@ impl Slider {
     pub fn end_time(&self) -> f64 {
         self.start_time + self.duration
     }
+
+    /// Control points where a new segment starts after the head
+    pub fn red_anchors(&self) -> impl Iterator<Item = &ControlPoint> {
+        self.control_points.iter()
+            .skip(1)
+            .filter(|x| x.kind.is_some())
+    }
+
+    /// Same as `Circle::hit_offset` for the slider head
+    #[inline]
+    pub fn head_hit_offset(&self) -> Option<Vector2<f64>> {
+        self.hit_result.as_ref().and_then(|x| x.head.offset_from(self.pos))
+    }
+
+    /// Position on the curve relative to the slider head,
+    /// within 0.5 osu!px of `Curve::position_at`
+    #[inline]
+    pub fn position_at(&self, progress: f64) -> Pos {
+        self.curve_lut
+            .get_or_init(|| CurveLut::new(&self.curve))
+            .position_at(progress)
+    }
     
+    /// Tail position in playfield coordinates, it's
+    /// the head when slider has an even amount of slides
+    pub fn end_position(&self) -> Pos {
+        let progress = if self.repeats % 2 == 0 { 0.0 } else { 1.0 };
+        let pos = self.position_at(progress);
+
+        Pos {
+            x: self.pos.x + pos.x,
+            y: self.pos.y + pos.y,
+        }
+    }
+
+    /// Lazy cursor path with `follow_radius` in osu!px. Computed once,
+    /// calls with another radius compute it again without caching
+    pub fn lazy_travel(&self, follow_radius: f32) -> LazyPath {
+        if let Some((radius, path)) = self.lazy_path.get() {
+            if *radius == follow_radius {
+                return *path;
+            }
+        }
+
+        let path = self.compute_lazy_travel(follow_radius);
+        let _ = self.lazy_path.set((follow_radius, path));
+
+        path
+    }
+
+    fn compute_lazy_travel(&self, follow_radius: f32) -> LazyPath {
+        let _span = tracy_client::span!("hit_objects::slider::compute_lazy_travel");
+
+        let tail = self.end_position();
+
+        let targets = self.checkpoints.iter()
+            .map(|x| x.pos)
+            .chain(std::iter::once(Vector2::new(tail.x, tail.y)));
+
+        let mut cursor = Vector2::new(self.pos.x, self.pos.y);
+        let mut travel_distance = 0.0;
+
+        // Cursor is only dragged along once it gets outside of the radius
+        for target in targets {
+            let diff = target - cursor;
+            let distance = diff.magnitude();
+
+            if distance > follow_radius {
+                let moved = distance - follow_radius;
+                cursor += diff * (moved / distance);
+                travel_distance += moved;
+            }
+        }
+
+        LazyPath {
+            end_pos: Pos { x: cursor.x, y: cursor.y },
+            travel_distance,
+        }
+    }
+
     /// Returns slide index for certain time
     /// Indexes starts from 1
     ///
@@ -186,7 +467,8 @@ impl Slider {
         &mut self, 
         input: &OsuInput,
         hit_window: &HitWindow,
-        circle_diameter: f32
+        circle_diameter: f32,
+        events: &mut Vec<SliderEvent>,
     ) -> Option<()> {
         let _span = tracy_client::span!("hit_objects::slider::update");
 
@@ -211,7 +493,7 @@ impl Slider {
 
         let slider_radius = circle_diameter as f64 / 2.0;
         let slider_ball_progress = self.get_slider_progress(input.ts);
-        let slider_ball_pos = self.curve.position_at(
+        let slider_ball_pos = self.position_at(
             slider_ball_progress
         );
         
@@ -250,6 +532,7 @@ impl Slider {
                 SliderResult {
                     head,
                     passed_checkpoints: vec![],
+                    resolved_checkpoints: 0,
                     state: SliderResultState::Middle,
                     holding_since: Some(input.ts),
                     in_radius_since: if is_inside_slider_ball { Some(input.ts) } else { None },
@@ -265,17 +548,22 @@ impl Slider {
                 }
             );
 
+            events.push(SliderEvent::Head { time: input.ts, hit: true });
+
             return Some(());
         }
 
         return None;
     }
 
+    /// Judges slider body, `events` get every part
+    /// of the slider at the moment it's judged
     pub fn update_post(
         &mut self, 
         input: &OsuInput,
         hit_window: &HitWindow,
-        circle_diameter: f32
+        circle_diameter: f32,
+        events: &mut Vec<SliderEvent>,
     ) -> Option<Hit> {
         let _span = tracy_client::span!("hit_objects::slider::update_post");
 
@@ -285,7 +573,7 @@ impl Slider {
         // Position at slider for current input
         let slider_progress = self.get_slider_progress(input.ts);
 
-        let pos_at_slider = self.curve.position_at(
+        let pos_at_slider = self.position_at(
             slider_progress
         );
 
@@ -321,6 +609,7 @@ impl Slider {
                                 result: Hit::MISS,
                             },
                             passed_checkpoints: vec![],
+                            resolved_checkpoints: 0,
                             state: SliderResultState::Middle,
                             holding_since: if is_holding { Some(input.ts) } else { None },
                             in_radius_since: if is_in_radius { Some(input.ts) } else { None },
@@ -335,6 +624,8 @@ impl Slider {
                             is_tracking,
                         }
                     );
+
+                    events.push(SliderEvent::Head { time: input.ts, hit: false });
                 }
 
                 return None;
@@ -381,6 +672,9 @@ impl Slider {
                                 panic!("Trying to set Passed slider state without final hit result");
                             };
 
+                            result.resolve_checkpoints(&self.checkpoints, f64::INFINITY, events);
+                            events.push(SliderEvent::End { time: lenience_hack_time, passed: true });
+
                             result.state = SliderResultState::Passed(final_result);
                             return Some(final_result);
                         }
@@ -480,6 +774,8 @@ impl Slider {
                 }
 
             }
+
+            result.resolve_checkpoints(&self.checkpoints, input.ts, events);
         }
 
         if input.ts >= self.start_time + self.duration {
@@ -487,6 +783,9 @@ impl Slider {
                 panic!("Trying to set Passed slider state without final hit result");
             };
 
+            result.resolve_checkpoints(&self.checkpoints, f64::INFINITY, events);
+            events.push(SliderEvent::End { time: self.start_time + self.duration, passed: false });
+
             result.state = SliderResultState::Passed(final_result);
             return Some(final_result);
         }
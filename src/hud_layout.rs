@@ -0,0 +1,208 @@
+use std::ops::RangeInclusive;
+
+/// Same range as the mouse wheel allows in the layout editor
+pub const HUD_SCALE_RANGE: RangeInclusive<f32> = 0.5..=3.0;
+
+/// Scale multiplier of a single mouse wheel step
+const HUD_SCALE_STEP: f32 = 1.1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HudElement {
+    /// Hit counts of the play
+    Score,
+    Combo,
+    Accuracy,
+    KeyOverlay,
+}
+
+impl HudElement {
+    pub const ALL: [HudElement; 4] = [
+        HudElement::Score,
+        HudElement::Combo,
+        HudElement::Accuracy,
+        HudElement::KeyOverlay,
+    ];
+
+    fn index(&self) -> usize {
+        match self {
+            HudElement::Score => 0,
+            HudElement::Combo => 1,
+            HudElement::Accuracy => 2,
+            HudElement::KeyOverlay => 3,
+        }
+    }
+
+    /// i18n key of the name shown in settings
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            HudElement::Score => "hud.score",
+            HudElement::Combo => "hud.combo",
+            HudElement::Accuracy => "hud.accuracy",
+            HudElement::KeyOverlay => "hud.key_overlay",
+        }
+    }
+
+    /// Settings file keys of x, y and scale
+    pub fn ini_keys(&self) -> [&'static str; 3] {
+        match self {
+            HudElement::Score => ["ScoreX", "ScoreY", "ScoreScale"],
+            HudElement::Combo => ["ComboX", "ComboY", "ComboScale"],
+            HudElement::Accuracy => ["AccuracyX", "AccuracyY", "AccuracyScale"],
+            HudElement::KeyOverlay => ["KeyOverlayX", "KeyOverlayY", "KeyOverlayScale"],
+        }
+    }
+
+    pub fn default_placement(&self) -> HudPlacement {
+        let (x, y) = match self {
+            HudElement::Score => (1.0, 0.0),
+            HudElement::Accuracy => (1.0, 0.06),
+            HudElement::Combo => (0.0, 1.0),
+            HudElement::KeyOverlay => (1.0, 0.5),
+        };
+
+        HudPlacement { x, y, scale: 1.0 }
+    }
+}
+
+/// Position of an element in 0..1 of the window, so it
+/// stays in place when the window is resized
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HudPlacement {
+    pub x: f32,
+    pub y: f32,
+    pub scale: f32,
+}
+
+impl HudPlacement {
+    /// Element is attached to the nearest window edges by its matching
+    /// corner and grows away from them, e.g. right half elements
+    /// are placed by their right edge
+    #[inline]
+    pub fn anchored_right(&self) -> bool {
+        self.x > 0.5
+    }
+
+    /// Key overlay stacks keys upwards when it's true
+    #[inline]
+    pub fn anchored_bottom(&self) -> bool {
+        self.y > 0.5
+    }
+
+    /// Keeps the whole element on the screen,
+    /// `size` is in 0..1 of the window as well
+    pub fn clamped(self, size: [f32; 2]) -> Self {
+        let clamp = |pos: f32, size: f32, far_edge: bool| {
+            let size = size.clamp(0.0, 1.0);
+
+            match far_edge {
+                true => pos.clamp(size, 1.0),
+                false => pos.clamp(0.0, 1.0 - size),
+            }
+        };
+
+        Self {
+            x: clamp(self.x, size[0], self.anchored_right()),
+            y: clamp(self.y, size[1], self.anchored_bottom()),
+            scale: self.scale.clamp(*HUD_SCALE_RANGE.start(), *HUD_SCALE_RANGE.end()),
+        }
+    }
+}
+
+/// Where gameplay HUD elements are drawn, every
+/// HUD renderer takes its placement from here
+#[derive(Debug, Clone, PartialEq)]
+pub struct HudLayout {
+    placements: [HudPlacement; 4],
+}
+
+impl Default for HudLayout {
+    fn default() -> Self {
+        Self {
+            placements: HudElement::ALL.map(|x| x.default_placement()),
+        }
+    }
+}
+
+impl HudLayout {
+    #[inline]
+    pub fn get(&self, element: HudElement) -> HudPlacement {
+        self.placements[element.index()]
+    }
+
+    pub fn set(&mut self, element: HudElement, placement: HudPlacement) {
+        self.placements[element.index()] = placement;
+    }
+
+    /// `delta` and `size` are in 0..1 of the window
+    pub fn move_by(&mut self, element: HudElement, delta: [f32; 2], size: [f32; 2]) {
+        let placement = self.get(element);
+
+        let moved = HudPlacement {
+            x: placement.x + delta[0],
+            y: placement.y + delta[1],
+            ..placement
+        };
+
+        self.set(element, moved.clamped(size));
+    }
+
+    /// Positive steps make the element bigger
+    pub fn zoom(&mut self, element: HudElement, steps: f32) {
+        let placement = self.get(element);
+
+        let scale = (placement.scale * HUD_SCALE_STEP.powf(steps))
+            .clamp(*HUD_SCALE_RANGE.start(), *HUD_SCALE_RANGE.end());
+
+        self.set(element, HudPlacement { scale, ..placement });
+    }
+
+    pub fn reset(&mut self, element: HudElement) {
+        self.set(element, element.default_placement());
+    }
+
+    pub fn reset_all(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[test]
+fn test_hud_placement_clamp() {
+    let size = [0.2, 0.1];
+
+    // Left-top anchored element can't go past the right and bottom edges
+    let placement = HudPlacement { x: 0.45, y: -0.3, scale: 1.0 };
+    assert_eq!(placement.clamped(size), HudPlacement { x: 0.45, y: 0.0, scale: 1.0 });
+
+    let placement = HudPlacement { x: 0.5, y: 0.5, scale: 10.0 }.clamped([0.75, 0.75]);
+    assert_eq!((placement.x, placement.y, placement.scale), (0.25, 0.25, 3.0));
+
+    // Right-bottom anchored one is placed by its far corner
+    let placement = HudPlacement { x: 0.55, y: 1.4, scale: 1.0 }.clamped(size);
+    assert!(placement.anchored_right() && placement.anchored_bottom());
+    assert_eq!((placement.x, placement.y), (0.55, 1.0));
+
+    let placement = HudPlacement { x: 0.51, y: 0.6, scale: 1.0 }.clamped([0.75, 0.75]);
+    assert_eq!((placement.x, placement.y), (0.75, 0.75));
+}
+
+#[test]
+fn test_hud_layout_edit_and_reset() {
+    let mut layout = HudLayout::default();
+
+    layout.move_by(HudElement::Combo, [0.25, -0.5], [0.1, 0.1]);
+    assert_eq!(layout.get(HudElement::Combo).x, 0.25);
+    assert_eq!(layout.get(HudElement::Combo).y, 0.5);
+
+    layout.zoom(HudElement::Combo, 100.0);
+    assert_eq!(layout.get(HudElement::Combo).scale, 3.0);
+    layout.zoom(HudElement::Accuracy, -1.0);
+    assert!(layout.get(HudElement::Accuracy).scale < 1.0);
+
+    // Other elements are not touched by a single reset
+    layout.reset(HudElement::Combo);
+    assert_eq!(layout.get(HudElement::Combo), HudElement::Combo.default_placement());
+    assert_ne!(layout, HudLayout::default());
+
+    layout.reset_all();
+    assert_eq!(layout, HudLayout::default());
+}
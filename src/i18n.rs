@@ -0,0 +1,639 @@
+use std::{fmt::Display, sync::atomic::{AtomicU8, Ordering}};
+
+/// UI language, english is used for anything missing in other locales
+#[repr(u8)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    English = 0,
+    Russian = 1,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::English, Lang::Russian];
+
+    /// Code stored in the settings file
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lang::English => "en",
+            Lang::Russian => "ru",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.code() == code)
+    }
+
+    /// Name of the language in itself, used in the language picker
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lang::English => "English",
+            Lang::Russian => "Русский",
+        }
+    }
+
+    fn table(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Lang::English => EN,
+            Lang::Russian => RU,
+        }
+    }
+
+    fn decimal_separator(&self) -> char {
+        match self {
+            Lang::English => '.',
+            Lang::Russian => ',',
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        Self::ALL.get(value as usize).copied().unwrap_or_default()
+    }
+}
+
+static CURRENT_LANG: AtomicU8 = AtomicU8::new(Lang::English as u8);
+
+/// Language used by [`t`] and [`tf`]
+pub fn current() -> Lang {
+    Lang::from_u8(CURRENT_LANG.load(Ordering::Relaxed))
+}
+
+/// Switches UI language, takes effect on the next frame
+pub fn set_current(lang: Lang) {
+    CURRENT_LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+fn lookup(lang: Lang, key: &'static str) -> &'static str {
+    let find = |table: &'static [(&'static str, &'static str)]| {
+        table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    };
+
+    find(lang.table())
+        .or_else(|| find(EN))
+        .unwrap_or_else(|| {
+            tracing::warn!("Missing translation key: {key}");
+            key
+        })
+}
+
+/// Text of `key` in the current language
+pub fn t(key: &'static str) -> &'static str {
+    lookup(current(), key)
+}
+
+/// Text of `key` in the current language with every
+/// `{}` replaced by `args` in the same order
+pub fn tf(key: &'static str, args: &[&dyn Display]) -> String {
+    fill_template(t(key), args)
+}
+
+fn fill_template(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+
+    if let Some(first) = parts.next() {
+        out.push_str(first);
+    }
+
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+
+        out.push_str(part);
+    }
+
+    out
+}
+
+/// Number with a fixed amount of decimals and the locale decimal separator
+pub fn format_number(value: f64, precision: usize) -> String {
+    format_number_in(current(), value, precision)
+}
+
+fn format_number_in(lang: Lang, value: f64, precision: usize) -> String {
+    let formatted = format!("{value:.precision$}");
+
+    match lang.decimal_separator() {
+        '.' => formatted,
+        separator => formatted.replace('.', &separator.to_string()),
+    }
+}
+
+/// Percentage of `value` where 1.0 is 100%
+pub fn format_percent(value: f64, precision: usize) -> String {
+    format_percent_in(current(), value, precision)
+}
+
+fn format_percent_in(lang: Lang, value: f64, precision: usize) -> String {
+    let number = format_number_in(lang, value * 100.0, precision);
+
+    match lang {
+        Lang::English => format!("{number}%"),
+        // Percent sign is separated with a non-breaking space
+        Lang::Russian => format!("{number}\u{a0}%"),
+    }
+}
+
+const EN: &[(&str, &str)] = &[
+    ("common.cancel", "Cancel"),
+    ("common.delete", "Delete"),
+    ("common.close", "Close"),
+
+    ("song_select.mapped_by", "Mapped by {}"),
+    ("song_select.mapped_by.filter", "Show beatmaps of this mapper"),
+    ("song_select.source", "Source: {}"),
+    ("song_select.search", "Search"),
+    ("song_select.new", "new"),
+    ("song_select.unsupported_mode", "{}, not playable"),
+    ("song_select.length_info", "Length: {} BPM: {} Objects: {}"),
+    ("song_select.objects_count", "Circles: {} Sliders: {} Spinners: {}"),
+    ("song_select.preempt", "Preempt: {}ms"),
+    ("song_select.circle_diameter", "Circle: {}px"),
+    ("song_select.hit_windows", "300/100/50: ±{}/±{}/±{}ms"),
+    ("song_select.open_folder", "Open folder"),
+    ("song_select.delete_difficulty", "Delete difficulty"),
+    ("song_select.delete_mapset", "Delete mapset"),
+    ("song_select.delete_mapset_confirm", "Delete every difficulty of {} - {}?"),
+    ("song_select.moved_to_trash", "{} will be moved to the trash"),
+    ("song_select.beatmaps_amount", "Beatmaps: {}"),
+    ("song_select.collection.all", "All beatmaps"),
+    ("song_select.collection.entry", "{} ({}/{})"),
+    ("song_select.collection.add", "Add to collection"),
+    ("song_select.collection.new", "New collection…"),
+    ("song_select.collection.remove", "Remove from collection"),
+    ("song_select.collection.manage", "Collections"),
+    ("song_select.collection.name", "Collection name"),
+    ("song_select.collection.create", "Create"),
+    ("song_select.collection.rename", "Rename"),
+    ("song_select.collection.delete_confirm", "Delete {}?"),
+
+    ("results.accuracy", "Accuracy: {}"),
+    ("results.unranked", "Unranked ({})"),
+    ("results.x300", "300: {}"),
+    ("results.x100", "100: {}"),
+    ("results.x50", "50: {}"),
+    ("results.miss", "Miss: {}"),
+    ("results.max_combo", "Max combo: {}x"),
+    ("results.back", "Back"),
+    ("results.aim_error", "Aim error"),
+    ("results.mean_offset", "Mean offset: {}, {} osu!px"),
+    ("results.save_replay", "Save replay"),
+    ("results.replay_saved", "Replay saved to {}"),
+    ("results.replay_save_failed", "Failed to save replay: {}"),
+
+    ("pause.title", "Paused"),
+    ("pause.resume", "Resume"),
+    ("pause.retry", "Retry"),
+    ("pause.back", "Back to song select"),
+    ("break.title", "Break"),
+    ("break.remaining", "{}s left"),
+
+    ("drop.hint", "Drop to open"),
+    ("drop.unknown", "Can't open {}"),
+    ("drop.importing", "Importing {}"),
+    ("drop.imported", "Imported {} ({}/{})"),
+    ("drop.import_failed", "Failed to import {}: {}"),
+
+    ("replay.watching", "Watching replay ({}x)"),
+    ("replay.combo", "Combo: {}x"),
+    ("replay.open_failed", "Can't open replay {}"),
+    ("replay.beatmap_not_found", "Beatmap of {} is not in the database"),
+
+    ("hud.score", "Hit counts"),
+    ("hud.combo", "Combo"),
+    ("hud.accuracy", "Accuracy"),
+    ("hud.key_overlay", "Key overlay"),
+    ("slider_debug.bezier", "Bezier"),
+    ("slider_debug.linear", "Linear"),
+    ("slider_debug.perfect", "Perfect curve"),
+    ("slider_debug.catmull", "Catmull"),
+    ("slider_debug.red_anchor", "Red anchor"),
+    ("slider_debug.curve", "Calculated curve"),
+    ("slider_debug.reverse", "Reverse"),
+    ("slider_debug.tick", "Tick"),
+
+    ("notify.audio_missing", "Audio file {} is missing"),
+    ("notify.audio_decode_failed", "Can't play audio file {}"),
+    ("notify.audio_backend_fallback", "Audio backend {} is not available, using {}"),
+    ("notify.background_failed", "Can't load background {}"),
+    ("notify.beatmap_read_failed", "Can't read beatmap {}"),
+    ("notify.broken_beatmaps", "Skipped {} broken beatmaps"),
+    ("notify.scan_commit_failed", "Failed to save {} scanned beatmaps"),
+    ("notify.scan_interrupted", "Beatmap import of {} was interrupted, it can be resumed in the settings"),
+    ("notify.skin_ini_broken", "Can't read skin.ini, using default skin settings"),
+    ("notify.skin_element_broken", "Skin element {} is broken, using the default one"),
+    ("notify.skin_judgements_fallback", "Skin judgements can't be used, using the default ones"),
+
+    ("settings.title", "Settings"),
+    ("settings.search", "Search settings"),
+    ("settings.search.nothing", "Nothing found"),
+    ("settings.general", "General"),
+    ("settings.language", "Language"),
+
+    ("settings.audio", "Audio"),
+    ("settings.audio.output", "Output"),
+    ("settings.audio.device", "Output device: system default"),
+    ("settings.audio.device_hint", "The audio engine can't list output devices, change the default one in system settings"),
+    ("settings.audio.backend", "Backend: {}"),
+    ("settings.audio.buffer", "Buffer: {} samples @ {} Hz, {} channels"),
+    ("settings.audio.latency", "Output latency: ~{}ms"),
+    ("settings.audio.effects", "Muffle music while paused"),
+    ("settings.audio.master_volume", "Master volume"),
+    ("settings.audio.music_volume", "Music volume"),
+    ("settings.audio.pause_volume", "Music volume while paused"),
+    ("settings.audio.fail_volume", "Music volume after fail"),
+
+    ("settings.skin", "Skin"),
+    ("settings.skin.open", "Open skin"),
+    ("settings.skin.name", "Name: {}"),
+    ("settings.skin.author", "Author: {}"),
+    ("settings.skin.color_preset", "Colour preset"),
+    ("settings.skin.color_preset.off", "Skin colours"),
+    ("settings.skin.color_preset.deuteranopia", "Deuteranopia"),
+    ("settings.skin.color_preset.protanopia", "Protanopia"),
+    ("settings.skin.color_preset.tritanopia", "Tritanopia"),
+    ("settings.skin.ignore_beatmap_skin", "Ignore beatmap skins"),
+    ("settings.skin.colours", "Skin colours"),
+    ("settings.skin.combo_colours", "Combo colours"),
+    ("settings.skin.colour", "Colour {}: "),
+    ("settings.skin.slider_colours", "Slider colours"),
+    ("settings.skin.slider_border", "Slider border color:"),
+    ("settings.skin.slider_body", "Slider body color:"),
+
+    ("settings.hud", "HUD"),
+    ("settings.hud.edit", "Edit HUD layout"),
+    ("settings.hud.edit_hint", "Drag elements to move them, scroll to resize, right click to reset"),
+    ("settings.hud.reset", "Reset"),
+    ("settings.hud.reset_all", "Reset all"),
+    ("settings.replays", "Replays"),
+    ("settings.replays.always_save", "Always save replays"),
+    ("settings.replays.dir", "Folder: {}"),
+    ("settings.replays.choose", "Choose"),
+
+    ("settings.renderer", "Renderer"),
+    ("settings.renderer.slider", "Slider"),
+    ("settings.renderer.store_slider_textures", "Store slider textures"),
+    ("settings.renderer.bake_ahead_ms", "Bake sliders ahead, ms"),
+    ("settings.renderer.bake_ahead_per_frame", "Sliders baked ahead per frame"),
+    ("settings.renderer.adaptive_quality", "Adaptive quality"),
+    ("settings.renderer.adaptive_quality.off", "Off"),
+    ("settings.renderer.adaptive_quality.conservative", "Conservative"),
+    ("settings.renderer.adaptive_quality.aggressive", "Aggressive"),
+    ("settings.renderer.border_feather", "Slider border feather"),
+    ("settings.renderer.border_size", "Slider border size"),
+    ("settings.renderer.body_saturation", "Slider body color saturation"),
+    ("settings.renderer.body_alpha", "Slider body alpha multiplier"),
+    ("settings.renderer.judgements", "Judgements"),
+    ("settings.renderer.fade_in", "Fade-In milliseconds"),
+    ("settings.renderer.stay_on_screen", "Stay on screen milliseconds"),
+    ("settings.renderer.fade_out", "Fade-out milliseconds"),
+    ("settings.renderer.high_contrast", "High contrast judgements"),
+    ("settings.renderer.hit_position_nudge", "Move towards hit position"),
+
+    ("settings.gameplay", "Gameplay"),
+    ("settings.gameplay.unranked_note", "Scores made with these are unranked"),
+    ("settings.gameplay.no_fail", "No-Fail"),
+    ("settings.gameplay.relax", "Relax"),
+    ("settings.gameplay.difficulty", "Difficulty adjust"),
+    ("settings.gameplay.visuals", "Visuals"),
+    ("settings.gameplay.hidden", "Hidden"),
+    ("settings.gameplay.hard_rock", "Hard Rock"),
+    ("settings.gameplay.cs", "CS"),
+    ("settings.gameplay.ar", "AR"),
+    ("settings.gameplay.od", "OD"),
+    ("settings.gameplay.hp", "HP"),
+    ("settings.gameplay.playfield_rotation", "Playfield rotation"),
+    ("settings.gameplay.playfield_scale", "Playfield scale"),
+    ("settings.gameplay.playfield_vertical_offset", "Playfield vertical offset"),
+
+    ("settings.cursor", "Cursor"),
+    ("settings.cursor.size", "Cursor size"),
+    ("settings.cursor.ripples", "Ripples on key presses"),
+    ("settings.cursor.ripple_scale", "Ripple size"),
+
+    ("settings.file", "Settings file"),
+    ("settings.file.export", "Export settings"),
+    ("settings.file.import", "Import settings"),
+    ("settings.file.reset_confirm", "Reset all settings to defaults?"),
+    ("settings.file.reset", "Reset"),
+    ("settings.file.reset_to_defaults", "Reset to defaults"),
+    ("settings.file.import_errors", "Some settings were not imported:"),
+    ("settings.file.read_failed", "Failed to read {}: {}"),
+
+    ("settings.stable_import", "Import from osu!stable"),
+    ("settings.stable_import.pick", "Choose osu! folder..."),
+    ("settings.stable_import.found", "Found {} collections and {} scores in {}"),
+    ("settings.stable_import.confirm", "Import"),
+    ("settings.stable_import.done", "Imported {} collections and {} new scores"),
+    ("settings.stable_import.unresolved", "{} beatmaps are not imported yet, they will show up once they are"),
+    ("settings.stable_import.scan_songs", "Scan osu!stable Songs folder ({} missing beatmaps)"),
+    ("settings.stable_import.failed", "Failed to import: {}"),
+    ("settings.stable_import.interrupted", "Import of {} stopped after {} folders"),
+    ("settings.stable_import.resume", "Resume"),
+    ("settings.stable_import.restart", "Start over"),
+
+    ("settings.session", "Today"),
+    ("settings.session.plays", "Plays: {} started, {} completed"),
+    ("settings.session.retries", "Retries: {}"),
+    ("settings.session.play_time", "Play time: {}"),
+    ("settings.session.judgements", "300/100/50/Miss: {}/{}/{}/{}"),
+    ("settings.diagnostics", "Diagnostics"),
+    ("settings.diagnostics.copy", "Copy diagnostics"),
+    ("settings.diagnostics.force_panic", "Force panic"),
+];
+
+const RU: &[(&str, &str)] = &[
+    ("common.cancel", "Отмена"),
+    ("common.delete", "Удалить"),
+    ("common.close", "Закрыть"),
+
+    ("song_select.mapped_by", "Автор карты: {}"),
+    ("song_select.mapped_by.filter", "Показать карты этого автора"),
+    ("song_select.source", "Источник: {}"),
+    ("song_select.search", "Поиск"),
+    ("song_select.new", "новая"),
+    ("song_select.unsupported_mode", "{}, недоступна для игры"),
+    ("song_select.length_info", "Длина: {} BPM: {} Объектов: {}"),
+    ("song_select.objects_count", "Кругов: {} Слайдеров: {} Спиннеров: {}"),
+    ("song_select.preempt", "Появление: {}мс"),
+    ("song_select.circle_diameter", "Круг: {}px"),
+    ("song_select.hit_windows", "300/100/50: ±{}/±{}/±{}мс"),
+    ("song_select.open_folder", "Открыть папку"),
+    ("song_select.delete_difficulty", "Удалить сложность"),
+    ("song_select.delete_mapset", "Удалить мапсет"),
+    ("song_select.delete_mapset_confirm", "Удалить все сложности {} - {}?"),
+    ("song_select.moved_to_trash", "{} будет перемещена в корзину"),
+    ("song_select.beatmaps_amount", "Карт: {}"),
+    ("song_select.collection.all", "Все карты"),
+    ("song_select.collection.entry", "{} ({}/{})"),
+    ("song_select.collection.add", "Добавить в коллекцию"),
+    ("song_select.collection.new", "Новая коллекция…"),
+    ("song_select.collection.remove", "Убрать из коллекции"),
+    ("song_select.collection.manage", "Коллекции"),
+    ("song_select.collection.name", "Название коллекции"),
+    ("song_select.collection.create", "Создать"),
+    ("song_select.collection.rename", "Переименовать"),
+    ("song_select.collection.delete_confirm", "Удалить {}?"),
+
+    ("results.accuracy", "Точность: {}"),
+    ("results.unranked", "Без рейтинга ({})"),
+    ("results.x300", "300: {}"),
+    ("results.x100", "100: {}"),
+    ("results.x50", "50: {}"),
+    ("results.miss", "Промахи: {}"),
+    ("results.max_combo", "Макс. комбо: {}x"),
+    ("results.back", "Назад"),
+    ("results.aim_error", "Ошибка прицеливания"),
+    ("results.mean_offset", "Среднее смещение: {}, {} osu!px"),
+    ("results.save_replay", "Сохранить реплей"),
+    ("results.replay_saved", "Реплей сохранён в {}"),
+    ("results.replay_save_failed", "Не удалось сохранить реплей: {}"),
+
+    ("pause.title", "Пауза"),
+    ("pause.resume", "Продолжить"),
+    ("pause.retry", "Заново"),
+    ("pause.back", "К выбору карты"),
+    ("break.title", "Перерыв"),
+    ("break.remaining", "Осталось {} с"),
+
+    ("drop.hint", "Отпустите, чтобы открыть"),
+    ("drop.unknown", "Не удалось открыть {}"),
+    ("drop.importing", "Импорт {}"),
+    ("drop.imported", "Импортировано {} ({}/{})"),
+    ("drop.import_failed", "Не удалось импортировать {}: {}"),
+
+    ("replay.watching", "Просмотр реплея ({}x)"),
+    ("replay.combo", "Комбо: {}x"),
+    ("replay.open_failed", "Не удалось открыть реплей {}"),
+    ("replay.beatmap_not_found", "Карты реплея {} нет в базе данных"),
+
+    ("hud.score", "Количество попаданий"),
+    ("hud.combo", "Комбо"),
+    ("hud.accuracy", "Точность"),
+    ("hud.key_overlay", "Нажатия клавиш"),
+    ("slider_debug.bezier", "Безье"),
+    ("slider_debug.linear", "Прямая"),
+    ("slider_debug.perfect", "Дуга окружности"),
+    ("slider_debug.catmull", "Катмулл-Ром"),
+    ("slider_debug.red_anchor", "Красная точка"),
+    ("slider_debug.curve", "Рассчитанная кривая"),
+    ("slider_debug.reverse", "Разворот"),
+    ("slider_debug.tick", "Тик"),
+
+    ("notify.audio_missing", "Аудиофайл {} не найден"),
+    ("notify.audio_decode_failed", "Не удалось воспроизвести аудиофайл {}"),
+    ("notify.audio_backend_fallback", "Аудиобэкенд {} недоступен, используется {}"),
+    ("notify.background_failed", "Не удалось загрузить фон {}"),
+    ("notify.beatmap_read_failed", "Не удалось прочитать карту {}"),
+    ("notify.broken_beatmaps", "Пропущено повреждённых карт: {}"),
+    ("notify.scan_commit_failed", "Не удалось сохранить найденные карты: {}"),
+    ("notify.scan_interrupted", "Импорт карт из {} был прерван, его можно продолжить в настройках"),
+    ("notify.skin_ini_broken", "Не удалось прочитать skin.ini, используются настройки по умолчанию"),
+    ("notify.skin_element_broken", "Элемент скина {} повреждён, используется стандартный"),
+    ("notify.skin_judgements_fallback", "Оценки скина нельзя использовать, используются стандартные"),
+
+    ("settings.title", "Настройки"),
+    ("settings.search", "Поиск настроек"),
+    ("settings.search.nothing", "Ничего не найдено"),
+    ("settings.general", "Общие"),
+    ("settings.language", "Язык"),
+
+    ("settings.audio", "Звук"),
+    ("settings.audio.output", "Вывод"),
+    ("settings.audio.device", "Устройство вывода: системное по умолчанию"),
+    ("settings.audio.device_hint", "Звуковой движок не умеет перечислять устройства вывода, смените устройство по умолчанию в настройках системы"),
+    ("settings.audio.backend", "Бэкенд: {}"),
+    ("settings.audio.buffer", "Буфер: {} сэмплов @ {} Гц, {} каналов"),
+    ("settings.audio.latency", "Задержка вывода: ~{} мс"),
+    ("settings.audio.effects", "Приглушать музыку на паузе"),
+    ("settings.audio.master_volume", "Общая громкость"),
+    ("settings.audio.music_volume", "Громкость музыки"),
+    ("settings.audio.pause_volume", "Громкость музыки на паузе"),
+    ("settings.audio.fail_volume", "Громкость музыки после провала"),
+
+    ("settings.skin", "Скин"),
+    ("settings.skin.open", "Открыть скин"),
+    ("settings.skin.name", "Название: {}"),
+    ("settings.skin.author", "Автор: {}"),
+    ("settings.skin.color_preset", "Цветовой пресет"),
+    ("settings.skin.color_preset.off", "Цвета скина"),
+    ("settings.skin.color_preset.deuteranopia", "Дейтеранопия"),
+    ("settings.skin.color_preset.protanopia", "Протанопия"),
+    ("settings.skin.color_preset.tritanopia", "Тританопия"),
+    ("settings.skin.ignore_beatmap_skin", "Игнорировать скины карт"),
+    ("settings.skin.colours", "Цвета скина"),
+    ("settings.skin.combo_colours", "Цвета комбо"),
+    ("settings.skin.colour", "Цвет {}: "),
+    ("settings.skin.slider_colours", "Цвета слайдеров"),
+    ("settings.skin.slider_border", "Цвет границы слайдера:"),
+    ("settings.skin.slider_body", "Цвет тела слайдера:"),
+
+    ("settings.hud", "Интерфейс игры"),
+    ("settings.hud.edit", "Редактировать расположение"),
+    ("settings.hud.edit_hint", "Перетаскивайте элементы, колесо мыши меняет размер, правый клик сбрасывает"),
+    ("settings.hud.reset", "Сбросить"),
+    ("settings.hud.reset_all", "Сбросить всё"),
+    ("settings.replays", "Реплеи"),
+    ("settings.replays.always_save", "Всегда сохранять реплеи"),
+    ("settings.replays.dir", "Папка: {}"),
+    ("settings.replays.choose", "Выбрать"),
+
+    ("settings.renderer", "Отрисовка"),
+    ("settings.renderer.slider", "Слайдеры"),
+    ("settings.renderer.store_slider_textures", "Хранить текстуры слайдеров"),
+    ("settings.renderer.bake_ahead_ms", "Заранее готовить слайдеры, мс"),
+    ("settings.renderer.bake_ahead_per_frame", "Слайдеров заранее за кадр"),
+    ("settings.renderer.adaptive_quality", "Адаптивное качество"),
+    ("settings.renderer.adaptive_quality.off", "Выключено"),
+    ("settings.renderer.adaptive_quality.conservative", "Осторожное"),
+    ("settings.renderer.adaptive_quality.aggressive", "Агрессивное"),
+    ("settings.renderer.border_feather", "Размытие границы слайдера"),
+    ("settings.renderer.border_size", "Толщина границы слайдера"),
+    ("settings.renderer.body_saturation", "Насыщенность тела слайдера"),
+    ("settings.renderer.body_alpha", "Прозрачность тела слайдера"),
+    ("settings.renderer.judgements", "Оценки попаданий"),
+    ("settings.renderer.fade_in", "Появление, мс"),
+    ("settings.renderer.stay_on_screen", "Время на экране, мс"),
+    ("settings.renderer.fade_out", "Исчезновение, мс"),
+    ("settings.renderer.high_contrast", "Контрастные оценки"),
+    ("settings.renderer.hit_position_nudge", "Смещение к месту попадания"),
+
+    ("settings.gameplay", "Геймплей"),
+    ("settings.gameplay.unranked_note", "Результаты с этими опциями не идут в рейтинг"),
+    ("settings.gameplay.no_fail", "No-Fail"),
+    ("settings.gameplay.relax", "Relax"),
+    ("settings.gameplay.difficulty", "Настройка сложности"),
+    ("settings.gameplay.visuals", "Визуал"),
+    ("settings.gameplay.hidden", "Hidden"),
+    ("settings.gameplay.hard_rock", "Hard Rock"),
+    ("settings.gameplay.cs", "CS"),
+    ("settings.gameplay.ar", "AR"),
+    ("settings.gameplay.od", "OD"),
+    ("settings.gameplay.hp", "HP"),
+    ("settings.gameplay.playfield_rotation", "Поворот игрового поля"),
+    ("settings.gameplay.playfield_scale", "Масштаб игрового поля"),
+    ("settings.gameplay.playfield_vertical_offset", "Сдвиг игрового поля по вертикали"),
+
+    ("settings.cursor", "Курсор"),
+    ("settings.cursor.size", "Размер курсора"),
+    ("settings.cursor.ripples", "Круги при нажатиях"),
+    ("settings.cursor.ripple_scale", "Размер кругов"),
+
+    ("settings.file", "Файл настроек"),
+    ("settings.file.export", "Экспорт настроек"),
+    ("settings.file.import", "Импорт настроек"),
+    ("settings.file.reset_confirm", "Сбросить все настройки?"),
+    ("settings.file.reset", "Сбросить"),
+    ("settings.file.reset_to_defaults", "Сбросить по умолчанию"),
+    ("settings.file.import_errors", "Некоторые настройки не были импортированы:"),
+    ("settings.file.read_failed", "Не удалось прочитать {}: {}"),
+
+    ("settings.stable_import", "Импорт из osu!stable"),
+    ("settings.stable_import.pick", "Выбрать папку osu!..."),
+    ("settings.stable_import.found", "Найдено коллекций: {}, рекордов: {} в {}"),
+    ("settings.stable_import.confirm", "Импортировать"),
+    ("settings.stable_import.done", "Импортировано коллекций: {}, новых рекордов: {}"),
+    ("settings.stable_import.unresolved", "Карт ещё не импортировано: {}, они появятся после импорта"),
+    ("settings.stable_import.scan_songs", "Просканировать папку Songs osu!stable (не хватает карт: {})"),
+    ("settings.stable_import.failed", "Не удалось импортировать: {}"),
+    ("settings.stable_import.interrupted", "Импорт {} остановлен, обработано папок: {}"),
+    ("settings.stable_import.resume", "Продолжить"),
+    ("settings.stable_import.restart", "Начать заново"),
+
+    ("settings.session", "Сегодня"),
+    ("settings.session.plays", "Игр: {} начато, {} завершено"),
+    ("settings.session.retries", "Перезапусков: {}"),
+    ("settings.session.play_time", "Время игры: {}"),
+    ("settings.session.judgements", "300/100/50/Промахи: {}/{}/{}/{}"),
+    ("settings.diagnostics", "Диагностика"),
+    ("settings.diagnostics.copy", "Скопировать диагностику"),
+    ("settings.diagnostics.force_panic", "Вызвать панику"),
+];
+
+#[test]
+fn test_every_locale_has_every_key() {
+    for lang in Lang::ALL {
+        for (key, _) in EN {
+            assert!(
+                lang.table().iter().any(|(k, _)| k == key),
+                "{key} is missing in {lang:?}"
+            );
+        }
+
+        assert_eq!(lang.table().len(), EN.len(), "{lang:?} has unknown keys");
+    }
+}
+
+#[test]
+fn test_referenced_keys_exist() {
+    // Screens with localized strings
+    let sources = [
+        include_str!("screen/song_select/mod.rs"),
+        include_str!("screen/settings.rs"),
+        include_str!("screen/results.rs"),
+        include_str!("screen/pause.rs"),
+        include_str!("screen/break_overlay.rs"),
+        include_str!("screen/drop_overlay.rs"),
+        include_str!("screen/slider_debug.rs"),
+        include_str!("osu_state.rs"),
+        include_str!("song_select_state.rs"),
+        include_str!("skin_manager.rs"),
+        include_str!("osu_db.rs"),
+    ];
+
+    let mut referenced = 0;
+
+    for source in sources {
+        for call in ["t(\"", "tf(\""] {
+            for (index, _) in source.match_indices(call) {
+                // Skipping calls like `set("` or `get("`
+                let prev = source[..index].chars().next_back();
+                if prev.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    continue;
+                }
+
+                let rest = &source[index + call.len()..];
+                let key = &rest[..rest.find('"').unwrap()];
+
+                for lang in Lang::ALL {
+                    assert!(
+                        lang.table().iter().any(|(k, _)| *k == key),
+                        "{key} is referenced but missing in {lang:?}"
+                    );
+                }
+
+                referenced += 1;
+            }
+        }
+    }
+
+    assert!(referenced > 0);
+}
+
+#[test]
+fn test_color_preset_names_exist() {
+    // Keys are picked at runtime, so the source scan can't see them
+    for preset in crate::color_preset::ColorPreset::ALL {
+        for lang in Lang::ALL {
+            assert!(
+                lang.table().iter().any(|(k, _)| *k == preset.name_key()),
+                "{} is missing in {lang:?}", preset.name_key()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_locale_formatting() {
+    assert_eq!(format_number_in(Lang::English, 98.536, 2), "98.54");
+    assert_eq!(format_number_in(Lang::Russian, 98.536, 2), "98,54");
+
+    assert_eq!(format_percent_in(Lang::English, 0.9853, 2), "98.53%");
+    assert_eq!(format_percent_in(Lang::Russian, 0.9853, 2), "98,53\u{a0}%");
+
+    assert_eq!(fill_template("Length: {} BPM: {}", &[&"01:30", &180]), "Length: 01:30 BPM: 180");
+    assert_eq!(Lang::from_code("ru"), Some(Lang::Russian));
+    assert_eq!(Lang::from_code("xx"), None);
+}
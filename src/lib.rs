@@ -1,53 +1,80 @@
-
-
-
+// Gameplay core, always built. Everything touching the GPU, the window
+// or the sound card is behind the `render` and `audio` features
+pub mod hit_objects;
+pub mod processor;
+pub mod math;
+pub mod timer;
+pub mod rgb;
+pub mod osu_input;
+pub mod difficulty;
+pub mod score;
+pub mod particles;
+pub mod rng;
+pub mod random_history;
+pub mod notifier;
+pub mod sound_scheduler;
 
 cfg_if::cfg_if! {
     if #[cfg(target_arch = "wasm32")] {
-        pub mod graphics;
-        pub mod osu_renderer;
-        pub mod hit_objects;
-        pub mod texture;
-        pub mod math;
-        pub mod camera;
-        pub mod rgb;
-        pub mod quad_renderer;
-        pub mod quad_instance;
-        pub mod skin_manager;
-        pub mod vertex;
+        #[cfg(feature = "render")] pub mod graphics;
+        #[cfg(feature = "render")] pub mod osu_renderer;
+        #[cfg(feature = "render")] pub mod gameplay_renderer;
+        #[cfg(feature = "render")] pub mod render_stats;
+        #[cfg(feature = "render")] pub mod texture;
+        #[cfg(feature = "render")] pub mod texture_upload;
+        #[cfg(feature = "render")] pub mod camera;
+        pub mod color_preset;
+        #[cfg(feature = "render")] pub mod quad_renderer;
+        #[cfg(feature = "render")] pub mod quad_instance;
+        #[cfg(feature = "render")] pub mod skin_manager;
+        #[cfg(feature = "render")] pub mod vertex;
         pub mod config;
-        pub mod hit_circle_instance;
-        pub mod slider_instance;
-        pub mod timer;
+        pub mod settings_table;
+        pub mod adaptive_quality;
+        pub mod hud_layout;
+        #[cfg(feature = "render")] pub mod hit_circle_instance;
+        #[cfg(feature = "render")] pub mod slider_instance;
         pub mod skin_ini;
-        pub mod processor;
-
-        pub mod osu_input;
+        pub mod i18n;
     } else {
-        pub mod graphics;
-        #[macro_use] pub mod osu_renderer;
-        pub mod hit_objects;
-        pub mod texture;
-        pub mod math;
-        pub mod camera;
-        pub mod rgb;
-        pub mod quad_renderer;
-        pub mod quad_instance;
-        pub mod skin_manager;
-        pub mod vertex;
+        #[cfg(feature = "render")] pub mod graphics;
+        #[cfg(feature = "render")] #[macro_use] pub mod osu_renderer;
+        #[cfg(feature = "render")] pub mod gameplay_renderer;
+        #[cfg(feature = "render")] pub mod render_stats;
+        #[cfg(feature = "render")] pub mod texture;
+        #[cfg(feature = "render")] pub mod texture_upload;
+        #[cfg(feature = "render")] pub mod camera;
+        pub mod color_preset;
+        #[cfg(feature = "render")] pub mod quad_renderer;
+        #[cfg(feature = "render")] pub mod quad_instance;
+        #[cfg(feature = "render")] pub mod skin_manager;
+        #[cfg(feature = "render")] pub mod vertex;
         pub mod config;
-        pub mod hit_circle_instance;
-        pub mod slider_instance;
-        pub mod timer;
+        pub mod settings_table;
+        pub mod adaptive_quality;
+        pub mod hud_layout;
+        #[cfg(feature = "render")] pub mod hit_circle_instance;
+        #[cfg(feature = "render")] pub mod slider_instance;
         pub mod skin_ini;
-        pub mod processor;
-        pub mod egui_state;
-        mod song_select_state;
-        pub mod renderer;
-        pub mod osu_input;
-        mod screen;
+        pub mod i18n;
+        #[cfg(feature = "render")] pub mod accuracy_graph;
+        #[cfg(feature = "render")] pub mod aim_scatter;
+        #[cfg(feature = "render")] pub mod egui_state;
+        #[cfg(feature = "audio")] pub mod audio;
+        #[cfg(feature = "audio")] pub mod audio_effects;
+        pub mod beatmap_cache;
+        #[cfg(feature = "render")] pub mod diagnostics;
+        #[cfg(feature = "alloc-counter")]
+        pub mod alloc_counter;
+        #[cfg(all(feature = "render", feature = "audio"))] mod song_select_state;
+        #[cfg(feature = "render")] pub mod renderer;
+        #[cfg(all(feature = "render", feature = "audio"))] mod screen;
         pub mod osu_db;
-        pub mod osu_state;
+        pub mod db_worker;
+        pub mod search_query;
+        pub mod session_stats;
+        pub mod dropped_file;
+        #[cfg(all(feature = "render", feature = "audio"))] pub mod osu_state;
+        #[cfg(feature = "render")] mod frame_history;
     }
 }
-
@@ -1,4 +1,6 @@
-use cgmath::Vector2;
+use cgmath::{InnerSpace, Matrix3, SquareMatrix, Vector2, Vector3};
+
+use crate::hit_objects::{CIRCLE_FADEOUT_TIME, SLIDER_FADEOUT_TIME};
 
 pub const OSU_COORDS_WIDTH: f32 = 512.0;
 pub const OSU_COORDS_HEIGHT: f32 = 384.0;
@@ -60,6 +62,250 @@ pub fn calc_playfield(screen_w: f32, screen_h: f32) -> (f32, Vector2<f32>) {
     (scale, offsets)
 }
 
+/// Player adjustments on top of the default playfield layout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayfieldLayout {
+    /// Multiplier of the default playfield size
+    pub scale: f32,
+    /// Pixels the playfield is moved down by, negative moves it up
+    pub vertical_offset: f32,
+}
+
+impl Default for PlayfieldLayout {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            vertical_offset: 0.0,
+        }
+    }
+}
+
+/// [`calc_playfield`] scaled around its center and moved vertically
+/// by the `layout`. Playfield never gets out of the window, the offset
+/// is clamped to the space that is left above and below it
+pub fn calc_adjusted_playfield(screen_w: f32, screen_h: f32, layout: &PlayfieldLayout) -> (f32, Vector2<f32>) {
+    let (scale, offsets) = calc_playfield(screen_w, screen_h);
+
+    let center = offsets + Vector2::new(OSU_COORDS_WIDTH, OSU_COORDS_HEIGHT) * scale / 2.0;
+    // Only shrinking, default playfield already takes all the space
+    let scale = scale * layout.scale.min(1.0);
+
+    let size = Vector2::new(OSU_COORDS_WIDTH, OSU_COORDS_HEIGHT) * scale;
+    let top = (center.y - size.y / 2.0 + layout.vertical_offset)
+        .clamp(0.0, (screen_h - size.y).max(0.0));
+
+    (scale, Vector2::new(center.x - size.x / 2.0, top))
+}
+
+/// Playfield-space transform around the playfield center, identity
+/// unless the playfield is flipped (Hard Rock) or rotated.
+/// `rotation` is in degrees, clockwise on the screen
+pub fn playfield_matrix(flip_vertical: bool, rotation: f64) -> Matrix3<f64> {
+    let center = Vector2::new(OSU_COORDS_WIDTH as f64 / 2.0, OSU_COORDS_HEIGHT as f64 / 2.0);
+
+    let flip_y = if flip_vertical { -1.0 } else { 1.0 };
+    let flip = Matrix3::new(
+        1.0, 0.0, 0.0,
+        0.0, flip_y, 0.0,
+        0.0, 0.0, 1.0,
+    );
+
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let rotate = Matrix3::new(
+        cos, sin, 0.0,
+        -sin, cos, 0.0,
+        0.0, 0.0, 1.0,
+    );
+
+    translation(center) * rotate * flip * translation(-center)
+}
+
+fn translation(offset: Vector2<f64>) -> Matrix3<f64> {
+    Matrix3::new(
+        1.0, 0.0, 0.0,
+        0.0, 1.0, 0.0,
+        offset.x, offset.y, 1.0,
+    )
+}
+
+/// Applies homogeneous 2D `matrix` to a point
+#[inline]
+pub fn transform_point(matrix: &Matrix3<f64>, pos: Vector2<f64>) -> Vector2<f64> {
+    let out = *matrix * Vector3::new(pos.x, pos.y, 1.0);
+    Vector2::new(out.x, out.y)
+}
+
+/// Conversion between screen space and osu! playfield coordinates.
+///
+/// Both input and cursor rendering should go through the same
+/// transform so the cursor is always where hits are registered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayfieldTransform {
+    pub scale: f32,
+    pub offsets: Vector2<f32>,
+    /// Same playfield matrix the renderer draws objects with
+    matrix: Matrix3<f64>,
+    inverse: Matrix3<f64>,
+}
+
+impl PlayfieldTransform {
+    pub fn new(screen_w: f32, screen_h: f32) -> Self {
+        Self::with_layout(screen_w, screen_h, &PlayfieldLayout::default())
+    }
+
+    /// Same as [`PlayfieldTransform::new`] with a
+    /// scaled or moved playfield, see [`calc_adjusted_playfield`]
+    pub fn with_layout(screen_w: f32, screen_h: f32, layout: &PlayfieldLayout) -> Self {
+        let (scale, offsets) = calc_adjusted_playfield(screen_w, screen_h, layout);
+
+        Self {
+            scale,
+            offsets,
+            matrix: Matrix3::identity(),
+            inverse: Matrix3::identity(),
+        }
+    }
+
+    /// Flips and rotations can always be inverted, anything
+    /// else falls back to identity
+    pub fn with_matrix(mut self, matrix: Matrix3<f64>) -> Self {
+        match matrix.invert() {
+            Some(inverse) => {
+                self.matrix = matrix;
+                self.inverse = inverse;
+            },
+            None => {
+                self.matrix = Matrix3::identity();
+                self.inverse = Matrix3::identity();
+            },
+        }
+
+        self
+    }
+
+    #[inline]
+    pub fn matrix(&self) -> Matrix3<f64> {
+        self.matrix
+    }
+
+    pub fn to_playfield(&self, screen_pos: Vector2<f64>) -> Vector2<f64> {
+        let pos = Vector2::new(
+            (screen_pos.x - self.offsets.x as f64) / self.scale as f64,
+            (screen_pos.y - self.offsets.y as f64) / self.scale as f64,
+        );
+
+        transform_point(&self.inverse, pos)
+    }
+
+    pub fn to_screen(&self, playfield_pos: Vector2<f64>) -> Vector2<f64> {
+        let pos = transform_point(&self.matrix, playfield_pos);
+
+        Vector2::new(
+            pos.x * self.scale as f64 + self.offsets.x as f64,
+            pos.y * self.scale as f64 + self.offsets.y as f64,
+        )
+    }
+
+    /// Keeps position inside of the playfield bounds
+    pub fn clamp(playfield_pos: Vector2<f64>) -> Vector2<f64> {
+        Vector2::new(
+            playfield_pos.x.clamp(0.0, OSU_COORDS_WIDTH as f64),
+            playfield_pos.y.clamp(0.0, OSU_COORDS_HEIGHT as f64),
+        )
+    }
+}
+
+#[test]
+pub fn test_playfield_transform() {
+    for (w, h) in [(1920.0, 1080.0), (800.0, 600.0), (3440.0, 600.0), (600.0, 2000.0)] {
+        let transform = PlayfieldTransform::new(w, h);
+
+        let pos = Vector2::new(256.0, 192.0);
+        let back = transform.to_playfield(transform.to_screen(pos));
+
+        assert!((back.x - pos.x).abs() < 1e-6 && (back.y - pos.y).abs() < 1e-6);
+
+        // Playfield center is always horizontally centered
+        assert!((transform.to_screen(pos).x - w as f64 / 2.0).abs() < 1e-3);
+    }
+
+    let clamped = PlayfieldTransform::clamp(Vector2::new(-10.0, 1000.0));
+    assert_eq!(clamped, Vector2::new(0.0, OSU_COORDS_HEIGHT as f64));
+}
+
+#[test]
+fn test_playfield_layout() {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-3;
+
+    for (w, h) in [(1920.0, 1080.0), (800.0, 600.0), (3440.0, 600.0), (600.0, 2000.0)] {
+        let default = PlayfieldTransform::new(w, h);
+        let center = Vector2::new(256.0, 192.0);
+
+        for (scale, vertical_offset) in [(0.8, 0.0), (1.0, -50.0), (0.9, 30.0), (0.85, -60.0)] {
+            let layout = PlayfieldLayout { scale, vertical_offset };
+            let transform = PlayfieldTransform::with_layout(w, h, &layout);
+
+            for pos in [center, Vector2::new(0.0, 0.0), Vector2::new(512.0, 384.0), Vector2::new(10.0, 300.0)] {
+                let back = transform.to_playfield(transform.to_screen(pos));
+                assert!(close(back.x, pos.x) && close(back.y, pos.y));
+            }
+
+            assert!(close(transform.scale as f64, (default.scale * scale) as f64));
+
+            // Scaled around the center, only moved vertically
+            let screen_center = transform.to_screen(center);
+            assert!(close(screen_center.x, w as f64 / 2.0));
+            assert!(close(screen_center.y, default.to_screen(center).y + vertical_offset as f64));
+        }
+
+        // Always inside of the window
+        for vertical_offset in [-10000.0, 10000.0] {
+            let layout = PlayfieldLayout { scale: 1.0, vertical_offset };
+            let transform = PlayfieldTransform::with_layout(w, h, &layout);
+
+            let top = transform.to_screen(Vector2::new(0.0, 0.0));
+            let bottom = transform.to_screen(Vector2::new(512.0, 384.0));
+
+            assert!(top.y >= -1e-3 && bottom.y <= h as f64 + 1e-3);
+            assert!(top.x >= -1e-3 && bottom.x <= w as f64 + 1e-3);
+        }
+
+        // Can't grow out of the window either
+        let layout = PlayfieldLayout { scale: 2.0, vertical_offset: 0.0 };
+        assert_eq!(PlayfieldTransform::with_layout(w, h, &layout).scale, default.scale);
+    }
+}
+
+#[test]
+fn test_playfield_matrix() {
+    let close = |a: Vector2<f64>, b: Vector2<f64>| (a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6;
+
+    // Center always stays in place
+    let center = Vector2::new(256.0, 192.0);
+    assert!(close(transform_point(&playfield_matrix(true, 37.0), center), center));
+
+    let flip = playfield_matrix(true, 0.0);
+    assert!(close(transform_point(&flip, Vector2::new(100.0, 0.0)), Vector2::new(100.0, 384.0)));
+    assert!(close(transform_point(&flip, Vector2::new(100.0, 300.0)), Vector2::new(100.0, 84.0)));
+
+    // Clockwise on the screen, y goes down
+    let rotate = playfield_matrix(false, 90.0);
+    assert!(close(transform_point(&rotate, Vector2::new(356.0, 192.0)), Vector2::new(256.0, 292.0)));
+
+    for (flip, rotation) in [(true, 0.0), (false, 90.0), (true, 213.0)] {
+        let transform = PlayfieldTransform::new(1920.0, 1080.0)
+            .with_matrix(playfield_matrix(flip, rotation));
+
+        let pos = Vector2::new(10.0, 300.0);
+        assert!(close(transform.to_playfield(transform.to_screen(pos)), pos));
+    }
+
+    // Flipped top of the playfield is drawn at the bottom
+    let plain = PlayfieldTransform::new(1920.0, 1080.0);
+    let flipped = plain.with_matrix(playfield_matrix(true, 0.0));
+    assert!(close(flipped.to_screen(Vector2::new(0.0, 0.0)), plain.to_screen(Vector2::new(0.0, 384.0))));
+}
+
 pub fn calc_direction_degree(p1: Vector2<f32>, p2: Vector2<f32>) -> f32 {
     let angle_rad = (p2.y - p1.y).atan2(p2.x - p1.x);
     let mut angle_deg = angle_rad.to_degrees();
@@ -75,6 +321,24 @@ pub fn calc_opposite_direction_degree(p1: Vector2<f32>, p2: Vector2<f32>) -> f32
     (calc_direction_degree(p1, p2) + 180.0) % 360.0
 }
 
+/// Angle at `cur` in degrees between the vectors to `prev` and `next`,
+/// 180 on a straight line and 0 on a full back and forth jump.
+/// `None` if `cur` overlaps one of its neighbours
+pub fn angle_between(prev: Vector2<f32>, cur: Vector2<f32>, next: Vector2<f32>) -> Option<f32> {
+    let incoming = prev - cur;
+    let outgoing = next - cur;
+
+    let lengths = incoming.magnitude() * outgoing.magnitude();
+    if lengths <= f32::EPSILON {
+        return None;
+    }
+
+    // Rounding may push the cosine a bit out of the acos domain
+    let cos = (incoming.dot(outgoing) / lengths).clamp(-1.0, 1.0);
+
+    Some(cos.acos().to_degrees())
+}
+
 /// Return preempt and fadein based on AR
 pub fn calculate_preempt_fadein(ar: f32) -> (f32, f32) {
     if ar > 5.0 {
@@ -97,6 +361,13 @@ pub fn calc_progress(current: f64, start: f64, end: f64) -> f64 {
     (current - start) / (end - start)
 }
 
+/// Guard for positions written into instance buffers, a single
+/// NaN there makes everything in the same draw call disappear
+#[inline]
+pub fn debug_assert_finite(x: f32, y: f32) {
+    debug_assert!(x.is_finite() && y.is_finite(), "non-finite position in instance data: ({x}, {y})");
+}
+
 #[inline]
 pub fn calc_fade_alpha(
     time: f64, 
@@ -109,15 +380,252 @@ pub fn calc_fade_alpha(
     let fade_out_start = fade_in_end + stay_ms as f64;
     let fade_out_end = fade_out_start + fade_out_ms as f64;
 
+    // Zero length fades are instant, progress over them is NaN
     if time <= fade_in_end {
+        if fade_in_ms <= 0.0 {
+            return if time < start { 0.0 } else { 1.0 };
+        }
+
         calc_progress(time, start, fade_in_end)
     } else if time >= fade_out_start {
+        if fade_out_ms <= 0.0 {
+            return if time < fade_out_end { 1.0 } else { 0.0 };
+        }
+
         1.0 - calc_progress(time, fade_out_start, fade_out_end)
     } else {
         1.0
     }
 }
 
+#[test]
+fn test_fade_alpha() {
+    assert_eq!(calc_fade_alpha(150.0, 100.0, 100.0, 100.0, 100.0), 0.5);
+    assert_eq!(calc_fade_alpha(250.0, 100.0, 100.0, 100.0, 100.0), 1.0);
+    assert_eq!(calc_fade_alpha(350.0, 100.0, 100.0, 100.0, 100.0), 0.5);
+
+    // Without fades it just pops in and out
+    assert_eq!(calc_fade_alpha(99.0, 100.0, 0.0, 300.0, 0.0), 0.0);
+    assert_eq!(calc_fade_alpha(100.0, 100.0, 0.0, 300.0, 0.0), 1.0);
+    assert_eq!(calc_fade_alpha(399.0, 100.0, 0.0, 300.0, 0.0), 1.0);
+    assert_eq!(calc_fade_alpha(400.0, 100.0, 0.0, 300.0, 0.0), 0.0);
+}
+
+/// Highest alpha of the slider body, it's never fully opaque
+pub const SLIDER_BODY_MAX_ALPHA: f64 = 0.95;
+
+/// Slider body alpha envelope. Fades in over `fadein` ms starting at
+/// `start_time - preempt`, holds at [`SLIDER_BODY_MAX_ALPHA`] until
+/// `end_time` and then fades out over [`SLIDER_FADEOUT_TIME`].
+///
+/// Both fades are applied at once so a short slider can't go
+/// above its fade-in while fading out
+pub fn calc_slider_body_alpha(
+    time: f64,
+    start_time: f64,
+    end_time: f64,
+    preempt: f32,
+    fadein: f32,
+) -> f64 {
+    let appear = start_time - preempt as f64;
+    let fade_in = calc_progress(time, appear, appear + fadein as f64).clamp(0.0, 1.0);
+    let fade_out = 1.0 - calc_progress(time, end_time, end_time + SLIDER_FADEOUT_TIME).clamp(0.0, 1.0);
+
+    fade_in.min(fade_out) * SLIDER_BODY_MAX_ALPHA
+}
+
+/// Approach circle alpha of the slider, follows body fade-in but
+/// reaches full alpha and disappears once slider is started
+pub fn calc_slider_approach_alpha(time: f64, start_time: f64, preempt: f32, fadein: f32) -> f64 {
+    if time >= start_time {
+        return 0.0;
+    }
+
+    let body_alpha = calc_slider_body_alpha(time, start_time, start_time, preempt, fadein);
+
+    (body_alpha / SLIDER_BODY_MAX_ALPHA).clamp(0.0, 1.0)
+}
+
+/// Approach circle scale at the moment it appears
+pub const APPROACH_CIRCLE_MAX_SCALE: f64 = 4.0;
+
+/// Approach circle `(alpha, scale)` of a hit circle, `None` once it's gone.
+///
+/// Shrinks down to 1x at `start_time` and holds there through the late
+/// hit window. When hit, it freezes at the scale it had at `hit_at`
+/// and fades out over [`CIRCLE_FADEOUT_TIME`] together with the circle
+pub fn calc_circle_approach(
+    time: f64,
+    start_time: f64,
+    preempt: f32,
+    fadein: f32,
+    hit_at: Option<f64>,
+    late_window: f64,
+) -> Option<(f64, f64)> {
+    let appear = start_time - preempt as f64;
+    let fade_in = calc_progress(time, appear, appear + fadein as f64).clamp(0.0, 1.0);
+
+    let scale_at = |time: f64| {
+        let progress = calc_progress(time, appear, start_time);
+        lerp(1.0, APPROACH_CIRCLE_MAX_SCALE, 1.0 - progress).clamp(1.0, APPROACH_CIRCLE_MAX_SCALE)
+    };
+
+    let (alpha, scale) = match hit_at {
+        Some(hit_at) => {
+            let fade_out = 1.0 - calc_progress(time, hit_at, hit_at + CIRCLE_FADEOUT_TIME).clamp(0.0, 1.0);
+            (fade_in.min(fade_out), scale_at(time.min(hit_at)))
+        },
+        None if time > start_time + late_window => return None,
+        None => (fade_in, scale_at(time)),
+    };
+
+    (alpha > 0.0).then_some((alpha, scale))
+}
+
+/// Part of preempt used by hidden to fade objects in
+pub const HIDDEN_FADE_IN: f64 = 0.4;
+/// Part of preempt used by hidden to fade circles out right after fade in
+pub const HIDDEN_FADE_OUT: f64 = 0.3;
+
+/// Hit circle alpha with hidden, circle is fully gone before its hit time
+pub fn calc_hidden_alpha(time: f64, start_time: f64, preempt: f32) -> f64 {
+    let preempt = preempt as f64;
+    let appear = start_time - preempt;
+    let fade_in_end = appear + preempt * HIDDEN_FADE_IN;
+    let fade_out_end = fade_in_end + preempt * HIDDEN_FADE_OUT;
+
+    if time <= fade_in_end {
+        calc_progress(time, appear, fade_in_end).clamp(0.0, 1.0)
+    } else {
+        (1.0 - calc_progress(time, fade_in_end, fade_out_end)).clamp(0.0, 1.0)
+    }
+}
+
+/// Slider body alpha with hidden, fades in as circles do
+/// and then slowly fades out until slider's `end_time`
+pub fn calc_hidden_body_alpha(time: f64, start_time: f64, end_time: f64, preempt: f32) -> f64 {
+    let preempt = preempt as f64;
+    let appear = start_time - preempt;
+    let fade_in_end = appear + preempt * HIDDEN_FADE_IN;
+
+    if time <= fade_in_end {
+        calc_progress(time, appear, fade_in_end).clamp(0.0, 1.0)
+    } else {
+        (1.0 - calc_progress(time, fade_in_end, end_time)).clamp(0.0, 1.0)
+    }
+}
+
+#[test]
+pub fn test_hidden_alpha() {
+    // preempt 1000 => appears at 0, fully visible at 400, gone at 700
+    assert_eq!(calc_hidden_alpha(0.0, 1000.0, 1000.0), 0.0);
+    assert_eq!(calc_hidden_alpha(200.0, 1000.0, 1000.0), 0.5);
+    assert_eq!(calc_hidden_alpha(400.0, 1000.0, 1000.0), 1.0);
+    assert!((calc_hidden_alpha(550.0, 1000.0, 1000.0) - 0.5).abs() < 1e-9);
+    assert_eq!(calc_hidden_alpha(700.0, 1000.0, 1000.0), 0.0);
+    assert_eq!(calc_hidden_alpha(1000.0, 1000.0, 1000.0), 0.0);
+
+    // Body keeps fading out until the slider end
+    assert_eq!(calc_hidden_body_alpha(400.0, 1000.0, 2000.0, 1000.0), 1.0);
+    assert!((calc_hidden_body_alpha(1200.0, 1000.0, 2000.0, 1000.0) - 0.5).abs() < 1e-9);
+    assert_eq!(calc_hidden_body_alpha(2000.0, 1000.0, 2000.0, 1000.0), 0.0);
+}
+
+#[test]
+pub fn test_slider_body_alpha() {
+    let approx = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+    // AR0: preempt 1800, fadein 1200
+    let (preempt, fadein) = calculate_preempt_fadein(0.0);
+    let (start, end) = (2000.0, 2100.0);
+
+    assert_eq!(calc_slider_body_alpha(199.0, start, end, preempt, fadein), 0.0);
+    assert!(approx(calc_slider_body_alpha(800.0, start, end, preempt, fadein), 0.5 * SLIDER_BODY_MAX_ALPHA));
+    assert_eq!(calc_slider_body_alpha(1400.0, start, end, preempt, fadein), SLIDER_BODY_MAX_ALPHA);
+    assert_eq!(calc_slider_body_alpha(end, start, end, preempt, fadein), SLIDER_BODY_MAX_ALPHA);
+    assert!(approx(
+        calc_slider_body_alpha(end + SLIDER_FADEOUT_TIME / 2.0, start, end, preempt, fadein),
+        0.5 * SLIDER_BODY_MAX_ALPHA
+    ));
+    assert_eq!(calc_slider_body_alpha(end + SLIDER_FADEOUT_TIME, start, end, preempt, fadein), 0.0);
+
+    // Approach circle is fully visible before the body, and gone at start
+    assert_eq!(calc_slider_approach_alpha(1400.0, start, preempt, fadein), 1.0);
+    assert!(approx(calc_slider_approach_alpha(800.0, start, preempt, fadein), 0.5));
+    assert_eq!(calc_slider_approach_alpha(start, start, preempt, fadein), 0.0);
+
+    // AR10: preempt 450, fadein 300
+    let (preempt, fadein) = calculate_preempt_fadein(10.0);
+
+    assert_eq!(calc_slider_body_alpha(1549.0, start, end, preempt, fadein), 0.0);
+    assert!(approx(calc_slider_body_alpha(1700.0, start, end, preempt, fadein), 0.5 * SLIDER_BODY_MAX_ALPHA));
+    assert_eq!(calc_slider_body_alpha(1850.0, start, end, preempt, fadein), SLIDER_BODY_MAX_ALPHA);
+    assert_eq!(calc_slider_approach_alpha(1999.0, start, preempt, fadein), 1.0);
+
+    // 10 repeats of 100ms each, body holds the whole duration
+    // and only starts fading out after the last repeat
+    let (preempt, fadein) = calculate_preempt_fadein(2.0);
+    let end = start + 10.0 * 100.0;
+
+    for repeat in 0..=10 {
+        let time = start + repeat as f64 * 100.0;
+        assert_eq!(calc_slider_body_alpha(time, start, end, preempt, fadein), SLIDER_BODY_MAX_ALPHA);
+        assert_eq!(calc_slider_approach_alpha(time, start, preempt, fadein), 0.0);
+    }
+
+    assert!(calc_slider_body_alpha(end + 1.0, start, end, preempt, fadein) < SLIDER_BODY_MAX_ALPHA);
+}
+
+#[test]
+pub fn test_circle_approach() {
+    let approx = |a: Option<(f64, f64)>, b: (f64, f64)| {
+        let a = a.expect("approach circle should be visible");
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+    };
+
+    // preempt 1000, fadein 400 => appears at 0 at 4x scale
+    let (start, late) = (1000.0, 100.0);
+
+    assert_eq!(calc_circle_approach(0.0, start, 1000.0, 400.0, None, late), None);
+    assert!(approx(calc_circle_approach(200.0, start, 1000.0, 400.0, None, late), (0.5, 3.4)));
+    assert!(approx(calc_circle_approach(500.0, start, 1000.0, 400.0, None, late), (1.0, 2.5)));
+    assert!(approx(calc_circle_approach(start, start, 1000.0, 400.0, None, late), (1.0, 1.0)));
+
+    // Not hit yet, holds at 1x through the late window and not further
+    assert!(approx(calc_circle_approach(start + 50.0, start, 1000.0, 400.0, None, late), (1.0, 1.0)));
+    assert!(approx(calc_circle_approach(start + late, start, 1000.0, 400.0, None, late), (1.0, 1.0)));
+    assert_eq!(calc_circle_approach(start + late + 1.0, start, 1000.0, 400.0, None, late), None);
+}
+
+#[test]
+pub fn test_circle_approach_hit() {
+    let approx = |a: Option<(f64, f64)>, b: (f64, f64)| {
+        let a = a.expect("approach circle should be visible");
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+    };
+
+    let (start, late) = (1000.0, 100.0);
+
+    // Early hit freezes the scale it had at the hit and fades out with the circle
+    let hit_at = Some(900.0);
+    assert!(approx(calc_circle_approach(900.0, start, 1000.0, 400.0, hit_at, late), (1.0, 1.3)));
+    assert!(approx(
+        calc_circle_approach(900.0 + CIRCLE_FADEOUT_TIME / 2.0, start, 1000.0, 400.0, hit_at, late),
+        (0.5, 1.3)
+    ));
+    assert_eq!(calc_circle_approach(900.0 + CIRCLE_FADEOUT_TIME, start, 1000.0, 400.0, hit_at, late), None);
+
+    // Late hit stays at 1x, never shrinks below it
+    let hit_at = Some(start + 80.0);
+    assert!(approx(
+        calc_circle_approach(start + 80.0 + CIRCLE_FADEOUT_TIME / 4.0, start, 1000.0, 400.0, hit_at, late),
+        (0.75, 1.0)
+    ));
+
+    // Fading out still can't go above the fade in
+    let hit_at = Some(100.0);
+    assert!(approx(calc_circle_approach(150.0, start, 1000.0, 400.0, hit_at, late), (0.375, 3.7)));
+}
 
 #[test]
 pub fn test_progress() {
@@ -131,3 +639,29 @@ pub fn test_directiondegrees() {
 
     assert_eq!(calc_opposite_direction_degree(p1, p2), 270.0)
 }
+
+#[test]
+fn test_angle_between() {
+    let close = |a: Option<f32>, b: f32| (a.unwrap() - b).abs() < 0.01;
+
+    // Straight line
+    let angle = angle_between(Vector2::new(0.0, 0.0), Vector2::new(100.0, 0.0), Vector2::new(200.0, 0.0));
+    assert!(close(angle, 180.0));
+
+    // Right angle, same either way around
+    let angle = angle_between(Vector2::new(0.0, 0.0), Vector2::new(100.0, 0.0), Vector2::new(100.0, 100.0));
+    assert!(close(angle, 90.0));
+
+    let angle = angle_between(Vector2::new(100.0, 100.0), Vector2::new(100.0, 0.0), Vector2::new(0.0, 0.0));
+    assert!(close(angle, 90.0));
+
+    // Back and forth
+    let angle = angle_between(Vector2::new(0.0, 0.0), Vector2::new(100.0, 0.0), Vector2::new(0.0, 0.0));
+    assert!(close(angle, 0.0));
+
+    // Stacked objects
+    let pos = Vector2::new(256.0, 192.0);
+    assert_eq!(angle_between(pos, pos, Vector2::new(0.0, 0.0)), None);
+    assert_eq!(angle_between(Vector2::new(0.0, 0.0), pos, pos), None);
+    assert_eq!(angle_between(pos, pos, pos), None);
+}
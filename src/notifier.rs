@@ -0,0 +1,72 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub severity: Severity,
+    pub text: String,
+}
+
+/// Sends notifications to the user from any thread, they end up as toasts.
+/// Default one is not connected to anything and drops everything,
+/// e.g. in tests or tools without UI
+#[derive(Debug, Clone, Default)]
+pub struct Notifier {
+    tx: Option<Sender<Notification>>,
+}
+
+impl Notifier {
+    pub fn channel() -> (Self, Receiver<Notification>) {
+        let (tx, rx) = channel();
+
+        (Self { tx: Some(tx) }, rx)
+    }
+
+    pub fn notify(&self, severity: Severity, text: impl Into<String>) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+
+        // UI is gone, nobody to show it to
+        let _ = tx.send(Notification {
+            severity,
+            text: text.into(),
+        });
+    }
+
+    #[inline]
+    pub fn info(&self, text: impl Into<String>) {
+        self.notify(Severity::Info, text);
+    }
+
+    #[inline]
+    pub fn warn(&self, text: impl Into<String>) {
+        self.notify(Severity::Warning, text);
+    }
+
+    #[inline]
+    pub fn error(&self, text: impl Into<String>) {
+        self.notify(Severity::Error, text);
+    }
+}
+
+#[test]
+fn test_notifier() {
+    let (notifier, rx) = Notifier::channel();
+
+    notifier.clone().warn("audio missing");
+    notifier.error("broken");
+
+    assert_eq!(rx.try_recv().unwrap(), Notification { severity: Severity::Warning, text: "audio missing".to_owned() });
+    assert_eq!(rx.try_recv().unwrap().severity, Severity::Error);
+    assert!(rx.try_recv().is_err());
+
+    // Not connected one is a no-op
+    Notifier::default().error("nobody listens");
+}
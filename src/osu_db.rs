@@ -1,12 +1,27 @@
-use std::{fs::{self, File}, io::{BufReader, Read}, path::{self, Path, PathBuf}, sync::{Arc, Mutex}};
+use std::{fs::{self, File}, io::{BufReader, Read}, path::{self, Path, PathBuf}, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex}, time::UNIX_EPOCH};
 
 use r2d2_sqlite::SqliteConnectionManager;
 use r2d2::Pool;
 use rosu_map::{section::general::GameMode, Beatmap};
-use rusqlite::{params, Connection};
+use rusqlite::{params, params_from_iter, types::Value, Connection, OptionalExtension};
+
+use crate::{hit_objects::{converter_for, mode_from_u8}, i18n::tf, notifier::Notifier, search_query::{parse_search_query, SearchQuery}, session_stats::{DayStats, PlayRecord}};
+
+use self::stable_import::{StableCollection, StableScore};
+
+pub mod stable_import;
+pub mod replay_export;
 
 pub const DEFAULT_DB_PATH: &str = "./rosu.db";
 
+/// Scanned beatmaps are committed in batches of this size, song
+/// select picks them up once per batch instead of once per file
+const SCAN_BATCH_SIZE: usize = 50;
+
+/// Scan checkpoint is saved at least once per this many directories,
+/// even if they had nothing new to commit
+const SCAN_CHECKPOINT_DIRECTORIES: usize = 20;
+
 #[derive(Clone, Debug)]
 pub struct DbBeatmapEntry {
     pub id: u64,
@@ -18,8 +33,178 @@ pub struct DbBeatmapEntry {
     pub version: String,
     pub path: PathBuf,
     pub hash: String,
+
+    // Stored at scan time so selecting a beatmap doesn't need
+    // to parse the whole .osu. `None` for rows from older databases
+
+    /// Relative to the beatmap directory
+    pub background_file: Option<String>,
+    /// Relative to the beatmap directory
+    pub audio_file: Option<String>,
+    pub preview_time: Option<i32>,
+    /// `GameMode` as u8
+    pub mode: Option<u8>,
+    pub source: Option<String>,
+    /// Space separated
+    pub tags: Option<String>,
+}
+
+impl DbBeatmapEntry {
+    /// Entry that is not stored in the DB yet, `id` is 0
+    pub fn from_beatmap(path: PathBuf, hash: String, beatmap: &Beatmap) -> Self {
+        Self {
+            id: 0,
+            beatmap_id: beatmap.beatmap_id as i64,
+            beatmapset_id: beatmap.beatmap_set_id as i64,
+            title: beatmap.title.clone(),
+            artist: beatmap.artist.clone(),
+            creator: beatmap.creator.clone(),
+            version: beatmap.version.clone(),
+            path,
+            hash,
+            background_file: Some(beatmap.background_file.clone()),
+            audio_file: Some(beatmap.audio_file.clone()),
+            preview_time: Some(beatmap.preview_time),
+            mode: Some(beatmap.mode as u8),
+            source: Some(beatmap.source.clone()),
+            tags: Some(beatmap.tags.clone()),
+        }
+    }
+
+    /// `None` for rows from older databases or unknown values
+    pub fn game_mode(&self) -> Option<GameMode> {
+        self.mode.and_then(mode_from_u8)
+    }
+
+    /// Mode of the beatmap when there is no converter for it,
+    /// such beatmaps are listed but can't be played
+    pub fn unsupported_mode(&self) -> Option<GameMode> {
+        self.game_mode().filter(|mode| converter_for(*mode).is_none())
+    }
+
+    /// Modal text starting such beatmap is refused with
+    pub fn unsupported_mode_modal(&self) -> Option<String> {
+        self.unsupported_mode()
+            .map(|mode| format!("{mode:?} beatmaps are not supported, only osu!standard ones"))
+    }
+}
+
+/// Progress of a scan that can be resumed. Directories of the root
+/// are scanned in sorted order, so every one up to `last_directory`
+/// is already committed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScanCheckpoint {
+    pub root: PathBuf,
+    /// Name of the last fully scanned directory, `None` before the first one
+    pub last_directory: Option<String>,
+    pub directories_done: usize,
+}
+
+impl ScanCheckpoint {
+    fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            last_directory: None,
+            directories_done: 0,
+        }
+    }
+}
+
+/// Work done by a single scan
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// Directories listed, the root included
+    pub directories_read: usize,
+    /// .osu files read and hashed
+    pub files_read: usize,
+    /// Beatmaps committed to the DB
+    pub added: usize,
+    /// `false` if it was stopped before the last directory
+    pub finished: bool,
+}
+
+/// Named list of beatmap md5s. Md5s that are not in the `beatmaps`
+/// table are kept unresolved and attach once the map is imported
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbCollection {
+    pub id: i64,
+    pub name: String,
+    /// Every md5 of the collection
+    pub beatmaps: usize,
+    /// Md5s that are present in the `beatmaps` table
+    pub resolved: usize,
+}
+
+/// Song select selection restored on the next launch. Beatmap
+/// is looked up by md5 since its index depends on the filter
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SavedSelection {
+    pub hash: String,
+    pub collection: Option<i64>,
+    /// Search box text
+    pub search: String,
+}
+
+/// Play made in rosu!, stored along with the imported scores
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LocalScore {
+    pub beatmap_hash: String,
+    /// Md5 of the exported replay
+    pub replay_hash: String,
+    pub player: String,
+    pub score: i64,
+    pub max_combo: u32,
+    pub x300: u32,
+    pub x100: u32,
+    pub x50: u32,
+    pub miss: u32,
+    pub perfect: bool,
+    pub mods: u32,
+    /// Unix seconds
+    pub timestamp: i64,
+    /// Encoded [`crate::score::Progression`] of the play
+    pub progression: Vec<u8>,
 }
 
+/// What the song select list is narrowed down to
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BeatmapFilter {
+    pub collection: Option<i64>,
+    pub query: SearchQuery,
+}
+
+impl BeatmapFilter {
+    pub fn collection(collection: Option<i64>) -> Self {
+        Self {
+            collection,
+            query: SearchQuery::default(),
+        }
+    }
+
+    /// SQL condition on the `beatmaps` table, `values` of its
+    /// placeholders are appended in the same order
+    fn condition(&self, values: &mut Vec<Value>) -> String {
+        let mut conditions = Vec::new();
+
+        if let Some(collection) = self.collection {
+            conditions.push("hash IN (SELECT hash FROM collection_beatmaps WHERE collection_id = ?)".to_owned());
+            values.push(Value::Integer(collection));
+        }
+
+        conditions.extend(query_conditions(&self.query, SEARCH_COLUMNS, values));
+
+        match conditions.is_empty() {
+            true => "1".to_owned(),
+            false => conditions.join(" AND "),
+        }
+    }
+}
+
+/// Columns plain search terms are looked up in
+const SEARCH_COLUMNS: &[&str] = &["title", "artist", "creator", "version", "source", "tags"];
+/// Matches in these columns are ordered before source and tags ones
+const PRIMARY_SEARCH_COLUMNS: &[&str] = &["title", "artist", "creator", "version"];
+
 impl TryFrom<&rusqlite::Row<'_>> for DbBeatmapEntry {
     type Error = rusqlite::Error;
 
@@ -35,6 +220,12 @@ impl TryFrom<&rusqlite::Row<'_>> for DbBeatmapEntry {
             version: row.get(6)?,
             path: PathBuf::from(path),
             hash: row.get(8)?,
+            background_file: row.get(9)?,
+            audio_file: row.get(10)?,
+            preview_time: row.get(11)?,
+            mode: row.get(12)?,
+            source: row.get(13)?,
+            tags: row.get(14)?,
         })
     }
 }
@@ -44,6 +235,18 @@ pub struct OsuDatabase {
 
     // A in-memory cache for faster loading times
     pub cache: Mutex<Vec<Arc<DbBeatmapEntry>>>,
+
+    /// Bumped after every committed batch of new beatmaps
+    generation: Arc<AtomicU64>,
+
+    /// Beatmaps with greater ids were added after the DB was opened
+    session_start_id: u64,
+
+    /// Scan problems are shown to the user through it
+    notifier: Notifier,
+
+    /// See [`Self::interrupted_scan`]
+    interrupted_scan: Mutex<Option<ScanCheckpoint>>,
 }
 
 impl OsuDatabase {
@@ -62,7 +265,13 @@ impl OsuDatabase {
                 creator TEXT, 
                 version TEXT,
                 path TEXT,
-                hash TEXT NOT NULL
+                hash TEXT NOT NULL,
+                background_file TEXT,
+                audio_file TEXT,
+                preview_time INTEGER,
+                mode INTEGER,
+                source TEXT,
+                tags TEXT
             );
 
             CREATE INDEX hash_beatmap
@@ -71,11 +280,136 @@ impl OsuDatabase {
 
         let conn = pool.get().unwrap();
 
-        conn.execute(QUERY, [])?;
+        conn.execute_batch(QUERY)?;
 
         Ok(pool)
     }
 
+    /// Tables added after the initial schema, created for old databases as well
+    const EXTRA_TABLES: &str = "
+        CREATE TABLE IF NOT EXISTS collections (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            position INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS collection_beatmaps (
+            collection_id INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+            hash TEXT NOT NULL,
+            PRIMARY KEY (collection_id, hash)
+        );
+
+        CREATE TABLE IF NOT EXISTS scores (
+            id INTEGER PRIMARY KEY,
+            hash TEXT NOT NULL,
+            replay_hash TEXT UNIQUE,
+            player TEXT,
+            mode INTEGER,
+            score INTEGER,
+            max_combo INTEGER,
+            x300 INTEGER,
+            x100 INTEGER,
+            x50 INTEGER,
+            geki INTEGER,
+            katu INTEGER,
+            miss INTEGER,
+            perfect INTEGER,
+            mods INTEGER,
+            timestamp INTEGER,
+            imported INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE INDEX IF NOT EXISTS hash_score
+        ON scores(hash);
+
+        CREATE TABLE IF NOT EXISTS session_stats (
+            day TEXT PRIMARY KEY,
+            plays_started INTEGER NOT NULL DEFAULT 0,
+            plays_completed INTEGER NOT NULL DEFAULT 0,
+            retries INTEGER NOT NULL DEFAULT 0,
+            play_time_ms INTEGER NOT NULL DEFAULT 0,
+            x300 INTEGER NOT NULL DEFAULT 0,
+            x100 INTEGER NOT NULL DEFAULT 0,
+            x50 INTEGER NOT NULL DEFAULT 0,
+            miss INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS saved_selection (
+            id INTEGER PRIMARY KEY CHECK (id = 0),
+            hash TEXT NOT NULL,
+            collection INTEGER,
+            search TEXT NOT NULL DEFAULT ''
+        );
+
+        CREATE TABLE IF NOT EXISTS file_hashes (
+            path TEXT PRIMARY KEY,
+            modified INTEGER NOT NULL,
+            size INTEGER NOT NULL,
+            hash BLOB NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS scan_sessions (
+            id INTEGER PRIMARY KEY,
+            root TEXT NOT NULL UNIQUE,
+            last_directory TEXT,
+            directories_done INTEGER NOT NULL DEFAULT 0
+        );
+    ";
+
+    /// Columns added after the initial schema, appended
+    /// to the old databases in this exact order
+    const MIGRATION_COLUMNS: &[(&str, &str)] = &[
+        ("background_file", "TEXT"),
+        ("audio_file", "TEXT"),
+        ("preview_time", "INTEGER"),
+        ("mode", "INTEGER"),
+        ("source", "TEXT"),
+        ("tags", "TEXT"),
+    ];
+
+    /// Same as [`Self::MIGRATION_COLUMNS`] but for the `collections` table
+    const COLLECTION_MIGRATION_COLUMNS: &[(&str, &str)] = &[
+        ("position", "INTEGER NOT NULL DEFAULT 0"),
+    ];
+
+    /// Same as [`Self::MIGRATION_COLUMNS`] but for the `scores` table
+    const SCORE_MIGRATION_COLUMNS: &[(&str, &str)] = &[
+        ("replay", "BLOB"),
+        ("progression", "BLOB"),
+    ];
+
+    fn migrate(conn: &Connection) -> Result<(), rusqlite::Error> {
+        Self::add_missing_columns(conn, "beatmaps", Self::MIGRATION_COLUMNS)?;
+
+        conn.execute_batch(Self::EXTRA_TABLES)?;
+
+        Self::add_missing_columns(conn, "collections", Self::COLLECTION_MIGRATION_COLUMNS)?;
+        Self::add_missing_columns(conn, "scores", Self::SCORE_MIGRATION_COLUMNS)?;
+
+        Ok(())
+    }
+
+    fn add_missing_columns(
+        conn: &Connection,
+        table: &str,
+        columns: &[(&str, &str)],
+    ) -> Result<(), rusqlite::Error> {
+        let mut stmt = conn.prepare(&format!("SELECT name FROM pragma_table_info('{table}')"))?;
+        let existing = stmt.query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (name, kind) in columns {
+            if existing.iter().any(|x| x == name) {
+                continue;
+            }
+
+            tracing::info!("Migrating DB: adding {name} column to {table}");
+            conn.execute(&format!("ALTER TABLE {table} ADD COLUMN {name} {kind}"), [])?;
+        }
+
+        Ok(())
+    }
+
     pub fn new_from_path<T: AsRef<Path>>(path: T) -> Result<Self, rusqlite::Error> {
         let pool = if path.as_ref().exists() {
             let manager = SqliteConnectionManager::file(&path);
@@ -90,82 +424,325 @@ impl OsuDatabase {
         {
             let conn = pool.get().unwrap();
             conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+
+            Self::migrate(&conn)?;
         }
 
+        let session_start_id = pool.get().unwrap()
+            .query_row("SELECT COALESCE(MAX(id), 0) FROM beatmaps", [], |row| row.get(0))?;
+
+        let interrupted_scan = Self::load_interrupted_scan(&pool.get().unwrap())?;
+
         tracing::info!("Initialized DB connection at {:?}", path.as_ref());
 
         let db = Self {
             cache: Vec::new().into(),
             conn: pool,
+            generation: Arc::new(AtomicU64::new(0)),
+            session_start_id,
+            notifier: Notifier::default(),
+            interrupted_scan: interrupted_scan.into(),
         };
 
         Ok(db)
     }
+
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = notifier;
+        self
+    }
     
     // Spawns a job to recursively look for beatmaps in directory
     pub fn scan_beatmaps(&self, look_path: impl AsRef<Path>, stop_rx: oneshot::Receiver<()>) {
+        self.spawn_scan(look_path, false, stop_rx);
+    }
+
+    /// Same as [`Self::scan_beatmaps`] but continues after the checkpoint
+    /// of an interrupted scan of `look_path`, if there is one
+    pub fn resume_scan_beatmaps(&self, look_path: impl AsRef<Path>, stop_rx: oneshot::Receiver<()>) {
+        self.spawn_scan(look_path, true, stop_rx);
+    }
+
+    fn spawn_scan(&self, look_path: impl AsRef<Path>, resume: bool, stop_rx: oneshot::Receiver<()>) {
         let pool = self.conn.clone();
+        let generation = self.generation.clone();
+        let notifier = self.notifier.clone();
         let path: PathBuf = look_path.as_ref().to_path_buf();
+
+        self.interrupted_scan.lock().unwrap().take();
     
         // TODO: Maybe keep a worker thread around instead of spawning a new one everytime :D
         std::thread::spawn(move || {
-            'main_loop: for entry in fs::read_dir(path).unwrap() {
-                let entry = entry.unwrap();
+            Self::run_scan(&pool, &generation, &notifier, &path, resume, |_| stop_rx.try_recv().is_ok());
+        });
+    }
+
+    /// Scans `look_path` on the current thread. `should_stop` is asked
+    /// before every file, the scan is interrupted once it returns `true`
+    pub fn scan_beatmaps_blocking(
+        &self,
+        look_path: impl AsRef<Path>,
+        resume: bool,
+        should_stop: impl FnMut(&ScanStats) -> bool,
+    ) -> ScanStats {
+        self.interrupted_scan.lock().unwrap().take();
+
+        Self::run_scan(&self.conn, &self.generation, &self.notifier, look_path.as_ref(), resume, should_stop)
+    }
+
+    /// Scan that was stopped before it went through every directory,
+    /// as found when the DB was opened. Cleared once a scan is started
+    pub fn interrupted_scan(&self) -> Option<ScanCheckpoint> {
+        self.interrupted_scan.lock().unwrap().clone()
+    }
+
+    /// Walks beatmap directories of `root` in sorted order, so a
+    /// checkpoint is enough to know which of them are done
+    fn run_scan(
+        pool: &Pool<SqliteConnectionManager>,
+        generation: &AtomicU64,
+        notifier: &Notifier,
+        root: &Path,
+        resume: bool,
+        mut should_stop: impl FnMut(&ScanStats) -> bool,
+    ) -> ScanStats {
+        let _span = tracy_client::span!("osu_db::run_scan");
+
+        let mut stats = ScanStats::default();
+        let mut conn = pool.get().unwrap();
+
+        let mut checkpoint = match Self::start_scan_session(&conn, root, resume) {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                tracing::error!("Failed to start scan session for {}: {e}", root.display());
+                ScanCheckpoint::new(root)
+            },
+        };
+
+        if let Some(last) = &checkpoint.last_directory {
+            tracing::info!("Resuming scan of {} after {last}", root.display());
+        }
+
+        let mut batch: Vec<DbBeatmapEntry> = Vec::with_capacity(SCAN_BATCH_SIZE);
+        // Reported once at the end, not per file
+        let mut broken = 0;
+        let mut since_checkpoint = 0;
+        let mut stopped = false;
+
+        let directories = Self::sorted_entries(root, &mut stats);
+
+        'main_loop: for dir in directories {
+            if !dir.is_dir() {
+                continue;
+            }
+
+            let Some(name) = dir.file_name().map(|x| x.to_string_lossy().into_owned()) else {
+                continue;
+            };
+
+            if checkpoint.last_directory.as_ref().is_some_and(|last| name <= *last) {
+                continue;
+            }
 
-                if !entry.path().is_dir() {
+            for path in Self::sorted_entries(&dir, &mut stats) {
+                if should_stop(&stats) {
+                    stopped = true;
+                    break 'main_loop;
+                }
+
+                if path.extension().is_none_or(|ext| ext != "osu") {
                     continue;
                 }
 
-                for entry in fs::read_dir(entry.path()).unwrap() {
-                    let entry = entry.unwrap();
-
-                    if stop_rx.try_recv().is_ok() {
-                        break 'main_loop;
-                    }
-
-                    // TODO insert in batches
-                    if let Some(ext) = entry.path().extension() {
-                        if ext == "osu" {
-                            let file = File::open(&entry.path()).unwrap();
-                            let mut reader = BufReader::new(file);
-
-                            let mut buff = Vec::new();
-                            reader.read_to_end(&mut buff).unwrap();
-
-                            let md5_hash = format!("{:x}", md5::compute(&buff));
-
-                            let conn = pool.get().unwrap();
-                            if Self::get_beatmap_by_hash_external(&conn, &md5_hash).is_some() {
-                                continue;
-                            }
-
-                            let beatmap = Beatmap::from_bytes(&buff).unwrap();
-
-                            if beatmap.mode != GameMode::Osu {
-                                continue
-                            }
-
-                            // raw entry
-                            let entry = DbBeatmapEntry {
-                                id: 0,
-                                beatmap_id: beatmap.beatmap_id as i64,
-                                beatmapset_id: beatmap.beatmap_set_id as i64,
-                                title: beatmap.title,
-                                artist: beatmap.artist,
-                                creator: beatmap.creator,
-                                version: beatmap.version,
-                                path: entry.path(),
-                                hash: md5_hash,
-                            };
-
-                            Self::insert_beatmap_external(&conn, &entry);
-                        }
-                    }
+                let file = File::open(&path).unwrap();
+                let mut reader = BufReader::new(file);
+
+                let mut buff = Vec::new();
+                reader.read_to_end(&mut buff).unwrap();
+                stats.files_read += 1;
+
+                let md5_hash = format!("{:x}", md5::compute(&buff));
+
+                if Self::get_beatmap_by_hash_external(&conn, &md5_hash).is_some() {
+                    continue;
+                }
+
+                // Same file in two places, first one isn't committed yet
+                if batch.iter().any(|x| x.hash == md5_hash) {
+                    continue;
                 }
 
-                tracing::info!("Parser .osu: {}", entry.path().display());
+                let beatmap = match Beatmap::from_bytes(&buff) {
+                    Ok(beatmap) => beatmap,
+                    Err(e) => {
+                        tracing::warn!("Skipping broken beatmap {}: {e}", path.display());
+                        broken += 1;
+                        continue;
+                    },
+                };
+
+                batch.push(DbBeatmapEntry::from_beatmap(path, md5_hash, &beatmap));
+            }
+
+            tracing::info!("Parsed .osu: {}", dir.display());
+
+            checkpoint.last_directory = Some(name);
+            checkpoint.directories_done += 1;
+            since_checkpoint += 1;
+
+            if batch.len() >= SCAN_BATCH_SIZE || since_checkpoint >= SCAN_CHECKPOINT_DIRECTORIES {
+                stats.added += Self::commit_scan_batch(&mut conn, &mut batch, &checkpoint, false, generation, notifier);
+                since_checkpoint = 0;
+            }
+        }
+
+        // Whatever was scanned before the stop is kept, the
+        // checkpoint stays at the last fully scanned directory
+        stats.finished = !stopped;
+        stats.added += Self::commit_scan_batch(&mut conn, &mut batch, &checkpoint, stats.finished, generation, notifier);
+
+        if broken > 0 {
+            notifier.warn(tf("notify.broken_beatmaps", &[&broken]));
+        }
+
+        stats
+    }
+
+    /// Entries of `dir` sorted by path, empty if it can't be read
+    fn sorted_entries(dir: &Path, stats: &mut ScanStats) -> Vec<PathBuf> {
+        stats.directories_read += 1;
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Can't read {}: {e}", dir.display());
+                return Vec::new();
+            },
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|x| x.ok())
+            .map(|x| x.path())
+            .collect();
+
+        paths.sort();
+        paths
+    }
+
+    /// Checkpoint of an interrupted scan of `root` if `resume` is set
+    /// and there is one, otherwise a fresh session replacing it
+    fn start_scan_session(conn: &Connection, root: &Path, resume: bool) -> Result<ScanCheckpoint, rusqlite::Error> {
+        let key = Self::scan_root_key(root);
+
+        if resume {
+            let checkpoint = conn.query_row(
+                "SELECT last_directory, directories_done FROM scan_sessions WHERE root = ?1",
+                [&key],
+                |row| Ok(ScanCheckpoint {
+                    root: root.to_path_buf(),
+                    last_directory: row.get(0)?,
+                    directories_done: row.get::<_, i64>(1)? as usize,
+                }),
+            ).optional()?;
+
+            if let Some(checkpoint) = checkpoint {
+                return Ok(checkpoint);
             }
+        }
+
+        conn.execute(
+            "INSERT OR REPLACE INTO scan_sessions (root, last_directory, directories_done) VALUES (?1, NULL, 0)",
+            [&key],
+        )?;
+
+        Ok(ScanCheckpoint::new(root))
+    }
+
+    /// Latest scan session that wasn't finished
+    fn load_interrupted_scan(conn: &Connection) -> Result<Option<ScanCheckpoint>, rusqlite::Error> {
+        conn.query_row(
+            "SELECT root, last_directory, directories_done FROM scan_sessions ORDER BY id DESC LIMIT 1",
+            [],
+            |row| Ok(ScanCheckpoint {
+                root: PathBuf::from(row.get::<_, String>(0)?),
+                last_directory: row.get(1)?,
+                directories_done: row.get::<_, i64>(2)? as usize,
+            }),
+        ).optional()
+    }
+
+    /// Same root given as a relative path resumes the same session
+    fn scan_root_key(root: &Path) -> String {
+        path::absolute(root)
+            .unwrap_or_else(|_| root.to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Inserts and clears the `batch` along with the `checkpoint` in a
+    /// single transaction, session is removed once it's `finished`.
+    /// Returns the amount of inserted beatmaps
+    fn commit_scan_batch(
+        conn: &mut Connection,
+        batch: &mut Vec<DbBeatmapEntry>,
+        checkpoint: &ScanCheckpoint,
+        finished: bool,
+        generation: &AtomicU64,
+        notifier: &Notifier,
+    ) -> usize {
+        let key = Self::scan_root_key(&checkpoint.root);
+
+        let result = conn.transaction().and_then(|tx| {
+            for entry in batch.iter() {
+                Self::insert_beatmap_external(&tx, entry);
+            }
+
+            if finished {
+                tx.execute("DELETE FROM scan_sessions WHERE root = ?1", [&key])?;
+            } else {
+                tx.execute(
+                    "UPDATE scan_sessions SET last_directory = ?2, directories_done = ?3 WHERE root = ?1",
+                    params![key, checkpoint.last_directory, checkpoint.directories_done as i64],
+                )?;
+            }
+
+            tx.commit()
         });
+
+        let inserted = match result {
+            Ok(()) => {
+                if !batch.is_empty() {
+                    generation.fetch_add(1, Ordering::Release);
+                }
+
+                batch.len()
+            },
+            Err(e) => {
+                tracing::error!("Failed to commit {} scanned beatmaps: {e}", batch.len());
+                notifier.error(tf("notify.scan_commit_failed", &[&batch.len()]));
+                0
+            },
+        };
+
+        batch.clear();
+        inserted
+    }
+
+    /// Changes every time new beatmaps are committed, compared
+    /// against the last seen value to know the list is outdated
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Same counter as [`Self::generation`], for other threads
+    pub fn generation_counter(&self) -> Arc<AtomicU64> {
+        self.generation.clone()
+    }
+
+    /// Largest beatmap id at the time the DB was opened
+    #[inline]
+    pub fn session_start_id(&self) -> u64 {
+        self.session_start_id
     }
 
     pub fn insert_beatmap_external(
@@ -174,8 +751,8 @@ impl OsuDatabase {
     ) {
         const QUERY: &str = "
             INSERT INTO beatmaps 
-            (beatmapset_id, beatmap_id, title, artist, creator, version, path, hash)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            (beatmapset_id, beatmap_id, title, artist, creator, version, path, hash, background_file, audio_file, preview_time, mode, source, tags)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
         ";
 
         conn.execute(
@@ -188,15 +765,31 @@ impl OsuDatabase {
                 &entry.creator,
                 &entry.version,
                 format!("{}", &path::absolute(&entry.path).unwrap().display()),
-                &entry.hash
+                &entry.hash,
+                &entry.background_file,
+                &entry.audio_file,
+                &entry.preview_time,
+                &entry.mode,
+                &entry.source,
+                &entry.tags,
             )
         ).unwrap();
     }
 
     pub fn beatmaps_amount(&self) -> usize {
-        const QUERY: &str = "SELECT COUNT(*) FROM beatmaps";
+        self.beatmaps_amount_in(None)
+    }
+
+    /// Amount of beatmaps in the `collection`, every beatmap when it's `None`
+    pub fn beatmaps_amount_in(&self, collection: Option<i64>) -> usize {
+        self.beatmaps_amount_matching(&BeatmapFilter::collection(collection))
+    }
 
-        let amount = self.conn.get().unwrap().query_row(QUERY, [], |row| {
+    pub fn beatmaps_amount_matching(&self, filter: &BeatmapFilter) -> usize {
+        let mut values = Vec::new();
+        let query = format!("SELECT COUNT(*) FROM beatmaps WHERE {}", filter.condition(&mut values));
+
+        let amount = self.conn.get().unwrap().query_row(&query, params_from_iter(values), |row| {
             Ok(row.get(0).unwrap())
         }).unwrap();
 
@@ -204,9 +797,26 @@ impl OsuDatabase {
     }
 
     pub fn get_beatmap_by_index(&self, index: usize) -> Option<DbBeatmapEntry> {
-        const QUERY: &str = "SELECT * FROM beatmaps ORDER BY id ASC LIMIT ?1 OFFSET ?1";
+        self.get_beatmap_by_index_in(None, index)
+    }
+
+    /// Same as [`Self::get_beatmap_by_index`] but `index` is
+    /// a position inside of the `collection`
+    pub fn get_beatmap_by_index_in(&self, collection: Option<i64>, index: usize) -> Option<DbBeatmapEntry> {
+        self.get_beatmap_by_index_matching(&BeatmapFilter::collection(collection), index)
+    }
+
+    /// `index` is a position inside of the filtered list
+    pub fn get_beatmap_by_index_matching(&self, filter: &BeatmapFilter, index: usize) -> Option<DbBeatmapEntry> {
+        let mut values = Vec::new();
+        let query = format!(
+            "SELECT * FROM beatmaps WHERE {} ORDER BY id ASC LIMIT 1 OFFSET ?",
+            filter.condition(&mut values),
+        );
+
+        values.push(Value::Integer(index as i64));
 
-        let entry = self.conn.get().unwrap().query_row(QUERY, [index], |row| {
+        let entry = self.conn.get().unwrap().query_row(&query, params_from_iter(values), |row| {
             DbBeatmapEntry::try_from(row)
         });
 
@@ -222,6 +832,30 @@ impl OsuDatabase {
         }
     }
 
+    /// Position of the beatmap with `hash` inside of the filtered
+    /// list, `None` if it's not there
+    pub fn get_beatmap_index_matching(&self, filter: &BeatmapFilter, hash: &str) -> Option<usize> {
+        let conn = self.conn.get().unwrap();
+
+        let mut values = vec![Value::Text(hash.to_owned())];
+        let query = format!(
+            "SELECT id FROM beatmaps WHERE hash = ? AND {} ORDER BY id ASC LIMIT 1",
+            filter.condition(&mut values),
+        );
+
+        let id: i64 = conn.query_row(&query, params_from_iter(values), |row| row.get(0)).ok()?;
+
+        let mut values = Vec::new();
+        let query = format!(
+            "SELECT COUNT(*) FROM beatmaps WHERE {} AND id < ?",
+            filter.condition(&mut values),
+        );
+
+        values.push(Value::Integer(id));
+
+        conn.query_row(&query, params_from_iter(values), |row| row.get(0)).ok()
+    }
+
     pub fn get_beatmap_by_hash(&self, hash: &str) -> Option<DbBeatmapEntry> {
         const QUERY: &str = "SELECT * FROM beatmaps WHERE hash = ?1";
 
@@ -263,23 +897,540 @@ impl OsuDatabase {
         }
     }
 
+    /// Inserts all entries in a single transaction
+    pub fn insert_beatmaps(&self, entries: &[DbBeatmapEntry]) -> Result<(), rusqlite::Error> {
+        let mut conn = self.conn.get().unwrap();
+        let tx = conn.transaction()?;
+
+        for entry in entries {
+            Self::insert_beatmap_external(&tx, entry);
+        }
+
+        tx.commit()?;
+
+        self.generation.fetch_add(1, Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Rows `min..max` of the `ORDER BY id` list
+    pub fn beatmaps_range(&self, min: usize, max: usize) -> Vec<Arc<DbBeatmapEntry>> {
+        self.beatmaps_range_in(None, min, max)
+    }
+
+    /// Rows `min..max` of the `ORDER BY id` list of the `collection`
+    pub fn beatmaps_range_in(&self, collection: Option<i64>, min: usize, max: usize) -> Vec<Arc<DbBeatmapEntry>> {
+        self.beatmaps_range_matching(&BeatmapFilter::collection(collection), min, max)
+    }
+
+    /// Rows `min..max` of the filtered `ORDER BY id` list
+    pub fn beatmaps_range_matching(&self, filter: &BeatmapFilter, min: usize, max: usize) -> Vec<Arc<DbBeatmapEntry>> {
+        let mut values = Vec::new();
+        let query = format!(
+            "SELECT * FROM beatmaps WHERE {} ORDER BY id ASC LIMIT ? OFFSET ?",
+            filter.condition(&mut values),
+        );
+
+        values.push(Value::Integer(max.saturating_sub(min) as i64));
+        values.push(Value::Integer(min as i64));
+
+        let conn = self.conn.get().unwrap();
+
+        let mut stmt = conn.prepare(&query).unwrap();
+
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            DbBeatmapEntry::try_from(row)
+        }).unwrap();
+
+        rows.filter_map(|x| x.ok())
+            .map(Arc::new)
+            .collect()
+    }
+
     pub fn fetch_beatmaps_range(&self, min: usize, max: usize) {
-        const QUERY: &str = 
-            "select * from beatmaps order by id ASC LIMIT ?1 OFFSET ?2";
+        let entries = self.beatmaps_range(min, max);
+
+        *self.cache.lock().unwrap() = entries;
+    }
+
+    /// Returns indexes of beatmaps matching the search box `query`, see
+    /// [`parse_search_query`]. Terms are looked up in title, artist, creator,
+    /// difficulty name, source and tags, case insensitive. Beatmaps matched
+    /// only through source or tags go after the rest
+    pub fn search_beatmaps(&self, query: &str) -> Vec<usize> {
+        self.search_beatmaps_in(None, query)
+    }
+
+    /// Same as [`Self::search_beatmaps`] but indexes are
+    /// positions inside of the `collection`
+    pub fn search_beatmaps_in(&self, collection: Option<i64>, query: &str) -> Vec<usize> {
+        let filter = BeatmapFilter {
+            collection,
+            query: parse_search_query(query),
+        };
+
+        let mut values = vec![Value::from(collection)];
+        let condition = filter.condition(&mut values);
+        let primary = query_conditions(&filter.query, PRIMARY_SEARCH_COLUMNS, &mut values);
+
+        let weight = match primary.is_empty() {
+            true => "0".to_owned(),
+            false => format!("CASE WHEN {} THEN 0 ELSE 1 END", primary.join(" AND ")),
+        };
+
+        let query = format!("
+            SELECT (
+                SELECT COUNT(*) FROM beatmaps AS b
+                WHERE b.id < beatmaps.id
+                AND (?1 IS NULL OR b.hash IN (SELECT hash FROM collection_beatmaps WHERE collection_id = ?1))
+            )
+            FROM beatmaps
+            WHERE {condition}
+            ORDER BY {weight} ASC, id ASC
+        ");
 
         let conn = self.conn.get().unwrap();
+        let mut stmt = conn.prepare(&query).unwrap();
 
-        let mut stmt = conn.prepare(QUERY).unwrap();
+        let rows = stmt.query_map(params_from_iter(values), |row| row.get(0)).unwrap();
+
+        rows.filter_map(|x| x.ok()).collect()
+    }
+
+    /// Returns position of the beatmap in the `ORDER BY id` list
+    pub fn get_beatmap_index(&self, id: u64) -> Option<usize> {
+        const QUERY: &str = "SELECT COUNT(*) FROM beatmaps WHERE id < ?1";
+
+        let conn = self.conn.get().unwrap();
+
+        if Self::get_beatmap_by_id_external(&conn, id).is_none() {
+            return None;
+        }
+
+        conn.query_row(QUERY, [id], |row| row.get(0)).ok()
+    }
+
+    pub fn get_beatmap_by_id_external(
+        conn: &Connection, 
+        id: u64
+    ) -> Option<DbBeatmapEntry> {
+        const QUERY: &str = "SELECT * FROM beatmaps WHERE id = ?1";
 
-        let rows = stmt.query_map(params![max - min, min], |row| {
+        let entry = conn.query_row(QUERY, [id], |row| {
             DbBeatmapEntry::try_from(row)
+        });
+
+        match entry {
+            Ok(entry) => Some(entry),
+            Err(e) => match e {
+                rusqlite::Error::QueryReturnedNoRows => None,
+                _ => {
+                    tracing::error!("selecting beatmap by id error");
+                    None
+                },
+            },
+        }
+    }
+
+    /// Returns indexes of all beatmaps located inside `dir`
+    pub fn get_beatmap_indexes_in_directory(&self, dir: impl AsRef<Path>) -> Vec<usize> {
+        const QUERY: &str = "
+            SELECT (SELECT COUNT(*) FROM beatmaps AS b WHERE b.id < beatmaps.id)
+            FROM beatmaps
+            WHERE substr(path, 1, length(?1)) = ?1
+        ";
+
+        let conn = self.conn.get().unwrap();
+        let mut stmt = conn.prepare(QUERY).unwrap();
+
+        let rows = stmt.query_map([Self::directory_prefix(dir)], |row| row.get(0)).unwrap();
+
+        rows.filter_map(|x| x.ok()).collect()
+    }
+
+    pub fn delete_beatmap(&self, id: u64) -> Result<usize, rusqlite::Error> {
+        const QUERY: &str = "DELETE FROM beatmaps WHERE id = ?1";
+
+        let deleted = self.conn.get().unwrap().execute(QUERY, [id])?;
+
+        // Rows are shifted now so cache is no longer valid
+        self.cache.lock().unwrap().clear();
+
+        Ok(deleted)
+    }
+
+    /// Deletes every beatmap that is located inside `dir`
+    pub fn delete_beatmaps_in_directory(&self, dir: impl AsRef<Path>) -> Result<usize, rusqlite::Error> {
+        const QUERY: &str = "DELETE FROM beatmaps WHERE substr(path, 1, length(?1)) = ?1";
+
+        let deleted = self.conn.get().unwrap().execute(QUERY, [Self::directory_prefix(dir)])?;
+
+        self.cache.lock().unwrap().clear();
+
+        Ok(deleted)
+    }
+
+    /// Paths are stored as absolute ones, so making sure the prefix
+    /// will match them and won't match `dir (1)` like directories
+    fn directory_prefix(dir: impl AsRef<Path>) -> String {
+        let dir = path::absolute(dir.as_ref()).unwrap_or(dir.as_ref().to_path_buf());
+
+        format!("{}{}", dir.display(), path::MAIN_SEPARATOR)
+    }
+
+    pub fn collections(&self) -> Vec<DbCollection> {
+        const QUERY: &str = "
+            SELECT
+                collections.id,
+                collections.name,
+                COUNT(DISTINCT collection_beatmaps.hash),
+                COUNT(DISTINCT beatmaps.hash)
+            FROM collections
+            LEFT JOIN collection_beatmaps ON collection_beatmaps.collection_id = collections.id
+            LEFT JOIN beatmaps ON beatmaps.hash = collection_beatmaps.hash
+            GROUP BY collections.id
+            ORDER BY collections.position ASC, collections.name ASC
+        ";
+
+        let conn = self.conn.get().unwrap();
+        let mut stmt = conn.prepare(QUERY).unwrap();
+
+        let rows = stmt.query_map([], |row| {
+            Ok(DbCollection {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                beatmaps: row.get(2)?,
+                resolved: row.get(3)?,
+            })
         }).unwrap();
 
-        let mut lock = self.cache.lock().unwrap();
+        rows.filter_map(|x| x.ok()).collect()
+    }
+
+    /// Creates an empty collection at the end of the list,
+    /// fails if the name is already taken
+    pub fn create_collection(&self, name: &str) -> Result<i64, rusqlite::Error> {
+        const QUERY: &str = "
+            INSERT INTO collections (name, position)
+            VALUES (?1, (SELECT COALESCE(MAX(position), 0) + 1 FROM collections))
+        ";
+
+        let conn = self.conn.get().unwrap();
+        conn.execute(QUERY, [name])?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn rename_collection(&self, id: i64, name: &str) -> Result<(), rusqlite::Error> {
+        const QUERY: &str = "UPDATE collections SET name = ?2 WHERE id = ?1";
+
+        self.conn.get().unwrap().execute(QUERY, params![id, name])?;
+
+        Ok(())
+    }
+
+    /// Deletes the collection with its md5s, beatmaps themselves stay
+    pub fn delete_collection(&self, id: i64) -> Result<(), rusqlite::Error> {
+        let mut conn = self.conn.get().unwrap();
+        let tx = conn.transaction()?;
+
+        // Foreign keys are not enforced, so no cascade
+        tx.execute("DELETE FROM collection_beatmaps WHERE collection_id = ?1", [id])?;
+        tx.execute("DELETE FROM collections WHERE id = ?1", [id])?;
+
+        tx.commit()
+    }
+
+    /// Returns `false` if the md5 was already in the collection
+    pub fn add_to_collection(&self, id: i64, hash: &str) -> Result<bool, rusqlite::Error> {
+        const QUERY: &str = "INSERT OR IGNORE INTO collection_beatmaps (collection_id, hash) VALUES (?1, ?2)";
+
+        let inserted = self.conn.get().unwrap().execute(QUERY, params![id, hash])?;
+
+        Ok(inserted > 0)
+    }
+
+    /// Returns `false` if the md5 wasn't in the collection
+    pub fn remove_from_collection(&self, id: i64, hash: &str) -> Result<bool, rusqlite::Error> {
+        const QUERY: &str = "DELETE FROM collection_beatmaps WHERE collection_id = ?1 AND hash = ?2";
+
+        let deleted = self.conn.get().unwrap().execute(QUERY, params![id, hash])?;
+
+        Ok(deleted > 0)
+    }
+
+    /// Collections are merged by name and scores are skipped if their
+    /// replay md5 is already known, returns amount of new scores
+    pub fn import_stable(
+        &self,
+        collections: &[StableCollection],
+        scores: &[StableScore],
+    ) -> Result<usize, rusqlite::Error> {
+        const INSERT_COLLECTION: &str = "
+            INSERT OR IGNORE INTO collections (name, position)
+            VALUES (?1, (SELECT COALESCE(MAX(position), 0) + 1 FROM collections))
+        ";
+        const SELECT_COLLECTION: &str = "SELECT id FROM collections WHERE name = ?1";
+        const INSERT_HASH: &str = "INSERT OR IGNORE INTO collection_beatmaps (collection_id, hash) VALUES (?1, ?2)";
+        const INSERT_SCORE: &str = "
+            INSERT OR IGNORE INTO scores
+            (hash, replay_hash, player, mode, score, max_combo, x300, x100, x50, geki, katu, miss, perfect, mods, timestamp, imported)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, 1)
+        ";
+
+        let mut conn = self.conn.get().unwrap();
+        let tx = conn.transaction()?;
+
+        for collection in collections {
+            tx.execute(INSERT_COLLECTION, [&collection.name])?;
+            let id: i64 = tx.query_row(SELECT_COLLECTION, [&collection.name], |row| row.get(0))?;
+
+            for hash in &collection.hashes {
+                tx.execute(INSERT_HASH, params![id, hash])?;
+            }
+        }
+
+        let mut inserted = 0;
+
+        for score in scores {
+            // Scores without a replay md5 can't be told apart
+            let replay_hash = Some(&score.replay_hash).filter(|x| !x.is_empty());
 
-        lock.clear();
-        for row in rows {
-            lock.push(row.unwrap().into());
+            inserted += tx.execute(INSERT_SCORE, params![
+                score.beatmap_hash,
+                replay_hash,
+                score.player,
+                score.mode,
+                score.score,
+                score.max_combo,
+                score.x300,
+                score.x100,
+                score.x50,
+                score.geki,
+                score.katu,
+                score.miss,
+                score.perfect,
+                score.mods,
+                score.timestamp,
+            ])?;
+        }
+
+        tx.commit()?;
+
+        Ok(inserted)
+    }
+
+    /// Stores a play made in rosu!. Replay is kept only for the best
+    /// local score of the beatmap, so the previous best loses its
+    /// replay in the same transaction. Returns `true` if it's the new best
+    pub fn store_local_score(&self, score: &LocalScore, replay: &[u8]) -> Result<bool, rusqlite::Error> {
+        const SELECT_BEST: &str = "
+            SELECT score, max_combo FROM scores
+            WHERE hash = ?1 AND imported = 0 AND replay IS NOT NULL
+            ORDER BY score DESC, max_combo DESC
+            LIMIT 1
+        ";
+        const INSERT_SCORE: &str = "
+            INSERT INTO scores
+            (hash, replay_hash, player, mode, score, max_combo, x300, x100, x50, geki, katu, miss, perfect, mods, timestamp, imported, progression)
+            VALUES (?1, ?2, ?3, 0, ?4, ?5, ?6, ?7, ?8, 0, 0, ?9, ?10, ?11, ?12, 0, ?13)
+        ";
+        const CLEAR_REPLAY: &str = "UPDATE scores SET replay = NULL WHERE hash = ?1 AND imported = 0";
+        const SET_REPLAY: &str = "UPDATE scores SET replay = ?1 WHERE id = ?2";
+
+        let mut conn = self.conn.get().unwrap();
+        let tx = conn.transaction()?;
+
+        let best: Option<(i64, i64)> = tx.query_row(SELECT_BEST, [&score.beatmap_hash], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        }).optional()?;
+
+        tx.execute(INSERT_SCORE, params![
+            score.beatmap_hash,
+            score.replay_hash,
+            score.player,
+            score.score,
+            score.max_combo,
+            score.x300,
+            score.x100,
+            score.x50,
+            score.miss,
+            score.perfect,
+            score.mods,
+            score.timestamp,
+            score.progression,
+        ])?;
+
+        let id = tx.last_insert_rowid();
+
+        // Ties keep the older replay
+        let is_best = match best {
+            Some(best) => (score.score, score.max_combo as i64) > best,
+            None => true,
+        };
+
+        if is_best {
+            tx.execute(CLEAR_REPLAY, [&score.beatmap_hash])?;
+            tx.execute(SET_REPLAY, params![replay, id])?;
+        }
+
+        tx.commit()?;
+
+        Ok(is_best)
+    }
+
+    /// Replay of the best local score of the beatmap with `hash`
+    pub fn best_score_replay(&self, hash: &str) -> Option<Vec<u8>> {
+        const QUERY: &str = "
+            SELECT replay FROM scores
+            WHERE hash = ?1 AND imported = 0 AND replay IS NOT NULL
+            ORDER BY score DESC, max_combo DESC
+            LIMIT 1
+        ";
+
+        self.conn.get().unwrap()
+            .query_row(QUERY, [hash], |row| row.get(0))
+            .ok()
+    }
+
+    /// Progression of the best local score of the beatmap with `hash`,
+    /// `None` if it was never played or the best play predates the column
+    pub fn best_score_progression(&self, hash: &str) -> Option<Vec<u8>> {
+        const QUERY: &str = "
+            SELECT progression FROM scores
+            WHERE hash = ?1 AND imported = 0
+            ORDER BY score DESC, max_combo DESC, id ASC
+            LIMIT 1
+        ";
+
+        self.conn.get().unwrap()
+            .query_row(QUERY, [hash], |row| row.get::<_, Option<Vec<u8>>>(0))
+            .ok()
+            .flatten()
+    }
+
+    /// Md5s referenced by collections or scores that
+    /// don't have a beatmap in the database yet
+    pub fn unresolved_hashes(&self) -> Vec<String> {
+        const QUERY: &str = "
+            SELECT hash FROM collection_beatmaps
+            UNION
+            SELECT hash FROM scores
+            EXCEPT
+            SELECT hash FROM beatmaps
+        ";
+
+        let conn = self.conn.get().unwrap();
+        let mut stmt = conn.prepare(QUERY).unwrap();
+
+        let rows = stmt.query_map([], |row| row.get(0)).unwrap();
+
+        rows.filter_map(|x| x.ok()).collect()
+    }
+
+    /// Adds plays to the stats of the local days they were
+    /// started at, all of them in a single transaction
+    pub fn record_plays(&self, plays: &[PlayRecord]) -> Result<(), rusqlite::Error> {
+        const QUERY: &str = "
+            INSERT INTO session_stats
+            (day, plays_started, plays_completed, retries, play_time_ms, x300, x100, x50, miss)
+            VALUES (date(?1, 'unixepoch', 'localtime'), 1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT(day) DO UPDATE SET
+                plays_started = plays_started + excluded.plays_started,
+                plays_completed = plays_completed + excluded.plays_completed,
+                retries = retries + excluded.retries,
+                play_time_ms = play_time_ms + excluded.play_time_ms,
+                x300 = x300 + excluded.x300,
+                x100 = x100 + excluded.x100,
+                x50 = x50 + excluded.x50,
+                miss = miss + excluded.miss
+        ";
+
+        let mut conn = self.conn.get().unwrap();
+        let tx = conn.transaction()?;
+
+        for play in plays {
+            tx.execute(QUERY, params![
+                play.started_at,
+                play.completed,
+                play.retry,
+                play.play_time_ms,
+                play.x300,
+                play.x100,
+                play.x50,
+                play.miss,
+            ])?;
+        }
+
+        tx.commit()
+    }
+
+    /// Stats of the local day `at` unix seconds belongs to,
+    /// zeroes if nothing was played that day
+    pub fn day_stats(&self, at: i64) -> DayStats {
+        const QUERY: &str = "
+            SELECT plays_started, plays_completed, retries, play_time_ms, x300, x100, x50, miss
+            FROM session_stats
+            WHERE day = date(?1, 'unixepoch', 'localtime')
+        ";
+
+        let stats = self.conn.get().unwrap().query_row(QUERY, [at], |row| {
+            Ok(DayStats {
+                plays_started: row.get(0)?,
+                plays_completed: row.get(1)?,
+                retries: row.get(2)?,
+                play_time_ms: row.get(3)?,
+                x300: row.get(4)?,
+                x100: row.get(5)?,
+                x50: row.get(6)?,
+                miss: row.get(7)?,
+            })
+        });
+
+        match stats {
+            Ok(stats) => stats,
+            Err(rusqlite::Error::QueryReturnedNoRows) => DayStats::default(),
+            Err(e) => {
+                tracing::error!("selecting day stats error: {e}");
+                DayStats::default()
+            },
+        }
+    }
+
+    /// Only the latest selection is kept
+    pub fn save_selection(&self, selection: &SavedSelection) -> Result<(), rusqlite::Error> {
+        const QUERY: &str = "
+            INSERT OR REPLACE INTO saved_selection (id, hash, collection, search)
+            VALUES (0, ?1, ?2, ?3)
+        ";
+
+        self.conn.get().unwrap().execute(QUERY, params![
+            selection.hash,
+            selection.collection,
+            selection.search,
+        ])?;
+
+        Ok(())
+    }
+
+    pub fn saved_selection(&self) -> Option<SavedSelection> {
+        const QUERY: &str = "SELECT hash, collection, search FROM saved_selection WHERE id = 0";
+
+        let selection = self.conn.get().unwrap().query_row(QUERY, [], |row| {
+            Ok(SavedSelection {
+                hash: row.get(0)?,
+                collection: row.get(1)?,
+                search: row.get(2)?,
+            })
+        });
+
+        match selection {
+            Ok(selection) => Some(selection),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => {
+                tracing::error!("selecting saved selection error: {e}");
+                None
+            },
         }
     }
 
@@ -289,4 +1440,100 @@ impl OsuDatabase {
         // Free to clone because Arc
         lock.get(current).cloned()
     }
+
+    /// md5 of `bytes` that were read from `path`. Stored along with
+    /// modification time and size of the file, so backgrounds and audio
+    /// are hashed only once until they are changed
+    pub fn file_hash(&self, path: &Path, bytes: &[u8]) -> md5::Digest {
+        let Some((modified, size)) = file_stamp(path) else {
+            return md5::compute(bytes);
+        };
+
+        let path = path.to_string_lossy();
+        let conn = self.conn.get().unwrap();
+
+        const SELECT: &str = "SELECT hash FROM file_hashes WHERE path = ?1 AND modified = ?2 AND size = ?3";
+
+        let cached = conn.query_row(SELECT, params![path, modified, size], |row| row.get::<_, Vec<u8>>(0))
+            .ok()
+            .and_then(|x| <[u8; 16]>::try_from(x.as_slice()).ok());
+
+        if let Some(hash) = cached {
+            return md5::Digest(hash);
+        }
+
+        let hash = md5::compute(bytes);
+
+        const INSERT: &str = "
+            INSERT OR REPLACE INTO file_hashes (path, modified, size, hash)
+            VALUES (?1, ?2, ?3, ?4)
+        ";
+
+        if let Err(e) = conn.execute(INSERT, params![path, modified, size, &hash.0[..]]) {
+            tracing::error!("Failed to store hash of {path}: {e}");
+        }
+
+        hash
+    }
+}
+
+/// Modification time in ns and size, either changes when file is replaced
+fn file_stamp(path: &Path) -> Option<(i64, i64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+    Some((modified.as_nanos() as i64, metadata.len() as i64))
+}
+
+/// One condition per term and field filter, star filters
+/// are skipped since star ratings are not stored
+fn query_conditions(query: &SearchQuery, columns: &[&str], values: &mut Vec<Value>) -> Vec<String> {
+    let mut conditions = Vec::new();
+
+    for term in &query.terms {
+        let pattern = format!("%{}%", escape_like(term));
+
+        let any = columns.iter()
+            .map(|column| {
+                values.push(Value::Text(pattern.clone()));
+                format!("{column} LIKE ? ESCAPE '\\'")
+            })
+            .collect::<Vec<_>>()
+            .join(" OR ");
+
+        conditions.push(format!("({any})"));
+    }
+
+    for filter in &query.fields {
+        let value = escape_like(&filter.value);
+
+        values.push(Value::Text(match filter.exact {
+            true => value,
+            false => format!("%{value}%"),
+        }));
+
+        conditions.push(format!("{} LIKE ? ESCAPE '\\'", filter.field.column()));
+    }
+
+    conditions
+}
+
+/// `LIKE` is case insensitive, so exact matches go through it too
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Calculates a new position of the `index` after rows at
+/// `deleted` indexes were removed.
+///
+/// If `index` itself was deleted it keeps pointing to the row
+/// that took its place, clamped to the new total amount
+pub fn shift_index_after_delete(index: usize, deleted: &[usize], total: usize) -> usize {
+    let removed_before = deleted.iter().filter(|x| **x < index).count();
+
+    index
+        .saturating_sub(removed_before)
+        .min(total.saturating_sub(1))
 }
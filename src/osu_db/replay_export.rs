@@ -0,0 +1,264 @@
+use std::{fmt::Write as _, fs, io::{self, Write}, path::{Path, PathBuf}};
+
+use liblzma::{stream::{LzmaOptions, Stream}, write::XzEncoder};
+
+use crate::{math::OSU_COORDS_HEIGHT, osu_input::OsuInput, score::Score};
+
+use super::stable_import::{TICKS_PER_SECOND, UNIX_EPOCH_TICKS};
+
+/// Player name of local plays, there are no accounts
+pub const LOCAL_PLAYER: &str = "Player";
+
+/// Game version written into exported replays
+const REPLAY_VERSION: u32 = 20151228;
+
+/// Key bits of a replay frame, mouse buttons are set
+/// along with keys the same way stable does it
+const FRAME_K1: u32 = 1 | 4;
+const FRAME_K2: u32 = 2 | 8;
+
+const LZMA_PRESET: u32 = 6;
+
+/// Everything about the play that ends up in a .osr
+pub struct ReplayInfo<'a> {
+    pub beatmap_hash: &'a str,
+    pub player: &'a str,
+    pub score: &'a Score,
+    /// Inputs in playfield coordinates sorted by timestamps,
+    /// same as the processor records them
+    pub frames: &'a [OsuInput],
+    /// Unix seconds
+    pub timestamp: i64,
+}
+
+/// Encoded replay of a finished play, written to
+/// the disk only once the player asks for it
+#[derive(Debug, Clone)]
+pub struct ExportedReplay {
+    pub file_name: String,
+    pub data: Vec<u8>,
+}
+
+/// Little-endian writer of the .NET `BinaryWriter` types,
+/// counterpart of [`super::stable_import::StableReader`]
+#[derive(Default)]
+pub struct StableWriter {
+    data: Vec<u8>,
+}
+
+impl StableWriter {
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn i32(&mut self, value: i32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn i64(&mut self, value: i64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn uleb128(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                self.u8(byte);
+                return;
+            }
+
+            self.u8(byte | 0x80);
+        }
+    }
+
+    /// Empty strings are written as absent ones
+    pub fn string(&mut self, value: &str) {
+        if value.is_empty() {
+            self.u8(0x00);
+            return;
+        }
+
+        self.u8(0x0b);
+        self.uleb128(value.len() as u64);
+        self.bytes(value.as_bytes());
+    }
+
+    /// Unix seconds as .NET ticks
+    pub fn datetime(&mut self, timestamp: i64) {
+        self.i64(timestamp * TICKS_PER_SECOND + UNIX_EPOCH_TICKS);
+    }
+}
+
+/// Encodes the play as a .osr stable can open
+pub fn encode_replay(info: &ReplayInfo) -> io::Result<Vec<u8>> {
+    let _span = tracy_client::span!("replay_export::encode_replay");
+
+    let score = info.score;
+    let frames = encode_frames(info.frames, score.rules.hard_rock);
+    let compressed = compress(frames.as_bytes())?;
+
+    // Judgement counts and combo are shorts in the format
+    let short = |value: u32| value.min(u16::MAX as u32) as u16;
+
+    let mut writer = StableWriter::default();
+
+    writer.u8(0);
+    writer.u32(REPLAY_VERSION);
+    writer.string(info.beatmap_hash);
+    writer.string(info.player);
+    writer.string(&format!("{:x}", md5::compute(&compressed)));
+    writer.u16(short(score.x300));
+    writer.u16(short(score.x100));
+    writer.u16(short(score.x50));
+    writer.u16(0);
+    writer.u16(0);
+    writer.u16(short(score.miss));
+    writer.i32(score.points().min(i32::MAX as u32) as i32);
+    writer.u16(short(score.max_combo));
+    writer.bool(score.miss == 0);
+    writer.u32(score.rules.replay_mods());
+    // Life bar graph
+    writer.string("");
+    writer.datetime(info.timestamp);
+    writer.i32(compressed.len() as i32);
+    writer.bytes(&compressed);
+    // Online score id
+    writer.i64(0);
+
+    Ok(writer.into_inner())
+}
+
+/// `w|x|y|z` frames separated by commas, `w` is the time since
+/// the previous frame. Stable keeps Hard Rock frames as they
+/// were seen on the flipped playfield
+fn encode_frames(frames: &[OsuInput], hard_rock: bool) -> String {
+    let mut out = String::with_capacity(frames.len() * 16);
+    let mut last_ts = 0;
+
+    for (i, frame) in frames.iter().enumerate() {
+        // Rounded absolute timestamps, so errors don't add up
+        let ts = frame.ts.round() as i64;
+
+        let y = match hard_rock {
+            true => OSU_COORDS_HEIGHT as f64 - frame.pos.y,
+            false => frame.pos.y,
+        };
+
+        let keys = (frame.keys.k1 as u32 * FRAME_K1) | (frame.keys.k2 as u32 * FRAME_K2);
+
+        if i > 0 {
+            out.push(',');
+        }
+
+        let _ = write!(out, "{}|{}|{}|{}", ts - last_ts, frame.pos.x as f32, y as f32, keys);
+
+        last_ts = ts;
+    }
+
+    out
+}
+
+/// .osr uses the legacy .lzma container
+fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let options = LzmaOptions::new_preset(LZMA_PRESET)?;
+    let stream = Stream::new_lzma_encoder(&options)?;
+
+    let mut encoder = XzEncoder::new_stream(Vec::new(), stream);
+    encoder.write_all(data)?;
+
+    encoder.finish()
+}
+
+/// `title — player — timestamp.osr` with characters
+/// not allowed in file names replaced
+pub fn replay_file_name(title: &str, player: &str, timestamp: i64) -> String {
+    format!("{title} — {player} — {timestamp}.osr")
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Writes encoded replay into `dir`, creating it if needed
+pub fn save_replay(dir: impl AsRef<Path>, file_name: &str, data: &[u8]) -> io::Result<PathBuf> {
+    let _span = tracy_client::span!("replay_export::save_replay");
+
+    fs::create_dir_all(dir.as_ref())?;
+
+    let path = dir.as_ref().join(file_name);
+    fs::write(&path, data)?;
+
+    tracing::info!("Saved replay to {}", path.display());
+
+    Ok(path)
+}
+
+#[test]
+fn test_replay_header_roundtrip() {
+    use crate::osu_input::KeyboardState;
+    use super::stable_import::{parse_replay_mods, StableReader};
+
+    let mut score = Score::default();
+    score.x300 = 2;
+    score.max_combo = 2;
+    score.rules.hard_rock = true;
+
+    let frames = [
+        OsuInput { ts: 10.4, pos: (100.0, 50.0).into(), keys: KeyboardState { k1: true, k2: false }, hold: KeyboardState::empty(), generation: 0 },
+        OsuInput { ts: 25.6, pos: (101.5, 60.0).into(), keys: KeyboardState::empty(), hold: KeyboardState::empty(), generation: 0 },
+    ];
+
+    let data = encode_replay(&ReplayInfo {
+        beatmap_hash: "e2f3e496b1014c84c998be738887e315",
+        player: LOCAL_PLAYER,
+        score: &score,
+        frames: &frames,
+        timestamp: 1_700_000_000,
+    }).unwrap();
+
+    assert_eq!(parse_replay_mods(&data).unwrap(), score.rules.replay_mods());
+
+    let mut reader = StableReader::new(&data);
+    assert_eq!(reader.u8().unwrap(), 0);
+    assert_eq!(reader.u32().unwrap(), REPLAY_VERSION);
+    assert_eq!(reader.string().unwrap(), "e2f3e496b1014c84c998be738887e315");
+    assert_eq!(reader.string().unwrap(), LOCAL_PLAYER);
+    assert_eq!(reader.string().unwrap().len(), 32);
+    assert_eq!(reader.u16().unwrap(), 2);
+
+    // Second frame is 16ms after the first one, y is flipped for Hard Rock
+    assert_eq!(encode_frames(&frames, true), "10|100|334|5,16|101.5|324|0");
+}
+
+#[test]
+fn test_replay_file_name() {
+    assert_eq!(
+        replay_file_name("Artist - Title [Hard?]", "Player", 1_700_000_000),
+        "Artist - Title [Hard_] — Player — 1700000000.osr",
+    );
+}
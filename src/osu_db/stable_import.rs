@@ -0,0 +1,661 @@
+use std::{collections::HashSet, io, path::{Path, PathBuf}};
+
+use thiserror::Error;
+
+use super::OsuDatabase;
+
+pub const COLLECTION_DB: &str = "collection.db";
+pub const SCORES_DB: &str = "scores.db";
+pub const OSU_DB: &str = "osu!.db";
+
+/// Beatmap entries of osu!.db are prefixed with their size before this version
+const OSU_DB_ENTRY_SIZE_UNTIL: u32 = 20191106;
+
+/// Difficulty values are stored as floats and star ratings are
+/// present since this version, bytes and nothing before it
+const OSU_DB_FLOAT_DIFFICULTY_SINCE: u32 = 20140609;
+
+/// Online score id of scores.db is a long since this version
+const SCORES_DB_LONG_ID_SINCE: u32 = 20140721;
+
+/// Target Practice stores an extra double after the score
+const MOD_TARGET_PRACTICE: u32 = 1 << 23;
+
+/// .NET ticks of the unix epoch
+pub(super) const UNIX_EPOCH_TICKS: i64 = 621_355_968_000_000_000;
+pub(super) const TICKS_PER_SECOND: i64 = 10_000_000;
+
+#[derive(Debug, Error)]
+pub enum StableImportError {
+    #[error("reading {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    #[error("unexpected end of file at offset {0}")]
+    UnexpectedEof(usize),
+    #[error("invalid string marker {marker:#04x} at offset {offset}")]
+    InvalidStringMarker {
+        offset: usize,
+        marker: u8,
+    },
+    #[error("string length overflows at offset {0}")]
+    InvalidLength(usize),
+    #[error("invalid UTF-8 string at offset {0}")]
+    InvalidUtf8(usize),
+    #[error("invalid int-double pair marker {marker:#04x} at offset {offset}")]
+    InvalidPairMarker {
+        offset: usize,
+        marker: u8,
+    },
+    #[error("nothing to import, neither {COLLECTION_DB} nor {SCORES_DB} was found in {0}")]
+    NothingFound(PathBuf),
+    #[error(transparent)]
+    Database(#[from] rusqlite::Error),
+}
+
+/// Little-endian reader of the .NET `BinaryWriter` types used by stable
+pub struct StableReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> StableReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], StableImportError> {
+        let end = self.offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(StableImportError::UnexpectedEof(self.offset))?;
+
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], StableImportError> {
+        let mut out = [0; N];
+        out.copy_from_slice(self.take(N)?);
+
+        Ok(out)
+    }
+
+    pub fn skip(&mut self, len: usize) -> Result<(), StableImportError> {
+        self.take(len).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, StableImportError> {
+        Ok(self.take_array::<1>()?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, StableImportError> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, StableImportError> {
+        Ok(u16::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn i32(&mut self) -> Result<i32, StableImportError> {
+        Ok(i32::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, StableImportError> {
+        Ok(u32::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn i64(&mut self) -> Result<i64, StableImportError> {
+        Ok(i64::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn f32(&mut self) -> Result<f32, StableImportError> {
+        Ok(f32::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, StableImportError> {
+        Ok(f64::from_le_bytes(self.take_array()?))
+    }
+
+    /// Amount of entries that follows, negative counts are treated as empty
+    pub fn count(&mut self) -> Result<usize, StableImportError> {
+        Ok(self.i32()?.max(0) as usize)
+    }
+
+    pub fn uleb128(&mut self) -> Result<u64, StableImportError> {
+        let start = self.offset;
+        let mut value = 0u64;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.u8()?;
+
+            if shift >= 64 {
+                return Err(StableImportError::InvalidLength(start));
+            }
+
+            value |= ((byte & 0x7f) as u64) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// `0x00` is an absent string, `0x0b` is followed
+    /// by ULEB128 length and UTF-8 bytes
+    pub fn string(&mut self) -> Result<String, StableImportError> {
+        let offset = self.offset;
+
+        match self.u8()? {
+            0x00 => Ok(String::new()),
+            0x0b => {
+                let len = usize::try_from(self.uleb128()?)
+                    .map_err(|_| StableImportError::InvalidLength(offset))?;
+                let bytes = self.take(len)?;
+
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|_| StableImportError::InvalidUtf8(offset))
+            },
+            marker => Err(StableImportError::InvalidStringMarker { offset, marker }),
+        }
+    }
+
+    /// .NET ticks as unix seconds
+    pub fn datetime(&mut self) -> Result<i64, StableImportError> {
+        Ok((self.i64()? - UNIX_EPOCH_TICKS) / TICKS_PER_SECOND)
+    }
+
+    /// Star rating entry, value is a double or
+    /// a float in the newer versions
+    fn int_float_pair(&mut self) -> Result<(), StableImportError> {
+        let offset = self.offset;
+
+        match self.u8()? {
+            0x08 => self.skip(4)?,
+            marker => return Err(StableImportError::InvalidPairMarker { offset, marker }),
+        }
+
+        let offset = self.offset;
+
+        match self.u8()? {
+            0x0c => self.skip(4),
+            0x0d => self.skip(8),
+            marker => Err(StableImportError::InvalidPairMarker { offset, marker }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StableCollection {
+    pub name: String,
+    /// Beatmap md5s
+    pub hashes: Vec<String>,
+}
+
+/// Local score of scores.db, replay itself is not stored there
+#[derive(Debug, Clone, PartialEq)]
+pub struct StableScore {
+    pub mode: u8,
+    pub version: u32,
+    pub beatmap_hash: String,
+    pub player: String,
+    pub replay_hash: String,
+    pub x300: u16,
+    pub x100: u16,
+    pub x50: u16,
+    pub geki: u16,
+    pub katu: u16,
+    pub miss: u16,
+    pub score: i32,
+    pub max_combo: u16,
+    pub perfect: bool,
+    pub mods: u32,
+    /// Unix seconds
+    pub timestamp: i64,
+    pub online_id: i64,
+}
+
+/// Beatmap of osu!.db, only what is needed to tell
+/// where a missing beatmap lives in stable's Songs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StableBeatmap {
+    pub hash: String,
+    pub artist: String,
+    pub title: String,
+    pub version: String,
+    /// Relative to stable's Songs directory
+    pub folder: String,
+    pub file: String,
+    pub beatmap_id: i32,
+    pub beatmapset_id: i32,
+    pub mode: u8,
+}
+
+pub fn parse_collection_db(data: &[u8]) -> Result<Vec<StableCollection>, StableImportError> {
+    let _span = tracy_client::span!("stable_import::parse_collection_db");
+
+    let mut reader = StableReader::new(data);
+
+    let _version = reader.u32()?;
+    let count = reader.count()?;
+
+    let mut collections = Vec::new();
+
+    for _ in 0..count {
+        let name = reader.string()?;
+        let amount = reader.count()?;
+
+        let mut hashes = Vec::new();
+
+        for _ in 0..amount {
+            let hash = reader.string()?;
+
+            if !hash.is_empty() {
+                hashes.push(hash);
+            }
+        }
+
+        collections.push(StableCollection { name, hashes });
+    }
+
+    Ok(collections)
+}
+
+pub fn parse_scores_db(data: &[u8]) -> Result<Vec<StableScore>, StableImportError> {
+    let _span = tracy_client::span!("stable_import::parse_scores_db");
+
+    let mut reader = StableReader::new(data);
+
+    let _version = reader.u32()?;
+    let beatmaps = reader.count()?;
+
+    let mut scores = Vec::new();
+
+    for _ in 0..beatmaps {
+        let _beatmap_hash = reader.string()?;
+        let amount = reader.count()?;
+
+        for _ in 0..amount {
+            let mode = reader.u8()?;
+            let version = reader.u32()?;
+            let beatmap_hash = reader.string()?;
+            let player = reader.string()?;
+            let replay_hash = reader.string()?;
+            let x300 = reader.u16()?;
+            let x100 = reader.u16()?;
+            let x50 = reader.u16()?;
+            let geki = reader.u16()?;
+            let katu = reader.u16()?;
+            let miss = reader.u16()?;
+            let score = reader.i32()?;
+            let max_combo = reader.u16()?;
+            let perfect = reader.bool()?;
+            let mods = reader.u32()?;
+            let _life_bar = reader.string()?;
+            let timestamp = reader.datetime()?;
+            let _replay_length = reader.i32()?;
+
+            let online_id = if version >= SCORES_DB_LONG_ID_SINCE {
+                reader.i64()?
+            } else {
+                reader.i32()? as i64
+            };
+
+            if mods & MOD_TARGET_PRACTICE != 0 {
+                let _accuracy = reader.f64()?;
+            }
+
+            scores.push(StableScore {
+                mode,
+                version,
+                beatmap_hash,
+                player,
+                replay_hash,
+                x300,
+                x100,
+                x50,
+                geki,
+                katu,
+                miss,
+                score,
+                max_combo,
+                perfect,
+                mods,
+                timestamp,
+                online_id,
+            });
+        }
+    }
+
+    Ok(scores)
+}
+
+/// Mods of a .osr, its header is laid out the same way as scores.db entries
+pub fn parse_replay_mods(data: &[u8]) -> Result<u32, StableImportError> {
+    let mut reader = StableReader::new(data);
+
+    let _mode = reader.u8()?;
+    let _version = reader.u32()?;
+    let _beatmap_hash = reader.string()?;
+    let _player = reader.string()?;
+    let _replay_hash = reader.string()?;
+
+    // Judgements, score, max combo and perfect
+    reader.skip(6 * 2 + 4 + 2 + 1)?;
+
+    reader.u32()
+}
+
+pub fn parse_osu_db(data: &[u8]) -> Result<Vec<StableBeatmap>, StableImportError> {
+    let _span = tracy_client::span!("stable_import::parse_osu_db");
+
+    let mut reader = StableReader::new(data);
+
+    let version = reader.u32()?;
+    let _folders = reader.i32()?;
+    let _account_unlocked = reader.bool()?;
+    let _unlock_date = reader.i64()?;
+    let _player = reader.string()?;
+    let count = reader.count()?;
+
+    let mut beatmaps = Vec::new();
+
+    for _ in 0..count {
+        if version < OSU_DB_ENTRY_SIZE_UNTIL {
+            let _size = reader.i32()?;
+        }
+
+        let artist = reader.string()?;
+        let _artist_unicode = reader.string()?;
+        let title = reader.string()?;
+        let _title_unicode = reader.string()?;
+        let _creator = reader.string()?;
+        let difficulty = reader.string()?;
+        let _audio = reader.string()?;
+        let hash = reader.string()?;
+        let file = reader.string()?;
+
+        // Ranked status, object counts and modification date
+        reader.skip(1 + 2 * 3 + 8)?;
+
+        if version >= OSU_DB_FLOAT_DIFFICULTY_SINCE {
+            // AR, CS, HP, OD
+            reader.skip(4 * 4)?;
+        } else {
+            reader.skip(4)?;
+        }
+
+        let _slider_velocity = reader.f64()?;
+
+        if version >= OSU_DB_FLOAT_DIFFICULTY_SINCE {
+            // Star ratings of every mode
+            for _ in 0..4 {
+                for _ in 0..reader.count()? {
+                    reader.int_float_pair()?;
+                }
+            }
+        }
+
+        // Drain, total and preview time
+        reader.skip(4 * 3)?;
+
+        for _ in 0..reader.count()? {
+            // BPM, offset and inherited flag
+            reader.skip(8 + 8 + 1)?;
+        }
+
+        let beatmap_id = reader.i32()?;
+        let beatmapset_id = reader.i32()?;
+        let _thread_id = reader.i32()?;
+
+        // Grades of every mode, local offset and stack leniency
+        reader.skip(4 + 2 + 4)?;
+
+        let mode = reader.u8()?;
+        let _source = reader.string()?;
+        let _tags = reader.string()?;
+        let _online_offset = reader.u16()?;
+        let _font = reader.string()?;
+        let _unplayed = reader.bool()?;
+        let _last_played = reader.i64()?;
+        let _osz2 = reader.bool()?;
+        let folder = reader.string()?;
+        let _last_checked = reader.i64()?;
+
+        // Ignore sound, skin, storyboard, video and visual override
+        reader.skip(5)?;
+
+        if version < OSU_DB_FLOAT_DIFFICULTY_SINCE {
+            let _unknown = reader.u16()?;
+        }
+
+        let _last_modified = reader.i32()?;
+        let _mania_scroll_speed = reader.u8()?;
+
+        beatmaps.push(StableBeatmap {
+            hash,
+            artist,
+            title,
+            version: difficulty,
+            folder,
+            file,
+            beatmap_id,
+            beatmapset_id,
+            mode,
+        });
+    }
+
+    Ok(beatmaps)
+}
+
+/// Everything found in a stable installation, parsed
+/// but not written to our database yet
+#[derive(Debug, Default, Clone)]
+pub struct StableData {
+    pub dir: PathBuf,
+    pub collections: Vec<StableCollection>,
+    pub scores: Vec<StableScore>,
+    pub beatmaps: Vec<StableBeatmap>,
+}
+
+impl StableData {
+    /// Reads whatever databases exist in the stable `dir`,
+    /// osu!.db is optional and only used for the cross-check
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, StableImportError> {
+        let _span = tracy_client::span!("stable_import::load");
+
+        let dir = dir.as_ref();
+
+        let collections = read_optional(&dir.join(COLLECTION_DB))?;
+        let scores = read_optional(&dir.join(SCORES_DB))?;
+
+        if collections.is_none() && scores.is_none() {
+            return Err(StableImportError::NothingFound(dir.to_path_buf()));
+        }
+
+        let beatmaps = match read_optional(&dir.join(OSU_DB))? {
+            Some(data) => match parse_osu_db(&data) {
+                Ok(beatmaps) => beatmaps,
+                Err(e) => {
+                    tracing::warn!("Skipping {OSU_DB} cross-check: {e}");
+                    Vec::new()
+                },
+            },
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            collections: collections.as_deref().map(parse_collection_db).transpose()?.unwrap_or_default(),
+            scores: scores.as_deref().map(parse_scores_db).transpose()?.unwrap_or_default(),
+            beatmaps,
+        })
+    }
+
+    /// Stable's Songs directory, where beatmaps that are
+    /// missing locally can be scanned from
+    pub fn songs_dir(&self) -> PathBuf {
+        self.dir.join("Songs")
+    }
+
+    /// Writes collections and scores in a single transaction,
+    /// importing the same data twice doesn't duplicate anything
+    pub fn import(&self, db: &OsuDatabase) -> Result<StableImportSummary, StableImportError> {
+        let _span = tracy_client::span!("stable_import::import");
+
+        let scores = db.import_stable(&self.collections, &self.scores)?;
+
+        let unresolved: HashSet<String> = db.unresolved_hashes().into_iter().collect();
+
+        let in_stable_songs = self.beatmaps
+            .iter()
+            .filter(|x| unresolved.contains(&x.hash))
+            .count();
+
+        let summary = StableImportSummary {
+            collections: self.collections.len(),
+            scores,
+            unresolved: unresolved.len(),
+            in_stable_songs,
+        };
+
+        tracing::info!("Imported from {}: {summary:?}", self.dir.display());
+
+        Ok(summary)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StableImportSummary {
+    pub collections: usize,
+    /// Newly inserted scores, already imported ones are skipped
+    pub scores: usize,
+    /// Referenced md5s that are not in the database yet
+    pub unresolved: usize,
+    /// Unresolved md5s that stable's Songs directory has
+    pub in_stable_songs: usize,
+}
+
+fn read_optional(path: &Path) -> Result<Option<Vec<u8>>, StableImportError> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(StableImportError::Io { path: path.to_path_buf(), source }),
+    }
+}
+
+#[test]
+fn test_stable_reader_strings() {
+    // 300 bytes long string needs two ULEB128 bytes
+    let long = "a".repeat(300);
+    let mut data = vec![0x00, 0x0b, 0xac, 0x02];
+    data.extend_from_slice(long.as_bytes());
+    data.extend_from_slice(&[0x0b, 0x02, 0xd0, 0xb9]);
+
+    let mut reader = StableReader::new(&data);
+
+    assert_eq!(reader.string().unwrap(), "");
+    assert_eq!(reader.string().unwrap(), long);
+    assert_eq!(reader.string().unwrap(), "й");
+    assert!(matches!(reader.string(), Err(StableImportError::UnexpectedEof(_))));
+
+    assert!(matches!(
+        StableReader::new(&[0x0a]).string(),
+        Err(StableImportError::InvalidStringMarker { offset: 0, marker: 0x0a })
+    ));
+
+    // Length is longer than the data
+    assert!(matches!(
+        StableReader::new(&[0x0b, 0x05, b'a']).string(),
+        Err(StableImportError::UnexpectedEof(2))
+    ));
+
+    assert!(matches!(
+        StableReader::new(&[0x0b, 0x01, 0xff]).string(),
+        Err(StableImportError::InvalidUtf8(0))
+    ));
+
+    // Unterminated ULEB128
+    assert!(StableReader::new(&[0xff; 12]).uleb128().is_err());
+}
+
+#[test]
+fn test_parse_collection_db() {
+    let data = include_bytes!("../../tests/data/stable/collection.db");
+    let collections = parse_collection_db(data).unwrap();
+
+    assert_eq!(collections.len(), 2);
+    assert_eq!(collections[0].name, "Farm");
+    assert_eq!(collections[0].hashes, ["e2f3e496b1014c84c998be738887e315", "0123456789abcdef0123456789abcdef"]);
+    assert_eq!(collections[1].name, "Пусто");
+    assert!(collections[1].hashes.is_empty());
+
+    assert!(parse_collection_db(&data[..data.len() - 1]).is_err());
+}
+
+#[test]
+fn test_parse_scores_db() {
+    let data = include_bytes!("../../tests/data/stable/scores.db");
+    let scores = parse_scores_db(data).unwrap();
+
+    assert_eq!(scores.len(), 3);
+
+    // Old score with an int online id
+    assert_eq!(scores[0].version, 20131110);
+    assert_eq!(scores[0].online_id, 12345);
+    assert_eq!(scores[0].player, "peppy");
+    assert_eq!((scores[0].x300, scores[0].x100, scores[0].x50, scores[0].miss), (100, 5, 1, 2));
+    assert_eq!(scores[0].timestamp, 1_500_000_000);
+
+    // Target practice has an extra double
+    assert_eq!(scores[1].mods & MOD_TARGET_PRACTICE, MOD_TARGET_PRACTICE);
+    assert_eq!(scores[1].online_id, 4_000_000_000);
+    assert!(scores[1].perfect);
+
+    assert_eq!(scores[2].beatmap_hash, "0123456789abcdef0123456789abcdef");
+    assert_eq!(scores[2].max_combo, 321);
+}
+
+#[test]
+fn test_parse_replay_mods() {
+    let data = include_bytes!("../../tests/data/gameplay/single_hit_circle1.osr");
+    assert_eq!(parse_replay_mods(data).unwrap(), 0);
+
+    let mut data = vec![0x00];
+    data.extend_from_slice(&20240101u32.to_le_bytes());
+    data.extend_from_slice(&[0x0b, 0x01, b'a', 0x00, 0x00]);
+    data.extend_from_slice(&[0; 6 * 2 + 4 + 2 + 1]);
+    data.extend_from_slice(&(1u32 << 4).to_le_bytes());
+    assert_eq!(parse_replay_mods(&data).unwrap(), 1 << 4);
+
+    assert!(matches!(parse_replay_mods(&data[..10]), Err(StableImportError::UnexpectedEof(_))));
+}
+
+#[test]
+fn test_parse_osu_db() {
+    let data = include_bytes!("../../tests/data/stable/osu!.db");
+    let beatmaps = parse_osu_db(data).unwrap();
+
+    assert_eq!(beatmaps.len(), 2);
+
+    // Double star ratings
+    assert_eq!(beatmaps[0].hash, "e2f3e496b1014c84c998be738887e315");
+    assert_eq!(beatmaps[0].folder, "1 Artist - Title");
+    assert_eq!(beatmaps[0].beatmap_id, 75);
+
+    // Float star ratings of the newer versions
+    assert_eq!(beatmaps[1].hash, "0123456789abcdef0123456789abcdef");
+    assert_eq!(beatmaps[1].title, "Missing");
+    assert_eq!(beatmaps[1].file, "missing.osu");
+    assert_eq!(beatmaps[1].mode, 0);
+}
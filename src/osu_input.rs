@@ -41,6 +41,9 @@ pub struct OsuInput {
 
     /// Is current input is holding one
     pub hold: KeyboardState,
+
+    /// Attempt the input was stored during, set by the processor
+    pub generation: u32,
 }
 
 impl OsuInput {
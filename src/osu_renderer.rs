@@ -1,6 +1,6 @@
-use std::{mem::size_of, ops::RangeInclusive, sync::{Arc, RwLock}};
+use std::{mem::size_of, ops::{Range, RangeInclusive}, sync::{Arc, RwLock}};
 
-use cgmath::Vector2;
+use cgmath::{Matrix3, Vector2};
 use smallvec::SmallVec;
 use wgpu::{
     util::DeviceExt, BindGroup, BindingType, BufferUsages, Extent3d,
@@ -8,13 +8,48 @@ use wgpu::{
     TextureUsages, TextureView, TextureViewDimension,
 };
 use winit::dpi::PhysicalSize;
+#[cfg(feature = "render-stats")]
+use crate::render_stats::{RenderStats, SLIDER_TEXTURE_BYTES};
 use crate::{
-    camera::Camera, config::Config, graphics::Graphics, hit_circle_instance::{ApproachCircleInstance, HitCircleInstance}, hit_objects::{self, hit_window::HitWindow, slider::{SliderRender, SliderResultState}, Hit, Object, CIRCLE_FADEOUT_TIME, CIRCLE_SCALEOUT_MAX, JUDGMENTS_FADEOUT_TIME, REVERSE_ARROW_FADEIN, REVERSE_ARROW_FADEOUT, SLIDER_FADEOUT_TIME}, math::{calc_fade_alpha, calc_hitcircle_diameter, calc_playfield, calc_playfield_scale_factor, calc_progress, lerp}, quad_instance::QuadInstance, quad_renderer::QuadRenderer, rgb::Rgb, skin_manager::SkinManager, slider_instance::SliderInstance, texture::{AtlasTexture, DepthTexture, Texture}, vertex::Vertex
+    camera::Camera, config::Config, graphics::Graphics, hit_circle_instance::{ApproachCircleInstance, HitCircleInstance}, hit_objects::{self, hit_window::HitWindow, slider::{SliderRender, SliderResultState}, Hit, Object, CIRCLE_FADEOUT_TIME, CIRCLE_SCALEOUT_MAX, JUDGMENTS_FADEOUT_TIME, REVERSE_ARROW_FADEIN, REVERSE_ARROW_FADEOUT}, math::{calc_circle_approach, calc_fade_alpha, calc_hidden_alpha, calc_hidden_body_alpha, calc_slider_approach_alpha, calc_slider_body_alpha, calc_adjusted_playfield, calc_hitcircle_diameter, calc_playfield_scale_factor, calc_progress, debug_assert_finite, lerp, SLIDER_BODY_MAX_ALPHA}, quad_instance::QuadInstance, quad_renderer::QuadRenderer, rgb::Rgb, skin_manager::{SkinManager, JUDGEMENT_RING_INDEX}, slider_instance::SliderInstance, texture::{AtlasTexture, DepthTexture, Texture}, vertex::{AtlasQuadVertex, Vertex}
 };
 
+// Blending convention: textures and shader outputs are straight
+// (non-premultiplied) alpha. Colour is blended with SrcAlpha and
+// OneMinusSrcAlpha, alpha with `BlendComponent::OVER`, so destination
+// alpha never drops below what was drawn underneath (background, video)
+// and later draws composite against an opaque framebuffer
+
+/// Parts of a hit circle, only the circle itself
+/// is tinted with the combo colour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitCircleLayer {
+    Circle,
+    Number,
+    Overlay,
+}
+
+impl HitCircleLayer {
+    /// Back to front, `overlay_above_number` is
+    /// `HitCircleOverlayAboveNumber` of skin.ini
+    pub fn draw_order(overlay_above_number: bool) -> [Self; 3] {
+        match overlay_above_number {
+            true => [Self::Circle, Self::Number, Self::Overlay],
+            false => [Self::Circle, Self::Overlay, Self::Number],
+        }
+    }
+}
+
 static SLIDER_SCALE: f32 = 2.0;
 pub const QUAD_INDECIES: &[u16] = &[0, 1, 2, 0, 2, 3];
 
+/// Judgement quad size in osu!pixels
+const JUDGEMENT_SIZE: f32 = 50.0;
+const JUDGEMENT_RING_SIZE: f32 = 64.0;
+/// How much bigger outline silhouettes are in high contrast mode
+const JUDGEMENT_OUTLINE: f32 = 6.0;
+const JUDGEMENT_OUTLINE_TINT: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
 // TODO: Move it outta her
 macro_rules! buffer_write_or_init {
     ($queue:expr, $device:expr, $buffer:expr, $data:expr, $t: ty) => {{
@@ -59,14 +94,30 @@ pub struct JudgementsEntry {
 pub struct SliderToScreenEntry {
     texture: Arc<Texture>,
     buffer: Arc<wgpu::Buffer>,
-    follow_circle: Option<u32>,
-    ticks: Vec<usize>,
-    reverse_arrow: Option<Vec<u32>>
+    /// Instances of this slider's follow circle
+    /// inside of `follow_points_instance_data`
+    follow_circle: Option<Range<u32>>,
+    /// Range of `slider_tick_indexes`
+    ticks: Range<usize>,
+    /// Instances of this slider's reverse arrows
+    /// inside of `reverse_arrows_instance_data`
+    reverse_arrows: Range<u32>,
 }
 
-pub struct OsuRenderer<'or> {
+/// Slider body which is prepared but not yet rendered to its texture.
+/// All bakes are recorded into the same encoder as objects,
+/// so each one holds own instance buffer and camera
+struct SliderBake {
+    view: TextureView,
+    depth_texture: DepthTexture,
+    camera: Camera,
+    instance_buffer: wgpu::Buffer,
+    instances: u32,
+}
+
+pub struct OsuRenderer {
     // Graphics State
-    graphics: Arc<Graphics<'or>>,
+    graphics: Arc<Graphics>,
 
     config: Arc<RwLock<Config>>,
     skin_manager: Arc<RwLock<SkinManager>>,
@@ -82,18 +133,21 @@ pub struct OsuRenderer<'or> {
     // Camera
     camera: Camera,
 
-    // Purely for reusability
-    slider_texture_camera: Camera,
-
     // Approach circle
     approach_circle_pipeline: RenderPipeline,
     //approach_circle_texture: Texture,
     approach_circle_instance_buffer: wgpu::Buffer,
     approach_circle_instance_data: SmallVec<[ApproachCircleInstance; 32]>,
+    /// Approach circle instance of every prepared object, in render queue
+    /// order, so each one is drawn right after its own object
+    approach_circle_indexes: Vec<Option<u32>>,
 
     // quad textured + color
     quad_colored_pipeline: RenderPipeline,
 
+    // quad textured, overlays and combo numbers
+    hit_circle_overlay_pipeline: RenderPipeline,
+
     // Hit Circle
     hit_circle_pipeline: RenderPipeline,
     hit_circle_vertex_buffer: wgpu::Buffer,
@@ -102,14 +156,13 @@ pub struct OsuRenderer<'or> {
     hit_circle_instance_buffer: wgpu::Buffer,
 
     // Slider to texture
-    slider_instance_buffer: wgpu::Buffer,
     slider_instance_data: Vec<SliderInstance>,
     slider_pipeline: RenderPipeline,
-    slider_indecies: SmallVec<[u16; 16]>,
+    slider_indecies: Vec<u16>,
 
     slider_vertex_buffer: wgpu::Buffer,
     slider_index_buffer: wgpu::Buffer,
-    slider_verticies: SmallVec<[Vertex; 256]>,
+    slider_verticies: Vec<Vertex>,
 
     // Slider texture to screen
     slider_to_screen_verticies: [Vertex; 4],
@@ -123,17 +176,29 @@ pub struct OsuRenderer<'or> {
     follow_points_instance_buffer: wgpu::Buffer,
 
     // Slider body queue
-    slider_to_screen_textures: SmallVec<[SliderToScreenEntry; 32]>,
+    slider_to_screen_textures: Vec<SliderToScreenEntry>,
+
+    // Ends of tick instances inside of `slider_ticks_instance_data`,
+    // shared by every slider so they are not allocated per slider every frame
+    slider_tick_indexes: Vec<usize>,
+
+    // Slider reverse arrows, rotated towards the previous slide
+    reverse_arrows_instance_data: Vec<HitCircleInstance>,
+    reverse_arrows_instance_buffer: wgpu::Buffer,
+
+    // Slider textures waiting to be rendered
+    slider_bakes: Vec<SliderBake>,
 
     // Slider settings
     slider_settings_buffer: wgpu::Buffer,
     slider_settings_bind_group: BindGroup,
 
-    depth_texture: DepthTexture,
-
-    quad_debug: QuadRenderer<'or>,
+    /// Screen sized, created on the first use after a size change
+    depth_texture: Option<DepthTexture>,
+    /// Surface size screen sized resources were made for
+    surface_size: (u32, u32),
 
-    slider_reverse_arrow_quad: QuadRenderer<'or>,
+    quad_debug: QuadRenderer,
 
     quad_debug_instance_data: Vec<QuadInstance>,
     quad_debug_instance_data2: Vec<QuadInstance>,
@@ -146,18 +211,22 @@ pub struct OsuRenderer<'or> {
     /// Queue of judgements that needs to be rendered
     /// Should be cleared after everything inside is rendered
     judgements_queue: Vec<JudgementsEntry>,
+
+    /// Counters of the current frame
+    #[cfg(feature = "render-stats")]
+    stats: RenderStats,
 }
 
-impl<'or> OsuRenderer<'or> {
+impl OsuRenderer {
     pub fn new(
-        graphics: Arc<Graphics<'or>>, 
+        graphics: Arc<Graphics>, 
         config: Arc<RwLock<Config>>,
         skin_manager: Arc<RwLock<SkinManager>>
     ) -> Self {
         let config_lock = config.read().expect("failed to acquire config read lock");
 
         let (graphics_width, graphics_height) = graphics.get_surface_size();
-        let surface_config = graphics.get_surface_config();
+        let format = graphics.format();
 
         let hit_circle_shader = graphics
             .device
@@ -179,9 +248,6 @@ impl<'or> OsuRenderer<'or> {
             .device
             .create_shader_module(wgpu::include_wgsl!("shaders/slider_to_screen.wgsl"));
 
-        let depth_texture =
-            DepthTexture::new(&graphics, graphics_width, graphics_height, 1);
-
         let quad_verticies = Vertex::quad_centered(1.0, 1.0);
 
         let all_depth = None;
@@ -234,14 +300,6 @@ impl<'or> OsuRenderer<'or> {
             1.0,
         );
 
-        let slider_texture_camera = Camera::ortho(
-            &graphics,
-            0.0,
-            0.0,
-            0.0,
-            0.0
-        );
-
         let slider_settings_buffer = graphics
                 .device
                 .create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -307,18 +365,15 @@ impl<'or> OsuRenderer<'or> {
                         module: &approach_circle_shader,
                         entry_point: Some("fs_main"),
                         targets: &[Some(wgpu::ColorTargetState {
-                            format: surface_config.format,
+                            format,
+                            // Straight alpha OVER
                             blend: Some(wgpu::BlendState {
                                 color: wgpu::BlendComponent {
                                     src_factor: wgpu::BlendFactor::SrcAlpha,
                                     dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
                                     operation: wgpu::BlendOperation::Add,
                                 },
-                                alpha: wgpu::BlendComponent {
-                                    src_factor: wgpu::BlendFactor::SrcAlpha,
-                                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                                    operation: wgpu::BlendOperation::Add,
-                                },
+                                alpha: wgpu::BlendComponent::OVER,
                             }),
                             write_mask: wgpu::ColorWrites::ALL,
                         })],
@@ -372,7 +427,8 @@ impl<'or> OsuRenderer<'or> {
                         module: &hit_circle_shader,
                         entry_point: Some("fs_main"),
                         targets: &[Some(wgpu::ColorTargetState {
-                            format: surface_config.format,
+                            format,
+                            // Straight alpha OVER
                             blend: Some(wgpu::BlendState {
                                 color: wgpu::BlendComponent {
                                     src_factor: wgpu::BlendFactor::SrcAlpha,
@@ -432,7 +488,58 @@ impl<'or> OsuRenderer<'or> {
                         module: &quad_colored_shader,
                         entry_point: Some("fs_main"),
                         targets: &[Some(wgpu::ColorTargetState {
-                            format: surface_config.format,
+                            format,
+                            // Straight alpha OVER
+                            blend: Some(wgpu::BlendState {
+                                color: wgpu::BlendComponent {
+                                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                                    operation: wgpu::BlendOperation::Add,
+                                },
+                                alpha: wgpu::BlendComponent::OVER,
+                            }),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: all_depth.clone(),
+                    multisample: wgpu::MultisampleState {
+                        count: 1,
+                        mask: !0,
+                        alpha_to_coverage_enabled: false,
+                    },
+                    multiview: None,
+                });
+
+        // Same quads as `quad_colored_pipeline` but not tinted
+        let hit_circle_overlay_pipeline =
+            graphics
+                .device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("hit circle overlay render pipeline"),
+                    cache: None,
+                    layout: Some(&hit_circle_pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &quad_colored_shader,
+                        entry_point: Some("vs_main"),
+                        buffers: &[Vertex::desc(), HitCircleInstance::desc()],
+                        compilation_options: Default::default(),
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        compilation_options: Default::default(),
+                        module: &quad_colored_shader,
+                        entry_point: Some("fs_untinted"),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format,
+                            // Straight alpha OVER
                             blend: Some(wgpu::BlendState {
                                 color: wgpu::BlendComponent {
                                     src_factor: wgpu::BlendFactor::SrcAlpha,
@@ -464,7 +571,9 @@ impl<'or> OsuRenderer<'or> {
 
 
 
-        let (slider_verticies, slider_indecies) = Vertex::cone(5.0);
+        // Bakes are rendered with a scaled cone, radius
+        // matches the initial `hit_circle_diameter` of 1.0
+        let (slider_verticies, slider_indecies) = Vertex::cone(0.5 * SLIDER_SCALE);
         let slider_instance_data: Vec<SliderInstance> = Vec::with_capacity(10);
 
         let slider_vertex_buffer =
@@ -476,15 +585,6 @@ impl<'or> OsuRenderer<'or> {
                     usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
                 });
 
-        let slider_instance_buffer =
-            graphics
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("linear instance buffer"),
-                    contents: bytemuck::cast_slice(&slider_instance_data),
-                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-                });
-
         let slider_index_buffer =
             graphics
                 .device
@@ -524,7 +624,9 @@ impl<'or> OsuRenderer<'or> {
                         compilation_options: Default::default(),
                         entry_point: Some("fs_main"),
                         targets: &[Some(wgpu::ColorTargetState {
-                            format: surface_config.format,
+                            format,
+                            // Bake stores tint parameters as is, depth
+                            // test keeps only the closest cone per pixel
                             blend: None,
                             write_mask: wgpu::ColorWrites::ALL,
                         })],
@@ -596,6 +698,7 @@ impl<'or> OsuRenderer<'or> {
                         //&Texture::default_bind_group_layout(&graphics, 1),
                         &slider_to_screen_bind_group_layout,
                         &camera.bind_group_layout(),
+                        &slider_settings_bind_group_layout,
                     ],
                     push_constant_ranges: &[],
                 });
@@ -618,14 +721,17 @@ impl<'or> OsuRenderer<'or> {
                         module: &slider_to_screen_shader,
                         entry_point: Some("fs_main"),
                         targets: &[Some(wgpu::ColorTargetState {
-                            format: surface_config.format,
+                            format,
+                            // Straight alpha OVER. REPLACE would write slider alpha
+                            // into the framebuffer and everything drawn over sliders
+                            // would composite against a see-through destination
                             blend: Some(wgpu::BlendState {
                                 color: wgpu::BlendComponent {
                                     src_factor: wgpu::BlendFactor::SrcAlpha,
                                     dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
                                     operation: wgpu::BlendOperation::Add,
                                 },
-                                alpha: wgpu::BlendComponent::REPLACE,
+                                alpha: wgpu::BlendComponent::OVER,
                             }),
                             write_mask: wgpu::ColorWrites::ALL,
                         })],
@@ -683,8 +789,16 @@ impl<'or> OsuRenderer<'or> {
         let slider_ticks_instance_data = Vec::new();
         let slider_ticks_instance_buffer = quad_debug.create_instance_buffer();
 
-        let slider_reverse_arrow_quad = QuadRenderer::new(graphics.clone(), false);
-        slider_reverse_arrow_quad.resize_vertex_centered(10.0, 10.0);
+        let reverse_arrows_instance_data: Vec<HitCircleInstance> = Vec::new();
+
+        let reverse_arrows_instance_buffer =
+            graphics
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("reverse arrows instance buffer"),
+                    contents: bytemuck::cast_slice(&reverse_arrows_instance_data),
+                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                });
 
         drop(config_lock);
 
@@ -697,30 +811,35 @@ impl<'or> OsuRenderer<'or> {
             approach_circle_pipeline,
             approach_circle_instance_buffer,
             approach_circle_instance_data,
+            approach_circle_indexes: Vec::new(),
             hit_circle_pipeline,
             hit_circle_vertex_buffer,
             hit_circle_index_buffer,
             hit_circle_instance_data,
             hit_circle_instance_buffer,
-            depth_texture,
-            slider_instance_buffer,
+            depth_texture: None,
+            surface_size: (0, 0),
             slider_instance_data,
             slider_pipeline,
-            slider_indecies: slider_indecies.into(),
+            slider_indecies,
             slider_vertex_buffer,
             slider_index_buffer,
-            slider_verticies: slider_verticies.into(),
+            slider_verticies,
             slider_to_screen_verticies,
             slider_to_screen_vertex_buffer,
             slider_to_screen_render_pipeline,
             slider_to_screen_instance_buffer,
             slider_to_screen_instance_data,
-            slider_to_screen_textures: SmallVec::new(),
+            slider_to_screen_textures: Vec::new(),
+            slider_tick_indexes: Vec::new(),
+            reverse_arrows_instance_data,
+            reverse_arrows_instance_buffer,
             follow_points_instance_data,
             follow_points_instance_buffer,
             offsets: Vector2::new(0.0, 0.0),
             hit_circle_diameter: 1.0,
             quad_colored_pipeline,
+            hit_circle_overlay_pipeline,
             slider_settings_buffer,
             slider_settings_bind_group,
             quad_debug_instance_data,
@@ -728,10 +847,11 @@ impl<'or> OsuRenderer<'or> {
             quad_debug_instance_data2,
             quad_debug_buffer2,
             judgements_queue: Vec::new(),
+            #[cfg(feature = "render-stats")]
+            stats: RenderStats::default(),
             slider_ticks_instance_data,
             slider_ticks_instance_buffer,
-            slider_reverse_arrow_quad,
-            slider_texture_camera,
+            slider_bakes: Vec::new(),
             config,
             skin_manager,
         }
@@ -757,6 +877,7 @@ impl<'or> OsuRenderer<'or> {
         let _span = tracy_client::span!("osu_renderer::prepare judgements");
 
         let config = self.config.read().expect("failed to acquire read lock");
+        let judgements = config.quality.judgements(&config.judgements);
 
         for index in queue {
             let object = &objects[*index];
@@ -765,8 +886,8 @@ impl<'or> OsuRenderer<'or> {
                 hit_objects::ObjectKind::Circle(circle) => {
                     if let Some(hit_result) = &circle.hit_result {
                         let range = RangeInclusive::new(
-                            hit_result.at - config.judgements.total_time() as f64,
-                            hit_result.at + config.judgements.total_time() as f64
+                            hit_result.at - judgements.total_time() as f64,
+                            hit_result.at + judgements.total_time() as f64
                         );
 
                         if !range.contains(&time) {
@@ -776,13 +897,18 @@ impl<'or> OsuRenderer<'or> {
                         let alpha = calc_fade_alpha(
                             time,
                             hit_result.at,
-                            config.judgements.fade_in_ms,
-                            config.judgements.stay_on_screen_ms,
-                            config.judgements.fade_out_ms,
+                            judgements.fade_in_ms,
+                            judgements.stay_on_screen_ms,
+                            judgements.fade_out_ms,
                         );
 
+                        let center = Vector2::new(circle.pos.x as f64, circle.pos.y as f64);
+                        let nudge = circle.hit_offset()
+                            .map(|x| x * judgements.hit_position_nudge as f64)
+                            .unwrap_or(Vector2::new(0.0, 0.0));
+
                         let entry = JudgementsEntry{
-                            pos: Vector2::new(circle.pos.x as f64, circle.pos.y as f64),
+                            pos: center + nudge,
                             alpha: alpha as f32,
                             result: hit_result.result
                         };
@@ -796,15 +922,15 @@ impl<'or> OsuRenderer<'or> {
                     };
 
                     let head_range = RangeInclusive::new(
-                        hit_result.head.at - config.judgements.total_time() as f64,
-                        hit_result.head.at + config.judgements.total_time() as f64,
+                        hit_result.head.at - judgements.total_time() as f64,
+                        hit_result.head.at + judgements.total_time() as f64,
                     );
 
                     let slider_end_time = slider.start_time + slider.duration;
 
                     let tail_range = RangeInclusive::new(
-                         slider_end_time - config.judgements.total_time() as f64,
-                         slider_end_time + config.judgements.total_time() as f64,
+                         slider_end_time - judgements.total_time() as f64,
+                         slider_end_time + judgements.total_time() as f64,
                     );
                     
                     // Judgement for the head
@@ -812,13 +938,18 @@ impl<'or> OsuRenderer<'or> {
                         let head_alpha = calc_fade_alpha(
                             time,
                             hit_result.head.at,
-                            config.judgements.fade_in_ms,
-                            config.judgements.stay_on_screen_ms,
-                            config.judgements.fade_out_ms,
+                            judgements.fade_in_ms,
+                            judgements.stay_on_screen_ms,
+                            judgements.fade_out_ms,
                         );
 
+                        let center = Vector2::new(slider.pos.x as f64, slider.pos.y as f64);
+                        let nudge = slider.head_hit_offset()
+                            .map(|x| x * judgements.hit_position_nudge as f64)
+                            .unwrap_or(Vector2::new(0.0, 0.0));
+
                         let entry = JudgementsEntry {
-                            pos: Vector2::new(slider.pos.x as f64, slider.pos.y as f64),
+                            pos: center + nudge,
                             alpha: head_alpha as f32,
                             result: hit_result.head.result,
                         };
@@ -842,9 +973,9 @@ impl<'or> OsuRenderer<'or> {
                         let end_alpha = calc_fade_alpha(
                             time,
                             slider.start_time + slider.duration,
-                            config.judgements.fade_in_ms,
-                            config.judgements.stay_on_screen_ms,
-                            config.judgements.fade_out_ms,
+                            judgements.fade_in_ms,
+                            judgements.stay_on_screen_ms,
+                            judgements.fade_out_ms,
                         );
 
                         self.judgements_queue.push(JudgementsEntry {
@@ -860,6 +991,46 @@ impl<'or> OsuRenderer<'or> {
 
     }
 
+    /// Bakes textures of sliders that are about to appear so the cost
+    /// is spread across frames instead of landing on the first visible one.
+    ///
+    /// Does nothing when slider textures are not stored since
+    /// they are baked every frame anyway
+    pub fn bake_ahead(&mut self, time: f64, preempt: f32, objects: &mut [Object]) {
+        let _span = tracy_client::span!("osu_renderer::bake_ahead");
+
+        let (window, budget) = {
+            let config = self.config.read().expect("failed to acquire read lock");
+
+            if !config.store_slider_textures {
+                return;
+            }
+
+            (config.bake_ahead_ms as f64, config.quality.bake_ahead_per_frame(config.bake_ahead_per_frame) as usize)
+        };
+
+        let preempt = preempt as f64;
+
+        // Objects are sorted by start time, skipping already visible ones
+        let first = objects.partition_point(|x| x.start_time - preempt <= time);
+        let mut baked = 0;
+
+        for obj in &mut objects[first..] {
+            if baked >= budget || obj.start_time - preempt > time + window {
+                break;
+            }
+
+            if let hit_objects::ObjectKind::Slider(slider) = &mut obj.kind {
+                if slider.render.is_none() {
+                    self.prepare_and_render_slider_texture(slider);
+                    baked += 1;
+                }
+            }
+        }
+
+        tracy_client::plot!("sliders baked ahead per frame", baked as f64);
+    }
+
     pub fn clear_cached_slider_textures(&self, objects: &mut [Object]) {
         let _span = tracy_client::span!("osu_renderer::clear_cached_slider_textures");
         for obj in objects {
@@ -879,22 +1050,29 @@ impl<'or> OsuRenderer<'or> {
         preempt: f32,
         fadein: f32,
         queue: &[usize],
-        objects: &[Object],
+        objects: &mut [Object],
         hit_window: &HitWindow,
     ) {
         let _span = tracy_client::span!("osu_renderer::prepare objects");
 
+        // Every slider needs a texture to be drawn, normally they are baked
+        // by the caller but missing ones are baked here instead of skipping them
+        for current_index in queue.iter() {
+            if let hit_objects::ObjectKind::Slider(slider) = &mut objects[*current_index].kind {
+                if slider.render.is_none() {
+                    tracing::warn!("Slider at {} is not baked, baking on demand", slider.start_time);
+                    self.prepare_and_render_slider_texture(slider);
+                }
+            }
+        }
+
         let config = self.config.read().expect("failed to acquire read lock");
         let skin = self.skin_manager.read().expect("failed to acquire read lock");
 
         for current_index in queue.iter() {
             let object = &objects[*current_index];
 
-            let skin_color = skin.ini.colours.combo_colors.iter()
-                .cycle()
-                .skip(object.color)
-                .next()
-                .expect("failed to cycle for skin colors");
+            let skin_color = config.color_preset.combo_color(&skin.ini.colours.combo_colors, object.color);
 
             let color = if config.debug_use_judgements_as_colors {
                 match &object.kind {
@@ -907,15 +1085,15 @@ impl<'or> OsuRenderer<'or> {
                                 hit_objects::Hit::MISS => Rgb::new(252, 51, 51),
                             }
                         } else {
-                            *skin_color
+                            skin_color
                         }
                     },
                     hit_objects::ObjectKind::Slider(_) => {
-                        *skin_color
+                        skin_color
                     },
                 }
             } else {
-                *skin_color
+                skin_color
             };
 
             match &object.kind {
@@ -931,13 +1109,12 @@ impl<'or> OsuRenderer<'or> {
 
                     let alpha = calc_progress(time, start_time, fade_in_end_time).clamp(0.0, 1.0);
 
-                    let approach_progress = calc_progress(time, start_time, circle.start_time);
-
-                    let approach_scale = lerp(1.0, 4.0, 1.0 - approach_progress).clamp(1.0, 4.0);
-
-                    let mut hit_circle_alpha = alpha;
+                    let mut hit_circle_alpha = if config.hidden {
+                        calc_hidden_alpha(time, circle.start_time, preempt)
+                    } else {
+                        alpha
+                    };
                     let mut hit_circle_scale = 1.0;
-                    let mut render_approach = true;
 
                     if let Some(hit_result) = &circle.hit_result {
                         self.quad_debug_instance_data.push(
@@ -947,34 +1124,46 @@ impl<'or> OsuRenderer<'or> {
                         // Hit appears early than the exact hit point is reached
                         // Apply fadeout immediatly
                         let progress = calc_progress(time, hit_result.at, hit_result.at + CIRCLE_FADEOUT_TIME);
-                        hit_circle_alpha = 1.0 - progress;
+                        hit_circle_alpha = hit_circle_alpha.min(1.0 - progress);
 
-                        hit_circle_scale = lerp(1.0, CIRCLE_SCALEOUT_MAX, progress);
-                        render_approach = false;
+                        // Missed circles only fade out
+                        if hit_result.result != hit_objects::Hit::MISS {
+                            hit_circle_scale = lerp(1.0, CIRCLE_SCALEOUT_MAX, progress);
+                        }
                     } else {
                         // In case if there are no hit result keep alpha at 1.0 until late x50 hit window point
                         // is passed
 
-                        if time >= object.start_time {
+                        if time >= object.start_time && !config.hidden {
                             hit_circle_alpha = 1.0;
                         }
                     }
 
-                    if time >= object.start_time {
-                        render_approach = false;
-                    }
-                    
-                    if render_approach {
+                    let approach = calc_circle_approach(
+                        time,
+                        circle.start_time,
+                        preempt,
+                        fadein,
+                        circle.hit_result.as_ref().map(|x| x.at),
+                        hit_window.x50,
+                    );
+
+                    let mut approach_circle = None;
+
+                    if let Some((approach_alpha, approach_scale)) = approach.filter(|_| !config.hidden) {
+                        approach_circle = Some(self.approach_circle_instance_data.len() as u32);
                         self.approach_circle_instance_data
                             .push(ApproachCircleInstance::new(
                                 circle.pos.x,
                                 circle.pos.y,
                                 0.0,
-                                alpha as f32,
+                                approach_alpha as f32,
                                 approach_scale as f32,
                             ));
                     }
 
+                    self.approach_circle_indexes.push(approach_circle);
+
                     let hit_circle_instance = HitCircleInstance::new(
                         circle.pos.x,
                         circle.pos.y,
@@ -982,6 +1171,7 @@ impl<'or> OsuRenderer<'or> {
                         hit_circle_alpha as f32,
                         hit_circle_scale as f32,
                         &color,
+                        0.0,
                     );
 
                     self.hit_circle_instance_data.push(hit_circle_instance);
@@ -992,8 +1182,9 @@ impl<'or> OsuRenderer<'or> {
                     let start_time = slider.start_time - preempt as f64;
                     let end_time = start_time + fadein as f64;
 
-                    let mut body_alpha =
-                        ((time - start_time) / (end_time - start_time)).clamp(0.0, 0.95);
+                    let mut body_alpha = calc_slider_body_alpha(
+                        time, slider.start_time, slider.end_time(), preempt, fadein
+                    );
 
 
                     //let mut hit_circle_alpha = if approach_alpha > 0.0 { body_alpha as f32 } else { 0.0 };
@@ -1022,7 +1213,7 @@ impl<'or> OsuRenderer<'or> {
                     //    Assuming [1] is our current position, and we already passed slider head
                     //    we should render the second reverse arrow [2] even if we haven't passed
                     //    first reverse arrow yet
-                    let mut repeats_index = Vec::new(); // TODO ah uh
+                    let reverse_arrows_start = self.reverse_arrows_instance_data.len() as u32;
                     for repeat in 0..slider.repeats - 1 {
                         let repeat = repeat + 1; // TODO: big brain
 
@@ -1069,35 +1260,25 @@ impl<'or> OsuRenderer<'or> {
                             }
                         }
 
-                        let alpha = alpha as f32;
-
-                        self.slider_ticks_instance_data.push(
-                            QuadInstance::from_xy_pos_alpha_degree(
-                                reverse_arrow_pos.x, 
-                                reverse_arrow_pos.y, 
+                        self.reverse_arrows_instance_data.push(
+                            HitCircleInstance::new(
+                                reverse_arrow_pos.x,
+                                reverse_arrow_pos.y,
+                                0.0,
                                 alpha as f32,
-                                slider.reverse_arrows[repeat as usize - 1].angle
+                                1.0,
+                                &Rgb::new(255, 255, 255),
+                                slider.reverse_arrows[repeat as usize - 1].angle,
                             )
                         );
-
-                        repeats_index.push(self.slider_ticks_instance_data.len() as u32);
                     }
 
-                    let reverse_arrow = Some(repeats_index);
+                    let reverse_arrows = reverse_arrows_start..self.reverse_arrows_instance_data.len() as u32;
 
-                    // FADEOUT
-                    if time >= object.start_time + slider.duration
-                        && time <= object.start_time + slider.duration + SLIDER_FADEOUT_TIME
-                    {
-                        let start = object.start_time + slider.duration;
-                        let end = object.start_time + slider.duration + SLIDER_FADEOUT_TIME;
-
-                        let min = start.min(end);
-                        let max = start.max(end);
-
-                        let percentage = 100.0 - (((time - min) * 100.0) / (max - min)); // TODO remove `* 100.0`
-
-                        body_alpha = (percentage / 100.0).clamp(0.0, 0.95);
+                    if config.hidden {
+                        body_alpha = calc_hidden_body_alpha(
+                            time, slider.start_time, slider.end_time(), preempt
+                        ).min(SLIDER_BODY_MAX_ALPHA);
                     }
 
                     // APPROACH
@@ -1105,11 +1286,9 @@ impl<'or> OsuRenderer<'or> {
 
                     let approach_scale = lerp(1.0, 3.95, 1.0 - approach_progress).clamp(1.0, 4.0);
 
-                    let approach_alpha = if time >= object.start_time {
-                        0.0
-                    } else {
-                        body_alpha
-                    };
+                    let approach_alpha = calc_slider_approach_alpha(
+                        time, slider.start_time, preempt, fadein
+                    );
 
                     // FOLLOW CIRCLE STUFF
                     // SCOPE IN WHICH SLIDER IS HITABLE
@@ -1137,34 +1316,66 @@ impl<'or> OsuRenderer<'or> {
                             percentage = 100.0 - percentage;
                         }
 
-                        let pos = slider.curve.position_at(percentage / 100.0);
+                        let pos = slider.position_at(percentage / 100.0);
+
+                        // Body is faded out with hidden, but
+                        // follow circle stays visible while tracking
+                        let is_tracking = slider.hit_result
+                            .as_ref()
+                            .is_some_and(|x| x.is_tracking);
+
+                        let follow_circle_alpha = if config.hidden && is_tracking {
+                            1.0
+                        } else {
+                            body_alpha
+                        };
+
+                        let follow_start = self.follow_points_instance_data.len() as u32;
+
+                        debug_assert_finite(pos.x + slider.pos.x, pos.y + slider.pos.y);
 
                         self.follow_points_instance_data.push(HitCircleInstance {
                             pos: [pos.x + slider.pos.x, pos.y + slider.pos.y, 0.0],
-                            alpha: body_alpha as f32,
+                            alpha: follow_circle_alpha as f32,
                             color: color.to_gpu_values(),
-                            scale: 1.0
+                            scale: 1.0,
+                            rotation: 0.0,
                         });
 
-                        follow_circle = Some(self.follow_points_instance_data.len() as u32);
+                        follow_circle = Some(follow_start..follow_start + 1);
                     }
 
                     // BODY
+                    // Baked texture only holds tint parameters,
+                    // so per-combo body colour doesn't need a re-bake
+                    let slider_body = if skin.ini.colours.slider_body_combo_color {
+                        color
+                    } else {
+                        skin.ini.colours.slider_body
+                    };
+
                     self.slider_to_screen_instance_data.push(SliderInstance {
                         pos: [0.0, 0.0, 0.0],
                         alpha: body_alpha as f32,
                         slider_border: skin.ini.colours.slider_border.to_gpu_values(),
-                        slider_body: skin.ini.colours.slider_body.to_gpu_values(),
+                        slider_body: slider_body.to_gpu_values(),
                     });
 
-                    self.approach_circle_instance_data
-                        .push(ApproachCircleInstance::new(
-                            slider.pos.x,
-                            slider.pos.y,
-                            0.0,
-                            approach_alpha as f32,
-                            approach_scale as f32,
-                        ));
+                    let mut approach_circle = None;
+
+                    if !config.hidden {
+                        approach_circle = Some(self.approach_circle_instance_data.len() as u32);
+                        self.approach_circle_instance_data
+                            .push(ApproachCircleInstance::new(
+                                slider.pos.x,
+                                slider.pos.y,
+                                0.0,
+                                approach_alpha as f32,
+                                approach_scale as f32,
+                            ));
+                    }
+
+                    self.approach_circle_indexes.push(approach_circle);
 
                     let mut hit_circle_scale = 1.0;
 
@@ -1192,6 +1403,12 @@ impl<'or> OsuRenderer<'or> {
                             hit_circle_alpha = 1.0 - progress;
                         }
                     }
+
+                    if config.hidden {
+                        hit_circle_alpha = hit_circle_alpha.min(
+                            calc_hidden_alpha(time, slider.start_time, preempt)
+                        );
+                    }
                     
                     // HIT CIRCLE
                     self.hit_circle_instance_data
@@ -1202,9 +1419,10 @@ impl<'or> OsuRenderer<'or> {
                             hit_circle_alpha as f32,
                             hit_circle_scale as f32,
                             &color,
+                            0.0,
                         ));
 
-                    let mut slider_tick_indexes = Vec::new();
+                    let ticks_start = self.slider_tick_indexes.len();
                     
                     // SLIDER TICKS
                     for tick in &slider.ticks {
@@ -1224,7 +1442,7 @@ impl<'or> OsuRenderer<'or> {
                                 continue;
                             }
 
-                            slider_tick_indexes.push(self.slider_ticks_instance_data.len());
+                            self.slider_tick_indexes.push(self.slider_ticks_instance_data.len());
                         }
 
                     };
@@ -1232,28 +1450,126 @@ impl<'or> OsuRenderer<'or> {
                     // That's tricky part. Since every slider have a according
                     // slider texture and a quad where texture will be rendered and presented on screen.
                     // So we are pushing all textures to the "queue" so we can iterate on it later
-                    if let Some(render) = &slider.render {
-                        self.slider_to_screen_textures.push(SliderToScreenEntry {
-                            texture: render.texture.clone(),
-                            buffer: render.quad.clone(),
-                            follow_circle,
-                            ticks: slider_tick_indexes,
-                            reverse_arrow,
-                        })
-                    } else {
-                        panic!("Texture and quad should be present");
-                    };
+                    let render = slider.render
+                        .as_ref()
+                        .expect("sliders are baked at the start of prepare_objects");
+
+                    self.slider_to_screen_textures.push(SliderToScreenEntry {
+                        texture: render.texture.clone(),
+                        buffer: render.quad.clone(),
+                        follow_circle,
+                        ticks: ticks_start..self.slider_tick_indexes.len(),
+                        reverse_arrows,
+                    });
                 }
             }
         }
     }
 
+    /// Follow circle of every slider prepared by [`Self::prepare_objects`],
+    /// in the same order as sliders appear in the render queue
+    pub fn prepared_follow_circles(&self) -> impl Iterator<Item = Option<&HitCircleInstance>> {
+        self.slider_to_screen_textures.iter().map(|entry| {
+            entry.follow_circle
+                .as_ref()
+                .map(|range| &self.follow_points_instance_data[range.start as usize])
+        })
+    }
+
+    /// Positions of every instance prepared by [`Self::prepare_objects`]
+    pub fn prepared_positions(&self) -> impl Iterator<Item = [f32; 3]> + '_ {
+        self.hit_circle_instance_data.iter().map(|x| x.pos)
+            .chain(self.approach_circle_instance_data.iter().map(|x| x.pos))
+            .chain(self.follow_points_instance_data.iter().map(|x| x.pos))
+            .chain(self.slider_ticks_instance_data.iter().map(|x| x.pos))
+            .chain(self.reverse_arrows_instance_data.iter().map(|x| x.pos))
+            .chain(self.slider_to_screen_instance_data.iter().map(|x| x.pos))
+    }
+
     pub fn get_graphics(&self) -> Arc<Graphics> {
         let _span = tracy_client::span!("osu_renderer::get_graphics");
         self.graphics.clone()
     }
 
-    /// Render slider to the **texture** not screen
+    /// Records all pending slider bakes into the `encoder`,
+    /// should happen before anything samples slider textures
+    fn record_slider_bakes(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let _span = tracy_client::span!("osu_renderer::record_slider_bakes");
+
+        tracy_client::plot!("slider bakes per frame", self.slider_bakes.len() as f64);
+        crate::render_stat!(self.stats, slider_bakes += self.slider_bakes.len());
+        crate::render_stat!(self.stats, draw_calls += self.slider_bakes.len());
+
+        for bake in self.slider_bakes.drain(..) {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("slider render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &bake.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &bake.depth_texture.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(&self.slider_pipeline);
+
+            render_pass.set_bind_group(0, bake.camera.bind_group(), &[]);
+            render_pass.set_bind_group(1, &self.slider_settings_bind_group, &[]);
+
+            render_pass.set_vertex_buffer(0, self.slider_vertex_buffer.slice(..));
+
+            render_pass.set_vertex_buffer(1, bake.instance_buffer.slice(..));
+
+            render_pass.set_index_buffer(
+                self.slider_index_buffer.slice(..),
+                wgpu::IndexFormat::Uint16,
+            );
+            
+            render_pass.draw_indexed(
+                0..self.slider_indecies.len() as u32,
+                0,
+                0..bake.instances,
+            );
+        }
+    }
+
+    /// Submits pending slider bakes on their own, used when
+    /// there is no objects pass to combine them with
+    pub fn flush_slider_bakes(&mut self) {
+        let _span = tracy_client::span!("osu_renderer::flush_slider_bakes");
+
+        if self.slider_bakes.is_empty() {
+            return;
+        }
+
+        let mut encoder =
+            self.graphics
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("SLIDER TEXTURE ENCODER"),
+                });
+
+        self.record_slider_bakes(&mut encoder);
+
+        self.graphics
+            .queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Prepares slider to be rendered to the **texture** not screen.
+    /// Actual rendering is deferred to the [`Self::render_objects`]
     pub fn prepare_and_render_slider_texture(
         &mut self,
         slider: &mut crate::hit_objects::slider::Slider,
@@ -1262,7 +1578,7 @@ impl<'or> OsuRenderer<'or> {
 
         let config = self.config.read().expect("failed to acquire read lock");
         let skin = self.skin_manager.read().expect("failed to acquire read lock");
-        let surface_config = self.graphics.get_surface_config();
+        let format = self.graphics.format();
 
         if !slider.render.is_none() && config.store_slider_textures {
             return;
@@ -1270,26 +1586,15 @@ impl<'or> OsuRenderer<'or> {
 
         let bbox = slider.bounding_box(self.hit_circle_diameter / 2.0);
 
-        let (slider_vertices, _) = Vertex::cone((self.hit_circle_diameter / 2.0) * SLIDER_SCALE);
-
-        self.slider_verticies = slider_vertices.into();
-
-        buffer_write_or_init!(
-            self.graphics.queue,
-            self.graphics.device,
-            self.slider_vertex_buffer,
-            &self.slider_verticies,
-            Vertex
-        );
-
         let bbox_width = bbox.width() * SLIDER_SCALE;
         let bbox_height = bbox.height() * SLIDER_SCALE;
 
         let depth_texture =
             DepthTexture::new(&self.graphics, bbox_width as u32, bbox_height as u32, 1);
         
-        // Do not create a new camera each time?
-        self.slider_texture_camera.set_ortho(
+        // Every bake needs its own camera since they all
+        // end up in the same encoder
+        let camera = Camera::ortho(
             &self.graphics,
             0.0, bbox_width, bbox_height, 0.0
         );
@@ -1308,9 +1613,9 @@ impl<'or> OsuRenderer<'or> {
             mip_level_count: 1,
             sample_count: 1,
             dimension: TextureDimension::D2,
-            format: surface_config.format,
+            format,
             usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
-            view_formats: &[surface_config.format],
+            view_formats: &[format],
         });
 
         // Preparing instances
@@ -1354,71 +1659,24 @@ impl<'or> OsuRenderer<'or> {
         origin.x = 0.0 + (origin.x - bbox.top_left.x);
         origin.y = 0.0 + (origin.y - bbox.top_left.y);
 
-        buffer_write_or_init!(
-            self.graphics.queue,
-            self.graphics.device,
-            self.slider_instance_buffer,
-            &self.slider_instance_data,
-            SliderInstance
-        );
-
-        // Drawing to the texture
-        let view = slider_texture_not_sampled.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder =
+        // Transient buffer, shared one would be overwritten
+        // by the next slider before the bake is submitted
+        let instance_buffer =
             self.graphics
                 .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("SLIDER TEXTURE ENCODER"),
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("slider bake instance buffer"),
+                    contents: bytemuck::cast_slice(&self.slider_instance_data),
+                    usage: BufferUsages::VERTEX,
                 });
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("slider render pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &depth_texture.view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            render_pass.set_pipeline(&self.slider_pipeline);
-
-            render_pass.set_bind_group(0, self.slider_texture_camera.bind_group(), &[]);
-            render_pass.set_bind_group(1, &self.slider_settings_bind_group, &[]);
-
-            render_pass.set_vertex_buffer(0, self.slider_vertex_buffer.slice(..));
-
-            render_pass.set_vertex_buffer(1, self.slider_instance_buffer.slice(..));
-
-            render_pass.set_index_buffer(
-                self.slider_index_buffer.slice(..),
-                wgpu::IndexFormat::Uint16,
-            );
-            
-            render_pass.draw_indexed(
-                0..self.slider_indecies.len() as u32,
-                0,
-                0..self.slider_instance_data.len() as u32,
-            );
-        }
-
-        self.graphics
-            .queue
-            .submit(std::iter::once(encoder.finish()));
+        self.slider_bakes.push(SliderBake {
+            view: slider_texture_not_sampled.create_view(&wgpu::TextureViewDescriptor::default()),
+            depth_texture,
+            camera,
+            instance_buffer,
+            instances: self.slider_instance_data.len() as u32,
+        });
 
         let slider_texture = Arc::new(Texture::from_texture(
             slider_texture_not_sampled,
@@ -1445,9 +1703,17 @@ impl<'or> OsuRenderer<'or> {
                     usage: BufferUsages::VERTEX,
                 });
 
+        #[cfg(feature = "render-stats")]
+        let bytes = slider_texture_width as u64 * slider_texture_height as u64 * 4;
+
+        #[cfg(feature = "render-stats")]
+        SLIDER_TEXTURE_BYTES.fetch_add(bytes, std::sync::atomic::Ordering::Relaxed);
+
         slider.render = Some(SliderRender {
             texture: slider_texture,
             quad: slider_quad.into(),
+            #[cfg(feature = "render-stats")]
+            bytes,
         });
     }
 
@@ -1459,8 +1725,6 @@ impl<'or> OsuRenderer<'or> {
 
         self.quad_verticies = Vertex::quad_centered(hit_circle_diameter, hit_circle_diameter);
 
-        self.slider_reverse_arrow_quad.resize_vertex_centered(hit_circle_diameter, hit_circle_diameter);
-
         self.hit_circle_vertex_buffer =
             self.graphics
                 .device
@@ -1470,10 +1734,10 @@ impl<'or> OsuRenderer<'or> {
                     usage: BufferUsages::VERTEX,
                 });
 
-        // Slider
-        let (slider_vertices, slider_index) = Vertex::cone(hit_circle_diameter / 2.0);
+        // Slider, cone is only used for bakes which are scaled
+        let (slider_vertices, slider_index) = Vertex::cone((hit_circle_diameter / 2.0) * SLIDER_SCALE);
 
-        self.slider_verticies = slider_vertices.into();
+        self.slider_verticies = slider_vertices;
 
         buffer_write_or_init!(
             self.graphics.queue,
@@ -1483,7 +1747,7 @@ impl<'or> OsuRenderer<'or> {
             Vertex
         );
 
-        self.slider_indecies = slider_index.into();
+        self.slider_indecies = slider_index;
 
         self.slider_index_buffer =
             self.graphics
@@ -1501,28 +1765,16 @@ impl<'or> OsuRenderer<'or> {
 
         let (graphics_width, graphics_height) = self.graphics.get_surface_size();
 
-        let (scale, offsets) = calc_playfield(new_size.width as f32, new_size.height as f32);
-
-        self.scale = scale;
-        self.offsets = offsets;
-
         self.camera.resize(new_size);
-        self.camera.transform(self.scale, self.offsets);
-        self.depth_texture = DepthTexture::new(
-            &self.graphics,
-            graphics_width,
-            graphics_height,
-            1,
-        );
-
-        self.camera.write_buffers(&self.graphics);
-
         self.quad_debug.resize_camera(new_size);
-        self.quad_debug.transform_camera(self.scale, self.offsets);
+        self.update_playfield(new_size.width as f32, new_size.height as f32);
 
+        if self.surface_size == (graphics_width, graphics_height) {
+            return;
+        }
 
-        self.slider_reverse_arrow_quad.resize_camera(new_size);
-        self.slider_reverse_arrow_quad.transform_camera(self.scale, self.offsets);
+        self.surface_size = (graphics_width, graphics_height);
+        self.depth_texture = None;
 
         // Slider to screen
         self.slider_to_screen_verticies = Vertex::quad_positional(
@@ -1541,16 +1793,51 @@ impl<'or> OsuRenderer<'or> {
         );
     }
 
+    /// Playfield scale or offset in the config changed
+    pub fn on_playfield_layout_changed(&mut self) {
+        let (width, height) = self.graphics.get_surface_size();
+        self.update_playfield(width as f32, height as f32);
+    }
+
+    fn update_playfield(&mut self, width: f32, height: f32) {
+        let layout = self.config.read().expect("failed to acquire read lock").playfield_layout();
+        let (scale, offsets) = calc_adjusted_playfield(width, height, &layout);
+
+        self.scale = scale;
+        self.offsets = offsets;
+
+        self.camera.transform(self.scale, self.offsets);
+        self.camera.write_buffers(&self.graphics);
+
+        self.quad_debug.transform_camera(self.scale, self.offsets);
+    }
+
+    pub fn depth_texture(&mut self) -> &DepthTexture {
+        let (width, height) = self.surface_size;
+
+        self.depth_texture.get_or_insert_with(|| {
+            DepthTexture::new(&self.graphics, width, height, 1)
+        })
+    }
+
     pub fn zoom_camera(&mut self, zoom_factor: f32, zoom_center: Vector2<f32>) {
         self.camera.zoom(zoom_factor, zoom_center);
         self.quad_debug.zoom_camera(zoom_factor, zoom_center);
-        self.slider_reverse_arrow_quad.zoom_camera(zoom_factor, zoom_center);
     }
 
     pub fn move_camera(&mut self, delta: Vector2<f32>) {
         self.camera.move_camera(delta);
         self.quad_debug.move_camera(delta);
-        self.slider_reverse_arrow_quad.move_camera(delta);
+    }
+
+    /// Flips or rotates the playfield. Everything drawn in playfield
+    /// space goes through it in the vertex stage, including composite
+    /// quads of baked sliders, so their textures stay valid
+    pub fn set_playfield_matrix(&mut self, matrix: Matrix3<f64>) {
+        self.camera.set_playfield_matrix(matrix);
+        self.camera.write_buffers(&self.graphics);
+
+        self.quad_debug.set_playfield_matrix(matrix);
     }
 
     pub fn write_camera_buffers(&mut self) {
@@ -1560,12 +1847,36 @@ impl<'or> OsuRenderer<'or> {
         self.camera.write_buffers(&self.graphics);
 
         self.quad_debug.write_camera_buffer();
-        self.slider_reverse_arrow_quad.write_camera_buffer();
+    }
+
+    /// Sizes of the instance buffers that are
+    /// recreated by `write_buffers` when they are too small
+    #[cfg(feature = "render-stats")]
+    fn instance_buffer_sizes(&self) -> [u64; 8] {
+        [
+            self.hit_circle_instance_buffer.size(),
+            self.approach_circle_instance_buffer.size(),
+            self.slider_to_screen_instance_buffer.size(),
+            self.follow_points_instance_buffer.size(),
+            self.quad_debug_buffer.size(),
+            self.quad_debug_buffer2.size(),
+            self.slider_ticks_instance_buffer.size(),
+            self.reverse_arrows_instance_buffer.size(),
+        ]
+    }
+
+    /// Returns counters of the frame and starts a new one
+    #[cfg(feature = "render-stats")]
+    pub fn finish_frame_stats(&mut self) -> RenderStats {
+        std::mem::take(&mut self.stats)
     }
 
     pub fn write_buffers(&mut self) {
         let _span = tracy_client::span!("osu_renderer::write_buffers");
 
+        #[cfg(feature = "render-stats")]
+        let sizes_before = self.instance_buffer_sizes();
+
         buffer_write_or_init!(
             self.graphics.queue,
             self.graphics.device,
@@ -1621,13 +1932,45 @@ impl<'or> OsuRenderer<'or> {
             &self.slider_ticks_instance_data,
             QuadInstance
         );
+
+        buffer_write_or_init!(
+            self.graphics.queue,
+            self.graphics.device,
+            self.reverse_arrows_instance_buffer,
+            &self.reverse_arrows_instance_data,
+            HitCircleInstance
+        );
+
+        #[cfg(feature = "render-stats")]
+        {
+            let reallocations = sizes_before
+                .iter()
+                .zip(self.instance_buffer_sizes())
+                .filter(|(before, after)| **before != *after)
+                .count();
+
+            self.stats.buffer_reallocations += reallocations as u32;
+        }
     }
 
-    /// Clears internal buffers
+    /// Reserves instance queues for `objects` amount of objects,
+    /// so they don't regrow in the middle of the play
+    pub fn reserve_instances(&mut self, objects: usize) {
+        let _span = tracy_client::span!("osu_renderer::reserve_instances");
+
+        self.hit_circle_instance_data.reserve(objects);
+        self.approach_circle_instance_data.reserve(objects);
+        self.approach_circle_indexes.reserve(objects);
+        self.slider_to_screen_textures.reserve(objects);
+        self.judgements_queue.reserve(objects);
+    }
+
+    /// Clears internal buffers, capacity is kept for the next frame
     pub fn clear_buffers(&mut self) {
         let _span = tracy_client::span!("osu_renderer::clear_buffers");
         self.hit_circle_instance_data.clear();
         self.approach_circle_instance_data.clear();
+        self.approach_circle_indexes.clear();
         self.slider_to_screen_instance_data.clear();
         self.slider_to_screen_textures.clear();
         self.follow_points_instance_data.clear();
@@ -1635,6 +1978,8 @@ impl<'or> OsuRenderer<'or> {
         self.quad_debug_instance_data2.clear();
         self.judgements_queue.clear();
         self.slider_ticks_instance_data.clear();
+        self.slider_tick_indexes.clear();
+        self.reverse_arrows_instance_data.clear();
         self.quad_debug.clear_atlas_buffers();
     }
     
@@ -1643,6 +1988,10 @@ impl<'or> OsuRenderer<'or> {
         let _span = tracy_client::span!("osu_renderer::render_judgements");
 
         let skin = self.skin_manager.read().expect("failed");
+        let high_contrast = {
+            let config = self.config.read().expect("failed to acquire read lock");
+            config.quality.judgements(&config.judgements).high_contrast
+        };
 
         if self.judgements_queue.is_empty() {
             return;
@@ -1656,16 +2005,93 @@ impl<'or> OsuRenderer<'or> {
                 hit_objects::Hit::MISS => 3,
             };
 
-            self.quad_debug.add_atlas_quad(
-                jdg.pos.x as f32, jdg.pos.y as f32,
-                50.0, 50.0,
-                image_index,
-                jdg.alpha,
-                &skin.judgments_atlas
-            )
+            let (x, y) = (jdg.pos.x as f32, jdg.pos.y as f32);
+
+            let mut layers: SmallVec<[(u32, f32, [f32; 4]); 4]> = SmallVec::new();
+
+            if high_contrast {
+                // Shape cue, so 50 doesn't rely on its color
+                if jdg.result == hit_objects::Hit::X50 {
+                    layers.push((JUDGEMENT_RING_INDEX, JUDGEMENT_RING_SIZE + JUDGEMENT_OUTLINE, JUDGEMENT_OUTLINE_TINT));
+                    layers.push((JUDGEMENT_RING_INDEX, JUDGEMENT_RING_SIZE, AtlasQuadVertex::NO_TINT));
+                }
+
+                // Silhouette behind the judgement acts as an outline
+                layers.push((image_index, JUDGEMENT_SIZE + JUDGEMENT_OUTLINE, JUDGEMENT_OUTLINE_TINT));
+            }
+
+            layers.push((image_index, JUDGEMENT_SIZE, AtlasQuadVertex::NO_TINT));
+
+            for (index, size, tint) in layers {
+                self.quad_debug.add_atlas_quad(
+                    x, y,
+                    size, size,
+                    index,
+                    jdg.alpha,
+                    tint,
+                    &skin.judgments_atlas
+                );
+
+                crate::render_stat!(self.stats, quad_instances += 1);
+            }
         }
 
         self.quad_debug.render_atlas_test(view, skin.judgments_atlas.bind_group());
+
+        crate::render_stat!(self.stats, draw_calls += 1);
+
+    }
+
+    /// Draws layers of the hit circle `instance` in the order skin asks
+    /// for, same for standalone circles and slider heads
+    fn draw_hit_circle(&self, render_pass: &mut wgpu::RenderPass, skin: &SkinManager, instance: u32) {
+        render_pass.set_bind_group(1, self.camera.bind_group(), &[]);
+        render_pass.set_vertex_buffer(0, self.hit_circle_vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.hit_circle_instance_buffer.slice(..));
+        render_pass.set_index_buffer(
+            self.hit_circle_index_buffer.slice(..),
+            wgpu::IndexFormat::Uint16,
+        );
+
+        for layer in HitCircleLayer::draw_order(skin.ini.general.hit_circle_overlay_above_number) {
+            match layer {
+                HitCircleLayer::Circle => {
+                    render_pass.set_pipeline(&self.quad_colored_pipeline);
+                    render_pass.set_bind_group(0, &skin.hit_circle.bind_group, &[]);
+                },
+                HitCircleLayer::Overlay => {
+                    render_pass.set_pipeline(&self.hit_circle_overlay_pipeline);
+                    render_pass.set_bind_group(0, &skin.hit_circle_overlay.bind_group, &[]);
+                },
+                // TODO: combo numbers, drawn with `hit_circle_overlay_pipeline` too
+                HitCircleLayer::Number => continue,
+            }
+
+            render_pass.draw_indexed(
+                0..QUAD_INDECIES.len() as u32,
+                0,
+                instance..instance + 1,
+            );
+        }
+    }
+
+    /// Draws `instances` of prepared approach circles
+    fn draw_approach_circles(&self, render_pass: &mut wgpu::RenderPass, instances: Range<u32>) {
+        render_pass.set_pipeline(&self.approach_circle_pipeline);
+        render_pass.set_bind_group(0, self.camera.bind_group(), &[]);
+
+        render_pass.set_vertex_buffer(0, self.hit_circle_vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.approach_circle_instance_buffer.slice(..));
+        render_pass.set_index_buffer(
+            self.hit_circle_index_buffer.slice(..),
+            wgpu::IndexFormat::Uint16,
+        );
+
+        render_pass.draw_indexed(
+            0..QUAD_INDECIES.len() as u32,
+            0,
+            instances,
+        );
     }
 
     /// Render all objects from internal buffers
@@ -1678,12 +2104,11 @@ impl<'or> OsuRenderer<'or> {
     ) -> Result<(), wgpu::SurfaceError> {
         let _span = tracy_client::span!("osu_renderer::render_objects");
 
-        let skin = self.skin_manager.read().expect("Failed to get skin manager");
-
         // If any of the buffers is empty dont even try to render
         if self.hit_circle_instance_buffer.size() == 0
         && self.approach_circle_instance_buffer.size() == 0 {
             tracing::warn!("Trying to render with hitcircle or approachcircle buffers being empty!");
+            self.flush_slider_bakes();
             return Ok(())
         }
 
@@ -1694,6 +2119,16 @@ impl<'or> OsuRenderer<'or> {
                     label: Some("HitObjects encoder"),
                 });
 
+        // Slider textures are sampled below, so baking them first
+        self.record_slider_bakes(&mut encoder);
+
+        let skin = self.skin_manager.read().expect("Failed to get skin manager");
+
+        let batch_approach_circles = self.config
+            .read()
+            .expect("failed to acquire read lock")
+            .debug_batch_approach_circles;
+
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("render objects render pass"),
@@ -1716,38 +2151,18 @@ impl<'or> OsuRenderer<'or> {
             let mut current_circle = 0;
             let mut current_slider = 0;
 
-            for current_index in queue.iter() {
+            // Queue goes from the latest object to the earliest one, so
+            // earlier objects end up on top together with their approach
+            // circles, like in osu!
+            for (queue_index, current_index) in queue.iter().enumerate() {
                 let object = &objects[*current_index];
 
                 match object.kind {
                     hit_objects::ObjectKind::Circle(_) => {
-                        render_pass.set_pipeline(&self.quad_colored_pipeline);
-                        
-                        // hit circle itself
-                        render_pass.set_bind_group(0, &skin.hit_circle.bind_group, &[]);
-                        render_pass.set_bind_group(1, self.camera.bind_group(), &[]);
-                        render_pass.set_vertex_buffer(0, self.hit_circle_vertex_buffer.slice(..));
-                        render_pass.set_vertex_buffer(1, self.hit_circle_instance_buffer.slice(..));
-                        render_pass.set_index_buffer(
-                            self.hit_circle_index_buffer.slice(..),
-                            wgpu::IndexFormat::Uint16,
-                        );
-                        render_pass.draw_indexed(
-                            0..QUAD_INDECIES.len() as u32,
-                            0,
-                            current_circle..current_circle + 1,
-                        );
+                        self.draw_hit_circle(&mut render_pass, &skin, current_circle);
 
-                        // overlay
-                        render_pass.set_pipeline(&self.quad_colored_pipeline);
-                        render_pass.set_bind_group(0, &skin.hit_circle_overlay.bind_group, &[]);
-                        render_pass.set_vertex_buffer(0, self.hit_circle_vertex_buffer.slice(..));
-                        render_pass.set_vertex_buffer(1, self.hit_circle_instance_buffer.slice(..));
-                        render_pass.draw_indexed(
-                            0..QUAD_INDECIES.len() as u32,
-                            0,
-                            current_circle..current_circle + 1,
-                        );
+                        crate::render_stat!(self.stats, draw_calls += 2);
+                        crate::render_stat!(self.stats, hit_circle_instances += 2);
 
                         current_circle += 1;
                     },
@@ -1764,37 +2179,53 @@ impl<'or> OsuRenderer<'or> {
                         let slider_to_screen = &self.slider_to_screen_textures[current_slider];
 
                         render_pass.set_bind_group(1, self.camera.bind_group(), &[]);
+                        render_pass.set_bind_group(2, &self.slider_settings_bind_group, &[]);
                         render_pass.set_vertex_buffer(0, slider_to_screen.buffer.slice(..));
 
                         render_pass.set_bind_group(0, &slider_to_screen.texture.bind_group, &[]);
 
                         // First draw a slider body
                         render_pass.draw_indexed(0..QUAD_INDECIES.len() as u32, 0, instance.clone());
+
+                        crate::render_stat!(self.stats, draw_calls += 1);
+                        crate::render_stat!(self.stats, slider_instances += 1);
                         
                         // Slider ticks
-                        for tick_index in &slider_to_screen.ticks {
+                        for tick_index in &self.slider_tick_indexes[slider_to_screen.ticks.clone()] {
                             self.quad_debug.render_on_view_instanced(
                                 view, 
                                 &skin.slider_tick.bind_group, 
                                 &self.slider_ticks_instance_buffer, 
                                 (tick_index -1) as u32..*tick_index as u32
                             );
+
+                            crate::render_stat!(self.stats, draw_calls += 1);
+                            crate::render_stat!(self.stats, quad_instances += 1);
                         }
 
                         // reverse arrow
-                        if let Some(reverse_arrow_index) = &slider_to_screen.reverse_arrow {
-                            for index in reverse_arrow_index {
-                                self.slider_reverse_arrow_quad.render_on_view_instanced(
-                                    view, 
-                                    &skin.slider_reverse_arrow.bind_group, 
-                                    &self.slider_ticks_instance_buffer, 
-                                    (index-1) as u32..*index as u32
-                                );
-                            }
+                        if !slider_to_screen.reverse_arrows.is_empty() {
+                            render_pass.set_pipeline(&self.quad_colored_pipeline);
+                            render_pass.set_bind_group(0, &skin.slider_reverse_arrow.bind_group, &[]);
+                            render_pass.set_bind_group(1, self.camera.bind_group(), &[]);
+                            render_pass.set_vertex_buffer(0, self.hit_circle_vertex_buffer.slice(..));
+                            render_pass.set_vertex_buffer(1, self.reverse_arrows_instance_buffer.slice(..));
+                            render_pass.set_index_buffer(
+                                self.hit_circle_index_buffer.slice(..),
+                                wgpu::IndexFormat::Uint16,
+                            );
+                            render_pass.draw_indexed(
+                                0..QUAD_INDECIES.len() as u32,
+                                0,
+                                slider_to_screen.reverse_arrows.clone(),
+                            );
+
+                            crate::render_stat!(self.stats, draw_calls += 1);
+                            crate::render_stat!(self.stats, hit_circle_instances += slider_to_screen.reverse_arrows.len());
                         }
 
                         // follow circle
-                        if let Some(follow) = &slider_to_screen.follow_circle {
+                        if let Some(follow) = slider_to_screen.follow_circle.clone() {
                             render_pass.set_pipeline(&self.hit_circle_pipeline);
                             render_pass.set_bind_group(1, self.camera.bind_group(), &[]);
                             render_pass.set_bind_group(0, &skin.sliderb0.bind_group, &[]);
@@ -1804,67 +2235,48 @@ impl<'or> OsuRenderer<'or> {
                                 self.hit_circle_index_buffer.slice(..),
                                 wgpu::IndexFormat::Uint16,
                             );
+                            crate::render_stat!(self.stats, draw_calls += 1);
+                            crate::render_stat!(self.stats, follow_circle_instances += follow.len());
+
                             render_pass.draw_indexed(
                                 0..QUAD_INDECIES.len() as u32,
                                 0,
-                                *follow - 1 as u32..*follow as u32,
+                                follow,
                             );
                         }
 
 
 
                         // Hit circle on top of everything
-                        render_pass.set_pipeline(&self.quad_colored_pipeline);
-                        render_pass.set_bind_group(0, &skin.hit_circle.bind_group, &[]);
-                        render_pass.set_bind_group(1, self.camera.bind_group(), &[]);
-
-                        render_pass.set_vertex_buffer(0, self.hit_circle_vertex_buffer.slice(..));
-
-                        render_pass.set_vertex_buffer(1, self.hit_circle_instance_buffer.slice(..));
-
-                        render_pass.set_index_buffer(
-                            self.hit_circle_index_buffer.slice(..),
-                            wgpu::IndexFormat::Uint16,
-                        );
+                        self.draw_hit_circle(&mut render_pass, &skin, current_circle);
 
-                        render_pass.draw_indexed(
-                            0..QUAD_INDECIES.len() as u32,
-                            0,
-                            current_circle..current_circle + 1,
-                        );
-
-                        render_pass.set_pipeline(&self.hit_circle_pipeline);
-                        render_pass.set_bind_group(0, &skin.hit_circle_overlay.bind_group, &[]);
-                        render_pass.set_vertex_buffer(0, self.hit_circle_vertex_buffer.slice(..));
-                        render_pass.set_vertex_buffer(1, self.hit_circle_instance_buffer.slice(..));
-                        render_pass.draw_indexed(
-                            0..QUAD_INDECIES.len() as u32,
-                            0,
-                            current_circle..current_circle + 1,
-                        );
+                        crate::render_stat!(self.stats, draw_calls += 2);
+                        crate::render_stat!(self.stats, hit_circle_instances += 2);
 
                         current_slider += 1;
                         current_circle += 1;
                     },
                 }
-            }
 
-            // Approach circles should be always on top
-            render_pass.set_pipeline(&self.approach_circle_pipeline);
-            render_pass.set_bind_group(0, self.camera.bind_group(), &[]);
+                if batch_approach_circles {
+                    continue;
+                }
 
-            render_pass.set_vertex_buffer(0, self.hit_circle_vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, self.approach_circle_instance_buffer.slice(..));
-            render_pass.set_index_buffer(
-                self.hit_circle_index_buffer.slice(..),
-                wgpu::IndexFormat::Uint16,
-            );
+                if let Some(approach_circle) = self.approach_circle_indexes[queue_index] {
+                    self.draw_approach_circles(&mut render_pass, approach_circle..approach_circle + 1);
 
-            render_pass.draw_indexed(
-                0..QUAD_INDECIES.len() as u32,
-                0,
-                0..self.approach_circle_instance_data.len() as u32,
-            );
+                    crate::render_stat!(self.stats, draw_calls += 1);
+                    crate::render_stat!(self.stats, approach_circle_instances += 1);
+                }
+            }
+
+            // Single draw call over all objects
+            if batch_approach_circles {
+                self.draw_approach_circles(&mut render_pass, 0..self.approach_circle_instance_data.len() as u32);
+
+                crate::render_stat!(self.stats, draw_calls += 1);
+                crate::render_stat!(self.stats, approach_circle_instances += self.approach_circle_instance_data.len());
+            }
             
             /*
             self.quad_debug.render_on_view_instanced(
@@ -1,175 +1,668 @@
-use std::{fs::File, io::BufReader, path::{Path, PathBuf}, sync::{mpsc::{channel, Receiver, Sender, TryRecvError}, Arc, RwLock}, time::Duration};
+use std::{collections::VecDeque, fs::File, io::BufReader, path::{Path, PathBuf}, sync::{mpsc::{channel, Receiver, Sender, TryRecvError}, Arc, RwLock}, time::{Duration, Instant}};
 
-use cgmath::Vector2;
+use cgmath::{Matrix3, Vector2};
 use egui::{RawInput, Slider};
+use osu_replay_parser::replay::Replay;
 use rosu_map::Beatmap;
-use soloud::{audio, AudioExt, Handle, LoadExt, Soloud, Wav};
+use soloud::{AudioExt, Handle, LoadExt, Soloud, Wav};
 use wgpu::TextureView;
 use winit::{dpi::{PhysicalPosition, PhysicalSize}, keyboard::KeyCode, window::Window};
 
 use crate::{
-    config::Config, egui_state::EguiState, graphics::Graphics, hit_objects::{hit_window::HitWindow, Object, ObjectKind}, math::{calc_playfield, calculate_preempt_fadein, calc_hitcircle_diameter}, renderer::cursor::CursorRenderer, osu_db::DbBeatmapEntry, osu_input::KeyboardState, osu_renderer::OsuRenderer, skin_manager::SkinManager, song_select_state::SongSelectionState, timer::Timer
+    adaptive_quality::AdaptiveQuality, audio::{self, AudioInfo}, audio_effects::{AudioEffects, AudioState}, config::{Config, CONFIG_PATH}, diagnostics, difficulty::Difficulty, dropped_file::{extract_beatmap_archive, route_dropped_file, DroppedFileKind, DEFAULT_SONGS_PATH}, egui_state::EguiState, frame_history::FrameHistory, gameplay_renderer::{GameplayFrame, GameplayRenderer}, graphics::{Graphics, PresentError}, i18n::{self, tf}, hit_objects::{breaks::{break_at, Break}, converter_for, hit_window::HitWindow, Object, ObjectKind}, math::{calculate_preempt_fadein, calc_hitcircle_diameter, playfield_matrix, PlayfieldTransform}, renderer::{cursor::CursorRenderer, particles::ParticleRenderer}, osu_db::{replay_export::{encode_replay, replay_file_name, ExportedReplay, ReplayInfo, LOCAL_PLAYER}, stable_import::parse_replay_mods, DbBeatmapEntry, LocalScore}, notifier::Severity, osu_input::KeyboardState, particles::{ParticleKind, ParticleSystem}, osu_renderer::OsuRenderer, screen::{break_overlay::render_break_overlay, drop_overlay::render_drop_overlay, slider_debug::render_slider_debug, hud::{render_hud, KeyOverlayState}, pause::{PauseMenu, PauseState}, results::{PendingReplay, ResultsScreen}, song_select::PreviewProgress, toast::Toasts, transition::{Transition, TransitionStep}}, skin_manager::{has_skin_elements, SkinImages, SkinLayers, SkinManager}, score::{Progression, Score}, rng::AppRng, session_stats::{unix_now, SessionTracker}, song_select_state::SongSelectionState, timer::Timer
 };
-use crate::processor::OsuProcessor;
+use crate::processor::{replay_cursor::ReplayCursor, replay_log::InterpolatedFrame, rules::GameplayRules, OsuProcessor, PlaySummary};
 
+/// How far arrow keys seek while watching a replay, in ms
+const REPLAY_SEEK_STEP: f64 = 5000.0;
+
+/// Playback rates that can be picked while watching a replay
+const REPLAY_RATES: [f64; 6] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0];
+
+#[derive(Debug)]
 pub enum OsuStates {
     Playing,
     SongSelection,
+    Results,
 }
 
 pub enum OsuStateEvent {
     ToSongSelection,
     SetCursorSize(f32),
+    /// Playfield scale or vertical offset changed
+    PlayfieldLayoutChanged,
+    /// Baked slider textures are outdated and have to be rebaked
+    SliderConfigChanged,
     ChangeSkin(PathBuf),
-    StartBeatmap(Arc<DbBeatmapEntry>),
-    PlaySound(i32, audio::Wav),
+    /// Skin images are decoded by the loader thread, only GPU upload is left
+    SkinLoaded(Box<SkinImages>),
+    /// Beatmap skin of the directory is decoded, layered over the user skin
+    BeatmapSkinLoaded(PathBuf, Box<SkinImages>),
+    /// Beatmap is provided when song select already has it parsed
+    StartBeatmap(Arc<DbBeatmapEntry>, Option<Arc<Beatmap>>),
+    PlaySound(i32, Wav),
+    /// Seeks the song select preview, seconds
+    SeekPreview(f64),
+    StopSound,
+    /// Play is over, sent once per attempt
+    MapCompleted(PlaySummary),
+    /// Restarts current beatmap from the beginning
+    Retry,
+    ChangeAudioBackend(String),
+    /// Dropped .osz was extracted, `Err` holds the reason it failed
+    ArchiveImported(PathBuf, Result<PathBuf, String>),
+    /// Replay frames are already parsed and its beatmap is found
+    WatchReplay(Arc<DbBeatmapEntry>, Box<ReplayCursor>),
 }
 
 
-pub struct OsuState<'s> {
+pub struct OsuState {
     pub window: Arc<Window>,
     pub egui: EguiState,
     pub event_receiver: Receiver<OsuStateEvent>,
     pub event_sender: Sender<OsuStateEvent>,
 
     pub sl: Soloud,
+    audio_info: Arc<RwLock<AudioInfo>>,
 
     pub current_state: OsuStates,
-    current_beatmap: Option<Beatmap>,
+    current_beatmap: Option<Arc<Beatmap>>,
+    current_beatmap_entry: Option<Arc<DbBeatmapEntry>>,
+    /// Break periods of the current beatmap, sorted by start time.
+    /// HP drain should be paused during them once HP is implemented
+    current_breaks: Vec<Break>,
     current_hit_window: HitWindow,
     current_screen_size: Vector2<f32>,
+    /// Dragging the window sends many `Resized` events per frame,
+    /// surface and renderers get only the latest size once per frame
+    pending_resize: Option<PhysicalSize<u32>>,
+    /// Size surface and renderers were last resized to
+    applied_size: Option<PhysicalSize<u32>>,
+    playfield: PlayfieldTransform,
+    /// Last cursor position in playfield coordinates
+    cursor_playfield_pos: Vector2<f64>,
     current_hit_circle_diameter: f32,
     current_audio: Option<Wav>,
     current_playing_audio: Option<Handle>,
+    /// Declared after `current_audio`, the filter
+    /// has to be dropped after music that uses it
+    audio_effects: AudioEffects,
 
-    pub song_select: SongSelectionState<'s>,
+    /// Time when the last object is done
+    current_play_end: f64,
 
-    skin_manager: Arc<RwLock<SkinManager>>,
+    /// Plays of the day, replays are not counted
+    session: SessionTracker,
+
+    /// Best local play of the current beatmap, the
+    /// score HUD shows how far ahead or behind of it the play is
+    pace: Option<Progression>,
 
-    osu_renderer: OsuRenderer<'s>,
+    pub song_select: SongSelectionState,
+
+    skin_manager: Arc<RwLock<SkinManager>>,
+    /// User skin put aside while the current beatmap ships its
+    /// own elements, swapped back on return to song select
+    user_skin: Option<SkinManager>,
+    /// Newly opened skin waiting for its textures to be uploaded
+    pending_skin: Option<SkinManager>,
+    /// Folder the active beatmap skin was loaded from,
+    /// set while its images are still being decoded
+    beatmap_skin_dir: Option<PathBuf>,
+    /// Beatmap skin decoded ahead for the selected beatmap
+    prepared_beatmap_skin: Option<(PathBuf, SkinImages)>,
+    config: Arc<RwLock<Config>>,
+
+    osu_renderer: OsuRenderer,
 
     preempt: f32,
     fadein: f32,
 
     hit_objects: Vec<Object>,
 
-    objects_render_queue: Vec<usize>,
-    objects_judgments_render_queue: Vec<usize>,
+    gameplay_renderer: GameplayRenderer,
 
     osu_clock: Timer,
     
-    cursor_renderer: CursorRenderer<'s>,
+    cursor_renderer: CursorRenderer,
+
+    /// Cursor ripples, in playfield coordinates and beatmap time
+    particles: ParticleSystem,
+    particle_renderer: ParticleRenderer,
+    /// Time replay cursor was sampled at last frame,
+    /// presses after it spawn ripples
+    last_replay_sample: f64,
 
     input_processor: OsuProcessor,
+    current_rules: GameplayRules,
+
+    /// Set while a replay is watched, live gameplay
+    /// inputs are ignored except pause, seek and rate
+    replay: Option<ReplayCursor>,
+
+    frame_history: FrameHistory,
+    adaptive_quality: AdaptiveQuality,
+    /// Control points and curves of visible sliders
+    /// are drawn over gameplay, toggled with Ctrl+D
+    show_slider_debug: bool,
+
+    modal_text: Option<String>,
+
+    results: Option<ResultsScreen>,
+
+    /// Set while gameplay is paused
+    pause: Option<PauseMenu>,
+    /// Keys that are physically held, tracked even while paused
+    held_keys: KeyboardState,
+
+    /// Files dropped onto the window, opened one
+    /// by one and never in the middle of gameplay
+    dropped_files: VecDeque<PathBuf>,
+    is_file_hovered: bool,
+    /// Archives are extracted one at a time by the import worker
+    archive_import_tx: Sender<PathBuf>,
+    archives_queued: usize,
+    archives_imported: usize,
+
+    toasts: Toasts,
+
+    key_overlay: KeyOverlayState,
+
+    /// Span of the last opened beatmap, closed
+    /// once its first gameplay frame is rendered
+    first_frame: Option<(tracing::Span, Instant)>,
+
+    /// Fade between song select and gameplay
+    transition: Transition<OsuStateEvent>,
+    /// Clock and audio are stopped at the play start until the fade in is over
+    is_clock_held: bool,
 }
 
-impl<'s> OsuState<'s> {
-    pub fn new(window: Arc<Window>, graphics: Graphics<'s>, sl: Soloud) -> Self {
+impl OsuState {
+    pub fn new(window: Arc<Window>, graphics: Graphics) -> Self {
         let egui = EguiState::new(&graphics, &window);
         let skin_manager = Arc::new(RwLock::new(
             SkinManager::from_path("skin", &graphics)
         ));
 
-        let config = Arc::new(RwLock::new(Config::default()));
+        let config = Config::load(CONFIG_PATH);
+        i18n::set_current(config.lang);
+
+        let (sl, backend_name) = audio::init_soloud(config.audio_backend.as_deref());
+
+        let audio_effects = AudioEffects::new(config.audio_effects, config.volume);
+
+        // Every random behaviour is seeded from here
+        let mut rng = AppRng::new(config.rng_seed);
+
+        let config = Arc::new(RwLock::new(config));
         let graphics = Arc::new(graphics);
 
         let osu_renderer = OsuRenderer::new(graphics.clone(), config.clone(), skin_manager.clone());
 
         let (event_sender, event_receiver) = channel::<OsuStateEvent>();
 
+        diagnostics::set_state(format!("{:?}", OsuStates::SongSelection));
+
+        let audio_info = Arc::new(RwLock::new(AudioInfo::new(backend_name, &sl)));
+
+        let (archive_import_tx, archive_import_rx) = channel::<PathBuf>();
+        spawn_archive_import_worker(archive_import_rx, event_sender.clone());
+
+        let mut toasts = Toasts::default();
+        notify_audio_fallback(
+            &mut toasts,
+            config.read().expect("failed to acquire read lock").audio_backend.as_deref(),
+            backend_name,
+        );
+
         let song_select = SongSelectionState::new(
             graphics.clone(), 
             event_sender.clone(),
             config.clone(),
-            skin_manager.clone()
+            skin_manager.clone(),
+            audio_info.clone(),
+            toasts.notifier(),
+            rng.split(),
         );
 
-        window.set_cursor_visible(false);
-
         Self {
             cursor_renderer: CursorRenderer::new(graphics.clone(), skin_manager.clone()),
+            particles: ParticleSystem::default(),
+            particle_renderer: ParticleRenderer::new(graphics.clone()),
+            last_replay_sample: f64::NEG_INFINITY,
             event_receiver,
             preempt: 0.0,
             fadein: 0.0,
             osu_renderer,
             window,
             current_beatmap: None,
+            current_beatmap_entry: None,
+            current_breaks: Vec::new(),
             egui,
             sl,
+            audio_info,
             osu_clock: Timer::new(),
-            objects_render_queue: Vec::with_capacity(20),
+            gameplay_renderer: GameplayRenderer::new(true),
             hit_objects: Vec::new(),
             skin_manager,
+            user_skin: None,
+            pending_skin: None,
+            beatmap_skin_dir: None,
+            prepared_beatmap_skin: None,
+            config,
             current_state: OsuStates::SongSelection,
             song_select,
             event_sender,
             input_processor: OsuProcessor::default(),
+            current_rules: GameplayRules::default(),
+            replay: None,
             current_hit_window: Default::default(),
             current_screen_size: Vector2::new(1.0, 1.0),
+            pending_resize: None,
+            applied_size: None,
+            playfield: PlayfieldTransform::new(1.0, 1.0),
+            cursor_playfield_pos: Vector2::new(0.0, 0.0),
             current_hit_circle_diameter: 1.0,
-            objects_judgments_render_queue: Vec::new(),
             current_audio: None,
             current_playing_audio: None,
+            audio_effects,
+            frame_history: FrameHistory::default(),
+            adaptive_quality: AdaptiveQuality::default(),
+            show_slider_debug: false,
+            modal_text: None,
+            current_play_end: 0.0,
+            session: SessionTracker::default(),
+            pace: None,
+            results: None,
+            pause: None,
+            held_keys: KeyboardState::empty(),
+            dropped_files: VecDeque::new(),
+            is_file_hovered: false,
+            archive_import_tx,
+            archives_queued: 0,
+            archives_imported: 0,
+            toasts,
+            key_overlay: KeyOverlayState::default(),
+            first_frame: None,
+            transition: Transition::default(),
+            is_clock_held: false,
         }
     }
 
+    /// Decodes skin on a separate thread, skin is
+    /// replaced once [`OsuStateEvent::SkinLoaded`] arrives
     pub fn open_skin(&mut self, path: impl AsRef<Path>) {
         let _span = tracy_client::span!("osu_state::open_skin");
-        let mut lock = self.skin_manager.write().expect("failed to acquire lock");
 
-        let skin = SkinManager::from_path(path, &self.osu_renderer.get_graphics());
+        let path = path.as_ref().to_path_buf();
+        let tx = self.event_sender.clone();
+        let notifier = self.toasts.notifier();
+
+        std::thread::spawn(move || {
+            let images = SkinImages::load(path, &notifier);
+            let _ = tx.send(OsuStateEvent::SkinLoaded(Box::new(images)));
+        });
+    }
+
+    /// Old skin stays in use until the new one is uploaded,
+    /// see [`Self::apply_ready_skin`]
+    fn apply_skin(&mut self, images: SkinImages) {
+        let _span = tracy_client::span!("osu_state::apply_skin");
 
+        self.pending_skin = Some(SkinManager::from_images_staged(images, &self.osu_renderer.get_graphics()));
+    }
+
+    fn apply_ready_skin(&mut self) {
+        if !self.pending_skin.as_ref().is_some_and(SkinManager::is_ready) {
+            return;
+        }
+
+        let _span = tracy_client::span!("osu_state::apply_ready_skin");
+
+        let skin = self.pending_skin.take().expect("pending skin should exist");
+
+        // Beatmap skin stays in use until the play is over
+        if self.user_skin.is_some() {
+            self.user_skin = Some(skin);
+            return;
+        }
+
+        let mut lock = self.skin_manager.write().expect("failed to acquire lock");
         *lock = skin;
     }
 
-    pub fn open_beatmap(&mut self, path: impl AsRef<Path>) {
+    /// Swaps in a skin with elements from the beatmap folder layered
+    /// over the user skin. Does nothing if beatmap doesn't ship any
+    /// or beatmap skins are ignored.
+    ///
+    /// Images are usually decoded ahead by the song select opener
+    /// worker, otherwise they are decoded on a separate thread and
+    /// swapped in once [`OsuStateEvent::BeatmapSkinLoaded`] arrives
+    fn apply_beatmap_skin(&mut self, beatmap_dir: &Path) {
+        let _span = tracy_client::span!("osu_state::apply_beatmap_skin");
+
+        // Retrying the same beatmap
+        if self.beatmap_skin_dir.as_deref() == Some(beatmap_dir) {
+            return;
+        }
+
+        self.restore_user_skin();
+
+        let ignore = self.config.read().expect("failed to acquire read lock").ignore_beatmap_skin;
+
+        if ignore || !has_skin_elements(beatmap_dir) {
+            return;
+        }
+
+        self.beatmap_skin_dir = Some(beatmap_dir.to_path_buf());
+
+        let skin_path = self.skin_manager.read().expect("failed to acquire read lock").path.clone();
+
+        // Skin could change since the images were decoded
+        let prepared = self.prepared_beatmap_skin.take()
+            .filter(|(dir, images)| dir == beatmap_dir && images.path == skin_path);
+
+        if let Some((_, images)) = prepared {
+            self.swap_beatmap_skin(images);
+            return;
+        }
+
+        let dir = beatmap_dir.to_path_buf();
+        let tx = self.event_sender.clone();
+        let notifier = self.toasts.notifier();
+
+        std::thread::spawn(move || {
+            let images = SkinImages::load_layered(SkinLayers {
+                beatmap: Some(&dir),
+                skin: &skin_path,
+            }, &notifier);
+
+            let _ = tx.send(OsuStateEvent::BeatmapSkinLoaded(dir, Box::new(images)));
+        });
+    }
+
+    fn on_beatmap_skin_loaded(&mut self, dir: PathBuf, images: SkinImages) {
+        // Play of this beatmap is already waiting for it
+        if self.beatmap_skin_dir.as_ref() == Some(&dir) && self.user_skin.is_none() {
+            self.swap_beatmap_skin(images);
+        } else {
+            self.prepared_beatmap_skin = Some((dir, images));
+        }
+    }
+
+    /// Textures are uploaded before taking the lock,
+    /// so rendering only waits for the swap itself
+    fn swap_beatmap_skin(&mut self, images: SkinImages) {
+        let _span = tracy_client::span!("osu_state::swap_beatmap_skin");
+
+        let skin = SkinManager::from_images(images, &self.osu_renderer.get_graphics());
+
+        let mut lock = self.skin_manager.write().expect("failed to acquire lock");
+        self.user_skin = Some(std::mem::replace(&mut *lock, skin));
+    }
+
+    /// Drops the beatmap skin, if any
+    fn restore_user_skin(&mut self) {
+        self.beatmap_skin_dir = None;
+
+        if let Some(skin) = self.user_skin.take() {
+            *self.skin_manager.write().expect("failed to acquire lock") = skin;
+        }
+    }
+
+    /// Returns `false` if beatmap can't be played.
+    ///
+    /// Already parsed `beatmap` is used when provided,
+    /// otherwise it's parsed from the `path`
+    pub fn open_beatmap(&mut self, path: impl AsRef<Path>, beatmap: Option<Arc<Beatmap>>) -> bool {
         let _span = tracy_client::span!("osu_state::open_beatmap");
-        self.osu_clock.reset_time();
-        self.osu_clock.unpause();
 
-        let map = match Beatmap::from_path(path.as_ref()) {
-            Ok(m) => m,
-            Err(e) => {
-                println!("Failed to parse beatmap");
-                println!("{}", e);
-                return;
-            }
+        let start = Instant::now();
+        let is_cached = beatmap.is_some();
+
+        // Every step below logs its timing inside of it, so
+        // loading regressions show up per step in the logs
+        let lifecycle = tracing::info_span!("open_beatmap", path = %path.as_ref().display(), cached = is_cached);
+        let _lifecycle = lifecycle.enter();
+
+        diagnostics::set_beatmap(Some(path.as_ref()));
+
+        let map = {
+            let _step = tracing::info_span!("parse").entered();
+            let step_start = Instant::now();
+
+            let map = match beatmap {
+                Some(m) => m,
+                None => match Beatmap::from_path(path.as_ref()) {
+                    Ok(m) => Arc::new(m),
+                    Err(e) => {
+                        tracing::error!("Failed to parse beatmap: {e}");
+                        self.modal_text = Some("Can't open beatmap".to_owned());
+                        return false;
+                    }
+                },
+            };
+
+            tracing::info!(elapsed_ms = elapsed_ms(step_start), "Parsed beatmap");
+            map
         };
 
+        // Convert rosu_map to our objects
+        let out_objects = {
+            let _step = tracing::info_span!("convert").entered();
+            let step_start = Instant::now();
+
+            let objects = match Object::from_rosu(&map) {
+                Ok(objects) => objects,
+                Err(e) => {
+                    tracing::error!("Failed to convert beatmap objects: {e}");
+                    self.modal_text = Some(format!("Can't play this beatmap: {e}"));
+                    return false;
+                }
+            };
+
+            tracing::info!(elapsed_ms = elapsed_ms(step_start), objects = objects.len(), "Converted beatmap");
+            objects
+        };
+
+        self.osu_clock.reset_time();
+        self.osu_clock.unpause();
+
+        // Preview voice is never resumed, so it's stopped
+        // instead of being left paused in the engine
         if let Some(audio_handle) = self.current_playing_audio.take() {
-            self.sl.pause(audio_handle);
+            self.sl.stop(audio_handle);
         }
 
         let beatmap_dir = path.as_ref().parent().expect("failed to get beatmap dir");
-        let audio_file = beatmap_dir.join(&map.audio_file);
-        
-        // We have to acknowlage the fact that there might be beatmaps
-        // without any audio files
-        if audio_file.is_file() {
-            let mut wav = audio::Wav::default();
-            wav.load(audio_file).unwrap(); // TODO handle error
-            self.set_audio(wav);
-            tracing::info!("Initialized a new audio file!");
-        }
+        self.apply_beatmap_skin(beatmap_dir);
+
+        self.load_beatmap_audio(beatmap_dir, &map.audio_file);
+
+        // Rules can't change in the middle of the play,
+        // watched replays keep the ones they were played with
+        let config = self.config.read().expect("failed to acquire read lock");
+        self.current_rules = self.replay.as_ref()
+            .map(ReplayCursor::rules)
+            .unwrap_or(config.rules);
+        let rotation = config.playfield_rotation;
+        self.audio_effects.set_enabled(config.audio_effects);
+        drop(config);
+
+        self.set_playfield_matrix(playfield_matrix(self.current_rules.hard_rock, rotation as f64));
 
-        let (preempt, fadein) = calculate_preempt_fadein(map.approach_rate);
-        let hit_window = HitWindow::from_od(map.overall_difficulty);
+        // Retrying from the pause menu unmuffles the music
+        self.audio_effects.set_state(AudioState::Normal);
+
+        let difficulty = Difficulty::from_beatmap(&map).with_rules(&self.current_rules);
+
+        let (preempt, fadein) = calculate_preempt_fadein(difficulty.ar);
+        let hit_window = converter_for(map.mode)
+            .expect("conversion already checked the mode")
+            .hit_window(&difficulty);
 
         self.preempt = preempt;
         self.fadein = fadein;
         self.current_hit_window = hit_window;
 
-        // Convert rosu_map to our objects
-        let out_objects = Object::from_rosu(&map);
+        self.current_play_end = out_objects.iter()
+            .map(|obj| match &obj.kind {
+                ObjectKind::Circle(circle) => circle.start_time + self.current_hit_window.x50,
+                ObjectKind::Slider(slider) => slider.start_time + slider.duration,
+            })
+            .fold(0.0, f64::max);
+
+        // Dropping leftovers from the previous attempt, watched
+        // replays already got a fresh processor with their frames
+        if self.replay.is_some() {
+            self.input_processor.take_score();
+        } else {
+            self.input_processor.reset();
+        }
+        self.key_overlay.reset();
+        self.particles.clear();
+        self.last_replay_sample = f64::NEG_INFINITY;
 
         self.hit_objects = out_objects;
 
+        self.current_breaks = Break::from_rosu(&map);
+        self.input_processor.set_breaks(self.current_breaks.clone());
+
+        let objects_amount = self.hit_objects.len();
+        self.gameplay_renderer.reserve(objects_amount);
+        self.osu_renderer.reserve_instances(objects_amount);
+
         self.current_beatmap = Some(map);
         self.apply_beatmap_transformations();
 
         if let Some(audio) = &self.current_audio {
             self.current_playing_audio = Some(self.sl.play(audio));
         }
+
+        // Replays can be restarted at a different rate
+        self.set_playback_rate(self.osu_clock.rate());
+
+        tracing::info!(elapsed_ms = elapsed_ms(start), "Opened beatmap");
+
+        self.first_frame = Some((lifecycle.clone(), start));
+
+        true
+    }
+
+    /// Beatmap plays silently if its audio is missing or broken,
+    /// audio of the previous beatmap is never kept
+    fn load_beatmap_audio(&mut self, beatmap_dir: &Path, audio_filename: &str) {
+        let _span = tracy_client::span!("osu_state::load_beatmap_audio");
+        let _step = tracing::info_span!("audio").entered();
+        let step_start = Instant::now();
+
+        self.current_audio = None;
+
+        // Beatmaps without audio at all are fine
+        if audio_filename.is_empty() {
+            return;
+        }
+
+        let audio_file = beatmap_dir.join(audio_filename);
+
+        if !audio_file.is_file() {
+            tracing::warn!("Audio file is missing: {}", audio_file.display());
+            self.toasts.push_with(Severity::Error, tf("notify.audio_missing", &[&audio_filename]));
+            return;
+        }
+
+        let mut wav = Wav::default();
+
+        if let Err(e) = wav.load(&audio_file) {
+            tracing::error!("Failed to load audio {}: {e:?}", audio_file.display());
+            self.toasts.push_with(Severity::Error, tf("notify.audio_decode_failed", &[&audio_filename]));
+            return;
+        }
+
+        self.audio_effects.attach(&mut wav);
+        self.set_audio(wav);
+
+        tracing::info!(elapsed_ms = elapsed_ms(step_start), "Loaded audio");
+    }
+
+    /// Closes the beatmap lifecycle span, see [`Self::open_beatmap`]
+    fn on_gameplay_frame_rendered(&mut self) {
+        let Some((lifecycle, start)) = self.first_frame.take() else {
+            return;
+        };
+
+        let _lifecycle = lifecycle.enter();
+        tracing::info!(elapsed_ms = elapsed_ms(start), "First frame rendered");
+    }
+
+    /// Reinitializes audio engine with another backend,
+    /// currently playing audio continues from the same position
+    pub fn change_audio_backend(&mut self, name: &str) {
+        let _span = tracy_client::span!("osu_state::change_audio_backend");
+
+        let position = self.current_playing_audio.take().map(|handle| {
+            let position = self.sl.stream_position(handle);
+            self.sl.stop(handle);
+            position
+        });
+
+        let (sl, backend_name) = audio::init_soloud(Some(name));
+        self.sl = sl;
+
+        notify_audio_fallback(&mut self.toasts, Some(name), backend_name);
+
+        *self.audio_info.write().expect("failed to acquire write lock") = AudioInfo::new(backend_name, &self.sl);
+
+        {
+            let mut config = self.config.write().expect("failed to acquire write lock");
+            config.audio_backend = Some(backend_name.to_owned());
+
+            if let Err(e) = config.save(CONFIG_PATH) {
+                tracing::error!("Failed to save settings: {e}");
+            }
+        }
+
+        if let (Some(position), Some(audio)) = (position, &self.current_audio) {
+            let handle = self.sl.play(audio);
+            self.sl.set_pause(handle, true);
+            if let Err(e) = self.sl.seek(handle, position) {
+                tracing::error!("Failed to seek audio after backend change: {e:?}");
+            }
+            self.sl.set_pause(handle, false);
+
+            self.current_playing_audio = Some(handle);
+        }
+    }
+
+    /// Picks up volume settings. Gameplay music volume is applied by
+    /// the audio effects every frame, previews only get `master × music`
+    fn sync_music_volume(&mut self) {
+        let volume = self.config.read().expect("failed to acquire read lock").volume;
+        self.audio_effects.set_volume(volume);
+
+        if matches!(self.current_state, OsuStates::Playing) {
+            return;
+        }
+
+        if let Some(handle) = self.current_playing_audio {
+            self.sl.set_volume(handle, self.audio_effects.music_volume());
+        }
+    }
+
+    /// Position of the song select preview, `None` once it's finished
+    fn preview_progress(&self) -> Option<PreviewProgress> {
+        let handle = self.current_playing_audio?;
+        let audio = self.current_audio.as_ref()?;
+
+        if !self.sl.is_valid_voice_handle(handle) {
+            return None;
+        }
+
+        Some(PreviewProgress {
+            position: self.sl.stream_position(handle),
+            length: audio.length(),
+        })
     }
 
     pub fn set_audio(&mut self, audio: Wav) {
@@ -179,47 +672,141 @@ impl<'s> OsuState<'s> {
 
     pub fn apply_beatmap_transformations(&mut self) {
         let _span = tracy_client::span!("osu_state::apply_beatmap_transformations");
-        let cs = match &self.current_beatmap {
-            Some(beatmap) => beatmap.circle_size,
-            None => 4.0,
-        };
+        let difficulty = self.current_beatmap.as_ref()
+            .map(|beatmap| Difficulty::from_beatmap(beatmap).with_rules(&self.current_rules));
+
+        let cs = difficulty.map(|x| x.cs).unwrap_or(4.0);
 
         self.osu_renderer.on_cs_change(cs);
-        self.current_hit_circle_diameter = calc_hitcircle_diameter(cs);
+        self.current_hit_circle_diameter = self.current_beatmap.as_ref()
+            .zip(difficulty)
+            .and_then(|(beatmap, difficulty)| {
+                converter_for(beatmap.mode).map(|x| x.hit_circle_diameter(&difficulty))
+            })
+            .unwrap_or_else(|| calc_hitcircle_diameter(cs));
     }
 
     pub fn resize(&mut self, new_size: &PhysicalSize<u32>) {
         let _span = tracy_client::span!("osu_state::resize");
         self.current_screen_size.x = new_size.width as f32;
         self.current_screen_size.y = new_size.height as f32;
+        self.update_playfield();
+
+        // Keeping gameplay cursor at the same playfield position
+        if let OsuStates::Playing = self.current_state {
+            self.move_gameplay_cursor(self.cursor_playfield_pos);
+        }
+
+        self.pending_resize = Some(*new_size);
+    }
+
+    /// Resizes surface and renderers to the latest size from [`Self::resize`],
+    /// called once per frame. Minimized window has nothing to resize to
+    pub fn apply_pending_resize(&mut self) {
+        let Some(new_size) = self.pending_resize.take() else {
+            return;
+        };
+
+        if new_size.width == 0 || new_size.height == 0 || self.applied_size == Some(new_size) {
+            return;
+        }
+
+        let _span = tracy_client::span!("osu_state::apply_pending_resize");
+
+        self.applied_size = Some(new_size);
+
+        self.cursor_renderer.on_resize(&new_size);
+        self.particle_renderer.on_resize(&new_size);
+        self.osu_renderer.on_resize(&new_size);
+        self.song_select.on_resize(&new_size);
+    }
+
+    /// Surface no longer matches the window, e.g. the window
+    /// was resized after the size for this frame was applied
+    pub fn on_surface_outdated(&mut self) {
+        let _span = tracy_client::span!("osu_state::on_surface_outdated");
 
-        self.cursor_renderer.on_resize(new_size);
-        self.osu_renderer.on_resize(new_size);
-        self.song_select.on_resize(new_size);
+        self.applied_size = None;
+        self.resize(&self.window.inner_size());
+        self.apply_pending_resize();
+    }
+
+    /// Window moved to a monitor with different DPI, physical size
+    /// changes with it and `Resized` is not guaranteed to follow
+    pub fn on_scale_factor_changed(&mut self, scale_factor: f64) {
+        let _span = tracy_client::span!("osu_state::on_scale_factor_changed");
+
+        let new_size = self.window.inner_size();
+        tracing::info!("Scale factor changed to {scale_factor}, new size: {}x{}", new_size.width, new_size.height);
+
+        self.resize(&new_size);
+    }
+
+    /// Recreates window surface, used when surface is lost
+    pub fn recreate_surface(&mut self) -> Result<(), PresentError> {
+        let _span = tracy_client::span!("osu_state::recreate_surface");
+
+        self.osu_renderer.get_graphics().recreate_surface(self.window.clone())
     }
 
     pub fn on_pressed_down(
         &mut self, 
         key_code: KeyCode, 
-        is_cntrl_pressed: bool
+        is_cntrl_pressed: bool,
+        is_shift_pressed: bool,
     ) {
         let _span = tracy_client::span!("osu_state::on_pressed_down");
+
+        if key_code == KeyCode::KeyF && is_cntrl_pressed {
+            self.frame_history.toggle();
+            return;
+        }
+
+        if key_code == KeyCode::KeyD && is_cntrl_pressed {
+            self.show_slider_debug = !self.show_slider_debug;
+            return;
+        }
+
+        // Key that started the transition shouldn't leak into the next screen
+        if self.transition.is_active() {
+            return;
+        }
+
         match self.current_state {
             OsuStates::Playing => {
                 if key_code == KeyCode::Escape {
-                    self.event_sender.send(OsuStateEvent::ToSongSelection)
-                        .expect("Failed to send ToSongSelection event to the OsuState");
+                    self.toggle_pause();
+                    return;
+                }
+
+                if self.replay.is_some() {
+                    self.on_replay_key_pressed(key_code);
+                    return;
                 }
                 
                 let ts = self.osu_clock.since_start();
 
+                // Inputs during pause and countdown are not judged
+                let is_paused = self.pause.is_some();
+
                 if key_code == KeyCode::KeyZ {
                     let state = KeyboardState {
                         k1: true,
                         k2: false,
                     };
 
-                    self.input_processor.store_keyboard_pressed(ts, state);
+                    let is_new_press = !self.held_keys.k1;
+
+                    self.held_keys.k1 = true;
+                    self.cursor_renderer.on_key_pressed(state);
+
+                    if !is_paused {
+                        self.input_processor.store_keyboard_pressed(ts, state);
+
+                        if is_new_press {
+                            self.spawn_ripple(self.cursor_playfield_pos, ts);
+                        }
+                    }
                 }
 
                 if key_code == KeyCode::KeyX {
@@ -228,28 +815,57 @@ impl<'s> OsuState<'s> {
                         k2: true,
                     };
 
-                    self.input_processor.store_keyboard_pressed(ts, state);
+                    let is_new_press = !self.held_keys.k2;
+
+                    self.held_keys.k2 = true;
+                    self.cursor_renderer.on_key_pressed(state);
+
+                    if !is_paused {
+                        self.input_processor.store_keyboard_pressed(ts, state);
+
+                        if is_new_press {
+                            self.spawn_ripple(self.cursor_playfield_pos, ts);
+                        }
+                    }
                 }
             },
             OsuStates::SongSelection => {
-                self.song_select.on_pressed_down(key_code, is_cntrl_pressed);
+                self.song_select.on_pressed_down(key_code, is_cntrl_pressed, is_shift_pressed);
+            },
+            OsuStates::Results => {
+                if key_code == KeyCode::Escape {
+                    self.event_sender.send(OsuStateEvent::ToSongSelection)
+                        .expect("Failed to send ToSongSelection event to the OsuState");
+                }
             },
         }
     }
 
     pub fn on_pressed_release(&mut self, key_code: KeyCode) {
         let _span = tracy_client::span!("osu_state::on_pressed_release");
+
+        if self.transition.is_active() {
+            return;
+        }
+
         match self.current_state {
-            OsuStates::Playing => {
+            OsuStates::Playing if self.replay.is_none() => {
 
                 let ts = self.osu_clock.since_start();
+                let is_paused = self.pause.is_some();
+
                 if key_code == KeyCode::KeyZ {
                     let state = KeyboardState {
                         k1: true,
                         k2: false,
                     };
 
-                    self.input_processor.store_keyboard_released(ts, state);
+                    self.held_keys.k1 = false;
+                    self.cursor_renderer.on_key_released(state);
+
+                    if !is_paused {
+                        self.input_processor.store_keyboard_released(ts, state);
+                    }
                 }
 
                 if key_code == KeyCode::KeyX {
@@ -258,7 +874,12 @@ impl<'s> OsuState<'s> {
                         k2: true,
                     };
 
-                    self.input_processor.store_keyboard_released(ts, state);
+                    self.held_keys.k2 = false;
+                    self.cursor_renderer.on_key_released(state);
+
+                    if !is_paused {
+                        self.input_processor.store_keyboard_released(ts, state);
+                    }
                 }
             }
             _ => {}
@@ -267,169 +888,842 @@ impl<'s> OsuState<'s> {
 
     pub fn on_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
         let _span = tracy_client::span!("osu_state::on_cursor_moved");
-        self.cursor_renderer.on_cursor_moved(position);
 
         match self.current_state {
-            OsuStates::Playing => {
+            // Cursor follows the replay instead
+            OsuStates::Playing if self.replay.is_none() => {
                 let ts = self.osu_clock.since_start();
 
-                let mut recv_pos = Vector2::new(position.x as f32, position.y as f32);
-                let (scale, offsets) = calc_playfield(self.current_screen_size.x, self.current_screen_size.y);
+                let pos = self.playfield.to_playfield(Vector2::new(position.x, position.y));
 
-                recv_pos -= offsets;
-                recv_pos /= scale;
-                
-                let pos = Vector2::new(recv_pos.x as f64, recv_pos.y as f64);
+                self.cursor_playfield_pos = pos;
+                self.move_gameplay_cursor(pos);
+
+                if self.pause.is_some() || self.transition.is_active() {
+                    return;
+                }
 
-                self.input_processor.store_cursor_moved(ts, pos);
+                // Clicking around the debug window or a modal
+                // shouldn't move the judged cursor
+                if self.is_ui_capturing_pointer() {
+                    self.input_processor.store_ignored(ts);
+                } else {
+                    self.input_processor.store_cursor_moved(ts, pos);
+                }
             },
             _ => {},
         }
     }
 
-    pub fn update_egui(&mut self, input: RawInput) {
-        let _span = tracy_client::span!("osu_state::update_egui");
-
-        self.egui.state.egui_ctx().begin_pass(input);
-
-        egui::Window::new("Debug Gameplay Window")
-            .resizable(false)
-            .show(&self.egui.state.egui_ctx(), |ui| {
+    pub fn on_file_hovered(&mut self, is_hovered: bool) {
+        self.is_file_hovered = is_hovered;
+    }
 
-            if let Some(beatmap) = &self.current_beatmap {
-                ui.add(egui::Label::new(format!("{}", self.osu_clock.get_time())));
+    /// Every dropped file arrives as a separate event,
+    /// so they are queued and opened in order
+    pub fn on_file_dropped(&mut self, path: PathBuf) {
+        let _span = tracy_client::span!("osu_state::on_file_dropped");
 
-                if ui.add(
-                    Slider::new(
-                        &mut self.osu_clock.last_time,
-                        1.0..=(beatmap.hit_objects.last().unwrap().start_time),
-                    )
-                    .step_by(1.0),
-                ).changed() {
-                    self.osu_clock.pause();
-                    //self.sink.try_seek(Duration::from_millis(self.osu_clock.get_time().round() as u64)).unwrap();
-                    self.osu_clock.unpause();
-                };
+        tracing::info!("Dropped file: {}", path.display());
 
-                if !self.osu_clock.is_paused() {
-                    if ui.add(egui::Button::new("pause")).clicked() {
-                        self.osu_clock.pause();
-                        //self.sink.pause();
-                    }
-                } else {
-                    if ui.add(egui::Button::new("unpause")).clicked() {
-                        //self.sink.try_seek(Duration::from_millis(self.osu_clock.get_time().round() as u64)).unwrap();
-                        self.osu_clock.unpause();
-                        //self.sink.play();
-                    }
-                }
-            }
-        });
+        self.is_file_hovered = false;
+        self.dropped_files.push_back(path);
+    }
 
-        let output = self.egui.state.egui_ctx().end_pass();
+    fn open_dropped_file(&mut self, path: PathBuf) {
+        let _span = tracy_client::span!("osu_state::open_dropped_file");
 
-        self.egui.state.handle_platform_output(
-            &self.window,
-            output.platform_output.to_owned(),
-        );
+        let file_name = path.file_name()
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_default();
 
-        self.egui.output = Some(output);
-    }
+        match route_dropped_file(&path) {
+            DroppedFileKind::Beatmap => self.start_beatmap_file(path),
+            DroppedFileKind::BeatmapArchive => {
+                self.archives_queued += 1;
+                self.toasts.push(tf("drop.importing", &[&file_name]));
 
-    pub fn process_inputs(&mut self, _process_time: f64) {
-        let _span = tracy_client::span!("osu_state::process_inputs");
+                let _ = self.archive_import_tx.send(path);
+            },
+            DroppedFileKind::Replay => self.watch_replay(path),
+            DroppedFileKind::Unknown => {
+                self.toasts.push(tf("drop.unknown", &[&file_name]));
+            },
+        }
     }
 
-    // Going through every object on beatmap and preparing it to
-    // assigned buffers
-    pub fn prepare_objects_for_renderer(&mut self, time: f64) {
-        let _span = tracy_client::span!("osu_state::prepare_objects_for_renderer");
+    /// Starts .osu directly without adding it to the DB
+    fn start_beatmap_file(&mut self, path: PathBuf) {
+        let buff = match std::fs::read(&path) {
+            Ok(buff) => buff,
+            Err(e) => {
+                tracing::error!("Failed to read {}: {e}", path.display());
+                self.modal_text = Some("Can't open beatmap".to_owned());
+                return;
+            },
+        };
+
+        let map = match Beatmap::from_bytes(&buff) {
+            Ok(map) => map,
+            Err(e) => {
+                tracing::error!("Failed to parse beatmap: {e}");
+                self.modal_text = Some("Can't open beatmap".to_owned());
+                return;
+            },
+        };
+
+        let hash = format!("{:x}", md5::compute(&buff));
+        let entry = DbBeatmapEntry::from_beatmap(path, hash, &map);
+
+        self.event_sender.send(OsuStateEvent::StartBeatmap(Arc::new(entry), Some(Arc::new(map))))
+            .expect("Failed to send StartBeatmap event to the OsuState");
+    }
+
+    /// Plays back .osr with the same rendering as a live play,
+    /// its beatmap has to be in the database
+    pub fn watch_replay(&mut self, path: impl AsRef<Path>) {
+        let _span = tracy_client::span!("osu_state::watch_replay");
+
+        let path = path.as_ref();
+        let file_name = path.file_name()
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let Ok(replay) = Replay::open(path) else {
+            tracing::error!("Failed to open replay {}", path.display());
+            self.toasts.push(tf("replay.open_failed", &[&file_name]));
+            return;
+        };
+
+        let Some(entry) = self.song_select.find_beatmap_by_hash(&replay.map_hash) else {
+            tracing::warn!("No beatmap with hash {} for replay {}", replay.map_hash, path.display());
+            self.toasts.push(tf("replay.beatmap_not_found", &[&file_name]));
+            return;
+        };
+
+        let rules = match std::fs::read(path).map(|data| parse_replay_mods(&data)) {
+            Ok(Ok(mods)) => GameplayRules::from_replay_mods(mods),
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to read mods of replay {}: {e}", path.display());
+                GameplayRules::default()
+            },
+            Err(e) => {
+                tracing::warn!("Failed to read replay {}: {e}", path.display());
+                GameplayRules::default()
+            },
+        };
+
+        let processor: OsuProcessor = replay.into();
+        let cursor = ReplayCursor::with_rules(processor.queued_inputs().to_vec(), rules);
+
+        self.event_sender.send(OsuStateEvent::WatchReplay(Arc::new(entry), Box::new(cursor)))
+            .expect("Failed to send WatchReplay event to the OsuState");
+    }
+
+    /// Starts tracking a play for the session stats, watched replays are skipped
+    fn start_play(&mut self, retry: bool) {
+        self.pace = None;
+
+        if self.replay.is_some() {
+            return;
+        }
+
+        self.pace = self.current_beatmap_entry.as_ref()
+            .and_then(|entry| self.song_select.best_progression(&entry.hash));
+
+        if let Some(play) = self.session.start(retry, self.input_processor.score()) {
+            self.song_select.record_play(play);
+        }
+    }
+
+    fn finish_play(&mut self, completed: bool) {
+        if let Some(play) = self.session.finish(completed, self.input_processor.score()) {
+            self.song_select.record_play(play);
+        }
+    }
+
+    /// Encodes the finished play and stores it as a local score,
+    /// the database keeps the replay only for the best one
+    fn export_replay(&self, title: &str, score: &Score) -> Option<ExportedReplay> {
+        let _span = tracy_client::span!("osu_state::export_replay");
+
+        if self.replay.is_some() {
+            return None;
+        }
+
+        let entry = self.current_beatmap_entry.as_ref()?;
+        let timestamp = unix_now();
+
+        let data = encode_replay(&ReplayInfo {
+            beatmap_hash: &entry.hash,
+            player: LOCAL_PLAYER,
+            score,
+            frames: self.input_processor.recorded_inputs(),
+            timestamp,
+        });
+
+        let data = match data {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::error!("Failed to encode replay: {e}");
+                return None;
+            },
+        };
+
+        self.song_select.store_local_score(LocalScore {
+            beatmap_hash: entry.hash.clone(),
+            replay_hash: format!("{:x}", md5::compute(&data)),
+            player: LOCAL_PLAYER.to_owned(),
+            score: score.points() as i64,
+            max_combo: score.max_combo,
+            x300: score.x300,
+            x100: score.x100,
+            x50: score.x50,
+            miss: score.miss,
+            perfect: score.miss == 0,
+            mods: score.rules.replay_mods(),
+            timestamp,
+            progression: Progression::from_score(score).to_bytes(),
+        }, data.clone());
+
+        Some(ExportedReplay {
+            file_name: replay_file_name(title, LOCAL_PLAYER, timestamp),
+            data,
+        })
+    }
+
+    /// Leaves replay watching, processor is replaced so
+    /// leftover replay frames don't end up in the next play
+    fn stop_watching(&mut self) {
+        if self.replay.take().is_none() {
+            return;
+        }
+
+        self.input_processor = OsuProcessor::default();
+        self.set_playback_rate(1.0);
+    }
+
+    fn on_replay_key_pressed(&mut self, key_code: KeyCode) {
+        match key_code {
+            KeyCode::ArrowLeft => self.seek_replay(-REPLAY_SEEK_STEP),
+            KeyCode::ArrowRight => self.seek_replay(REPLAY_SEEK_STEP),
+            KeyCode::ArrowUp => self.step_replay_rate(1),
+            KeyCode::ArrowDown => self.step_replay_rate(-1),
+            _ => {},
+        }
+    }
+
+    /// Moves replay playback by `offset` ms. Judgements can't be undone,
+    /// so seeking back judges everything again from the beginning
+    fn seek_replay(&mut self, offset: f64) {
+        let _span = tracy_client::span!("osu_state::seek_replay");
+
+        let Some(replay) = &self.replay else {
+            return;
+        };
 
-        for (i, obj) in self.hit_objects.iter_mut().enumerate().rev() {
-            self.objects_judgments_render_queue.push(i);
+        let time = self.osu_clock.update();
+        let target = (time + offset).clamp(0.0, self.current_play_end.max(0.0));
 
-            if !obj.is_visible(time, self.preempt, &self.current_hit_window) {
-                continue;
+        if target < time {
+            let Some(map) = &self.current_beatmap else {
+                return;
+            };
+
+            match Object::from_rosu(map) {
+                Ok(objects) => self.hit_objects = objects,
+                Err(e) => {
+                    tracing::error!("Failed to convert beatmap objects: {e}");
+                    return;
+                },
             }
 
-            match &mut obj.kind {
-                ObjectKind::Slider(slider) => {
-                    self.osu_renderer.prepare_and_render_slider_texture(slider);
-                }
-                _ => {},
+            self.input_processor = replay.processor();
+            self.input_processor.set_breaks(self.current_breaks.clone());
+        }
+
+        self.osu_clock.set_time(target);
+
+        // Otherwise audio is seeked once countdown is over
+        if let (Some(handle), None) = (self.current_playing_audio, &self.pause) {
+            if let Err(e) = self.sl.seek(handle, target / 1000.0) {
+                tracing::error!("Failed to seek replay audio: {e:?}");
             }
+        }
+    }
+
+    fn step_replay_rate(&mut self, step: isize) {
+        let current = REPLAY_RATES.iter()
+            .position(|x| *x == self.osu_clock.rate())
+            .unwrap_or(3);
+
+        let index = current.saturating_add_signed(step).min(REPLAY_RATES.len() - 1);
+
+        self.set_playback_rate(REPLAY_RATES[index]);
+    }
+
+    /// Clock and audio always play at the same rate
+    fn set_playback_rate(&mut self, rate: f64) {
+        self.osu_clock.set_rate(rate);
 
-            self.objects_render_queue.push(i);
+        if let Some(handle) = self.current_playing_audio {
+            if let Err(e) = self.sl.set_relative_play_speed(handle, rate as f32) {
+                tracing::error!("Failed to change audio rate: {e:?}");
+            }
+        }
+    }
+
+    /// Moves rendered cursor along the replay, keys
+    /// are shown the same way as the live ones. Rendering
+    /// only, processor keeps judging the real frames
+    fn update_replay_cursor(&mut self, time: f64) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+
+        let InterpolatedFrame { pos, keys, .. } = replay.sample(time);
+
+        // Seeking back starts over, presses older
+        // than a ripple's lifetime wouldn't be visible
+        if time < self.last_replay_sample {
+            self.particles.clear();
+        }
+
+        let from = self.last_replay_sample.max(time - ParticleKind::CursorRipple.lifetime());
+        self.last_replay_sample = time;
+
+        if self.config.read().expect("failed to acquire read lock").cursor.ripples {
+            for frame in replay.presses_between(from, time) {
+                self.particles.spawn(frame.pos, frame.ts, ParticleKind::CursorRipple);
+            }
         }
-        
-        self.osu_renderer.prepare_judgements(
-            time, 
-            &self.objects_judgments_render_queue, 
-            &self.hit_objects,
+
+        self.cursor_renderer.on_key_released(KeyboardState { k1: !keys.k1, k2: !keys.k2 });
+        self.cursor_renderer.on_key_pressed(keys);
+        self.key_overlay.update(keys);
+
+        self.cursor_playfield_pos = pos;
+        self.move_gameplay_cursor(pos);
+    }
+
+    /// Spawned along with the press the processor gets,
+    /// so the ripple starts exactly when it's judged
+    fn spawn_ripple(&mut self, pos: Vector2<f64>, ts: f64) {
+        if self.config.read().expect("failed to acquire read lock").cursor.ripples {
+            self.particles.spawn(pos, ts, ParticleKind::CursorRipple);
+        }
+    }
+
+    /// Drawn under the cursor, through the same playfield transform
+    fn render_particles(&mut self, view: &TextureView) {
+        let _span = tracy_client::span!("osu_state::render_particles");
+
+        let scale = self.config.read().expect("failed to acquire read lock").cursor.ripple_scale;
+        let playfield = self.playfield;
+
+        self.particle_renderer.prepare(
+            &self.particles,
+            self.osu_clock.get_time(),
+            scale,
+            |x| playfield.to_screen(x),
         );
 
-        self.osu_renderer.prepare_objects(
-            time, self.preempt, self.fadein,
-            &self.objects_render_queue, 
-            &self.hit_objects,
-            &self.current_hit_window
+        let skin = self.skin_manager.read().expect("failed to acquire skin lock");
+        self.particle_renderer.render_on_view(view, &skin.cursor_ripple.bind_group);
+    }
+
+    /// Pointer is over egui or a modal is up
+    fn is_ui_capturing_pointer(&self) -> bool {
+        self.modal_text.is_some() || self.egui.state.egui_ctx().wants_pointer_input()
+    }
+
+    /// Feeds the last frame into adaptive quality, dropped
+    /// visuals reach renderers through the config
+    fn update_adaptive_quality(&mut self) {
+        let Some(frame_time) = self.frame_history.last_frame_time() else {
+            return;
+        };
+
+        let mode = self.config.read().expect("failed to acquire read lock").adaptive_quality;
+
+        let before = self.adaptive_quality.level();
+        self.adaptive_quality.set_mode(mode);
+        self.adaptive_quality.push_frame_time(frame_time);
+
+        let level = self.adaptive_quality.level();
+
+        if level == before {
+            return;
+        }
+
+        self.config.write().expect("failed to acquire write lock").quality = level;
+        self.cursor_renderer.set_trail_density(level.cursor_trail_density());
+        self.frame_history.set_quality_level(level);
+    }
+
+    /// Playfield for the current window size and configured layout,
+    /// renderer gets the same one on resize
+    fn update_playfield(&mut self) {
+        let layout = self.config.read().expect("failed to acquire read lock").playfield_layout();
+
+        self.playfield = PlayfieldTransform::with_layout(self.current_screen_size.x, self.current_screen_size.y, &layout)
+            .with_matrix(self.playfield.matrix());
+    }
+
+    /// Input and rendering always share the same playfield matrix
+    fn set_playfield_matrix(&mut self, matrix: Matrix3<f64>) {
+        self.playfield = self.playfield.with_matrix(matrix);
+        self.osu_renderer.set_playfield_matrix(matrix);
+    }
+
+    /// Renders cursor through the playfield transform, so sprite
+    /// and the judged position always match
+    fn move_gameplay_cursor(&mut self, playfield_pos: Vector2<f64>) {
+        let screen_pos = self.playfield.to_screen(PlayfieldTransform::clamp(playfield_pos));
+
+        self.cursor_renderer.on_cursor_moved(
+            PhysicalPosition::new(screen_pos.x, screen_pos.y)
         );
+    }
+
+    /// OS cursor is hidden only during gameplay, every
+    /// other state relies on egui interactions
+    fn set_state(&mut self, state: OsuStates) {
+        diagnostics::set_state(format!("{state:?}"));
+
+        if matches!(state, OsuStates::SongSelection) {
+            diagnostics::set_beatmap(None);
+        }
+
+        self.window.set_cursor_visible(!matches!(state, OsuStates::Playing));
+        self.current_state = state;
+    }
+
+    /// Keeps clock and audio at the start of the play
+    /// while the playfield fades in, so nothing is missed
+    fn hold_clock(&mut self) {
+        self.osu_clock.pause();
+
+        if let Some(handle) = self.current_playing_audio {
+            self.sl.set_pause(handle, true);
+        }
+
+        self.is_clock_held = true;
+    }
+
+    fn release_clock(&mut self) {
+        if !std::mem::take(&mut self.is_clock_held) {
+            return;
+        }
+
+        let time = self.osu_clock.get_time();
+
+        if let Some(handle) = self.current_playing_audio {
+            if let Err(e) = self.sl.seek(handle, (time / 1000.0).max(0.0)) {
+                tracing::error!("Failed to seek audio after transition: {e:?}");
+            }
+            self.sl.set_pause(handle, false);
+        }
+
+        self.osu_clock.unpause();
+    }
+
+    /// Escape pauses gameplay, resumes from the menu
+    /// and goes back to the menu during countdown
+    fn toggle_pause(&mut self) {
+        match self.pause.as_ref().map(PauseMenu::state) {
+            None => self.pause_gameplay(),
+            Some(PauseState::Menu) => {
+                if let Some(pause) = &mut self.pause {
+                    pause.start_countdown();
+                }
+                self.window.set_cursor_visible(false);
+            },
+            Some(PauseState::Countdown { .. }) => {
+                if let Some(pause) = &mut self.pause {
+                    pause.show_menu();
+                }
+                self.window.set_cursor_visible(true);
+            },
+        }
+    }
+
+    /// Stops clock and audio and shows the pause menu
+    fn pause_gameplay(&mut self) {
+        let _span = tracy_client::span!("osu_state::pause_gameplay");
+
+        self.osu_clock.update();
+        self.osu_clock.pause();
+
+        if let Some(handle) = self.current_playing_audio {
+            // Music keeps playing muffled, resume seeks it back to the clock
+            if self.audio_effects.is_enabled() {
+                self.audio_effects.set_state(AudioState::Paused);
+            } else {
+                self.sl.set_pause(handle, true);
+            }
+        }
+
+        self.pause = Some(PauseMenu::new(self.event_sender.clone()));
+        self.window.set_cursor_visible(true);
+    }
+
+    /// Continues gameplay once countdown is over, audio is
+    /// seeked to the clock so both start from the same point
+    fn resume_gameplay(&mut self) {
+        let _span = tracy_client::span!("osu_state::resume_gameplay");
+
+        self.pause = None;
+
+        let time = self.osu_clock.get_time();
 
-        // Syncing osu state settings with the osu renderer
-        self.osu_renderer.prepare();
+        if let Some(handle) = self.current_playing_audio {
+            if let Err(e) = self.sl.seek(handle, (time / 1000.0).max(0.0)) {
+                tracing::error!("Failed to seek audio on resume: {e:?}");
+            }
+            self.sl.set_pause(handle, false);
+        }
+
+        self.audio_effects.set_state(AudioState::Normal);
+        self.osu_clock.unpause();
 
-        // When we are done preparing all objects for rendering
-        // we should not forget to upload all that to gpu
-        self.osu_renderer.write_buffers();
+        // Inputs were not recorded while paused
+        if self.replay.is_none() {
+            let ts = self.osu_clock.since_start();
+            self.input_processor.store_resume(ts, self.held_keys);
+        }
+    }
+
+    pub fn update_egui(&mut self, input: RawInput) {
+        let _span = tracy_client::span!("osu_state::update_egui");
+
+        self.egui.state.egui_ctx().begin_pass(input);
+
+        egui::Window::new("Debug Gameplay Window")
+            .resizable(false)
+            .show(&self.egui.state.egui_ctx(), |ui| {
+
+            if let Some(beatmap) = &self.current_beatmap {
+                ui.add(egui::Label::new(format!("{}", self.osu_clock.get_time())));
+
+                let mut time = self.osu_clock.get_time();
+
+                if ui.add(
+                    Slider::new(
+                        &mut time,
+                        1.0..=(beatmap.hit_objects.last().unwrap().start_time),
+                    )
+                    .step_by(1.0),
+                ).changed() {
+                    //self.sink.try_seek(Duration::from_millis(time.round() as u64)).unwrap();
+                    self.osu_clock.set_time(time);
+                };
+
+                if !self.osu_clock.is_paused() {
+                    if ui.add(egui::Button::new("pause")).clicked() {
+                        self.osu_clock.pause();
+                        //self.sink.pause();
+                    }
+                } else {
+                    if ui.add(egui::Button::new("unpause")).clicked() {
+                        //self.sink.try_seek(Duration::from_millis(self.osu_clock.get_time().round() as u64)).unwrap();
+                        self.osu_clock.unpause();
+                        //self.sink.play();
+                    }
+                }
+            }
+        });
+
+        let output = self.egui.state.egui_ctx().end_pass();
+
+        self.egui.state.handle_platform_output(
+            &self.window,
+            output.platform_output.to_owned(),
+        );
+
+        self.egui.output = Some(output);
+    }
+
+    pub fn process_inputs(&mut self, _process_time: f64) {
+        let _span = tracy_client::span!("osu_state::process_inputs");
+    }
+
+    pub fn prepare_objects_for_renderer(&mut self, time: f64) {
+        let _span = tracy_client::span!("osu_state::prepare_objects_for_renderer");
+
+        let frame = GameplayFrame {
+            time,
+            preempt: self.preempt,
+            fadein: self.fadein,
+            hit_window: &self.current_hit_window,
+        };
+
+        self.gameplay_renderer.prepare(&mut self.osu_renderer, &frame, &mut self.hit_objects);
     }
     
+    fn handle_event(&mut self, event: OsuStateEvent) {
+        match event {
+            OsuStateEvent::SetCursorSize(new_size) => {
+                self.cursor_renderer.set_size(new_size);
+            },
+            OsuStateEvent::PlayfieldLayoutChanged => {
+                self.update_playfield();
+                self.osu_renderer.on_playfield_layout_changed();
+
+                if let OsuStates::Playing = self.current_state {
+                    self.move_gameplay_cursor(self.cursor_playfield_pos);
+                }
+            },
+            OsuStateEvent::SliderConfigChanged => {
+                let _span = tracy_client::span!("osu_state::update::event::slider_config_changed");
+                self.osu_renderer.clear_cached_slider_textures(&mut self.hit_objects);
+            },
+            OsuStateEvent::ChangeSkin(path) => {
+                let _span = tracy_client::span!("osu_state::update::event::change_skin");
+                self.open_skin(path)
+            },
+            OsuStateEvent::SkinLoaded(images) => {
+                let _span = tracy_client::span!("osu_state::update::event::skin_loaded");
+                self.apply_skin(*images)
+            },
+            OsuStateEvent::BeatmapSkinLoaded(dir, images) => {
+                let _span = tracy_client::span!("osu_state::update::event::beatmap_skin_loaded");
+                self.on_beatmap_skin_loaded(dir, *images)
+            },
+            OsuStateEvent::StartBeatmap(entry, beatmap) => {
+                let _span = tracy_client::span!("osu_state::update::event::start_beatmap");
+                self.pause = None;
+                self.stop_watching();
+                self.finish_play(false);
+
+                // Checked before parsing, so unsupported modes
+                // don't stop the song select preview for nothing
+                if let Some(text) = entry.unsupported_mode_modal() {
+                    tracing::warn!("Refusing to start beatmap {}: {text}", entry.path.display());
+                    self.modal_text = Some(text);
+                } else if self.open_beatmap(&entry.path, beatmap) {
+                    self.current_beatmap_entry = Some(entry);
+                    self.start_play(false);
+                    self.set_state(OsuStates::Playing);
+                }
+            },
+            OsuStateEvent::Retry => {
+                let _span = tracy_client::span!("osu_state::update::event::retry");
+
+                if let Some(entry) = self.current_beatmap_entry.clone() {
+                    if let Some(handle) = self.current_playing_audio.take() {
+                        self.sl.stop(handle);
+                    }
+
+                    self.pause = None;
+
+                    if let Some(replay) = &self.replay {
+                        self.input_processor = replay.processor();
+                    }
+
+                    // Score of the unfinished play is gone after opening
+                    self.finish_play(false);
+
+                    if self.open_beatmap(&entry.path, self.current_beatmap.clone()) {
+                        self.start_play(true);
+                        self.set_state(OsuStates::Playing);
+                    } else {
+                        self.set_state(OsuStates::SongSelection);
+                    }
+                }
+            },
+            OsuStateEvent::ToSongSelection => {
+                let _span = tracy_client::span!("osu_state::update::event::to_song_selection");
+                self.osu_clock.reset_time();
+                self.results = None;
+                self.finish_play(false);
+
+                // Audio stays paused otherwise
+                if self.pause.take().is_some() {
+                    if let Some(handle) = self.current_playing_audio.take() {
+                        self.sl.stop(handle);
+                    }
+                }
+
+                self.stop_watching();
+                self.restore_user_skin();
+
+                // Key releases are not tracked outside of gameplay
+                self.cursor_renderer.on_key_released(KeyboardState { k1: true, k2: true });
+                self.set_state(OsuStates::SongSelection);
+            },
+            OsuStateEvent::ChangeAudioBackend(name) => {
+                let _span = tracy_client::span!("osu_state::update::event::change_audio_backend");
+                self.change_audio_backend(&name);
+            },
+            OsuStateEvent::ArchiveImported(archive, result) => {
+                let _span = tracy_client::span!("osu_state::update::event::archive_imported");
+                self.archives_imported += 1;
+
+                let file_name = archive.file_name()
+                    .map(|x| x.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+
+                match result {
+                    Ok(_) => self.toasts.push(tf("drop.imported", &[
+                        &file_name,
+                        &self.archives_imported,
+                        &self.archives_queued,
+                    ])),
+                    Err(e) => self.toasts.push(tf("drop.import_failed", &[&file_name, &e])),
+                }
+
+                // Scanning once the whole batch is extracted
+                if self.archives_imported >= self.archives_queued {
+                    self.archives_imported = 0;
+                    self.archives_queued = 0;
+                    self.song_select.import_songs_directory(DEFAULT_SONGS_PATH);
+                }
+            },
+            OsuStateEvent::WatchReplay(entry, replay) => {
+                let _span = tracy_client::span!("osu_state::update::event::watch_replay");
+                self.pause = None;
+                self.stop_watching();
+
+                self.input_processor = replay.processor();
+
+                // Set before opening, so the beatmap is opened with replay's rules
+                self.replay = Some(*replay);
+
+                if self.open_beatmap(&entry.path, None) {
+                    tracing::info!("Watching replay on {}", entry.path.display());
+
+                    self.current_beatmap_entry = Some(entry);
+                    self.set_state(OsuStates::Playing);
+                } else {
+                    self.stop_watching();
+                }
+            },
+            OsuStateEvent::MapCompleted(summary) => {
+                let _span = tracy_client::span!("osu_state::update::event::map_completed");
+                let title = match &self.current_beatmap {
+                    Some(map) => format!("{} - {} [{}]", map.artist, map.title, map.version),
+                    None => String::new(),
+                };
+
+                let play_start = self.hit_objects.first()
+                    .map(|obj| obj.start_time)
+                    .unwrap_or(0.0);
+
+                self.finish_play(!summary.failed);
+
+                let score = self.input_processor.take_score();
+
+                let config = self.config.read().expect("failed to acquire read lock");
+                let replays_dir = PathBuf::from(&config.replays_dir);
+                let always_save = config.always_save_replays;
+                drop(config);
+
+                let replay = self.export_replay(&title, &score).map(|replay| {
+                    let mut replay = PendingReplay::new(replay, replays_dir);
+
+                    if always_save {
+                        replay.save();
+                    }
+
+                    replay
+                });
+
+                self.results = Some(ResultsScreen::new(
+                    title,
+                    score,
+                    (play_start, self.current_play_end),
+                    self.current_breaks.clone(),
+                    replay,
+                    self.event_sender.clone(),
+                ));
+
+                self.set_state(OsuStates::Results);
+            },
+            OsuStateEvent::PlaySound(start_at, audio_source) => {
+                if let Some(audio_handle) = self.current_playing_audio.take() {
+                    self.sl.stop(audio_handle);
+                };
+
+                let handle = self.sl.play(&audio_source);
+                self.sl.set_pause(handle, true);
+                self.sl.set_volume(handle, self.audio_effects.music_volume());
+                let seek_to = (start_at as f64 / 1000.0).max(0.0);
+                self.sl.seek(handle, seek_to).unwrap(); // TODO: Handle
+                self.sl.set_pause(handle, false);
+
+                self.current_playing_audio = Some(handle);
+                self.current_audio = Some(audio_source);
+            },
+            OsuStateEvent::SeekPreview(position) => {
+                let Some(handle) = self.current_playing_audio else {
+                    return;
+                };
+
+                // Finished preview has nothing to seek
+                if !self.sl.is_valid_voice_handle(handle) {
+                    return;
+                }
+
+                self.sl.set_pause(handle, true);
+                if let Err(e) = self.sl.seek(handle, position.max(0.0)) {
+                    tracing::error!("Failed to seek preview: {e:?}");
+                }
+                self.sl.set_pause(handle, false);
+            },
+            OsuStateEvent::StopSound => {
+                if let Some(audio_handle) = self.current_playing_audio.take() {
+                    self.sl.stop(audio_handle);
+                };
+
+                self.current_audio = None;
+            },
+        }
+    }
+
     pub fn update(&mut self) {
         let _span = tracy_client::span!("osu_state::update");
         self.cursor_renderer.update();
+        self.apply_ready_skin();
 
         // Recv all events
         let event = self.event_receiver.try_recv();
 
         match event {
-            Ok(event) => {
-                match event {
-                    OsuStateEvent::SetCursorSize(new_size) => {
-                        self.cursor_renderer.set_size(new_size);
-                    },
-                    OsuStateEvent::ChangeSkin(path) => {
-                        let _span = tracy_client::span!("osu_state::update::event::change_skin");
-                        self.open_skin(path)
-                    },
-                    OsuStateEvent::StartBeatmap(entry) => {
-                        let _span = tracy_client::span!("osu_state::update::event::start_beatmap");
-                        self.open_beatmap(&entry.path);
-                        self.current_state = OsuStates::Playing;
-                    },
-                    OsuStateEvent::ToSongSelection => {
-                        let _span = tracy_client::span!("osu_state::update::event::to_song_selection");
-                        self.osu_clock.reset_time();
-                        self.current_state = OsuStates::SongSelection;
-                    },
-                    OsuStateEvent::PlaySound(start_at, audio_source) => {
-                        if let Some(audio_handle) = self.current_playing_audio.take() {
-                            self.sl.stop(audio_handle);
-                        };
-
-                        let handle = self.sl.play(&audio_source);
-                        self.sl.set_pause(handle, true);
-                        let seek_to = (start_at as f64 / 1000.0).max(0.0);
-                        self.sl.seek(handle, seek_to).unwrap(); // TODO: Handle
-                        self.sl.set_pause(handle, false);
-
-                        self.current_playing_audio = Some(handle);
-                        self.current_audio = Some(audio_source);
-                    },
-                }
-            },
+            // Screen changes wait for the screen to fade to black
+            Ok(event @ (
+                OsuStateEvent::StartBeatmap(..)
+                | OsuStateEvent::WatchReplay(..)
+                | OsuStateEvent::ToSongSelection
+            )) => self.transition.start(event),
+            Ok(event) => self.handle_event(event),
             Err(TryRecvError::Empty) => {},
             _ => panic!("sender disconnected"),
         }
 
+        match self.transition.update() {
+            Some(TransitionStep::Switch(event)) => {
+                self.handle_event(event);
+
+                // Nothing is missed while the playfield fades in
+                if matches!(self.current_state, OsuStates::Playing) {
+                    self.hold_clock();
+                }
+
+                self.transition.fade_in();
+            },
+            Some(TransitionStep::Finished) => self.release_clock(),
+            Some(TransitionStep::TimedOut) => {
+                tracing::error!("Screen transition timed out");
+                self.release_clock();
+
+                if self.modal_text.is_none() {
+                    self.modal_text = Some("Loading took too long".to_owned());
+                }
+            },
+            None => {},
+        }
+
         // egui inputs
         //let input = self.egui.state.take_egui_input(&self.window);
 
@@ -438,6 +1732,17 @@ impl<'s> OsuState<'s> {
             OsuStates::SongSelection => {
                 self.song_select.update();
             },
+            OsuStates::Results => {},
+        }
+
+        self.sync_music_volume();
+
+        // One per update, so a started beatmap switches
+        // the state before the rest of the queue is opened
+        if !matches!(self.current_state, OsuStates::Playing) && !self.transition.is_active() {
+            if let Some(path) = self.dropped_files.pop_front() {
+                self.open_dropped_file(path);
+            }
         }
 
     }
@@ -453,9 +1758,93 @@ impl<'s> OsuState<'s> {
         Ok(())
     }
 
+    fn render_modal(&mut self, ctx: &egui::Context) {
+        let Some(modal_text) = &self.modal_text else {
+            return;
+        };
+
+        let modal = egui::Modal::new(egui::Id::new("osu_state_modal")).show(ctx, |ui| {
+            ui.label(modal_text);
+
+            ui.button("Ok").clicked()
+        });
+
+        if modal.inner || modal.should_close() {
+            self.modal_text = None;
+        }
+    }
+
+    fn render_replay_hud(&self, ctx: &egui::Context) {
+        egui::Area::new(egui::Id::new("replay_hud"))
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-16.0, 16.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                ui.label(
+                    egui::RichText::new(tf("replay.watching", &[&self.osu_clock.rate()]))
+                        .size(18.0)
+                        .color(egui::Color32::WHITE)
+                );
+
+                ui.label(
+                    egui::RichText::new(tf("replay.combo", &[&self.input_processor.score().combo]))
+                        .size(18.0)
+                        .color(egui::Color32::WHITE)
+                );
+            });
+    }
+
+    /// Gameplay HUD, or its layout editor over song select with
+    /// made up values. Edited layout goes straight to the config
+    fn render_hud(&self, ctx: &egui::Context, edit: bool) {
+        let mut layout = self.config.read().expect("failed to acquire read lock").hud_layout.clone();
+
+        let preview = Score {
+            x300: 727,
+            x100: 12,
+            x50: 1,
+            miss: 2,
+            combo: 420,
+            ..Default::default()
+        };
+
+        let score = match edit {
+            true => &preview,
+            false => self.input_processor.score(),
+        };
+
+        let pace = match edit {
+            true => Some(-1250.0),
+            false => self.pace.as_ref()
+                .and_then(|x| x.score_at(self.osu_clock.get_time()))
+                .map(|best| score.points() as f64 - best),
+        };
+
+        if render_hud(ctx, &mut layout, score, pace, &self.key_overlay, edit) {
+            self.config.write().expect("failed to acquire write lock").hud_layout = layout;
+        }
+    }
+
+    /// Toasts and the drop hint, not shown during gameplay
+    fn render_notifications(&mut self, ctx: &egui::Context) {
+        self.toasts.render(ctx);
+
+        if self.is_file_hovered {
+            render_drop_overlay(ctx);
+        }
+    }
+
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let _span = tracy_client::span!("osu_state::render");
 
+        self.apply_pending_resize();
+
+        self.frame_history.on_new_frame();
+
+        self.osu_renderer.get_graphics().flush_uploads();
+
+        #[cfg(feature = "render-stats")]
+        self.frame_history.set_render_stats(self.osu_renderer.finish_frame_stats());
+
         //println!("diff: {}", self.osu_clock.get_time() as u128 - self.sink.get_pos().as_millis());
 
         //let graphics = self.osu_renderer.get_graphics();
@@ -469,28 +1858,43 @@ impl<'s> OsuState<'s> {
 
         match self.current_state {
             OsuStates::Playing => {
+                if self.pause.as_ref().is_some_and(PauseMenu::is_countdown_finished) {
+                    self.resume_gameplay();
+                }
+
+                self.update_replay_cursor(self.osu_clock.get_time());
+
+                // Paused and fading frames say nothing about dense sections
+                if self.pause.is_none() && !self.transition.is_active() {
+                    self.update_adaptive_quality();
+                }
+
+                #[cfg(feature = "alloc-counter")]
+                let allocations = crate::alloc_counter::allocations();
 
                 self.prepare_objects_for_renderer(self.osu_clock.get_time());
 
+                #[cfg(feature = "alloc-counter")]
+                tracy_client::plot!(
+                    "allocations in prepare_objects_for_renderer",
+                    (crate::alloc_counter::allocations() - allocations) as f64
+                );
+
                 // TODO THIS SHOULN'T BE HERE, fix when dicided what to
                 // do with egui_input thing
                 //self.update_egui(egui_input);
 
-                self.osu_renderer.render_objects(
-                    &view,
-                    &self.objects_render_queue, 
-                    &self.hit_objects
-                )?;
+                self.gameplay_renderer.render(&mut self.osu_renderer, &view, &self.hit_objects)?;
 
-                // Clearing objects queue only after they successfully rendered
-                self.objects_render_queue.clear();
-                self.objects_judgments_render_queue.clear();
+                self.on_gameplay_frame_rendered();
                 //self.render_egui(&view)?;
 
                 //self.render_playing(&view);
 
                 let time = self.osu_clock.update() / 1000.0;
-                if let Some(audio_handle) = self.current_playing_audio {
+
+                // Both clock and audio are stopped while paused
+                if let (Some(audio_handle), None) = (self.current_playing_audio, &self.pause) {
                     let pos = self.sl.stream_position(audio_handle);
 
                     let diff = pos - time;
@@ -500,39 +1904,166 @@ impl<'s> OsuState<'s> {
                     // Applying simple time correction if 
                     // 9ms threeshold is hit
                     if diff_abs * 1000.0 >= 9.0 {
-                        if diff > 0.0 {
-                            self.osu_clock.last_time += diff_abs;
-                        } else if diff < 0.0 {
-                            self.osu_clock.last_time -= diff_abs;
-                        }
+                        self.osu_clock.set_time(self.osu_clock.get_time() + diff);
                     }
 
 
                 }
 
-                self.input_processor.process_all(
+                if let Some(audio_handle) = self.current_playing_audio {
+                    self.audio_effects.update(&mut self.sl, audio_handle);
+                }
+
+                // Replay frames are fed as the clock reaches them, live
+                // inputs are stamped before the clock update above.
+                // Objects the clock went past can't be hit anymore
+                self.input_processor.process_until(
+                    self.osu_clock.get_time(),
                     &mut self.hit_objects,
                     &self.current_hit_window,
-                    self.current_hit_circle_diameter
+                    self.current_hit_circle_diameter,
+                    &self.current_rules,
                 );
+
+                if let Some(summary) = self.input_processor.poll_end(self.osu_clock.get_time()) {
+                    self.event_sender.send(OsuStateEvent::MapCompleted(summary))
+                        .expect("Failed to send MapCompleted event to the OsuState");
+                }
+
+                // Replay frames have no latency to measure
+                if self.replay.is_none() {
+                    let judged_at = self.osu_clock.since_start();
+                    let generation = self.input_processor.generation();
+
+                    let judged = self.input_processor.judged_inputs()
+                        .iter()
+                        .filter(|x| x.generation == generation);
+
+                    for input in judged {
+                        self.frame_history.push_input_latency(judged_at - input.ts);
+                    }
+                }
+
+                let time = self.osu_clock.get_time();
+                let current_break = break_at(&self.current_breaks, time).copied();
+
+                // Replay keys are fed along with the replay cursor
+                if self.replay.is_none() {
+                    self.key_overlay.update(self.held_keys);
+                }
+
+                // HUD is always shown, so gameplay always has an egui pass
+                {
+                    let ctx = self.egui.state.egui_ctx().clone();
+                    ctx.begin_pass(egui_input);
+
+                    self.render_hud(&ctx, false);
+
+                    if self.show_slider_debug {
+                        render_slider_debug(&ctx, &self.hit_objects, time, self.preempt as f64, &self.playfield);
+                    }
+
+                    if self.replay.is_some() {
+                        self.render_replay_hud(&ctx);
+                    }
+
+                    if let Some(brk) = &current_break {
+                        render_break_overlay(&ctx, brk, time, self.input_processor.score().accuracy());
+                    }
+
+                    if let Some(pause) = &mut self.pause {
+                        let was_menu = pause.state() == PauseState::Menu;
+                        pause.render(&ctx);
+
+                        // Resume button was pressed
+                        if was_menu && pause.state() != PauseState::Menu {
+                            self.window.set_cursor_visible(false);
+                        }
+                    }
+
+                    self.frame_history.render(&ctx);
+                    self.transition.render(&ctx);
+                    self.egui.output = Some(ctx.end_pass());
+                    self.render_egui(&view)?;
+                }
             },
             OsuStates::SongSelection => {
-                let egui_output = self.song_select.render(
-                    egui_input, 
-                    self.egui.state.egui_ctx(),
-                    &view,
-                );
+                let ctx = self.egui.state.egui_ctx().clone();
+                ctx.begin_pass(egui_input);
+                self.song_select.set_preview_progress(self.preview_progress());
+                self.song_select.render(&ctx, &view);
+
+                if self.config.read().expect("failed to acquire read lock").hud_edit {
+                    self.render_hud(&ctx, true);
+                }
+                self.render_modal(&ctx);
+                self.render_notifications(&ctx);
+                self.frame_history.render(&ctx);
+                self.transition.render(&ctx);
+                self.egui.output = Some(ctx.end_pass());
+                self.render_egui(&view)?;
+            },
+            OsuStates::Results => {
+                let ctx = self.egui.state.egui_ctx().clone();
+                ctx.begin_pass(egui_input);
+                if let Some(results) = &mut self.results {
+                    results.render(&ctx);
+                }
+                self.render_notifications(&ctx);
+                self.frame_history.render(&ctx);
+                self.transition.render(&ctx);
+                self.egui.output = Some(ctx.end_pass());
                 self.render_egui(&view)?;
-                self.egui.output = Some(egui_output)
             },
         }
 
-        self.cursor_renderer.render_on_view(
-            &view
-        );
+        // Outside of gameplay and in the pause menu OS cursor is used
+        let is_pause_menu = self.pause.as_ref()
+            .is_some_and(|x| x.state() == PauseState::Menu);
+
+        // Transition has to stay on top of everything
+        if matches!(self.current_state, OsuStates::Playing) && !is_pause_menu && !self.transition.is_active() {
+            self.render_particles(&view);
+            self.cursor_renderer.render_on_view(
+                &view
+            );
+        }
 
         output.present();
 
         Ok(())
     }
 }
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Requested audio backend failed and the default one is used instead
+fn notify_audio_fallback(toasts: &mut Toasts, requested: Option<&str>, actual: &str) {
+    if let Some(requested) = requested.filter(|x| *x != actual) {
+        toasts.push_with(Severity::Warning, tf("notify.audio_backend_fallback", &[&requested, &actual]));
+    }
+}
+
+/// Extracts dropped archives sequentially, each result is sent back
+/// as [`OsuStateEvent::ArchiveImported`]
+fn spawn_archive_import_worker(
+    rx: Receiver<PathBuf>,
+    state_tx: Sender<OsuStateEvent>,
+) {
+    std::thread::spawn(move || {
+        // Exits once `OsuState` is dropped
+        while let Ok(archive) = rx.recv() {
+            let result = extract_beatmap_archive(&archive, DEFAULT_SONGS_PATH)
+                .map_err(|e| {
+                    tracing::error!("Failed to import {}: {e}", archive.display());
+                    e.to_string()
+                });
+
+            if state_tx.send(OsuStateEvent::ArchiveImported(archive, result)).is_err() {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,119 @@
+use cgmath::Vector2;
+
+/// Live particles at most, the oldest one is replaced once it's full.
+/// Mashing at 20 presses per second keeps about 4 ripples alive
+pub const MAX_PARTICLES: usize = 64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParticleKind {
+    /// Ring expanding from the cursor on a key press
+    CursorRipple,
+}
+
+impl ParticleKind {
+    /// Time in ms the particle stays alive, in beatmap time
+    /// so it freezes along with the pause
+    pub fn lifetime(self) -> f64 {
+        match self {
+            Self::CursorRipple => 200.0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Particle {
+    /// Playfield coordinates
+    pub pos: Vector2<f64>,
+    /// Spawn time in ms
+    pub time: f64,
+    pub kind: ParticleKind,
+}
+
+impl Particle {
+    /// 0.0 at spawn and 1.0 at the end of its lifetime,
+    /// `None` if it isn't alive at `time`
+    pub fn progress(&self, time: f64) -> Option<f64> {
+        let progress = (time - self.time) / self.kind.lifetime();
+
+        (0.0..1.0).contains(&progress).then_some(progress)
+    }
+}
+
+/// Short-lived effects spawned by gameplay events. Slots are
+/// allocated once and reused as a ring buffer, so spawning
+/// never allocates
+pub struct ParticleSystem {
+    slots: Vec<Option<Particle>>,
+    /// Slot the next particle goes to
+    next: usize,
+}
+
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self::with_capacity(MAX_PARTICLES)
+    }
+}
+
+impl ParticleSystem {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: vec![None; capacity.max(1)],
+            next: 0,
+        }
+    }
+
+    pub fn spawn(&mut self, pos: Vector2<f64>, time: f64, kind: ParticleKind) {
+        self.slots[self.next] = Some(Particle { pos, time, kind });
+        self.next = (self.next + 1) % self.slots.len();
+    }
+
+    /// Drops every particle, used on retries and seeks
+    pub fn clear(&mut self) {
+        self.slots.fill(None);
+        self.next = 0;
+    }
+
+    /// Particles alive at `time` along with their progress, oldest first
+    pub fn alive(&self, time: f64) -> impl Iterator<Item = (&Particle, f64)> {
+        let (newer, older) = self.slots.split_at(self.next);
+
+        older.iter()
+            .chain(newer)
+            .flatten()
+            .filter_map(move |x| x.progress(time).map(|progress| (x, progress)))
+    }
+}
+
+#[test]
+fn test_particles_lifetime() {
+    let mut particles = ParticleSystem::default();
+    let pos = Vector2::new(256.0, 192.0);
+
+    particles.spawn(pos, 1000.0, ParticleKind::CursorRipple);
+
+    assert_eq!(particles.alive(999.0).count(), 0);
+    assert_eq!(particles.alive(1200.0).count(), 0);
+
+    let (particle, progress) = particles.alive(1050.0).next().unwrap();
+    assert_eq!(particle.pos, pos);
+    assert_eq!(progress, 0.25);
+
+    particles.clear();
+    assert_eq!(particles.alive(1050.0).count(), 0);
+}
+
+#[test]
+fn test_particles_ring_buffer() {
+    let mut particles = ParticleSystem::with_capacity(3);
+
+    for i in 0..5 {
+        particles.spawn(Vector2::new(i as f64, 0.0), i as f64, ParticleKind::CursorRipple);
+    }
+
+    // Two oldest ones are replaced
+    let alive: Vec<_> = particles.alive(4.0)
+        .map(|(x, _)| x.pos.x)
+        .collect();
+
+    assert_eq!(alive, [2.0, 3.0, 4.0]);
+}
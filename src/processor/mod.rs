@@ -1,10 +1,28 @@
 use cgmath::Vector2;
 use osu_replay_parser::replay::Replay;
 use replay_log::ReplayLog;
+use rules::GameplayRules;
 
-use crate::{hit_objects::{circle::CircleHitResult, hit_window::HitWindow, slider::SliderResult, Object}, osu_input::{KeyboardState, OsuInput}};
+use crate::{hit_objects::{breaks::{break_at, Break}, circle::CircleHitResult, hit_window::HitWindow, slider::{SliderEvent, SliderResult, SliderResultState}, Hit, Object, ObjectKind, CIRCLE_FADEOUT_TIME, JUDGMENTS_FADEOUT_TIME}, osu_input::{KeyboardState, OsuInput}, score::Score};
 
+pub mod replay_cursor;
 pub mod replay_log;
+pub mod rules;
+
+/// Frames after the last judgement kept in the replay log, ms
+pub const REPLAY_TAIL: f64 = 200.0;
+
+/// Time after the last judgement before the play is over,
+/// so the last judgements finish fading out, ms
+pub const COMPLETION_DELAY: f64 = CIRCLE_FADEOUT_TIME + JUDGMENTS_FADEOUT_TIME * 2.0;
+
+/// How a play ended, see [`OsuProcessor::poll_end`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaySummary {
+    /// Time of the last judgement or of the fail, ms
+    pub end_time: f64,
+    pub failed: bool,
+}
 
 /// Responsible for 
 /// 1. Handling inputs
@@ -13,7 +31,42 @@ pub struct OsuProcessor {
     replay_log: ReplayLog,
     queue: Vec<OsuInput>,
 
+    /// Inputs that got judged during last processing call
+    judged_inputs: Vec<JudgedInput>,
+
+    /// Slider parts judged during last processing call, in order
+    slider_events: Vec<SliderEvent>,
+
+    score: Score,
+
     last_cursor_pos: Vector2<f64>,
+
+    /// Inputs in the middle of a break can't hit anything
+    breaks: Vec<Break>,
+
+    /// Attempt inputs are stored for, bumped on [`OsuProcessor::reset`]
+    generation: u32,
+
+    /// Time inputs are processed up to, objects
+    /// before it can't be judged anymore
+    processed_until: f64,
+    /// Miss window of the last processing call
+    miss_window: f64,
+
+    /// Set once every object has its final result or the play
+    /// is failed, no inputs are accepted after that
+    end: Option<PlaySummary>,
+    /// [`OsuProcessor::poll_end`] already returned the end
+    end_reported: bool,
+}
+
+/// Input that got a result assigned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JudgedInput {
+    pub ts: f64,
+    /// Attempt the input belongs to, judgements of
+    /// an older one are leftovers and can be dropped
+    pub generation: u32,
 }
 
 impl Default for OsuProcessor {
@@ -22,11 +75,48 @@ impl Default for OsuProcessor {
             last_cursor_pos: Vector2::new(0.0, 0.0),
             replay_log: Default::default(),
             queue: Vec::new(),
+            judged_inputs: Vec::new(),
+            slider_events: Vec::new(),
+            score: Score::default(),
+            breaks: Vec::new(),
+            generation: 0,
+            processed_until: f64::NEG_INFINITY,
+            miss_window: 0.0,
+            end: None,
+            end_reported: false,
         }
     }
 }
 
 impl OsuProcessor {
+    /// Starts a new attempt, e.g. on retry. Queued inputs, judgements
+    /// and score are dropped, replay log starts a new segment so only
+    /// the last attempt is exported. Breaks stay as they are
+    pub fn reset(&mut self) {
+        let _span = tracy_client::span!("processor::reset");
+
+        self.generation += 1;
+        self.queue.clear();
+        self.judged_inputs.clear();
+        self.slider_events.clear();
+        self.score = Score::default();
+        self.replay_log.start_segment(self.generation);
+        self.processed_until = f64::NEG_INFINITY;
+        self.end = None;
+        self.end_reported = false;
+    }
+
+    /// Current attempt, see [`OsuProcessor::reset`]
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Breaks of the current beatmap, sorted by start time
+    pub fn set_breaks(&mut self, breaks: Vec<Break>) {
+        self.breaks = breaks;
+    }
+
     pub fn set_cursor_pos(&mut self, pos: Vector2<f64>) {
         self.last_cursor_pos = pos;
     }
@@ -46,6 +136,7 @@ impl OsuProcessor {
                     k1: last.keys.k1,
                     k2: last.keys.k2,
                 },
+                generation: self.generation,
             });
         } else {
             self.store_input(OsuInput {
@@ -53,6 +144,7 @@ impl OsuProcessor {
                 pos,
                 keys: KeyboardState::empty(),
                 hold: KeyboardState::empty(),
+                generation: self.generation,
             });
         }
     }
@@ -63,38 +155,116 @@ impl OsuProcessor {
         objects: &mut [Object], 
         hit_window: &HitWindow,
         circle_diameter: f32,
+        rules: &GameplayRules,
     ) {
         let _span = tracy_client::span!("processor::process_all");
 
-        'input_loop: for input in &self.queue {
+        self.process_until(f64::INFINITY, objects, hit_window, circle_diameter, rules);
+    }
+
+    /// Processes queued inputs up to `time` inclusive, the rest
+    /// stays queued. Used to play back a replay in real time,
+    /// queue is expected to be sorted by timestamps
+    pub fn process_until(
+        &mut self, 
+        time: f64,
+        objects: &mut [Object], 
+        hit_window: &HitWindow,
+        circle_diameter: f32,
+        rules: &GameplayRules,
+    ) {
+        let _span = tracy_client::span!("processor::process_until");
+
+        self.judged_inputs.clear();
+        self.slider_events.clear();
+
+        if self.end.is_some() {
+            self.queue.clear();
+            return;
+        }
+
+        self.score.rules = *rules;
+        self.score.circle_radius = circle_diameter as f64 / 2.0;
+
+        let amount = self.queue.partition_point(|x| x.ts <= time);
+        self.miss_window = hit_window.x50.round();
+
+        'input_loop: for input in &self.queue[..amount] {
+            // Stored before the last reset
+            if input.generation != self.generation {
+                continue;
+            }
+
+            // Misses go before the input, so combo breaks in time order
+            Self::expire_circles(&mut self.score, self.miss_window, input.ts, input.pos, objects);
+
+            // Objects before the break are judged once their hit window is
+            // over, after that there is nothing to scan until the break ends
+            let in_break = break_at(&self.breaks, input.ts)
+                .is_some_and(|x| input.ts >= x.start_time + hit_window.x50);
+
+            if in_break {
+                continue;
+            }
+
             for object in objects.iter_mut() {
                 match &mut object.kind {
                     crate::hit_objects::ObjectKind::Circle(circle) => {
                         let res = circle.update(
-                            input,
+                            &rules.hit_input(input, circle.start_time),
                             hit_window,
                             circle_diameter
                         );
 
                         if res {
+                            if let Some(result) = &circle.hit_result {
+                                self.score.push(result.at, result.result);
+                                self.score.push_combo(result.result != Hit::MISS);
+                            }
+
+                            if let Some(offset) = circle.hit_offset() {
+                                self.score.push_hit_offset(offset);
+                            }
+
+                            self.judged_inputs.push(JudgedInput { ts: input.ts, generation: self.generation });
                             continue 'input_loop;
                         }
 
                     },
                     crate::hit_objects::ObjectKind::Slider(slider) => {
-                        if slider.update(
-                            input,
+                        let events_before = self.slider_events.len();
+
+                        let is_head_hit = slider.update(
+                            &rules.hit_input(input, slider.start_time),
                             hit_window,
-                            circle_diameter
-                        ).is_some() {
-                            continue 'input_loop;
+                            circle_diameter,
+                            &mut self.slider_events,
+                        ).is_some();
+
+                        if is_head_hit {
+                            if let Some(offset) = slider.head_hit_offset() {
+                                self.score.push_hit_offset(offset);
+                            }
+
+                            self.judged_inputs.push(JudgedInput { ts: input.ts, generation: self.generation });
+                        } else if let Some(hit) = slider.update_post(
+                            &rules.hold_input(input),
+                            hit_window,
+                            circle_diameter,
+                            &mut self.slider_events,
+                        ) {
+                            self.score.push(input.ts, hit);
+                            self.judged_inputs.push(JudgedInput { ts: input.ts, generation: self.generation });
                         };
 
-                        slider.update_post(
-                            input,
-                            hit_window,
-                            circle_diameter
-                        );
+                        // Combo follows slider parts, not the final result
+                        for event in &self.slider_events[events_before..] {
+                            self.score.push_slider_event(event);
+                        }
+
+                        if is_head_hit {
+                            continue 'input_loop;
+                        }
 
                         continue;
                     },
@@ -102,9 +272,157 @@ impl OsuProcessor {
             }
         }
 
+        // Without a time limit inputs are all there is to go by
+        let processed_until = if time.is_finite() {
+            time
+        } else {
+            self.queue[..amount].last().map_or(f64::NEG_INFINITY, |x| x.ts)
+        };
+
+        self.processed_until = self.processed_until.max(processed_until);
+        self.queue.drain(..amount);
+
+        Self::expire_circles(&mut self.score, self.miss_window, self.processed_until, self.last_cursor_pos, objects);
+
+        if self.is_complete(objects) {
+            let last_judgement = objects.iter()
+                .map(|x| self.judged_at(x))
+                .reduce(f64::max)
+                .unwrap_or(self.processed_until);
+
+            self.end_play(last_judgement, false);
+        }
+    }
+
+    /// Circles that weren't clicked until their hit window passed
+    /// `ts` get a miss, `pos` is the cursor position at that time
+    fn expire_circles(
+        score: &mut Score,
+        miss_window: f64,
+        ts: f64,
+        pos: Vector2<f64>,
+        objects: &mut [Object],
+    ) {
+        for object in objects.iter_mut() {
+            // Objects are sorted, the rest can still be hit
+            if object.start_time + miss_window > ts {
+                break;
+            }
+
+            let ObjectKind::Circle(circle) = &mut object.kind else {
+                continue;
+            };
+
+            if circle.hit_result.is_some() {
+                continue;
+            }
+
+            let at = circle.start_time + miss_window;
+
+            circle.hit_result = Some(CircleHitResult {
+                at,
+                pos,
+                result: Hit::MISS,
+            });
+
+            score.push(at, Hit::MISS);
+            score.push_combo(false);
+        }
+    }
+
+    /// Every object has its final result or can't get one anymore,
+    /// e.g. circle wasn't clicked until its hit window passed
+    pub fn is_complete(&self, objects: &[Object]) -> bool {
+        // Last objects are the last to be judged
+        objects.iter().rev().all(|object| match &object.kind {
+            ObjectKind::Circle(circle) => circle.hit_result.is_some()
+                || self.processed_until >= circle.start_time + self.miss_window,
+            ObjectKind::Slider(slider) => {
+                let is_passed = slider.hit_result.as_ref()
+                    .is_some_and(|x| matches!(x.state, SliderResultState::Passed(_)));
+
+                is_passed || self.processed_until >= slider.end_time()
+            },
+        })
+    }
+
+    /// When `object` got or would get its final result
+    fn judged_at(&self, object: &Object) -> f64 {
+        match &object.kind {
+            ObjectKind::Circle(circle) => circle.hit_result.as_ref()
+                .map_or(circle.start_time + self.miss_window, |x| x.at),
+            ObjectKind::Slider(slider) => slider.end_time(),
+        }
+    }
+
+    /// Ends the play early, inputs are not accepted after `ts`
+    pub fn fail(&mut self, ts: f64) {
+        self.end_play(ts, true);
+    }
+
+    fn end_play(&mut self, end_time: f64, failed: bool) {
+        if self.end.is_some() {
+            return;
+        }
+
+        tracing::info!(end_time, failed, "Play ended");
+
+        self.end = Some(PlaySummary { end_time, failed });
         self.queue.clear();
+        self.replay_log.truncate_after(end_time + REPLAY_TAIL);
+    }
+
+    /// Play is over and inputs are ignored
+    #[inline]
+    pub fn is_ended(&self) -> bool {
+        self.end.is_some()
+    }
+
+    /// Returns the end of the play once `time` is [`COMPLETION_DELAY`]
+    /// past it, only once per attempt
+    pub fn poll_end(&mut self, time: f64) -> Option<PlaySummary> {
+        let end = self.end.filter(|x| !self.end_reported && time >= x.end_time + COMPLETION_DELAY)?;
+        self.end_reported = true;
+
+        Some(end)
+    }
+
+    /// Inputs that are not processed yet
+    #[inline]
+    pub fn queued_inputs(&self) -> &[OsuInput] {
+        &self.queue
     }
     
+    /// Every input of the play so far, judged ones included
+    #[inline]
+    pub fn recorded_inputs(&self) -> &[OsuInput] {
+        self.replay_log.frames()
+    }
+
+    /// Inputs which got a result assigned during
+    /// last `process_all` or `process_until` call
+    #[inline]
+    pub fn judged_inputs(&self) -> &[JudgedInput] {
+        &self.judged_inputs
+    }
+
+    /// Slider parts judged during last `process_all`
+    /// or `process_until` call
+    #[inline]
+    pub fn slider_events(&self) -> &[SliderEvent] {
+        &self.slider_events
+    }
+
+    #[inline]
+    pub fn score(&self) -> &Score {
+        &self.score
+    }
+
+    /// Takes collected score leaving an empty one in place
+    pub fn take_score(&mut self) -> Score {
+        std::mem::take(&mut self.score)
+    }
+
     pub fn process(&mut self, _ts: f64, _objects: &mut [Object]) {
         todo!();
     }
@@ -135,6 +453,7 @@ impl OsuProcessor {
                     k1: if last.hold.k1 && state.k1 { false } else { last.hold.k1 },
                     k2: if last.hold.k2 && state.k2 { false } else { last.hold.k2 },
                 },
+                generation: self.generation,
             });
         } else {
             tracing::warn!("Trying to store release without previous input")
@@ -160,6 +479,7 @@ impl OsuProcessor {
                     k1: last.k1 && state.k1,
                     k2: last.k2 && state.k2,
                 },
+                generation: self.generation,
             });
         } else {
             self.store_input(OsuInput {
@@ -170,12 +490,70 @@ impl OsuProcessor {
                     k1: false,
                     k2: false,
                 },
+                generation: self.generation,
             });
         }
     }
 
-    pub fn store_input(&mut self, input: OsuInput) {
+    /// Stores keys that are held when gameplay resumes after a pause.
+    ///
+    /// Inputs are not recorded while paused, so cursor stays where it
+    /// was paused and keys held through the pause keep holding.
+    /// That way the pause itself doesn't break slider tracking
+    pub fn store_resume(&mut self, ts: f64, keys: KeyboardState) {
+        let _span = tracy_client::span!("processor::store_resume");
+
+        let last_keys = self.replay_log
+            .last_input()
+            .map(|x| x.keys)
+            .unwrap_or_default();
+
+        self.store_input(OsuInput {
+            ts,
+            pos: self.last_cursor_pos,
+            keys,
+            hold: KeyboardState {
+                k1: last_keys.k1 && keys.k1,
+                k2: last_keys.k2 && keys.k2,
+            },
+            generation: self.generation,
+        });
+    }
+
+    /// Records a frame only to the replay log while gameplay inputs
+    /// are ignored, e.g. cursor is over the UI. Frame repeats the
+    /// last position and keys so it's never judged,
+    /// but replay log timestamps keep going forward
+    pub fn store_ignored(&mut self, ts: f64) {
+        let _span = tracy_client::span!("processor::store_ignored");
+
+        if self.end.is_some() {
+            return;
+        }
+
+        let Some(last) = self.replay_log.last_input() else {
+            return;
+        };
+
+        if ts <= last.ts {
+            return;
+        }
+
+        self.replay_log.store_input(OsuInput {
+            ts,
+            ..last
+        });
+    }
+
+    /// Input is stamped with the current attempt
+    pub fn store_input(&mut self, mut input: OsuInput) {
         let _span = tracy_client::span!("processor::store_input");
+
+        if self.end.is_some() {
+            return;
+        }
+
+        input.generation = self.generation;
         self.queue.push(input.clone());
         self.replay_log.store_input(input);
     }
@@ -197,6 +575,7 @@ impl From<Replay> for OsuProcessor {
                     k2: frame.z.contains(osu_replay_parser::replay::replay_data::Keys::K2),
                 },
                 hold: KeyboardState::default(),
+                generation: 0,
             };
 
             inputs.push(input);
@@ -254,7 +633,16 @@ impl From<Replay> for OsuProcessor {
         Self {
             replay_log: ReplayLog::default(),
             queue: new_inputs,
+            judged_inputs: Vec::new(),
+            slider_events: Vec::new(),
+            score: Score::default(),
             last_cursor_pos: Vector2::new(0.0, 0.0),
+            breaks: Vec::new(),
+            generation: 0,
+            processed_until: f64::NEG_INFINITY,
+            miss_window: 0.0,
+            end: None,
+            end_reported: false,
         }
     }
 }
@@ -311,3 +699,256 @@ fn test_input_released() {
     assert_eq!(last_input.keys.is_keys_hit(), false);
     assert_eq!(last_input.ts, 150.0);
 }
+
+#[test]
+fn test_resume_keeps_hold() {
+    let mut processor = OsuProcessor::default();
+    processor.store_cursor_moved(90.0, Vector2::new(10.0, 20.0));
+    processor.store_keyboard_pressed(100.0, KeyboardState { k1: true, k2: false });
+
+    // Paused here, K1 is held through the pause and K2 is pressed during it
+    processor.store_resume(100.0, KeyboardState { k1: true, k2: true });
+
+    let last_input = processor.replay_log.last_input().unwrap();
+
+    assert!(last_input.is_k1_hold());
+    assert!(last_input.is_keys_hit_no_hold());
+    assert_eq!(last_input.pos, Vector2::new(10.0, 20.0));
+
+    // Released during the pause
+    processor.store_resume(100.0, KeyboardState::empty());
+
+    let last_input = processor.replay_log.last_input().unwrap();
+    assert!(!last_input.is_keys_hold());
+}
+
+#[test]
+fn test_ignored_input_is_not_judged() {
+    let mut processor = OsuProcessor::default();
+    processor.store_cursor_moved(90.0, Vector2::new(10.0, 20.0));
+    processor.store_keyboard_pressed(100.0, KeyboardState { k1: true, k2: false });
+
+    processor.store_ignored(150.0);
+    processor.store_ignored(120.0);
+
+    assert_eq!(processor.queue.len(), 2);
+
+    let last_input = processor.replay_log.last_input().unwrap();
+    assert_eq!(last_input.ts, 150.0);
+    assert_eq!(last_input.pos, Vector2::new(10.0, 20.0));
+    assert!(last_input.keys.k1);
+}
+
+#[test]
+fn test_inputs_during_break_are_skipped() {
+    use crate::hit_objects::{circle::Circle, ObjectKind};
+    use rosu_map::util::Pos;
+
+    let circle = |start_time: f64| Object {
+        start_time,
+        kind: ObjectKind::Circle(Circle {
+            start_time,
+            pos: Pos { x: 0.0, y: 0.0 },
+            hit_result: None,
+        }),
+        color: 0,
+    };
+
+    // Second circle is inside of the break, such maps are
+    // broken but it shows that break inputs are not judged
+    let mut objects = vec![circle(900.0), circle(5000.0)];
+    let hit_window = HitWindow::from_od(5.0);
+
+    let mut processor = OsuProcessor::default();
+    processor.set_breaks(vec![Break { start_time: 1000.0, end_time: 11000.0 }]);
+
+    // Still within the hit window of the object before the break
+    processor.store_cursor_moved(1000.0, Vector2::new(0.0, 0.0));
+    processor.store_keyboard_pressed(1000.0, KeyboardState { k1: true, k2: false });
+    processor.store_keyboard_released(1010.0, KeyboardState { k1: true, k2: false });
+
+    processor.store_keyboard_pressed(5000.0, KeyboardState { k1: true, k2: false });
+    processor.process_all(&mut objects, &hit_window, 50.0, &GameplayRules::default());
+
+    assert_eq!(processor.judged_inputs(), [JudgedInput { ts: 1000.0, generation: 0 }]);
+}
+
+#[test]
+fn test_reset_drops_previous_attempt() {
+    use crate::hit_objects::{circle::Circle, ObjectKind};
+    use rosu_map::util::Pos;
+
+    let circle = |start_time: f64| Object {
+        start_time,
+        kind: ObjectKind::Circle(Circle {
+            start_time,
+            pos: Pos { x: 0.0, y: 0.0 },
+            hit_result: None,
+        }),
+        color: 0,
+    };
+
+    let mut objects = vec![circle(1000.0), circle(2000.0)];
+    let hit_window = HitWindow::from_od(5.0);
+
+    let mut processor = OsuProcessor::default();
+
+    // First attempt hits the first circle, but is never processed
+    processor.store_cursor_moved(990.0, Vector2::new(0.0, 0.0));
+    processor.store_keyboard_pressed(1000.0, KeyboardState { k1: true, k2: false });
+
+    processor.reset();
+    assert_eq!(processor.generation(), 1);
+    assert!(processor.queued_inputs().is_empty());
+    assert!(processor.recorded_inputs().is_empty());
+
+    // Second attempt only hits the second one
+    processor.store_cursor_moved(1990.0, Vector2::new(0.0, 0.0));
+    processor.store_keyboard_pressed(2000.0, KeyboardState { k1: true, k2: false });
+
+    // Straggler of the first attempt stored directly to the queue
+    processor.queue.insert(0, OsuInput {
+        ts: 1000.0,
+        pos: Vector2::new(0.0, 0.0),
+        keys: KeyboardState { k1: true, k2: false },
+        hold: KeyboardState::empty(),
+        generation: 0,
+    });
+
+    processor.process_all(&mut objects, &hit_window, 50.0, &GameplayRules::default());
+
+    assert_eq!(processor.judged_inputs(), [JudgedInput { ts: 2000.0, generation: 1 }]);
+    assert_eq!(processor.score().x300 + processor.score().x100 + processor.score().x50, 1);
+
+    // Replay log keeps attempts apart, only the last one is exported
+    let recorded: Vec<_> = processor.recorded_inputs().iter().map(|x| (x.ts, x.generation)).collect();
+    assert_eq!(recorded, [(1990.0, 1), (2000.0, 1)]);
+
+    let segments: Vec<_> = processor.replay_log.segments().map(|x| x.len()).collect();
+    assert_eq!(segments, [2, 2]);
+
+    // Hold state doesn't carry over from the previous attempt
+    assert!(!processor.recorded_inputs()[1].is_k1_hold());
+}
+
+#[test]
+fn test_is_complete_circles() {
+    use crate::hit_objects::circle::Circle;
+    use rosu_map::util::Pos;
+
+    let circle = |start_time: f64| Object {
+        start_time,
+        kind: ObjectKind::Circle(Circle {
+            start_time,
+            pos: Pos { x: 0.0, y: 0.0 },
+            hit_result: None,
+        }),
+        color: 0,
+    };
+
+    let mut objects = vec![circle(1000.0), circle(2000.0)];
+    let hit_window = HitWindow::from_od(5.0);
+    let rules = GameplayRules::default();
+
+    let mut processor = OsuProcessor::default();
+    processor.store_cursor_moved(990.0, Vector2::new(0.0, 0.0));
+    processor.store_keyboard_pressed(1000.0, KeyboardState { k1: true, k2: false });
+    processor.store_keyboard_released(1010.0, KeyboardState { k1: true, k2: false });
+
+    processor.process_until(1500.0, &mut objects, &hit_window, 50.0, &rules);
+    assert!(!processor.is_complete(&objects));
+
+    // Second one can still be hit
+    processor.process_until(2100.0, &mut objects, &hit_window, 50.0, &rules);
+    assert!(!processor.is_complete(&objects));
+    assert!(!processor.is_ended());
+
+    // Its hit window is over without a click
+    processor.process_until(2150.0, &mut objects, &hit_window, 50.0, &rules);
+    assert!(processor.is_complete(&objects));
+    assert!(processor.is_ended());
+
+    // Nothing is recorded after the end
+    processor.store_cursor_moved(2200.0, Vector2::new(10.0, 10.0));
+    assert_eq!(processor.recorded_inputs().last().map(|x| x.ts), Some(1010.0));
+
+    // Reported once judgements faded out
+    assert_eq!(processor.poll_end(2150.0), None);
+
+    let end = PlaySummary { end_time: 2150.0, failed: false };
+    assert_eq!(processor.poll_end(2150.0 + COMPLETION_DELAY), Some(end));
+    assert_eq!(processor.poll_end(2150.0 + COMPLETION_DELAY), None);
+
+    // Next attempt starts over
+    processor.reset();
+    assert!(!processor.is_ended());
+}
+
+#[test]
+fn test_fail_ends_play() {
+    let mut processor = OsuProcessor::default();
+    processor.store_cursor_moved(400.0, Vector2::new(0.0, 0.0));
+    processor.store_cursor_moved(800.0, Vector2::new(10.0, 0.0));
+
+    processor.fail(500.0);
+    assert!(processor.is_ended());
+
+    // Frames past the tail are dropped
+    let recorded: Vec<_> = processor.recorded_inputs().iter().map(|x| x.ts).collect();
+    assert_eq!(recorded, [400.0]);
+    assert!(processor.queued_inputs().is_empty());
+
+    // Failing or completing again doesn't change the end
+    processor.fail(600.0);
+
+    let end = PlaySummary { end_time: 500.0, failed: true };
+    assert_eq!(processor.poll_end(1000.0 + COMPLETION_DELAY), Some(end));
+    assert_eq!(processor.poll_end(2000.0 + COMPLETION_DELAY), None);
+}
+
+#[test]
+fn test_skipped_circle_is_miss() {
+    use crate::hit_objects::circle::Circle;
+    use rosu_map::util::Pos;
+
+    let circle = |start_time: f64| Object {
+        start_time,
+        kind: ObjectKind::Circle(Circle {
+            start_time,
+            pos: Pos { x: 0.0, y: 0.0 },
+            hit_result: None,
+        }),
+        color: 0,
+    };
+
+    let mut objects = vec![circle(1000.0), circle(2000.0), circle(3000.0)];
+    let hit_window = HitWindow::from_od(5.0);
+
+    // Second circle is never clicked
+    let mut processor = OsuProcessor::default();
+    processor.store_cursor_moved(990.0, Vector2::new(0.0, 0.0));
+
+    for ts in [1000.0, 3000.0] {
+        processor.store_keyboard_pressed(ts, KeyboardState { k1: true, k2: false });
+        processor.store_keyboard_released(ts + 10.0, KeyboardState { k1: true, k2: false });
+    }
+
+    processor.process_all(&mut objects, &hit_window, 50.0, &GameplayRules::default());
+
+    let ObjectKind::Circle(skipped) = &objects[1].kind else {
+        unreachable!();
+    };
+
+    let miss = skipped.hit_result.as_ref().unwrap();
+    assert_eq!((miss.result, miss.at), (Hit::MISS, 2000.0 + hit_window.x50.round()));
+
+    let score = processor.score();
+    assert_eq!((score.x300, score.miss), (2, 1));
+    assert!(score.accuracy() < 1.0);
+
+    // Miss broke the combo in between the hits
+    assert_eq!((score.max_combo, score.combo), (1, 1));
+
+    let times: Vec<_> = score.accuracy_series.iter().map(|x| x.0).collect();
+    assert_eq!(times, [1000.0, 2000.0 + hit_window.x50.round(), 3000.0]);
+}
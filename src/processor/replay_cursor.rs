@@ -0,0 +1,135 @@
+use cgmath::Vector2;
+
+use crate::{math::OSU_COORDS_HEIGHT, osu_input::{KeyboardState, OsuInput}};
+
+use super::{replay_log::{InterpolatedFrame, ReplayLog}, rules::GameplayRules, OsuProcessor};
+
+/// Cursor of a replay that is being watched. Processor drops
+/// inputs once they are judged, so a full copy is kept here
+pub struct ReplayCursor {
+    log: ReplayLog,
+    rules: GameplayRules,
+}
+
+impl ReplayCursor {
+    /// `frames` are expected to be sorted by timestamps
+    pub fn new(frames: Vec<OsuInput>) -> Self {
+        Self::with_rules(frames, GameplayRules::default())
+    }
+
+    /// Replay that was played with `rules`. Stable stores Hard Rock
+    /// frames as they were seen on the flipped playfield, they are
+    /// flipped back so they're judged the same way live inputs are
+    pub fn with_rules(mut frames: Vec<OsuInput>, rules: GameplayRules) -> Self {
+        if rules.hard_rock {
+            for frame in &mut frames {
+                frame.pos.y = OSU_COORDS_HEIGHT as f64 - frame.pos.y;
+            }
+        }
+
+        Self {
+            log: ReplayLog::from_frames(frames),
+            rules,
+        }
+    }
+
+    #[inline]
+    pub fn rules(&self) -> GameplayRules {
+        self.rules
+    }
+
+    #[inline]
+    pub fn frames(&self) -> &[OsuInput] {
+        self.log.frames()
+    }
+
+    /// Interpolated cursor for rendering, see [`ReplayLog::sample`]
+    #[inline]
+    pub fn sample(&mut self, time: f64) -> InterpolatedFrame {
+        self.log.sample(time)
+    }
+
+    /// Frames where a key goes down, see [`ReplayLog::presses_between`]
+    #[inline]
+    pub fn presses_between(&self, from: f64, to: f64) -> impl Iterator<Item = &OsuInput> {
+        self.log.presses_between(from, to)
+    }
+
+    /// Fresh processor with every frame queued,
+    /// used to start watching from the beginning
+    pub fn processor(&self) -> OsuProcessor {
+        let mut processor = OsuProcessor::default();
+
+        for frame in self.frames() {
+            processor.store_input(frame.clone());
+        }
+
+        processor
+    }
+
+    /// Position linearly interpolated between two
+    /// closest frames, in playfield coordinates
+    pub fn position_at(&self, time: f64) -> Vector2<f64> {
+        let frames = self.frames();
+        let index = frames.partition_point(|x| x.ts <= time);
+
+        let Some(prev) = index.checked_sub(1).map(|i| &frames[i]) else {
+            return frames.first()
+                .map(|x| x.pos)
+                .unwrap_or(Vector2::new(0.0, 0.0));
+        };
+
+        let Some(next) = frames.get(index) else {
+            return prev.pos;
+        };
+
+        let duration = next.ts - prev.ts;
+
+        if duration <= 0.0 {
+            return next.pos;
+        }
+
+        let t = (time - prev.ts) / duration;
+
+        prev.pos + (next.pos - prev.pos) * t
+    }
+
+    /// Keys of the last frame at or before `time`
+    pub fn keys_at(&self, time: f64) -> KeyboardState {
+        let frames = self.frames();
+        let index = frames.partition_point(|x| x.ts <= time);
+
+        index.checked_sub(1)
+            .map(|i| frames[i].keys)
+            .unwrap_or_default()
+    }
+}
+
+#[test]
+fn test_replay_cursor_interpolation() {
+    let input = |ts: f64, x: f64, k1: bool| OsuInput {
+        ts,
+        pos: Vector2::new(x, 0.0),
+        keys: KeyboardState { k1, k2: false },
+        hold: KeyboardState::empty(),
+        generation: 0,
+    };
+
+    let cursor = ReplayCursor::new(vec![
+        input(100.0, 0.0, false),
+        input(200.0, 100.0, true),
+        input(200.0, 50.0, true),
+        input(300.0, 150.0, false),
+    ]);
+
+    assert_eq!(cursor.position_at(0.0), Vector2::new(0.0, 0.0));
+    assert_eq!(cursor.position_at(150.0), Vector2::new(50.0, 0.0));
+    assert_eq!(cursor.position_at(250.0), Vector2::new(100.0, 0.0));
+    assert_eq!(cursor.position_at(1000.0), Vector2::new(150.0, 0.0));
+
+    assert!(!cursor.keys_at(150.0).k1);
+    assert!(cursor.keys_at(200.0).k1);
+    assert!(!cursor.keys_at(300.0).k1);
+
+    assert_eq!(ReplayCursor::new(Vec::new()).position_at(10.0), Vector2::new(0.0, 0.0));
+}
@@ -1,16 +1,221 @@
-use crate::osu_input::OsuInput;
+use cgmath::Vector2;
 
+use crate::osu_input::{KeyboardState, OsuInput};
+
+/// Frame produced by [`ReplayLog::sample`], only meant for
+/// rendering, judgements always work with the real frames
+#[derive(Debug, Copy, Clone)]
+pub struct InterpolatedFrame {
+    pub ts: f64,
+    pub pos: Vector2<f64>,
+    pub keys: KeyboardState,
+}
+
+/// Frames of every attempt, split into segments by their
+/// generation. Everything but `segments` sees only the last one
 #[derive(Default)]
 pub struct ReplayLog {
-    frames: Vec<OsuInput>
+    frames: Vec<OsuInput>,
+
+    /// Index of the first frame of the current attempt
+    segment_start: usize,
+
+    /// Index of the first frame after the last sampled timestamp,
+    /// playback is mostly monotonic so it's usually still valid
+    next_index: usize,
 }
 
 impl ReplayLog {
+    /// `frames` are expected to be sorted by timestamps
+    pub fn from_frames(frames: Vec<OsuInput>) -> Self {
+        Self {
+            frames,
+            segment_start: 0,
+            next_index: 0,
+        }
+    }
+
+    /// Frames of the current attempt
+    #[inline]
+    pub fn frames(&self) -> &[OsuInput] {
+        &self.frames[self.segment_start..]
+    }
+
+    /// Frames of every attempt, oldest first
+    pub fn segments(&self) -> impl Iterator<Item = &[OsuInput]> {
+        self.frames.chunk_by(|a, b| a.generation == b.generation)
+    }
+
+    /// Frames stored after this belong to the attempt `generation`
+    pub fn start_segment(&mut self, generation: u32) {
+        tracing::debug!(generation, frames = self.frames().len(), "Starting replay log segment");
+
+        self.segment_start = self.frames.len();
+        self.next_index = 0;
+    }
+
     pub fn store_input(&mut self, input: OsuInput) {
         self.frames.push(input);
     }
 
+    /// Drops frames of the current attempt after `ts`
+    pub fn truncate_after(&mut self, ts: f64) {
+        let keep = self.frames().partition_point(|x| x.ts <= ts);
+
+        self.frames.truncate(self.segment_start + keep);
+        self.next_index = self.next_index.min(keep);
+    }
+
     pub fn last_input(&self) -> Option<OsuInput> {
-        self.frames.last().cloned() // TODO remove unwrap lol
+        self.frames().last().cloned()
+    }
+
+    /// Index of the first frame with timestamp greater than `ts`,
+    /// relative to the current segment
+    fn locate(&mut self, ts: f64) -> usize {
+        let frames = &self.frames[self.segment_start..];
+        let mut index = self.next_index.min(frames.len());
+
+        let is_behind = index > 0 && frames[index - 1].ts > ts;
+
+        if is_behind {
+            index = frames.partition_point(|x| x.ts <= ts);
+        } else {
+            // Stepping a few frames is cheaper than a search
+            while index < frames.len() && frames[index].ts <= ts {
+                index += 1;
+            }
+        }
+
+        self.next_index = index;
+        index
     }
+
+    /// Cursor state at `ts`, position is linearly interpolated between
+    /// two closest frames and keys are taken from the earlier one.
+    /// Clamped to the first and the last frame
+    pub fn sample(&mut self, ts: f64) -> InterpolatedFrame {
+        let _span = tracy_client::span!("replay_log::sample");
+
+        let index = self.locate(ts);
+        let frames = self.frames();
+
+        let Some(prev) = index.checked_sub(1).map(|i| &frames[i]) else {
+            return InterpolatedFrame {
+                ts,
+                pos: frames.first()
+                    .map(|x| x.pos)
+                    .unwrap_or(Vector2::new(0.0, 0.0)),
+                keys: KeyboardState::empty(),
+            };
+        };
+
+        let Some(next) = frames.get(index) else {
+            return InterpolatedFrame { ts, pos: prev.pos, keys: prev.keys };
+        };
+
+        let duration = next.ts - prev.ts;
+
+        let pos = if duration <= 0.0 {
+            next.pos
+        } else {
+            prev.pos + (next.pos - prev.pos) * ((ts - prev.ts) / duration)
+        };
+
+        InterpolatedFrame { ts, pos, keys: prev.keys }
+    }
+
+    /// Frames in `(from, to]` where a key goes down. Imported replays
+    /// don't have `hold` set, so keys are compared to the previous frame
+    pub fn presses_between(&self, from: f64, to: f64) -> impl Iterator<Item = &OsuInput> {
+        let frames = self.frames();
+        let start = frames.partition_point(|x| x.ts <= from);
+        let end = frames.partition_point(|x| x.ts <= to);
+
+        (start..end).filter_map(move |i| {
+            let frame = &frames[i];
+            let prev = i.checked_sub(1)
+                .map(|i| frames[i].keys)
+                .unwrap_or_default();
+
+            let is_pressed = (frame.keys.k1 && !prev.k1) || (frame.keys.k2 && !prev.k2);
+
+            is_pressed.then_some(frame)
+        })
+    }
+}
+
+#[cfg(test)]
+fn test_frames() -> Vec<OsuInput> {
+    let input = |ts: f64, x: f64, k1: bool| OsuInput {
+        ts,
+        pos: Vector2::new(x, x * 2.0),
+        keys: KeyboardState { k1, k2: false },
+        hold: KeyboardState::empty(),
+        generation: 0,
+    };
+
+    vec![
+        input(100.0, 0.0, false),
+        input(200.0, 100.0, true),
+        input(300.0, 150.0, false),
+    ]
+}
+
+#[test]
+fn test_replay_log_sample() {
+    let mut log = ReplayLog::from_frames(test_frames());
+
+    // Exact frame times
+    let frame = log.sample(200.0);
+    assert_eq!(frame.pos, Vector2::new(100.0, 200.0));
+    assert!(frame.keys.k1);
+
+    // Between frames, keys of the earlier one
+    let frame = log.sample(250.0);
+    assert_eq!(frame.pos, Vector2::new(125.0, 250.0));
+    assert!(frame.keys.k1);
+
+    // Clamped at the ends
+    let frame = log.sample(1000.0);
+    assert_eq!(frame.pos, Vector2::new(150.0, 300.0));
+    assert!(!frame.keys.k1);
+
+    let frame = log.sample(0.0);
+    assert_eq!(frame.pos, Vector2::new(0.0, 0.0));
+    assert!(!frame.keys.is_keys_hit());
+
+    assert_eq!(ReplayLog::default().sample(10.0).pos, Vector2::new(0.0, 0.0));
+}
+
+#[test]
+fn test_replay_log_sample_seek() {
+    let mut log = ReplayLog::from_frames(test_frames());
+
+    assert_eq!(log.sample(150.0).pos.x, 50.0);
+    assert_eq!(log.sample(250.0).pos.x, 125.0);
+    assert_eq!(log.next_index, 2);
+
+    // Backwards seek falls back to the search
+    assert_eq!(log.sample(120.0).pos.x, 20.0);
+    assert_eq!(log.next_index, 1);
+
+    assert_eq!(log.sample(300.0).pos.x, 150.0);
+    assert_eq!(log.next_index, 3);
+}
+
+#[test]
+fn test_replay_log_presses_between() {
+    let log = ReplayLog::from_frames(test_frames());
+
+    let presses = |from, to| log.presses_between(from, to)
+        .map(|x| x.ts)
+        .collect::<Vec<_>>();
+
+    assert_eq!(presses(0.0, 1000.0), [200.0]);
+    assert_eq!(presses(150.0, 200.0), [200.0]);
+
+    // Start of the range is excluded, so a frame is never counted twice
+    assert!(presses(200.0, 1000.0).is_empty());
+    assert!(presses(0.0, 150.0).is_empty());
 }
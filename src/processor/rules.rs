@@ -0,0 +1,159 @@
+use std::borrow::Cow;
+
+use crate::osu_input::{KeyboardState, OsuInput};
+
+/// How early relax presses the key before object's start time, in ms
+pub const RELAX_HIT_LENIENCY: f64 = 3.0;
+
+/// No Fail bit of stable mods
+pub const MOD_NO_FAIL: u32 = 1 << 0;
+/// Hard Rock bit of stable mods
+pub const MOD_HARD_ROCK: u32 = 1 << 4;
+/// Relax bit of stable mods
+pub const MOD_RELAX: u32 = 1 << 7;
+
+/// Practice toggles that change how inputs are judged.
+///
+/// Objects know nothing about these, processor just feeds
+/// them inputs adjusted by the rules
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct GameplayRules {
+    /// Keys are pressed automatically, only aim matters
+    pub relax: bool,
+    /// Play can't be failed regardless of health.
+    /// Nothing to check yet since health is not implemented
+    pub no_fail: bool,
+    /// Playfield is flipped vertically and difficulty is raised,
+    /// same as stable. Not a practice toggle, stays ranked
+    pub hard_rock: bool,
+    pub difficulty: DifficultyOverrides,
+}
+
+/// Difficulty values used instead of the beatmap ones,
+/// `None` keeps the value from the beatmap
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct DifficultyOverrides {
+    pub cs: Option<f32>,
+    pub ar: Option<f32>,
+    pub od: Option<f32>,
+    pub hp: Option<f32>,
+}
+
+impl DifficultyOverrides {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.cs.is_none() && self.ar.is_none() && self.od.is_none() && self.hp.is_none()
+    }
+}
+
+impl GameplayRules {
+    /// Rules a stable replay was played with. Only Hard Rock
+    /// changes how its frames are judged here
+    pub fn from_replay_mods(mods: u32) -> Self {
+        Self {
+            hard_rock: mods & MOD_HARD_ROCK != 0,
+            ..Default::default()
+        }
+    }
+
+    /// Stable mods of a replay made with these rules, difficulty
+    /// overrides have no stable counterpart and are left out
+    pub fn replay_mods(&self) -> u32 {
+        let mut mods = 0;
+
+        if self.no_fail {
+            mods |= MOD_NO_FAIL;
+        }
+
+        if self.hard_rock {
+            mods |= MOD_HARD_ROCK;
+        }
+
+        if self.relax {
+            mods |= MOD_RELAX;
+        }
+
+        mods
+    }
+
+    /// Scores made with any practice toggle are unranked
+    #[inline]
+    pub fn is_ranked(&self) -> bool {
+        !self.relax && !self.no_fail && self.difficulty.is_empty()
+    }
+
+    /// Short names of enabled toggles, used for displaying
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+
+        if self.relax {
+            names.push("Relax");
+        }
+
+        if self.no_fail {
+            names.push("No-Fail");
+        }
+
+        if self.hard_rock {
+            names.push("Hard Rock");
+        }
+
+        if !self.difficulty.is_empty() {
+            names.push("Difficulty Adjust");
+        }
+
+        names
+    }
+
+    /// Input used to hit an object that starts at `start_time`.
+    ///
+    /// With relax it's a fresh key press once object's
+    /// time comes, so only cursor position is judged
+    pub fn hit_input<'a>(&self, input: &'a OsuInput, start_time: f64) -> Cow<'a, OsuInput> {
+        if !self.relax || input.ts < start_time - RELAX_HIT_LENIENCY {
+            return Cow::Borrowed(input);
+        }
+
+        Cow::Owned(OsuInput {
+            keys: KeyboardState { k1: true, k2: false },
+            hold: KeyboardState::empty(),
+            ..input.clone()
+        })
+    }
+
+    /// Input used for slider tracking, with relax
+    /// key is always considered as being held
+    pub fn hold_input<'a>(&self, input: &'a OsuInput) -> Cow<'a, OsuInput> {
+        if !self.relax {
+            return Cow::Borrowed(input);
+        }
+
+        Cow::Owned(OsuInput {
+            keys: KeyboardState { k1: true, k2: false },
+            hold: KeyboardState { k1: true, k2: false },
+            ..input.clone()
+        })
+    }
+}
+
+#[test]
+fn test_relax_hit_input() {
+    let rules = GameplayRules { relax: true, ..Default::default() };
+
+    let input = OsuInput {
+        ts: 1000.0,
+        pos: (0.0, 0.0).into(),
+        keys: KeyboardState::empty(),
+        hold: KeyboardState::empty(),
+        generation: 0,
+    };
+
+    assert!(!rules.hit_input(&input, 1100.0).is_keys_hit_no_hold());
+    assert!(rules.hit_input(&input, 1000.0 + RELAX_HIT_LENIENCY).is_keys_hit_no_hold());
+    assert!(rules.hold_input(&input).is_keys_hold());
+
+    // Without relax inputs are untouched
+    let rules = GameplayRules::default();
+    assert!(!rules.hit_input(&input, 1000.0).is_keys_hit_no_hold());
+    assert!(!rules.hold_input(&input).is_keys_hold());
+}
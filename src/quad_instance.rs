@@ -1,3 +1,5 @@
+use crate::math::debug_assert_finite;
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Zeroable, bytemuck::Pod)]
 pub struct QuadInstance {
@@ -25,6 +27,8 @@ impl QuadInstance {
     }
 
     pub fn from_xy_pos(width: f32, height: f32) -> Self {
+        debug_assert_finite(width, height);
+
         Self {
             pos: [width, height, 1.0],
             color: [0.0, 0.0, 0.0],
@@ -35,6 +39,8 @@ impl QuadInstance {
 
 
     pub fn from_xy_pos_alpha(width: f32, height: f32, alpha: f32) -> Self {
+        debug_assert_finite(width, height);
+
         Self {
             pos: [width, height, 1.0],
             color: [0.0, 0.0, 0.0],
@@ -44,6 +50,8 @@ impl QuadInstance {
     }
 
     pub fn from_xy_pos_alpha_degree(width: f32, height: f32, alpha: f32, degree: f32) -> Self {
+        debug_assert_finite(width, height);
+
         Self {
             pos: [width, height, 1.0],
             color: [0.0, 0.0, 0.0],
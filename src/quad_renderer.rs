@@ -1,6 +1,6 @@
 use std::{ops::Range, sync::Arc};
 
-use cgmath::Vector2;
+use cgmath::{Matrix3, Vector2};
 use wgpu::{util::DeviceExt, BindGroup, Buffer, BufferUsages, TextureView};
 
 use crate::{camera::Camera, graphics::Graphics, quad_instance::QuadInstance, texture::{AtlasTexture, Texture}, vertex::{AtlasQuadVertex, Vertex}};
@@ -11,8 +11,8 @@ pub struct AtlasInfo {
     atlas_pipeline: wgpu::RenderPipeline
 }
 
-pub struct QuadRenderer<'qr> {
-    graphics: Arc<Graphics<'qr>>,
+pub struct QuadRenderer {
+    graphics: Arc<Graphics>,
 
     quad_vertex_buffer: wgpu::Buffer,
     quad_index_buffer: wgpu::Buffer,
@@ -26,21 +26,22 @@ pub struct QuadRenderer<'qr> {
     atlas: Option<AtlasInfo>,
 }
 
-impl<'qr> QuadRenderer<'qr> {
+impl QuadRenderer {
     pub fn new(
-        graphics: Arc<Graphics<'qr>>, 
+        graphics: Arc<Graphics>, 
         is_using_atlas: bool
     ) -> Self {
         let quad_shader = graphics
             .device
             .create_shader_module(wgpu::include_wgsl!("shaders/quad.wgsl"));
 
-        let surface_config = graphics.get_surface_config();
+        let format = graphics.format();
+        let (width, height) = graphics.get_surface_size();
 
         let camera = Camera::new(
             &graphics,
-            surface_config.width as f32,
-            surface_config.height as f32,
+            width as f32,
+            height as f32,
             1.0,
         );
 
@@ -92,7 +93,8 @@ impl<'qr> QuadRenderer<'qr> {
                         module: &quad_shader,
                         entry_point: Some("fs_main"),
                         targets: &[Some(wgpu::ColorTargetState {
-                            format: surface_config.format,
+                            format,
+                            // Straight alpha OVER, see `osu_renderer`
                             blend: Some(wgpu::BlendState {
                                 color: wgpu::BlendComponent {
                                     src_factor: wgpu::BlendFactor::SrcAlpha,
@@ -166,7 +168,8 @@ impl<'qr> QuadRenderer<'qr> {
                         module: &atlas_quad_shader,
                         entry_point: Some("fs_main"),
                         targets: &[Some(wgpu::ColorTargetState {
-                            format: surface_config.format,
+                            format,
+                            // Straight alpha OVER, see `osu_renderer`
                             blend: Some(wgpu::BlendState {
                                 color: wgpu::BlendComponent {
                                     src_factor: wgpu::BlendFactor::SrcAlpha,
@@ -241,6 +244,12 @@ impl<'qr> QuadRenderer<'qr> {
         self.write_camera_buffer();
     }
 
+    pub fn set_playfield_matrix(&mut self, matrix: Matrix3<f64>) {
+        self.camera.set_playfield_matrix(matrix);
+
+        self.write_camera_buffer();
+    }
+
     pub fn resize_vertex_centered(&self, width: f32, height: f32) {
         self.graphics
             .queue
@@ -249,6 +258,16 @@ impl<'qr> QuadRenderer<'qr> {
             ));
     }
 
+    /// Same as [`Self::resize_vertex_centered`] but instance
+    /// position is the top-left corner of the quad
+    pub fn resize_vertex_top_left(&self, width: f32, height: f32) {
+        self.graphics
+            .queue
+            .write_buffer(&self.quad_vertex_buffer, 0, bytemuck::cast_slice(
+                &Vertex::quad_origin(0.0, 0.0, width, height)
+            ));
+    }
+
     pub fn create_instance_buffer(&self) -> Buffer {
         self.graphics
             .device
@@ -264,6 +283,7 @@ impl<'qr> QuadRenderer<'qr> {
         width: f32, height: f32,
         image_index: u32,
         alpha: f32,
+        tint: [f32; 4],
         atlas: &AtlasTexture
     ) -> [AtlasQuadVertex; 6] {
         let atlas_width = atlas.width();
@@ -286,14 +306,14 @@ impl<'qr> QuadRenderer<'qr> {
 
         [
             // First triangle (bottom-left, top-left, top-right)
-            AtlasQuadVertex { pos: [x - half_width, y - half_height, 0.0].into(), uv: [u_min, v_min], alpha }, // Bottom-left
-            AtlasQuadVertex { pos: [x - half_width, y + half_height, 0.0].into(), uv: [u_min, v_max], alpha }, // Top-left
-            AtlasQuadVertex { pos: [x + half_width, y + half_height, 0.0].into(), uv: [u_max, v_max], alpha }, // Top-right
+            AtlasQuadVertex { pos: [x - half_width, y - half_height, 0.0].into(), uv: [u_min, v_min], alpha, tint }, // Bottom-left
+            AtlasQuadVertex { pos: [x - half_width, y + half_height, 0.0].into(), uv: [u_min, v_max], alpha, tint }, // Top-left
+            AtlasQuadVertex { pos: [x + half_width, y + half_height, 0.0].into(), uv: [u_max, v_max], alpha, tint }, // Top-right
 
             // Second triangle (bottom-left, top-right, bottom-right)
-            AtlasQuadVertex { pos: [x - half_width, y - half_height, 0.0].into(), uv: [u_min, v_min], alpha }, // Bottom-left
-            AtlasQuadVertex { pos: [x + half_width, y + half_height, 0.0].into(), uv: [u_max, v_max], alpha }, // Top-right
-            AtlasQuadVertex { pos: [x + half_width, y - half_height, 0.0].into(), uv: [u_max, v_min], alpha }, // Bottom-right
+            AtlasQuadVertex { pos: [x - half_width, y - half_height, 0.0].into(), uv: [u_min, v_min], alpha, tint }, // Bottom-left
+            AtlasQuadVertex { pos: [x + half_width, y + half_height, 0.0].into(), uv: [u_max, v_max], alpha, tint }, // Top-right
+            AtlasQuadVertex { pos: [x + half_width, y - half_height, 0.0].into(), uv: [u_max, v_min], alpha, tint }, // Bottom-right
         ]
     }
 
@@ -303,9 +323,10 @@ impl<'qr> QuadRenderer<'qr> {
         width: f32, height: f32,
         image_index: u32,
         alpha: f32,
+        tint: [f32; 4],
         atlas: &AtlasTexture
     ) {
-        let verticies = Self::atlas_quad_centered(x,y, width, height, image_index, alpha, atlas);
+        let verticies = Self::atlas_quad_centered(x,y, width, height, image_index, alpha, tint, atlas);
 
         if let Some(ref mut atlas) = &mut self.atlas {
             atlas.atlas_vertex_data.extend(verticies);
@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::rng::AppRng;
+
+/// Beatmaps left behind by random picks that can be returned to
+pub const RANDOM_HISTORY_LEN: usize = 64;
+
+/// Random beatmap selection (F2) along with the way back (Shift+F2).
+/// Works with indices of the song select list, history is dropped
+/// once the list is filtered differently
+pub struct RandomHistory {
+    rng: AppRng,
+    /// Selections random picks moved away from, latest last
+    previous: VecDeque<usize>,
+}
+
+impl RandomHistory {
+    pub fn new(rng: AppRng) -> Self {
+        Self {
+            rng,
+            previous: VecDeque::with_capacity(RANDOM_HISTORY_LEN),
+        }
+    }
+
+    /// Random index out of `amount` that isn't `current` unless it's
+    /// the only one, `current` is remembered. `None` for an empty list
+    pub fn pick(&mut self, current: usize, amount: usize) -> Option<usize> {
+        if amount == 0 {
+            return None;
+        }
+
+        let index = match amount {
+            1 => 0,
+            // Skipping over `current` keeps the rest equally likely
+            _ => {
+                let index = self.rng.gen_range(0..amount - 1);
+
+                match index >= current {
+                    true => index + 1,
+                    false => index,
+                }
+            },
+        };
+
+        if self.previous.len() == RANDOM_HISTORY_LEN {
+            self.previous.pop_front();
+        }
+
+        self.previous.push_back(current);
+
+        Some(index)
+    }
+
+    /// Selection before the last random pick
+    pub fn rewind(&mut self) -> Option<usize> {
+        self.previous.pop_back()
+    }
+
+    pub fn clear(&mut self) {
+        self.previous.clear();
+    }
+}
@@ -0,0 +1,139 @@
+//! Per-frame renderer counters for the debug overlay.
+//!
+//! Everything except [`render_stat!`] only exists with the `render-stats`
+//! feature, without it the macro expands to nothing so counting has no cost
+
+#[cfg(feature = "render-stats")]
+use std::{fmt::Display, sync::atomic::{AtomicU64, Ordering}, time::{Duration, Instant}};
+
+/// Adds to a [`RenderStats`] field, compiled out without `render-stats` feature.
+///
+/// `render_stat!(self.stats, draw_calls += 1)`
+#[macro_export]
+macro_rules! render_stat {
+    ($stats:expr, $field:ident += $value:expr) => {
+        #[cfg(feature = "render-stats")]
+        {
+            $stats.$field += $value as u32;
+        }
+    };
+}
+
+/// How often a summary is logged at debug level
+#[cfg(feature = "render-stats")]
+pub const RENDER_STATS_LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Bytes of currently alive slider textures, slider textures
+/// are owned by objects so renderer can't sum them on its own
+#[cfg(feature = "render-stats")]
+pub static SLIDER_TEXTURE_BYTES: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(feature = "render-stats")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    pub hit_circle_instances: u32,
+    pub approach_circle_instances: u32,
+    pub slider_instances: u32,
+    pub follow_circle_instances: u32,
+    /// Ticks, reverse arrows and judgements
+    pub quad_instances: u32,
+    pub slider_bakes: u32,
+    /// Instance buffers that had to be recreated to fit the data
+    pub buffer_reallocations: u32,
+}
+
+#[cfg(feature = "render-stats")]
+impl RenderStats {
+    pub fn instances(&self) -> u32 {
+        self.hit_circle_instances
+            + self.approach_circle_instances
+            + self.slider_instances
+            + self.follow_circle_instances
+            + self.quad_instances
+    }
+
+    pub fn slider_texture_bytes() -> u64 {
+        SLIDER_TEXTURE_BYTES.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(feature = "render-stats")]
+impl Display for RenderStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "draw calls: {}, instances: {} (circles {}, approach {}, sliders {}, follow {}, quads {}), \
+            slider bakes: {}, slider textures: {:.1}MiB, buffer reallocations: {}",
+            self.draw_calls,
+            self.instances(),
+            self.hit_circle_instances,
+            self.approach_circle_instances,
+            self.slider_instances,
+            self.follow_circle_instances,
+            self.quad_instances,
+            self.slider_bakes,
+            Self::slider_texture_bytes() as f64 / (1024.0 * 1024.0),
+            self.buffer_reallocations,
+        )
+    }
+}
+
+/// Logs the last frame stats once in [`RENDER_STATS_LOG_INTERVAL`]
+#[cfg(feature = "render-stats")]
+pub struct RenderStatsLogger {
+    last_logged: Instant,
+}
+
+#[cfg(feature = "render-stats")]
+impl Default for RenderStatsLogger {
+    fn default() -> Self {
+        Self { last_logged: Instant::now() }
+    }
+}
+
+#[cfg(feature = "render-stats")]
+impl RenderStatsLogger {
+    pub fn on_frame(&mut self, stats: &RenderStats) {
+        if self.last_logged.elapsed() < RENDER_STATS_LOG_INTERVAL {
+            return;
+        }
+
+        self.last_logged = Instant::now();
+        tracing::debug!("Render stats: {stats}");
+    }
+}
+
+#[cfg(all(feature = "render-stats", not(target_arch = "wasm32")))]
+pub fn render_stats_ui(ui: &mut egui::Ui, stats: &RenderStats) {
+    egui::CollapsingHeader::new("Renderer")
+        .default_open(false)
+        .show(ui, |ui| {
+            ui.label(format!("Draw calls: {}", stats.draw_calls));
+            ui.label(format!("Instances: {}", stats.instances()));
+            ui.label(format!("  Hit circles: {}", stats.hit_circle_instances));
+            ui.label(format!("  Approach circles: {}", stats.approach_circle_instances));
+            ui.label(format!("  Sliders: {}", stats.slider_instances));
+            ui.label(format!("  Follow circles: {}", stats.follow_circle_instances));
+            ui.label(format!("  Quads: {}", stats.quad_instances));
+            ui.label(format!("Slider bakes: {}", stats.slider_bakes));
+            ui.label(format!(
+                "Slider textures: {:.1} MiB",
+                RenderStats::slider_texture_bytes() as f64 / (1024.0 * 1024.0)
+            ));
+            ui.label(format!("Buffer reallocations: {}", stats.buffer_reallocations));
+        });
+}
+
+#[cfg(feature = "render-stats")]
+#[test]
+fn test_render_stat_macro() {
+    let mut stats = RenderStats::default();
+
+    render_stat!(stats, draw_calls += 2);
+    render_stat!(stats, hit_circle_instances += 3usize);
+    render_stat!(stats, quad_instances += 1);
+
+    assert_eq!(stats.draw_calls, 2);
+    assert_eq!(stats.instances(), 4);
+}
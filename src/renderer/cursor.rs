@@ -4,16 +4,24 @@ use std::{collections::VecDeque, sync::{Arc, RwLock}, time::{Duration, Instant}}
 use wgpu::{util::DeviceExt, BufferUsages, TextureView};
 use winit::dpi::PhysicalPosition;
 
-use crate::{graphics::Graphics, quad_instance::QuadInstance, quad_renderer::QuadRenderer, skin_manager::SkinManager};
+use crate::{graphics::Graphics, osu_input::KeyboardState, quad_instance::QuadInstance, quad_renderer::QuadRenderer, skin_manager::SkinManager};
 
 // TODO: control this through settings
 const TRAIL_KEEP_MS: u64 = 55;
 const TARGET_TRAIL_UPDATE_RATE: f64 = 120.0; // Per sec
 const BASE_CURSOR_SIZE: f32 = 50.0;
+/// Screen pixels per SD skin pixel at 1x cursor size
+const CURSOR_SKIN_SCALE: f32 = 2.0;
 
-pub struct CursorRenderer<'cr> {
-    graphics: Arc<Graphics<'cr>>,
-    quad_renderer: QuadRenderer<'cr>,
+const CURSOR_EXPAND_SCALE: f32 = 1.3;
+/// How fast expand scale follows the target, per second
+const CURSOR_EXPAND_SPEED: f32 = 15.0;
+/// Degrees per second
+const CURSOR_ROTATE_SPEED: f32 = 36.0;
+
+pub struct CursorRenderer {
+    graphics: Arc<Graphics>,
+    quad_renderer: QuadRenderer,
 
     skin_manager: Arc<RwLock<SkinManager>>,
     
@@ -26,12 +34,23 @@ pub struct CursorRenderer<'cr> {
 
     inner_buffer: Vec<QuadInstance>,
 
+    /// Size multiplier from the config
     size: f32,
+    /// Multiplier of [`TARGET_TRAIL_UPDATE_RATE`], lowered by adaptive quality
+    trail_density: f64,
+
+    held_keys: KeyboardState,
+    expand: f32,
+    rotation: f32,
+    last_frame: Instant,
+
+    /// (size, is centered) that is currently in the vertex buffer
+    applied_vertex: (f32, bool),
 }
 
-impl<'cr> CursorRenderer<'cr> {
+impl CursorRenderer {
     pub fn new(
-        graphics: Arc<Graphics<'cr>>,
+        graphics: Arc<Graphics>,
         skin_manager: Arc<RwLock<SkinManager>>,
     ) -> Self {
         let quad_renderer = QuadRenderer::new(graphics.clone(), false);
@@ -53,21 +72,76 @@ impl<'cr> CursorRenderer<'cr> {
             last_update: Instant::now(),
             skin_manager,
             size: 1.0,
+            trail_density: 1.0,
             inner_buffer: Vec::with_capacity(10),
+            held_keys: KeyboardState::empty(),
+            expand: 1.0,
+            rotation: 0.0,
+            last_frame: Instant::now(),
+            applied_vertex: (BASE_CURSOR_SIZE, true),
         }
     }
 
+    /// Config size, multiplies on top of the skin cursor size
     pub fn set_size(&mut self, new_size: f32) {
         self.size = new_size;
+    }
 
-        let new_size_multiplied = BASE_CURSOR_SIZE * new_size;
-        self.quad_renderer.resize_vertex_centered(
-            new_size_multiplied,
-            new_size_multiplied
-        );
+    pub fn set_trail_density(&mut self, density: f64) {
+        self.trail_density = density;
+    }
+
+    pub fn on_key_pressed(&mut self, state: KeyboardState) {
+        self.held_keys.k1 |= state.k1;
+        self.held_keys.k2 |= state.k2;
+    }
+
+    /// `true` in `state` means that key is released
+    pub fn on_key_released(&mut self, state: KeyboardState) {
+        self.held_keys.k1 &= !state.k1;
+        self.held_keys.k2 &= !state.k2;
     }
 
     pub fn update(&mut self) {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        let skin = self.skin_manager.read().expect("failed to acquire skin lock");
+        let general = &skin.ini.general;
+
+        let target_expand = if general.cursor_expand && self.held_keys.is_keys_hit() {
+            CURSOR_EXPAND_SCALE
+        } else {
+            1.0
+        };
+
+        self.expand += (target_expand - self.expand) * (dt * CURSOR_EXPAND_SPEED).min(1.0);
+
+        if general.cursor_rotate {
+            self.rotation = (self.rotation + dt * CURSOR_ROTATE_SPEED) % 360.0;
+        } else {
+            self.rotation = 0.0;
+        }
+
+        let cursor_size = skin.cursor.logical_width() * CURSOR_SKIN_SCALE * self.size * self.expand;
+        let is_centered = general.cursor_centre;
+
+        drop(skin);
+
+        // Vertex buffer is rewritten only when something changed
+        if self.applied_vertex != (cursor_size, is_centered) {
+            if is_centered {
+                self.quad_renderer.resize_vertex_centered(cursor_size, cursor_size);
+            } else {
+                self.quad_renderer.resize_vertex_top_left(cursor_size, cursor_size);
+            }
+
+            self.applied_vertex = (cursor_size, is_centered);
+        }
+
+        self.cursor_instance.degree = self.rotation;
+
         self.trail_instance_data.retain(|(last, _)| {
             if *last < Instant::now() - Duration::from_millis(TRAIL_KEEP_MS) {
                 false
@@ -78,11 +152,13 @@ impl<'cr> CursorRenderer<'cr> {
     }
 
     pub fn on_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
-        let instance = QuadInstance::from_xy_pos(position.x as f32, position.y as f32);
+        let instance = QuadInstance::from_xy_pos_alpha_degree(
+            position.x as f32, position.y as f32, 1.0, self.rotation
+        );
         
         // Weird logic required to keep cursor trail updated at the same rate
         let now = Instant::now();
-        let frame_duration: Duration = Duration::from_secs_f64(1.0 / TARGET_TRAIL_UPDATE_RATE as f64);
+        let frame_duration: Duration = Duration::from_secs_f64(1.0 / (TARGET_TRAIL_UPDATE_RATE * self.trail_density));
         let update_time = now.duration_since(self.last_update);
         if update_time > frame_duration {
             self.trail_instance_data.push_back((Instant::now(), self.cursor_instance));
@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use cgmath::Vector2;
+use wgpu::{util::DeviceExt, BindGroup, BufferUsages, TextureView};
+use winit::dpi::PhysicalSize;
+
+use crate::{camera::Camera, graphics::Graphics, hit_circle_instance::HitCircleInstance, particles::{ParticleKind, ParticleSystem, MAX_PARTICLES}, rgb::Rgb, texture::Texture, vertex::Vertex};
+
+/// Ripple diameter in screen pixels at 1x scale once fully expanded
+const RIPPLE_SIZE: f32 = 128.0;
+/// Ripple starts at this fraction of its final size
+const RIPPLE_START_SCALE: f32 = 0.3;
+
+/// Draws particles of a [`ParticleSystem`] as instanced quads in screen
+/// space. Instances and their buffer are sized for [`MAX_PARTICLES`]
+/// up front, so preparing a frame never allocates
+pub struct ParticleRenderer {
+    graphics: Arc<Graphics>,
+    pipeline: wgpu::RenderPipeline,
+    camera: Camera,
+
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    instance_buffer: wgpu::Buffer,
+    instances: Vec<HitCircleInstance>,
+}
+
+impl ParticleRenderer {
+    pub fn new(graphics: Arc<Graphics>) -> Self {
+        let shader = graphics
+            .device
+            .create_shader_module(wgpu::include_wgsl!("../shaders/quad_textured.wgsl"));
+
+        let (width, height) = graphics.get_surface_size();
+        let camera = Camera::new(&graphics, width as f32, height as f32, 1.0);
+
+        let vertex_buffer = graphics
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("particle vertex buffer"),
+                contents: bytemuck::cast_slice(&Vertex::quad_centered(1.0, 1.0)),
+                usage: BufferUsages::VERTEX,
+            });
+
+        let index_buffer = graphics
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("particle index buffer"),
+                contents: bytemuck::cast_slice(crate::osu_renderer::QUAD_INDECIES),
+                usage: BufferUsages::INDEX,
+            });
+
+        let instance_buffer = graphics
+            .device
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some("particle instance buffer"),
+                size: (MAX_PARTICLES * size_of::<HitCircleInstance>()) as u64,
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+        let pipeline_layout = graphics
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("particle pipeline layout"),
+                bind_group_layouts: &[
+                    &Texture::default_bind_group_layout(&graphics, 1),
+                    camera.bind_group_layout(),
+                ],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline = graphics
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("particle render pipeline"),
+                cache: None,
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[Vertex::desc(), HitCircleInstance::desc()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_untinted"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: graphics.format(),
+                        // Straight alpha OVER, see `osu_renderer`
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent {
+                                src_factor: wgpu::BlendFactor::SrcAlpha,
+                                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                                operation: wgpu::BlendOperation::Add,
+                            },
+                            alpha: wgpu::BlendComponent::OVER,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            });
+
+        Self {
+            graphics,
+            pipeline,
+            camera,
+            vertex_buffer,
+            index_buffer,
+            instance_buffer,
+            instances: Vec::with_capacity(MAX_PARTICLES),
+        }
+    }
+
+    pub fn on_resize(&mut self, new_size: &PhysicalSize<u32>) {
+        self.camera.resize(new_size);
+        self.camera.write_buffers(&self.graphics);
+    }
+
+    /// Builds instances of particles alive at `time`, `to_screen` maps
+    /// their playfield positions to screen pixels. `scale` multiplies
+    /// particle sizes
+    pub fn prepare(
+        &mut self,
+        particles: &ParticleSystem,
+        time: f64,
+        scale: f32,
+        to_screen: impl Fn(Vector2<f64>) -> Vector2<f64>,
+    ) {
+        let _span = tracy_client::span!("particle_renderer::prepare");
+
+        self.instances.clear();
+
+        let white = Rgb::new(255, 255, 255);
+
+        for (particle, progress) in particles.alive(time) {
+            let pos = to_screen(particle.pos);
+            let progress = progress as f32;
+
+            let instance = match particle.kind {
+                ParticleKind::CursorRipple => {
+                    // Fast start that slows down towards the end
+                    let expand = 1.0 - (1.0 - progress).powi(3);
+                    let size = RIPPLE_SIZE * scale
+                        * (RIPPLE_START_SCALE + (1.0 - RIPPLE_START_SCALE) * expand);
+
+                    HitCircleInstance::new(pos.x as f32, pos.y as f32, 1.0, 1.0 - progress, size, &white, 0.0)
+                },
+            };
+
+            self.instances.push(instance);
+        }
+    }
+
+    /// Draws instances from the last [`Self::prepare`] with `texture`
+    pub fn render_on_view(&self, view: &TextureView, texture: &BindGroup) {
+        if self.instances.is_empty() {
+            return;
+        }
+
+        self.graphics.queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&self.instances));
+
+        let mut encoder = self.graphics
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("particle render encoder"),
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("particle render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, texture, &[]);
+            render_pass.set_bind_group(1, self.camera.bind_group(), &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+            render_pass.draw_indexed(
+                0..crate::osu_renderer::QUAD_INDECIES.len() as u32,
+                0,
+                0..self.instances.len() as u32,
+            );
+        }
+
+        self.graphics.queue.submit([encoder.finish()]);
+    }
+}
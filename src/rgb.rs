@@ -61,7 +61,7 @@ impl Rgb {
         ]
     }
     
-    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg(all(feature = "render", not(target_arch = "wasm32")))]
     pub fn to_egui_color(&self) -> egui::Color32 {
         egui::Color32::from_rgb(self.r(), self.g(), self.b())
     }
@@ -0,0 +1,98 @@
+use rand::{rngs::SmallRng, Rng, RngCore, SeedableRng};
+
+/// Seed used instead of the config and the entropy one when set
+pub const RNG_SEED_ENV: &str = "ROSU_SEED";
+
+/// Randomness of the whole app comes from here, so flows like
+/// random beatmap selection can be replayed with a fixed seed.
+/// Subsystems get their own generator through [`AppRng::split`],
+/// that way one of them drawing more numbers doesn't shift the others
+#[derive(Debug, Clone)]
+pub struct AppRng {
+    rng: SmallRng,
+    seed: u64,
+}
+
+impl AppRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            rng: SmallRng::seed_from_u64(seed),
+            seed,
+        }
+    }
+
+    /// Seed is drawn from the OS, it's still logged
+    /// so a session can be reproduced afterwards
+    pub fn from_entropy() -> Self {
+        Self::from_seed(rand::random())
+    }
+
+    /// [`RNG_SEED_ENV`] first, then `config_seed`, entropy otherwise
+    pub fn new(config_seed: Option<u64>) -> Self {
+        let env_seed = std::env::var(RNG_SEED_ENV).ok().and_then(|x| {
+            x.trim().parse()
+                .inspect_err(|e| tracing::warn!("Ignoring {RNG_SEED_ENV}={x:?}: {e}"))
+                .ok()
+        });
+
+        let rng = match env_seed.or(config_seed) {
+            Some(seed) => Self::from_seed(seed),
+            None => Self::from_entropy(),
+        };
+
+        tracing::info!("RNG seed: {}", rng.seed);
+
+        rng
+    }
+
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Independent generator seeded from this one
+    pub fn split(&mut self) -> Self {
+        Self::from_seed(self.rng.gen())
+    }
+}
+
+impl RngCore for AppRng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    #[inline]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+#[test]
+fn test_rng_seed() {
+    let mut a = AppRng::from_seed(727);
+    let mut b = AppRng::from_seed(727);
+
+    let numbers = |rng: &mut AppRng| (0..16).map(|_| rng.gen_range(0..1000)).collect::<Vec<u32>>();
+
+    assert_eq!(numbers(&mut a), numbers(&mut b));
+    assert_eq!(a.seed(), 727);
+
+    // Children don't depend on what the parent draws afterwards
+    let mut child_a = a.split();
+    let mut child_b = b.split();
+    a.next_u64();
+
+    assert_eq!(numbers(&mut child_a), numbers(&mut child_b));
+    assert_ne!(numbers(&mut AppRng::from_seed(1)), numbers(&mut AppRng::from_seed(2)));
+}
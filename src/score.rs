@@ -0,0 +1,333 @@
+use cgmath::Vector2;
+
+use crate::{hit_objects::{slider::SliderEvent, Hit}, processor::rules::GameplayRules};
+
+/// Amount of points in accuracy graph, should be enough
+/// for any graph size we are showing
+pub const GRAPH_POINTS: usize = 200;
+
+/// Play statistics, collected as judgements happen
+#[derive(Default, Debug, Clone)]
+pub struct Score {
+    pub x300: u32,
+    pub x100: u32,
+    pub x50: u32,
+    pub miss: u32,
+
+    pub combo: u32,
+    pub max_combo: u32,
+
+    /// (time, running accuracy) pairs, one per judgement
+    pub accuracy_series: Vec<(f64, f64)>,
+
+    /// Click positions relative to the object center in osu!px,
+    /// one per hit circle or slider head that was hit
+    pub hit_offsets: Vec<Vector2<f64>>,
+    /// Hit circle radius of the play in osu!px, scale for `hit_offsets`
+    pub circle_radius: f64,
+
+    /// Rules the play was made with
+    pub rules: GameplayRules,
+}
+
+impl Score {
+    pub fn push(&mut self, time: f64, hit: Hit) {
+        match hit {
+            Hit::X300 => self.x300 += 1,
+            Hit::X100 => self.x100 += 1,
+            Hit::X50 => self.x50 += 1,
+            Hit::MISS => self.miss += 1,
+        }
+
+        self.accuracy_series.push((time, self.accuracy()));
+    }
+
+    /// Circles and slider parts, slider's final result
+    /// doesn't touch combo since its parts already did
+    pub fn push_combo(&mut self, hit: bool) {
+        if hit {
+            self.combo += 1;
+            self.max_combo = self.max_combo.max(self.combo);
+        } else {
+            self.combo = 0;
+        }
+    }
+
+    pub fn push_slider_event(&mut self, event: &SliderEvent) {
+        if event.is_combo_hit() {
+            self.push_combo(true);
+        } else if event.is_combo_break() {
+            self.push_combo(false);
+        }
+    }
+
+    pub fn push_hit_offset(&mut self, offset: Vector2<f64>) {
+        self.hit_offsets.push(offset);
+    }
+
+    /// Average aim error, `None` if nothing was hit
+    pub fn mean_hit_offset(&self) -> Option<Vector2<f64>> {
+        if self.hit_offsets.is_empty() {
+            return None;
+        }
+
+        let sum = self.hit_offsets
+            .iter()
+            .fold(Vector2::new(0.0, 0.0), |acc, x| acc + *x);
+
+        Some(sum / self.hit_offsets.len() as f64)
+    }
+
+    #[inline]
+    pub fn is_ranked(&self) -> bool {
+        self.rules.is_ranked()
+    }
+
+    #[inline]
+    pub fn judgements(&self) -> u32 {
+        self.x300 + self.x100 + self.x50 + self.miss
+    }
+
+    /// Sum of judgement values, stands in for the score
+    /// since combo and mod multipliers are not implemented
+    #[inline]
+    pub fn points(&self) -> u32 {
+        self.x300 * 300 + self.x100 * 100 + self.x50 * 50
+    }
+
+    /// Accuracy in 0.0..=1.0 range, play without judgements is 100%
+    pub fn accuracy(&self) -> f64 {
+        let total = self.judgements();
+
+        if total == 0 {
+            return 1.0;
+        }
+
+        self.points() as f64 / (total * 300) as f64
+    }
+
+    /// Accuracy series reduced to at most `max_points`.
+    /// Keeps the lowest accuracy of every bucket so drops
+    /// are not smoothed away
+    pub fn downsampled_accuracy(&self, max_points: usize) -> Vec<(f64, f64)> {
+        downsample(&self.accuracy_series, max_points)
+    }
+}
+
+/// Score at time track of a play, stored with local scores so
+/// later attempts can be compared to the best one as they go
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Progression {
+    /// (time in ms, points) sorted by time
+    points: Vec<(f64, u32)>,
+}
+
+impl Progression {
+    /// Time as i32 ms and points as u32, both little-endian
+    const POINT_SIZE: usize = 8;
+
+    /// Built from the same series the accuracy graph is, reduced to
+    /// [`GRAPH_POINTS`]. The last judgement of every bucket is kept
+    /// since points only grow
+    pub fn from_score(score: &Score) -> Self {
+        let series = &score.accuracy_series;
+
+        let bucket_size = series.len().div_ceil(GRAPH_POINTS).max(1);
+
+        let points = series
+            .iter()
+            .enumerate()
+            .map(|(i, (time, accuracy))| (*time, (accuracy * (i + 1) as f64 * 300.0).round() as u32))
+            .collect::<Vec<_>>()
+            .chunks(bucket_size)
+            .map(|bucket| *bucket.last().expect("bucket can't be empty"))
+            .collect();
+
+        Self { points }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Points at `time`, linear between the stored ones. Nothing is
+    /// judged before the first one and nothing changes after the last one
+    pub fn score_at(&self, time: f64) -> Option<f64> {
+        let first = self.points.first()?;
+        let last = self.points.last()?;
+
+        if time < first.0 {
+            return Some(0.0);
+        }
+
+        if time >= last.0 {
+            return Some(last.1 as f64);
+        }
+
+        let next = self.points.partition_point(|x| x.0 <= time);
+        let (t0, p0) = self.points[next - 1];
+        let (t1, p1) = self.points[next];
+
+        let progress = (time - t0) / (t1 - t0);
+
+        Some(p0 as f64 + (p1 as f64 - p0 as f64) * progress)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.points.len() * Self::POINT_SIZE);
+
+        for (time, points) in &self.points {
+            bytes.extend_from_slice(&(time.round() as i32).to_le_bytes());
+            bytes.extend_from_slice(&points.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    /// `None` if `bytes` are not made of whole points
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() % Self::POINT_SIZE != 0 {
+            return None;
+        }
+
+        let points = bytes
+            .chunks_exact(Self::POINT_SIZE)
+            .map(|x| {
+                let time = i32::from_le_bytes(x[..4].try_into().expect("chunk is 8 bytes"));
+                let points = u32::from_le_bytes(x[4..].try_into().expect("chunk is 8 bytes"));
+
+                (time as f64, points)
+            })
+            .collect();
+
+        Some(Self { points })
+    }
+}
+
+pub fn downsample(series: &[(f64, f64)], max_points: usize) -> Vec<(f64, f64)> {
+    if series.len() <= max_points || max_points == 0 {
+        return series.to_vec();
+    }
+
+    let bucket_size = series.len().div_ceil(max_points);
+
+    series
+        .chunks(bucket_size)
+        .map(|bucket| {
+            let (_, value) = bucket
+                .iter()
+                .min_by(|a, b| a.1.partial_cmp(&b.1).expect("failed to compare"))
+                .expect("bucket can't be empty");
+
+            (bucket[0].0, *value)
+        })
+        .collect()
+}
+
+#[test]
+fn test_score_accuracy_series() {
+    let mut score = Score::default();
+
+    score.push(100.0, Hit::X300);
+    score.push(200.0, Hit::X100);
+    score.push(300.0, Hit::MISS);
+    score.push(400.0, Hit::X50);
+
+    let expected = [
+        (100.0, 1.0),
+        (200.0, 400.0 / 600.0),
+        (300.0, 400.0 / 900.0),
+        (400.0, 450.0 / 1200.0),
+    ];
+
+    assert_eq!(score.accuracy_series.len(), expected.len());
+
+    for ((time, acc), (expected_time, expected_acc)) in score.accuracy_series.iter().zip(expected) {
+        assert_eq!(*time, expected_time);
+        assert!((acc - expected_acc).abs() < f64::EPSILON);
+    }
+}
+
+#[test]
+fn test_score_downsample() {
+    let mut score = Score::default();
+
+    for i in 0..1000 {
+        let hit = if i == 500 { Hit::MISS } else { Hit::X300 };
+        score.push(i as f64, hit);
+    }
+
+    let points = score.downsampled_accuracy(GRAPH_POINTS);
+
+    assert!(points.len() <= GRAPH_POINTS);
+    assert_eq!(points[0], (0.0, 1.0));
+
+    // Miss shouldn't be smoothed away
+    assert!(points.iter().any(|(_, acc)| *acc < 1.0));
+}
+
+#[test]
+fn test_score_combo() {
+    let mut score = Score::default();
+
+    score.push_combo(true);
+    score.push_slider_event(&SliderEvent::Head { time: 100.0, hit: true });
+    score.push_slider_event(&SliderEvent::Checkpoint { time: 200.0, index: 0, passed: true });
+    assert_eq!(score.combo, 3);
+
+    score.push_slider_event(&SliderEvent::Checkpoint { time: 300.0, index: 1, passed: false });
+    assert_eq!(score.combo, 0);
+
+    // Missed slider end doesn't break combo
+    score.push_combo(true);
+    score.push_slider_event(&SliderEvent::End { time: 400.0, passed: false });
+    assert_eq!((score.combo, score.max_combo), (1, 3));
+}
+
+#[test]
+fn test_progression_score_at() {
+    let mut score = Score::default();
+
+    score.push(100.0, Hit::X300);
+    score.push(200.0, Hit::X100);
+    score.push(400.0, Hit::X300);
+
+    let progression = Progression::from_score(&score);
+
+    // Before the first judgement and after the last one
+    assert_eq!(progression.score_at(0.0), Some(0.0));
+    assert_eq!(progression.score_at(99.9), Some(0.0));
+    assert_eq!(progression.score_at(400.0), Some(700.0));
+    assert_eq!(progression.score_at(10_000.0), Some(700.0));
+
+    // Exactly on the stored points and between them
+    assert_eq!(progression.score_at(100.0), Some(300.0));
+    assert_eq!(progression.score_at(200.0), Some(400.0));
+    assert_eq!(progression.score_at(150.0), Some(350.0));
+    assert_eq!(progression.score_at(300.0), Some(550.0));
+
+    assert_eq!(Progression::default().score_at(100.0), None);
+}
+
+#[test]
+fn test_progression_bytes() {
+    let mut score = Score::default();
+
+    // Marathon-sized play
+    for i in 0..10_000 {
+        let hit = if i % 50 == 0 { Hit::X100 } else { Hit::X300 };
+        score.push(i as f64 * 60.0, hit);
+    }
+
+    let progression = Progression::from_score(&score);
+    let bytes = progression.to_bytes();
+
+    assert!(bytes.len() < 4096, "{} bytes", bytes.len());
+    assert_eq!(Progression::from_bytes(&bytes), Some(progression.clone()));
+
+    // Last judgement is always kept
+    assert_eq!(progression.score_at(f64::MAX), Some(score.points() as f64));
+
+    assert_eq!(Progression::from_bytes(&bytes[..bytes.len() - 1]), None);
+}
@@ -0,0 +1,67 @@
+use egui::{Align2, Color32, FontId};
+
+use crate::{hit_objects::breaks::Break, i18n::{t, tf}};
+
+/// Accuracy at which break is shown as passing
+pub const PASSING_ACCURACY: f64 = 0.9;
+
+/// Period of the dim pulse, in ms
+const PULSE_PERIOD: f64 = 2000.0;
+
+/// Dims playfield with a slow pulse and shows how long
+/// the break lasts and whether play is passing so far
+pub fn render_break_overlay(ctx: &egui::Context, brk: &Break, time: f64, accuracy: f64) {
+    let _span = tracy_client::span!("break_overlay::render_break_overlay");
+
+    let alpha = brk.overlay_alpha(time);
+
+    if alpha <= 0.0 {
+        return;
+    }
+
+    let pulse = ((time - brk.start_time) / PULSE_PERIOD * std::f64::consts::TAU).sin() * 0.5 + 0.5;
+    let dim = (alpha * (60.0 + 30.0 * pulse)) as u8;
+
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let screen = ctx.screen_rect();
+
+    painter.rect_filled(screen, 0.0, Color32::from_black_alpha(dim));
+
+    let text_alpha = (alpha * 255.0) as u8;
+    let center = screen.center();
+
+    painter.text(
+        center,
+        Align2::CENTER_BOTTOM,
+        t("break.title"),
+        FontId::proportional(48.0),
+        Color32::from_white_alpha(text_alpha),
+    );
+
+    let remaining = ((brk.end_time - time) / 1000.0).ceil().max(0.0) as u64;
+
+    painter.text(
+        center + egui::vec2(0.0, 8.0),
+        Align2::CENTER_TOP,
+        tf("break.remaining", &[&remaining]),
+        FontId::proportional(24.0),
+        Color32::from_white_alpha(text_alpha),
+    );
+
+    let (mark, color) = if accuracy >= PASSING_ACCURACY {
+        ("✔", Color32::from_rgba_unmultiplied(80, 220, 80, text_alpha))
+    } else {
+        ("✘", Color32::from_rgba_unmultiplied(220, 60, 60, text_alpha))
+    };
+
+    painter.text(
+        center - egui::vec2(0.0, 72.0),
+        Align2::CENTER_BOTTOM,
+        mark,
+        FontId::proportional(64.0),
+        color,
+    );
+
+    // Overlay is driven by time, not input
+    ctx.request_repaint();
+}
@@ -0,0 +1,30 @@
+use egui::{Align2, Color32, FontId, Stroke, StrokeKind};
+
+use crate::i18n::t;
+
+/// Subtle hint shown while a file is dragged over the window
+pub fn render_drop_overlay(ctx: &egui::Context) {
+    let _span = tracy_client::span!("drop_overlay::render_drop_overlay");
+
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("drop_overlay"),
+    ));
+    let screen = ctx.screen_rect();
+
+    painter.rect(
+        screen.shrink(8.0),
+        8.0,
+        Color32::from_black_alpha(120),
+        Stroke::new(2.0, Color32::from_white_alpha(100)),
+        StrokeKind::Inside,
+    );
+
+    painter.text(
+        screen.center(),
+        Align2::CENTER_CENTER,
+        t("drop.hint"),
+        FontId::proportional(32.0),
+        Color32::from_white_alpha(200),
+    );
+}
@@ -0,0 +1,203 @@
+use egui::{Align, Align2, Color32, FontId, Layout, Order, RichText, Sense, Stroke, StrokeKind, Ui, Vec2};
+
+use crate::{hud_layout::{HudElement, HudLayout, HudPlacement}, i18n::{format_number, t}, osu_input::KeyboardState, score::Score};
+
+/// Font size of text elements at 1x scale
+const FONT_SIZE: f32 = 24.0;
+
+/// Side of a single key of the key overlay at 1x scale
+const KEY_SIZE: f32 = 44.0;
+
+const EDIT_OUTLINE: Color32 = Color32::from_rgb(240, 200, 60);
+
+const PACE_AHEAD: Color32 = Color32::from_rgb(110, 220, 110);
+const PACE_BEHIND: Color32 = Color32::from_rgb(235, 90, 90);
+
+/// Press counts of the key overlay
+#[derive(Default)]
+pub struct KeyOverlayState {
+    presses: [u32; 2],
+    held: KeyboardState,
+}
+
+impl KeyOverlayState {
+    /// Called every gameplay frame, a press is counted once the key goes down
+    pub fn update(&mut self, keys: KeyboardState) {
+        self.presses[0] += (keys.k1 && !self.held.k1) as u32;
+        self.presses[1] += (keys.k2 && !self.held.k2) as u32;
+        self.held = keys;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Draws every HUD element where `layout` says. In edit mode elements are
+/// outlined and can be dragged, scaled with the mouse wheel and reset
+/// with the right click. Returns `true` if `layout` was changed.
+///
+/// `pace` is the difference between the play and the personal best
+/// at the same time, shown next to the score when there is a best
+pub fn render_hud(
+    ctx: &egui::Context,
+    layout: &mut HudLayout,
+    score: &Score,
+    pace: Option<f64>,
+    keys: &KeyOverlayState,
+    edit: bool,
+) -> bool {
+    let _span = tracy_client::span!("hud::render_hud");
+
+    let mut changed = false;
+
+    changed |= show_element(ctx, layout, HudElement::Score, edit, |ui, placement| {
+        let text = format!("{} / {} / {} / {}", score.x300, score.x100, score.x50, score.miss);
+
+        ui.horizontal(|ui| {
+            ui.label(hud_text(text, placement));
+
+            if let Some(pace) = pace {
+                ui.label(pace_text(pace, placement));
+            }
+        });
+    });
+
+    changed |= show_element(ctx, layout, HudElement::Combo, edit, |ui, placement| {
+        ui.label(hud_text(format!("{}x", score.combo), placement));
+    });
+
+    changed |= show_element(ctx, layout, HudElement::Accuracy, edit, |ui, placement| {
+        let accuracy = format_number(score.accuracy() * 100.0, 2);
+        ui.label(hud_text(format!("{accuracy}%"), placement));
+    });
+
+    changed |= show_element(ctx, layout, HudElement::KeyOverlay, edit, |ui, placement| {
+        let align = if placement.anchored_right() { Align::Max } else { Align::Min };
+
+        // Keys grow away from the edge the overlay is attached to
+        let layout = match placement.anchored_bottom() {
+            true => Layout::bottom_up(align),
+            false => Layout::top_down(align),
+        };
+
+        ui.with_layout(layout, |ui| {
+            key(ui, "K1", keys.presses[0], keys.held.k1, placement.scale);
+            key(ui, "K2", keys.presses[1], keys.held.k2, placement.scale);
+        });
+    });
+
+    changed
+}
+
+fn hud_text(text: String, placement: HudPlacement) -> RichText {
+    RichText::new(text)
+        .font(FontId::proportional(FONT_SIZE * placement.scale))
+        .color(Color32::WHITE)
+}
+
+/// `+N` in green when ahead of the best, `-N` in red when behind
+fn pace_text(pace: f64, placement: HudPlacement) -> RichText {
+    let pace = pace.round() as i64;
+
+    let color = match pace >= 0 {
+        true => PACE_AHEAD,
+        false => PACE_BEHIND,
+    };
+
+    hud_text(format!("{pace:+}"), placement).color(color)
+}
+
+fn key(ui: &mut Ui, name: &str, presses: u32, is_held: bool, scale: f32) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(KEY_SIZE * scale), Sense::hover());
+
+    let fill = match is_held {
+        true => Color32::from_rgba_unmultiplied(255, 255, 255, 200),
+        false => Color32::from_black_alpha(160),
+    };
+
+    let text_color = match is_held {
+        true => Color32::BLACK,
+        false => Color32::WHITE,
+    };
+
+    // Name until the first press, then the count
+    let text = match presses {
+        0 => name.to_owned(),
+        n => n.to_string(),
+    };
+
+    ui.painter().rect_filled(rect, 4.0 * scale, fill);
+    ui.painter().text(rect.center(), Align2::CENTER_CENTER, text, FontId::proportional(16.0 * scale), text_color);
+}
+
+/// Places a single element by its placement, kept
+/// on the screen using its size from the last frame
+fn show_element(
+    ctx: &egui::Context,
+    layout: &mut HudLayout,
+    element: HudElement,
+    edit: bool,
+    add_contents: impl FnOnce(&mut Ui, HudPlacement),
+) -> bool {
+    let id = egui::Id::new(("hud", element.name_key()));
+    let screen = ctx.screen_rect();
+
+    let size = ctx.memory(|x| x.area_rect(id))
+        .map(|rect| [rect.width() / screen.width(), rect.height() / screen.height()])
+        .unwrap_or([0.0, 0.0]);
+
+    let placement = layout.get(element).clamped(size);
+
+    let pivot = Align2([
+        if placement.anchored_right() { Align::Max } else { Align::Min },
+        if placement.anchored_bottom() { Align::Max } else { Align::Min },
+    ]);
+
+    let pos = screen.min + Vec2::new(placement.x * screen.width(), placement.y * screen.height());
+
+    let response = egui::Area::new(id)
+        .order(Order::Foreground)
+        .fixed_pos(pos)
+        .pivot(pivot)
+        .interactable(edit)
+        .show(ctx, |ui| {
+            add_contents(ui, placement);
+
+            if !edit {
+                return None;
+            }
+
+            let rect = ui.min_rect();
+            ui.painter().rect_stroke(rect, 2.0, Stroke::new(1.5, EDIT_OUTLINE), StrokeKind::Outside);
+
+            Some(ui.interact(rect, id.with("edit"), Sense::click_and_drag()))
+        });
+
+    let Some(edit_response) = response.inner else {
+        return false;
+    };
+
+    if edit_response.secondary_clicked() {
+        layout.reset(element);
+        return true;
+    }
+
+    let mut changed = false;
+
+    if edit_response.dragged() {
+        let delta = edit_response.drag_delta();
+        layout.set(element, placement);
+        layout.move_by(element, [delta.x / screen.width(), delta.y / screen.height()], size);
+        changed = true;
+    }
+
+    let scroll = ctx.input(|x| x.raw_scroll_delta.y);
+
+    if edit_response.hovered() && scroll != 0.0 {
+        layout.zoom(element, scroll.signum());
+        changed = true;
+    }
+
+    changed
+}
@@ -1,2 +1,10 @@
+pub mod break_overlay;
+pub mod drop_overlay;
+pub mod hud;
+pub mod pause;
+pub mod results;
 pub mod settings;
+pub mod slider_debug;
 pub mod song_select;
+pub mod toast;
+pub mod transition;
@@ -0,0 +1,135 @@
+use std::{sync::mpsc::Sender, time::Instant};
+
+use egui::{Align2, Color32, FontId, RichText};
+
+use crate::{i18n::t, osu_state::OsuStateEvent};
+
+/// Duration of every countdown number, in ms
+pub const COUNTDOWN_STEP_MS: f64 = 600.0;
+/// Countdown starts from this number
+pub const COUNTDOWN_FROM: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PauseState {
+    /// Menu is shown, clock and audio are stopped
+    Menu,
+    /// Resume was chosen, gameplay continues once countdown is over
+    Countdown { started_at: Instant },
+}
+
+/// Number shown by the countdown after `elapsed_ms`,
+/// `None` means countdown is over
+pub fn countdown_number(elapsed_ms: f64) -> Option<u32> {
+    let step = (elapsed_ms.max(0.0) / COUNTDOWN_STEP_MS) as u32;
+
+    COUNTDOWN_FROM.checked_sub(step).filter(|x| *x > 0)
+}
+
+/// Paused sub-state of gameplay, owns only
+/// the UI part, `OsuState` stops clock and audio
+pub struct PauseMenu {
+    state: PauseState,
+    osu_state_tx: Sender<OsuStateEvent>,
+}
+
+impl PauseMenu {
+    pub fn new(osu_state_tx: Sender<OsuStateEvent>) -> Self {
+        Self {
+            state: PauseState::Menu,
+            osu_state_tx,
+        }
+    }
+
+    #[inline]
+    pub fn state(&self) -> PauseState {
+        self.state
+    }
+
+    pub fn start_countdown(&mut self) {
+        self.state = PauseState::Countdown { started_at: Instant::now() };
+    }
+
+    pub fn show_menu(&mut self) {
+        self.state = PauseState::Menu;
+    }
+
+    /// Returns `true` once the countdown is over and gameplay should resume
+    pub fn is_countdown_finished(&self) -> bool {
+        match self.state {
+            PauseState::Menu => false,
+            PauseState::Countdown { started_at } => {
+                countdown_number(started_at.elapsed().as_secs_f64() * 1000.0).is_none()
+            },
+        }
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) {
+        let _span = tracy_client::span!("pause_menu::render");
+
+        match self.state {
+            PauseState::Menu => self.render_menu(ctx),
+            PauseState::Countdown { started_at } => {
+                let elapsed = started_at.elapsed().as_secs_f64() * 1000.0;
+
+                if let Some(number) = countdown_number(elapsed) {
+                    let painter = ctx.layer_painter(egui::LayerId::background());
+
+                    painter.text(
+                        ctx.screen_rect().center(),
+                        Align2::CENTER_CENTER,
+                        number.to_string(),
+                        FontId::proportional(96.0),
+                        Color32::WHITE,
+                    );
+                }
+
+                // Countdown is driven by time, not input
+                ctx.request_repaint();
+            },
+        }
+    }
+
+    fn render_menu(&mut self, ctx: &egui::Context) {
+        // Dimming the playfield
+        ctx.layer_painter(egui::LayerId::background()).rect_filled(
+            ctx.screen_rect(),
+            0.0,
+            Color32::from_black_alpha(180),
+        );
+
+        egui::Area::new(egui::Id::new("pause_menu"))
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.heading(RichText::new(t("pause.title")).size(32.0));
+                    ui.add_space(16.0);
+
+                    let button_size = [200.0, 40.0];
+
+                    if ui.add_sized(button_size, egui::Button::new(t("pause.resume"))).clicked() {
+                        self.start_countdown();
+                    }
+
+                    if ui.add_sized(button_size, egui::Button::new(t("pause.retry"))).clicked() {
+                        self.osu_state_tx.send(OsuStateEvent::Retry)
+                            .expect("Failed to send Retry event to the OsuState");
+                    }
+
+                    if ui.add_sized(button_size, egui::Button::new(t("pause.back"))).clicked() {
+                        self.osu_state_tx.send(OsuStateEvent::ToSongSelection)
+                            .expect("Failed to send ToSongSelection event to the OsuState");
+                    }
+                });
+            });
+    }
+}
+
+#[test]
+fn test_countdown_number() {
+    assert_eq!(countdown_number(0.0), Some(3));
+    assert_eq!(countdown_number(COUNTDOWN_STEP_MS - 1.0), Some(3));
+    assert_eq!(countdown_number(COUNTDOWN_STEP_MS), Some(2));
+    assert_eq!(countdown_number(COUNTDOWN_STEP_MS * 2.5), Some(1));
+    assert_eq!(countdown_number(COUNTDOWN_STEP_MS * 3.0), None);
+    assert_eq!(countdown_number(COUNTDOWN_STEP_MS * 100.0), None);
+}
@@ -0,0 +1,149 @@
+use std::{path::PathBuf, sync::mpsc::Sender};
+
+use egui::Vec2;
+
+use crate::{accuracy_graph::accuracy_graph, aim_scatter::aim_scatter, hit_objects::breaks::Break, i18n::{format_number, format_percent, t, tf}, osu_db::replay_export::{save_replay, ExportedReplay}, osu_state::OsuStateEvent, score::{Score, GRAPH_POINTS}};
+
+/// Replay of the finished play, written to
+/// the replays directory once it's asked for
+pub struct PendingReplay {
+    replay: ExportedReplay,
+    dir: PathBuf,
+    /// Saved path or the error text
+    saved: Option<Result<PathBuf, String>>,
+}
+
+impl PendingReplay {
+    pub fn new(replay: ExportedReplay, dir: PathBuf) -> Self {
+        Self {
+            replay,
+            dir,
+            saved: None,
+        }
+    }
+
+    /// Failed save can be retried, successful one is not repeated
+    pub fn save(&mut self) {
+        if matches!(self.saved, Some(Ok(_))) {
+            return;
+        }
+
+        let result = save_replay(&self.dir, &self.replay.file_name, &self.replay.data);
+
+        if let Err(e) = &result {
+            tracing::error!("Failed to save replay to {}: {e}", self.dir.display());
+        }
+
+        self.saved = Some(result.map_err(|e| e.to_string()));
+    }
+}
+
+/// Shown after the play is finished, owns
+/// the score so it outlives gameplay state
+pub struct ResultsScreen {
+    title: String,
+    score: Score,
+    accuracy_points: Vec<(f64, f64)>,
+    time_range: (f64, f64),
+    breaks: Vec<Break>,
+    /// `None` for watched replays
+    replay: Option<PendingReplay>,
+
+    osu_state_tx: Sender<OsuStateEvent>,
+}
+
+impl ResultsScreen {
+    pub fn new(
+        title: String,
+        score: Score,
+        time_range: (f64, f64),
+        breaks: Vec<Break>,
+        replay: Option<PendingReplay>,
+        osu_state_tx: Sender<OsuStateEvent>,
+    ) -> Self {
+        let accuracy_points = score.downsampled_accuracy(GRAPH_POINTS);
+
+        Self {
+            title,
+            score,
+            accuracy_points,
+            time_range,
+            breaks,
+            replay,
+            osu_state_tx,
+        }
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) {
+        let _span = tracy_client::span!("results_screen::render");
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(&self.title);
+
+                ui.label(tf("results.accuracy", &[&format_percent(self.score.accuracy(), 2)]));
+
+                if !self.score.is_ranked() {
+                    ui.label(tf("results.unranked", &[&self.score.rules.names().join(", ")]));
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label(tf("results.x300", &[&self.score.x300]));
+                    ui.label(tf("results.x100", &[&self.score.x100]));
+                    ui.label(tf("results.x50", &[&self.score.x50]));
+                    ui.label(tf("results.miss", &[&self.score.miss]));
+                });
+
+                ui.label(tf("results.max_combo", &[&self.score.max_combo]));
+
+                let width = ui.available_width().min(600.0);
+
+                accuracy_graph(
+                    ui,
+                    &self.accuracy_points,
+                    self.time_range,
+                    &self.breaks,
+                    None,
+                    Vec2::new(width, 120.0),
+                );
+
+                egui::CollapsingHeader::new(t("results.aim_error"))
+                    .id_salt("results_aim_error")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        aim_scatter(ui, &self.score, 200.0);
+
+                        if let Some(mean) = self.score.mean_hit_offset() {
+                            ui.label(tf("results.mean_offset", &[
+                                &format_number(mean.x, 1),
+                                &format_number(mean.y, 1),
+                            ]));
+                        }
+                    });
+
+                if let Some(replay) = &mut self.replay {
+                    match &replay.saved {
+                        Some(Ok(path)) => {
+                            ui.label(tf("results.replay_saved", &[&path.display()]));
+                        },
+                        Some(Err(e)) => {
+                            ui.colored_label(ui.visuals().error_fg_color, tf("results.replay_save_failed", &[e]));
+                        },
+                        None => {},
+                    }
+
+                    let is_saved = matches!(replay.saved, Some(Ok(_)));
+
+                    if ui.add_enabled(!is_saved, egui::Button::new(t("results.save_replay"))).clicked() {
+                        replay.save();
+                    }
+                }
+
+                if ui.button(t("results.back")).clicked() {
+                    self.osu_state_tx.send(OsuStateEvent::ToSongSelection)
+                        .expect("Failed to send ToSongSelection event to the OsuState");
+                }
+            });
+        });
+    }
+}
@@ -1,34 +1,102 @@
-use std::{path::PathBuf, sync::{mpsc::{Receiver, Sender}, Arc, RwLock}};
+use std::{ops::RangeInclusive, path::{Path, PathBuf}, sync::{mpsc::{Receiver, Sender}, Arc, RwLock}};
 
 use egui::{color_picker::show_color, Slider, TextStyle, Ui};
-
-use crate::{config::Config, osu_state::OsuStateEvent, skin_manager::SkinManager};
+use ini::Ini;
+
+use crate::{audio::{available_backends, AudioInfo, DEFAULT_BACKEND}, hud_layout::HudElement, config::{Config, ConfigFieldError, CONFIG_PATH}, diagnostics, i18n::{self, t, tf}, osu_db::{stable_import::{StableData, StableImportSummary}, OsuDatabase}, osu_state::OsuStateEvent, session_stats::{format_play_time, unix_now, DayStats}, settings_table::{section_settings, SettingDescriptor, SettingEffect, SettingWidget, SettingsSection}, skin_manager::SkinManager, song_select_state::{SongSelectionEvents, SongsImportJob}};
+
+/// Width of the section jump links column
+const SECTION_LINKS_WIDTH: f32 = 128.0;
+
+/// Steps of the osu!stable import wizard
+#[derive(Default)]
+enum StableImportStep {
+    #[default]
+    Idle,
+    /// Databases are being read or written
+    Working,
+    /// Parsed and waiting for the confirmation
+    Preview(Arc<StableData>),
+    Done {
+        summary: StableImportSummary,
+        songs_dir: PathBuf,
+    },
+    Failed(String),
+}
 
 pub struct SettingsScreen {
     config: Arc<RwLock<Config>>,
     skin_manager: Arc<RwLock<SkinManager>>,
+    audio_info: Arc<RwLock<AudioInfo>>,
+    db: Arc<OsuDatabase>,
     is_open: bool,
 
+    /// Lowercased on use, rows with labels containing it are shown
+    search: String,
+    /// Search field takes focus on the next frame
+    focus_search: bool,
+    /// Section link that was clicked
+    jump_to: Option<SettingsSection>,
+
+    /// Reset button was pressed once and waits for the confirmation
+    confirm_reset: bool,
+    /// Fields skipped by the last import
+    import_errors: Arc<RwLock<Vec<String>>>,
+
+    stable_import: Arc<RwLock<StableImportStep>>,
+
+    /// Today's plays, refetched every time settings are opened
+    today: Arc<RwLock<Option<DayStats>>>,
+
     osu_state_tx: Sender<OsuStateEvent>,
+    song_select_tx: Sender<SongSelectionEvents>,
 }
 
 impl SettingsScreen {
     pub fn new(
         config: Arc<RwLock<Config>>,
         skin_manager: Arc<RwLock<SkinManager>>,
+        audio_info: Arc<RwLock<AudioInfo>>,
+        db: Arc<OsuDatabase>,
         osu_state_tx: Sender<OsuStateEvent>,
+        song_select_tx: Sender<SongSelectionEvents>,
     ) -> Self {
 
         Self {
             is_open: false,
+            search: String::new(),
+            focus_search: false,
+            jump_to: None,
+            confirm_reset: false,
+            import_errors: Arc::new(RwLock::new(Vec::new())),
+            stable_import: Arc::new(RwLock::new(StableImportStep::Idle)),
+            today: Arc::new(RwLock::new(None)),
             config,
             skin_manager,
+            audio_info,
+            db,
             osu_state_tx,
+            song_select_tx,
         }
     }
 
     pub fn toggle(&mut self) {
-        self.is_open = if self.is_open { false } else { true}
+        self.is_open = if self.is_open { false } else { true};
+
+        if self.is_open {
+            self.focus_search = true;
+            self.refresh_today();
+        }
+    }
+
+    fn refresh_today(&self) {
+        let today = self.today.clone();
+        let db = self.db.clone();
+
+        std::thread::spawn(move || {
+            let stats = db.day_stats(unix_now());
+            *today.write().expect("failed to acquire write lock") = Some(stats);
+        });
     }
     
     pub fn close(&mut self) {
@@ -46,7 +114,7 @@ impl SettingsScreen {
         }
 
         // TODO: calculate dynamicly instead of hardcoded value
-        let width = 512.0; 
+        let width = SECTION_LINKS_WIDTH + 512.0;
 
         // TODO: Animation doesn't work
         let _offset = ctx.animate_bool_with_time_and_easing(
@@ -60,7 +128,24 @@ impl SettingsScreen {
             return;
         }
 
-        egui::Window::new("Settings")
+        // Tab is handled by egui, vertical arrows move between rows the
+        // same way. Sliders and combo boxes only use horizontal ones
+        let direction = ctx.input(|i| {
+            if i.key_pressed(egui::Key::ArrowDown) {
+                Some(egui::FocusDirection::Down)
+            } else if i.key_pressed(egui::Key::ArrowUp) {
+                Some(egui::FocusDirection::Up)
+            } else {
+                None
+            }
+        });
+
+        if let Some(direction) = direction {
+            ctx.memory_mut(|x| x.move_focus(direction));
+        }
+
+        egui::Window::new(t("settings.title"))
+            .id(egui::Id::new("settings_window"))
             .movable(false)
             .resizable(false)
             .title_bar(false)
@@ -72,118 +157,563 @@ impl SettingsScreen {
                 //.outer_margin(egui::epaint::Marginf { left: -offset, ..Default::default() }),
             )
             .show(ctx, |ui| {
-                egui::ScrollArea::vertical()
-                    .auto_shrink([false, false])
-                    .show(ui, |ui| {
-                        self.show_settings_ui(ui);
-                        self.show_skin_settings_ui(ui);
+                ui.horizontal_top(|ui| {
+                    self.show_section_links(ui);
+
+                    ui.vertical(|ui| {
+                        self.show_search_ui(ui);
+
+                        let query = self.search.trim().to_lowercase();
+
+                        egui::ScrollArea::vertical()
+                            .auto_shrink([false, false])
+                            .show(ui, |ui| {
+                                let mut shown = false;
+
+                                for section in SettingsSection::ALL {
+                                    shown |= self.show_section(ui, section, &query);
+                                }
+
+                                if !shown {
+                                    ui.label(t("settings.search.nothing"));
+                                }
+                            });
                     });
+                });
             });
     }
 
-    pub fn show_skin_settings_ui(&self, ui: &mut Ui) {
+    /// Jump links to every section, clicked one is expanded
+    /// and scrolled to on the next frame
+    fn show_section_links(&mut self, ui: &mut Ui) {
+        ui.vertical(|ui| {
+            ui.set_width(SECTION_LINKS_WIDTH);
+
+            for section in SettingsSection::ALL {
+                if ui.link(t(section.name_key())).clicked() {
+                    self.search.clear();
+                    self.jump_to = Some(section);
+                }
+            }
+        });
+    }
+
+    fn show_search_ui(&mut self, ui: &mut Ui) {
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.search)
+                .hint_text(t("settings.search"))
+                .desired_width(f32::INFINITY)
+        );
+
+        if std::mem::take(&mut self.focus_search) {
+            response.request_focus();
+        }
+    }
+
+    /// Returns `false` if nothing in the section matches the `query`
+    fn show_section(&mut self, ui: &mut Ui, section: SettingsSection, query: &str) -> bool {
         let heading_font = egui::FontId::new(20.0, egui::FontFamily::Proportional);
 
-        let skin = self.skin_manager.read().expect("failed to acquire read lock");
+        // Hand-made rows are only searched by the section name
+        let section_matches = query.is_empty()
+            || t(section.name_key()).to_lowercase().contains(query);
+
+        let rows: Vec<_> = section_settings(section, query).collect();
+
+        if !section_matches && rows.is_empty() {
+            return false;
+        }
+
+        let is_jump_target = self.jump_to == Some(section);
+
+        // Stable id, otherwise the section collapses when language is changed
+        let mut header = egui::CollapsingHeader::new(egui::RichText::new(t(section.name_key())).font(heading_font))
+            .id_salt(section.name_key());
+
+        if !query.is_empty() || is_jump_target {
+            header = header.open(Some(true));
+        }
+
+        let response = header.show(ui, |ui| {
+            if section_matches {
+                self.show_extras_before(ui, section);
+            }
+
+            self.show_rows(ui, &rows);
+
+            if section_matches {
+                self.show_extras_after(ui, section);
+            }
+        });
+
+        if is_jump_target {
+            response.header_response.scroll_to_me(Some(egui::Align::TOP));
+            self.jump_to = None;
+        }
+
+        true
+    }
+
+    /// Rows from the [`crate::settings_table::SETTINGS`] table, side effects
+    /// of the changed ones are applied after the config is written
+    fn show_rows(&self, ui: &mut Ui, rows: &[&SettingDescriptor]) {
+        let mut effects = Vec::new();
+        let mut config = self.config.write().expect("failed to acquire write lock");
+        let mut group = None;
+
+        for setting in rows {
+            if let Some(key) = setting.group.filter(|_| setting.group != group) {
+                ui.heading(t(key));
+            }
 
-        ui.collapsing(egui::RichText::new("Skin").font(heading_font), |ui| {
-            if ui.button("Open skin").clicked() {
-                self.spawn_skin_selector_dialog();
+            group = setting.group;
+
+            if setting_ui(ui, setting, &mut config) && setting.effect != SettingEffect::None {
+                effects.push(setting.effect);
+            }
+        }
+
+        effects.dedup();
+
+        for effect in &effects {
+            match effect {
+                SettingEffect::None => {},
+                SettingEffect::RebakeSliders => {
+                    let _ = self.osu_state_tx.send(OsuStateEvent::SliderConfigChanged);
+                },
+                SettingEffect::DifficultyOverrides => {
+                    let _ = self.song_select_tx.send(SongSelectionEvents::DifficultyOverridesChanged);
+                },
+                SettingEffect::CursorSize => {
+                    let _ = self.osu_state_tx.send(OsuStateEvent::SetCursorSize(config.cursor.size));
+                },
+                SettingEffect::PlayfieldLayout => {
+                    let _ = self.osu_state_tx.send(OsuStateEvent::PlayfieldLayoutChanged);
+                },
+                SettingEffect::Language => i18n::set_current(config.lang),
             }
+        }
+
+        drop(config);
+
+        // Language is remembered right away, other settings
+        // are only saved through the settings file section
+        if effects.contains(&SettingEffect::Language) {
+            save_config(&self.config);
+        }
+    }
 
-            ui.label(format!("Name: {}", skin.ini.general.name));
-            ui.label(format!("Author: {}", skin.ini.general.author));
+    /// Hand-made rows shown above the table ones
+    fn show_extras_before(&self, ui: &mut Ui, section: SettingsSection) {
+        match section {
+            SettingsSection::Gameplay => {
+                ui.label(t("settings.gameplay.unranked_note"));
+            },
+            SettingsSection::Audio => self.show_audio_output_ui(ui),
+            SettingsSection::Skin => {
+                if ui.button(t("settings.skin.open")).clicked() {
+                    self.spawn_skin_selector_dialog();
+                }
+
+                let skin = self.skin_manager.read().expect("failed to acquire read lock");
+
+                ui.label(tf("settings.skin.name", &[&skin.ini.general.name]));
+                ui.label(tf("settings.skin.author", &[&skin.ini.general.author]));
+            },
+            _ => {},
+        }
+    }
+
+    /// Hand-made rows shown below the table ones
+    fn show_extras_after(&mut self, ui: &mut Ui, section: SettingsSection) {
+        match section {
+            SettingsSection::Skin => self.show_skin_colours_ui(ui),
+            SettingsSection::Hud => self.show_hud_elements_ui(ui),
+            SettingsSection::Replays => self.show_replays_dir_ui(ui),
+            SettingsSection::File => self.show_settings_file_ui(ui),
+            SettingsSection::StableImport => self.show_stable_import_ui(ui),
+            SettingsSection::Session => self.show_session_ui(ui),
+            SettingsSection::Diagnostics => self.show_diagnostics_ui(ui),
+            _ => {},
+        }
+    }
 
-            ui.collapsing("Skin colours", |ui| {
-                ui.collapsing("Combo colours", |ui| {
-                    for (i, c) in skin.ini.colours.combo_colors.iter().enumerate() {
-                        ui.group(|ui| {
-                            ui.label(format!("Colour {}: ", i));
-                            show_color(ui, c.to_egui_color(), egui::Vec2::new(30.0, 10.0));
-                        });
+    fn show_audio_output_ui(&self, ui: &mut Ui) {
+        let info = self.audio_info.read().expect("failed to acquire read lock");
+
+        egui::ComboBox::from_label(t("settings.audio.output"))
+            .selected_text(&info.name)
+            .show_ui(ui, |ui| {
+                for (name, _) in available_backends() {
+                    if ui.selectable_label(info.name == *name, *name).clicked()
+                    && info.name != *name {
+                        let _ = self.osu_state_tx.send(
+                            OsuStateEvent::ChangeAudioBackend(name.to_string())
+                        );
                     }
-                });
+                }
+            });
 
-                ui.collapsing("Slider colours", |ui| {
-                    ui.label("Slider border color:");
-                    show_color(
-                        ui, 
-                        skin.ini.colours.slider_border.to_egui_color(),
-                        egui::Vec2::new(30.0, 10.0)
-                    );
-
-                    ui.label("Slider body color:");
-                    show_color(
-                        ui, 
-                        skin.ini.colours.slider_body.to_egui_color(),
-                        egui::Vec2::new(30.0, 10.0)
-                    );
-                });
+        // Soloud opens the system default device of a backend
+        // and has no api to list or pick other devices
+        ui.label(t("settings.audio.device"));
+        ui.label(t("settings.audio.device_hint"));
+
+        ui.label(tf("settings.audio.backend", &[&info.backend]));
+        ui.label(tf("settings.audio.buffer", &[
+            &info.buffer_size,
+            &info.samplerate,
+            &info.channels,
+        ]));
+        ui.label(tf("settings.audio.latency", &[
+            &i18n::format_number(info.buffer_latency_ms(), 1),
+        ]));
+    }
+
+    fn show_skin_colours_ui(&self, ui: &mut Ui) {
+        let skin = self.skin_manager.read().expect("failed to acquire read lock");
+
+        ui.collapsing(t("settings.skin.colours"), |ui| {
+            ui.collapsing(t("settings.skin.combo_colours"), |ui| {
+                for (i, c) in skin.ini.colours.combo_colors.iter().enumerate() {
+                    ui.group(|ui| {
+                        ui.label(tf("settings.skin.colour", &[&i]));
+                        show_color(ui, c.to_egui_color(), egui::Vec2::new(30.0, 10.0));
+                    });
+                }
             });
 
+            ui.collapsing(t("settings.skin.slider_colours"), |ui| {
+                ui.label(t("settings.skin.slider_border"));
+                show_color(
+                    ui, 
+                    skin.ini.colours.slider_border.to_egui_color(),
+                    egui::Vec2::new(30.0, 10.0)
+                );
+
+                ui.label(t("settings.skin.slider_body"));
+                show_color(
+                    ui, 
+                    skin.ini.colours.slider_body.to_egui_color(),
+                    egui::Vec2::new(30.0, 10.0)
+                );
+            });
         });
     }
-    
-    /// Shows a settings UI that can be placed in any container
-    pub fn show_settings_ui(&self, ui: &mut Ui) {
-        let heading_font = egui::FontId::new(20.0, egui::FontFamily::Proportional);
 
+    fn show_hud_elements_ui(&self, ui: &mut Ui) {
         let mut config = self.config.write().expect("failed to acquire write lock");
 
-        ui.collapsing(egui::RichText::new("Renderer").font(heading_font.clone()), |ui| {
-            ui.heading("Slider");
+        if config.hud_edit {
+            ui.label(t("settings.hud.edit_hint"));
+        }
+
+        for element in HudElement::ALL {
+            ui.horizontal(|ui| {
+                ui.label(t(element.name_key()));
+
+                if ui.button(t("settings.hud.reset")).clicked() {
+                    config.hud_layout.reset(element);
+                }
+            });
+        }
+
+        if ui.button(t("settings.hud.reset_all")).clicked() {
+            config.hud_layout.reset_all();
+        }
+    }
+
+    fn show_replays_dir_ui(&self, ui: &mut Ui) {
+        let config = self.config.read().expect("failed to acquire read lock");
+
+        ui.horizontal(|ui| {
+            ui.label(tf("settings.replays.dir", &[&config.replays_dir]));
+
+            if ui.button(t("settings.replays.choose")).clicked() {
+                self.spawn_replays_dir_dialog();
+            }
+        });
+    }
+
+    fn show_settings_file_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            if ui.button(t("settings.file.export")).clicked() {
+                self.spawn_export_dialog();
+            }
+
+            if ui.button(t("settings.file.import")).clicked() {
+                self.spawn_import_dialog();
+            }
+        });
+
+        if self.confirm_reset {
+            ui.label(t("settings.file.reset_confirm"));
+
+            ui.horizontal(|ui| {
+                if ui.button(t("settings.file.reset")).clicked() {
+                    apply_config(&self.config, Config::default(), &self.osu_state_tx);
+                    save_config(&self.config);
+                    let _ = self.song_select_tx.send(SongSelectionEvents::DifficultyOverridesChanged);
+
+                    self.import_errors.write().expect("failed to acquire write lock").clear();
+                    self.confirm_reset = false;
+                }
+
+                if ui.button(t("common.cancel")).clicked() {
+                    self.confirm_reset = false;
+                }
+            });
+        } else if ui.button(t("settings.file.reset_to_defaults")).clicked() {
+            self.confirm_reset = true;
+        }
+
+        let errors = self.import_errors.read().expect("failed to acquire read lock");
+
+        if !errors.is_empty() {
+            ui.label(t("settings.file.import_errors"));
+
+            for e in errors.iter() {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+        }
+    }
+
+    /// Resume or restart choice for a scan that was stopped midway
+    fn show_interrupted_scan_ui(&self, ui: &mut Ui) {
+        let Some(scan) = self.db.interrupted_scan() else {
+            return;
+        };
+
+        ui.label(tf("settings.stable_import.interrupted", &[
+            &scan.root.display(),
+            &scan.directories_done,
+        ]));
+
+        let mut resume = None;
+
+        ui.horizontal(|ui| {
+            if ui.button(t("settings.stable_import.resume")).clicked() {
+                resume = Some(true);
+            }
+
+            if ui.button(t("settings.stable_import.restart")).clicked() {
+                resume = Some(false);
+            }
+        });
+
+        if let Some(resume) = resume {
+            let (_stop_tx, stop_rx) = oneshot::channel();
+
+            let _ = self.song_select_tx.send(SongSelectionEvents::ImportSongsDirectory(SongsImportJob {
+                path: scan.root,
+                resume,
+                stop_rx,
+            }));
+        }
+
+        ui.separator();
+    }
+
+    fn show_stable_import_ui(&self, ui: &mut Ui) {
+        self.show_interrupted_scan_ui(ui);
+
+        let mut step = self.stable_import.write().expect("failed to acquire write lock");
+
+        match &*step {
+            StableImportStep::Idle => {},
+            StableImportStep::Working => {
+                ui.spinner();
+                return;
+            },
+            StableImportStep::Preview(data) => {
+                ui.label(tf("settings.stable_import.found", &[
+                    &data.collections.len(),
+                    &data.scores.len(),
+                    &data.dir.display(),
+                ]));
+
+                let mut cancel = false;
+
+                ui.horizontal(|ui| {
+                    if ui.button(t("settings.stable_import.confirm")).clicked() {
+                        self.spawn_stable_import(data.clone());
+                    }
+
+                    cancel = ui.button(t("common.cancel")).clicked();
+                });
+
+                if cancel {
+                    *step = StableImportStep::Idle;
+                }
+
+                return;
+            },
+            StableImportStep::Done { summary, songs_dir } => {
+                ui.label(tf("settings.stable_import.done", &[
+                    &summary.collections,
+                    &summary.scores,
+                ]));
+
+                if summary.unresolved > 0 {
+                    ui.label(tf("settings.stable_import.unresolved", &[&summary.unresolved]));
+                }
+
+                if summary.in_stable_songs > 0 && songs_dir.is_dir() {
+                    let text = tf("settings.stable_import.scan_songs", &[&summary.in_stable_songs]);
+
+                    if ui.button(text).clicked() {
+                        let (_stop_tx, stop_rx) = oneshot::channel();
+
+                        let _ = self.song_select_tx.send(SongSelectionEvents::ImportSongsDirectory(SongsImportJob {
+                            path: songs_dir.clone(),
+                            resume: false,
+                            stop_rx,
+                        }));
+                    }
+                }
+            },
+            StableImportStep::Failed(e) => {
+                ui.colored_label(egui::Color32::RED, e);
+            },
+        }
+
+        drop(step);
+
+        if ui.button(t("settings.stable_import.pick")).clicked() {
+            self.spawn_stable_import_dialog();
+        }
+    }
+
+    fn show_session_ui(&self, ui: &mut Ui) {
+        let today = self.today.read().expect("failed to acquire read lock");
 
-            ui.checkbox(&mut config.store_slider_textures, "Store slider textures");
+        let Some(stats) = &*today else {
+            ui.spinner();
+            return;
+        };
+
+        ui.label(tf("settings.session.plays", &[&stats.plays_started, &stats.plays_completed]));
+        ui.label(tf("settings.session.retries", &[&stats.retries]));
+        ui.label(tf("settings.session.play_time", &[&format_play_time(stats.play_time_ms)]));
+        ui.label(tf("settings.session.judgements", &[
+            &stats.x300,
+            &stats.x100,
+            &stats.x50,
+            &stats.miss,
+        ]));
+    }
 
-            ui.add(Slider::new(
-                &mut config.slider.border_feather,
-                0.0..=2.0
-            ).text("Slider border feather"));
+    fn show_diagnostics_ui(&self, ui: &mut Ui) {
+        if ui.button(t("settings.diagnostics.copy")).clicked() {
+            ui.ctx().copy_text(diagnostics::report());
+        }
 
-            ui.add(Slider::new(
-                &mut config.slider.border_size_multiplier,
-                0.0..=2.0
-            ).text("Slider border size"));
+        #[cfg(debug_assertions)]
+        if ui.button(t("settings.diagnostics.force_panic")).clicked() {
+            panic!("Forced panic from the settings");
+        }
+    }
 
-            ui.add(Slider::new(
-                &mut config.slider.body_color_saturation,
-                0.0..=2.0
-            ).text("Slider body color saturation"));
+    fn spawn_export_dialog(&self) {
+        let config = self.config.read().expect("failed to acquire read lock").clone();
 
-            ui.add(Slider::new(
-                &mut config.slider.body_alpha_multiplier,
-                0.0..=2.0
-            ).text("Slider body alpha multiplier"));
+        std::thread::spawn(move || {
+            let path = rfd::FileDialog::new()
+                .add_filter(t("settings.title"), &["ini"])
+                .set_file_name("rosu-settings.ini")
+                .save_file();
+
+            if let Some(path) = path {
+                if let Err(e) = config.export(&path) {
+                    tracing::error!("Failed to export settings to {}: {e}", path.display());
+                }
+            }
+        });
+    }
 
-            ui.heading("Judgements");
+    fn spawn_import_dialog(&self) {
+        let config = self.config.clone();
+        let import_errors = self.import_errors.clone();
+        let tx = self.osu_state_tx.clone();
+        let song_select_tx = self.song_select_tx.clone();
 
-            ui.add(Slider::new(
-                &mut config.judgements.fade_in_ms,
-                0.0..=1000.0
-            ).text("Fade-In milliseconds"));
+        std::thread::spawn(move || {
+            let path = rfd::FileDialog::new()
+                .add_filter(t("settings.title"), &["ini"])
+                .pick_file();
 
-            ui.add(Slider::new(
-                &mut config.judgements.stay_on_screen_ms,
-                0.0..=1000.0
-            ).text("Stay on screen milliseconds"));
+            let Some(path) = path else {
+                return;
+            };
 
-            ui.add(Slider::new(
-                &mut config.judgements.fade_out_ms,
-                0.0..=1000.0
-            ).text("Fade-out milliseconds"));
+            let errors = match import_config(&config, &path, &tx) {
+                Ok(errors) => {
+                    save_config(&config);
+                    let _ = song_select_tx.send(SongSelectionEvents::DifficultyOverridesChanged);
+                    errors.iter().map(|x| x.to_string()).collect()
+                },
+                Err(e) => vec![tf("settings.file.read_failed", &[&path.display(), &e])],
+            };
+
+            *import_errors.write().expect("failed to acquire write lock") = errors;
         });
+    }
+
+    fn spawn_stable_import_dialog(&self) {
+        let stable_import = self.stable_import.clone();
+
+        std::thread::spawn(move || {
+            let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+                return;
+            };
+
+            *stable_import.write().expect("failed to acquire write lock") = StableImportStep::Working;
+
+            let step = match StableData::load(&dir) {
+                Ok(data) => StableImportStep::Preview(Arc::new(data)),
+                Err(e) => StableImportStep::Failed(tf("settings.stable_import.failed", &[&e])),
+            };
+
+            *stable_import.write().expect("failed to acquire write lock") = step;
+        });
+    }
+
+    /// Writes already parsed `data` to the database, the
+    /// wizard lock is held by the caller so it's set later
+    fn spawn_stable_import(&self, data: Arc<StableData>) {
+        let stable_import = self.stable_import.clone();
+        let db = self.db.clone();
+        let song_select_tx = self.song_select_tx.clone();
 
+        std::thread::spawn(move || {
+            *stable_import.write().expect("failed to acquire write lock") = StableImportStep::Working;
+
+            let step = match data.import(&db) {
+                Ok(summary) => {
+                    let _ = song_select_tx.send(SongSelectionEvents::CollectionsChanged);
 
-        ui.collapsing(egui::RichText::new("Cursor").font(heading_font), |ui| {
-            if ui.add(Slider::new(
-                &mut config.cursor.size,
-                1.0..=10.0
-            )).changed() {
-                let _ = self.osu_state_tx.send(OsuStateEvent::SetCursorSize(config.cursor.size));
+                    StableImportStep::Done {
+                        summary,
+                        songs_dir: data.songs_dir(),
+                    }
+                },
+                Err(e) => StableImportStep::Failed(tf("settings.stable_import.failed", &[&e])),
             };
+
+            *stable_import.write().expect("failed to acquire write lock") = step;
         });
+    }
+
+    fn spawn_replays_dir_dialog(&self) {
+        let config = self.config.clone();
 
+        std::thread::spawn(move || {
+            let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+                return;
+            };
 
+            config.write().expect("failed to acquire write lock").replays_dir = dir.display().to_string();
+        });
     }
 
     fn spawn_skin_selector_dialog(&self) {
@@ -199,3 +729,165 @@ impl SettingsScreen {
         });
     }
 }
+
+/// Widget of a single [`crate::settings_table::SETTINGS`] row, returns `true` if the value was changed
+fn setting_ui(ui: &mut Ui, setting: &SettingDescriptor, config: &mut Config) -> bool {
+    let enabled = setting.is_enabled(config);
+    let label = t(setting.label);
+
+    match &setting.widget {
+        SettingWidget::Checkbox(value) => {
+            ui.add_enabled(enabled, egui::Checkbox::new(value(config), label)).changed()
+        },
+        SettingWidget::Slider { value, range, suffix } => {
+            ui.add_enabled(enabled, Slider::new(value(config), range.clone())
+                .suffix(*suffix)
+                .text(label)
+            ).changed()
+        },
+        SettingWidget::IntSlider { value, range } => {
+            ui.add_enabled(enabled, Slider::new(value(config), range.clone()).text(label)).changed()
+        },
+        SettingWidget::OptionalSlider { value, range, default } => {
+            optional_slider_ui(ui, label, value(config), range.clone(), *default)
+        },
+        SettingWidget::Choice { options, selected, select, label: option_label } => {
+            let before = selected(config);
+            let mut current = before;
+
+            ui.add_enabled_ui(enabled, |ui| {
+                egui::ComboBox::from_label(label)
+                    .selected_text(option_label(current))
+                    .show_ui(ui, |ui| {
+                        for i in 0..*options {
+                            ui.selectable_value(&mut current, i, option_label(i));
+                        }
+                    });
+            });
+
+            if current != before {
+                select(config, current);
+            }
+
+            current != before
+        },
+    }
+}
+
+/// Checkbox that enables the override and a slider for its value,
+/// returns `true` if anything was changed
+fn optional_slider_ui(ui: &mut Ui, label: &str, value: &mut Option<f32>, range: RangeInclusive<f32>, default: f32) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+
+        if ui.checkbox(&mut enabled, label).changed() {
+            *value = if enabled { Some(default) } else { None };
+            changed = true;
+        }
+
+        let mut current = value.unwrap_or(default);
+
+        let response = ui.add_enabled(
+            enabled,
+            Slider::new(&mut current, range).step_by(0.1),
+        );
+
+        if response.changed() {
+            *value = Some(current);
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+/// Replaces live config, changes are propagated through
+/// the same events as individual settings use
+pub fn apply_config(config: &RwLock<Config>, new: Config, tx: &Sender<OsuStateEvent>) {
+    let mut config = config.write().expect("failed to acquire write lock");
+
+    if config.slider.needs_rebake(&new.slider) {
+        let _ = tx.send(OsuStateEvent::SliderConfigChanged);
+    }
+
+    if config.cursor.size != new.cursor.size {
+        let _ = tx.send(OsuStateEvent::SetCursorSize(new.cursor.size));
+    }
+
+    if config.audio_backend != new.audio_backend {
+        let backend = new.audio_backend.as_deref().unwrap_or(DEFAULT_BACKEND);
+        let _ = tx.send(OsuStateEvent::ChangeAudioBackend(backend.to_owned()));
+    }
+
+    i18n::set_current(new.lang);
+
+    // Quality level belongs to the adaptive quality controller,
+    // layout editor is only toggled from the settings screen
+    *config = Config {
+        quality: config.quality,
+        hud_edit: config.hud_edit,
+        ..new
+    };
+}
+
+fn save_config(config: &RwLock<Config>) {
+    let config = config.read().expect("failed to acquire read lock");
+
+    if let Err(e) = config.save(CONFIG_PATH) {
+        tracing::error!("Failed to save settings: {e}");
+    }
+}
+
+/// Applies valid fields of the settings file at `path` on
+/// top of the current config, invalid fields are returned
+pub fn import_config(
+    config: &RwLock<Config>,
+    path: impl AsRef<Path>,
+    tx: &Sender<OsuStateEvent>,
+) -> Result<Vec<ConfigFieldError>, ini::Error> {
+    let ini = Ini::load_from_file(path)?;
+
+    let mut new = config.read().expect("failed to acquire read lock").clone();
+    let errors = new.read_from_ini(&ini);
+
+    apply_config(config, new, tx);
+
+    Ok(errors)
+}
+
+#[test]
+fn test_settings_import_roundtrip() {
+    let dir = std::env::temp_dir().join(format!("rosu-settings-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("exported.ini");
+
+    let config = RwLock::new(Config::default());
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    let exported = config.read().unwrap().clone();
+    exported.export(&path).unwrap();
+
+    // Changing a few fields including slider ones
+    {
+        let mut config = config.write().unwrap();
+        config.hidden = true;
+        config.slider.border_feather = 1.5;
+        config.slider.body_alpha_multiplier = 0.1;
+        config.judgements.fade_in_ms = 500.0;
+    }
+
+    let errors = import_config(&config, &path, &tx).unwrap();
+
+    assert!(errors.is_empty());
+    assert_eq!(*config.read().unwrap(), exported);
+
+    let slider_changes = rx.try_iter()
+        .filter(|x| matches!(x, OsuStateEvent::SliderConfigChanged))
+        .count();
+
+    assert_eq!(slider_changes, 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,136 @@
+use cgmath::Vector2;
+use egui::{Align2, Color32, FontId, Pos2, Shape, Stroke};
+
+use crate::{hit_objects::{slider::{segment_kinds, SegmentKind, Slider}, Object, ObjectKind}, i18n::t, math::PlayfieldTransform};
+
+/// Points the curve is drawn with, enough for
+/// perfect curves to look round at any size
+const CURVE_SAMPLES: usize = 128;
+
+const CONTROL_POINT_RADIUS: f32 = 4.0;
+const RED_ANCHOR_RADIUS: f32 = 6.0;
+const TICK_RADIUS: f32 = 3.0;
+
+const RED_ANCHOR_COLOR: Color32 = Color32::from_rgb(240, 60, 60);
+const POLYGON_COLOR: Color32 = Color32::from_gray(150);
+const CURVE_COLOR: Color32 = Color32::from_rgb(250, 240, 120);
+const TICK_COLOR: Color32 = Color32::WHITE;
+const REVERSE_COLOR: Color32 = Color32::from_rgb(120, 220, 255);
+
+fn segment_color(kind: SegmentKind) -> Color32 {
+    match kind {
+        SegmentKind::Bezier => Color32::from_rgb(90, 150, 255),
+        SegmentKind::Linear => Color32::from_rgb(100, 220, 120),
+        SegmentKind::PerfectCurve => Color32::from_rgb(255, 160, 60),
+        SegmentKind::Catmull => Color32::from_rgb(200, 110, 255),
+    }
+}
+
+fn segment_name_key(kind: SegmentKind) -> &'static str {
+    match kind {
+        SegmentKind::Bezier => "slider_debug.bezier",
+        SegmentKind::Linear => "slider_debug.linear",
+        SegmentKind::PerfectCurve => "slider_debug.perfect",
+        SegmentKind::Catmull => "slider_debug.catmull",
+    }
+}
+
+/// Editor-like view of the sliders visible at `time`: control points
+/// colored by their segment type, red anchors, the polygon between
+/// the points, the curve calculated from them and the checkpoints
+pub fn render_slider_debug(
+    ctx: &egui::Context,
+    objects: &[Object],
+    time: f64,
+    preempt: f64,
+    playfield: &PlayfieldTransform,
+) {
+    let _span = tracy_client::span!("slider_debug::render_slider_debug");
+
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("slider_debug"),
+    ));
+
+    // Playfield transform works in physical pixels
+    let pixels_per_point = ctx.pixels_per_point();
+    let to_screen = |x: f64, y: f64| {
+        let pos = playfield.to_screen(Vector2::new(x, y));
+        Pos2::new(pos.x as f32 / pixels_per_point, pos.y as f32 / pixels_per_point)
+    };
+
+    let visible = objects.iter()
+        .filter_map(|object| match &object.kind {
+            ObjectKind::Slider(slider) => Some(slider),
+            _ => None,
+        })
+        .filter(|slider| time >= slider.start_time - preempt && time <= slider.end_time());
+
+    for slider in visible {
+        render_slider(&painter, slider, &to_screen);
+    }
+
+    render_legend(ctx, &painter);
+}
+
+fn render_slider(painter: &egui::Painter, slider: &Slider, to_screen: &impl Fn(f64, f64) -> Pos2) {
+    let head = slider.pos;
+    let point_pos = |x: f32, y: f32| to_screen((head.x + x) as f64, (head.y + y) as f64);
+
+    let points: Vec<Pos2> = slider.control_points.iter()
+        .map(|x| point_pos(x.pos.x, x.pos.y))
+        .collect();
+
+    painter.add(Shape::line(points.clone(), Stroke::new(1.0, POLYGON_COLOR)));
+
+    let curve = (0..=CURVE_SAMPLES)
+        .map(|i| {
+            let pos = slider.position_at(i as f64 / CURVE_SAMPLES as f64);
+            point_pos(pos.x, pos.y)
+        })
+        .collect();
+
+    painter.add(Shape::line(curve, Stroke::new(2.0, CURVE_COLOR)));
+
+    for checkpoint in &slider.checkpoints {
+        let color = if checkpoint.is_reverse { REVERSE_COLOR } else { TICK_COLOR };
+        let pos = to_screen(checkpoint.pos.x as f64, checkpoint.pos.y as f64);
+
+        painter.circle_filled(pos, TICK_RADIUS, color);
+    }
+
+    let kinds = segment_kinds(&slider.control_points);
+
+    for (i, ((point, kind), pos)) in slider.control_points.iter().zip(kinds).zip(points).enumerate() {
+        let is_red_anchor = i > 0 && point.kind.is_some();
+
+        // Red anchor is filled with the type of the segment it starts
+        if is_red_anchor {
+            painter.circle(pos, RED_ANCHOR_RADIUS, segment_color(kind), Stroke::new(2.0, RED_ANCHOR_COLOR));
+        } else {
+            painter.circle(pos, CONTROL_POINT_RADIUS, segment_color(kind), Stroke::new(1.0, Color32::BLACK));
+        }
+    }
+}
+
+fn render_legend(ctx: &egui::Context, painter: &egui::Painter) {
+    const LINE_HEIGHT: f32 = 18.0;
+
+    let font = FontId::proportional(14.0);
+    let mut pos = ctx.screen_rect().left_bottom() + egui::vec2(16.0, -16.0);
+
+    let mut entry = |color: Color32, stroke: Color32, text: &str| {
+        painter.circle(pos, CONTROL_POINT_RADIUS, color, Stroke::new(1.5, stroke));
+        painter.text(pos + egui::vec2(12.0, 0.0), Align2::LEFT_CENTER, text, font.clone(), Color32::WHITE);
+        pos.y -= LINE_HEIGHT;
+    };
+
+    entry(TICK_COLOR, TICK_COLOR, t("slider_debug.tick"));
+    entry(REVERSE_COLOR, REVERSE_COLOR, t("slider_debug.reverse"));
+    entry(CURVE_COLOR, CURVE_COLOR, t("slider_debug.curve"));
+    entry(Color32::TRANSPARENT, RED_ANCHOR_COLOR, t("slider_debug.red_anchor"));
+
+    for kind in [SegmentKind::Catmull, SegmentKind::PerfectCurve, SegmentKind::Linear, SegmentKind::Bezier] {
+        entry(segment_color(kind), Color32::BLACK, t(segment_name_key(kind)));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{sync::Arc, time::Duration};
+use std::{path::PathBuf, sync::{Arc, RwLock}, time::{Duration, Instant}};
 use std::sync::mpsc::Sender;
 
 
@@ -6,12 +6,19 @@ use egui::Direction;
 use image::DynamicImage;
 use md5::Digest;
 use wgpu::{util::DeviceExt, BufferUsages, TextureView};
-use egui::{scroll_area::ScrollBarVisibility, Align, Color32, Label, Margin, RichText, Stroke};
+use egui::{scroll_area::ScrollBarVisibility, Color32, Label, Margin, MouseWheelUnit, RichText, Stroke};
 use egui_extras::{Size, StripBuilder};
 use rosu_map::Beatmap;
 use winit::dpi::PhysicalSize;
 
+use crate::config::Config;
+use crate::db_worker::{DbResponse, DbWorker};
+use crate::difficulty::Difficulty;
+use crate::i18n::{self, format_number, t, tf, Lang};
+use crate::processor::rules::DifficultyOverrides;
+use crate::search_query::creator_query;
 use crate::texture::Texture;
+use crate::osu_db::{shift_index_after_delete, DbBeatmapEntry, DbCollection, SavedSelection};
 use crate::{graphics::Graphics, osu_db::OsuDatabase, quad_instance::QuadInstance, quad_renderer::QuadRenderer, song_select_state::SongSelectionEvents};
 
 const CARD_INNER_MARGIN: Margin = Margin {
@@ -23,61 +30,187 @@ const CARD_INNER_MARGIN: Margin = Margin {
 
 const ROW_HEIGHT: f32 = 72.0;
 
+/// Difficulty values changed by practice overrides
+const OVERRIDDEN_VALUE_COLOR: Color32 = Color32::from_rgb(255, 180, 60);
+
+/// How fast list scroll catches up with its target, higher is faster
+const SCROLL_EASE_SPEED: f32 = 12.0;
+
+/// Pixels per wheel line, notches are coarse on purpose
+const WHEEL_LINE_HEIGHT: f32 = ROW_HEIGHT / 2.0;
+
+/// Height of the preview progress strip on top of the beatmap card
+const PREVIEW_STRIP_HEIGHT: f32 = 4.0;
+
+/// Seeking closer than this to the end would finish the preview
+/// before anything is heard, seconds
+const PREVIEW_SEEK_END_MARGIN: f64 = 1.0;
+
+/// Touchpad fling, velocities are in px/s and decay is per second
+const FLING_DECAY: f32 = 5.0;
+const FLING_MIN_VELOCITY: f32 = 20.0;
+const FLING_MAX_VELOCITY: f32 = 10000.0;
+
+/// How much a single frame of touchpad movement affects
+/// the fling velocity, smooths out jittery deltas
+const FLING_SAMPLE_WEIGHT: f32 = 0.5;
+
+/// Rows ahead of a fling are requested this many seconds early
+const FLING_LOOKAHEAD: f32 = 0.25;
+
+/// Selection is saved once it stays the same for this long,
+/// scrolling through the list with arrows doesn't write every row
+const SAVE_SELECTION_DELAY: Duration = Duration::from_secs(1);
+
+/// Beat length limits used by stable, anything
+/// outside of them is clamped
+const MIN_BEAT_LEN: f64 = 6.0;
+const MAX_BEAT_LEN: f64 = 60000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BpmInfo {
+    pub min: f64,
+    pub max: f64,
+    /// BPM that is active for the longest time
+    pub common: f64,
+}
+
+impl BpmInfo {
+    /// Takes `(time, beat_len)` pairs sorted by time, `last_time` is
+    /// the end of the last object.
+    ///
+    /// Timing points with broken beat length are ignored
+    pub fn from_timing_points(
+        points: impl IntoIterator<Item = (f64, f64)>, 
+        last_time: f64
+    ) -> Option<Self> {
+        let points: Vec<(f64, f64)> = points.into_iter()
+            .filter(|(_, beat_len)| beat_len.is_finite() && *beat_len > 0.0)
+            .map(|(time, beat_len)| (time, beat_len.clamp(MIN_BEAT_LEN, MAX_BEAT_LEN)))
+            .collect();
+
+        if points.is_empty() {
+            return None;
+        }
+
+        let mut min = f64::MAX;
+        let mut max = f64::MIN;
+
+        // (bpm, total duration)
+        let mut durations: Vec<(f64, f64)> = Vec::new();
+
+        for (i, (time, beat_len)) in points.iter().enumerate() {
+            let bpm = 60000.0 / beat_len;
+
+            min = min.min(bpm);
+            max = max.max(bpm);
+
+            // First timing point is active from the very start of the map
+            let start = if i == 0 { 0.0 } else { *time };
+            let end = points.get(i + 1)
+                .map(|(next_time, _)| *next_time)
+                .unwrap_or(last_time);
+
+            let duration = (end.min(last_time) - start).max(0.0);
+
+            match durations.iter_mut().find(|(x, _)| (x - bpm).abs() < 0.001) {
+                Some((_, total)) => *total += duration,
+                None => durations.push((bpm, duration)),
+            }
+        }
+
+        let common = durations.iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(bpm, _)| *bpm)
+            .unwrap_or(min);
+
+        Some(Self {
+            min,
+            max,
+            common,
+        })
+    }
+
+    /// `common (min-max)` or just `common` if map has a single BPM
+    pub fn to_display_string(&self) -> String {
+        if self.min.round() == self.max.round() {
+            format!("{:.0}", self.common)
+        } else {
+            format!("{:.0} ({:.0}-{:.0})", self.common, self.min, self.max)
+        }
+    }
+}
+
+/// `mm:ss` string for a length in milliseconds
+pub fn format_length(ms: f64) -> String {
+    let length = Duration::from_millis(ms.max(0.0) as u64);
+
+    format!(
+        "{:02}:{:02}",
+        length.as_secs() / 60,
+        length.as_secs() % 60
+    )
+}
+
 // A struct that contains beatmap metadata
 // Build only once when loading beatmap because
-// calculating all the stuff + reallocating new strings
+// calculating all the stuff + reallocating new strings,
+// strings are rebuilt only when UI language changes
 pub struct BeatmapCardInfoMetadata {
+    creator: String,
+    source: String,
+    length_ms: f64,
+    bpm: Option<BpmInfo>,
+    objects: usize,
+    circles: usize,
+    sliders: usize,
+    spinners: usize,
+    /// Values from the .osu file
+    difficulty: Difficulty,
+    /// Overrides that `difficulty_info` and `derived_info` are built with
+    overrides: DifficultyOverrides,
+
+    /// Language of the strings below
+    lang: Lang,
+
     // `{} - {} [{}]`
     beatmap_header: String,
 
     // `Mapped by {}`
     mapped_by: String,
 
-    // `Length: {} BPM: {}-{} Objects: {}`
+    // `Source: {}`, empty if beatmap has no source
+    source_info: String,
+
+    // `Length: {} BPM: {} Objects: {}`
     length_info: String,
     
     // `Circles: {} Sliders: {} Spinners: {}`
     objects_count: String,
 
-    // `CS: {} AR: {} OD: {} HP: {} Start: {}`
-    difficutly_info: String,
+    // `CS:{}`, `AR:{}`, ... with effective values,
+    // `true` if value differs from the file
+    difficulty_info: Vec<(String, bool)>,
+
+    // Preempt, circle diameter and hit windows
+    derived_info: Vec<(String, bool)>,
 }
 
 impl BeatmapCardInfoMetadata {
+    /// Expected to be called from the loader thread,
+    /// UI thread only receives the finished struct
     pub fn from_beatmap(b: &mut Beatmap) -> Self {
+        let _span = tracy_client::span!("beatmap_card_info_metadata::from_beatmap");
+
         let last_hitobject_time = if let Some(obj) = b.hit_objects.last_mut() {
-            obj.end_time().clone() as u64
+            obj.end_time()
         } else {
-            0
-        };
-
-        let length = Duration::from_millis(last_hitobject_time);
-
-        let length_str = format!(
-            "{:02}:{:02}",
-            length.as_secs() / 60,
-            length.as_secs() % 60
-        );
-
-        let (bpm_max, bpm_min) = {
-            let mut max: f64 = f64::MIN;
-            let mut min: f64 = f64::MAX;
-
-            for point in &b.control_points.timing_points {
-                let bpm = 1.0 / point.beat_len * 1000.0 * 60.0;
-
-                max = max.max(bpm);
-                min = max.min(bpm);
-            }
-
-            (max, min)
+            0.0
         };
 
-        let length_info = format!(
-            "Length: {} BPM: {:.0}-{:.0} Objects: {}",
-            length_str, 
-            bpm_min, bpm_max,
-            b.hit_objects.len() 
+        let bpm = BpmInfo::from_timing_points(
+            b.control_points.timing_points.iter().map(|x| (x.time, x.beat_len)),
+            last_hitobject_time,
         );
 
         let circles = b.hit_objects.iter().filter(|h| {
@@ -101,19 +234,100 @@ impl BeatmapCardInfoMetadata {
             }
         }).count();
 
-        let difficutly_info = format!(
-            "CS:{:.2} AR:{:.2} OD:{:.2} HP:{:.2} Stars: TODO",
-            b.circle_size, b.approach_rate, b.overall_difficulty, b.hp_drain_rate
-        );
-
-        Self {
+        let mut metadata = Self {
+            creator: b.creator.clone(),
+            source: b.source.clone(),
+            length_ms: last_hitobject_time,
+            bpm,
+            objects: b.hit_objects.len(),
+            circles,
+            sliders,
+            spinners,
+            difficulty: Difficulty::from_beatmap(b),
+            overrides: DifficultyOverrides::default(),
+            lang: i18n::current(),
             beatmap_header: format!("{} - {} [{}]", b.artist, b.title, b.version),
-            mapped_by: format!("Mapped by {}", b.creator),
-            length_info,
-            objects_count: format!("Circles: {} Sliders: {} Spinners: {}", circles, sliders, spinners),
-            difficutly_info,
+            mapped_by: String::new(),
+            source_info: String::new(),
+            length_info: String::new(),
+            objects_count: String::new(),
+            difficulty_info: Vec::new(),
+            derived_info: Vec::new(),
+        };
+
+        metadata.build_strings();
+
+        metadata
+    }
+
+    /// Rebuilds strings if UI language was changed since the last call
+    pub fn localize(&mut self) {
+        if self.lang != i18n::current() {
+            self.lang = i18n::current();
+            self.build_strings();
+        }
+    }
+
+    /// Rebuilds difficulty strings if `overrides` differ from the current ones
+    pub fn set_overrides(&mut self, overrides: DifficultyOverrides) {
+        if self.overrides != overrides {
+            self.overrides = overrides;
+            self.build_strings();
         }
     }
+
+    fn build_strings(&mut self) {
+        let bpm_str = match self.bpm {
+            Some(bpm) => bpm.to_display_string(),
+            None => "-".to_owned(),
+        };
+
+        self.mapped_by = tf("song_select.mapped_by", &[&self.creator]);
+
+        self.source_info = match self.source.trim() {
+            "" => String::new(),
+            source => tf("song_select.source", &[&source]),
+        };
+
+        self.length_info = tf("song_select.length_info", &[
+            &format_length(self.length_ms),
+            &bpm_str,
+            &self.objects,
+        ]);
+
+        self.objects_count = tf("song_select.objects_count", &[
+            &self.circles,
+            &self.sliders,
+            &self.spinners,
+        ]);
+
+        let file = self.difficulty;
+        let effective = file.with_overrides(&self.overrides);
+        let derived = effective.derive();
+
+        let cs_changed = effective.cs != file.cs;
+        let ar_changed = effective.ar != file.ar;
+        let od_changed = effective.od != file.od;
+
+        self.difficulty_info = vec![
+            (format!("CS:{}", format_number(effective.cs as f64, 2)), cs_changed),
+            (format!("AR:{}", format_number(effective.ar as f64, 2)), ar_changed),
+            (format!("OD:{}", format_number(effective.od as f64, 2)), od_changed),
+            (format!("HP:{}", format_number(effective.hp as f64, 2)), effective.hp != file.hp),
+        ];
+
+        let window = &derived.hit_window;
+
+        self.derived_info = vec![
+            (tf("song_select.preempt", &[&format_number(derived.preempt as f64, 0)]), ar_changed),
+            (tf("song_select.circle_diameter", &[&format_number(derived.circle_diameter as f64, 1)]), cs_changed),
+            (tf("song_select.hit_windows", &[
+                &format_number(window.x300, 1),
+                &format_number(window.x100, 1),
+                &format_number(window.x50, 1),
+            ]), od_changed),
+        ];
+    }
 }
 
 pub struct CurrentBeatmap {
@@ -129,11 +343,56 @@ pub struct CurrentBackground {
 
 pub struct CurrentAudio {
     pub audio_hash: md5::Digest,
+    /// Directory of the beatmap that audio belongs to
+    pub beatmap_dir: PathBuf,
+}
+
+/// Position of the playing preview. Voice handle is owned by
+/// `OsuState`, which passes the progress every frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreviewProgress {
+    /// Seconds
+    pub position: f64,
+    /// Seconds
+    pub length: f64,
+}
+
+impl PreviewProgress {
+    pub fn fraction(&self) -> f32 {
+        match self.length > 0.0 {
+            true => (self.position / self.length).clamp(0.0, 1.0) as f32,
+            false => 0.0,
+        }
+    }
+
+    /// Position at `fraction` of the track, kept away from the
+    /// end so the preview doesn't finish right after the seek
+    pub fn seek_position(&self, fraction: f32) -> f64 {
+        let max = (self.length - PREVIEW_SEEK_END_MARGIN).max(0.0);
+
+        (fraction.clamp(0.0, 1.0) as f64 * self.length).min(max)
+    }
+}
+
+/// State of the collection management dialog
+#[derive(Default)]
+struct CollectionsDialog {
+    /// Name of the collection being created
+    new_name: String,
+    /// Beatmap md5 that is added to the created collection,
+    /// dialog closes after creation when it's set
+    add_hash: Option<String>,
+    /// Collection being renamed and its new name
+    renaming: Option<(i64, String)>,
+    /// Collection waiting for the delete confirmation
+    pending_delete: Option<i64>,
 }
 
-pub struct SongSelectScreen<'sss> {
-    db: Arc<OsuDatabase>,
-    graphics: Arc<Graphics<'sss>>,
+pub struct SongSelectScreen {
+    // All queries go through the worker so UI never waits on SQL
+    db: DbWorker,
+    graphics: Arc<Graphics>,
+    config: Arc<RwLock<Config>>,
 
     // Min & Max row that we currently need to draw
     min: usize,
@@ -145,24 +404,75 @@ pub struct SongSelectScreen<'sss> {
     // Contains a index to the beatmap that we need to scroll to
     // Used by initial scroll like F2, arrows and etc
     need_scroll_to: Option<usize>,
+
+    // Index that was requested from the DB because it's outside
+    // of the visible window, stale responses are ignored
+    pending_scroll_to: Option<usize>,
+
+    // Md5 of the selected beatmap which index is being looked up
+    // after new beatmaps were added, selection follows the beatmap
+    pending_reselect: Option<String>,
+
+    // Md5 of the beatmap selected on the last launch
+    // which index is being looked up
+    pending_restore: Option<String>,
+
+    // Selection that is stored in the DB and the one that
+    // is waiting to be stored with the time it was made
+    saved_selection: Option<SavedSelection>,
+    unsaved_selection: Option<(SavedSelection, Instant)>,
     
-    // Used when we need to center scroll a some beatmap card
-    need_scroll_center: Option<usize>,
+    // Scroll offset that list is animating to, `None`
+    // when user scrolls the list on their own
+    scroll_target: Option<f32>,
+
+    // Scroll offset of the list on the last frame
+    scroll_offset: f32,
+
+    // Touchpad fling velocity in px/s, only used
+    // while there is no `scroll_target`
+    scroll_velocity: f32,
+
+    // Height of the list viewport on the last frame
+    viewport_height: f32,
+
+    // Amount of pixels we need to scroll to compensate
+    // rows deleted above the visible window
+    need_scroll_delta: Option<f32>,
+
+    // Beatmapset waiting for the delete confirmation
+    pending_beatmapset_delete: Option<Arc<DbBeatmapEntry>>,
+
+    // Collections the list can be filtered by
+    collections: Vec<DbCollection>,
+
+    // Open collection management dialog
+    collections_dialog: Option<CollectionsDialog>,
+
+    // Text of the search box, applied as it's typed
+    search: String,
 
     song_select_tx: Sender<SongSelectionEvents>,
 
-    quad_renderer: QuadRenderer<'sss>,
+    quad_renderer: QuadRenderer,
     quad_test_buffer: wgpu::Buffer,
     quad_test_instance_data: Vec<QuadInstance>,
 
     current_beatmap: Option<CurrentBeatmap>,
     current_background_image: Option<CurrentBackground>,
+    // Still uploading, replaces the current one once ready
+    // so the previous background stays instead of a black frame
+    pending_background_image: Option<CurrentBackground>,
+
+    // `None` when nothing is playing
+    preview_progress: Option<PreviewProgress>,
 }
 
-impl<'sss> SongSelectScreen<'sss> {
+impl SongSelectScreen {
     pub fn new(
         db: Arc<OsuDatabase>,
-        graphics: Arc<Graphics<'sss>>, 
+        graphics: Arc<Graphics>, 
+        config: Arc<RwLock<Config>>,
         song_select_tx: Sender<SongSelectionEvents>,
     ) -> Self {
         let quad_renderer = QuadRenderer::new(graphics.clone(), false);
@@ -170,25 +480,136 @@ impl<'sss> SongSelectScreen<'sss> {
         let quad_test_buffer = quad_renderer.create_instance_buffer();
         let quad_test_instance_data = Vec::new();
 
+        let saved_selection = db.saved_selection();
+
+        let mut db = DbWorker::spawn(db);
+        db.request_collections();
+
+        // Index depends on the filter, so it's resolved
+        // by md5 only after the filter is restored
+        let pending_restore = saved_selection.as_ref().map(|saved| {
+            db.set_collection(saved.collection);
+            db.set_search(saved.search.clone());
+            db.request_hash_index(saved.hash.clone());
+
+            saved.hash.clone()
+        });
+
+        let search = saved_selection.as_ref()
+            .map(|x| x.search.clone())
+            .unwrap_or_default();
+
         Self {
             db,
             graphics,
+            config,
             min: 0,
             max: 0,
             current: 0,
             need_scroll_to: None,
-            need_scroll_center: None,
+            pending_scroll_to: None,
+            pending_reselect: None,
+            pending_restore,
+            saved_selection,
+            unsaved_selection: None,
+            scroll_target: None,
+            scroll_offset: 0.0,
+            scroll_velocity: 0.0,
+            viewport_height: 0.0,
+            need_scroll_delta: None,
+            pending_beatmapset_delete: None,
+            collections: Vec::new(),
+            collections_dialog: None,
+            search,
             song_select_tx,
             quad_renderer,
             quad_test_buffer,
             quad_test_instance_data,
             current_beatmap: None,
             current_background_image: None,
+            pending_background_image: None,
+            preview_progress: None,
         }
     }
 
-    pub fn current_in_cache(&self) -> usize {
-        self.current - self.min
+    /// Currently selected beatmap, `None` if it's not fetched yet
+    pub fn current_entry(&self) -> Option<Arc<DbBeatmapEntry>> {
+        self.db.window().get(self.current).cloned()
+    }
+
+    /// Index of the selected beatmap in the current list
+    #[inline]
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Search the list is narrowed down by, empty if there is none
+    #[inline]
+    pub fn search(&self) -> &str {
+        self.db.search()
+    }
+
+    /// Amount of beatmaps as of the last fetched window
+    pub fn beatmaps_amount(&self) -> usize {
+        self.db.window().total
+    }
+
+    /// Collection the list is filtered by
+    #[inline]
+    pub fn collection(&self) -> Option<i64> {
+        self.db.collection()
+    }
+
+    /// Called every frame before `render`
+    #[inline]
+    pub fn set_preview_progress(&mut self, progress: Option<PreviewProgress>) {
+        self.preview_progress = progress;
+    }
+
+    /// Refetches collections, e.g. after they were imported
+    pub fn refresh_collections(&mut self) {
+        self.db.request_collections();
+    }
+
+    /// Filters the list by `collection` and selects its first beatmap,
+    /// also used to reload the list when indexes are no longer valid
+    pub fn set_collection(&mut self, collection: Option<i64>) {
+        self.db.set_collection(collection);
+        self.reload_list();
+    }
+
+    /// Filters the list by the search box `query`, see [`crate::search_query`]
+    pub fn set_search(&mut self, query: &str) {
+        self.search = query.to_owned();
+
+        self.db.set_search(query);
+        self.reload_list();
+    }
+
+    /// List is narrowed down by a collection or a search,
+    /// so its indexes differ from the whole list ones
+    pub fn is_filtered(&self) -> bool {
+        self.collection().is_some() || !self.db.search().is_empty()
+    }
+
+    /// Selects the first beatmap of the current list, used when
+    /// filter changes or indexes are no longer valid
+    pub fn reload_list(&mut self) {
+        self.db.invalidate();
+
+        self.current = 0;
+        self.min = 0;
+        self.max = 0;
+        self.scroll_offset = 0.0;
+        self.scroll_target = None;
+        self.scroll_velocity = 0.0;
+        self.need_scroll_to = None;
+        self.need_scroll_delta = None;
+
+        // Window is still from the previous list, so
+        // first entry is requested from the DB itself
+        self.pending_scroll_to = Some(0);
+        self.db.request_index(0);
     }
 
     pub fn set_scroll_to(&mut self, to: usize) {
@@ -203,28 +624,193 @@ impl<'sss> SongSelectScreen<'sss> {
         self.set_scroll_to(self.current.saturating_sub(1));
     }
 
+    /// Starts animating the list so card at `index` ends up
+    /// in the vertical center, clamped at the list ends
+    fn scroll_to_center(&mut self, index: usize) {
+        self.scroll_target = Some(centered_scroll_offset(
+            index,
+            self.beatmaps_amount(),
+            self.viewport_height,
+        ));
+    }
+
+    /// Adjusts current selection and visible window after
+    /// rows at `deleted` indexes were removed from the database
+    pub fn on_beatmaps_deleted(&mut self, deleted: &[usize]) {
+        let total = self.beatmaps_amount().saturating_sub(deleted.len());
+
+        let removed_above = deleted.iter().filter(|x| **x < self.min).count();
+
+        self.current = shift_index_after_delete(self.current, deleted, total);
+
+        if removed_above > 0 {
+            self.need_scroll_delta = Some(removed_above as f32 * ROW_HEIGHT);
+        }
+
+        self.db.invalidate();
+    }
+
+    fn select_index(&mut self, index: usize, entry: Arc<DbBeatmapEntry>) {
+        self.current = index;
+
+        self.song_select_tx.send(
+            SongSelectionEvents::SelectBeatmap(entry)
+        ).expect(
+            "Failed to send SelectBeatmap event to the SongSelectState"
+        );
+
+        self.scroll_to_center(index);
+    }
+
+    /// Handles responses of the DB worker, never blocks
+    fn poll_db(&mut self) {
+        let _span = tracy_client::span!("osu_song_select_state::poll_db");
+
+        while let Some(response) = self.db.poll() {
+            match response {
+                DbResponse::Index(index, entry) => {
+                    if self.pending_scroll_to != Some(index) {
+                        continue;
+                    }
+
+                    self.pending_scroll_to = None;
+
+                    if let Some(entry) = entry {
+                        self.select_index(index, entry);
+                    }
+                },
+                DbResponse::Collections(collections) => {
+                    self.collections = collections;
+
+                    // Filtered collection is gone
+                    if let Some(id) = self.collection() {
+                        if !self.collections.iter().any(|x| x.id == id) {
+                            self.set_collection(None);
+                        }
+                    }
+                },
+                DbResponse::Changed => {
+                    // Selected entry is only known while it's in the window
+                    if let Some(entry) = self.current_entry() {
+                        self.pending_reselect = Some(entry.hash.clone());
+                        self.db.request_hash_index(entry.hash.clone());
+                    }
+                },
+                DbResponse::HashIndex(hash, index) if self.pending_restore.as_ref() == Some(&hash) => {
+                    self.pending_restore = None;
+
+                    // Selected as if it was clicked, so card, background
+                    // and preview are loaded. Deleted one falls back to the first
+                    self.set_scroll_to(index.unwrap_or(0));
+                },
+                DbResponse::HashIndex(hash, index) => {
+                    if self.pending_reselect.as_ref() != Some(&hash) {
+                        continue;
+                    }
+
+                    self.pending_reselect = None;
+
+                    // Beatmap itself is the same, so it's not selected again
+                    if let Some(index) = index.filter(|x| *x != self.current) {
+                        self.current = index;
+                        self.scroll_to_center(index);
+                    }
+                },
+                DbResponse::Hash(..) | DbResponse::Search(..) => {},
+            }
+        }
+    }
+
+    /// Stores the selection once it settles, called every frame
+    fn save_selection(&mut self) {
+        // Would overwrite the saved one with the first beatmap
+        if self.pending_restore.is_some() {
+            return;
+        }
+
+        let Some(entry) = self.current_entry() else {
+            return;
+        };
+
+        let is_same = |x: &SavedSelection| {
+            x.hash == entry.hash
+            && x.collection == self.db.collection()
+            && x.search == self.db.search()
+        };
+
+        if self.saved_selection.as_ref().is_some_and(is_same) {
+            self.unsaved_selection = None;
+            return;
+        }
+
+        match &self.unsaved_selection {
+            Some((selection, at)) if is_same(selection) => {
+                if at.elapsed() < SAVE_SELECTION_DELAY {
+                    return;
+                }
+            },
+            _ => {
+                let selection = SavedSelection {
+                    hash: entry.hash.clone(),
+                    collection: self.db.collection(),
+                    search: self.db.search().to_owned(),
+                };
+
+                self.unsaved_selection = Some((selection, Instant::now()));
+                return;
+            },
+        }
+
+        if let Some((selection, _)) = self.unsaved_selection.take() {
+            self.db.save_selection(selection.clone());
+            self.saved_selection = Some(selection);
+        }
+    }
+
     pub fn set_background(&mut self, image: DynamicImage, md5: Digest) {
         // Do not preform any operations if background is the same
         if let Some(current_background) = &self.current_background_image {
             if current_background.image_hash == md5 {
                 tracing::info!("Background already cached, doing nothing");
+                self.pending_background_image = None;
                 return;
             }
         }
 
-        self.resize_background_vertex(image.width() as f32, image.height() as f32);
+        if self.pending_background_image.as_ref().is_some_and(|x| x.image_hash == md5) {
+            return;
+        }
 
-        let texture = Texture::from_image(
+        let texture = Texture::from_image_staged(
             image,
             &self.graphics
         );
 
-        let current_background_image = CurrentBackground {
+        self.pending_background_image = Some(CurrentBackground {
             texture,
             image_hash: md5,
-        };
+        });
+
+        self.swap_ready_background();
+    }
+
+    /// Shows pending background once its upload is finished
+    fn swap_ready_background(&mut self) {
+        if !self.pending_background_image.as_ref().is_some_and(|x| x.texture.is_ready()) {
+            return;
+        }
+
+        let background = self.pending_background_image.take()
+            .expect("pending background should exist");
 
-        self.current_background_image = Some(current_background_image);
+        self.resize_background_vertex(background.texture.width, background.texture.height);
+        self.current_background_image = Some(background);
+    }
+
+    pub fn set_difficulty_overrides(&mut self, overrides: DifficultyOverrides) {
+        if let Some(b) = &mut self.current_beatmap {
+            b.metadata.set_overrides(overrides);
+        }
     }
 
     pub fn set_current_beatmap(&mut self, beatmap: Option<CurrentBeatmap>) {
@@ -303,7 +889,10 @@ impl<'sss> SongSelectScreen<'sss> {
         ctx: &egui::Context, 
         view: &TextureView,
     ) {
+        self.swap_ready_background();
         self.render_background(view);
+        self.render_delete_confirmation(ctx);
+        self.render_collections_dialog(ctx);
 
         egui::CentralPanel::default().frame(egui::Frame::NONE).show(ctx, |ui| {
             StripBuilder::new(ui)
@@ -344,68 +933,145 @@ impl<'sss> SongSelectScreen<'sss> {
                     });
 
                     strip.cell(|ui| {
-                        egui::ScrollArea::vertical()
-                        .scroll_bar_visibility(ScrollBarVisibility::AlwaysHidden)
-                        .show_viewport(ui, |ui, rect| {
-                            let total_height = ROW_HEIGHT * self.db.beatmaps_amount() as f32;
-                            ui.set_height(total_height);
-                            
-                            // Handling custom scrolling event
-                            // Cases:
-                            //     1. Pressed F2 so we got random beatmap
-                            //     2. Pressed ArrowDown/Up so we increment by 1
-                            if let Some(need_scroll_to) = self.need_scroll_to.take() {
-                                let entry = self.db.get_beatmap_by_index(need_scroll_to);
+                        self.viewport_height = ui.available_height();
+
+                        // Handling custom scrolling event
+                        // Cases:
+                        //     1. Pressed F2 so we got random beatmap
+                        //     2. Pressed ArrowDown/Up so we increment by 1
+                        self.poll_db();
+                        self.save_selection();
+
+                        if let Some(need_scroll_to) = self.need_scroll_to.take() {
+                            let entry = self.db.window().get(need_scroll_to).cloned();
+
+                            match entry {
+                                Some(entry) => self.select_index(need_scroll_to, entry),
+                                None => {
+                                    self.pending_scroll_to = Some(need_scroll_to);
+                                    self.db.request_index(need_scroll_to);
+                                },
+                            }
+                        }
+
+                        let dt = ctx.input(|i| i.stable_dt);
+                        let max_offset = max_scroll_offset(self.beatmaps_amount(), self.viewport_height);
+
+                        // Scrolling is handled by us, so it only moves the list
+                        // and never touches the selection
+                        let (wheel, touchpad) = if ui.rect_contains_pointer(ui.max_rect()) {
+                            ctx.input(|i| scroll_deltas(&i.events, self.viewport_height))
+                        } else {
+                            (0.0, 0.0)
+                        };
+
+                        // Wheel notches are eased like the rest of the list animation
+                        if wheel != 0.0 {
+                            let target = self.scroll_target.unwrap_or(self.scroll_offset);
+                            self.scroll_target = Some((target - wheel).clamp(0.0, max_offset));
+                        }
+
+                        // Touchpad takes over the animation
+                        if touchpad != 0.0 {
+                            self.scroll_target = None;
+                        }
+
+                        let mut offset = self.scroll_offset;
+
+                        if let Some(target) = self.scroll_target {
+                            self.scroll_velocity = 0.0;
+                            offset = ease_scroll(offset, target, dt);
+
+                            if (offset - target).abs() < 0.5 {
+                                self.scroll_target = None;
+                                offset = target;
+                            } else {
+                                ctx.request_repaint();
+                            }
+                        } else {
+                            offset = fling_scroll(offset, &mut self.scroll_velocity, touchpad, dt, max_offset);
 
-                                if let Some(entry) = entry {
-                                    let current_y = self.current as f32 * ROW_HEIGHT;
+                            if self.scroll_velocity != 0.0 {
+                                ctx.request_repaint();
+                            }
+                        }
 
-                                    let scroll_y = need_scroll_to as f32 * ROW_HEIGHT;
+                        // Compensating rows deleted above the visible window
+                        if let Some(delta) = self.need_scroll_delta.take() {
+                            offset -= delta;
 
-                                    let scroll_y = scroll_y - current_y;
-                                    self.current = need_scroll_to;
+                            if let Some(target) = &mut self.scroll_target {
+                                *target -= delta;
+                            }
+                        }
 
-                                    ui.scroll_with_delta(
-                                        egui::Vec2::new(0.0, -1.0 * scroll_y)
-                                    );
+                        // List might've shrunk after a search
+                        offset = offset.clamp(0.0, max_offset);
 
-                                    self.song_select_tx.send(
-                                        SongSelectionEvents::SelectBeatmap(entry.into())
-                                    ).expect(
-                                        "Failed to send SelectBeatmap event to the SongSelectState"
-                                    );
+                        if let Some(target) = &mut self.scroll_target {
+                            *target = target.clamp(0.0, max_offset);
+                        }
 
-                                    self.need_scroll_center = Some(need_scroll_to);
-                                }
-                            }
+                        let scroll_area = egui::ScrollArea::vertical()
+                            .scroll_bar_visibility(ScrollBarVisibility::AlwaysHidden)
+                            .enable_scrolling(false)
+                            .vertical_scroll_offset(offset);
+
+                        let velocity = self.scroll_velocity;
 
+                        let output = scroll_area.show_viewport(ui, |ui, rect| {
+                            let window = self.db.window().clone();
+                            let total = window.total;
+                            let total_height = ROW_HEIGHT * total as f32;
+                            ui.set_height(total_height);
+
+                            // Rect is already at the animated offset
+                            // so rows stream in while list is moving
                             let min_row = (rect.min.y / ROW_HEIGHT).floor() as usize;
                             let max_row = (rect.max.y / ROW_HEIGHT).floor() as usize;
 
+                            // Fetching ahead of a fling, otherwise fast
+                            // lists show blank rows until the DB catches up
+                            let lookahead = (velocity.abs() * FLING_LOOKAHEAD / ROW_HEIGHT).ceil() as usize;
+                            let (fetch_min, fetch_max) = if velocity < 0.0 {
+                                (min_row.saturating_sub(lookahead), max_row)
+                            } else {
+                                (min_row, max_row + lookahead)
+                            };
+
+                            // Newest range wins, while it's in flight
+                            // previous window is drawn at its own position
+                            self.db.request_range(fetch_min, fetch_max);
+
+                            if self.db.is_range_in_flight() {
+                                ctx.request_repaint();
+                            }
 
-                            let fill_top = (min_row - 0) as f32 * (ROW_HEIGHT);
+                            let selection_color = self.config.read()
+                                .expect("failed to acquire read lock")
+                                .color_preset
+                                .selection_color()
+                                .to_egui_color();
+
+                            let fill_top = window.min as f32 * ROW_HEIGHT;
                             egui::Frame::NONE
                                 .show(ui, |ui| {
                                     ui.set_height(fill_top);
                                 });
 
-                            if max_row != self.max || min_row != self.min {
-                                self.db.fetch_beatmaps_range(min_row, max_row);
-                            }
-
-                            let current = min_row;
+                            // Row that left the filtered collection, handled
+                            // after the loop like a deleted one
+                            let mut removed_from_collection = None;
 
-                            let lock = self.db.cache.lock().unwrap();
-                            
-                            for (i, beatmap) in lock.iter().enumerate() {
-                                let id = current + i;
+                            for (i, beatmap) in window.entries.iter().enumerate() {
+                                let id = window.min + i;
                                 let res = egui::Frame::default()
                                     .inner_margin(CARD_INNER_MARGIN)
                                     .outer_margin(0.0)
                                     .fill(Color32::from_rgba_unmultiplied(0, 0, 0, 250))
                                     .stroke({
                                         if self.current == id {
-                                            Stroke::new(1.0, Color32::RED)
+                                            Stroke::new(1.0, selection_color)
                                         } else {
                                             Stroke::new(1.0, Color32::BLACK)
                                         }
@@ -416,19 +1082,74 @@ impl<'sss> SongSelectScreen<'sss> {
                                         ui.set_max_height(64.0);
 
 
-                                        ui.add(Label::new(RichText::new(&beatmap.title).heading()).selectable(false));
+                                        ui.horizontal(|ui| {
+                                            ui.add(Label::new(RichText::new(&beatmap.title).heading()).selectable(false));
+
+                                            if self.db.is_new(beatmap) {
+                                                ui.add(Label::new(RichText::new(t("song_select.new")).small().color(selection_color)).selectable(false));
+                                            }
+
+                                            if let Some(mode) = beatmap.unsupported_mode() {
+                                                ui.add(Label::new(RichText::new(tf("song_select.unsupported_mode", &[&format!("{mode:?}")])).small().weak()).selectable(false));
+                                            }
+                                        });
                                         ui.add(Label::new(format!("{} // {}", &beatmap.artist, &beatmap.creator)).selectable(false));
                                         ui.add(Label::new(&beatmap.version).selectable(false));
                                     });
 
                                 let sense = res.response.interact(egui::Sense::click());
 
-                                if let Some(need_scroll_center) = self.need_scroll_center {
-                                    if id == need_scroll_center {
-                                        res.response.scroll_to_me(Some(Align::Center));
-                                        let _ = self.need_scroll_center.take();
+                                sense.context_menu(|ui| {
+                                    if ui.button(t("song_select.open_folder")).clicked() {
+                                        let _ = self.song_select_tx.send(
+                                            SongSelectionEvents::OpenBeatmapFolder(beatmap.clone())
+                                        );
+                                        ui.close_menu();
                                     }
-                                }
+
+                                    if ui.button(t("song_select.delete_difficulty")).clicked() {
+                                        let _ = self.song_select_tx.send(
+                                            SongSelectionEvents::DeleteBeatmap(beatmap.clone())
+                                        );
+                                        ui.close_menu();
+                                    }
+
+                                    if ui.button(t("song_select.delete_mapset")).clicked() {
+                                        self.pending_beatmapset_delete = Some(beatmap.clone());
+                                        ui.close_menu();
+                                    }
+
+                                    ui.separator();
+
+                                    ui.menu_button(t("song_select.collection.add"), |ui| {
+                                        for collection in &self.collections {
+                                            if ui.button(&collection.name).clicked() {
+                                                self.db.add_to_collection(collection.id, beatmap.hash.clone());
+                                                ui.close_menu();
+                                            }
+                                        }
+
+                                        if !self.collections.is_empty() {
+                                            ui.separator();
+                                        }
+
+                                        if ui.button(t("song_select.collection.new")).clicked() {
+                                            self.collections_dialog = Some(CollectionsDialog {
+                                                add_hash: Some(beatmap.hash.clone()),
+                                                ..Default::default()
+                                            });
+                                            ui.close_menu();
+                                        }
+                                    });
+
+                                    if let Some(collection) = self.db.collection() {
+                                        if ui.button(t("song_select.collection.remove")).clicked() {
+                                            self.db.remove_from_collection(collection, beatmap.hash.clone());
+                                            removed_from_collection = Some(id);
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
 
                                 if sense.clicked() {
                                     if id == self.current {
@@ -445,7 +1166,7 @@ impl<'sss> SongSelectScreen<'sss> {
                                         "Failed to send SelectBeatmap event to the SongSelectState"
                                     );
 
-                                    res.response.scroll_to_me(Some(Align::Center));
+                                    self.scroll_target = Some(centered_scroll_offset(id, total, self.viewport_height));
                                 }
 
                                 if sense.double_clicked() {
@@ -455,21 +1176,68 @@ impl<'sss> SongSelectScreen<'sss> {
                                     ).expect(
                                         "Failed to send StartBeatmap event to the SongSelectState"
                                     );
-                                    res.response.scroll_to_me(Some(Align::Center));
+                                    self.scroll_target = Some(centered_scroll_offset(id, total, self.viewport_height));
                                 }
                             };
+
+                            if let Some(id) = removed_from_collection {
+                                self.on_beatmaps_deleted(&[id]);
+                            }
                             
                             self.min = min_row;
                             self.max = max_row;
                         });
 
+                        self.scroll_offset = output.state.offset.y;
+
                     })
                 })
         });
     }
 
+    fn render_delete_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(entry) = &self.pending_beatmapset_delete else {
+            return;
+        };
+
+        let mut is_done = false;
+
+        egui::Modal::new(egui::Id::new("delete_beatmapset_modal")).show(ctx, |ui| {
+            ui.label(tf("song_select.delete_mapset_confirm", &[
+                &entry.artist,
+                &entry.title,
+            ]));
+
+            if let Some(dir) = entry.path.parent() {
+                ui.label(tf("song_select.moved_to_trash", &[&dir.display()]));
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button(t("common.delete")).clicked() {
+                    let _ = self.song_select_tx.send(
+                        SongSelectionEvents::DeleteBeatmapset(entry.clone())
+                    );
+                    is_done = true;
+                }
+
+                if ui.button(t("common.cancel")).clicked() {
+                    is_done = true;
+                }
+            });
+        });
+
+        if is_done {
+            self.pending_beatmapset_delete = None;
+        }
+    }
+
     fn render_beatmap_card_info(&mut self, ui: &mut egui::Ui) {
         let _span = tracy_client::span!("osu_song_select_state::render_beatmap_card_info");
+
+        // Clicking on the mapper shows only their beatmaps
+        let mut creator_search = None;
+        let mut preview_seek = None;
+
         egui::Frame::default()
             .corner_radius(5.0)
             .outer_margin(10.0)
@@ -479,30 +1247,197 @@ impl<'sss> SongSelectScreen<'sss> {
 
                 ui.set_width(ui.available_rect_before_wrap().width());
                 ui.set_height(ui.available_rect_before_wrap().height());
+
+                if let Some(progress) = self.preview_progress {
+                    preview_seek = preview_strip(ui, progress);
+                }
+
                 if let Some(b) = &mut self.current_beatmap {
+                    b.metadata.localize();
+
                     ui.add(Label::new(RichText::new(&b.metadata.beatmap_header).heading()).selectable(false));
-                    ui.add(Label::new(&b.metadata.mapped_by).selectable(false));
+                    let mapped_by = Label::new(&b.metadata.mapped_by)
+                        .selectable(false)
+                        .sense(egui::Sense::click());
+
+                    let response = ui.add(mapped_by)
+                        .on_hover_cursor(egui::CursorIcon::PointingHand)
+                        .on_hover_text(t("song_select.mapped_by.filter"));
+
+                    if response.clicked() {
+                        creator_search = Some(creator_query(&b.metadata.creator));
+                    }
+
+                    if !b.metadata.source_info.is_empty() {
+                        ui.add(Label::new(&b.metadata.source_info).selectable(false));
+                    }
 
                     ui.add(Label::new(RichText::new(&b.metadata.length_info).strong()).selectable(false));
 
                     ui.add(Label::new(&b.metadata.objects_count).selectable(false));
-                    ui.add(Label::new(&b.metadata.difficutly_info).selectable(false));
+                    difficulty_line(ui, &b.metadata.difficulty_info);
+                    difficulty_line(ui, &b.metadata.derived_info);
                 } else {
                     ui.centered_and_justified(|ui| {
                         ui.spinner();
                     });
                 }
             });
+
+        if let Some(query) = creator_search {
+            self.set_search(&query);
+        }
+
+        if let Some(position) = preview_seek {
+            let _ = self.song_select_tx.send(SongSelectionEvents::SeekPreview(position));
+        }
+    }
+
+    fn render_search(&mut self, ui: &mut egui::Ui) {
+        let _span = tracy_client::span!("osu_song_select_state::render_search");
+
+        let response = ui.add(egui::TextEdit::singleline(&mut self.search)
+            .hint_text(t("song_select.search"))
+        );
+
+        if response.changed() {
+            let query = self.search.clone();
+            self.set_search(&query);
+        }
+    }
+
+    fn render_collection_filter(&mut self, ui: &mut egui::Ui) {
+        let _span = tracy_client::span!("osu_song_select_state::render_collection_filter");
+
+        let current = self.collection();
+
+        let selected_text = self.collections
+            .iter()
+            .find(|x| Some(x.id) == current)
+            .map(|x| x.name.as_str())
+            .unwrap_or(t("song_select.collection.all"));
+
+        let mut selected = current;
+
+        egui::ComboBox::from_id_salt("song_select_collection")
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, None, t("song_select.collection.all"));
+
+                for collection in &self.collections {
+                    let text = tf(
+                        "song_select.collection.entry",
+                        &[&collection.name, &collection.resolved, &collection.beatmaps],
+                    );
+
+                    ui.selectable_value(&mut selected, Some(collection.id), text);
+                }
+            });
+
+        if selected != current {
+            self.set_collection(selected);
+        }
+
+        if ui.button(t("song_select.collection.manage")).clicked() {
+            self.collections_dialog = Some(CollectionsDialog::default());
+        }
+    }
+
+    fn render_collections_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.collections_dialog else {
+            return;
+        };
+
+        let mut is_done = false;
+
+        egui::Modal::new(egui::Id::new("collections_modal")).show(ctx, |ui| {
+            ui.heading(t("song_select.collection.manage"));
+
+            for collection in &self.collections {
+                ui.horizontal(|ui| {
+                    match &mut dialog.renaming {
+                        Some((id, name)) if *id == collection.id => {
+                            ui.text_edit_singleline(name);
+
+                            let is_valid = is_valid_collection_name(name, &self.collections, Some(*id));
+
+                            if ui.add_enabled(is_valid, egui::Button::new(t("song_select.collection.rename"))).clicked() {
+                                self.db.rename_collection(*id, name.trim());
+                                dialog.renaming = None;
+                            }
+
+                            if ui.button(t("common.cancel")).clicked() {
+                                dialog.renaming = None;
+                            }
+                        },
+                        _ if dialog.pending_delete == Some(collection.id) => {
+                            ui.label(tf("song_select.collection.delete_confirm", &[&collection.name]));
+
+                            if ui.button(t("common.delete")).clicked() {
+                                self.db.delete_collection(collection.id);
+                                dialog.pending_delete = None;
+                            }
+
+                            if ui.button(t("common.cancel")).clicked() {
+                                dialog.pending_delete = None;
+                            }
+                        },
+                        _ => {
+                            ui.label(tf(
+                                "song_select.collection.entry",
+                                &[&collection.name, &collection.resolved, &collection.beatmaps],
+                            ));
+
+                            if ui.button(t("song_select.collection.rename")).clicked() {
+                                dialog.renaming = Some((collection.id, collection.name.clone()));
+                            }
+
+                            if ui.button(t("common.delete")).clicked() {
+                                dialog.pending_delete = Some(collection.id);
+                            }
+                        },
+                    }
+                });
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut dialog.new_name)
+                    .hint_text(t("song_select.collection.name"))
+                );
+
+                let is_valid = is_valid_collection_name(&dialog.new_name, &self.collections, None);
+
+                if ui.add_enabled(is_valid, egui::Button::new(t("song_select.collection.create"))).clicked() {
+                    self.db.create_collection(dialog.new_name.trim(), dialog.add_hash.clone());
+                    dialog.new_name.clear();
+
+                    is_done = dialog.add_hash.is_some();
+                }
+            });
+
+            if ui.button(t("common.close")).clicked() {
+                is_done = true;
+            }
+        });
+
+        if is_done {
+            self.collections_dialog = None;
+        }
     }
 
     fn render_beatmap_footer(&mut self, ui: &mut egui::Ui) {
         let _span = tracy_client::span!("osu_song_select_state::render_beatmap_footer");
         ui.with_layout(egui::Layout::centered_and_justified(Direction::LeftToRight), |ui| {
-            let text = format!("Beatmaps: {}", self.db.beatmaps_amount());
+            let text = tf("song_select.beatmaps_amount", &[&self.beatmaps_amount()]);
             ui.add(Label::new(RichText::new(text).heading())
                 .selectable(false)
             );
 
+            self.render_search(ui);
+            self.render_collection_filter(ui);
+
             egui::Frame::NONE
                 .show(ui, |ui| {
                     ui.set_min_width(50.0);
@@ -516,3 +1451,289 @@ impl<'sss> SongSelectScreen<'sss> {
         });
     }
 }
+
+/// Names are trimmed, non-empty and unique, `except` is
+/// the collection that is being renamed
+fn is_valid_collection_name(name: &str, collections: &[DbCollection], except: Option<i64>) -> bool {
+    let name = name.trim();
+
+    !name.is_empty() && !collections
+        .iter()
+        .any(|x| x.name == name && Some(x.id) != except)
+}
+
+/// Thin strip with the preview position, returns
+/// the position to seek to if it was clicked
+fn preview_strip(ui: &mut egui::Ui, progress: PreviewProgress) -> Option<f64> {
+    let size = egui::vec2(ui.available_width(), PREVIEW_STRIP_HEIGHT);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+    let response = response.on_hover_cursor(egui::CursorIcon::PointingHand);
+
+    let mut filled = rect;
+    filled.set_width(rect.width() * progress.fraction());
+
+    ui.painter().rect_filled(rect, 2.0, Color32::from_gray(60));
+    ui.painter().rect_filled(filled, 2.0, Color32::from_gray(220));
+
+    if !response.clicked() {
+        return None;
+    }
+
+    let pos = response.interact_pointer_pos()?;
+    let fraction = (pos.x - rect.left()) / rect.width();
+
+    Some(progress.seek_position(fraction))
+}
+
+/// Values that differ from the beatmap file are highlighted
+fn difficulty_line(ui: &mut egui::Ui, values: &[(String, bool)]) {
+    ui.horizontal_wrapped(|ui| {
+        for (text, changed) in values {
+            let text = if *changed {
+                RichText::new(text).color(OVERRIDDEN_VALUE_COLOR)
+            } else {
+                RichText::new(text)
+            };
+
+            ui.add(Label::new(text).selectable(false));
+        }
+    });
+}
+
+/// Scroll offset that puts card at `index` in the vertical
+/// center of the viewport, clamped at the list ends
+fn centered_scroll_offset(index: usize, total: usize, viewport_height: f32) -> f32 {
+    let max_offset = max_scroll_offset(total, viewport_height);
+    let offset = index as f32 * ROW_HEIGHT + ROW_HEIGHT / 2.0 - viewport_height / 2.0;
+
+    offset.clamp(0.0, max_offset)
+}
+
+/// Exponential ease towards the `target`, frame-rate independent
+fn ease_scroll(current: f32, target: f32, dt: f32) -> f32 {
+    let t = 1.0 - (-SCROLL_EASE_SPEED * dt).exp();
+
+    current + (target - current) * t
+}
+
+fn max_scroll_offset(total: usize, viewport_height: f32) -> f32 {
+    (total as f32 * ROW_HEIGHT - viewport_height).max(0.0)
+}
+
+/// Vertical wheel and touchpad deltas of the frame in pixels, positive
+/// values scroll up. Wheels report lines, touchpads report points
+fn scroll_deltas(events: &[egui::Event], viewport_height: f32) -> (f32, f32) {
+    let mut wheel = 0.0;
+    let mut touchpad = 0.0;
+
+    for event in events {
+        let egui::Event::MouseWheel { unit, delta, .. } = event else {
+            continue;
+        };
+
+        match unit {
+            MouseWheelUnit::Point => touchpad += delta.y,
+            MouseWheelUnit::Line => wheel += delta.y * WHEEL_LINE_HEIGHT,
+            MouseWheelUnit::Page => wheel += delta.y * viewport_height,
+        }
+    }
+
+    (wheel, touchpad)
+}
+
+/// Moves the list by touchpad `delta` and keeps it coasting with
+/// decaying `velocity` once fingers are lifted. Velocity is reset
+/// when the list hits either end
+fn fling_scroll(offset: f32, velocity: &mut f32, delta: f32, dt: f32, max_offset: f32) -> f32 {
+    let offset = if delta != 0.0 {
+        if dt > 0.0 {
+            let sample = (-delta / dt).clamp(-FLING_MAX_VELOCITY, FLING_MAX_VELOCITY);
+            *velocity += (sample - *velocity) * FLING_SAMPLE_WEIGHT;
+        }
+
+        offset - delta
+    } else {
+        let offset = offset + *velocity * dt;
+        *velocity *= (-FLING_DECAY * dt).exp();
+
+        if velocity.abs() < FLING_MIN_VELOCITY {
+            *velocity = 0.0;
+        }
+
+        offset
+    };
+
+    if offset <= 0.0 || offset >= max_offset {
+        *velocity = 0.0;
+    }
+
+    offset.clamp(0.0, max_offset)
+}
+
+#[test]
+fn test_bpm_info_single() {
+    let bpm = BpmInfo::from_timing_points([(500.0, 500.0)], 60000.0).unwrap();
+
+    assert_eq!(bpm, BpmInfo { min: 120.0, max: 120.0, common: 120.0 });
+    assert_eq!(bpm.to_display_string(), "120");
+}
+
+#[test]
+fn test_bpm_info_multiple() {
+    // 120 bpm for 10s, 240 bpm for 40s, 120 bpm again for 10s
+    let points = [(0.0, 500.0), (10000.0, 250.0), (50000.0, 500.0)];
+    let bpm = BpmInfo::from_timing_points(points, 60000.0).unwrap();
+
+    assert_eq!(bpm, BpmInfo { min: 120.0, max: 240.0, common: 240.0 });
+    assert_eq!(bpm.to_display_string(), "240 (120-240)");
+
+    // Both 120 sections together are longer
+    let points = [(0.0, 500.0), (30000.0, 250.0), (45000.0, 500.0)];
+    let bpm = BpmInfo::from_timing_points(points, 60000.0).unwrap();
+
+    assert_eq!(bpm.common, 120.0);
+}
+
+#[test]
+fn test_bpm_info_weird_initial_beat_len() {
+    // Broken first timing point is ignored
+    let points = [(0.0, -100.0), (1000.0, 500.0)];
+    let bpm = BpmInfo::from_timing_points(points, 60000.0).unwrap();
+
+    assert_eq!(bpm, BpmInfo { min: 120.0, max: 120.0, common: 120.0 });
+
+    // Huge beat length is clamped like stable does,
+    // but it's active only for a moment
+    let points = [(0.0, 1e9), (100.0, 500.0)];
+    let bpm = BpmInfo::from_timing_points(points, 60000.0).unwrap();
+
+    assert_eq!(bpm.min, 1.0);
+    assert_eq!(bpm.common, 120.0);
+
+    assert!(BpmInfo::from_timing_points([(0.0, f64::NAN)], 1000.0).is_none());
+}
+
+#[test]
+fn test_format_length() {
+    assert_eq!(format_length(90_500.0), "01:30");
+    assert_eq!(format_length(0.0), "00:00");
+}
+
+#[test]
+fn test_centered_scroll_offset() {
+    // 10 rows in a viewport of 3 rows
+    let viewport = ROW_HEIGHT * 3.0;
+
+    assert_eq!(centered_scroll_offset(0, 10, viewport), 0.0);
+    assert_eq!(centered_scroll_offset(1, 10, viewport), 0.0);
+    assert_eq!(centered_scroll_offset(5, 10, viewport), ROW_HEIGHT * 4.0);
+    assert_eq!(centered_scroll_offset(9, 10, viewport), ROW_HEIGHT * 7.0);
+
+    // Whole list fits into the viewport
+    assert_eq!(centered_scroll_offset(2, 2, viewport), 0.0);
+}
+
+#[test]
+fn test_ease_scroll_frame_rate_independent() {
+    let one_step = ease_scroll(0.0, 1000.0, 0.1);
+
+    let mut many_steps = 0.0;
+    for _ in 0..10 {
+        many_steps = ease_scroll(many_steps, 1000.0, 0.01);
+    }
+
+    assert!((one_step - many_steps).abs() < 0.01);
+    assert!(one_step > 0.0 && one_step < 1000.0);
+    assert_eq!(ease_scroll(500.0, 500.0, 0.016), 500.0);
+}
+
+#[test]
+fn test_fling_scroll() {
+    let dt = 0.01;
+    let mut velocity = 0.0;
+
+    // Swipe up the touchpad scrolls the list down
+    let mut offset = fling_scroll(500.0, &mut velocity, -10.0, dt, 5000.0);
+    assert_eq!(offset, 510.0);
+    assert!(velocity > 0.0);
+
+    // Coasting after fingers are lifted, slowing down until it stops
+    let mut last_step = f32::INFINITY;
+    let mut frames = 0;
+    while velocity != 0.0 {
+        let next = fling_scroll(offset, &mut velocity, 0.0, dt, 5000.0);
+        assert!(next > offset);
+        assert!(next - offset <= last_step);
+
+        last_step = next - offset;
+        offset = next;
+        frames += 1;
+        assert!(frames < 1000);
+    }
+
+    assert!(offset > 510.0);
+
+    // Hitting the end stops the fling
+    velocity = FLING_MAX_VELOCITY;
+    assert_eq!(fling_scroll(4990.0, &mut velocity, 0.0, dt, 5000.0), 5000.0);
+    assert_eq!(velocity, 0.0);
+
+    // Shrunk list clamps the offset
+    assert_eq!(fling_scroll(3000.0, &mut velocity, 0.0, dt, max_scroll_offset(2, ROW_HEIGHT * 3.0)), 0.0);
+}
+
+#[test]
+fn test_scroll_deltas() {
+    let wheel = |unit, y| egui::Event::MouseWheel {
+        unit,
+        delta: egui::vec2(0.0, y),
+        modifiers: egui::Modifiers::NONE,
+    };
+
+    let events = [
+        wheel(MouseWheelUnit::Line, -2.0),
+        wheel(MouseWheelUnit::Point, 15.0),
+        egui::Event::PointerGone,
+        wheel(MouseWheelUnit::Page, 1.0),
+    ];
+
+    assert_eq!(scroll_deltas(&events, 300.0), (300.0 - WHEEL_LINE_HEIGHT * 2.0, 15.0));
+}
+
+#[test]
+fn test_is_valid_collection_name() {
+    let collections = [DbCollection {
+        id: 1,
+        name: "Farm".to_string(),
+        beatmaps: 0,
+        resolved: 0,
+    }];
+
+    assert!(is_valid_collection_name("Jumps", &collections, None));
+    assert!(!is_valid_collection_name("  ", &collections, None));
+    assert!(!is_valid_collection_name(" Farm ", &collections, None));
+
+    // Renaming to the same name is allowed
+    assert!(is_valid_collection_name("Farm", &collections, Some(1)));
+    assert!(!is_valid_collection_name("Farm", &collections, Some(2)));
+}
+
+#[test]
+fn test_preview_seek_position() {
+    let progress = PreviewProgress { position: 30.0, length: 120.0 };
+
+    assert_eq!(progress.fraction(), 0.25);
+    assert_eq!(progress.seek_position(0.5), 60.0);
+    assert_eq!(progress.seek_position(-0.2), 0.0);
+
+    // Clicking at the very end doesn't finish the preview
+    assert_eq!(progress.seek_position(1.0), 120.0 - PREVIEW_SEEK_END_MARGIN);
+    assert_eq!(progress.seek_position(1.5), 120.0 - PREVIEW_SEEK_END_MARGIN);
+
+    // Tracks shorter than the margin seek to the start
+    let short = PreviewProgress { position: 0.2, length: 0.5 };
+    assert_eq!(short.seek_position(0.9), 0.0);
+
+    let empty = PreviewProgress { position: 0.0, length: 0.0 };
+    assert_eq!(empty.fraction(), 0.0);
+}
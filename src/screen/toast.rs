@@ -0,0 +1,152 @@
+use std::{sync::mpsc::Receiver, time::{Duration, Instant}};
+
+use egui::{Align2, Color32, RichText, Stroke};
+
+use crate::notifier::{Notification, Notifier, Severity};
+
+/// How long a toast stays on the screen
+pub const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// Errors stay longer, they are usually the reason something didn't work
+pub const ERROR_TOAST_DURATION: Duration = Duration::from_secs(8);
+
+/// Max amount of toasts shown at once, oldest are dropped
+const MAX_TOASTS: usize = 5;
+
+struct Toast {
+    severity: Severity,
+    text: String,
+    shown_at: Instant,
+}
+
+impl Toast {
+    fn duration(&self) -> Duration {
+        match self.severity {
+            Severity::Error => ERROR_TOAST_DURATION,
+            Severity::Info | Severity::Warning => TOAST_DURATION,
+        }
+    }
+}
+
+fn severity_color(severity: Severity) -> Option<Color32> {
+    match severity {
+        Severity::Info => None,
+        Severity::Warning => Some(Color32::from_rgb(230, 180, 40)),
+        Severity::Error => Some(Color32::from_rgb(220, 60, 60)),
+    }
+}
+
+/// Short notifications in the bottom right corner
+/// that disappear on their own
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    /// Handed out to loader threads through [`Self::notifier`]
+    notifier: Notifier,
+    notifications: Receiver<Notification>,
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        let (notifier, notifications) = Notifier::channel();
+
+        Self {
+            toasts: Vec::new(),
+            notifier,
+            notifications,
+        }
+    }
+}
+
+impl Toasts {
+    /// Handle for pushing toasts from other threads
+    pub fn notifier(&self) -> Notifier {
+        self.notifier.clone()
+    }
+
+    pub fn push(&mut self, text: impl Into<String>) {
+        self.push_with(Severity::Info, text);
+    }
+
+    pub fn push_with(&mut self, severity: Severity, text: impl Into<String>) {
+        let text = text.into();
+
+        // Same failure repeated, e.g. scrolling over beatmaps
+        // with missing audio, restarts the old toast instead
+        if let Some(pos) = self.toasts.iter().position(|x| x.severity == severity && x.text == text) {
+            self.toasts.remove(pos);
+        }
+
+        if self.toasts.len() >= MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+
+        self.toasts.push(Toast {
+            severity,
+            text,
+            shown_at: Instant::now(),
+        });
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) {
+        let _span = tracy_client::span!("toasts::render");
+
+        while let Ok(notification) = self.notifications.try_recv() {
+            self.push_with(notification.severity, notification.text);
+        }
+
+        self.toasts.retain(|x| x.shown_at.elapsed() < x.duration());
+
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(Align2::RIGHT_BOTTOM, [-16.0, -16.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                for toast in &self.toasts {
+                    let stroke = severity_color(toast.severity)
+                        .map(|color| Stroke::new(1.5, color))
+                        .unwrap_or(Stroke::NONE);
+
+                    egui::Frame::default()
+                        .corner_radius(5.0)
+                        .inner_margin(8.0)
+                        .fill(Color32::from_black_alpha(220))
+                        .stroke(stroke)
+                        .show(ui, |ui| {
+                            ui.label(RichText::new(&toast.text).color(Color32::WHITE));
+                        });
+
+                    ui.add_space(4.0);
+                }
+            });
+
+        // Toasts are driven by time, not input
+        ctx.request_repaint();
+    }
+}
+
+#[test]
+fn test_toasts_from_notifier() {
+    let mut toasts = Toasts::default();
+    let notifier = toasts.notifier();
+
+    std::thread::spawn(move || notifier.error("audio missing"))
+        .join()
+        .unwrap();
+
+    toasts.push("imported");
+
+    while let Ok(notification) = toasts.notifications.try_recv() {
+        toasts.push_with(notification.severity, notification.text);
+    }
+
+    assert_eq!(toasts.toasts.len(), 2);
+    assert_eq!(toasts.toasts[1].severity, Severity::Error);
+    assert_eq!(toasts.toasts[1].duration(), ERROR_TOAST_DURATION);
+
+    // Repeated one is not stacked
+    toasts.push_with(Severity::Error, "audio missing");
+    assert_eq!(toasts.toasts.len(), 2);
+}
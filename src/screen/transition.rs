@@ -0,0 +1,197 @@
+use std::time::Instant;
+
+use egui::{Color32, Order, Sense};
+
+/// Duration of each half of the fade, in ms
+pub const TRANSITION_FADE_TIME: f64 = 350.0;
+
+/// Transition running longer than this is dropped, so a
+/// switch that never finishes doesn't block inputs forever, in ms
+pub const TRANSITION_TIMEOUT: f64 = 5000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    FadeOut,
+    FadeIn,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TransitionStep<T> {
+    /// Screen is fully black, switch to the next screen and call
+    /// [`Transition::fade_in`], it stays black until then
+    Switch(T),
+    /// Fade in is over, the new screen is fully visible
+    Finished,
+    /// Took longer than [`TRANSITION_TIMEOUT`] and was dropped
+    TimedOut,
+}
+
+/// Fade through black between screens. Switch happens while the
+/// screen is fully black, so the old one never flashes after it
+pub struct Transition<T> {
+    phase: Option<Phase>,
+    /// 0.0 is transparent, 1.0 is fully black
+    alpha: f64,
+    /// Handed out once the screen is black
+    pending: Option<T>,
+    /// Since the transition started, in ms
+    elapsed: f64,
+    last_update: Instant,
+}
+
+impl<T> Default for Transition<T> {
+    fn default() -> Self {
+        Self {
+            phase: None,
+            alpha: 0.0,
+            pending: None,
+            elapsed: 0.0,
+            last_update: Instant::now(),
+        }
+    }
+}
+
+impl<T> Transition<T> {
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.phase.is_some()
+    }
+
+    #[inline]
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Starts fading out, `switch` is returned once the screen is black.
+    /// Starting again mid-way continues from the current alpha
+    /// and replaces the pending switch
+    pub fn start(&mut self, switch: T) {
+        if self.phase.is_none() {
+            self.elapsed = 0.0;
+        }
+
+        self.phase = Some(Phase::FadeOut);
+        self.pending = Some(switch);
+        self.last_update = Instant::now();
+    }
+
+    /// Switch is done, time spent on it is not counted
+    /// so the fade in isn't skipped after a long load
+    pub fn fade_in(&mut self) {
+        self.phase = Some(Phase::FadeIn);
+        self.last_update = Instant::now();
+    }
+
+    /// Advances the fade by the time passed since the last update
+    pub fn update(&mut self) -> Option<TransitionStep<T>> {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_update).as_secs_f64() * 1000.0;
+        self.last_update = now;
+
+        self.advance(dt)
+    }
+
+    /// Advances the fade by `dt` ms
+    pub fn advance(&mut self, dt: f64) -> Option<TransitionStep<T>> {
+        let phase = self.phase?;
+
+        self.elapsed += dt;
+
+        if self.elapsed >= TRANSITION_TIMEOUT {
+            self.phase = None;
+            self.alpha = 0.0;
+            self.pending = None;
+
+            return Some(TransitionStep::TimedOut);
+        }
+
+        match phase {
+            Phase::FadeOut => {
+                self.alpha = (self.alpha + dt / TRANSITION_FADE_TIME).min(1.0);
+
+                if self.alpha < 1.0 {
+                    return None;
+                }
+
+                self.pending.take().map(TransitionStep::Switch)
+            },
+            Phase::FadeIn => {
+                self.alpha = (self.alpha - dt / TRANSITION_FADE_TIME).max(0.0);
+
+                if self.alpha > 0.0 {
+                    return None;
+                }
+
+                self.phase = None;
+
+                Some(TransitionStep::Finished)
+            },
+        }
+    }
+
+    /// Drawn over everything else and swallows pointer
+    /// inputs, so nothing behind is clicked mid-way
+    pub fn render(&self, ctx: &egui::Context) {
+        let _span = tracy_client::span!("transition::render");
+
+        if !self.is_active() {
+            return;
+        }
+
+        let screen = ctx.screen_rect();
+        let color = Color32::from_black_alpha((self.alpha * 255.0).round() as u8);
+
+        egui::Area::new(egui::Id::new("screen_transition"))
+            .order(Order::Tooltip)
+            .fixed_pos(screen.min)
+            .show(ctx, |ui| {
+                let (rect, _) = ui.allocate_exact_size(screen.size(), Sense::click_and_drag());
+                ui.painter().rect_filled(rect, 0.0, color);
+            });
+    }
+}
+
+#[test]
+fn test_transition_switch_at_black() {
+    let mut transition = Transition::default();
+    assert_eq!(transition.advance(100.0), None);
+
+    transition.start("playing");
+    assert!(transition.is_active());
+
+    assert_eq!(transition.advance(TRANSITION_FADE_TIME / 2.0), None);
+    assert_eq!(transition.alpha(), 0.5);
+
+    assert_eq!(transition.advance(TRANSITION_FADE_TIME), Some(TransitionStep::Switch("playing")));
+    assert_eq!(transition.alpha(), 1.0);
+
+    // Stays black until the switch is done
+    assert_eq!(transition.advance(TRANSITION_FADE_TIME), None);
+    assert_eq!(transition.alpha(), 1.0);
+
+    transition.fade_in();
+    assert_eq!(transition.advance(TRANSITION_FADE_TIME / 2.0), None);
+    assert_eq!(transition.alpha(), 0.5);
+    assert_eq!(transition.advance(TRANSITION_FADE_TIME / 2.0), Some(TransitionStep::Finished));
+    assert!(!transition.is_active());
+}
+
+#[test]
+fn test_transition_restart_and_timeout() {
+    let mut transition = Transition::default();
+
+    // Restarting during fade in goes back to black from the current alpha
+    transition.start(1);
+    assert_eq!(transition.advance(TRANSITION_FADE_TIME), Some(TransitionStep::Switch(1)));
+    transition.fade_in();
+    transition.advance(TRANSITION_FADE_TIME * 0.25);
+    assert_eq!(transition.alpha(), 0.75);
+
+    transition.start(2);
+    assert_eq!(transition.advance(TRANSITION_FADE_TIME * 0.25), Some(TransitionStep::Switch(2)));
+
+    // Switch that never finishes doesn't keep it black forever
+    assert_eq!(transition.advance(TRANSITION_TIMEOUT), Some(TransitionStep::TimedOut));
+    assert!(!transition.is_active());
+    assert_eq!(transition.alpha(), 0.0);
+}
@@ -0,0 +1,270 @@
+/// Column that `key:value` filters are applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    Artist,
+    Title,
+    Creator,
+}
+
+impl SearchField {
+    fn from_key(key: &str) -> Option<Self> {
+        match key.to_ascii_lowercase().as_str() {
+            "artist" => Some(Self::Artist),
+            "title" => Some(Self::Title),
+            "creator" | "mapper" => Some(Self::Creator),
+            _ => None,
+        }
+    }
+
+    /// Column of the `beatmaps` table
+    pub fn column(&self) -> &'static str {
+        match self {
+            Self::Artist => "artist",
+            Self::Title => "title",
+            Self::Creator => "creator",
+        }
+    }
+}
+
+/// `creator:X` matches creators containing X,
+/// quoted `creator:"X"` only the exact name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldFilter {
+    pub field: SearchField,
+    pub value: String,
+    pub exact: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    pub fn matches(&self, value: f64, than: f64) -> bool {
+        match self {
+            Self::Less => value < than,
+            Self::LessOrEqual => value <= than,
+            Self::Equal => value == than,
+            Self::GreaterOrEqual => value >= than,
+            Self::Greater => value > than,
+        }
+    }
+}
+
+/// `star>5`, `stars<=3.5`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StarFilter {
+    pub comparison: Comparison,
+    pub value: f64,
+}
+
+/// Parsed song select search box
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SearchQuery {
+    /// Every term has to be found in any of the text columns,
+    /// `"quoted phrases"` stay a single term
+    pub terms: Vec<String>,
+    pub fields: Vec<FieldFilter>,
+    /// Star ratings are not stored in the DB yet,
+    /// so these are parsed but not applied
+    pub stars: Vec<StarFilter>,
+}
+
+impl SearchQuery {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.fields.is_empty() && self.stars.is_empty()
+    }
+}
+
+/// Search box text that shows only maps of the `creator`
+pub fn creator_query(creator: &str) -> String {
+    // Quotes can't be escaped, such names are matched loosely
+    if creator.contains('"') {
+        return format!("creator:{creator}");
+    }
+
+    format!("creator:\"{creator}\"")
+}
+
+/// Splits the search box text into terms and filters.
+/// Unknown `key:value` pairs and broken comparisons are kept
+/// as plain terms, so nothing the user typed is dropped
+pub fn parse_search_query(input: &str) -> SearchQuery {
+    let _span = tracy_client::span!("search_query::parse_search_query");
+
+    let mut query = SearchQuery::default();
+
+    for (token, quoted) in tokenize(input) {
+        if quoted {
+            query.terms.push(token);
+            continue;
+        }
+
+        if let Some(stars) = parse_star_filter(&token) {
+            query.stars.push(stars);
+            continue;
+        }
+
+        let field = token.split_once(':')
+            .and_then(|(key, value)| Some((SearchField::from_key(key)?, value)));
+
+        match field {
+            Some((field, value)) if !value.is_empty() => {
+                let exact = value.len() >= 2 && value.starts_with('"') && value.ends_with('"');
+
+                query.fields.push(FieldFilter {
+                    field,
+                    value: value.trim_matches('"').to_owned(),
+                    exact,
+                });
+            },
+            _ => query.terms.push(token),
+        }
+    }
+
+    query
+}
+
+/// Whitespace separated tokens, quotes group words together both as
+/// a whole token and as a `key:"value"`. Second value is `true` for
+/// tokens that are quoted as a whole, quotes are stripped from them
+fn tokenize(input: &str) -> Vec<(String, bool)> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut token = String::new();
+
+        if c == '"' {
+            chars.next();
+
+            for c in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+
+                token.push(c);
+            }
+
+            if !token.trim().is_empty() {
+                tokens.push((token.trim().to_owned(), true));
+            }
+
+            continue;
+        }
+
+        let mut in_quotes = false;
+
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() && !in_quotes {
+                break;
+            }
+
+            if c == '"' {
+                in_quotes = !in_quotes;
+            }
+
+            token.push(c);
+            chars.next();
+        }
+
+        // Unclosed quote still closes the value
+        if in_quotes {
+            token.push('"');
+        }
+
+        tokens.push((token, false));
+    }
+
+    tokens
+}
+
+fn parse_star_filter(token: &str) -> Option<StarFilter> {
+    let lower = token.to_ascii_lowercase();
+
+    let rest = lower.strip_prefix("stars")
+        .or_else(|| lower.strip_prefix("star"))?;
+
+    let (comparison, value) = if let Some(value) = rest.strip_prefix(">=") {
+        (Comparison::GreaterOrEqual, value)
+    } else if let Some(value) = rest.strip_prefix("<=") {
+        (Comparison::LessOrEqual, value)
+    } else if let Some(value) = rest.strip_prefix('>') {
+        (Comparison::Greater, value)
+    } else if let Some(value) = rest.strip_prefix('<') {
+        (Comparison::Less, value)
+    } else if let Some(value) = rest.strip_prefix('=').or_else(|| rest.strip_prefix(':')) {
+        (Comparison::Equal, value)
+    } else {
+        return None;
+    };
+
+    let value: f64 = value.parse().ok()?;
+
+    value.is_finite().then_some(StarFilter { comparison, value })
+}
+
+#[test]
+fn test_parse_search_query_terms() {
+    let query = parse_search_query("  camellia   \"ghost rule\" extra ");
+
+    assert_eq!(query.terms, ["camellia", "ghost rule", "extra"]);
+    assert!(query.fields.is_empty());
+    assert!(parse_search_query("   ").is_empty());
+}
+
+#[test]
+fn test_parse_search_query_fields() {
+    let query = parse_search_query("creator:\"Some Mapper\" artist:xi TITLE:freedom");
+
+    assert_eq!(query.fields, [
+        FieldFilter { field: SearchField::Creator, value: "Some Mapper".into(), exact: true },
+        FieldFilter { field: SearchField::Artist, value: "xi".into(), exact: false },
+        FieldFilter { field: SearchField::Title, value: "freedom".into(), exact: false },
+    ]);
+    assert!(query.terms.is_empty());
+
+    // Unknown keys, empty values and unclosed quotes
+    let query = parse_search_query("bpm:200 creator: title:\"open end");
+    assert_eq!(query.terms, ["bpm:200", "creator:"]);
+    assert_eq!(query.fields[0].value, "open end");
+    assert!(query.fields[0].exact);
+}
+
+#[test]
+fn test_parse_search_query_stars() {
+    let query = parse_search_query("star>5.5 stars<=7 star=6 star>abc");
+
+    assert_eq!(query.stars, [
+        StarFilter { comparison: Comparison::Greater, value: 5.5 },
+        StarFilter { comparison: Comparison::LessOrEqual, value: 7.0 },
+        StarFilter { comparison: Comparison::Equal, value: 6.0 },
+    ]);
+    assert_eq!(query.terms, ["star>abc"]);
+
+    assert!(query.stars[0].comparison.matches(6.0, 5.5));
+    assert!(!query.stars[1].comparison.matches(7.1, 7.0));
+}
+
+#[test]
+fn test_creator_query_roundtrip() {
+    for creator in ["Sotarks", "Some Mapper", "a\"b"] {
+        let query = parse_search_query(&creator_query(creator));
+
+        assert_eq!(query.fields.len(), 1, "{creator}");
+        assert_eq!(query.fields[0].field, SearchField::Creator);
+    }
+
+    assert_eq!(parse_search_query(&creator_query("Some Mapper")).fields[0].value, "Some Mapper");
+}
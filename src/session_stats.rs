@@ -0,0 +1,135 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::score::Score;
+
+/// Single play as it's written to the DB, plays are
+/// attributed to the day they were started at
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PlayRecord {
+    /// Unix seconds
+    pub started_at: i64,
+    pub play_time_ms: u64,
+    /// Reached the results screen
+    pub completed: bool,
+    /// Started with the retry button
+    pub retry: bool,
+    pub x300: u32,
+    pub x100: u32,
+    pub x50: u32,
+    pub miss: u32,
+}
+
+/// Aggregated plays of a single day
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DayStats {
+    pub plays_started: u32,
+    pub plays_completed: u32,
+    pub retries: u32,
+    pub play_time_ms: u64,
+    pub x300: u32,
+    pub x100: u32,
+    pub x50: u32,
+    pub miss: u32,
+}
+
+impl DayStats {
+    #[inline]
+    pub fn judgements(&self) -> u32 {
+        self.x300 + self.x100 + self.x50 + self.miss
+    }
+}
+
+struct ActivePlay {
+    started_at: i64,
+    started: Instant,
+    retry: bool,
+}
+
+/// Follows gameplay state transitions and turns
+/// every started play into a [`PlayRecord`]
+#[derive(Default)]
+pub struct SessionTracker {
+    active: Option<ActivePlay>,
+}
+
+impl SessionTracker {
+    /// Previous play is still going if it was left without `finish`,
+    /// its record is returned as an unfinished one
+    pub fn start(&mut self, retry: bool, score: &Score) -> Option<PlayRecord> {
+        let previous = self.finish(false, score);
+
+        self.active = Some(ActivePlay {
+            started_at: unix_now(),
+            started: Instant::now(),
+            retry,
+        });
+
+        previous
+    }
+
+    /// `None` if nothing is being played
+    pub fn finish(&mut self, completed: bool, score: &Score) -> Option<PlayRecord> {
+        let play = self.active.take()?;
+
+        Some(PlayRecord {
+            started_at: play.started_at,
+            play_time_ms: play.started.elapsed().as_millis() as u64,
+            completed,
+            retry: play.retry,
+            x300: score.x300,
+            x100: score.x100,
+            x50: score.x50,
+            miss: score.miss,
+        })
+    }
+
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.active.is_some()
+    }
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// `1h 05m`, or just minutes for shorter sessions
+pub fn format_play_time(ms: u64) -> String {
+    let minutes = ms / 60_000;
+
+    match minutes / 60 {
+        0 => format!("{minutes}m"),
+        hours => format!("{hours}h {:02}m", minutes % 60),
+    }
+}
+
+#[test]
+fn test_session_tracker() {
+    let mut tracker = SessionTracker::default();
+    let mut score = Score::default();
+
+    assert!(tracker.finish(true, &score).is_none());
+    assert!(tracker.start(false, &score).is_none());
+
+    score.x300 = 10;
+    score.miss = 1;
+
+    // Retry in the middle of the play leaves it unfinished
+    let first = tracker.start(true, &score).unwrap();
+    assert!(!first.completed && !first.retry);
+    assert_eq!((first.x300, first.miss), (10, 1));
+
+    let second = tracker.finish(true, &Score::default()).unwrap();
+    assert!(second.completed && second.retry);
+    assert!(!tracker.is_playing());
+}
+
+#[test]
+fn test_format_play_time() {
+    assert_eq!(format_play_time(59_000), "0m");
+    assert_eq!(format_play_time(5 * 60_000), "5m");
+    assert_eq!(format_play_time(65 * 60_000), "1h 05m");
+}
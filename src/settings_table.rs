@@ -0,0 +1,665 @@
+use std::ops::RangeInclusive;
+
+use crate::{adaptive_quality::AdaptiveQualityMode, color_preset::ColorPreset, config::{Config, DIFFICULTY_RANGE, PLAYFIELD_OFFSET_RANGE, PLAYFIELD_ROTATION_RANGE, PLAYFIELD_SCALE_RANGE, RIPPLE_SCALE_RANGE, VOLUME_RANGE}, i18n::{t, Lang}};
+
+/// Collapsible sections of the settings screen in the order they are shown.
+/// Some of them only have hand-made rows, like the skin picker or stable import
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SettingsSection {
+    General,
+    Renderer,
+    Gameplay,
+    Cursor,
+    Audio,
+    Skin,
+    Hud,
+    Replays,
+    File,
+    StableImport,
+    Session,
+    Diagnostics,
+}
+
+impl SettingsSection {
+    pub const ALL: [SettingsSection; 12] = [
+        SettingsSection::General,
+        SettingsSection::Renderer,
+        SettingsSection::Gameplay,
+        SettingsSection::Cursor,
+        SettingsSection::Audio,
+        SettingsSection::Skin,
+        SettingsSection::Hud,
+        SettingsSection::Replays,
+        SettingsSection::File,
+        SettingsSection::StableImport,
+        SettingsSection::Session,
+        SettingsSection::Diagnostics,
+    ];
+
+    /// i18n key of the section heading
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            SettingsSection::General => "settings.general",
+            SettingsSection::Renderer => "settings.renderer",
+            SettingsSection::Gameplay => "settings.gameplay",
+            SettingsSection::Cursor => "settings.cursor",
+            SettingsSection::Audio => "settings.audio",
+            SettingsSection::Skin => "settings.skin",
+            SettingsSection::Hud => "settings.hud",
+            SettingsSection::Replays => "settings.replays",
+            SettingsSection::File => "settings.file",
+            SettingsSection::StableImport => "settings.stable_import",
+            SettingsSection::Session => "settings.session",
+            SettingsSection::Diagnostics => "settings.diagnostics",
+        }
+    }
+}
+
+/// What has to happen besides writing the config
+/// once the setting is changed from the UI
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SettingEffect {
+    None,
+    /// Stored slider textures are baked again
+    RebakeSliders,
+    /// Song select recalculates shown difficulty
+    DifficultyOverrides,
+    CursorSize,
+    /// Playfield is laid out again for input and rendering
+    PlayfieldLayout,
+    /// Applied and saved right away, unlike other settings
+    Language,
+}
+
+/// How the setting is shown and where its value lives in the [`Config`]
+pub enum SettingWidget {
+    Checkbox(fn(&mut Config) -> &mut bool),
+    Slider {
+        value: fn(&mut Config) -> &mut f32,
+        range: RangeInclusive<f32>,
+        suffix: &'static str,
+    },
+    IntSlider {
+        value: fn(&mut Config) -> &mut u32,
+        range: RangeInclusive<u32>,
+    },
+    /// Checkbox enabling a slider, `None` when disabled
+    OptionalSlider {
+        value: fn(&mut Config) -> &mut Option<f32>,
+        range: RangeInclusive<f32>,
+        default: f32,
+    },
+    /// One of `options` values, picked by their index
+    Choice {
+        options: usize,
+        selected: fn(&Config) -> usize,
+        select: fn(&mut Config, usize),
+        /// Already translated name of the option
+        label: fn(usize) -> &'static str,
+    },
+}
+
+pub struct SettingDescriptor {
+    /// Stable id, doesn't change with the label
+    pub id: &'static str,
+    /// i18n key of the label
+    pub label: &'static str,
+    pub section: SettingsSection,
+    /// i18n key of the heading the row is shown under, if any
+    pub group: Option<&'static str>,
+    pub widget: SettingWidget,
+    /// Row is greyed out when it returns `false`
+    pub enabled: Option<fn(&Config) -> bool>,
+    pub effect: SettingEffect,
+}
+
+impl SettingDescriptor {
+    #[inline]
+    pub fn is_enabled(&self, config: &Config) -> bool {
+        self.enabled.is_none_or(|x| x(config))
+    }
+
+    /// Lowercased `query` is found in the label or in the section name
+    pub fn matches(&self, query: &str) -> bool {
+        query.is_empty()
+            || t(self.label).to_lowercase().contains(query)
+            || t(self.section.name_key()).to_lowercase().contains(query)
+    }
+}
+
+fn index_of<T: PartialEq>(all: &[T], value: &T) -> usize {
+    all.iter().position(|x| x == value).unwrap_or(0)
+}
+
+/// Every setting backed by the [`Config`], in the order they are shown
+pub static SETTINGS: &[SettingDescriptor] = &[
+    SettingDescriptor {
+        id: "general.language",
+        label: "settings.language",
+        section: SettingsSection::General,
+        group: None,
+        widget: SettingWidget::Choice {
+            options: Lang::ALL.len(),
+            selected: |c| index_of(&Lang::ALL, &c.lang),
+            select: |c, i| c.lang = Lang::ALL[i],
+            label: |i| Lang::ALL[i].name(),
+        },
+        enabled: None,
+        effect: SettingEffect::Language,
+    },
+    SettingDescriptor {
+        id: "renderer.store_slider_textures",
+        label: "settings.renderer.store_slider_textures",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.slider"),
+        widget: SettingWidget::Checkbox(|c| &mut c.store_slider_textures),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "renderer.bake_ahead_ms",
+        label: "settings.renderer.bake_ahead_ms",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.slider"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.bake_ahead_ms,
+            range: 0.0..=2000.0,
+            suffix: "",
+        },
+        enabled: Some(|c| c.store_slider_textures),
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "renderer.bake_ahead_per_frame",
+        label: "settings.renderer.bake_ahead_per_frame",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.slider"),
+        widget: SettingWidget::IntSlider {
+            value: |c| &mut c.bake_ahead_per_frame,
+            range: 0..=16,
+        },
+        enabled: Some(|c| c.store_slider_textures),
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "renderer.adaptive_quality",
+        label: "settings.renderer.adaptive_quality",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.slider"),
+        widget: SettingWidget::Choice {
+            options: AdaptiveQualityMode::ALL.len(),
+            selected: |c| index_of(&AdaptiveQualityMode::ALL, &c.adaptive_quality),
+            select: |c, i| c.adaptive_quality = AdaptiveQualityMode::ALL[i],
+            label: |i| t(AdaptiveQualityMode::ALL[i].name_key()),
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "renderer.border_feather",
+        label: "settings.renderer.border_feather",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.slider"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.slider.border_feather,
+            range: 0.0..=2.0,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::RebakeSliders,
+    },
+    SettingDescriptor {
+        id: "renderer.border_size",
+        label: "settings.renderer.border_size",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.slider"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.slider.border_size_multiplier,
+            range: 0.0..=2.0,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::RebakeSliders,
+    },
+    // Body settings are applied on the next frame without re-baking
+    SettingDescriptor {
+        id: "renderer.body_saturation",
+        label: "settings.renderer.body_saturation",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.slider"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.slider.body_color_saturation,
+            range: 0.0..=2.0,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "renderer.body_alpha",
+        label: "settings.renderer.body_alpha",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.slider"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.slider.body_alpha_multiplier,
+            range: 0.0..=2.0,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "renderer.fade_in",
+        label: "settings.renderer.fade_in",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.judgements"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.judgements.fade_in_ms,
+            range: 0.0..=1000.0,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "renderer.stay_on_screen",
+        label: "settings.renderer.stay_on_screen",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.judgements"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.judgements.stay_on_screen_ms,
+            range: 0.0..=1000.0,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "renderer.fade_out",
+        label: "settings.renderer.fade_out",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.judgements"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.judgements.fade_out_ms,
+            range: 0.0..=1000.0,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "renderer.high_contrast",
+        label: "settings.renderer.high_contrast",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.judgements"),
+        widget: SettingWidget::Checkbox(|c| &mut c.judgements.high_contrast),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "renderer.hit_position_nudge",
+        label: "settings.renderer.hit_position_nudge",
+        section: SettingsSection::Renderer,
+        group: Some("settings.renderer.judgements"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.judgements.hit_position_nudge,
+            range: 0.0..=1.0,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "gameplay.no_fail",
+        label: "settings.gameplay.no_fail",
+        section: SettingsSection::Gameplay,
+        group: None,
+        widget: SettingWidget::Checkbox(|c| &mut c.rules.no_fail),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "gameplay.relax",
+        label: "settings.gameplay.relax",
+        section: SettingsSection::Gameplay,
+        group: None,
+        widget: SettingWidget::Checkbox(|c| &mut c.rules.relax),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "gameplay.cs",
+        label: "settings.gameplay.cs",
+        section: SettingsSection::Gameplay,
+        group: Some("settings.gameplay.difficulty"),
+        widget: SettingWidget::OptionalSlider {
+            value: |c| &mut c.rules.difficulty.cs,
+            range: DIFFICULTY_RANGE,
+            default: 5.0,
+        },
+        enabled: None,
+        effect: SettingEffect::DifficultyOverrides,
+    },
+    SettingDescriptor {
+        id: "gameplay.ar",
+        label: "settings.gameplay.ar",
+        section: SettingsSection::Gameplay,
+        group: Some("settings.gameplay.difficulty"),
+        widget: SettingWidget::OptionalSlider {
+            value: |c| &mut c.rules.difficulty.ar,
+            range: DIFFICULTY_RANGE,
+            default: 5.0,
+        },
+        enabled: None,
+        effect: SettingEffect::DifficultyOverrides,
+    },
+    SettingDescriptor {
+        id: "gameplay.od",
+        label: "settings.gameplay.od",
+        section: SettingsSection::Gameplay,
+        group: Some("settings.gameplay.difficulty"),
+        widget: SettingWidget::OptionalSlider {
+            value: |c| &mut c.rules.difficulty.od,
+            range: DIFFICULTY_RANGE,
+            default: 5.0,
+        },
+        enabled: None,
+        effect: SettingEffect::DifficultyOverrides,
+    },
+    SettingDescriptor {
+        id: "gameplay.hp",
+        label: "settings.gameplay.hp",
+        section: SettingsSection::Gameplay,
+        group: Some("settings.gameplay.difficulty"),
+        widget: SettingWidget::OptionalSlider {
+            value: |c| &mut c.rules.difficulty.hp,
+            range: DIFFICULTY_RANGE,
+            default: 5.0,
+        },
+        enabled: None,
+        effect: SettingEffect::DifficultyOverrides,
+    },
+    SettingDescriptor {
+        id: "gameplay.hard_rock",
+        label: "settings.gameplay.hard_rock",
+        section: SettingsSection::Gameplay,
+        group: Some("settings.gameplay.difficulty"),
+        widget: SettingWidget::Checkbox(|c| &mut c.rules.hard_rock),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "gameplay.hidden",
+        label: "settings.gameplay.hidden",
+        section: SettingsSection::Gameplay,
+        group: Some("settings.gameplay.visuals"),
+        widget: SettingWidget::Checkbox(|c| &mut c.hidden),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    // Applied on the next play
+    SettingDescriptor {
+        id: "gameplay.playfield_rotation",
+        label: "settings.gameplay.playfield_rotation",
+        section: SettingsSection::Gameplay,
+        group: Some("settings.gameplay.visuals"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.playfield_rotation,
+            range: PLAYFIELD_ROTATION_RANGE,
+            suffix: "°",
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "gameplay.playfield_scale",
+        label: "settings.gameplay.playfield_scale",
+        section: SettingsSection::Gameplay,
+        group: Some("settings.gameplay.visuals"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.playfield_scale,
+            range: PLAYFIELD_SCALE_RANGE,
+            suffix: "x",
+        },
+        enabled: None,
+        effect: SettingEffect::PlayfieldLayout,
+    },
+    SettingDescriptor {
+        id: "gameplay.playfield_vertical_offset",
+        label: "settings.gameplay.playfield_vertical_offset",
+        section: SettingsSection::Gameplay,
+        group: Some("settings.gameplay.visuals"),
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.playfield_vertical_offset_px,
+            range: PLAYFIELD_OFFSET_RANGE,
+            suffix: "px",
+        },
+        enabled: None,
+        effect: SettingEffect::PlayfieldLayout,
+    },
+    SettingDescriptor {
+        id: "cursor.size",
+        label: "settings.cursor.size",
+        section: SettingsSection::Cursor,
+        group: None,
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.cursor.size,
+            range: 1.0..=10.0,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::CursorSize,
+    },
+    SettingDescriptor {
+        id: "cursor.ripples",
+        label: "settings.cursor.ripples",
+        section: SettingsSection::Cursor,
+        group: None,
+        widget: SettingWidget::Checkbox(|c| &mut c.cursor.ripples),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "cursor.ripple_scale",
+        label: "settings.cursor.ripple_scale",
+        section: SettingsSection::Cursor,
+        group: None,
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.cursor.ripple_scale,
+            range: RIPPLE_SCALE_RANGE,
+            suffix: "x",
+        },
+        enabled: Some(|c| c.cursor.ripples),
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "audio.master_volume",
+        label: "settings.audio.master_volume",
+        section: SettingsSection::Audio,
+        group: None,
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.volume.master,
+            range: VOLUME_RANGE,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "audio.music_volume",
+        label: "settings.audio.music_volume",
+        section: SettingsSection::Audio,
+        group: None,
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.volume.music,
+            range: VOLUME_RANGE,
+            suffix: "",
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "audio.effects",
+        label: "settings.audio.effects",
+        section: SettingsSection::Audio,
+        group: None,
+        widget: SettingWidget::Checkbox(|c| &mut c.audio_effects),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "audio.pause_volume",
+        label: "settings.audio.pause_volume",
+        section: SettingsSection::Audio,
+        group: None,
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.volume.pause,
+            range: VOLUME_RANGE,
+            suffix: "",
+        },
+        enabled: Some(|c| c.audio_effects),
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "audio.fail_volume",
+        label: "settings.audio.fail_volume",
+        section: SettingsSection::Audio,
+        group: None,
+        widget: SettingWidget::Slider {
+            value: |c| &mut c.volume.fail,
+            range: VOLUME_RANGE,
+            suffix: "",
+        },
+        enabled: Some(|c| c.audio_effects),
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "skin.color_preset",
+        label: "settings.skin.color_preset",
+        section: SettingsSection::Skin,
+        group: None,
+        widget: SettingWidget::Choice {
+            options: ColorPreset::ALL.len(),
+            selected: |c| index_of(&ColorPreset::ALL, &c.color_preset),
+            select: |c, i| c.color_preset = ColorPreset::ALL[i],
+            label: |i| t(ColorPreset::ALL[i].name_key()),
+        },
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "skin.ignore_beatmap_skin",
+        label: "settings.skin.ignore_beatmap_skin",
+        section: SettingsSection::Skin,
+        group: None,
+        widget: SettingWidget::Checkbox(|c| &mut c.ignore_beatmap_skin),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "hud.edit",
+        label: "settings.hud.edit",
+        section: SettingsSection::Hud,
+        group: None,
+        widget: SettingWidget::Checkbox(|c| &mut c.hud_edit),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+    SettingDescriptor {
+        id: "replays.always_save",
+        label: "settings.replays.always_save",
+        section: SettingsSection::Replays,
+        group: None,
+        widget: SettingWidget::Checkbox(|c| &mut c.always_save_replays),
+        enabled: None,
+        effect: SettingEffect::None,
+    },
+];
+
+/// Settings of the `section` matching the lowercased `query`
+pub fn section_settings(section: SettingsSection, query: &str) -> impl Iterator<Item = &'static SettingDescriptor> + '_ {
+    SETTINGS.iter().filter(move |x| x.section == section && x.matches(query))
+}
+
+#[test]
+fn test_settings_table_ids() {
+    let mut ids: Vec<_> = SETTINGS.iter().map(|x| x.id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    assert_eq!(ids.len(), SETTINGS.len());
+
+    // Rows of a section are next to each other, so groups are shown once
+    for section in SettingsSection::ALL {
+        let first = SETTINGS.iter().position(|x| x.section == section);
+        let last = SETTINGS.iter().rposition(|x| x.section == section);
+
+        if let (Some(first), Some(last)) = (first, last) {
+            assert!(SETTINGS[first..=last].iter().all(|x| x.section == section), "{section:?}");
+        }
+    }
+}
+
+#[test]
+fn test_settings_table_toggles() {
+    let default = Config::default();
+
+    // Every boolean setting is bound to its own field
+    for setting in SETTINGS {
+        let SettingWidget::Checkbox(value) = &setting.widget else {
+            continue;
+        };
+
+        let mut config = default.clone();
+        let before = *value(&mut config);
+        *value(&mut config) = !before;
+
+        assert_ne!(config, default, "{} changed nothing", setting.id);
+
+        for other in SETTINGS.iter().filter(|x| x.id != setting.id) {
+            if let SettingWidget::Checkbox(other_value) = &other.widget {
+                assert_eq!(other_value(&mut config), other_value(&mut default.clone()), "{} changed {}", setting.id, other.id);
+            }
+        }
+
+        *value(&mut config) = before;
+        assert_eq!(config, default);
+    }
+
+    // Choices round trip through their indices
+    for setting in SETTINGS {
+        let SettingWidget::Choice { options, selected, select, .. } = &setting.widget else {
+            continue;
+        };
+
+        let mut config = default.clone();
+
+        for i in 0..*options {
+            select(&mut config, i);
+            assert_eq!(selected(&config), i, "{}", setting.id);
+        }
+    }
+}
+
+#[test]
+fn test_settings_search() {
+    let found: Vec<_> = SETTINGS.iter()
+        .filter(|x| x.matches("slider"))
+        .map(|x| x.id)
+        .collect();
+
+    assert_eq!(found, [
+        "renderer.store_slider_textures",
+        "renderer.bake_ahead_ms",
+        "renderer.bake_ahead_per_frame",
+        "renderer.border_feather",
+        "renderer.border_size",
+        "renderer.body_saturation",
+        "renderer.body_alpha",
+    ]);
+
+    // Section name shows the whole section
+    assert_eq!(section_settings(SettingsSection::Audio, "audio").count(), 5);
+    assert_eq!(section_settings(SettingsSection::Audio, "").count(), 5);
+    assert_eq!(section_settings(SettingsSection::Cursor, "hidden").count(), 0);
+}
@@ -17,6 +17,7 @@ struct InstanceInput {
 	@location(3) color: vec3<f32>,
 	@location(4) alpha: f32,
 	@location(5) scale: f32,
+	@location(6) rotation: f32,
 }
 
 struct VertexOutput {
@@ -25,6 +26,15 @@ struct VertexOutput {
 	@location(1) alpha: f32,
 };
 
+// Rotates vertex of a centered quad, direction is
+// the same as UV rotation of `quad.wgsl`
+fn rotate(pos: vec2<f32>, degree: f32) -> vec2<f32> {
+	let c = cos(radians(degree));
+	let s = sin(radians(degree));
+
+	return vec2<f32>(c * pos.x + s * pos.y, -s * pos.x + c * pos.y);
+}
+
 @vertex
 fn vs_main(
 	model: VertexInput,
@@ -34,10 +44,12 @@ fn vs_main(
 	out.uv = model.uv;
 	out.alpha = instance.alpha;
 
+	let pos = rotate(model.pos, instance.rotation);
+
     out.clip_position = camera.proj * camera.view
 		* vec4<f32>(
-			model.pos.x + instance.pos.x, 
-			model.pos.y + instance.pos.y, 
+			pos.x + instance.pos.x, 
+			pos.y + instance.pos.y, 
 			instance.pos.z + 0.0, 
 			1.0
 		);
@@ -11,12 +11,14 @@ struct VertexInput {
 	@location(0) pos: vec2<f32>,
 	@location(1) uv: vec2<f32>,
 	@location(2) alpha: f32,
+	@location(3) tint: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
 	@location(0) uv: vec2<f32>,
-	@location(1) alpha: f32
+	@location(1) alpha: f32,
+	@location(2) tint: vec4<f32>,
 };
 
 @vertex
@@ -26,6 +28,7 @@ fn vs_main(
     var out: VertexOutput;
 	out.uv = model.uv;
 	out.alpha = model.alpha;
+	out.tint = model.tint;
 
     out.clip_position = camera.proj * camera.view
 		* vec4<f32>(
@@ -48,6 +51,8 @@ var hitrcirle_texture_sampler: sampler;
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 	var hc = textureSample(hitcircle_texture, hitrcirle_texture_sampler, in.uv);
 	hc.w = hc.w * in.alpha;
+	// Tinted quads keep only the shape of the image
+	hc = vec4<f32>(mix(hc.xyz, in.tint.xyz, in.tint.w), hc.w);
 
 	return hc;
 }
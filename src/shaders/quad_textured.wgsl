@@ -16,7 +16,8 @@ struct InstanceInput {
 	@location(2) pos: vec3<f32>,
 	@location(3) color: vec3<f32>,
 	@location(4) alpha: f32,
-	@location(5) scale: f32
+	@location(5) scale: f32,
+	@location(6) rotation: f32,
 }
 
 struct VertexOutput {
@@ -26,6 +27,15 @@ struct VertexOutput {
 	@location(2) alpha: f32,
 };
 
+// Rotates vertex of a centered quad, direction is
+// the same as UV rotation of `quad.wgsl`
+fn rotate(pos: vec2<f32>, degree: f32) -> vec2<f32> {
+	let c = cos(radians(degree));
+	let s = sin(radians(degree));
+
+	return vec2<f32>(c * pos.x + s * pos.y, -s * pos.x + c * pos.y);
+}
+
 @vertex
 fn vs_main(
 	model: VertexInput,
@@ -44,7 +54,7 @@ fn vs_main(
 		instance.pos.x, instance.pos.y, instance.pos.z, 1.0,
 	);
 
-	let scaled_pos = vec4<f32>(instance.scale, instance.scale, 0.0, 1.0) * vec4<f32>(model.pos, 0.0, 1.0);
+	let scaled_pos = vec4<f32>(instance.scale, instance.scale, 0.0, 1.0) * vec4<f32>(rotate(model.pos, instance.rotation), 0.0, 1.0);
 
     out.clip_position = camera.proj * camera.view
 		* model_matrix
@@ -69,3 +79,13 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
 	return hc;
 }
+
+// Same as `fs_main` without the colour tint, hit circle overlays
+// and combo numbers keep their own colours
+@fragment
+fn fs_untinted(in: VertexOutput) -> @location(0) vec4<f32> {
+	var hc = textureSample(hitcircle_texture, hitrcirle_texture_sampler, in.uv);
+	hc.a = hc.a * in.alpha;
+
+	return hc;
+}
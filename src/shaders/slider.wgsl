@@ -63,32 +63,19 @@ var<uniform> slider_settings: SliderSettingsUniform;
 const DEFAULT_TRANSITION_SIZE: f32 = 0.011;
 const DEFAULT_BORDER_SIZE: f32 = 0.11;
 const OUTER_SHADOW_SIZE: f32 = 0.08;
-
-fn get_inner_body_color(body_color: vec4<f32>) -> vec4<f32> {
-// TODO redo
-	let brightness_multiplier = 0.25;
-
-	var b = vec4<f32>(body_color);
-	b.r = min(1.0, body_color.r * (1.0 + 0.5 * brightness_multiplier) + brightness_multiplier);
-	b.g = min(1.0, body_color.g * (1.0 + 0.5 * brightness_multiplier) + brightness_multiplier);
-	b.b = min(1.0, body_color.b * (1.0 + 0.5 * brightness_multiplier) + brightness_multiplier);
-	return b;
-}
-
-
-fn get_outer_body_color(body_color: vec4<f32>) -> vec4<f32> {
-	let darkness_multiplier = 0.1;
-	var b = vec4<f32>(body_color);
-
-	b.r = min(1.0, body_color.r / (1.0 + darkness_multiplier));
-	b.g = min(1.0, body_color.g / (1.0 + darkness_multiplier));
-	b.b = min(1.0, body_color.b / (1.0 + darkness_multiplier));
-	return b;
-}
-
+const OUTER_SHADOW_ALPHA: f32 = 0.25;
+
+// Texture stores how the slider should be tinted instead of colors,
+// so body color and alpha are applied in slider_to_screen.wgsl
+// without re-baking:
+// r - body weight, 0 is border and 1 is body
+// g - gradient from outer to inner body color
+// a - alpha of the shadow and border
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-	var out_color = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+	var body_weight = 0.0;
+	var body_gradient = 0.0;
+	var base_alpha = 0.0;
 
 	let border_size_multiplier = slider_settings.border_size_multiplier;
 
@@ -96,51 +83,30 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
 	let transition_size = DEFAULT_TRANSITION_SIZE;
 
-	var border_color = vec4<f32>(in.slider_border, 1.0);
-	let outer_shadow_color = vec4<f32>(0.0, 0.0, 0.0, 0.25);
-
-	let bodyColor = vec4<f32>(in.slider_body, 0.7 * slider_settings.body_alpha_multiplier);
-
-	var inner_body_color = get_inner_body_color(bodyColor);
-	var outer_body_color = get_outer_body_color(bodyColor);
-
-	inner_body_color.r *= slider_settings.body_color_saturation;
-	inner_body_color.g *= slider_settings.body_color_saturation;
-	inner_body_color.b *= slider_settings.body_color_saturation;
-
-	outer_body_color.r *= slider_settings.body_color_saturation;
-	outer_body_color.g *= slider_settings.body_color_saturation;
-	outer_body_color.b *= slider_settings.body_color_saturation;
-
-	if (in.uv.x < OUTER_SHADOW_SIZE - transition_size) {
-		let delta: f32 = in.uv.x / (OUTER_SHADOW_SIZE - transition_size);
-		//out_color = mix(vec4<f32>(0.0, 0.0, 0.0, 0.0), outer_shadow_color, delta);
-	}
-
 	if (in.uv.x > OUTER_SHADOW_SIZE - transition_size && in.uv.x < OUTER_SHADOW_SIZE + transition_size) {
 		let delta: f32 = (in.uv.x - OUTER_SHADOW_SIZE + transition_size) / (2.0*transition_size);
-		out_color = mix(outer_shadow_color, border_color, delta);
+		base_alpha = mix(OUTER_SHADOW_ALPHA, 1.0, delta);
 	}
 
 	if (in.uv.x > OUTER_SHADOW_SIZE + transition_size && in.uv.x < OUTER_SHADOW_SIZE + border_size - transition_size) {
-		out_color = border_color;
+		base_alpha = 1.0;
 	}
 
 	if (in.uv.x > OUTER_SHADOW_SIZE + border_size - transition_size && in.uv.x < OUTER_SHADOW_SIZE + border_size + transition_size)
 	{
 		let delta = (in.uv.x - OUTER_SHADOW_SIZE - border_size + transition_size) / (2.0*transition_size);
-		out_color = mix(border_color, outer_body_color, delta);
+		base_alpha = 1.0;
+		body_weight = delta;
 	}
 
 	if (in.uv.x > OUTER_SHADOW_SIZE + border_size + transition_size) // outer body + inner body
 	{	
 		let size = OUTER_SHADOW_SIZE + border_size + transition_size;
 		let delta = ((in.uv.x - size) / (1.0-size));
-		out_color = mix(outer_body_color, inner_body_color, delta);
+		base_alpha = 1.0;
+		body_weight = 1.0;
+		body_gradient = delta;
 	}
 
-	//return vec4<f32>(1.0, 1.0, 1.0, 1.0);
-
-	return out_color;
+	return vec4<f32>(body_weight, body_gradient, 0.0, base_alpha);
 }
-
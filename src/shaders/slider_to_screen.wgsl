@@ -23,6 +23,8 @@ struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
 	@location(0) uv: vec2<f32>,
 	@location(1) alpha: f32,
+	@location(2) slider_border: vec3<f32>,
+	@location(3) slider_body: vec3<f32>,
 };
 
 @vertex
@@ -33,6 +35,8 @@ fn vs_main(
     var out: VertexOutput;
 	out.uv = model.uv;
 	out.alpha = instance.alpha;
+	out.slider_border = instance.slider_border;
+	out.slider_body = instance.slider_body;
 
 	out.clip_position = camera.proj * camera.view * vec4<f32>(
 		model.pos.x + instance.pos.x, 
@@ -50,9 +54,71 @@ var texture: texture_2d<f32>;
 @group(0) @binding(1)
 var texture_sampler: sampler;
 
+struct SliderSettingsUniform {
+    border_feather: f32,
+    border_size_multiplier: f32,
+    body_color_saturation: f32,
+    body_alpha_multiplier: f32
+}
+
+@group(2) @binding(0)
+var<uniform> slider_settings: SliderSettingsUniform;
+
+const OUTER_SHADOW_ALPHA: f32 = 0.25;
+
+fn get_inner_body_color(body_color: vec4<f32>) -> vec4<f32> {
+// TODO redo
+	let brightness_multiplier = 0.25;
+
+	var b = vec4<f32>(body_color);
+	b.r = min(1.0, body_color.r * (1.0 + 0.5 * brightness_multiplier) + brightness_multiplier);
+	b.g = min(1.0, body_color.g * (1.0 + 0.5 * brightness_multiplier) + brightness_multiplier);
+	b.b = min(1.0, body_color.b * (1.0 + 0.5 * brightness_multiplier) + brightness_multiplier);
+	return b;
+}
+
+
+fn get_outer_body_color(body_color: vec4<f32>) -> vec4<f32> {
+	let darkness_multiplier = 0.1;
+	var b = vec4<f32>(body_color);
+
+	b.r = min(1.0, body_color.r / (1.0 + darkness_multiplier));
+	b.g = min(1.0, body_color.g / (1.0 + darkness_multiplier));
+	b.b = min(1.0, body_color.b / (1.0 + darkness_multiplier));
+	return b;
+}
+
+// Baked texture holds tint parameters, see slider.wgsl.
+// Colors are only mixed here, so the result differs from mixing
+// before the bake by a couple LSB at most, mostly on
+// the outer shadow edge where parameters are filtered
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-	var out = textureSample(texture, texture_sampler, in.uv);
+	let params = textureSample(texture, texture_sampler, in.uv);
+	let body_weight = params.r;
+	let body_gradient = params.g;
+	let base_alpha = params.a;
+
+	// Outer shadow is black and fades into the border
+	let border_mix = clamp((base_alpha - OUTER_SHADOW_ALPHA) / (1.0 - OUTER_SHADOW_ALPHA), 0.0, 1.0);
+	let base_color = vec4<f32>(in.slider_border * border_mix, base_alpha);
+
+	let body_color = vec4<f32>(in.slider_body, 0.7 * slider_settings.body_alpha_multiplier);
+
+	var inner_body_color = get_inner_body_color(body_color);
+	var outer_body_color = get_outer_body_color(body_color);
+
+	inner_body_color.r *= slider_settings.body_color_saturation;
+	inner_body_color.g *= slider_settings.body_color_saturation;
+	inner_body_color.b *= slider_settings.body_color_saturation;
+
+	outer_body_color.r *= slider_settings.body_color_saturation;
+	outer_body_color.g *= slider_settings.body_color_saturation;
+	outer_body_color.b *= slider_settings.body_color_saturation;
+
+	let slider_body_color = mix(outer_body_color, inner_body_color, body_gradient);
+
+	var out = mix(base_color, slider_body_color, body_weight);
 	out.w = out.w * in.alpha;
 
 	return out;
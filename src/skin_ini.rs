@@ -23,12 +23,27 @@ pub struct Colours {
     pub combo_colors: Vec<Rgb>,
     pub slider_border: Rgb,
     pub slider_body: Rgb,
+    /// Slider body takes the combo colour of its slider,
+    /// stable does that unless `SliderTrackOverride` is set
+    pub slider_body_combo_color: bool,
 }
 
 #[derive(Debug)]
 pub struct General {
     pub name: String,
-    pub author: String
+    pub author: String,
+
+    /// Cursor hotspot is at the center of the texture,
+    /// otherwise at the top-left corner
+    pub cursor_centre: bool,
+    /// Cursor grows while any key is held
+    pub cursor_expand: bool,
+    /// Cursor slowly rotates
+    pub cursor_rotate: bool,
+
+    /// Hit circle overlay is drawn above the combo number,
+    /// otherwise number is on top of everything
+    pub hit_circle_overlay_above_number: bool,
 }
 
 #[derive(Debug)]
@@ -48,6 +63,15 @@ impl SkinIni {
         let general = General {
             name: name.to_owned(), 
             author: author.to_owned(),
+            cursor_centre: parse_bool(ini.get_from(Some("General"), "CursorCentre"), true),
+            cursor_expand: parse_bool(ini.get_from(Some("General"), "CursorExpand"), true),
+            cursor_rotate: parse_bool(ini.get_from(Some("General"), "CursorRotate"), true),
+            // Old skins have it misspelled and stable reads both
+            hit_circle_overlay_above_number: parse_bool(
+                ini.get_from(Some("General"), "HitCircleOverlayAboveNumber")
+                    .or(ini.get_from(Some("General"), "HitCircleOverlayAboveNumer")),
+                true,
+            ),
         };
 
 
@@ -55,11 +79,10 @@ impl SkinIni {
         let slider_border = Rgb::parse(ini.get_from(Some("Colours"), "SliderBorder").unwrap_or("255, 255, 255"))
             .unwrap();
 
-        let slider_body = Rgb::parse(ini.get_from(Some("Colours"), "SliderTrackOverride").unwrap_or("0, 0, 0"))
-            .unwrap();
-
+        let slider_track_override = ini.get_from(Some("Colours"), "SliderTrackOverride")
+            .and_then(Rgb::parse);
 
-        //SliderTrackOverride
+        let slider_body = slider_track_override.unwrap_or(Rgb::new(0, 0, 0));
 
         // TODO write some cool macro here?
         let mut colors = Vec::new();
@@ -112,6 +135,7 @@ impl SkinIni {
         let colours = Colours {
             slider_border,
             slider_body,
+            slider_body_combo_color: slider_track_override.is_none(),
             combo_colors: colors,
         };
 
@@ -126,13 +150,18 @@ impl Default for SkinIni {
     fn default() -> Self {
         let general = General {
             name: "Default".to_owned(),
-            author: "486c".to_owned()
+            author: "486c".to_owned(),
+            cursor_centre: true,
+            cursor_expand: true,
+            cursor_rotate: true,
+            hit_circle_overlay_above_number: true,
         };
 
         let colours = Colours {
             combo_colors: vec![Rgb::new(255, 255, 255)],
             slider_border: Rgb::new(255, 255, 255),
             slider_body: Rgb::new(0, 0, 0),
+            slider_body_combo_color: false,
         };
 
         Self {
@@ -141,3 +170,96 @@ impl Default for SkinIni {
         }
     }
 }
+
+/// Skin flags are stored as `0` or `1`
+fn parse_bool(value: Option<&str>, default: bool) -> bool {
+    match value.map(|x| x.trim()) {
+        Some("1") => true,
+        Some("0") => false,
+        _ => default,
+    }
+}
+
+#[test]
+fn test_skin_ini_cursor_flags() {
+    let ini = b"[General]
+Name: test
+Author: test
+CursorCentre: 0
+CursorExpand: 1
+CursorRotate: 0
+";
+
+    let skin = SkinIni::parse(ini).unwrap();
+
+    assert!(!skin.general.cursor_centre);
+    assert!(skin.general.cursor_expand);
+    assert!(!skin.general.cursor_rotate);
+
+    let ini = b"[General]
+Name: test
+Author: test
+";
+
+    let skin = SkinIni::parse(ini).unwrap();
+
+    assert!(skin.general.cursor_centre);
+    assert!(skin.general.cursor_expand);
+    assert!(skin.general.cursor_rotate);
+}
+
+#[test]
+fn test_skin_ini_overlay_above_number() {
+    let ini = b"[General]
+Name: test
+Author: test
+HitCircleOverlayAboveNumber: 0
+";
+
+    assert!(!SkinIni::parse(ini).unwrap().general.hit_circle_overlay_above_number);
+
+    let ini = b"[General]
+Name: test
+Author: test
+HitCircleOverlayAboveNumer: 0
+";
+
+    assert!(!SkinIni::parse(ini).unwrap().general.hit_circle_overlay_above_number);
+
+    let ini = b"[General]
+Name: test
+Author: test
+";
+
+    assert!(SkinIni::parse(ini).unwrap().general.hit_circle_overlay_above_number);
+    assert!(SkinIni::default().general.hit_circle_overlay_above_number);
+}
+
+#[test]
+fn test_skin_ini_slider_body_color() {
+    let ini = b"[General]
+Name: test
+Author: test
+
+[Colours]
+Combo1: 255, 0, 0
+";
+
+    let skin = SkinIni::parse(ini).unwrap();
+    assert!(skin.colours.slider_body_combo_color);
+
+    let ini = b"[General]
+Name: test
+Author: test
+
+[Colours]
+SliderTrackOverride: 10, 20, 30
+";
+
+    let skin = SkinIni::parse(ini).unwrap();
+    assert!(!skin.colours.slider_body_combo_color);
+    assert_eq!(skin.colours.slider_body.g(), 20);
+
+    // Built-in skin keeps its fixed body colour
+    assert!(!SkinIni::default().colours.slider_body_combo_color);
+}
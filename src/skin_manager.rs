@@ -1,71 +1,314 @@
-use std::path::Path;
-use crate::{graphics::Graphics, skin_ini::SkinIni, texture::{AtlasTexture, Texture}};
-use image::load_from_memory;
+use std::path::{Path, PathBuf};
+use crate::{graphics::Graphics, i18n::{t, tf}, notifier::Notifier, skin_ini::SkinIni, texture::{AtlasImage, AtlasTexture, Texture, UploadMode}};
+use image::{imageops::FilterType, load_from_memory, DynamicImage, GenericImageView, Rgba, RgbaImage};
+
+/// Default judgements are embedded so the atlas
+/// can be built even if `./skin` is missing some of them
+const DEFAULT_JUDGEMENTS: [(&str, &[u8]); 4] = [
+    ("hit300.png", include_bytes!("../skin/hit300.png")),
+    ("hit100.png", include_bytes!("../skin/hit100.png")),
+    ("hit50.png", include_bytes!("../skin/hit50.png")),
+    ("hit0.png", include_bytes!("../skin/hit0.png")),
+];
+
+/// Generated ring placed after the judgements in the atlas,
+/// drawn around 50s in high contrast mode
+pub const JUDGEMENT_RING_INDEX: u32 = DEFAULT_JUDGEMENTS.len() as u32;
+
+/// Skin that is used for elements missing from the user skin
+pub const DEFAULT_SKIN_PATH: &str = "./skin";
+
+/// Images a skin consists of, beatmap folder having any
+/// of them means that beatmap ships its own skin
+pub const SKIN_ELEMENTS: [&str; 11] = [
+    "hitcircle.png",
+    "hitcircleoverlay.png",
+    "sliderb0.png",
+    "cursor.png",
+    "cursortrail.png",
+    "hit300.png",
+    "hit100.png",
+    "hit50.png",
+    "hit0.png",
+    "sliderscorepoint.png",
+    "reversearrow.png",
+];
+
+/// Directories skin elements are looked up in
+#[derive(Debug, Clone, Copy)]
+pub struct SkinLayers<'a> {
+    /// Folder of the beatmap being played, `None`
+    /// outside of gameplay or when beatmap skins are ignored
+    pub beatmap: Option<&'a Path>,
+    pub skin: &'a Path,
+}
+
+impl<'a> SkinLayers<'a> {
+    pub fn skin(skin: &'a Path) -> Self {
+        Self { beatmap: None, skin }
+    }
+
+    /// Resolution order of every element: beatmap folder first, then
+    /// user skin. `None` means the default element has to be used
+    pub fn find(&self, name: &str, exists: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+        self.beatmap
+            .into_iter()
+            .chain(std::iter::once(self.skin))
+            .map(|dir| dir.join(name))
+            .find(|path| exists(path))
+    }
+
+    /// Same as `find`, falling back to `fallback_name` from the default skin
+    pub fn resolve(&self, name: &str, fallback_name: &str, exists: impl Fn(&Path) -> bool) -> PathBuf {
+        self.find(name, exists)
+            .unwrap_or_else(|| Path::new(DEFAULT_SKIN_PATH).join(fallback_name))
+    }
+
+    /// Same as `find`, but `@2x` variant is preferred within the same
+    /// layer, an SD beatmap element still wins over an HD skin one.
+    /// Returns the path along with its scale
+    pub fn find_hd(&self, name: &str, exists: impl Fn(&Path) -> bool) -> Option<(PathBuf, f32)> {
+        self.beatmap
+            .into_iter()
+            .chain(std::iter::once(self.skin))
+            .find_map(|dir| find_in_dir(dir, name, &exists))
+    }
+
+    /// Same as `resolve` with the `@2x` preference of `find_hd`
+    pub fn resolve_hd(&self, name: &str, fallback_name: &str, exists: impl Fn(&Path) -> bool) -> (PathBuf, f32) {
+        self.find_hd(name, &exists)
+            .or_else(|| find_in_dir(Path::new(DEFAULT_SKIN_PATH), fallback_name, &exists))
+            .unwrap_or_else(|| (Path::new(DEFAULT_SKIN_PATH).join(fallback_name), 1.0))
+    }
+}
+
+/// `hitcircle.png` => `hitcircle@2x.png`
+pub fn hd_name(name: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) => format!("{stem}@2x.{ext}"),
+        None => format!("{name}@2x"),
+    }
+}
+
+fn find_in_dir(dir: &Path, name: &str, exists: impl Fn(&Path) -> bool) -> Option<(PathBuf, f32)> {
+    let hd = dir.join(hd_name(name));
+
+    if exists(&hd) {
+        return Some((hd, 2.0));
+    }
+
+    let sd = dir.join(name);
+    exists(&sd).then_some((sd, 1.0))
+}
+
+/// Decoded skin element along with the resolution it was made for
+pub struct SkinImage {
+    pub image: DynamicImage,
+    /// 2.0 for `@2x` elements
+    pub scale: f32,
+}
+
+impl SkinImage {
+    /// Size in SD skin pixels
+    pub fn logical_size(&self) -> (f32, f32) {
+        let (width, height) = self.image.dimensions();
+
+        (width as f32 / self.scale, height as f32 / self.scale)
+    }
+}
+
+/// Loads `name` from `layers`, `fallback_name` from the default skin if it's missing
+fn load_skin_image(layers: SkinLayers, name: &str, fallback_name: &str) -> SkinImage {
+    let (path, scale) = layers.resolve_hd(name, fallback_name, |x| x.exists());
+
+    let bytes = std::fs::read(&path)
+        .unwrap_or_else(|e| panic!("Failed to load image from {}: {e}", path.display()));
+
+    SkinImage {
+        image: Texture::decode_bytes(&bytes),
+        scale,
+    }
+}
+
+/// Beatmap folder has at least one skin element
+pub fn has_skin_elements(dir: impl AsRef<Path>) -> bool {
+    SKIN_ELEMENTS.iter()
+        .any(|name| dir.as_ref().join(name).is_file())
+}
 
 macro_rules! load_or_fallback_image {
-    ($path:expr, $name: expr, $graphics:expr) => {{
-        load_or_fallback_texture!($path, $name, $name, $graphics)
+    ($layers:expr, $name: expr) => {{
+        load_or_fallback_image!($layers, $name, $name)
     }};
-    ($path:expr, $name: expr, $fallback_name: expr, $graphics:expr) => {{
-        let path = $path.as_ref().join($name);
-
-        let bytes = if path.exists() {
-            std::fs::read(path).unwrap()
-        } else {
-            let fallback_path = format!("./skin/{}", $fallback_name);
-            std::fs::read(&fallback_path)
-                .expect(&format!("Failed to load fallback image from {}", &fallback_path))
-        };
-
-        load_from_memory(&bytes).unwrap()
+    ($layers:expr, $name: expr, $fallback_name: expr) => {{
+        load_skin_image($layers, $name, $fallback_name)
     }}
 }
 
-macro_rules! load_or_fallback_texture {
-    ($path:expr, $name: expr, $graphics:expr) => {{
-        load_or_fallback_texture!($path, $name, $name, $graphics)
-    }};
-    ($path:expr, $name: expr, $fallback_name: expr, $graphics:expr) => {{
-        let path = $path.as_ref().join($name);
-
-        let bytes = if path.exists() {
-            std::fs::read(path).unwrap()
-        } else {
-            let fallback_path = format!("./skin/{}", $fallback_name);
-            std::fs::read(&fallback_path)
-                .expect(&format!("Failed to load fallback image from {}", &fallback_path))
-        };
+fn decode_default_judgement(bytes: &[u8]) -> DynamicImage {
+    load_from_memory(bytes).expect("embedded judgement image should be valid")
+}
 
-        Texture::from_bytes(&bytes, $graphics)
-    }}
+/// White ring touching the image edges. Judgements are drawn as squares,
+/// so it's built in normalized coordinates to end up round after stretching
+fn judgement_ring(width: u32, height: u32) -> DynamicImage {
+    const THICKNESS: f32 = 0.12;
+
+    // Roughly one pixel of anti-aliasing
+    let feather = 2.0 / width.min(height).max(1) as f32;
+    let middle = 1.0 - feather - THICKNESS / 2.0;
+
+    let image = RgbaImage::from_fn(width, height, |x, y| {
+        let nx = (x as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+        let ny = (y as f32 + 0.5) / height as f32 * 2.0 - 1.0;
+
+        let distance = (nx * nx + ny * ny).sqrt();
+        let coverage = (THICKNESS / 2.0 - (distance - middle).abs()) / feather + 0.5;
+
+        Rgba([255, 255, 255, (coverage.clamp(0.0, 1.0) * 255.0) as u8])
+    });
+
+    DynamicImage::ImageRgba8(image)
 }
 
-/// Handles loading a skin & skin settings from an osu skin
-/// If texture requested image is not found will fallback to the 
-/// default skin
-pub struct SkinManager {
+/// Skin's `cursor-ripple.png`, a generated ring when
+/// neither the skin nor the beatmap have one
+fn load_cursor_ripple(layers: SkinLayers) -> SkinImage {
+    const RING_SIZE: u32 = 128;
+
+    let image = layers.find_hd("cursor-ripple.png", |x| x.exists())
+        .and_then(|(path, scale)| std::fs::read(&path).ok().map(|bytes| (bytes, scale)))
+        .and_then(|(bytes, scale)| {
+            load_from_memory(&bytes)
+                .inspect_err(|e| tracing::error!("Failed to decode cursor-ripple.png: {e}"))
+                .ok()
+                .map(|image| SkinImage { image, scale })
+        });
+
+    image.unwrap_or_else(|| SkinImage {
+        image: judgement_ring(RING_SIZE, RING_SIZE),
+        scale: 1.0,
+    })
+}
+
+/// Appends the ring sized as the first judgement
+fn with_judgement_ring(mut images: Vec<DynamicImage>) -> Vec<DynamicImage> {
+    if let Some((width, height)) = images.first().map(|x| x.dimensions()) {
+        images.push(judgement_ring(width, height));
+    }
+
+    images
+}
+
+/// Atlas needs every image at the same resolution,
+/// SD ones are upscaled when some of them are `@2x`
+fn match_scales(images: Vec<SkinImage>) -> Vec<DynamicImage> {
+    let max_scale = images.iter().map(|x| x.scale).fold(1.0, f32::max);
+
+    images.into_iter()
+        .map(|x| match x.scale < max_scale {
+            true => {
+                let factor = max_scale / x.scale;
+                let (width, height) = x.image.dimensions();
+
+                x.image.resize_exact(
+                    (width as f32 * factor).round() as u32,
+                    (height as f32 * factor).round() as u32,
+                    FilterType::Lanczos3,
+                )
+            },
+            false => x.image,
+        })
+        .collect()
+}
+
+/// Builds judgements atlas from skin images, missing ones are replaced with
+/// the embedded defaults. Default set is used as a whole if skin images
+/// can't be placed in one atlas
+fn load_judgments_atlas(layers: SkinLayers, notifier: &Notifier) -> AtlasImage {
+    let _span = tracy_client::span!("skin_manager::load_judgments_atlas");
+
+    let images: Vec<_> = DEFAULT_JUDGEMENTS.iter()
+        .map(|(name, default)| {
+            let image = layers.find_hd(name, |x| x.exists())
+                .and_then(|(path, scale)| std::fs::read(path).ok().map(|bytes| (bytes, scale)))
+                .and_then(|(bytes, scale)| {
+                    load_from_memory(&bytes)
+                        .inspect_err(|e| {
+                            tracing::error!("Failed to decode {name}: {e}");
+                            notifier.warn(tf("notify.skin_element_broken", &[name]));
+                        })
+                        .ok()
+                        .map(|image| SkinImage { image, scale })
+                });
+
+            image.unwrap_or_else(|| {
+                tracing::info!("Skin is missing {name}, using default one");
+
+                SkinImage {
+                    image: decode_default_judgement(default),
+                    scale: 1.0,
+                }
+            })
+        })
+        .collect();
+
+    AtlasImage::build(&with_judgement_ring(match_scales(images))).unwrap_or_else(|e| {
+        tracing::warn!("Failed to build judgements atlas from skin images: {e}, using default judgements");
+        notifier.warn(t("notify.skin_judgements_fallback"));
+
+        let images: Vec<_> = DEFAULT_JUDGEMENTS.iter()
+            .map(|(_, default)| decode_default_judgement(default))
+            .collect();
+
+        AtlasImage::build(&with_judgement_ring(images)).expect("default judgements should be the same size")
+    })
+}
+
+/// Decoded skin images, everything that doesn't need the GPU.
+/// Built on a loader thread so switching skins doesn't stall rendering
+pub struct SkinImages {
+    /// User skin directory, beatmap skin layer is not included
+    pub path: PathBuf,
     pub ini: SkinIni,
-    pub hit_circle: Texture,
-    pub hit_circle_overlay: Texture,
-    pub sliderb0: Texture,
-    pub cursor: Texture,
-    pub cursor_trail: Texture,
-    pub judgments_atlas: AtlasTexture,
-    pub slider_tick: Texture,
-    pub slider_reverse_arrow: Texture,
+    pub hit_circle: SkinImage,
+    pub hit_circle_overlay: SkinImage,
+    pub sliderb0: SkinImage,
+    pub cursor: SkinImage,
+    pub cursor_trail: SkinImage,
+    pub cursor_ripple: SkinImage,
+    pub judgments_atlas: AtlasImage,
+    pub slider_tick: SkinImage,
+    pub slider_reverse_arrow: SkinImage,
 }
 
-impl SkinManager {
-    pub fn from_path(path: impl AsRef<Path>, graphics: &Graphics) -> Self {
-        tracing::info!("Attempt to initialize SkinManager from path: {}", &path.as_ref().display());
+impl SkinImages {
+    pub fn load(path: impl AsRef<Path>, notifier: &Notifier) -> Self {
+        Self::load_layered(SkinLayers::skin(path.as_ref()), notifier)
+    }
+
+    /// Every element is resolved through `layers`, skin.ini
+    /// always comes from the user skin. Problems that
+    /// end up in fallbacks are reported to `notifier`
+    pub fn load_layered(layers: SkinLayers, notifier: &Notifier) -> Self {
+        let _span = tracy_client::span!("skin_images::load");
+
+        let path = layers.skin;
+
+        tracing::info!("Loading skin images from path: {}", path.display());
+
+        if let Some(beatmap) = layers.beatmap {
+            tracing::info!("Using beatmap skin elements from: {}", beatmap.display());
+        }
+
         let skin_ini = {
             let path = {
-                if path.as_ref().join("skin.ini").exists() {
-                    path.as_ref().join("skin.ini")
-                } else if path.as_ref().join("Skin.ini").exists() {
-                    path.as_ref().join("Skin.ini")
+                if path.join("skin.ini").exists() {
+                    path.join("skin.ini")
+                } else if path.join("Skin.ini").exists() {
+                    path.join("Skin.ini")
                 } else {
-                    path.as_ref().join("FIXMESOMEDAYPLS.ini")
+                    path.join("FIXMESOMEDAYPLS.ini")
                 }
             };
 
@@ -76,7 +319,11 @@ impl SkinManager {
             match skin_ini_bytes {
                 Ok(bytes) => {
                     let skin_ini = SkinIni::parse(&bytes)
-                        .inspect_err(|e| tracing::error!("Failed to deserialize skin.ini: {e}")).unwrap_or(SkinIni::default());
+                        .inspect_err(|e| {
+                            tracing::error!("Failed to deserialize skin.ini: {e}");
+                            notifier.warn(t("notify.skin_ini_broken"));
+                        })
+                        .unwrap_or(SkinIni::default());
 
                     skin_ini
                 },
@@ -94,37 +341,201 @@ impl SkinManager {
         //     We SHOULD NOT fallback to the default skin
         //     because it might that skin is intentially not using overlay
         //     In that case we loading empty 1x1 image
-        let hit_circle = load_or_fallback_texture!(path, "hitcircle.png", graphics);
-        let hit_circle_overlay = load_or_fallback_texture!(path, "hitcircleoverlay.png", "empty.png", graphics);
+        let hit_circle = load_or_fallback_image!(layers, "hitcircle.png");
+        let hit_circle_overlay = load_or_fallback_image!(layers, "hitcircleoverlay.png", "empty.png");
 
-        let _approach_circle = load_or_fallback_texture!(path, "approachcircle.png", graphics);
-        let sliderb0 = load_or_fallback_texture!(path, "sliderb0.png", graphics);
-        let cursor = load_or_fallback_texture!(path, "cursor.png", graphics);
-        let cursor_trail = load_or_fallback_texture!(path, "cursortrail.png", graphics);
+        let sliderb0 = load_or_fallback_image!(layers, "sliderb0.png");
+        let cursor = load_or_fallback_image!(layers, "cursor.png");
+        let cursor_trail = load_or_fallback_image!(layers, "cursortrail.png");
+        let cursor_ripple = load_cursor_ripple(layers);
 
-        let hit_miss = load_or_fallback_image!(path, "unexistenttexture.pnng", "hit0.png", graphics);
-        let hit_300 = load_or_fallback_image!(path, "unexistenttexture.pnng", "hit300.png", graphics);
-        let hit_100 = load_or_fallback_image!(path, "unexistenttexture.pnng", "hit100.png", graphics);
-        let hit_50 = load_or_fallback_image!(path, "unexistenttexture.pnng", "hit50.png", graphics);
+        let judgments_atlas = load_judgments_atlas(layers, notifier);
 
-        let judgments_atlas = AtlasTexture::from_images(
-            graphics, 
-            &[hit_300, hit_100, hit_50, hit_miss]
-        );
-
-        let slider_tick = load_or_fallback_texture!(path, "sliderscorepoint.png", "sliderscorepoint.png", graphics);
-        let slider_reverse_arrow = load_or_fallback_texture!(path, "reversearrow.png", graphics);
+        let slider_tick = load_or_fallback_image!(layers, "sliderscorepoint.png", "sliderscorepoint.png");
+        let slider_reverse_arrow = load_or_fallback_image!(layers, "reversearrow.png");
 
         Self {
+            path: path.to_path_buf(),
             ini: skin_ini,
             hit_circle,
             hit_circle_overlay,
             sliderb0,
             cursor,
             cursor_trail,
+            cursor_ripple,
             judgments_atlas,
             slider_tick,
-            slider_reverse_arrow,        
+            slider_reverse_arrow,
+        }
+    }
+}
+
+/// Handles loading a skin & skin settings from an osu skin
+/// If texture requested image is not found will fallback to the 
+/// default skin
+pub struct SkinManager {
+    /// User skin directory, beatmap skin layer is not included
+    pub path: PathBuf,
+    pub ini: SkinIni,
+    pub hit_circle: Texture,
+    pub hit_circle_overlay: Texture,
+    pub sliderb0: Texture,
+    pub cursor: Texture,
+    pub cursor_trail: Texture,
+    pub cursor_ripple: Texture,
+    pub judgments_atlas: AtlasTexture,
+    pub slider_tick: Texture,
+    pub slider_reverse_arrow: Texture,
+}
+
+impl SkinManager {
+    pub fn from_path(path: impl AsRef<Path>, graphics: &Graphics) -> Self {
+        tracing::info!("Attempt to initialize SkinManager from path: {}", &path.as_ref().display());
+
+        Self::from_images(SkinImages::load(path, &Notifier::default()), graphics)
+    }
+
+    /// Uploads already decoded skin images to the GPU
+    pub fn from_images(images: SkinImages, graphics: &Graphics) -> Self {
+        Self::upload(images, UploadMode::Immediate, graphics)
+    }
+
+    /// Same as [`Self::from_images`], but big elements are copied
+    /// over the next frames. Skin should be kept aside until
+    /// [`Self::is_ready`], otherwise they are drawn transparent
+    pub fn from_images_staged(images: SkinImages, graphics: &Graphics) -> Self {
+        Self::upload(images, UploadMode::Staged, graphics)
+    }
+
+    fn upload(images: SkinImages, mode: UploadMode, graphics: &Graphics) -> Self {
+        let _span = tracy_client::span!("skin_manager::upload");
+
+        let upload = |x: SkinImage| Texture::from_image_mipmapped(x.image, x.scale, mode, graphics);
+
+        Self {
+            path: images.path,
+            ini: images.ini,
+            hit_circle: upload(images.hit_circle),
+            hit_circle_overlay: upload(images.hit_circle_overlay),
+            sliderb0: upload(images.sliderb0),
+            cursor: upload(images.cursor),
+            cursor_trail: upload(images.cursor_trail),
+            cursor_ripple: upload(images.cursor_ripple),
+            judgments_atlas: AtlasTexture::from_atlas_image(graphics, images.judgments_atlas, mode),
+            slider_tick: upload(images.slider_tick),
+            slider_reverse_arrow: upload(images.slider_reverse_arrow),
         }
     }
+
+    /// Every element is on the GPU
+    pub fn is_ready(&self) -> bool {
+        [
+            &self.hit_circle,
+            &self.hit_circle_overlay,
+            &self.sliderb0,
+            &self.cursor,
+            &self.cursor_trail,
+            &self.cursor_ripple,
+            &self.slider_tick,
+            &self.slider_reverse_arrow,
+        ].iter().all(|x| x.is_ready()) && self.judgments_atlas.is_ready()
+    }
+}
+
+#[test]
+fn test_judgement_ring() {
+    let ring = judgement_ring(64, 32);
+
+    assert_eq!(ring.dimensions(), (64, 32));
+
+    // Empty in the middle and corners, opaque on the ring
+    assert_eq!(ring.get_pixel(32, 16)[3], 0);
+    assert_eq!(ring.get_pixel(0, 0)[3], 0);
+    assert!(ring.get_pixel(3, 16)[3] > 200);
+    assert!(ring.get_pixel(32, 1)[3] > 200);
+}
+
+#[test]
+fn test_skin_layers_resolution_order() {
+    let beatmap = Path::new("songs/map");
+    let skin = Path::new("skins/user");
+
+    let files = [
+        beatmap.join("hitcircle.png"),
+        skin.join("hitcircle.png"),
+        skin.join("cursor.png"),
+        skin.join("cursor@2x.png"),
+        skin.join("hitcircle@2x.png"),
+    ];
+    let exists = |path: &Path| files.iter().any(|x| x == path);
+
+    let layers = SkinLayers { beatmap: Some(beatmap), skin };
+
+    // Beatmap wins over the user skin
+    assert_eq!(layers.find("hitcircle.png", exists), Some(beatmap.join("hitcircle.png")));
+    // Missing from beatmap goes to the user skin
+    assert_eq!(layers.find("cursor.png", exists), Some(skin.join("cursor.png")));
+    // Missing everywhere goes to the default skin
+    assert_eq!(layers.find("sliderb0.png", exists), None);
+    assert_eq!(
+        layers.resolve("hitcircleoverlay.png", "empty.png", exists),
+        Path::new(DEFAULT_SKIN_PATH).join("empty.png"),
+    );
+
+    // Ignored beatmap skin
+    let layers = SkinLayers::skin(skin);
+    assert_eq!(layers.find("hitcircle.png", exists), Some(skin.join("hitcircle.png")));
+
+    // @2x is preferred within the same layer only
+    let layers = SkinLayers { beatmap: Some(beatmap), skin };
+    assert_eq!(layers.find_hd("cursor.png", exists), Some((skin.join("cursor@2x.png"), 2.0)));
+    assert_eq!(layers.find_hd("hitcircle.png", exists), Some((beatmap.join("hitcircle.png"), 1.0)));
+    assert_eq!(
+        layers.resolve_hd("sliderb0.png", "sliderb0.png", exists),
+        (Path::new(DEFAULT_SKIN_PATH).join("sliderb0.png"), 1.0),
+    );
+}
+
+#[test]
+fn test_skin_hd_elements() {
+    let dir = std::env::temp_dir().join(format!("rosu-skin-hd-{}", std::process::id()));
+    let beatmap = dir.join("beatmap");
+    let skin = dir.join("skin");
+    std::fs::create_dir_all(&beatmap).unwrap();
+    std::fs::create_dir_all(&skin).unwrap();
+
+    let save = |path: PathBuf, size: u32| RgbaImage::new(size, size).save(path).unwrap();
+
+    save(skin.join("hitcircle.png"), 64);
+    save(skin.join("hitcircle@2x.png"), 128);
+    save(skin.join("cursor@2x.png"), 32);
+    save(beatmap.join("sliderb0.png"), 40);
+    save(skin.join("sliderb0@2x.png"), 80);
+
+    assert_eq!(hd_name("hitcircle.png"), "hitcircle@2x.png");
+
+    let layers = SkinLayers { beatmap: Some(&beatmap), skin: &skin };
+
+    // @2x is preferred, logical size is the same as of the SD one
+    let hit_circle = load_skin_image(layers, "hitcircle.png", "hitcircle.png");
+    assert_eq!(hit_circle.image.dimensions(), (128, 128));
+    assert_eq!(hit_circle.scale, 2.0);
+    assert_eq!(hit_circle.logical_size(), (64.0, 64.0));
+
+    // Skin can have only the @2x variant
+    let cursor = load_skin_image(layers, "cursor.png", "cursor.png");
+    assert_eq!((cursor.scale, cursor.logical_size()), (2.0, (16.0, 16.0)));
+
+    // SD beatmap element still wins over the HD skin one
+    let sliderb0 = load_skin_image(layers, "sliderb0.png", "sliderb0.png");
+    assert_eq!((sliderb0.scale, sliderb0.logical_size()), (1.0, (40.0, 40.0)));
+
+    // Judgements of different resolutions end up in the same atlas
+    let images = match_scales(vec![
+        SkinImage { image: DynamicImage::new_rgba8(10, 10), scale: 1.0 },
+        SkinImage { image: DynamicImage::new_rgba8(20, 20), scale: 2.0 },
+    ]);
+    assert!(images.iter().all(|x| x.dimensions() == (20, 20)));
+
+    std::fs::remove_dir_all(&dir).unwrap();
 }
@@ -1,7 +1,7 @@
 
 use cgmath::Vector3;
 
-use crate::rgb::Rgb;
+use crate::{math::debug_assert_finite, rgb::Rgb};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Zeroable, bytemuck::Pod)]
@@ -26,6 +26,8 @@ impl SliderInstance {
         slider_border: &Rgb,
         slider_body: &Rgb,
     ) -> Self {
+        debug_assert_finite(x, y);
+
         let mat = Vector3::new(x, y, z);
 
         Self {
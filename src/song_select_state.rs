@@ -1,42 +1,69 @@
-use std::{fs::File, io::{Cursor, Read}, path::PathBuf, sync::{mpsc::{Receiver, Sender}, Arc, RwLock}, time::Duration};
+use std::{io::Cursor, path::{Path, PathBuf}, sync::{mpsc::{Receiver, Sender}, Arc, RwLock}, time::Duration};
 
 use image::{DynamicImage, ImageReader};
 use md5::Digest;
-use rand::Rng;
 use rosu_map::Beatmap;
 use soloud::{audio, AudioExt, LoadExt};
 use wgpu::TextureView;
 use winit::{dpi::PhysicalSize, keyboard::KeyCode};
 
-use crate::{config::Config, graphics::Graphics, osu_db::{DbBeatmapEntry, OsuDatabase, DEFAULT_DB_PATH}, osu_state::OsuStateEvent, screen::{settings::SettingsScreen, song_select::{BeatmapCardInfoMetadata, CurrentAudio, CurrentBeatmap, SongSelectScreen}}, skin_manager::SkinManager};
+use crate::{audio::AudioInfo, beatmap_cache::BeatmapCache, config::Config, graphics::Graphics, i18n::tf, notifier::Notifier, osu_db::{DbBeatmapEntry, LocalScore, OsuDatabase, DEFAULT_DB_PATH}, osu_state::OsuStateEvent, processor::rules::DifficultyOverrides, random_history::RandomHistory, rng::AppRng, score::Progression, session_stats::PlayRecord, screen::{settings::SettingsScreen, song_select::{BeatmapCardInfoMetadata, CurrentAudio, CurrentBeatmap, PreviewProgress, SongSelectScreen}}, skin_manager::{has_skin_elements, SkinImages, SkinLayers, SkinManager}};
 
 pub struct SongsImportJob {
     pub path: PathBuf,
+    /// Continue after the checkpoint of an interrupted scan of `path`
+    pub resume: bool,
     pub stop_rx: oneshot::Receiver<()>,
 }
 
 pub enum SongSelectionEvents {
     /// Request to select beatmap from song select screen
     SelectBeatmap(Arc<DbBeatmapEntry>),
-    /// When beatmap loading thread is successfully returned a beatmap
+    /// When beatmap loading thread is successfully loaded background and audio
     LoadedBeatmap{ 
-        beatmap: Beatmap, 
-        //beatmap_md5: Digest,
+        path: PathBuf,
+        preview_time: i32,
         image: DynamicImage,
         image_md5: Digest,
         audio_source: audio::Wav,
         audio_md5: Digest
     },
+    /// Sent after `LoadedBeatmap` once .osu file is parsed
+    LoadedBeatmapMetadata{
+        path: PathBuf,
+        metadata: BeatmapCardInfoMetadata,
+    },
+    /// Skin elements shipped with the selected beatmap are decoded
+    LoadedBeatmapSkin{
+        dir: PathBuf,
+        images: Box<SkinImages>,
+    },
     /// Request to start the beatmap
     StartBeatmap(Arc<DbBeatmapEntry>),
     ImportSongsDirectory(SongsImportJob),
+    /// Opens directory of the beatmap in the OS file manager
+    OpenBeatmapFolder(Arc<DbBeatmapEntry>),
+    /// Removes a single difficulty from the DB and moves its file to the trash
+    DeleteBeatmap(Arc<DbBeatmapEntry>),
+    /// Removes the whole beatmapset directory from the DB and moves it to the trash
+    DeleteBeatmapset(Arc<DbBeatmapEntry>),
     ToggleSettings,
     CloseSettings,
+    /// Practice difficulty overrides were changed in the settings
+    DifficultyOverridesChanged,
+    /// Collections were imported from stable
+    CollectionsChanged,
+    /// Preview progress strip was clicked, seconds
+    SeekPreview(f64),
 }
 
-pub struct SongSelectionState<'ss> {
+pub struct SongSelectionState {
     db: Arc<OsuDatabase>,
 
+    // Beatmaps parsed by the opener worker, reused
+    // when selected beatmap is started
+    beatmap_cache: Arc<BeatmapCache>,
+
     // SongSelection state senders, used by
     // components inside song selection
     inner_tx: Sender<SongSelectionEvents>,
@@ -47,40 +74,121 @@ pub struct SongSelectionState<'ss> {
     // Events sender for "god" state
     state_tx: Sender<OsuStateEvent>,
 
+    config: Arc<RwLock<Config>>,
+
     settings: SettingsScreen,
-    song_select_screen: SongSelectScreen<'ss>,
+    song_select_screen: SongSelectScreen,
 
     worker_tx: Sender<DbBeatmapEntry>,
+
+    random_history: RandomHistory,
+    /// Collection and search the history indices belong to
+    random_list: (Option<i64>, String),
 }
 
-impl<'ss> SongSelectionState<'ss> {
+impl SongSelectionState {
     pub fn new(
-        graphics: Arc<Graphics<'ss>>, 
+        graphics: Arc<Graphics>, 
         state_tx: Sender<OsuStateEvent>,
         config: Arc<RwLock<Config>>,
         skin_manager: Arc<RwLock<SkinManager>>,
+        audio_info: Arc<RwLock<AudioInfo>>,
+        notifier: Notifier,
+        rng: AppRng,
     ) -> Self {
         let (inner_tx, inner_rx) = std::sync::mpsc::channel();
         let (worker_tx, worker_rx) = std::sync::mpsc::channel::<DbBeatmapEntry>();
 
         let db: Arc<OsuDatabase> = OsuDatabase::new_from_path(DEFAULT_DB_PATH)
             .unwrap()
+            .with_notifier(notifier.clone())
             .into(); // TODO: REMOVE UNRAP
 
-        spawn_beatmap_opener_worker(worker_rx, inner_tx.clone());
+        if let Some(scan) = db.interrupted_scan() {
+            notifier.info(tf("notify.scan_interrupted", &[&scan.root.display()]));
+        }
+
+        let beatmap_cache = Arc::new(BeatmapCache::default());
+
+        spawn_beatmap_opener_worker(
+            worker_rx,
+            inner_tx.clone(),
+            beatmap_cache.clone(),
+            db.clone(),
+            config.clone(),
+            skin_manager.clone(),
+            notifier,
+        );
 
         Self {
             db: db.clone(),
+            beatmap_cache,
             inner_tx: inner_tx.clone(),
             inner_rx,
             state_tx: state_tx.clone(),
-            settings: SettingsScreen::new(config.clone(), skin_manager.clone(), state_tx.clone()),
-            song_select_screen: SongSelectScreen::new(db.clone(), graphics.clone(), inner_tx.clone()),
+            settings: SettingsScreen::new(config.clone(), skin_manager.clone(), audio_info, db.clone(), state_tx.clone(), inner_tx.clone()),
+            song_select_screen: SongSelectScreen::new(db.clone(), graphics.clone(), config.clone(), inner_tx.clone()),
             current_audio: None,
             worker_tx,
+            config,
+            random_history: RandomHistory::new(rng),
+            random_list: (None, String::new()),
         }
     }
     
+    fn difficulty_overrides(&self) -> DifficultyOverrides {
+        self.config.read().expect("failed to acquire read lock").rules.difficulty
+    }
+
+    /// Scans `path` for new beatmaps, e.g. after archives were extracted there
+    pub fn import_songs_directory(&self, path: impl Into<PathBuf>) {
+        let (_stop_tx, stop_rx) = oneshot::channel();
+
+        let _ = self.inner_tx.send(SongSelectionEvents::ImportSongsDirectory(SongsImportJob {
+            path: path.into(),
+            resume: false,
+            stop_rx,
+        }));
+    }
+
+    /// Single lookup when a replay is opened, so it's fine to block on it
+    pub fn find_beatmap_by_hash(&self, hash: &str) -> Option<DbBeatmapEntry> {
+        let _span = tracy_client::span!("osu_song_select_state::find_beatmap_by_hash");
+
+        self.db.get_beatmap_by_hash(hash)
+    }
+
+    /// Progression of the best local play, `None` if there is nothing to compare to
+    pub fn best_progression(&self, hash: &str) -> Option<Progression> {
+        let _span = tracy_client::span!("osu_song_select_state::best_progression");
+
+        self.db.best_score_progression(hash)
+            .and_then(|x| Progression::from_bytes(&x))
+            .filter(|x| !x.is_empty())
+    }
+
+    /// Writes finished play on a separate thread, so gameplay never waits on SQL
+    pub fn record_play(&self, play: PlayRecord) {
+        let db = self.db.clone();
+
+        std::thread::spawn(move || {
+            if let Err(e) = db.record_plays(&[play]) {
+                tracing::error!("Failed to record play: {e}");
+            }
+        });
+    }
+
+    /// Same as [`Self::record_play`], the replay is kept only if it's the best one
+    pub fn store_local_score(&self, score: LocalScore, replay: Vec<u8>) {
+        let db = self.db.clone();
+
+        std::thread::spawn(move || {
+            if let Err(e) = db.store_local_score(&score, &replay) {
+                tracing::error!("Failed to store local score: {e}");
+            }
+        });
+    }
+
     // Spawns a thread to parse a beatmap
     fn open_beatmap(&self, beatmap: &DbBeatmapEntry) {
         let _span = tracy_client::span!("osu_song_select_state::open_beatmap");
@@ -91,26 +199,38 @@ impl<'ss> SongSelectionState<'ss> {
     pub fn on_pressed_down(
         &mut self,
         key_code: KeyCode,
-        is_cntrl_pressed: bool
+        is_cntrl_pressed: bool,
+        is_shift_pressed: bool,
     ) {
         let _span = tracy_client::span!("osu_song_select_state::on_pressed_down");
 
-        if key_code == KeyCode::Enter {
-            let current_in_cache = self.song_select_screen.current_in_cache();
-
-            self.inner_tx.send(
-                SongSelectionEvents::StartBeatmap(self.db.get_from_cache(current_in_cache).unwrap())
-            ).expect(
-                "Failed to send StartBeatmap event to the SongSelectState"
-            );
+        if key_code == KeyCode::KeyO && is_cntrl_pressed {
+            let _ = self.inner_tx.send(SongSelectionEvents::ToggleSettings);
+            return;
         }
 
-        if key_code == KeyCode::F2 {
-            let mut rng = rand::thread_rng();
+        // Keys navigate settings while they are open,
+        // Enter shouldn't start the beatmap behind them
+        if self.settings.is_open() {
+            if key_code == KeyCode::Escape {
+                let _ = self.inner_tx.send(SongSelectionEvents::CloseSettings);
+            }
 
-            let random_beatmap = rng.gen_range(0..self.db.beatmaps_amount());
+            return;
+        }
 
-            self.song_select_screen.set_scroll_to(random_beatmap);
+        if key_code == KeyCode::Enter {
+            if let Some(entry) = self.song_select_screen.current_entry() {
+                self.inner_tx.send(
+                    SongSelectionEvents::StartBeatmap(entry)
+                ).expect(
+                    "Failed to send StartBeatmap event to the SongSelectState"
+                );
+            }
+        }
+
+        if key_code == KeyCode::F2 {
+            self.on_random_pressed(is_shift_pressed);
         }
 
         if key_code == KeyCode::ArrowDown || key_code == KeyCode::ArrowRight {
@@ -120,18 +240,36 @@ impl<'ss> SongSelectionState<'ss> {
         if key_code == KeyCode::ArrowUp || key_code == KeyCode::ArrowLeft {
             self.song_select_screen.decrement_beatmap();
         }
+    }
+    
 
-        if key_code == KeyCode::KeyO && is_cntrl_pressed {
-            let _ = self.inner_tx.send(SongSelectionEvents::ToggleSettings);
+    
+    /// F2 jumps to a random beatmap, Shift+F2 goes back to
+    /// where the previous random pick moved away from
+    fn on_random_pressed(&mut self, rewind: bool) {
+        let _span = tracy_client::span!("osu_song_select_state::on_random_pressed");
+
+        // Indices are only valid for the list they were taken in
+        let list = (self.song_select_screen.collection(), self.song_select_screen.search().to_owned());
+
+        if list != self.random_list {
+            self.random_history.clear();
+            self.random_list = list;
         }
 
-        if key_code == KeyCode::Escape && self.settings.is_open() {
-            let _ = self.inner_tx.send(SongSelectionEvents::CloseSettings);
+        let index = match rewind {
+            true => self.random_history.rewind(),
+            false => self.random_history.pick(
+                self.song_select_screen.current_index(),
+                self.song_select_screen.beatmaps_amount(),
+            ),
+        };
+
+        if let Some(index) = index {
+            self.song_select_screen.set_scroll_to(index);
         }
     }
-    
 
-    
     #[inline]
     fn load_background(&mut self, image: DynamicImage, md5: Digest) {
         let _span = tracy_client::span!("osu_song_select_state::load_background");
@@ -145,7 +283,8 @@ impl<'ss> SongSelectionState<'ss> {
         &mut self, 
         audio_source: audio::Wav,
         md5: md5::Digest,
-        beatmap: &Beatmap,
+        preview_time: i32,
+        beatmap_path: &Path,
     ) {
         let _span = tracy_client::span!("osu_song_select_state::load_audio");
 
@@ -157,7 +296,7 @@ impl<'ss> SongSelectionState<'ss> {
         };
 
         self.state_tx.send(OsuStateEvent::PlaySound(
-                beatmap.preview_time,
+                preview_time,
                 audio_source,
         )).expect(
             "Failed to send PlaySound event to the OsuState"
@@ -165,6 +304,7 @@ impl<'ss> SongSelectionState<'ss> {
 
         self.current_audio = Some(CurrentAudio {
             audio_hash: md5,
+            beatmap_dir: beatmap_path.parent().map(|x| x.to_path_buf()).unwrap_or_default(),
         });
     }
 
@@ -177,12 +317,14 @@ impl<'ss> SongSelectionState<'ss> {
                         let _span = tracy_client::span!("osu_song_select_state::update::event::select_beatmap");
                         self.open_beatmap(&entry);
                     },
-                    SongSelectionEvents::LoadedBeatmap{ mut beatmap, image, audio_source, image_md5, audio_md5, .. }  => {
+                    SongSelectionEvents::LoadedBeatmap{ path, preview_time, image, audio_source, image_md5, audio_md5 }  => {
                         let _span = tracy_client::span!("osu_song_select_state::update::event::loaded_beatmap");
                         self.load_background(image, image_md5);
-                        self.load_audio(audio_source, audio_md5, &beatmap);
-
-                        let metadata = BeatmapCardInfoMetadata::from_beatmap(&mut beatmap);
+                        self.load_audio(audio_source, audio_md5, preview_time, &path);
+                    },
+                    SongSelectionEvents::LoadedBeatmapMetadata{ mut metadata, .. } => {
+                        let _span = tracy_client::span!("osu_song_select_state::update::event::loaded_beatmap_metadata");
+                        metadata.set_overrides(self.difficulty_overrides());
 
                         let current_beatmap = CurrentBeatmap {
                             metadata,
@@ -190,21 +332,61 @@ impl<'ss> SongSelectionState<'ss> {
 
                         self.song_select_screen.set_current_beatmap(Some(current_beatmap));
                     },
+                    SongSelectionEvents::LoadedBeatmapSkin{ dir, images } => {
+                        let _span = tracy_client::span!("osu_song_select_state::update::event::loaded_beatmap_skin");
+                        let _ = self.state_tx.send(OsuStateEvent::BeatmapSkinLoaded(dir, images));
+                    },
                     SongSelectionEvents::ToggleSettings => {
                         self.settings.toggle();
                     },
                     SongSelectionEvents::CloseSettings => {
                         self.settings.close();
                     },
+                    SongSelectionEvents::DifficultyOverridesChanged => {
+                        let overrides = self.difficulty_overrides();
+                        self.song_select_screen.set_difficulty_overrides(overrides);
+                    },
+                    SongSelectionEvents::CollectionsChanged => {
+                        self.song_select_screen.refresh_collections();
+                    },
+                    SongSelectionEvents::SeekPreview(position) => {
+                        let _ = self.state_tx.send(OsuStateEvent::SeekPreview(position));
+                    },
                     SongSelectionEvents::StartBeatmap(entry) => {
                         let _span = tracy_client::span!("osu_song_select_state::update::event::start_beatmap");
                         self.settings.close();
-                        self.state_tx.send(OsuStateEvent::StartBeatmap(entry))
+
+                        let beatmap = self.beatmap_cache.get(&entry.path, &entry.hash);
+                        if beatmap.is_none() {
+                            tracing::info!("Beatmap is not cached, parsing from disk: {}", entry.path.display());
+                        }
+
+                        self.state_tx.send(OsuStateEvent::StartBeatmap(entry, beatmap))
                             .expect("Failed to send StartBeatmap event to the OsuState");
                     },
                     SongSelectionEvents::ImportSongsDirectory(job) => {
                         let _span = tracy_client::span!("osu_song_select_state::update::event::import_songs_directory");
-                        self.db.scan_beatmaps(job.path, job.stop_rx);
+                        if job.resume {
+                            self.db.resume_scan_beatmaps(job.path, job.stop_rx);
+                        } else {
+                            self.db.scan_beatmaps(job.path, job.stop_rx);
+                        }
+                    },
+                    SongSelectionEvents::OpenBeatmapFolder(entry) => {
+                        let _span = tracy_client::span!("osu_song_select_state::update::event::open_beatmap_folder");
+                        if let Some(dir) = entry.path.parent() {
+                            if let Err(e) = open::that_detached(dir) {
+                                tracing::error!("Failed to open {}: {e}", dir.display());
+                            }
+                        }
+                    },
+                    SongSelectionEvents::DeleteBeatmap(entry) => {
+                        let _span = tracy_client::span!("osu_song_select_state::update::event::delete_beatmap");
+                        self.delete_beatmap(&entry);
+                    },
+                    SongSelectionEvents::DeleteBeatmapset(entry) => {
+                        let _span = tracy_client::span!("osu_song_select_state::update::event::delete_beatmapset");
+                        self.delete_beatmapset(&entry);
                     },
                 }
             },
@@ -217,118 +399,354 @@ impl<'ss> SongSelectionState<'ss> {
         }
     }
 
+    /// `deleted` are indexes of the whole list, so the
+    /// filtered list is reloaded from the start instead
+    fn on_beatmaps_deleted(&mut self, deleted: &[usize]) {
+        match self.song_select_screen.is_filtered() {
+            true => self.song_select_screen.reload_list(),
+            false => self.song_select_screen.on_beatmaps_deleted(deleted),
+        }
+    }
+
+    fn delete_beatmap(&mut self, entry: &DbBeatmapEntry) {
+        let Some(index) = self.db.get_beatmap_index(entry.id) else {
+            tracing::warn!("Trying to delete beatmap that is not in the database: {}", entry.path.display());
+            return;
+        };
+
+        if let Err(e) = self.db.delete_beatmap(entry.id) {
+            tracing::error!("Failed to delete beatmap from database: {e}");
+            return;
+        }
+
+        self.on_beatmaps_deleted(&[index]);
+        self.beatmap_cache.invalidate(&entry.path);
+
+        move_to_trash(entry.path.clone());
+    }
+
+    fn delete_beatmapset(&mut self, entry: &DbBeatmapEntry) {
+        let Some(dir) = entry.path.parent() else {
+            return;
+        };
+
+        let indexes = self.db.get_beatmap_indexes_in_directory(dir);
+
+        if let Err(e) = self.db.delete_beatmaps_in_directory(dir) {
+            tracing::error!("Failed to delete beatmapset from database: {e}");
+            return;
+        }
+
+        self.on_beatmaps_deleted(&indexes);
+        self.beatmap_cache.invalidate(dir);
+
+        // Stopping preview if it belongs to the deleted beatmapset
+        let is_playing_deleted = self.current_audio
+            .as_ref()
+            .is_some_and(|x| x.beatmap_dir == dir);
+
+        if is_playing_deleted {
+            self.current_audio = None;
+            let _ = self.state_tx.send(OsuStateEvent::StopSound);
+        }
+
+        move_to_trash(dir.to_path_buf());
+    }
+
+    /// Preview voice is owned by `OsuState`, it passes the position every frame
+    #[inline]
+    pub fn set_preview_progress(&mut self, progress: Option<PreviewProgress>) {
+        self.song_select_screen.set_preview_progress(progress);
+    }
+
     pub fn on_resize(&mut self, new_size: &PhysicalSize<u32>) {
         self.song_select_screen.on_resize(new_size);
     }
 
+    /// Expects egui pass to be already started
     pub fn render(
         &mut self, 
-        input: egui::RawInput, 
         ctx: &egui::Context, 
         view: &TextureView,
-    ) -> egui::FullOutput {
-        ctx.begin_pass(input);
-
+    ) {
         self.settings.render(ctx);
         self.song_select_screen.render(ctx, view);
-
-        ctx.end_pass()
     }
 }
 
+/// Moves file or directory to the OS trash in a separate thread
+fn move_to_trash(path: PathBuf) {
+    std::thread::spawn(move || {
+        match trash::delete(&path) {
+            Ok(_) => tracing::info!("Moved to trash: {}", path.display()),
+            Err(e) => tracing::error!("Failed to move {} to trash: {e}", path.display()),
+        }
+    });
+}
+
 /// Worker for opening requested beatmaps
 fn spawn_beatmap_opener_worker(
     worker_rx: Receiver<DbBeatmapEntry>, 
-    song_select_tx: Sender<SongSelectionEvents>
+    song_select_tx: Sender<SongSelectionEvents>,
+    beatmap_cache: Arc<BeatmapCache>,
+    db: Arc<OsuDatabase>,
+    config: Arc<RwLock<Config>>,
+    skin_manager: Arc<RwLock<SkinManager>>,
+    notifier: Notifier,
 ) {
     std::thread::spawn(move || {
         loop {
             let res = worker_rx.try_recv();
 
             match res {
-                Ok(job) => {
-                    let _span = tracy_client::span!("osu_song_select_state::open_beatmap_thread");
-                    let path = job.path;
+                Ok(job) => open_beatmap_job(job, &song_select_tx, &beatmap_cache, &db, &config, &skin_manager, &notifier),
+                Err(e) => match e {
+                    std::sync::mpsc::TryRecvError::Empty => continue,
+                    std::sync::mpsc::TryRecvError::Disconnected => {
+                        panic!("disconnected");
+                    },
+                },
+            }
+        }
+    });
+}
 
-                    tracing::info!("Starting opening beatmap for path {}", path.display());
+/// Loads background and audio of the selected beatmap using paths
+/// stored in the DB, .osu file is parsed only afterwards for metadata.
+/// Old DB rows without stored paths have to be parsed first
+fn open_beatmap_job(
+    job: DbBeatmapEntry,
+    song_select_tx: &Sender<SongSelectionEvents>,
+    beatmap_cache: &BeatmapCache,
+    db: &OsuDatabase,
+    config: &RwLock<Config>,
+    skin_manager: &RwLock<SkinManager>,
+    notifier: &Notifier,
+) {
+    let _span = tracy_client::span!("osu_song_select_state::open_beatmap_thread");
+    let path = job.path;
 
-                    // Beatmap stuff
-                    let mut beatmap_file = File::open(&path).unwrap();
-                    let mut beatmap_buffer = Vec::new();
-                    beatmap_file.read_to_end(&mut beatmap_buffer).unwrap();
+    tracing::info!("Starting opening beatmap for path {}", path.display());
 
-                    let _beatmap_md5 = md5::compute(&beatmap_buffer);
+    let Some(beatmap_dir) = path.parent() else {
+        tracing::error!("Beatmap has no parent directory: {}", path.display());
+        return;
+    };
 
-                    let parsed_beatmap = Beatmap::from_bytes(&beatmap_buffer).unwrap();
+    let mut parsed_beatmap = None;
 
-                    let bg_filename = parsed_beatmap.background_file.clone();
-                    let audio_filename = parsed_beatmap.audio_file.clone();
+    let (bg_filename, audio_filename, preview_time) = match (job.background_file, job.audio_file, job.preview_time) {
+        (Some(bg), Some(audio), Some(preview_time)) => (bg, audio, preview_time),
+        _ => {
+            let Some((beatmap_md5, beatmap)) = parse_beatmap(&path, notifier) else {
+                return;
+            };
 
-                    let bg_path = path.parent()
-                        .unwrap()
-                        .join(&bg_filename);
+            let paths = (beatmap.background_file.clone(), beatmap.audio_file.clone(), beatmap.preview_time);
+            parsed_beatmap = Some((beatmap_md5, beatmap));
 
-                    let audio_path = path.parent()
-                        .unwrap()
-                        .join(audio_filename);
+            paths
+        },
+    };
 
-                    if bg_path.is_dir() {
-                        tracing::error!(
-                            "Trying to read dir as background: {} for map {}",
-                            bg_path.display(),
-                            path.display()
-                        );
+    let (img, bg_md5) = load_background_image(&beatmap_dir.join(&bg_filename), db, notifier);
 
-                        continue;
-                    }
+    // Audio file stuff
+    let audio_path = beatmap_dir.join(&audio_filename);
+    let Ok(audio_buffer) = std::fs::read(&audio_path) else {
+        tracing::error!("Failed to open audio: {}", audio_path.display());
+        notifier.error(tf("notify.audio_missing", &[&audio_filename]));
+        return;
+    };
 
-                    // BG image stuff
-                    let Ok(mut bg_file) = File::open(&bg_path) else {
-                        tracing::error!("Failed to open bg: {}", bg_path.display());
-                        continue
-                    };
+    // Only tells previews apart, cached so reselecting doesn't hash the whole file
+    let audio_md5 = db.file_hash(&audio_path, &audio_buffer);
 
-                    let mut bg_buffer = Vec::new();
-                    bg_file.read_to_end(&mut bg_buffer).unwrap();
-                    let bg_md5 = md5::compute(&bg_buffer);
+    let mut wav = audio::Wav::default();
+    if let Err(e) = wav.load_mem(&audio_buffer) {
+        tracing::error!("Failed to decode audio {}: {e}", audio_path.display());
+        notifier.error(tf("notify.audio_decode_failed", &[&audio_filename]));
+        return;
+    }
 
-                    let bg_buffer = Cursor::new(bg_buffer);
+    let _ = song_select_tx.send(SongSelectionEvents::LoadedBeatmap{
+        path: path.clone(),
+        preview_time,
+        image: img,
+        image_md5: bg_md5,
+        audio_md5,
+        audio_source: wav,
+    });
 
-                    let img_reader = ImageReader::new(bg_buffer)
-                        .with_guessed_format().unwrap();
+    let Some((beatmap_md5, mut parsed_beatmap)) = parsed_beatmap.or_else(|| parse_beatmap(&path, notifier)) else {
+        return;
+    };
 
-                    let img = img_reader.decode().unwrap();
-                    let img = img.blur(5.0);
+    // Building it here so UI thread doesn't
+    // have to go through every object
+    let metadata = BeatmapCardInfoMetadata::from_beatmap(&mut parsed_beatmap);
+    beatmap_cache.insert(&path, beatmap_md5, Arc::new(parsed_beatmap));
 
-                    // Audio file stuff
-                    let Ok(mut audio_file) = File::open(&audio_path) else {
-                        tracing::error!("Failed to open audio: {}", audio_path.display());
-                        continue;
-                    };
+    let _ = song_select_tx.send(SongSelectionEvents::LoadedBeatmapMetadata{
+        path: path.clone(),
+        metadata,
+    });
 
-                    let mut audio_buffer = Vec::new();
-                    audio_file.read_to_end(&mut audio_buffer).unwrap();
+    // Decoded ahead, so starting the beatmap only uploads the textures
+    if let Some(images) = load_beatmap_skin(beatmap_dir, config, skin_manager, notifier) {
+        let _ = song_select_tx.send(SongSelectionEvents::LoadedBeatmapSkin{
+            dir: beatmap_dir.to_path_buf(),
+            images: Box::new(images),
+        });
+    }
+}
 
-                    let audio_md5 = md5::compute(&audio_buffer);
+/// Skin elements of the beatmap layered over the user skin, `None` if
+/// beatmap doesn't ship any or beatmap skins are ignored
+fn load_beatmap_skin(
+    beatmap_dir: &Path,
+    config: &RwLock<Config>,
+    skin_manager: &RwLock<SkinManager>,
+    notifier: &Notifier,
+) -> Option<SkinImages> {
+    let ignore = config.read().expect("failed to acquire read lock").ignore_beatmap_skin;
+
+    if ignore || !has_skin_elements(beatmap_dir) {
+        return None;
+    }
 
-                    let mut wav = audio::Wav::default();
-                    wav.load_mem(&audio_buffer).unwrap(); // TODO: Handle error
+    let skin_path = skin_manager.read().expect("failed to acquire read lock").path.clone();
 
-                    let _ = song_select_tx.send(SongSelectionEvents::LoadedBeatmap{
-                        beatmap: parsed_beatmap,
-                        image: img,
-                        image_md5: bg_md5,
-                        audio_md5,
-                        audio_source: wav,
-                    });
-                },
-                Err(e) => match e {
-                    std::sync::mpsc::TryRecvError::Empty => continue,
-                    std::sync::mpsc::TryRecvError::Disconnected => {
-                        panic!("disconnected");
-                    },
-                },
-            }
-        }
-    });
+    Some(SkinImages::load_layered(SkinLayers {
+        beatmap: Some(beatmap_dir),
+        skin: &skin_path,
+    }, notifier))
+}
+
+/// Returns md5 hex of the file along with parsed beatmap
+fn parse_beatmap(path: &Path, notifier: &Notifier) -> Option<(String, Beatmap)> {
+    let _span = tracy_client::span!("osu_song_select_state::parse_beatmap");
+
+    let beatmap_buffer = match std::fs::read(path) {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            tracing::error!("Failed to read beatmap {}: {e}", path.display());
+            notifier.error(tf("notify.beatmap_read_failed", &[&file_name(path)]));
+            return None;
+        },
+    };
+
+    let beatmap_md5 = format!("{:x}", md5::compute(&beatmap_buffer));
+
+    match Beatmap::from_bytes(&beatmap_buffer) {
+        Ok(beatmap) => Some((beatmap_md5, beatmap)),
+        Err(e) => {
+            tracing::error!("Failed to parse beatmap {}: {e}", path.display());
+            notifier.error(tf("notify.beatmap_read_failed", &[&file_name(path)]));
+            None
+        },
+    }
+}
+
+/// Reads and blurs background image, placeholder is
+/// returned if file is missing or can't be decoded
+fn load_background_image(bg_path: &Path, db: &OsuDatabase, notifier: &Notifier) -> (DynamicImage, Digest) {
+    let _span = tracy_client::span!("osu_song_select_state::load_background_image");
+
+    let placeholder = || (DynamicImage::new_rgba8(1, 1), md5::compute(b""));
+
+    if bg_path.is_dir() {
+        tracing::warn!("Beatmap has no background, using placeholder: {}", bg_path.display());
+        return placeholder();
+    }
+
+    let Ok(bg_buffer) = std::fs::read(bg_path) else {
+        tracing::error!("Failed to open bg: {}", bg_path.display());
+        notifier.warn(tf("notify.background_failed", &[&file_name(bg_path)]));
+        return placeholder();
+    };
+
+    let bg_md5 = db.file_hash(bg_path, &bg_buffer);
+
+    let img = ImageReader::new(Cursor::new(bg_buffer))
+        .with_guessed_format()
+        .map_err(image::ImageError::IoError)
+        .and_then(|reader| reader.decode());
+
+    match img {
+        Ok(img) => (img.blur(5.0), bg_md5),
+        Err(e) => {
+            tracing::error!("Failed to decode bg {}: {e}", bg_path.display());
+            notifier.warn(tf("notify.background_failed", &[&file_name(bg_path)]));
+            placeholder()
+        },
+    }
+}
+
+/// Shown in notifications instead of the whole path
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[test]
+fn test_open_beatmap_missing_background() {
+    let path = PathBuf::from("tests/data/songs_folder/953303 Our Stolen Theory - United (LAOS Remix)")
+        .join("Our Stolen Theory - United (L.A.O.S Remix) (Sotarks) [Eternity].osu");
+
+    let entry = DbBeatmapEntry {
+        id: 0,
+        beatmap_id: 0,
+        beatmapset_id: 0,
+        title: String::new(),
+        artist: String::new(),
+        creator: String::new(),
+        version: String::new(),
+        path: path.clone(),
+        hash: String::new(),
+        background_file: Some("missing.jpg".to_string()),
+        audio_file: Some("audio.mp3".to_string()),
+        preview_time: Some(147259),
+        mode: Some(0),
+        source: None,
+        tags: None,
+    };
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let (notifier, notifications) = Notifier::channel();
+    let cache = BeatmapCache::default();
+    let db = OsuDatabase::new_from_path(testdir::testdir!().join("rosu.db")).unwrap();
+
+    open_beatmap_job(entry.clone(), &tx, &cache, &db, &notifier);
+
+    let SongSelectionEvents::LoadedBeatmap { image, image_md5, preview_time, .. } = rx.try_recv().unwrap() else {
+        panic!("expected LoadedBeatmap event");
+    };
+
+    assert_eq!((image.width(), image.height()), (1, 1));
+    assert_eq!(image_md5, md5::compute(b""));
+    assert_eq!(preview_time, 147259);
+
+    assert!(matches!(rx.try_recv().unwrap(), SongSelectionEvents::LoadedBeatmapMetadata { .. }));
+    assert_eq!(cache.len(), 1);
+
+    let notification = notifications.try_recv().unwrap();
+    assert_eq!(notification.severity, crate::notifier::Severity::Warning);
+    assert!(notification.text.contains("missing.jpg"));
+
+    // Missing audio stops loading and is reported
+    let entry = DbBeatmapEntry {
+        audio_file: Some("missing.mp3".to_string()),
+        ..entry
+    };
+
+    open_beatmap_job(entry, &tx, &cache, &db, &notifier);
+
+    assert!(rx.try_recv().is_err());
+
+    let notifications: Vec<_> = notifications.try_iter().collect();
+    let last = notifications.last().unwrap();
+    assert_eq!(last.severity, crate::notifier::Severity::Error);
+    assert!(last.text.contains("missing.mp3"));
 }
@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+
+/// Sounds due within this many ms are handed to the audio backend
+/// on the current update, it delays them to their exact time
+pub const SCHEDULE_LOOKAHEAD_MS: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledSound<T> {
+    /// Gameplay clock time in ms
+    pub at: f64,
+    pub sound: T,
+}
+
+/// Sounds ordered by the time they should be heard at. Predictable
+/// ones, like slider ticks or the countdown, are scheduled ahead.
+/// Reactive ones, like circle hits, are scheduled for the current time
+/// and go through the same dispatch, so volume and effects apply to both
+#[derive(Debug)]
+pub struct SoundScheduler<T> {
+    queue: VecDeque<ScheduledSound<T>>,
+}
+
+impl<T> Default for SoundScheduler<T> {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl<T> SoundScheduler<T> {
+    /// Sounds with the same time keep the order they were scheduled in
+    pub fn schedule(&mut self, at: f64, sound: T) {
+        let index = self.queue.partition_point(|x| x.at <= at);
+        self.queue.insert(index, ScheduledSound { at, sound });
+    }
+
+    /// Takes every sound due before `now + lookahead` in time order.
+    /// Late ones are taken too, so nothing is lost on a long frame
+    pub fn drain_due(&mut self, now: f64, lookahead: f64) -> impl Iterator<Item = ScheduledSound<T>> + '_ {
+        let due = self.queue.partition_point(|x| x.at < now + lookahead);
+
+        self.queue.drain(..due)
+    }
+
+    /// Drops everything scheduled, e.g. on seek or restart
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[test]
+fn test_sound_scheduler_order() {
+    let mut scheduler = SoundScheduler::default();
+
+    scheduler.schedule(300.0, "tick2");
+    scheduler.schedule(100.0, "tick1");
+    scheduler.schedule(300.0, "hit");
+    scheduler.schedule(50.0, "late");
+
+    let due: Vec<_> = scheduler.drain_due(310.0, SCHEDULE_LOOKAHEAD_MS).map(|x| x.sound).collect();
+    assert_eq!(due, ["late", "tick1", "tick2", "hit"]);
+    assert!(scheduler.is_empty());
+}
+
+#[test]
+fn test_sound_scheduler_lookahead_boundary() {
+    let mut scheduler = SoundScheduler::default();
+
+    scheduler.schedule(104.9, "inside");
+    scheduler.schedule(105.0, "boundary");
+    scheduler.schedule(200.0, "later");
+
+    let due: Vec<_> = scheduler.drain_due(100.0, 5.0).collect();
+    assert_eq!(due, [ScheduledSound { at: 104.9, sound: "inside" }]);
+
+    // Exactly at the end of the lookahead waits for the next update
+    assert_eq!(scheduler.len(), 2);
+    assert_eq!(scheduler.drain_due(100.1, 5.0).map(|x| x.sound).collect::<Vec<_>>(), ["boundary"]);
+
+    scheduler.clear();
+    assert_eq!(scheduler.drain_due(1000.0, 5.0).count(), 0);
+}
@@ -1,8 +1,96 @@
-use std::{io::Cursor, path::Path};
-use image::{imageops::FilterType, io::Reader as ImageReader, DynamicImage, GenericImageView, RgbaImage};
+use std::{borrow::Cow, io::Cursor, path::Path, sync::{atomic::{AtomicBool, Ordering}, Arc}};
+use image::{io::Reader as ImageReader, DynamicImage, GenericImageView, Rgba, RgbaImage};
 use wgpu::{ShaderStages, BindingType, TextureSampleType, TextureViewDimension};
 
-use crate::graphics::Graphics;
+use crate::{graphics::Graphics, texture_upload::DIRECT_UPLOAD_MAX_BYTES};
+
+/// How pixels get to the GPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadMode {
+    /// `queue.write_texture` on the calling thread, ready on return
+    Immediate,
+    /// Through the [`crate::texture_upload::TextureUploader`] over the
+    /// next frames, anything under [`DIRECT_UPLOAD_MAX_BYTES`] is still immediate
+    Staged,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AtlasError {
+    #[error("atlas requires at least one image")]
+    Empty,
+    #[error("image {index} is {got:?} while atlas expects {expected:?}")]
+    SizeMismatch {
+        index: usize,
+        expected: (u32, u32),
+        got: (u32, u32),
+    },
+}
+
+/// CPU side of the [`AtlasTexture`], can be built
+/// on any thread and uploaded to the GPU later
+pub struct AtlasImage {
+    image: RgbaImage,
+    images: u32,
+    image_width: u32,
+    image_height: u32,
+}
+
+impl AtlasImage {
+    /// Places provided images in one row, all of them should be the same size
+    pub fn build(images: &[DynamicImage]) -> Result<Self, AtlasError> {
+        let _span = tracy_client::span!("atlas_image::build");
+
+        let (f_w, f_h) = images.first()
+            .ok_or(AtlasError::Empty)?
+            .dimensions();
+
+        for (index, img) in images.iter().enumerate() {
+            if img.dimensions() != (f_w, f_h) {
+                return Err(AtlasError::SizeMismatch {
+                    index,
+                    expected: (f_w, f_h),
+                    got: img.dimensions(),
+                });
+            }
+        }
+
+        // Placing it in one row
+        let total_width = f_w * images.len() as u32;
+
+        let row_len = f_w as usize * 4;
+        let atlas_row_len = total_width as usize * 4;
+        let mut buffer = vec![0; atlas_row_len * f_h as usize];
+
+        // Copying whole rows since every image row
+        // is contiguous in both buffers
+        for (i, img) in images.iter().enumerate() {
+            let rgba = match img.as_rgba8() {
+                Some(rgba) => Cow::Borrowed(rgba),
+                None => Cow::Owned(img.to_rgba8()),
+            };
+
+            for (y, row) in rgba.chunks_exact(row_len).enumerate() {
+                let start = y * atlas_row_len + i * row_len;
+                buffer[start..start + row_len].copy_from_slice(row);
+            }
+        }
+
+        let image = RgbaImage::from_raw(total_width, f_h, buffer)
+            .expect("atlas buffer should match its dimensions");
+
+        Ok(Self {
+            image,
+            images: images.len() as u32,
+            image_width: f_w,
+            image_height: f_h,
+        })
+    }
+
+    #[inline]
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+}
 
 pub struct AtlasTexture {
     texture: Texture,
@@ -19,44 +107,23 @@ pub struct AtlasTexture {
 /// Atlas Texture of multiple images
 /// Currently it is vertical only for simplicity
 impl AtlasTexture {
-    pub fn from_images(graphics: &Graphics, images: &[DynamicImage]) -> Self {
-        // Provided images should be the same size in order to fit propely
-        for i in images {
-            for j in images {
-                assert_eq!(i.dimensions(), j.dimensions());
-            }
-        }
-
-        let (f_w, f_h) = images.get(0).unwrap().dimensions();
-        
-        // Placing it in one row
-        let total_height = f_h;
-        let total_width = f_w * images.len() as u32;
-
-        let mut atlas_rgba_image = RgbaImage::new(total_width, total_height);
+    pub fn from_images(graphics: &Graphics, images: &[DynamicImage]) -> Result<Self, AtlasError> {
+        let atlas = AtlasImage::build(images)?;
 
-        let mut current_x = 0;
-        for img in images {
-            for y in 0..f_h {
-                for x in 0..f_w {
-                    let pixel = img.get_pixel(x, y);
-                    atlas_rgba_image.put_pixel(current_x + x, y, pixel);
-                }
-            }
-            current_x += f_w;
-        }
+        Ok(Self::from_atlas_image(graphics, atlas, UploadMode::Immediate))
+    }
 
-        let atlas_image = DynamicImage::ImageRgba8(atlas_rgba_image);
+    /// Uploads already built atlas to the GPU
+    pub fn from_atlas_image(graphics: &Graphics, atlas: AtlasImage, mode: UploadMode) -> Self {
+        let _span = tracy_client::span!("atlas_texture::from_atlas_image");
 
-        let atlas_texture = Texture::from_image(
-            atlas_image, graphics
-        );
+        let atlas_texture = Texture::upload(atlas.image, Vec::new(), 1.0, mode, graphics);
 
         Self {
             texture: atlas_texture,
-            images: images.len() as u32,
-            image_width: f_w as f32,
-            image_height: f_h as f32,
+            images: atlas.images,
+            image_width: atlas.image_width as f32,
+            image_height: atlas.image_height as f32,
         }
     }
 
@@ -87,6 +154,11 @@ impl AtlasTexture {
     pub fn bind_group(&self) -> &wgpu::BindGroup {
         &self.texture.bind_group
     }
+
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        self.texture.is_ready()
+    }
 }
 
 pub struct DepthTexture {
@@ -137,11 +209,67 @@ impl DepthTexture {
     }
 }
 
+/// Number of levels in a full mip chain, down to 1x1
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/// Every level below `image` down to 1x1, each one is a 2x2 box
+/// filter of the previous. Colors are weighted by alpha, so fully
+/// transparent pixels don't darken the edges of skin elements
+pub fn mip_chain(image: &RgbaImage) -> Vec<RgbaImage> {
+    let _span = tracy_client::span!("texture::mip_chain");
+
+    let levels = mip_level_count(image.width(), image.height());
+    let mut chain: Vec<RgbaImage> = Vec::with_capacity(levels as usize - 1);
+
+    for _ in 1..levels {
+        let prev = chain.last().unwrap_or(image);
+
+        let (width, height) = ((prev.width() / 2).max(1), (prev.height() / 2).max(1));
+
+        let level = RgbaImage::from_fn(width, height, |x, y| {
+            let mut color = [0.0; 3];
+            let mut alpha = 0.0;
+
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                // Odd sizes clamp to the last row or column
+                let px = (x * 2 + dx).min(prev.width() - 1);
+                let py = (y * 2 + dy).min(prev.height() - 1);
+                let pixel = prev.get_pixel(px, py).0;
+
+                let a = pixel[3] as f32;
+                alpha += a;
+
+                for (sum, c) in color.iter_mut().zip(pixel) {
+                    *sum += c as f32 * a;
+                }
+            }
+
+            let color = match alpha > 0.0 {
+                true => color.map(|x| (x / alpha).round() as u8),
+                false => [0; 3],
+            };
+
+            Rgba([color[0], color[1], color[2], (alpha / 4.0).round() as u8])
+        });
+
+        chain.push(level);
+    }
+
+    chain
+}
+
 pub struct Texture {
     pub bind_group: wgpu::BindGroup,
     pub bind_group_layout: wgpu::BindGroupLayout,
     pub width: f32,
     pub height: f32,
+    /// Pixels per logical pixel, 2.0 for `@2x` skin elements
+    pub scale: f32,
+    /// Set once pixels are on the GPU, staged uploads
+    /// sample as transparent black until then
+    ready: Arc<AtomicBool>,
 }
 
 impl Texture {
@@ -151,25 +279,36 @@ impl Texture {
         let image = ImageReader::open(path).unwrap()
             .decode().unwrap();
 
-        let width = image.width();
-        let height = image.height();
-
-        let image = image.resize(width*2, height*2, FilterType::Lanczos3);
-
         Self::from_image(image, graphics)
     }
 
     pub fn from_bytes(bytes: &[u8], graphics: &Graphics) -> Self {
-        let image = ImageReader::new(Cursor::new(bytes))
+        Self::from_image(Self::decode_bytes(bytes), graphics)
+    }
+
+    /// Decodes image the same way as [`Texture::from_bytes`]
+    /// does, without touching the GPU
+    pub fn decode_bytes(bytes: &[u8]) -> DynamicImage {
+        ImageReader::new(Cursor::new(bytes))
             .with_guessed_format().unwrap()
-            .decode().unwrap();
+            .decode().unwrap()
+    }
 
-        let width = image.width();
-        let height = image.height();
+    /// Always `true` for textures that weren't staged
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
 
-        let image = image.resize(width*2, height*2, FilterType::Lanczos3);
+    /// Size in SD skin pixels, the same for `element.png` and `element@2x.png`
+    #[inline]
+    pub fn logical_width(&self) -> f32 {
+        self.width / self.scale
+    }
 
-        Self::from_image(image, graphics)
+    #[inline]
+    pub fn logical_height(&self) -> f32 {
+        self.height / self.scale
     }
 
     pub fn default_bind_group_layout(graphics: &Graphics, sample_count: u32) -> wgpu::BindGroupLayout {
@@ -245,14 +384,39 @@ impl Texture {
         Self {
             width: width as f32,
             height: height as f32,
+            scale: 1.0,
             bind_group_layout,
             bind_group,
+            ready: Arc::new(AtomicBool::new(true)),
         }
     }
 
     pub fn from_image(image: DynamicImage, graphics: &Graphics) -> Self {
-        let dimensions = image.dimensions();
-        let buffer = image.to_rgba8();
+        Self::upload(image.to_rgba8(), Vec::new(), 1.0, UploadMode::Immediate, graphics)
+    }
+
+    /// Same as [`Self::from_image`], but large images are copied
+    /// over the next frames, see [`Self::is_ready`]
+    pub fn from_image_staged(image: DynamicImage, graphics: &Graphics) -> Self {
+        Self::upload(image.to_rgba8(), Vec::new(), 1.0, UploadMode::Staged, graphics)
+    }
+
+    /// Same as [`Self::from_image`] with a full mip chain, so elements
+    /// drawn much smaller than their size don't shimmer. `scale`
+    /// is 2.0 for `@2x` elements
+    pub fn from_image_mipmapped(image: DynamicImage, scale: f32, mode: UploadMode, graphics: &Graphics) -> Self {
+        let _span = tracy_client::span!("texture::from_image_mipmapped");
+
+        let image = image.to_rgba8();
+        let mips = mip_chain(&image);
+
+        Self::upload(image, mips, scale, mode, graphics)
+    }
+
+    fn upload(buffer: RgbaImage, mips: Vec<RgbaImage>, scale: f32, mode: UploadMode, graphics: &Graphics) -> Self {
+        let _span = tracy_client::span!("texture::upload");
+
+        let dimensions = buffer.dimensions();
 
         let size = wgpu::Extent3d {
             width: dimensions.0,
@@ -264,7 +428,7 @@ impl Texture {
             &wgpu::TextureDescriptor {
                 label: Some("Whatever"),
                 size,
-                mip_level_count: 1,
+                mip_level_count: 1 + mips.len() as u32,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
                 format: wgpu::TextureFormat::Rgba8UnormSrgb,
@@ -273,26 +437,41 @@ impl Texture {
             }
             );
 
-        graphics.queue.write_texture(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &buffer,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            size,
-            );
+        // Blending between levels only makes sense when there are any
+        let mipmap_filter = match mips.is_empty() {
+            true => wgpu::FilterMode::Nearest,
+            false => wgpu::FilterMode::Linear,
+        };
 
         let view = texture.create_view(
             &wgpu::TextureViewDescriptor::default()
         );
 
+        let bytes: u64 = std::iter::once(&buffer)
+            .chain(&mips)
+            .map(|x| x.as_raw().len() as u64)
+            .sum();
+
+        let ready = match mode {
+            UploadMode::Staged if bytes > DIRECT_UPLOAD_MAX_BYTES => {
+                let ready = Arc::new(AtomicBool::new(false));
+
+                let mut levels = mips;
+                levels.insert(0, buffer);
+
+                graphics.uploads
+                    .lock()
+                    .expect("failed to acquire lock")
+                    .push(texture, levels, ready.clone());
+
+                ready
+            },
+            _ => {
+                write_levels(graphics, &texture, &buffer, &mips);
+                Arc::new(AtomicBool::new(true))
+            },
+        };
+
         let sampler = graphics.device.create_sampler(
             &wgpu::SamplerDescriptor {
                 address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -300,7 +479,7 @@ impl Texture {
                 address_mode_w: wgpu::AddressMode::ClampToEdge,
                 mag_filter: wgpu::FilterMode::Linear,
                 min_filter: wgpu::FilterMode::Linear,
-                mipmap_filter: wgpu::FilterMode::Nearest,
+                mipmap_filter,
                 ..Default::default()
             }
         );
@@ -360,8 +539,94 @@ impl Texture {
             //sampler,
             width: dimensions.0 as f32,
             height: dimensions.1 as f32,
+            scale,
             bind_group_layout,
             bind_group,
+            ready,
         }
     }
 }
+
+/// Whole texture at once with `queue.write_texture`
+fn write_levels(graphics: &Graphics, texture: &wgpu::Texture, buffer: &RgbaImage, mips: &[RgbaImage]) {
+    let levels = std::iter::once(buffer).chain(mips);
+
+    for (level, image) in levels.enumerate() {
+        graphics.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture,
+                mip_level: level as u32,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            image,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * image.width()),
+                rows_per_image: Some(image.height()),
+            },
+            wgpu::Extent3d {
+                width: image.width(),
+                height: image.height(),
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+#[test]
+fn test_atlas_image_build() {
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 255, 128]];
+    let images: Vec<_> = colors.iter()
+        .map(|c| DynamicImage::ImageRgba8(RgbaImage::from_pixel(1024, 1024, Rgba(*c))))
+        .collect();
+
+    let atlas = AtlasImage::build(&images).unwrap();
+
+    assert_eq!(atlas.image().dimensions(), (4096, 1024));
+
+    for (i, c) in colors.iter().enumerate() {
+        let x = i as u32 * 1024;
+        assert_eq!(atlas.image().get_pixel(x, 0).0, *c);
+        assert_eq!(atlas.image().get_pixel(x + 1023, 1023).0, *c);
+    }
+
+    // Non rgba8 images are converted
+    let rgb = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(2, 2, image::Rgb([1, 2, 3])));
+    let atlas = AtlasImage::build(&[rgb.clone(), rgb]).unwrap();
+    assert_eq!(atlas.image().get_pixel(3, 1).0, [1, 2, 3, 255]);
+}
+
+#[test]
+fn test_atlas_image_errors() {
+    assert!(matches!(AtlasImage::build(&[]), Err(AtlasError::Empty)));
+
+    let images = [DynamicImage::new_rgba8(2, 2), DynamicImage::new_rgba8(2, 3)];
+    assert!(matches!(
+        AtlasImage::build(&images),
+        Err(AtlasError::SizeMismatch { index: 1, expected: (2, 2), got: (2, 3) })
+    ));
+}
+
+#[test]
+fn test_mip_chain() {
+    assert_eq!(mip_level_count(1, 1), 1);
+    assert_eq!(mip_level_count(128, 128), 8);
+    assert_eq!(mip_level_count(160, 3), 8);
+
+    // Left half is opaque red, right half is transparent black
+    let image = RgbaImage::from_fn(4, 2, |x, _| match x < 2 {
+        true => Rgba([255, 0, 0, 255]),
+        false => Rgba([0, 0, 0, 0]),
+    });
+
+    let chain = mip_chain(&image);
+    let sizes: Vec<_> = chain.iter().map(|x| x.dimensions()).collect();
+    assert_eq!(sizes, [(2, 1), (1, 1)]);
+
+    assert_eq!(chain[0].get_pixel(0, 0).0, [255, 0, 0, 255]);
+    assert_eq!(chain[0].get_pixel(1, 0).0, [0, 0, 0, 0]);
+
+    // Transparent half only lowers alpha, color stays red
+    assert_eq!(chain[1].get_pixel(0, 0).0, [255, 0, 0, 128]);
+}
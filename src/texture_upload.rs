@@ -0,0 +1,196 @@
+use std::{collections::VecDeque, sync::{atomic::{AtomicBool, Ordering}, Arc}};
+
+use image::RgbaImage;
+
+/// Bytes copied into staging buffers per frame,
+/// a 4K background takes about 8 frames
+pub const UPLOAD_BUDGET_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Textures up to this size, mips included, are written with
+/// `queue.write_texture` right away even when staged upload is asked
+pub const DIRECT_UPLOAD_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Row length in a staging buffer, copies
+/// require it to be aligned to 256 bytes
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    (width * 4).div_ceil(align) * align
+}
+
+/// Rows that fit into the `budget`, at least one
+/// so rows wider than the budget still get through
+pub fn chunk_rows(padded_row: u32, rows_left: u32, budget: u64) -> u32 {
+    let fits = (budget / padded_row as u64).min(rows_left as u64) as u32;
+
+    fits.max(1).min(rows_left)
+}
+
+struct UploadJob {
+    texture: wgpu::Texture,
+    /// Base level first, then mips
+    levels: Vec<RgbaImage>,
+    level: usize,
+    /// First row of `level` that isn't copied yet
+    row: u32,
+    ready: Arc<AtomicBool>,
+}
+
+impl UploadJob {
+    #[inline]
+    fn is_done(&self) -> bool {
+        self.level == self.levels.len()
+    }
+
+    /// Records a copy of the next rows of the current level,
+    /// returns the amount of staging bytes it took
+    fn copy_chunk(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, budget: u64) -> u64 {
+        let image = &self.levels[self.level];
+        let (width, height) = image.dimensions();
+
+        let padded_row = padded_bytes_per_row(width);
+        let rows = chunk_rows(padded_row, height - self.row, budget);
+        let row_len = width as usize * 4;
+        let size = padded_row as u64 * rows as u64;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("texture upload staging buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        });
+
+        {
+            let mut mapped = buffer.slice(..).get_mapped_range_mut();
+            let start = self.row as usize * row_len;
+            let src = &image.as_raw()[start..start + rows as usize * row_len];
+
+            for (dst, src) in mapped.chunks_exact_mut(padded_row as usize).zip(src.chunks_exact(row_len)) {
+                dst[..row_len].copy_from_slice(src);
+            }
+        }
+
+        buffer.unmap();
+
+        encoder.copy_buffer_to_texture(
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(rows),
+                },
+            },
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture,
+                mip_level: self.level as u32,
+                origin: wgpu::Origin3d { x: 0, y: self.row, z: 0 },
+            },
+            wgpu::Extent3d {
+                width,
+                height: rows,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.row += rows;
+
+        if self.row == height {
+            self.level += 1;
+            self.row = 0;
+        }
+
+        size
+    }
+}
+
+/// Copies large textures through mapped staging buffers in row
+/// chunks, at most [`UPLOAD_BUDGET_BYTES`] per frame, so a huge
+/// background doesn't stall the frame it was selected on.
+///
+/// Texture handle exists right away, its ready flag
+/// is set once the GPU finished the last copy
+#[derive(Default)]
+pub struct TextureUploader {
+    jobs: VecDeque<UploadJob>,
+}
+
+impl TextureUploader {
+    /// `levels` should match the mip levels of the `texture`
+    pub fn push(&mut self, texture: wgpu::Texture, levels: Vec<RgbaImage>, ready: Arc<AtomicBool>) {
+        self.jobs.push_back(UploadJob {
+            texture,
+            levels,
+            level: 0,
+            row: 0,
+            ready,
+        });
+    }
+
+    /// Textures still being copied
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Submits the next chunks, should be called once per frame
+    pub fn flush(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let _span = tracy_client::span!("texture_upload::flush");
+
+        // Ready flags of copies submitted on previous frames
+        let _ = device.poll(wgpu::Maintain::Poll);
+
+        if self.jobs.is_empty() {
+            return;
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("texture upload encoder"),
+        });
+
+        let mut budget = UPLOAD_BUDGET_BYTES;
+        let mut finished = Vec::new();
+
+        while budget > 0 {
+            let Some(job) = self.jobs.front_mut() else {
+                break;
+            };
+
+            budget = budget.saturating_sub(job.copy_chunk(device, &mut encoder, budget));
+
+            if job.is_done() {
+                let job = self.jobs.pop_front().expect("job should exist");
+                finished.push(job.ready);
+            }
+        }
+
+        queue.submit([encoder.finish()]);
+
+        for ready in finished {
+            queue.on_submitted_work_done(move || ready.store(true, Ordering::Release));
+        }
+
+        tracy_client::plot!("pending texture uploads", self.jobs.len() as f64);
+    }
+}
+
+#[test]
+fn test_upload_chunks() {
+    assert_eq!(padded_bytes_per_row(1), 256);
+    assert_eq!(padded_bytes_per_row(64), 256);
+    assert_eq!(padded_bytes_per_row(100), 512);
+    assert_eq!(padded_bytes_per_row(3840), 15360);
+
+    // 4K background, 2160 rows in 8 frames
+    let padded_row = padded_bytes_per_row(3840);
+    assert_eq!(chunk_rows(padded_row, 2160, UPLOAD_BUDGET_BYTES), 273);
+    assert_eq!(2160_u32.div_ceil(273), 8);
+
+    // Tail of the image and what's left of the budget
+    assert_eq!(chunk_rows(padded_row, 10, UPLOAD_BUDGET_BYTES), 10);
+    assert_eq!(chunk_rows(padded_row, 2160, 20000), 1);
+
+    // Row wider than the whole budget still moves forward
+    assert_eq!(chunk_rows(padded_row, 2160, 0), 1);
+}
@@ -1,87 +1,185 @@
+#[cfg(test)]
+use std::time::Duration;
+
 cfg_if::cfg_if! {
     if #[cfg(target_arch = "wasm32")] {
-        use web_time::{Instant, Duration};
+        use web_time::Instant;
     } else {
-        use std::time::{Duration, Instant};
+        use std::time::Instant;
+    }
+}
+
+/// Source of wall time for the [`Timer`], swapped in tests
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
     }
 }
 
-pub struct Timer {
-    now: Instant,
-    started_at: Instant,
+/// Gameplay clock in milliseconds. Time is advanced explicitly with
+/// [`Timer::update`] once per frame, [`Timer::get_time`] returns the
+/// value of the last update so everything within a frame agrees on it
+pub struct Timer<C: Clock = SystemClock> {
+    clock: C,
+
+    /// Wall time `time` corresponds to
+    synced_at: Instant,
 
-    /// Milliseconds 
-    pub last_time: f64,
+    /// Milliseconds, can be negative during lead-in
+    time: f64,
+
+    /// Playback rate, multiplies elapsed wall time
+    rate: f64,
 
     paused: bool,
 }
 
 impl Timer {
     pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> Timer<C> {
+    /// Paused at 0
+    pub fn with_clock(clock: C) -> Self {
         Self {
-            now: Instant::now(),
-            last_time: 0.0,
+            synced_at: clock.now(),
+            clock,
+            time: 0.0,
+            rate: 1.0,
             paused: true,
-            started_at: Instant::now(),
         }
     }
-    
+
     #[inline]
     pub fn is_paused(&self) -> bool {
-        self.paused == true
+        self.paused
     }
 
-    #[inline]
+    /// Time elapsed since the last sync is kept
     pub fn pause(&mut self) {
+        self.update();
         self.paused = true;
     }
 
-    #[inline]
+    /// Paused interval is skipped, does nothing if already running
     pub fn unpause(&mut self) {
-        self.paused = false;
+        if !self.paused {
+            return;
+        }
 
-        self.now = Instant::now();
+        self.synced_at = self.clock.now();
+        self.paused = false;
     }
-    
+
+    /// Time of the last [`Timer::update`]
     #[inline]
     pub fn get_time(&self) -> f64 {
-        self.last_time
+        self.time
     }
 
+    /// Actual time at this instant, doesn't advance the frame time
+    pub fn now(&self) -> f64 {
+        if self.paused {
+            return self.time;
+        }
+
+        self.time + self.elapsed_since_sync(self.clock.now())
+    }
+
+    /// Judgement timestamp source. Inputs are stamped with it as they
+    /// arrive, instead of the time of the last frame
+    #[inline]
+    pub fn since_start(&self) -> f64 {
+        self.now()
+    }
+
+    #[inline]
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Changes playback rate, time elapsed so far is
+    /// accounted with the previous rate so there is no jumps
+    pub fn set_rate(&mut self, rate: f64) {
+        self.update();
+        self.rate = rate;
+    }
+
+    /// Seeks to `time`, keeps the paused state
     pub fn set_time(&mut self, time: f64) {
-        self.last_time = time;
+        self.time = time;
+        self.synced_at = self.clock.now();
     }
 
+    /// Paused at 0, rate is kept
     pub fn reset_time(&mut self) {
-        self.started_at = Instant::now();
-        self.last_time = 0.0;
+        self.set_time(0.0);
         self.paused = true;
     }
 
-    /// Updates and returns current time
+    /// Advances frame time up to now and returns it
     pub fn update(&mut self) -> f64 {
-        // TODO refactor
         if self.paused {
-            return self.last_time
-        };
+            return self.time;
+        }
 
-        let now = Instant::now();
+        let now = self.clock.now();
 
-        let diff = now.duration_since(self.now);
+        self.time += self.elapsed_since_sync(now);
+        self.synced_at = now;
 
-        // Converting to millis
-        self.last_time += diff.as_secs_f64() * 1000.0;
+        self.time
+    }
 
-        self.now = now;
+    /// Milliseconds, scaled by the rate
+    fn elapsed_since_sync(&self, now: Instant) -> f64 {
+        now.saturating_duration_since(self.synced_at).as_secs_f64() * 1000.0 * self.rate
+    }
+}
 
-        self.last_time
+/// Clock that only moves when told to
+#[cfg(test)]
+#[derive(Clone)]
+struct ManualClock(std::rc::Rc<std::cell::Cell<Instant>>);
+
+#[cfg(test)]
+impl ManualClock {
+    fn new() -> Self {
+        Self(std::rc::Rc::new(std::cell::Cell::new(Instant::now())))
     }
 
-    pub fn since_start(&mut self) -> f64 {
-        (self.now.elapsed().as_secs_f64() * 1000.0) + self.last_time
+    fn advance(&self, ms: u64) {
+        self.0.set(self.0.get() + Duration::from_millis(ms));
     }
 }
 
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.0.get()
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+#[cfg(not(target_arch = "wasm32"))]
 #[test]
 fn test_timer_logic() {
     let mut clock = Timer::new();
@@ -102,3 +200,143 @@ fn test_timer_logic() {
 
     assert!(clock.update() == expected)
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_timer_rate() {
+    let mut clock = Timer::new();
+    clock.set_rate(0.5);
+
+    // Rate doesn't affect paused clock
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(clock.update() == 0.0);
+
+    clock.unpause();
+    std::thread::sleep(Duration::from_millis(40));
+
+    let half = clock.update();
+    assert!(half > 17.0 && half < 25.0);
+
+    // Changing rate mid-playback keeps already elapsed time
+    clock.set_rate(2.0);
+    assert!(clock.get_time() - half < 2.0);
+
+    std::thread::sleep(Duration::from_millis(20));
+    let double = clock.update() - half;
+    assert!(double > 37.0 && double < 45.0);
+
+    // set_time stays absolute
+    clock.set_time(1000.0);
+    assert!(clock.get_time() == 1000.0);
+
+    clock.pause();
+    clock.set_rate(1.0);
+    assert!(clock.update() == 1000.0);
+}
+
+#[test]
+fn test_timer_pause_seek_unpause() {
+    let clock = ManualClock::new();
+    let mut timer = Timer::with_clock(clock.clone());
+
+    timer.unpause();
+    clock.advance(100);
+    assert_eq!(timer.update(), 100.0);
+
+    // Time passed before the pause is kept without an update
+    clock.advance(50);
+    timer.pause();
+    assert_eq!(timer.get_time(), 150.0);
+
+    // Paused interval and seeks don't add up
+    clock.advance(1000);
+    timer.set_time(500.0);
+    clock.advance(1000);
+    assert_eq!(timer.update(), 500.0);
+    assert_eq!(timer.now(), 500.0);
+
+    timer.unpause();
+    clock.advance(20);
+    assert_eq!(timer.update(), 520.0);
+
+    // Seek while running starts counting from the seek
+    clock.advance(30);
+    timer.set_time(100.0);
+    clock.advance(10);
+    assert_eq!(timer.update(), 110.0);
+
+    // Unpausing twice doesn't lose time
+    clock.advance(10);
+    timer.unpause();
+    assert_eq!(timer.update(), 120.0);
+}
+
+#[test]
+fn test_timer_now_between_updates() {
+    let clock = ManualClock::new();
+    let mut timer = Timer::with_clock(clock.clone());
+
+    timer.unpause();
+    clock.advance(40);
+
+    // Frame time stays until the next update
+    assert_eq!(timer.get_time(), 0.0);
+    assert_eq!(timer.now(), 40.0);
+    assert_eq!(timer.since_start(), 40.0);
+
+    assert_eq!(timer.update(), 40.0);
+    assert_eq!(timer.now(), 40.0);
+}
+
+#[test]
+fn test_timer_rate_mid_flight() {
+    let clock = ManualClock::new();
+    let mut timer = Timer::with_clock(clock.clone());
+
+    timer.set_rate(1.5);
+    timer.unpause();
+    clock.advance(100);
+
+    // Elapsed time is counted with the previous rate
+    timer.set_rate(0.5);
+    assert_eq!(timer.get_time(), 150.0);
+
+    clock.advance(100);
+    assert_eq!(timer.update(), 200.0);
+
+    // Paused timer doesn't care about the rate
+    timer.pause();
+    timer.set_rate(2.0);
+    clock.advance(100);
+    assert_eq!(timer.update(), 200.0);
+
+    timer.unpause();
+    clock.advance(100);
+    assert_eq!(timer.update(), 400.0);
+}
+
+#[test]
+fn test_timer_negative_lead_in() {
+    let clock = ManualClock::new();
+    let mut timer = Timer::with_clock(clock.clone());
+
+    timer.set_time(-1000.0);
+    timer.unpause();
+
+    clock.advance(400);
+    assert_eq!(timer.update(), -600.0);
+
+    clock.advance(600);
+    assert_eq!(timer.update(), 0.0);
+
+    clock.advance(250);
+    assert_eq!(timer.update(), 250.0);
+
+    // Reset keeps the rate and pauses at zero
+    timer.set_rate(2.0);
+    timer.reset_time();
+    clock.advance(100);
+    assert!(timer.is_paused());
+    assert_eq!(timer.update(), 0.0);
+    assert_eq!(timer.rate(), 2.0);
+}
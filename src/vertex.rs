@@ -10,11 +10,15 @@ pub struct AtlasQuadVertex {
     pub pos: Vector3::<f32>,
     pub uv: [f32; 2],
     pub alpha: f32,
+    /// Color that replaces the texture color, mixed by `tint[3]`
+    pub tint: [f32; 4],
 }
 
 impl AtlasQuadVertex {
-    const ATTRIBS: [wgpu::VertexAttribute; 3] = 
-        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Float32];
+    pub const NO_TINT: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
+
+    const ATTRIBS: [wgpu::VertexAttribute; 4] = 
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Float32, 3 => Float32x4];
 
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
@@ -0,0 +1,126 @@
+use std::sync::{Arc, RwLock};
+
+use cgmath::Vector2;
+
+use rosu::{alloc_counter::{count_allocations, CountingAllocator}, config::Config, particles::{ParticleKind, ParticleSystem, MAX_PARTICLES}, renderer::particles::ParticleRenderer, graphics::Graphics, hit_objects::{hit_window::HitWindow, Object}, math::calculate_preempt_fadein, osu_renderer::OsuRenderer, skin_manager::SkinManager};
+use rosu_map::Beatmap;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 64;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+struct Fixture {
+    renderer: OsuRenderer,
+    objects: Vec<Object>,
+    preempt: f32,
+    fadein: f32,
+    hit_window: HitWindow,
+    queue: Vec<usize>,
+    judgements_queue: Vec<usize>,
+}
+
+impl Fixture {
+    /// Prepares frames at `times` the same way `OsuState` does,
+    /// returns allocations made by the prepare path
+    fn prepare_frames(&mut self, times: &[f64]) -> usize {
+        let mut total = 0;
+
+        for time in times {
+            let (_, allocations) = count_allocations(|| {
+                for (i, obj) in self.objects.iter().enumerate().rev() {
+                    self.judgements_queue.push(i);
+
+                    if obj.is_visible(*time, self.preempt, &self.hit_window) {
+                        self.queue.push(i);
+                    }
+                }
+
+                self.renderer.prepare_judgements(*time, &self.judgements_queue, &self.objects);
+                self.renderer.prepare_objects(
+                    *time, self.preempt, self.fadein,
+                    &self.queue, &mut self.objects, &self.hit_window
+                );
+                self.renderer.clear_buffers();
+
+                self.queue.clear();
+                self.judgements_queue.clear();
+            });
+
+            total += allocations;
+        }
+
+        total
+    }
+}
+
+#[test]
+fn test_steady_state_prepare_does_not_allocate() {
+    let Some(graphics) = pollster::block_on(Graphics::headless_from_env(WIDTH, HEIGHT, FORMAT)) else {
+        eprintln!("No wgpu adapter available, skipping");
+        return;
+    };
+    let graphics = Arc::new(graphics);
+
+    let skin_manager = Arc::new(RwLock::new(SkinManager::from_path("skin", &graphics)));
+    let config = Arc::new(RwLock::new(Config::default()));
+    let mut renderer = OsuRenderer::new(graphics.clone(), config, skin_manager);
+
+    let beatmap = Beatmap::from_path("tests/data/gameplay/overlapping_sliders.osu").unwrap();
+    renderer.on_cs_change(beatmap.circle_size);
+
+    let objects = Object::from_rosu(&beatmap).unwrap();
+    let (preempt, fadein) = calculate_preempt_fadein(beatmap.approach_rate);
+
+    renderer.reserve_instances(objects.len());
+
+    let mut fixture = Fixture {
+        renderer,
+        preempt,
+        fadein,
+        hit_window: HitWindow::from_od(beatmap.overall_difficulty),
+        queue: Vec::with_capacity(objects.len()),
+        judgements_queue: Vec::with_capacity(objects.len()),
+        objects,
+    };
+
+    let times = [0.0, 500.0, 1000.0, 1800.0, 2500.0];
+
+    // First frames bake slider textures and grow instance buffers
+    fixture.prepare_frames(&times);
+
+    assert_eq!(fixture.prepare_frames(&times), 0);
+}
+
+#[test]
+fn test_particle_spawn_does_not_allocate() {
+    let mut particles = ParticleSystem::default();
+
+    // Mashing well past the capacity reuses the slots
+    let (alive, allocations) = count_allocations(|| {
+        for i in 0..MAX_PARTICLES * 4 {
+            particles.spawn(Vector2::new(i as f64, 0.0), i as f64, ParticleKind::CursorRipple);
+        }
+
+        particles.alive(MAX_PARTICLES as f64 * 4.0).count()
+    });
+
+    assert_eq!(allocations, 0);
+    assert!(alive <= MAX_PARTICLES);
+
+    let Some(graphics) = pollster::block_on(Graphics::headless_from_env(WIDTH, HEIGHT, FORMAT)) else {
+        eprintln!("No wgpu adapter available, skipping");
+        return;
+    };
+
+    let mut renderer = ParticleRenderer::new(Arc::new(graphics));
+
+    let time = MAX_PARTICLES as f64 * 4.0;
+    let (_, allocations) = count_allocations(|| {
+        renderer.prepare(&particles, time, 1.0, |x| x);
+    });
+
+    assert_eq!(allocations, 0);
+}
@@ -1,6 +1,7 @@
-use std::{path::PathBuf, thread::sleep, time::Duration};
+use std::{path::{Path, PathBuf}, sync::Arc, thread::sleep, time::{Duration, Instant}};
 
-use rosu::osu_db::OsuDatabase;
+use rosu::{db_worker::{DbResponse, DbWorker}, osu_db::{shift_index_after_delete, stable_import::StableData, BeatmapFilter, DbBeatmapEntry, LocalScore, OsuDatabase, SavedSelection}, search_query::{creator_query, parse_search_query}, session_stats::{DayStats, PlayRecord}};
+use rosu_map::section::general::GameMode;
 use testdir::testdir;
 
 #[test]
@@ -40,3 +41,895 @@ fn test_osu_database_scanning() {
 
     assert_eq!(&database.get_beatmap_by_hash(expected_hash).unwrap().hash, expected_hash);
 }
+
+#[test]
+fn test_osu_database_scanning_stores_paths() {
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+    let songs_path = PathBuf::from("tests/data/songs_folder");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+
+    let (_tx, rx) = oneshot::channel();
+
+    database.scan_beatmaps(&songs_path, rx);
+
+    sleep(Duration::from_secs(2));
+    assert_eq!(database.beatmaps_amount(), 1);
+
+    let entry = database.get_beatmap_by_hash("e2f3e496b1014c84c998be738887e315").unwrap();
+
+    assert_eq!(entry.background_file.as_deref(), Some("bg.jpg"));
+    assert_eq!(entry.audio_file.as_deref(), Some("audio.mp3"));
+    assert_eq!(entry.preview_time, Some(147259));
+    assert_eq!(entry.mode, Some(0));
+    assert_eq!(entry.source.as_deref(), Some(""));
+    assert_eq!(entry.tags.as_deref(), Some("laos drum and bass dnb marathon collaboration reform"));
+}
+
+#[test]
+fn test_osu_database_migration() {
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+
+    // Schema before paths were stored
+    {
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch("
+            CREATE TABLE beatmaps (
+                id INTEGER PRIMARY KEY, 
+                beatmapset_id INTEGER, 
+                beatmap_id INTEGER, 
+                title TEXT, 
+                artist TEXT, 
+                creator TEXT, 
+                version TEXT,
+                path TEXT,
+                hash TEXT NOT NULL
+            );
+
+            INSERT INTO beatmaps (beatmapset_id, beatmap_id, title, artist, creator, version, path, hash)
+            VALUES (1, 1, 'title', 'artist', 'creator', 'version', 'a.osu', 'aaaa');
+        ").unwrap();
+    }
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+    assert_eq!(database.beatmaps_amount(), 1);
+
+    let entry = database.get_beatmap_by_hash("aaaa").unwrap();
+
+    assert_eq!(entry.title, "title");
+    assert_eq!(entry.background_file, None);
+    assert_eq!(entry.audio_file, None);
+    assert_eq!(entry.preview_time, None);
+    assert_eq!(entry.mode, None);
+    assert_eq!(entry.source, None);
+    assert_eq!(entry.tags, None);
+
+    // Migrating twice shouldn't fail
+    drop(database);
+    assert!(OsuDatabase::new_from_path(&db_path).is_ok());
+}
+
+#[test]
+fn test_osu_database_delete_beatmap() {
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+    let songs_path = PathBuf::from("tests/data/songs_folder");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+
+    let (_tx, rx) = oneshot::channel();
+
+    database.scan_beatmaps(&songs_path, rx);
+
+    sleep(Duration::from_secs(2));
+    assert_eq!(database.beatmaps_amount(), 1);
+
+    let entry = database.get_beatmap_by_hash("e2f3e496b1014c84c998be738887e315").unwrap();
+
+    assert_eq!(database.get_beatmap_index(entry.id), Some(0));
+    assert_eq!(database.delete_beatmap(entry.id).unwrap(), 1);
+
+    assert_eq!(database.beatmaps_amount(), 0);
+    assert_eq!(database.get_beatmap_index(entry.id), None);
+    assert!(database.cache.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_osu_database_delete_beatmaps_in_directory() {
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+    let songs_path = PathBuf::from("tests/data/songs_folder");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+
+    let (_tx, rx) = oneshot::channel();
+
+    database.scan_beatmaps(&songs_path, rx);
+
+    sleep(Duration::from_secs(2));
+    assert_eq!(database.beatmaps_amount(), 1);
+
+    let entry = database.get_beatmap_by_hash("e2f3e496b1014c84c998be738887e315").unwrap();
+    let dir = entry.path.parent().unwrap();
+
+    // Sibling directory with the same prefix shouldn't match
+    let sibling = PathBuf::from(format!("{}2", dir.display()));
+    assert!(database.get_beatmap_indexes_in_directory(&sibling).is_empty());
+    assert_eq!(database.delete_beatmaps_in_directory(&sibling).unwrap(), 0);
+
+    assert_eq!(database.get_beatmap_indexes_in_directory(dir), vec![0]);
+    assert_eq!(database.delete_beatmaps_in_directory(dir).unwrap(), 1);
+    assert_eq!(database.beatmaps_amount(), 0);
+}
+
+#[test]
+fn test_shift_index_after_delete() {
+    // Deleted rows after selection doesn't change anything
+    assert_eq!(shift_index_after_delete(2, &[3, 4], 8), 2);
+
+    // Deleted rows before selection shifts it up
+    assert_eq!(shift_index_after_delete(5, &[0, 1], 8), 3);
+
+    // Selected row itself is deleted, next one takes its place
+    assert_eq!(shift_index_after_delete(5, &[5], 9), 5);
+
+    // Last row deleted, clamping to the new last one
+    assert_eq!(shift_index_after_delete(9, &[9], 9), 8);
+
+    // Everything is deleted
+    assert_eq!(shift_index_after_delete(0, &[0], 0), 0);
+}
+
+fn synthetic_entry(i: usize) -> DbBeatmapEntry {
+    DbBeatmapEntry {
+        id: 0,
+        beatmap_id: i as i64,
+        beatmapset_id: i as i64,
+        title: format!("title {i}"),
+        artist: "artist".to_string(),
+        creator: "creator".to_string(),
+        version: "version".to_string(),
+        path: PathBuf::from(format!("{i}/{i}.osu")),
+        hash: format!("{i:032x}"),
+        background_file: None,
+        audio_file: None,
+        preview_time: None,
+        mode: Some(0),
+        source: None,
+        tags: None,
+    }
+}
+
+#[test]
+fn test_db_worker_scrolling() {
+    const ROWS: usize = 10_000;
+    const VISIBLE_ROWS: usize = 12;
+    const ROWS_PER_FRAME: usize = 7;
+
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+    let entries: Vec<_> = (0..ROWS).map(synthetic_entry).collect();
+    database.insert_beatmaps(&entries).unwrap();
+
+    assert_eq!(database.beatmaps_amount(), ROWS);
+    assert_eq!(database.get_beatmap_by_index(0).unwrap().title, "title 0");
+    assert_eq!(database.search_beatmaps("title 9999"), vec![9999]);
+
+    let database = Arc::new(database);
+    let mut worker = DbWorker::spawn(database.clone());
+
+    // Nothing on the scrolling path is allowed to touch the cache mutex,
+    // if it does the worker gets stuck and the final window never arrives
+    let _cache = database.cache.lock().unwrap();
+
+    let mut last_min = 0;
+
+    for frame in 0..ROWS / ROWS_PER_FRAME {
+        let min = (frame * ROWS_PER_FRAME).min(ROWS - VISIBLE_ROWS);
+        let sent = worker.requests_sent();
+        let start = Instant::now();
+
+        while worker.poll().is_some() {}
+        worker.request_range(min, min + VISIBLE_ROWS);
+
+        let window = worker.window();
+        assert!(window.entries.len() <= VISIBLE_ROWS);
+
+        assert!(worker.requests_sent() - sent <= 1, "frame {frame} issued more than one query");
+        assert!(start.elapsed() < Duration::from_millis(50), "frame {frame} blocked");
+
+        last_min = min;
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    while !worker.window().contains(last_min, last_min + VISIBLE_ROWS) {
+        assert!(Instant::now() < deadline, "newest range never arrived");
+
+        sleep(Duration::from_millis(1));
+        while worker.poll().is_some() {}
+    }
+
+    let window = worker.window();
+
+    assert_eq!(window.total, ROWS);
+    assert_eq!(window.entries.len(), VISIBLE_ROWS);
+    assert_eq!(window.get(last_min).unwrap().title, format!("title {last_min}"));
+
+    assert_eq!(worker.queries_issued(), worker.requests_sent());
+}
+
+#[test]
+fn test_stable_import_collections() {
+    const IMPORTED: &str = "e2f3e496b1014c84c998be738887e315";
+    const MISSING: &str = "0123456789abcdef0123456789abcdef";
+
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+
+    let mut entries: Vec<_> = (0..5).map(synthetic_entry).collect();
+    entries[2].hash = IMPORTED.to_string();
+    database.insert_beatmaps(&entries).unwrap();
+
+    let data = StableData::load("tests/data/stable").unwrap();
+    let summary = data.import(&database).unwrap();
+
+    assert_eq!(summary.collections, 2);
+    assert_eq!(summary.scores, 3);
+    assert_eq!(summary.unresolved, 1);
+    assert_eq!(summary.in_stable_songs, 1);
+    assert_eq!(database.unresolved_hashes(), vec![MISSING.to_string()]);
+
+    let collections = database.collections();
+    assert_eq!(collections.len(), 2);
+
+    let farm = &collections[0];
+    assert_eq!(farm.name, "Farm");
+    assert_eq!((farm.resolved, farm.beatmaps), (1, 2));
+    assert_eq!(collections[1].beatmaps, 0);
+
+    // Filtered list only has resolved beatmaps of the collection
+    assert_eq!(database.beatmaps_amount_in(Some(farm.id)), 1);
+    assert_eq!(database.beatmaps_range_in(Some(farm.id), 0, 10)[0].hash, IMPORTED);
+    assert_eq!(database.get_beatmap_by_index_in(Some(farm.id), 0).unwrap().title, "title 2");
+    assert!(database.get_beatmap_by_index_in(Some(farm.id), 1).is_none());
+    assert_eq!(database.beatmaps_amount_in(None), 5);
+
+    // Importing again doesn't duplicate anything
+    let summary = data.import(&database).unwrap();
+    assert_eq!(summary.scores, 0);
+    assert_eq!(database.collections(), collections);
+
+    // Missing beatmap attaches once it's imported
+    let mut missing = synthetic_entry(5);
+    missing.hash = MISSING.to_string();
+    database.insert_beatmaps(&[missing]).unwrap();
+
+    assert!(database.unresolved_hashes().is_empty());
+    assert_eq!(database.beatmaps_amount_in(Some(farm.id)), 2);
+
+    assert!(StableData::load("tests/data/songs_folder").is_err());
+}
+
+#[test]
+fn test_collections() {
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+    let entries: Vec<_> = (0..6).map(synthetic_entry).collect();
+    database.insert_beatmaps(&entries).unwrap();
+
+    let jumps = database.create_collection("Jumps").unwrap();
+    let streams = database.create_collection("Streams").unwrap();
+    assert!(database.create_collection("Jumps").is_err());
+
+    assert!(database.add_to_collection(jumps, &entries[1].hash).unwrap());
+    assert!(database.add_to_collection(jumps, &entries[4].hash).unwrap());
+    assert!(!database.add_to_collection(jumps, &entries[4].hash).unwrap());
+    assert!(database.add_to_collection(streams, &entries[4].hash).unwrap());
+
+    // Kept in creation order
+    let collections = database.collections();
+    assert_eq!(collections.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), ["Jumps", "Streams"]);
+    assert_eq!((collections[0].resolved, collections[0].beatmaps), (2, 2));
+
+    assert_eq!(database.beatmaps_amount_in(Some(jumps)), 2);
+    assert_eq!(database.get_beatmap_by_index_in(Some(jumps), 1).unwrap().title, "title 4");
+    assert_eq!(database.search_beatmaps_in(Some(jumps), "title 4"), vec![1]);
+    assert_eq!(database.search_beatmaps_in(Some(streams), "title 1"), Vec::<usize>::new());
+
+    assert!(database.remove_from_collection(jumps, &entries[1].hash).unwrap());
+    assert!(!database.remove_from_collection(jumps, &entries[1].hash).unwrap());
+    assert_eq!(database.beatmaps_range_in(Some(jumps), 0, 10).len(), 1);
+
+    database.rename_collection(streams, "Tech").unwrap();
+    database.delete_collection(jumps).unwrap();
+
+    let collections = database.collections();
+    assert_eq!(collections.len(), 1);
+    assert_eq!(collections[0].name, "Tech");
+    assert_eq!(database.beatmaps_amount_in(Some(jumps)), 0);
+
+    // Beatmaps themselves are untouched
+    assert_eq!(database.beatmaps_amount(), 6);
+}
+
+#[test]
+fn test_search_beatmaps_query() {
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+
+    let mut entries: Vec<_> = (0..5).map(synthetic_entry).collect();
+    entries[1].tags = Some("touhou remix".to_string());
+    entries[2].title = "Touhou medley".to_string();
+    entries[3].source = Some("Touhou Project".to_string());
+    entries[3].creator = "Some Mapper".to_string();
+    entries[4].creator = "Some Mapper 2".to_string();
+    entries[4].title = "100%_done".to_string();
+    database.insert_beatmaps(&entries).unwrap();
+
+    // Title matches go before tags and source ones
+    assert_eq!(database.search_beatmaps("touhou"), vec![2, 1, 3]);
+    assert_eq!(database.search_beatmaps("TOUHOU remix"), vec![1]);
+    assert_eq!(database.search_beatmaps("\"touhou medley\""), vec![2]);
+
+    // Quoted creator is an exact match
+    assert_eq!(database.search_beatmaps("creator:mapper"), vec![3, 4]);
+    assert_eq!(database.search_beatmaps(&creator_query("some mapper")), vec![3]);
+    assert_eq!(database.search_beatmaps("creator:mapper touhou"), vec![3]);
+
+    // LIKE wildcards are literal
+    assert_eq!(database.search_beatmaps("%"), vec![4]);
+    assert_eq!(database.search_beatmaps("_"), vec![4]);
+
+    // Star ratings aren't stored, so they don't filter anything
+    assert_eq!(database.search_beatmaps("star>5").len(), 5);
+
+    let filter = BeatmapFilter {
+        collection: None,
+        query: parse_search_query("creator:mapper"),
+    };
+
+    assert_eq!(database.beatmaps_amount_matching(&filter), 2);
+    assert_eq!(database.get_beatmap_by_index_matching(&filter, 1).unwrap().hash, entries[4].hash);
+    assert_eq!(database.beatmaps_range_matching(&filter, 0, 10).len(), 2);
+}
+
+#[test]
+fn test_db_worker_search_filter() {
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+    let entries: Vec<_> = (0..20).map(synthetic_entry).collect();
+    database.insert_beatmaps(&entries).unwrap();
+
+    let mut worker = DbWorker::spawn(Arc::new(database));
+
+    worker.set_search("\"title 1\"");
+    worker.request_range(0, 20);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    // `title 1` and `title 10..19`
+    while worker.window().total != 11 {
+        assert!(Instant::now() < deadline, "filtered window never arrived");
+
+        sleep(Duration::from_millis(1));
+        while worker.poll().is_some() {}
+        worker.request_range(0, 20);
+    }
+
+    assert_eq!(worker.search(), "\"title 1\"");
+    assert_eq!(worker.window().get(1).unwrap().title, "title 10");
+
+    // Clearing the search brings the whole list back
+    worker.set_search("");
+
+    while worker.window().total != 20 {
+        assert!(Instant::now() < deadline, "unfiltered window never arrived");
+
+        sleep(Duration::from_millis(1));
+        while worker.poll().is_some() {}
+        worker.request_range(0, 20);
+    }
+}
+
+#[test]
+fn test_db_worker_live_inserts() {
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+
+    let entries: Vec<_> = (0..50).map(synthetic_entry).collect();
+    OsuDatabase::new_from_path(&db_path).unwrap().insert_beatmaps(&entries).unwrap();
+
+    // Reopened, so the existing beatmaps are not new anymore
+    let database = Arc::new(OsuDatabase::new_from_path(&db_path).unwrap());
+    let mut worker = DbWorker::spawn(database.clone());
+
+    worker.request_range(0, 12);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    while worker.window().total != 50 {
+        assert!(Instant::now() < deadline, "window never arrived");
+
+        sleep(Duration::from_millis(1));
+        while worker.poll().is_some() {}
+        worker.request_range(0, 12);
+    }
+
+    let selected = worker.window().get(10).unwrap().clone();
+    assert!(!worker.is_new(&selected));
+
+    // Import lands behind the open list
+    let new_entries: Vec<_> = (50..150).map(synthetic_entry).collect();
+    database.insert_beatmaps(&new_entries).unwrap();
+
+    wait_for_response(&mut worker, |response| matches!(response, DbResponse::Changed));
+
+    worker.request_hash_index(selected.hash.clone());
+
+    wait_for_response(&mut worker, |response| match response {
+        DbResponse::HashIndex(hash, index) => {
+            assert_eq!(hash, selected.hash);
+            assert_eq!(database.get_beatmap_by_index(index.unwrap()).unwrap().hash, selected.hash);
+            true
+        },
+        _ => false,
+    });
+
+    // Range past the old end is fetched with the new total
+    worker.request_range(140, 160);
+
+    while worker.window().total != 150 || !worker.window().contains(140, 160) {
+        assert!(Instant::now() < deadline, "refreshed window never arrived");
+
+        sleep(Duration::from_millis(1));
+        while worker.poll().is_some() {}
+        worker.request_range(140, 160);
+    }
+
+    let window = worker.window().clone();
+    assert_eq!(window.entries.len(), 10);
+    assert!(window.get(155).is_none());
+    assert_eq!(window.get(145).unwrap().hash, new_entries[95].hash);
+    assert!(worker.is_new(window.get(149).unwrap()));
+
+    // Lookup follows filters too, `title 1` and `title 10..19`
+    let filter = BeatmapFilter {
+        collection: None,
+        query: parse_search_query("\"title 1\""),
+    };
+
+    assert_eq!(database.get_beatmap_index_matching(&filter, &entries[12].hash), Some(3));
+    assert_eq!(database.get_beatmap_index_matching(&filter, &entries[2].hash), None);
+}
+
+/// Polls the worker until `f` accepts a response
+#[test]
+fn test_saved_selection() {
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+    let entries: Vec<_> = (0..10).map(synthetic_entry).collect();
+    database.insert_beatmaps(&entries).unwrap();
+
+    let collection = database.create_collection("Farm").unwrap();
+    database.add_to_collection(collection, &entries[7].hash).unwrap();
+    database.add_to_collection(collection, &entries[2].hash).unwrap();
+
+    assert_eq!(database.saved_selection(), None);
+
+    database.save_selection(&SavedSelection {
+        hash: entries[1].hash.clone(),
+        collection: None,
+        search: String::new(),
+    }).unwrap();
+
+    // Only the latest one is kept
+    let saved = SavedSelection {
+        hash: entries[7].hash.clone(),
+        collection: Some(collection),
+        search: "title".to_owned(),
+    };
+    database.save_selection(&saved).unwrap();
+    drop(database);
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+    assert_eq!(database.saved_selection(), Some(saved.clone()));
+
+    // Same md5 resolves to a different index in every list
+    let all = BeatmapFilter::default();
+    let in_collection = BeatmapFilter::collection(Some(collection));
+    let searched = BeatmapFilter { collection: None, query: parse_search_query("\"title 7\"") };
+
+    assert_eq!(database.get_beatmap_index_matching(&all, &saved.hash), Some(7));
+    assert_eq!(database.get_beatmap_index_matching(&in_collection, &saved.hash), Some(1));
+    assert_eq!(database.get_beatmap_index_matching(&searched, &saved.hash), Some(0));
+    assert_eq!(database.get_beatmap_index_matching(&in_collection, &entries[1].hash), None);
+
+    // Deleted beatmap is not found, song select falls back to the first one
+    let id = database.get_beatmap_by_hash(&saved.hash).unwrap().id;
+    database.delete_beatmap(id).unwrap();
+    assert_eq!(database.get_beatmap_index_matching(&all, &saved.hash), None);
+}
+
+fn wait_for_response(worker: &mut DbWorker, mut f: impl FnMut(DbResponse) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    loop {
+        assert!(Instant::now() < deadline, "response never arrived");
+
+        match worker.poll() {
+            Some(response) => if f(response) {
+                return;
+            },
+            None => sleep(Duration::from_millis(1)),
+        }
+    }
+}
+
+#[test]
+fn test_db_worker_collection_filter() {
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+    let entries: Vec<_> = (0..10).map(synthetic_entry).collect();
+    database.insert_beatmaps(&entries).unwrap();
+
+    let mut worker = DbWorker::spawn(Arc::new(database));
+
+    worker.create_collection("Farm", Some(entries[3].hash.clone()));
+
+    let mut collection = None;
+    wait_for_response(&mut worker, |response| match response {
+        DbResponse::Collections(collections) => {
+            collection = collections.first().map(|x| x.id);
+            true
+        },
+        _ => false,
+    });
+
+    let collection = collection.unwrap();
+    worker.add_to_collection(collection, entries[7].hash.clone());
+    worker.set_collection(Some(collection));
+    worker.request_range(0, 10);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    while worker.window().total != 2 {
+        assert!(Instant::now() < deadline, "filtered window never arrived");
+
+        sleep(Duration::from_millis(1));
+        while worker.poll().is_some() {}
+        worker.request_range(0, 10);
+    }
+
+    let window = worker.window().clone();
+    assert_eq!(window.entries.len(), 2);
+    assert_eq!(window.get(1).unwrap().title, "title 7");
+
+    // Selecting by index is inside of the collection as well
+    worker.request_index(1);
+
+    wait_for_response(&mut worker, |response| match response {
+        DbResponse::Index(1, entry) => {
+            assert_eq!(entry.unwrap().hash, entries[7].hash);
+            true
+        },
+        _ => false,
+    });
+}
+
+#[test]
+fn test_session_stats_per_day() {
+    // Noon UTC, a minute later is still the same local day in every timezone
+    const DAY_START: i64 = 1_700_049_600;
+    const DAY: i64 = 24 * 60 * 60;
+
+    let tmp_dir = testdir!();
+    let db_path = tmp_dir.join("rosu.db");
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+
+    let first = PlayRecord {
+        started_at: DAY_START,
+        play_time_ms: 90_000,
+        completed: true,
+        retry: false,
+        x300: 100,
+        x100: 5,
+        x50: 1,
+        miss: 2,
+    };
+
+    let second = PlayRecord {
+        started_at: DAY_START + 60,
+        play_time_ms: 30_000,
+        completed: false,
+        retry: true,
+        x300: 10,
+        ..Default::default()
+    };
+
+    let next_day = PlayRecord {
+        started_at: DAY_START + DAY,
+        ..first.clone()
+    };
+
+    assert_eq!(database.day_stats(DAY_START), DayStats::default());
+
+    database.record_plays(&[first, second]).unwrap();
+    database.record_plays(&[next_day]).unwrap();
+
+    let stats = database.day_stats(DAY_START);
+
+    assert_eq!(stats, DayStats {
+        plays_started: 2,
+        plays_completed: 1,
+        retries: 1,
+        play_time_ms: 120_000,
+        x300: 110,
+        x100: 5,
+        x50: 1,
+        miss: 2,
+    });
+    assert_eq!(stats.judgements(), 118);
+
+    // Play after the day boundary goes to its own day
+    let stats = database.day_stats(DAY_START + DAY);
+    assert_eq!((stats.plays_started, stats.play_time_ms), (1, 90_000));
+
+    assert_eq!(database.day_stats(DAY_START - DAY), DayStats::default());
+}
+
+#[test]
+fn test_file_hash_cache_invalidation() {
+    let tmp_dir = testdir!();
+    let database = OsuDatabase::new_from_path(tmp_dir.join("rosu.db")).unwrap();
+
+    let file_path = tmp_dir.join("bg.jpg");
+    std::fs::write(&file_path, b"first").unwrap();
+
+    assert_eq!(database.file_hash(&file_path, b"first"), md5::compute(b"first"));
+
+    // File is untouched, so the stored hash is used without hashing the bytes
+    assert_eq!(database.file_hash(&file_path, b"ignored"), md5::compute(b"first"));
+
+    // Same size, only the modification time tells them apart
+    std::fs::write(&file_path, b"other").unwrap();
+    let file = std::fs::File::options().write(true).open(&file_path).unwrap();
+    file.set_modified(std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(60)).unwrap();
+
+    assert_eq!(database.file_hash(&file_path, b"other"), md5::compute(b"other"));
+    assert_eq!(database.file_hash(&file_path, b"ignored"), md5::compute(b"other"));
+
+    // Not existing files are hashed every time
+    let missing = tmp_dir.join("missing.mp3");
+    assert_eq!(database.file_hash(&missing, b"audio"), md5::compute(b"audio"));
+}
+
+#[test]
+fn test_local_score_keeps_best_replay() {
+    const HASH: &str = "e2f3e496b1014c84c998be738887e315";
+
+    let tmp_dir = testdir!();
+    let database = OsuDatabase::new_from_path(tmp_dir.join("rosu.db")).unwrap();
+
+    let score = |replay_hash: &str, score: i64, max_combo: u32| LocalScore {
+        beatmap_hash: HASH.to_owned(),
+        replay_hash: replay_hash.to_owned(),
+        player: "Player".to_owned(),
+        score,
+        max_combo,
+        timestamp: 1_700_000_000,
+        ..Default::default()
+    };
+
+    assert_eq!(database.best_score_replay(HASH), None);
+
+    assert!(database.store_local_score(&score("a", 1000, 10), b"first").unwrap());
+    assert_eq!(database.best_score_replay(HASH).as_deref(), Some(&b"first"[..]));
+
+    // Worse play and a tie don't replace the stored replay
+    assert!(!database.store_local_score(&score("b", 500, 20), b"worse").unwrap());
+    assert!(!database.store_local_score(&score("c", 1000, 10), b"tie").unwrap());
+    assert_eq!(database.best_score_replay(HASH).as_deref(), Some(&b"first"[..]));
+
+    // Same score with a higher combo is better
+    assert!(database.store_local_score(&score("d", 1000, 11), b"best").unwrap());
+    assert_eq!(database.best_score_replay(HASH).as_deref(), Some(&b"best"[..]));
+
+    assert_eq!(database.best_score_replay("other"), None);
+}
+
+#[test]
+fn test_local_score_best_progression() {
+    const HASH: &str = "e2f3e496b1014c84c998be738887e315";
+
+    let tmp_dir = testdir!();
+    let database = OsuDatabase::new_from_path(tmp_dir.join("rosu.db")).unwrap();
+
+    let score = |score: i64, progression: &[u8]| LocalScore {
+        beatmap_hash: HASH.to_owned(),
+        player: "Player".to_owned(),
+        score,
+        progression: progression.to_vec(),
+        ..Default::default()
+    };
+
+    assert_eq!(database.best_score_progression(HASH), None);
+
+    database.store_local_score(&score(1000, &[1; 8]), b"first").unwrap();
+    database.store_local_score(&score(500, &[2; 8]), b"worse").unwrap();
+    assert_eq!(database.best_score_progression(HASH), Some(vec![1; 8]));
+
+    database.store_local_score(&score(2000, &[3; 16]), b"best").unwrap();
+    assert_eq!(database.best_score_progression(HASH), Some(vec![3; 16]));
+}
+
+/// Songs folder with a beatmap per directory, sorted names
+/// don't match the order they are created in
+fn scan_fixture(root: &Path) {
+    const MAPS: &[&str] = &[
+        "slider",
+        "jumps_simple",
+        "aozora_hard",
+        "single_hit_circle",
+        "koise",
+        "slider_two_ticks",
+    ];
+
+    for (i, name) in MAPS.iter().enumerate() {
+        let dir = root.join(format!("{} {name}", MAPS.len() - i));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(format!("tests/data/gameplay/{name}.osu"), dir.join(format!("{name}.osu"))).unwrap();
+        std::fs::write(dir.join("audio.mp3"), b"not a beatmap").unwrap();
+    }
+}
+
+fn scanned_beatmaps(database: &OsuDatabase) -> Vec<(String, PathBuf)> {
+    let mut entries: Vec<_> = (0..database.beatmaps_amount())
+        .map(|i| database.get_beatmap_by_index(i).unwrap())
+        .map(|x| (x.hash, x.path))
+        .collect();
+
+    entries.sort();
+    entries
+}
+
+#[test]
+fn test_scan_resumes_from_checkpoint() {
+    let tmp_dir = testdir!();
+    let songs_path = tmp_dir.join("Songs");
+    scan_fixture(&songs_path);
+
+    let expected = OsuDatabase::new_from_path(tmp_dir.join("full.db")).unwrap();
+    let full = expected.scan_beatmaps_blocking(&songs_path, false, |_| false);
+
+    assert!(full.finished);
+    assert_eq!(full.files_read, 6);
+    assert_eq!(full.added, 6);
+    assert_eq!(expected.interrupted_scan(), None);
+
+    let db_path = tmp_dir.join("rosu.db");
+
+    {
+        let database = OsuDatabase::new_from_path(&db_path).unwrap();
+        let stopped = database.scan_beatmaps_blocking(&songs_path, false, |stats| stats.files_read >= 3);
+
+        assert!(!stopped.finished);
+        assert_eq!(stopped.added, 3);
+    }
+
+    // Restart finds the checkpoint of the last fully scanned directory
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+    let checkpoint = database.interrupted_scan().unwrap();
+
+    assert_eq!(checkpoint.root, std::path::absolute(&songs_path).unwrap());
+    assert_eq!(checkpoint.last_directory.as_deref(), Some("3 single_hit_circle"));
+    assert_eq!(checkpoint.directories_done, 3);
+
+    let resumed = database.scan_beatmaps_blocking(&checkpoint.root, true, |_| false);
+
+    assert!(resumed.finished);
+    assert_eq!(resumed.added, 3);
+    assert!(resumed.files_read < full.files_read);
+    assert!(resumed.directories_read < full.directories_read);
+
+    assert_eq!(scanned_beatmaps(&database), scanned_beatmaps(&expected));
+
+    // Finished scan leaves nothing to resume
+    drop(database);
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+    assert_eq!(database.interrupted_scan(), None);
+}
+
+#[test]
+fn test_scan_restart_ignores_checkpoint() {
+    let tmp_dir = testdir!();
+    let songs_path = tmp_dir.join("Songs");
+    scan_fixture(&songs_path);
+
+    let db_path = tmp_dir.join("rosu.db");
+
+    {
+        let database = OsuDatabase::new_from_path(&db_path).unwrap();
+        database.scan_beatmaps_blocking(&songs_path, false, |stats| stats.files_read >= 2);
+    }
+
+    let database = OsuDatabase::new_from_path(&db_path).unwrap();
+    assert!(database.interrupted_scan().is_some());
+
+    // Every directory is walked again, committed beatmaps are only hashed
+    let restarted = database.scan_beatmaps_blocking(&songs_path, false, |_| false);
+
+    assert!(restarted.finished);
+    assert_eq!(restarted.files_read, 6);
+    assert_eq!(restarted.added, 4);
+    assert_eq!(database.beatmaps_amount(), 6);
+}
+
+#[test]
+fn test_scan_stores_unsupported_modes() {
+    let tmp_dir = testdir!();
+    let songs_path = tmp_dir.join("Songs");
+
+    for (dir, file) in [("1 slider", "tests/data/gameplay/slider.osu"), ("2 mania", "tests/data/other/mania.osu")] {
+        let dir = songs_path.join(dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(file, dir.join(Path::new(file).file_name().unwrap())).unwrap();
+    }
+
+    let database = OsuDatabase::new_from_path(tmp_dir.join("rosu.db")).unwrap();
+    let stats = database.scan_beatmaps_blocking(&songs_path, false, |_| false);
+    assert_eq!(stats.added, 2);
+
+    let mut modes: Vec<_> = (0..database.beatmaps_amount())
+        .map(|i| database.get_beatmap_by_index(i).unwrap())
+        .map(|x| (x.mode, x.unsupported_mode()))
+        .collect();
+    modes.sort_by_key(|x| x.0);
+
+    assert_eq!(modes, [(Some(0), None), (Some(3), Some(GameMode::Mania))]);
+
+    // Known hashes are not parsed and added again
+    let rescan = database.scan_beatmaps_blocking(&songs_path, false, |_| false);
+    assert_eq!(rescan.added, 0);
+    assert_eq!(database.beatmaps_amount(), 2);
+}
+
+#[test]
+fn test_scanned_unsupported_mode_is_refused() {
+    let tmp_dir = testdir!();
+    let dir = tmp_dir.join("Songs").join("1 mania");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("tests/data/other/mania.osu", dir.join("mania.osu")).unwrap();
+
+    let database = OsuDatabase::new_from_path(tmp_dir.join("rosu.db")).unwrap();
+    database.scan_beatmaps_blocking(tmp_dir.join("Songs"), false, |_| false);
+
+    // Same entry song select sends with StartBeatmap
+    let entry = database.get_beatmap_by_index(0).unwrap();
+
+    assert_eq!(entry.unsupported_mode(), Some(GameMode::Mania));
+    assert_eq!(
+        entry.unsupported_mode_modal().as_deref(),
+        Some("Mania beatmaps are not supported, only osu!standard ones"),
+    );
+
+    let std_entry = DbBeatmapEntry { mode: Some(0), ..entry };
+    assert_eq!(std_entry.unsupported_mode_modal(), None);
+}
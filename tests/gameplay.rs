@@ -1,9 +1,10 @@
 use std::path::{Path, PathBuf};
 
 use osu_replay_parser::replay::Replay;
-use rosu::{hit_objects::{hit_window::HitWindow, slider::SliderResultState, Hit, Object}, math::calc_hitcircle_diameter, processor::OsuProcessor};
+use rosu::{hit_objects::{hit_window::HitWindow, slider::{segment_kinds, SegmentKind, SliderEvent, SliderResultState}, Hit, Object, ObjectKind}, math::calc_hitcircle_diameter, osu_db::replay_export::{encode_replay, replay_file_name, save_replay, ReplayInfo, LOCAL_PLAYER}, osu_input::{KeyboardState, OsuInput}, processor::{replay_cursor::ReplayCursor, rules::{GameplayRules, MOD_HARD_ROCK}, OsuProcessor, PlaySummary, COMPLETION_DELAY, REPLAY_TAIL}};
 use rosu_map::Beatmap;
 use test_case::case;
+use testdir::testdir;
 
 /// Comparing gameplay process with replays
 
@@ -20,13 +21,13 @@ struct Expected {
 fn test_gameplay<T: AsRef<Path>>(replay_file: T, beatmap: T, expected: Expected) {
     let mut processor: OsuProcessor = Replay::open(replay_file.as_ref()).unwrap().into();
     let beatmap = Beatmap::from_path(beatmap.as_ref()).unwrap();
-    let mut beatmap_objects = Object::from_rosu(&beatmap);
+    let mut beatmap_objects = Object::from_rosu(&beatmap).unwrap();
 
     let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
 
     let circle_diameter = calc_hitcircle_diameter(beatmap.circle_size);
 
-    processor.process_all(&mut beatmap_objects, &hit_window, circle_diameter);
+    processor.process_all(&mut beatmap_objects, &hit_window, circle_diameter, &GameplayRules::default());
 
     let mut out = Expected {
         x300: 0,
@@ -395,6 +396,42 @@ fn test_slider_two_ticks(replay: &str, beatmap: &str, expected: Expected) {
     );
 }
 
+#[test]
+fn test_slider_two_ticks_events() {
+    let base = get_gameplay_tests_path();
+
+    let mut processor: OsuProcessor = Replay::open(base.join("slider_two_ticks3.osr")).unwrap().into();
+    let beatmap = Beatmap::from_path(base.join("slider_two_ticks.osu")).unwrap();
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+    let circle_diameter = calc_hitcircle_diameter(beatmap.circle_size);
+
+    processor.process_all(&mut objects, &hit_window, circle_diameter, &GameplayRules::default());
+
+    let ObjectKind::Slider(slider) = &objects[0].kind else {
+        panic!("expected a slider");
+    };
+
+    let events = processor.slider_events();
+
+    assert!(matches!(events[0], SliderEvent::Head { hit: true, .. }));
+    assert_eq!(events[1], SliderEvent::Checkpoint { time: slider.checkpoints[0].time, index: 0, passed: true });
+    assert_eq!(events[2], SliderEvent::Checkpoint { time: slider.checkpoints[1].time, index: 1, passed: false });
+    assert!(matches!(events[3], SliderEvent::End { passed: true, .. }));
+    assert_eq!(events.len(), 4);
+
+    // Judged as they happen, in order
+    assert!(events.windows(2).all(|x| x[0].time() <= x[1].time()));
+
+    let result = slider.hit_result.as_ref().unwrap();
+    assert_eq!(result.state, SliderResultState::Passed(Hit::X100));
+
+    // Combo broke on the second tick and went on with the end
+    let score = processor.score();
+    assert_eq!((score.max_combo, score.combo), (2, 1));
+}
+
 #[case(
     "two_sliders.osr", 
     "two_sliders.osu",
@@ -642,3 +679,444 @@ fn test_stacks_one_key_hold(replay: &str, beatmap: &str, expected: Expected) {
         expected
     );
 }
+
+/// Cursor perfectly following every object without pressing any keys
+fn keyless_inputs(objects: &[Object]) -> Vec<OsuInput> {
+    const FRAME_TIME: f64 = 8.0;
+    const PADDING: f64 = 100.0;
+
+    let mut inputs = Vec::new();
+
+    for object in objects {
+        let (start, end) = match &object.kind {
+            ObjectKind::Circle(circle) => (circle.start_time, circle.start_time),
+            ObjectKind::Slider(slider) => (slider.start_time, slider.end_time()),
+        };
+
+        let mut ts = start - PADDING;
+
+        while ts <= end + PADDING {
+            let pos = match &object.kind {
+                ObjectKind::Circle(circle) => (circle.pos.x as f64, circle.pos.y as f64),
+                ObjectKind::Slider(slider) => {
+                    let progress = slider.get_slider_progress(ts.clamp(start, end));
+                    let offset = slider.curve.position_at(progress);
+
+                    (
+                        (slider.pos.x + offset.x) as f64,
+                        (slider.pos.y + offset.y) as f64,
+                    )
+                },
+            };
+
+            inputs.push(OsuInput {
+                ts,
+                pos: pos.into(),
+                keys: KeyboardState::empty(),
+                hold: KeyboardState::empty(),
+                generation: 0,
+            });
+
+            ts += FRAME_TIME;
+        }
+    }
+
+    inputs.sort_by(|a, b| a.ts.total_cmp(&b.ts));
+    inputs
+}
+
+fn test_keyless_play(beatmap: &str, rules: GameplayRules) -> (u32, u32) {
+    let beatmap = Beatmap::from_path(get_gameplay_tests_path().join(beatmap)).unwrap();
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+    let circle_diameter = calc_hitcircle_diameter(beatmap.circle_size);
+
+    let mut processor = OsuProcessor::default();
+
+    for input in keyless_inputs(&objects) {
+        processor.store_input(input);
+    }
+
+    processor.process_all(&mut objects, &hit_window, circle_diameter, &rules);
+
+    let score = processor.score();
+
+    assert_eq!(score.rules, rules);
+    assert_eq!(score.is_ranked(), rules == GameplayRules::default());
+
+    (score.x300, score.x100 + score.x50)
+}
+
+#[case("jumps_simple.osu", 6; "jumps")]
+#[case("slider.osu", 1; "slider")]
+fn test_relax_full_combo(beatmap: &str, objects: u32) {
+    let relax = GameplayRules { relax: true, ..Default::default() };
+
+    assert_eq!(test_keyless_play(beatmap, relax), (objects, 0));
+    assert_eq!(test_keyless_play(beatmap, GameplayRules::default()), (0, 0));
+}
+
+#[case("gin_no_kaze.osu"; "gin no kaze")]
+#[case("slider_with_ticks_and_reverse.osu"; "ticks and reverse")]
+#[case("aozora_hard.osu"; "aozora hard")]
+fn test_curve_lut_accuracy(beatmap: &str) {
+    const STEPS: usize = 10_000;
+
+    let beatmap = Beatmap::from_path(get_gameplay_tests_path().join(beatmap)).unwrap();
+    let objects = Object::from_rosu(&beatmap).unwrap();
+
+    for object in &objects {
+        let ObjectKind::Slider(slider) = &object.kind else {
+            continue;
+        };
+
+        for i in 0..=STEPS {
+            let progress = i as f64 / STEPS as f64;
+
+            let expected = slider.curve.position_at(progress);
+            let actual = slider.position_at(progress);
+
+            let error = ((expected.x - actual.x).powi(2) + (expected.y - actual.y).powi(2)).sqrt();
+
+            assert!(
+                error < 0.5, 
+                "Slider at {} is off by {error} at {progress}", slider.start_time
+            );
+        }
+    }
+}
+
+/// `B|237:180|237:180|364:127` is two bezier segments,
+/// the repeated point is kept once as a red anchor
+#[test]
+fn test_slider_control_points() {
+    let beatmap = Beatmap::from_path(get_gameplay_tests_path().join("slider_two_ticks.osu")).unwrap();
+    let objects = Object::from_rosu(&beatmap).unwrap();
+
+    let Some(ObjectKind::Slider(slider)) = objects.first().map(|x| &x.kind) else {
+        panic!("First object is not a slider");
+    };
+
+    let first = slider.control_points.first().unwrap();
+    assert_eq!((first.pos.x, first.pos.y), (0.0, 0.0));
+    assert_eq!(first.kind, Some(SegmentKind::Bezier));
+
+    let anchors: Vec<_> = slider.red_anchors().map(|x| (x.pos.x, x.pos.y)).collect();
+    assert_eq!(anchors, [(96.0, -35.0)]);
+
+    let last = slider.control_points.last().unwrap();
+    assert_eq!((last.pos.x, last.pos.y), (223.0, -88.0));
+
+    assert!(segment_kinds(&slider.control_points).all(|x| x == SegmentKind::Bezier));
+}
+
+/// Clicks every object at the same offset from its center,
+/// aim error of the score has to end up being that offset
+#[case("jumps_simple.osu"; "circles")]
+#[case("slider.osu"; "slider head")]
+fn test_hit_offsets(beatmap: &str) {
+    const OFFSET: (f64, f64) = (10.0, -5.0);
+
+    let beatmap = Beatmap::from_path(get_gameplay_tests_path().join(beatmap)).unwrap();
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+    let circle_diameter = calc_hitcircle_diameter(beatmap.circle_size);
+
+    let mut processor = OsuProcessor::default();
+
+    for object in &objects {
+        let (center, start, end) = match &object.kind {
+            ObjectKind::Circle(circle) => (circle.pos, circle.start_time, circle.start_time + 30.0),
+            ObjectKind::Slider(slider) => (slider.pos, slider.start_time, slider.end_time()),
+        };
+
+        let pos = (center.x as f64 + OFFSET.0, center.y as f64 + OFFSET.1);
+
+        processor.store_input(OsuInput {
+            ts: start,
+            pos: pos.into(),
+            keys: KeyboardState { k1: true, k2: false },
+            hold: KeyboardState::empty(),
+            generation: 0,
+        });
+
+        processor.store_input(OsuInput {
+            ts: end,
+            pos: pos.into(),
+            keys: KeyboardState::empty(),
+            hold: KeyboardState::empty(),
+            generation: 0,
+        });
+    }
+
+    processor.process_all(&mut objects, &hit_window, circle_diameter, &GameplayRules::default());
+
+    let score = processor.score();
+
+    assert_eq!(score.hit_offsets.len(), objects.len());
+    assert_eq!(score.circle_radius, circle_diameter as f64 / 2.0);
+
+    let mean = score.mean_hit_offset().unwrap();
+
+    assert!(
+        (mean.x - OFFSET.0).abs() < 1e-3 && (mean.y - OFFSET.1).abs() < 1e-3,
+        "Mean offset is ({}, {})", mean.x, mean.y
+    );
+}
+
+/// Watch mode feeds replay frames as the clock reaches them,
+/// judgements have to match processing everything at once
+#[case("gin_no_kaze.osr", "gin_no_kaze.osu"; "gin no kaze")]
+#[case("slider_with_ticks_and_reverse.osr", "slider_with_ticks_and_reverse.osu"; "ticks and reverse")]
+#[case("aozora_hard.osr", "aozora_hard.osu"; "aozora hard")]
+fn test_replay_watch_playback(replay: &str, beatmap: &str) {
+    const FRAME_TIME: f64 = 16.0;
+
+    let base = get_gameplay_tests_path();
+    let beatmap = Beatmap::from_path(base.join(beatmap)).unwrap();
+
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+    let circle_diameter = calc_hitcircle_diameter(beatmap.circle_size);
+    let rules = GameplayRules::default();
+
+    let mut full: OsuProcessor = Replay::open(base.join(replay)).unwrap().into();
+    let cursor = ReplayCursor::new(full.queued_inputs().to_vec());
+
+    let mut full_objects = Object::from_rosu(&beatmap).unwrap();
+    full.process_all(&mut full_objects, &hit_window, circle_diameter, &rules);
+
+    let mut watched = cursor.processor();
+    let mut watched_objects = Object::from_rosu(&beatmap).unwrap();
+
+    let mut time = cursor.frames().first().map(|x| x.ts).unwrap_or(0.0);
+
+    while !watched.queued_inputs().is_empty() {
+        watched.process_until(time, &mut watched_objects, &hit_window, circle_diameter, &rules);
+
+        assert!(watched.queued_inputs().iter().all(|x| x.ts > time));
+
+        time += FRAME_TIME;
+    }
+
+    let (full, watched) = (full.score(), watched.score());
+
+    assert!(full.judgements() > 0);
+    assert_eq!(
+        (watched.x300, watched.x100, watched.x50, watched.miss),
+        (full.x300, full.x100, full.x50, full.miss),
+    );
+
+    for (watched, full) in watched_objects.iter().zip(&full_objects) {
+        if let (ObjectKind::Circle(watched), ObjectKind::Circle(full)) = (&watched.kind, &full.kind) {
+            assert_eq!(
+                watched.hit_result.as_ref().map(|x| x.result),
+                full.hit_result.as_ref().map(|x| x.result),
+                "Circle at {}", full.start_time
+            );
+        }
+    }
+}
+
+/// Hard Rock replays store frames as they were seen on the flipped
+/// playfield. Flipping a nomod replay the same way has to be judged
+/// exactly like the original once watched with Hard Rock
+#[case("gin_no_kaze.osr", "gin_no_kaze.osu"; "gin no kaze")]
+#[case("slider_with_ticks_and_reverse.osr", "slider_with_ticks_and_reverse.osu"; "ticks and reverse")]
+fn test_hard_rock_replay_flip(replay: &str, beatmap: &str) {
+    let base = get_gameplay_tests_path();
+    let beatmap = Beatmap::from_path(base.join(beatmap)).unwrap();
+
+    // Difficulty is kept the same, only the flip is checked
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+    let circle_diameter = calc_hitcircle_diameter(beatmap.circle_size);
+
+    let mut nomod: OsuProcessor = Replay::open(base.join(replay)).unwrap().into();
+
+    let flipped = nomod.queued_inputs().iter()
+        .map(|x| OsuInput { pos: (x.pos.x, 384.0 - x.pos.y).into(), ..x.clone() })
+        .collect();
+
+    let rules = GameplayRules::from_replay_mods(MOD_HARD_ROCK);
+    let cursor = ReplayCursor::with_rules(flipped, rules);
+    assert!(cursor.rules().hard_rock);
+
+    let mut nomod_objects = Object::from_rosu(&beatmap).unwrap();
+    nomod.process_all(&mut nomod_objects, &hit_window, circle_diameter, &GameplayRules::default());
+
+    let mut hard_rock = cursor.processor();
+    let mut hard_rock_objects = Object::from_rosu(&beatmap).unwrap();
+    hard_rock.process_all(&mut hard_rock_objects, &hit_window, circle_diameter, &rules);
+
+    let (nomod, hard_rock) = (nomod.score(), hard_rock.score());
+
+    assert!(nomod.judgements() > 0);
+    assert_eq!(
+        (hard_rock.x300, hard_rock.x100, hard_rock.x50, hard_rock.miss),
+        (nomod.x300, nomod.x100, nomod.x50, nomod.miss),
+    );
+}
+
+/// Inputs recorded during a play are exported as a .osr that
+/// opens back with the same frames and gets judged the same way
+#[case("gin_no_kaze.osr", "gin_no_kaze.osu"; "gin no kaze")]
+#[case("aozora_hard.osr", "aozora_hard.osu"; "aozora hard")]
+fn test_replay_export_roundtrip(replay: &str, beatmap: &str) {
+    let base = get_gameplay_tests_path();
+    let beatmap = Beatmap::from_path(base.join(beatmap)).unwrap();
+
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+    let circle_diameter = calc_hitcircle_diameter(beatmap.circle_size);
+    let rules = GameplayRules::default();
+
+    // Feeding frames one by one is what happens during a play
+    let source: OsuProcessor = Replay::open(base.join(replay)).unwrap().into();
+    let mut played = OsuProcessor::default();
+
+    for input in source.queued_inputs() {
+        played.store_input(input.clone());
+    }
+
+    let mut played_objects = Object::from_rosu(&beatmap).unwrap();
+    played.process_all(&mut played_objects, &hit_window, circle_diameter, &rules);
+
+    let data = encode_replay(&ReplayInfo {
+        beatmap_hash: "e2f3e496b1014c84c998be738887e315",
+        player: LOCAL_PLAYER,
+        score: played.score(),
+        frames: played.recorded_inputs(),
+        timestamp: 1_700_000_000,
+    }).unwrap();
+
+    let file_name = replay_file_name(&beatmap.title, LOCAL_PLAYER, 1_700_000_000);
+    let path = save_replay(testdir!(), &file_name, &data).unwrap();
+
+    let exported = Replay::open(&path).unwrap();
+    assert_eq!(exported.replay_data.frames.len(), played.recorded_inputs().len());
+
+    let mut watched: OsuProcessor = exported.into();
+    let mut watched_objects = Object::from_rosu(&beatmap).unwrap();
+    watched.process_all(&mut watched_objects, &hit_window, circle_diameter, &rules);
+
+    let (played, watched) = (played.score(), watched.score());
+
+    assert!(played.judgements() > 0);
+    assert_eq!(
+        (watched.x300, watched.x100, watched.x50, watched.miss, watched.max_combo),
+        (played.x300, played.x100, played.x50, played.miss, played.max_combo),
+    );
+}
+
+#[test]
+fn test_is_complete_slider_tail_pending() {
+    let beatmap = Beatmap::from_path(get_gameplay_tests_path().join("slider.osu")).unwrap();
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+    let circle_diameter = calc_hitcircle_diameter(beatmap.circle_size);
+    let rules = GameplayRules::default();
+
+    let Some(ObjectKind::Slider(slider)) = objects.first().map(|x| &x.kind) else {
+        panic!("First object is not a slider");
+    };
+
+    let (start, end) = (slider.start_time, slider.end_time());
+
+    // Cursor following the ball with K1 held from the head
+    let follow: Vec<_> = std::iter::successors(Some(start), |ts| Some(ts + 8.0))
+        .take_while(|ts| *ts <= end + 100.0)
+        .map(|ts| {
+            let progress = slider.get_slider_progress(ts.clamp(start, end));
+            let offset = slider.curve.position_at(progress);
+
+            (ts, ((slider.pos.x + offset.x) as f64, (slider.pos.y + offset.y) as f64))
+        })
+        .collect();
+
+    let mut processor = OsuProcessor::default();
+    processor.store_cursor_moved(start - 10.0, follow[0].1.into());
+    processor.store_keyboard_pressed(start, KeyboardState { k1: true, k2: false });
+
+    for (ts, pos) in follow {
+        processor.store_cursor_moved(ts, pos.into());
+    }
+
+    // Head is hit, but the tail isn't judged yet
+    processor.process_until(start + (end - start) * 0.4, &mut objects, &hit_window, circle_diameter, &rules);
+    assert!(!processor.is_complete(&objects));
+    assert!(!processor.is_ended());
+
+    let ObjectKind::Slider(slider) = &objects[0].kind else {
+        unreachable!();
+    };
+    assert_eq!(slider.hit_result.as_ref().map(|x| x.state), Some(SliderResultState::Middle));
+
+    processor.process_until(end + 100.0, &mut objects, &hit_window, circle_diameter, &rules);
+    assert!(processor.is_complete(&objects));
+    assert!(processor.is_ended());
+}
+
+/// Clicks every circle on time while the cursor wiggles
+/// around, wiggling goes on well after the last object
+#[test]
+fn test_map_completed_once() {
+    const FRAME_TIME: f64 = 16.0;
+    const CLICK_LENGTH: f64 = 40.0;
+
+    let beatmap = Beatmap::from_path(get_gameplay_tests_path().join("jumps_simple.osu")).unwrap();
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+    let circle_diameter = calc_hitcircle_diameter(beatmap.circle_size);
+    let rules = GameplayRules::default();
+
+    let clicks: Vec<_> = objects.iter()
+        .map(|x| match &x.kind {
+            ObjectKind::Circle(circle) => (circle.start_time, (circle.pos.x as f64, circle.pos.y as f64)),
+            ObjectKind::Slider(_) => panic!("Map is expected to have circles only"),
+        })
+        .collect();
+
+    let first = clicks.first().unwrap().0;
+    let last = clicks.last().unwrap().0;
+    let keys = KeyboardState { k1: true, k2: false };
+
+    let mut processor = OsuProcessor::default();
+    let mut clicks = clicks.into_iter().peekable();
+    let mut pressed_at = None;
+    let mut summaries = Vec::new();
+
+    let mut time = first - 1000.0;
+
+    while time <= last + COMPLETION_DELAY + 3000.0 {
+        // Inputs are stored in order, so releases land on frames
+        if pressed_at.is_some_and(|ts| time >= ts + CLICK_LENGTH) {
+            processor.store_keyboard_released(time, keys);
+            pressed_at = None;
+        }
+
+        if let Some((ts, pos)) = clicks.next_if(|(ts, _)| *ts <= time) {
+            processor.store_cursor_moved(ts, pos.into());
+            processor.store_keyboard_pressed(ts, keys);
+            pressed_at = Some(ts);
+        }
+
+        let wiggle = (256.0 + (time / 50.0).sin() * 100.0, 192.0);
+        processor.store_cursor_moved(time, wiggle.into());
+
+        processor.process_until(time, &mut objects, &hit_window, circle_diameter, &rules);
+        summaries.extend(processor.poll_end(time));
+
+        time += FRAME_TIME;
+    }
+
+    let score = processor.score();
+    assert_eq!(score.x300, objects.len() as u32);
+
+    assert_eq!(summaries, [PlaySummary { end_time: last, failed: false }]);
+
+    // Wiggling after the end isn't recorded
+    let last_frame = processor.recorded_inputs().last().unwrap();
+    assert!(last_frame.ts <= last + REPLAY_TAIL);
+}
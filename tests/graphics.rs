@@ -0,0 +1,714 @@
+use std::sync::Arc;
+
+use std::sync::RwLock;
+
+use image::{DynamicImage, Rgba, RgbaImage};
+use rosu::{camera::Camera, config::Config, graphics::Graphics, hit_circle_instance::HitCircleInstance, hit_objects::{hit_window::HitWindow, Object, ObjectKind}, notifier::Notifier, math::{calc_hitcircle_diameter, calculate_preempt_fadein}, osu_input::{KeyboardState, OsuInput}, osu_renderer::{HitCircleLayer, OsuRenderer}, processor::{rules::GameplayRules, OsuProcessor}, quad_instance::QuadInstance, quad_renderer::QuadRenderer, rgb::Rgb, skin_manager::{SkinImages, SkinManager}, texture::Texture, vertex::Vertex};
+use rosu_map::Beatmap;
+use wgpu::util::DeviceExt;
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 64;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+fn headless_graphics() -> Option<Arc<Graphics>> {
+    let graphics = pollster::block_on(Graphics::headless_from_env(WIDTH, HEIGHT, FORMAT));
+
+    if graphics.is_none() {
+        eprintln!("No wgpu adapter available, skipping");
+    }
+
+    graphics.map(Arc::new)
+}
+
+fn create_target(graphics: &Graphics) -> wgpu::Texture {
+    graphics.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("offscreen target"),
+        size: wgpu::Extent3d {
+            width: WIDTH,
+            height: HEIGHT,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+fn clear(graphics: &Graphics, view: &wgpu::TextureView) {
+    clear_with(graphics, view, wgpu::Color::BLACK);
+}
+
+fn clear_with(graphics: &Graphics, view: &wgpu::TextureView, color: wgpu::Color) {
+    let mut encoder = graphics.device.create_command_encoder(&Default::default());
+
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("clear"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(color),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    graphics.queue.submit([encoder.finish()]);
+}
+
+/// Returns tightly packed RGBA pixels of the `target`
+fn read_pixels(graphics: &Graphics, target: &wgpu::Texture) -> Vec<u8> {
+    let padded_bytes_per_row = (WIDTH * 4).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    let readback = graphics.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("readback"),
+        size: (padded_bytes_per_row * HEIGHT) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = graphics.device.create_command_encoder(&Default::default());
+
+    encoder.copy_texture_to_buffer(
+        target.as_image_copy(),
+        wgpu::TexelCopyBufferInfo {
+            buffer: &readback,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(HEIGHT),
+            },
+        },
+        target.size(),
+    );
+
+    graphics.queue.submit([encoder.finish()]);
+
+    let slice = readback.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |res| res.expect("failed to map readback buffer"));
+    graphics.device.poll(wgpu::Maintain::Wait);
+
+    let data = slice.get_mapped_range();
+
+    data.chunks(padded_bytes_per_row as usize)
+        .flat_map(|row| &row[..(WIDTH * 4) as usize])
+        .copied()
+        .collect()
+}
+
+fn pixel(pixels: &[u8], x: u32, y: u32) -> &[u8] {
+    let i = ((y * WIDTH + x) * 4) as usize;
+    &pixels[i..i + 4]
+}
+
+#[test]
+fn test_headless_graphics_has_no_surface() {
+    let Some(graphics) = headless_graphics() else {
+        return;
+    };
+
+    assert!(graphics.is_headless());
+    assert_eq!(graphics.format(), FORMAT);
+    assert_eq!(graphics.get_surface_size(), (WIDTH, HEIGHT));
+    assert!(graphics.get_current_texture().is_err());
+}
+
+#[test]
+fn test_quad_renderer_offscreen() {
+    let Some(graphics) = headless_graphics() else {
+        return;
+    };
+
+    let quad_renderer = QuadRenderer::new(graphics.clone(), false);
+
+    let white = RgbaImage::from_pixel(4, 4, Rgba([255, 255, 255, 255]));
+    let texture = Texture::from_image(DynamicImage::ImageRgba8(white), &graphics);
+
+    // 32x32 quad in the middle of the target
+    quad_renderer.resize_vertex_centered(32.0, 32.0);
+
+    let instance = QuadInstance::from_xy_pos(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0);
+    let instances = graphics.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("quad instances"),
+        contents: bytemuck::bytes_of(&instance),
+        usage: wgpu::BufferUsages::VERTEX,
+    });
+
+    let target = create_target(&graphics);
+    let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+    clear(&graphics, &view);
+    quad_renderer.render_on_view_instanced(&view, &texture.bind_group, &instances, 0..1);
+
+    let pixels = read_pixels(&graphics, &target);
+
+    assert_eq!(pixel(&pixels, WIDTH / 2, HEIGHT / 2), [255, 255, 255, 255]);
+    assert_eq!(pixel(&pixels, 2, 2), [0, 0, 0, 255]);
+    assert_eq!(pixel(&pixels, WIDTH - 3, HEIGHT - 3), [0, 0, 0, 255]);
+}
+
+#[test]
+fn test_overlapping_sliders_follow_circles() {
+    let Some(graphics) = headless_graphics() else {
+        return;
+    };
+
+    let skin_manager = Arc::new(RwLock::new(SkinManager::from_path("skin", &graphics)));
+    let config = Arc::new(RwLock::new(Config::default()));
+    let mut renderer = OsuRenderer::new(graphics.clone(), config, skin_manager);
+
+    let beatmap = Beatmap::from_path("tests/data/gameplay/overlapping_sliders.osu").unwrap();
+    renderer.on_cs_change(beatmap.circle_size);
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+
+    let (preempt, fadein) = calculate_preempt_fadein(beatmap.approach_rate);
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+
+    // Both sliders are being followed at this point, second one
+    // comes first in the queue same as in `OsuState`.
+    // Sliders are not baked beforehand, renderer should bake them itself
+    let time = 1800.0;
+    let queue = [1, 0];
+
+    renderer.prepare_objects(time, preempt, fadein, &queue, &mut objects, &hit_window);
+
+    let follow_circles: Vec<_> = renderer.prepared_follow_circles()
+        .map(|x| x.expect("both sliders should have a follow circle").pos)
+        .collect();
+
+    assert_eq!(follow_circles.len(), 2);
+
+    // Each follow circle stays on its own slider's curve
+    assert!((follow_circles[0][1] - 300.0).abs() < 1.0, "{follow_circles:?}");
+    assert!((follow_circles[1][1] - 100.0).abs() < 1.0, "{follow_circles:?}");
+
+    // And they are moving at their own progress
+    assert!((follow_circles[0][0] - 240.0).abs() < 1.0, "{follow_circles:?}");
+    assert!((follow_circles[1][0] - 260.0).abs() < 1.0, "{follow_circles:?}");
+}
+
+#[test]
+fn test_bake_ahead_budget() {
+    let Some(graphics) = headless_graphics() else {
+        return;
+    };
+
+    let skin_manager = Arc::new(RwLock::new(SkinManager::from_path("skin", &graphics)));
+    let config = Arc::new(RwLock::new(Config {
+        bake_ahead_ms: 500.0,
+        bake_ahead_per_frame: 1,
+        ..Default::default()
+    }));
+    let mut renderer = OsuRenderer::new(graphics.clone(), config, skin_manager);
+
+    let beatmap = Beatmap::from_path("tests/data/gameplay/overlapping_sliders.osu").unwrap();
+    renderer.on_cs_change(beatmap.circle_size);
+
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+    let (preempt, _) = calculate_preempt_fadein(beatmap.approach_rate);
+
+    let is_baked = |obj: &Object| match &obj.kind {
+        ObjectKind::Slider(slider) => slider.render.is_some(),
+        _ => false,
+    };
+
+    // Sliders appear at -200 and -100, too far ahead
+    renderer.bake_ahead(-1000.0, preempt, &mut objects);
+    assert!(!objects.iter().any(is_baked));
+
+    // Both are within the window but only one is baked per frame
+    renderer.bake_ahead(-600.0, preempt, &mut objects);
+    assert!(is_baked(&objects[0]));
+    assert!(!is_baked(&objects[1]));
+
+    renderer.bake_ahead(-600.0, preempt, &mut objects);
+    assert!(objects.iter().all(is_baked));
+}
+
+#[test]
+fn test_degenerate_sliders_prepare_finite() {
+    let Some(graphics) = headless_graphics() else {
+        return;
+    };
+
+    let skin_manager = Arc::new(RwLock::new(SkinManager::from_path("skin", &graphics)));
+    let config = Arc::new(RwLock::new(Config::default()));
+    let mut renderer = OsuRenderer::new(graphics.clone(), config, skin_manager);
+
+    let fixtures = [
+        "slider_duplicate_points.osu",
+        "slider_zero_length_segment.osu",
+        "slider_collinear_perfect.osu",
+        "slider_broken_tick_rate.osu",
+    ];
+
+    for name in fixtures {
+        let beatmap = Beatmap::from_path(format!("tests/data/other/{name}")).unwrap();
+        renderer.on_cs_change(beatmap.circle_size);
+
+        let mut objects = Object::from_rosu(&beatmap).unwrap();
+        let (preempt, fadein) = calculate_preempt_fadein(beatmap.approach_rate);
+        let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+
+        let queue: Vec<usize> = (0..objects.len()).collect();
+
+        // Approaching, head, middle of the slide and reverse
+        for time in [0.0, 513.0, 800.0, 1500.0] {
+            renderer.prepare_objects(time, preempt, fadein, &queue, &mut objects, &hit_window);
+
+            assert!(
+                renderer.prepared_positions().all(|pos| pos.iter().all(|x| x.is_finite())),
+                "{name} at {time}"
+            );
+
+            renderer.clear_buffers();
+        }
+    }
+}
+
+/// Renders visible objects at `time` the same way `OsuState` does
+fn render_objects_at(
+    renderer: &mut OsuRenderer,
+    target: &wgpu::Texture,
+    time: f64,
+    objects: &mut [Object],
+    preempt: f32,
+    fadein: f32,
+    hit_window: &HitWindow,
+) -> Vec<u8> {
+    render_objects_over(renderer, target, time, objects, preempt, fadein, hit_window, wgpu::Color::BLACK)
+}
+
+/// Same as [`render_objects_at`], over `background` instead of black
+#[allow(clippy::too_many_arguments)]
+fn render_objects_over(
+    renderer: &mut OsuRenderer,
+    target: &wgpu::Texture,
+    time: f64,
+    objects: &mut [Object],
+    preempt: f32,
+    fadein: f32,
+    hit_window: &HitWindow,
+    background: wgpu::Color,
+) -> Vec<u8> {
+    let graphics = renderer.get_graphics();
+    let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+    clear_with(&graphics, &view, background);
+
+    let queue: Vec<usize> = (0..objects.len())
+        .rev()
+        .filter(|i| objects[*i].is_visible(time, preempt, hit_window))
+        .collect();
+
+    renderer.prepare_judgements(time, &queue, objects);
+    renderer.prepare_objects(time, preempt, fadein, &queue, objects, hit_window);
+    renderer.prepare();
+    renderer.write_buffers();
+    renderer.render_objects(&view, &queue, objects).expect("failed to render objects");
+
+    read_pixels(&graphics, target)
+}
+
+#[test]
+fn test_slider_body_tint_without_rebake() {
+    let Some(graphics) = headless_graphics() else {
+        return;
+    };
+
+    let skin_manager = Arc::new(RwLock::new(SkinManager::from_path("skin", &graphics)));
+    let config = Arc::new(RwLock::new(Config::default()));
+    let mut renderer = OsuRenderer::new(graphics.clone(), config.clone(), skin_manager);
+
+    let beatmap = Beatmap::from_path("tests/data/gameplay/overlapping_sliders.osu").unwrap();
+    renderer.on_cs_change(beatmap.circle_size);
+
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+    let (preempt, fadein) = calculate_preempt_fadein(beatmap.approach_rate);
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+
+    let target = create_target(&graphics);
+    let time = 1000.0;
+
+    let defaults = render_objects_at(&mut renderer, &target, time, &mut objects, preempt, fadein, &hit_window);
+
+    {
+        let mut config = config.write().unwrap();
+        config.slider.body_color_saturation = 1.5;
+        config.slider.body_alpha_multiplier = 0.3;
+    }
+
+    // Textures baked with defaults are reused
+    let cached = render_objects_at(&mut renderer, &target, time, &mut objects, preempt, fadein, &hit_window);
+
+    renderer.clear_cached_slider_textures(&mut objects);
+    let rebaked = render_objects_at(&mut renderer, &target, time, &mut objects, preempt, fadein, &hit_window);
+
+    assert_ne!(defaults, cached, "body settings should be applied to cached textures");
+    assert_eq!(cached, rebaked);
+
+    // Going back to defaults gives the same frame as before
+    *config.write().unwrap() = Config::default();
+    let restored = render_objects_at(&mut renderer, &target, time, &mut objects, preempt, fadein, &hit_window);
+
+    assert_eq!(defaults, restored);
+}
+
+#[test]
+fn test_slider_composite_over_background() {
+    let Some(graphics) = headless_graphics() else {
+        return;
+    };
+
+    let skin_manager = Arc::new(RwLock::new(SkinManager::from_path("skin", &graphics)));
+    let config = Arc::new(RwLock::new(Config::default()));
+    let mut renderer = OsuRenderer::new(graphics.clone(), config, skin_manager);
+
+    let beatmap = Beatmap::from_path("tests/data/gameplay/overlapping_sliders.osu").unwrap();
+    renderer.on_cs_change(beatmap.circle_size);
+
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+    let (preempt, fadein) = calculate_preempt_fadein(beatmap.approach_rate);
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+
+    // Hitting the first head puts a judgement over the slider body
+    let mut processor = OsuProcessor::default();
+
+    for (ts, k1) in [(1000.0, true), (1200.0, false)] {
+        processor.store_input(OsuInput {
+            ts,
+            pos: (100.0, 100.0).into(),
+            keys: KeyboardState { k1, k2: false },
+            hold: KeyboardState::empty(),
+            generation: 0,
+        });
+    }
+
+    processor.process_all(&mut objects, &hit_window, calc_hitcircle_diameter(beatmap.circle_size), &GameplayRules::default());
+
+    let target = create_target(&graphics);
+    let time = 1100.0;
+    let background = wgpu::Color { r: 0.9, g: 0.85, b: 0.8, a: 1.0 };
+
+    let layers = render_objects_over(&mut renderer, &target, time, &mut objects, preempt, fadein, &hit_window, wgpu::Color::TRANSPARENT);
+    let composite = render_objects_over(&mut renderer, &target, time, &mut objects, preempt, fadein, &hit_window, background);
+
+    clear_with(&graphics, &target.create_view(&Default::default()), background);
+    let background = read_pixels(&graphics, &target);
+
+    assert_ne!(composite, background, "objects should cover part of the target");
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let (composite, layers, background) = (
+                pixel(&composite, x, y),
+                pixel(&layers, x, y),
+                pixel(&background, x, y),
+            );
+
+            // Background stays opaque under sliders and judgements
+            assert_eq!(composite[3], 255, "({x}, {y}) {composite:?}");
+
+            // Blending can't go darker than both of the sources
+            for c in 0..3 {
+                let darkest = layers[c].min(background[c]);
+
+                assert!(
+                    composite[c].saturating_add(1) >= darkest,
+                    "({x}, {y}) {composite:?} over {background:?} with {layers:?}",
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_hit_circle_instance_rotation() {
+    let Some(graphics) = headless_graphics() else {
+        return;
+    };
+
+    let shader = graphics.device.create_shader_module(wgpu::include_wgsl!("../src/shaders/quad_textured.wgsl"));
+    let camera = Camera::new(&graphics, WIDTH as f32, HEIGHT as f32, 1.0);
+    let texture_layout = Texture::default_bind_group_layout(&graphics, 1);
+
+    let layout = graphics.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("rotation pipeline layout"),
+        bind_group_layouts: &[&texture_layout, camera.bind_group_layout()],
+        push_constant_ranges: &[],
+    });
+
+    let pipeline = graphics.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("rotation pipeline"),
+        cache: None,
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::desc(), HitCircleInstance::desc()],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(FORMAT.into())],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    });
+
+    // Top half is white, bottom half is red
+    let image = RgbaImage::from_fn(8, 8, |_, y| {
+        if y < 4 { Rgba([255, 255, 255, 255]) } else { Rgba([255, 0, 0, 255]) }
+    });
+    let texture = Texture::from_image(DynamicImage::ImageRgba8(image), &graphics);
+
+    let vertices = graphics.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("quad vertices"),
+        contents: bytemuck::cast_slice(&Vertex::quad_centered(32.0, 32.0)),
+        usage: wgpu::BufferUsages::VERTEX,
+    });
+
+    let indices = graphics.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("quad indices"),
+        contents: bytemuck::cast_slice(rosu::osu_renderer::QUAD_INDECIES),
+        usage: wgpu::BufferUsages::INDEX,
+    });
+
+    let render = |rotation: f32| {
+        let instance = HitCircleInstance::new(
+            WIDTH as f32 / 2.0,
+            HEIGHT as f32 / 2.0,
+            0.0,
+            1.0,
+            1.0,
+            &Rgb::new(255, 255, 255),
+            rotation,
+        );
+
+        let instances = graphics.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("rotated instance"),
+            contents: bytemuck::bytes_of(&instance),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let target = create_target(&graphics);
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+        clear(&graphics, &view);
+
+        let mut encoder = graphics.device.create_command_encoder(&Default::default());
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("rotation"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(&pipeline);
+            render_pass.set_bind_group(0, &texture.bind_group, &[]);
+            render_pass.set_bind_group(1, camera.bind_group(), &[]);
+            render_pass.set_vertex_buffer(0, vertices.slice(..));
+            render_pass.set_vertex_buffer(1, instances.slice(..));
+            render_pass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..rosu::osu_renderer::QUAD_INDECIES.len() as u32, 0, 0..1);
+        }
+
+        graphics.queue.submit([encoder.finish()]);
+
+        read_pixels(&graphics, &target)
+    };
+
+    let (center_x, center_y) = (WIDTH / 2, HEIGHT / 2);
+    let white = [255, 255, 255, 255];
+    let red = [255, 0, 0, 255];
+
+    let straight = render(0.0);
+    assert_eq!(pixel(&straight, center_x, center_y - 8), white);
+    assert_eq!(pixel(&straight, center_x, center_y + 8), red);
+
+    // Layout is transposed, top half ends up on the left
+    let rotated = render(90.0);
+    assert_eq!(pixel(&rotated, center_x - 8, center_y), white);
+    assert_eq!(pixel(&rotated, center_x + 8, center_y), red);
+    assert_eq!(pixel(&rotated, center_x - 8, center_y + 8), white);
+    assert_eq!(pixel(&rotated, center_x + 8, center_y - 8), red);
+
+    // Outside of the quad stays clear
+    assert_eq!(pixel(&rotated, 2, 2), [0, 0, 0, 255]);
+}
+
+#[test]
+fn test_hit_circle_layer_order() {
+    assert_eq!(
+        HitCircleLayer::draw_order(true),
+        [HitCircleLayer::Circle, HitCircleLayer::Number, HitCircleLayer::Overlay],
+    );
+    assert_eq!(
+        HitCircleLayer::draw_order(false),
+        [HitCircleLayer::Circle, HitCircleLayer::Overlay, HitCircleLayer::Number],
+    );
+}
+
+#[test]
+fn test_hit_circle_overlay_not_tinted() {
+    let Some(graphics) = headless_graphics() else {
+        return;
+    };
+
+    let beatmap = Beatmap::from_path("tests/data/gameplay/single_hit_circle.osu").unwrap();
+    let (preempt, fadein) = calculate_preempt_fadein(beatmap.approach_rate);
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+
+    let target = create_target(&graphics);
+
+    // White circle and an overlay that covers only its left half
+    let render = |overlay_above_number: bool| {
+        let mut images = SkinImages::load("skin", &Notifier::default());
+        images.ini.general.hit_circle_overlay_above_number = overlay_above_number;
+        images.ini.colours.combo_colors = vec![Rgb::new(255, 0, 0)];
+        images.hit_circle = DynamicImage::ImageRgba8(RgbaImage::from_pixel(16, 16, Rgba([255, 255, 255, 255])));
+        images.hit_circle_overlay = DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 16, |x, _| {
+            if x < 8 { Rgba([255, 255, 255, 255]) } else { Rgba([0, 0, 0, 0]) }
+        }));
+
+        let skin_manager = Arc::new(RwLock::new(SkinManager::from_images(images, &graphics)));
+        let config = Arc::new(RwLock::new(Config::default()));
+        let mut renderer = OsuRenderer::new(graphics.clone(), config, skin_manager);
+        renderer.on_cs_change(beatmap.circle_size);
+
+        let mut objects = Object::from_rosu(&beatmap).unwrap();
+
+        render_objects_at(&mut renderer, &target, 1990.0, &mut objects, preempt, fadein, &hit_window)
+    };
+
+    let count = |pixels: &[u8], f: fn(&[u8]) -> bool| pixels.chunks(4).filter(|x| f(x)).count();
+
+    for overlay_above_number in [true, false] {
+        let pixels = render(overlay_above_number);
+
+        // Tint is only applied to the circle under the overlay
+        let white = count(&pixels, |x| x[0] >= 250 && x[1] >= 250 && x[2] >= 250);
+        let red = count(&pixels, |x| x[0] >= 250 && x[1] <= 5 && x[2] <= 5);
+
+        assert!(white > 0, "overlay is tinted, above number: {overlay_above_number}");
+        assert!(red > 0, "circle is not tinted, above number: {overlay_above_number}");
+    }
+
+    // There are no combo numbers yet, so the order alone changes nothing
+    assert_eq!(render(true), render(false));
+}
+
+/// Graphics presenting to a hidden window, `None` without
+/// a display or an adapter that can present to it.
+/// Event loop has to outlive the window
+#[cfg(target_os = "linux")]
+fn windowed_graphics() -> Option<(Graphics, Arc<winit::window::Window>, winit::event_loop::EventLoop<()>)> {
+    use rosu::graphics::GraphicsInitialized;
+    use winit::platform::x11::EventLoopBuilderExtX11;
+
+    // Tests don't run on the main thread
+    let event_loop = match winit::event_loop::EventLoop::builder().with_any_thread(true).build() {
+        Ok(event_loop) => event_loop,
+        Err(e) => {
+            eprintln!("No display available ({e}), skipping");
+            return None;
+        },
+    };
+
+    #[allow(deprecated)]
+    let window = event_loop
+        .create_window(winit::window::Window::default_attributes().with_visible(false))
+        .ok()
+        .map(Arc::new)?;
+
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let surface = instance.create_surface(window.clone()).ok()?;
+
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::None,
+        force_fallback_adapter: false,
+        compatible_surface: Some(&surface),
+    }));
+
+    let Some(adapter) = adapter else {
+        eprintln!("No wgpu adapter can present to the window, skipping");
+        return None;
+    };
+
+    let (device, queue) = pollster::block_on(adapter.request_device(&Default::default(), None)).ok()?;
+
+    let graphics = Graphics::from_initialized(GraphicsInitialized {
+        instance,
+        surface,
+        device,
+        queue,
+        adapter,
+        size: window.inner_size(),
+    });
+
+    Some((graphics, window, event_loop))
+}
+
+/// Presents a cleared frame, the same frame `OsuState` would get
+#[cfg(target_os = "linux")]
+fn present_frame(graphics: &Graphics) -> Result<(), wgpu::SurfaceError> {
+    let frame = graphics.get_current_texture()?;
+    let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    clear(graphics, &view);
+    frame.present();
+
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_recreate_surface_after_lost() {
+    let Some((graphics, window, _event_loop)) = windowed_graphics() else {
+        return;
+    };
+
+    let config = graphics.get_surface_config();
+
+    present_frame(&graphics).expect("failed to present before the surface was lost");
+
+    // Surface comes back lost, recovery is the same as on `SurfaceError::Lost` in the client
+    graphics.recreate_surface(window.clone()).expect("failed to recreate surface");
+
+    // Pipelines are built against the old format, so it has to stay
+    let recreated = graphics.get_surface_config();
+    assert_eq!(recreated.format, config.format);
+    assert_eq!((recreated.width, recreated.height), (config.width, config.height));
+    assert_eq!(recreated.present_mode, config.present_mode);
+
+    // And the new surface keeps presenting, resizes included
+    present_frame(&graphics).expect("failed to present after the surface was recreated");
+
+    graphics.resize(&winit::dpi::PhysicalSize::new(config.width / 2, config.height / 2));
+    present_frame(&graphics).expect("failed to present after resizing the recreated surface");
+
+    // Twice in a row is fine as well, e.g. lost again on the next frame
+    graphics.recreate_surface(window).expect("failed to recreate surface twice");
+    present_frame(&graphics).expect("failed to present after the second recreation");
+}
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use approx::assert_relative_eq;
-use rosu::hit_objects::{Object, ObjectKind};
+use rosu::hit_objects::{ConversionError, Object, ObjectKind};
 use rosu_map::Beatmap;
 
 fn get_other_tests_path() -> PathBuf {
@@ -14,7 +14,7 @@ fn test_slider_slides_stuff() {
         .join("slider_with_ticks_and_reverse.osu");
 
     let beatmap = Beatmap::from_path(base).unwrap();
-    let beatmap_objects = Object::from_rosu(&beatmap);
+    let beatmap_objects = Object::from_rosu(&beatmap).unwrap();
 
     assert_eq!(beatmap_objects.len(), 1);
     assert!(matches!(beatmap_objects[0].kind, ObjectKind::Slider(_)));
@@ -41,7 +41,7 @@ fn test_slider_ticks() {
     println!("{}", base.display());
 
     let beatmap = Beatmap::from_path(base).unwrap();
-    let beatmap_objects = Object::from_rosu(&beatmap);
+    let beatmap_objects = Object::from_rosu(&beatmap).unwrap();
 
     assert_eq!(beatmap_objects.len(), 1);
     assert!(matches!(beatmap_objects[0].kind, ObjectKind::Slider(_)));
@@ -59,7 +59,7 @@ fn test_slider_reverse_slides() {
     let base = get_other_tests_path().join("slider_fast_with_reverse_slides.osu");
 
     let beatmap = Beatmap::from_path(base).unwrap();
-    let beatmap_objects = Object::from_rosu(&beatmap);
+    let beatmap_objects = Object::from_rosu(&beatmap).unwrap();
 
     assert_eq!(beatmap_objects.len(), 1);
     assert!(matches!(beatmap_objects[0].kind, ObjectKind::Slider(_)));
@@ -78,7 +78,7 @@ fn test_slider_reverse_slides2() {
     let base = get_other_tests_path().join("slider_fast_with_reverse_slides2.osu");
 
     let beatmap = Beatmap::from_path(base).unwrap();
-    let beatmap_objects = Object::from_rosu(&beatmap);
+    let beatmap_objects = Object::from_rosu(&beatmap).unwrap();
 
     assert_eq!(beatmap_objects.len(), 2);
     assert!(matches!(beatmap_objects[0].kind, ObjectKind::Slider(_)));
@@ -102,3 +102,150 @@ fn test_slider_reverse_slides2() {
 }
 // 
 
+
+#[test]
+fn test_slider_before_first_timing_point() {
+    let base = get_other_tests_path().join("slider_before_timing_point.osu");
+
+    let beatmap = Beatmap::from_path(base).unwrap();
+    let beatmap_objects = Object::from_rosu(&beatmap).unwrap();
+
+    assert_eq!(beatmap_objects.len(), 2);
+    assert!(matches!(beatmap_objects[0].kind, ObjectKind::Slider(_)));
+}
+
+#[test]
+fn test_slider_without_timing_points() {
+    let base = get_other_tests_path().join("slider_without_timing_points.osu");
+
+    let beatmap = Beatmap::from_path(base).unwrap();
+    let beatmap_objects = Object::from_rosu(&beatmap).unwrap();
+
+    assert_eq!(beatmap_objects.len(), 1);
+
+    if let ObjectKind::Slider(slider) = &beatmap_objects[0].kind {
+        assert!(slider.duration.is_finite());
+        assert!(slider.ticks.iter().all(|x| x.time.is_finite()));
+    }
+}
+
+#[test]
+fn test_slider_zero_length() {
+    let base = get_other_tests_path().join("slider_zero_length.osu");
+
+    let beatmap = Beatmap::from_path(base).unwrap();
+    let beatmap_objects = Object::from_rosu(&beatmap).unwrap();
+
+    // Zero length slider is not playable as a slider
+    assert_eq!(beatmap_objects.len(), 1);
+    assert!(matches!(beatmap_objects[0].kind, ObjectKind::Circle(_)));
+}
+
+#[test]
+fn test_slider_nan_velocity() {
+    let base = get_other_tests_path().join("slider_nan_velocity.osu");
+
+    let beatmap = Beatmap::from_path(base).unwrap();
+    let beatmap_objects = Object::from_rosu(&beatmap).unwrap();
+
+    assert_eq!(beatmap_objects.len(), 1);
+
+    match &beatmap_objects[0].kind {
+        ObjectKind::Circle(_) => {},
+        ObjectKind::Slider(slider) => {
+            assert!(slider.duration.is_finite());
+            assert!(slider.checkpoints.iter().all(|x| x.time.is_finite()));
+        },
+    }
+}
+
+/// Fixtures with degenerate slider curves, each has a single slider
+const DEGENERATE_SLIDERS: [&str; 4] = [
+    "slider_duplicate_points.osu",
+    "slider_zero_length_segment.osu",
+    "slider_collinear_perfect.osu",
+    "slider_broken_tick_rate.osu",
+];
+
+#[test]
+fn test_degenerate_sliders_are_finite() {
+    for name in DEGENERATE_SLIDERS {
+        let beatmap = Beatmap::from_path(get_other_tests_path().join(name)).unwrap();
+        let beatmap_objects = Object::from_rosu(&beatmap).unwrap();
+
+        assert!(beatmap_objects.len() <= 1, "{name}");
+
+        for object in &beatmap_objects {
+            let ObjectKind::Slider(slider) = &object.kind else {
+                continue;
+            };
+
+            for i in 0..=100 {
+                let pos = slider.position_at(i as f64 / 100.0);
+                assert!(pos.x.is_finite() && pos.y.is_finite(), "{name}: curve at {i}%");
+            }
+
+            assert!(
+                slider.ticks.iter()
+                    .chain(slider.checkpoints.iter())
+                    .all(|x| x.pos.x.is_finite() && x.pos.y.is_finite() && x.time.is_finite()),
+                "{name}: ticks"
+            );
+
+            assert!(slider.reverse_arrows.iter().all(|x| x.angle.is_finite()), "{name}: reverse arrows");
+
+            let bbox = slider.bounding_box(32.0);
+            assert!(bbox.width().is_finite() && bbox.height().is_finite(), "{name}: bounding box");
+        }
+    }
+}
+
+#[test]
+fn test_unsupported_mode() {
+    let base = get_other_tests_path().join("mania.osu");
+
+    let beatmap = Beatmap::from_path(base).unwrap();
+
+    assert!(matches!(
+        Object::from_rosu(&beatmap),
+        Err(ConversionError::UnsupportedMode(_))
+    ));
+}
+
+#[test]
+fn test_slider_lazy_travel() {
+    let beatmap = Beatmap::from_path(get_other_tests_path().join("lazy_travel.osu")).unwrap();
+    let objects = Object::from_rosu(&beatmap).unwrap();
+
+    let sliders: Vec<_> = objects.iter()
+        .filter_map(|x| match &x.kind {
+            ObjectKind::Slider(slider) => Some(slider),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(sliders.len(), 3);
+
+    let assert_path = |index: usize, radius: f32, end: (f32, f32), travel: f32| {
+        let path = sliders[index].lazy_travel(radius);
+
+        assert_relative_eq!(path.end_pos.x, end.0, epsilon = 0.5);
+        assert_relative_eq!(path.end_pos.y, end.1, epsilon = 0.5);
+        assert_relative_eq!(path.travel_distance, travel, epsilon = 0.5);
+    };
+
+    // Straight, cursor stays one radius behind the tail
+    assert_eq!(objects[0].end_position(), (200.0, 0.0).into());
+    assert_path(0, 50.0, (150.0, 0.0), 150.0);
+
+    // U-shaped, cursor cuts the turn and ends up between the legs
+    assert_eq!(objects[1].end_position(), (100.0, 240.0).into());
+    assert_path(1, 50.0, (149.06, 230.36), 255.70);
+
+    // Long one with a tight radius follows the path almost exactly
+    assert_path(2, 5.0, (400.0, 85.0), 690.12);
+
+    // Cached path is only reused for the same radius
+    assert_path(0, 10.0, (190.0, 0.0), 190.0);
+    assert_path(0, 50.0, (150.0, 0.0), 150.0);
+}
@@ -0,0 +1,70 @@
+use rosu::{random_history::{RandomHistory, RANDOM_HISTORY_LEN}, rng::AppRng};
+
+const SEED: u64 = 727;
+const AMOUNT: usize = 50;
+
+/// Presses F2 `times` times starting at `current`
+fn picks(history: &mut RandomHistory, mut current: usize, times: usize) -> Vec<usize> {
+    (0..times)
+        .map(|_| {
+            current = history.pick(current, AMOUNT).unwrap();
+            current
+        })
+        .collect()
+}
+
+#[test]
+fn test_random_history_is_reproducible() {
+    let first = picks(&mut RandomHistory::new(AppRng::from_seed(SEED)), 0, 20);
+    let second = picks(&mut RandomHistory::new(AppRng::from_seed(SEED)), 0, 20);
+
+    assert_eq!(first, second);
+    assert!(first.iter().all(|x| *x < AMOUNT));
+
+    // Same seed through the app root, song select gets the first split
+    let third = picks(&mut RandomHistory::new(AppRng::from_seed(SEED).split()), 0, 20);
+    let fourth = picks(&mut RandomHistory::new(AppRng::from_seed(SEED).split()), 0, 20);
+
+    assert_eq!(third, fourth);
+}
+
+#[test]
+fn test_random_history_never_repeats_selection() {
+    let picked = picks(&mut RandomHistory::new(AppRng::from_seed(SEED)), 0, 200);
+
+    let mut current = 0;
+
+    for x in picked {
+        assert_ne!(x, current);
+        current = x;
+    }
+
+    // Single beatmap has nowhere else to go
+    let mut history = RandomHistory::new(AppRng::from_seed(SEED));
+    assert_eq!(history.pick(0, 1), Some(0));
+    assert_eq!(history.pick(0, 0), None);
+}
+
+#[test]
+fn test_random_history_rewind() {
+    let mut history = RandomHistory::new(AppRng::from_seed(SEED));
+    let picked = picks(&mut history, 7, 5);
+
+    // Back through every pick to where it started
+    let rewound: Vec<_> = std::iter::from_fn(|| history.rewind()).collect();
+
+    let mut expected = vec![7];
+    expected.extend_from_slice(&picked[..4]);
+    expected.reverse();
+
+    assert_eq!(rewound, expected);
+    assert_eq!(history.rewind(), None);
+
+    // Only the latest picks are kept
+    picks(&mut history, 0, RANDOM_HISTORY_LEN + 10);
+    assert_eq!(std::iter::from_fn(|| history.rewind()).count(), RANDOM_HISTORY_LEN);
+
+    picks(&mut history, 0, 3);
+    history.clear();
+    assert_eq!(history.rewind(), None);
+}
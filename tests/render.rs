@@ -0,0 +1,244 @@
+//! Rendering regression tests.
+//!
+//! Renders fixture beatmaps headlessly and checks pixels of the frame.
+//!
+//! Run with `cargo test --features render-tests --test render`.
+//! Software adapter can be forced with `WGPU_ADAPTER_NAME=llvmpipe`
+
+use std::sync::{Arc, RwLock};
+
+use rosu::{config::Config, gameplay_renderer::{GameplayFrame, GameplayRenderer}, graphics::Graphics, hit_objects::{hit_window::HitWindow, Object, ObjectKind}, math::{calc_circle_approach, calc_hitcircle_diameter, calc_playfield, calculate_preempt_fadein}, osu_renderer::OsuRenderer, skin_manager::SkinManager};
+use rosu_map::{util::Pos, Beatmap};
+use winit::dpi::PhysicalSize;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 480;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Max difference of a single channel considered as equal
+const CHANNEL_TOLERANCE: u8 = 8;
+
+/// Three circles 50ms apart, positions are set by the draw order test
+const OVERLAPPING_CIRCLES: &str = "tests/data/gameplay/overlapping_circles.osu";
+
+/// Time when all three overlapping circles are fully faded in
+const OVERLAPPING_CIRCLES_TIME: f64 = 700.0;
+
+/// Distance from the circle center, in circle radiuses, where both
+/// hitcircle and its overlay are opaque in the test skin
+const OPAQUE_RING: f32 = 0.86;
+
+struct RenderContext {
+    graphics: Arc<Graphics>,
+    config: Arc<RwLock<Config>>,
+    renderer: OsuRenderer,
+    target: wgpu::Texture,
+    readback: wgpu::Buffer,
+    padded_bytes_per_row: u32,
+}
+
+impl RenderContext {
+    fn new() -> Option<Self> {
+        let graphics = Arc::new(pollster::block_on(Graphics::headless_from_env(WIDTH, HEIGHT, FORMAT))?);
+
+        let skin_manager = Arc::new(RwLock::new(SkinManager::from_path("skin", &graphics)));
+        let config = Arc::new(RwLock::new(Config::default()));
+
+        let mut renderer = OsuRenderer::new(graphics.clone(), config.clone(), skin_manager);
+        renderer.on_resize(&PhysicalSize::new(WIDTH, HEIGHT));
+
+        let target = graphics.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("render test target"),
+            size: wgpu::Extent3d {
+                width: WIDTH,
+                height: HEIGHT,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let padded_bytes_per_row = (WIDTH * 4).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let readback = graphics.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("render test readback"),
+            size: (padded_bytes_per_row * HEIGHT) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            graphics,
+            config,
+            renderer,
+            target,
+            readback,
+            padded_bytes_per_row,
+        })
+    }
+
+    /// Same flow as `OsuState` uses for a single frame
+    fn render_frame(
+        &mut self,
+        time: f64,
+        objects: &mut [Object],
+        preempt: f32,
+        fadein: f32,
+        hit_window: &HitWindow,
+    ) -> Vec<u8> {
+        let view = self.target.create_view(&wgpu::TextureViewDescriptor::default());
+
+        self.clear(&view);
+
+        let frame = GameplayFrame { time, preempt, fadein, hit_window };
+        let mut gameplay = GameplayRenderer::new(true);
+
+        gameplay.prepare(&mut self.renderer, &frame, objects);
+        gameplay.render(&mut self.renderer, &view, objects)
+            .expect("failed to render objects");
+
+        self.read_pixels()
+    }
+
+    fn clear(&self, view: &wgpu::TextureView) {
+        let mut encoder = self.graphics.device.create_command_encoder(&Default::default());
+
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("render test clear"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        self.graphics.queue.submit([encoder.finish()]);
+    }
+
+    fn read_pixels(&self) -> Vec<u8> {
+        let mut encoder = self.graphics.device.create_command_encoder(&Default::default());
+
+        encoder.copy_texture_to_buffer(
+            self.target.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_bytes_per_row),
+                    rows_per_image: Some(HEIGHT),
+                },
+            },
+            wgpu::Extent3d {
+                width: WIDTH,
+                height: HEIGHT,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.graphics.queue.submit([encoder.finish()]);
+
+        let slice = self.readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |res| res.expect("failed to map readback buffer"));
+        self.graphics.device.poll(wgpu::Maintain::Wait);
+
+        let mut pixels = Vec::with_capacity((WIDTH * HEIGHT * 4) as usize);
+
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(self.padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..(WIDTH * 4) as usize]);
+            }
+        }
+
+        self.readback.unmap();
+
+        pixels
+    }
+}
+
+/// Later circle's approach circle crosses an opaque part of the earliest
+/// circle, which has to stay on top unless approach circles are batched
+fn check_draw_order(ctx: &mut RenderContext) -> Result<(), String> {
+    let beatmap = Beatmap::from_path(OVERLAPPING_CIRCLES).unwrap();
+    let mut objects = Object::from_rosu(&beatmap).unwrap();
+
+    let (preempt, fadein) = calculate_preempt_fadein(beatmap.approach_rate);
+    let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
+    ctx.renderer.on_cs_change(beatmap.circle_size);
+
+    let radius = calc_hitcircle_diameter(beatmap.circle_size) / 2.0;
+    let first = Pos { x: 256.0, y: 192.0 };
+    let overlap = Pos { x: first.x + radius * OPAQUE_RING, y: first.y };
+
+    // Middle of the approach circle line of the last circle goes through `overlap`
+    let (_, approach_scale) = calc_circle_approach(
+        OVERLAPPING_CIRCLES_TIME, objects[2].start_time, preempt, fadein, None, hit_window.x50,
+    ).expect("last circle is approaching");
+
+    let positions = [
+        first,
+        Pos { x: first.x - radius, y: first.y },
+        Pos { x: overlap.x + radius * approach_scale as f32 * 0.975, y: overlap.y },
+    ];
+
+    for (object, pos) in objects.iter_mut().zip(positions) {
+        if let ObjectKind::Circle(circle) = &mut object.kind {
+            circle.pos = pos;
+        }
+    }
+
+    let (scale, offsets) = calc_playfield(WIDTH as f32, HEIGHT as f32);
+    let x = (offsets.x + overlap.x * scale) as u32;
+    let y = (offsets.y + overlap.y * scale) as u32;
+    let pixel = |pixels: &[u8]| {
+        let i = ((y * WIDTH + x) * 4) as usize;
+        [pixels[i], pixels[i + 1], pixels[i + 2]]
+    };
+
+    let mut render = |objects: &mut [Object], batch: bool| {
+        ctx.config.write().unwrap().debug_batch_approach_circles = batch;
+        pixel(&ctx.render_frame(OVERLAPPING_CIRCLES_TIME, objects, preempt, fadein, &hit_window))
+    };
+
+    let alone = render(&mut objects[..1], false);
+    let interleaved = render(&mut objects, false);
+    let batched = render(&mut objects, true);
+
+    ctx.config.write().unwrap().debug_batch_approach_circles = false;
+
+    let same = |a: [u8; 3], b: [u8; 3]| a.iter().zip(b).all(|(a, b)| a.abs_diff(b) <= CHANNEL_TOLERANCE);
+
+    if !same(alone, interleaved) {
+        return Err(format!("earliest circle is covered: {interleaved:?}, expected {alone:?}"));
+    }
+
+    // Otherwise the check above proves nothing
+    if same(alone, batched) {
+        return Err(format!("approach circle doesn't cross the overlap pixel: {batched:?}"));
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let Some(mut ctx) = RenderContext::new() else {
+        eprintln!("No wgpu adapter available, skipping render tests");
+        return;
+    };
+
+    match check_draw_order(&mut ctx) {
+        Ok(_) => println!("ok draw order"),
+        Err(e) => panic!("FAILED draw order: {e}"),
+    }
+}
@@ -11,8 +11,9 @@ use winit::dpi::PhysicalSize;
 use winit::event_loop::{ControlFlow, EventLoopProxy};
 use winit::window::Window;
 use winit::{event_loop::EventLoop, platform::web::WindowAttributesExtWebSys};
-use rosu::hit_objects::ObjectKind;
+use rosu::gameplay_renderer::{GameplayFrame, GameplayRenderer};
 use rosu::{math::calculate_preempt_fadein, config::Config, graphics::Graphics, osu_renderer::OsuRenderer};
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use rosu::skin_manager::SkinManager;
 use rosu::hit_objects::Object;
@@ -23,20 +24,19 @@ use web_time::{Instant};
 
 static TEST_BEATMAP_BYTES: &[u8] = include_bytes!("../1.osu");
 
-struct OsuWasmState<'ows> {
-    osu_renderer: OsuRenderer<'ows>,
+struct OsuWasmState {
+    osu_renderer: OsuRenderer,
 
     clock: Timer,
     objects: Vec<Object>,
-    objects_render_queue: Vec<usize>,
-    objects_jedgments_render_qeue: Vec<usize>,
+    gameplay_renderer: GameplayRenderer,
     current_preempt: f32,
     current_fadein: f32,
     current_hit_window: HitWindow,
     last_frame_ts: Instant,
 }
 
-impl<'ows> OsuWasmState<'ows> {
+impl OsuWasmState {
     pub fn open_beatmap_from_bytes(&mut self, bytes: &[u8]) {
         let beatmap: rosu_map::Beatmap = rosu_map::from_bytes(&bytes).unwrap();
         info!("Read beatmap from bytes");
@@ -44,15 +44,20 @@ impl<'ows> OsuWasmState<'ows> {
         let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
         let cs = beatmap.circle_size;
         let (preempt, fadein) = calculate_preempt_fadein(beatmap.approach_rate);
-        self.objects = Object::from_rosu(&beatmap);
+        self.objects = match Object::from_rosu(&beatmap) {
+            Ok(objects) => objects,
+            Err(e) => {
+                error!("Failed to convert beatmap objects: {e}");
+                return;
+            },
+        };
         self.osu_renderer.on_cs_change(cs);
         self.current_preempt = preempt;
         self.current_fadein = fadein;
         self.current_hit_window = hit_window;
 
         self.clock.reset_time();
-        self.objects_render_queue.clear();
-        self.objects_jedgments_render_qeue.clear();
+        self.gameplay_renderer.reserve(self.objects.len());
     }
 
     pub fn on_resize(&mut self, new_size: &PhysicalSize<u32>) {
@@ -60,42 +65,16 @@ impl<'ows> OsuWasmState<'ows> {
     }
 
     pub fn on_draw(&mut self) {
-        self.objects_render_queue.clear();
-        self.objects_jedgments_render_qeue.clear();
-
         let time = self.clock.update();
 
-        // TODO: For now i'm just copied it from
-        // OsuState, for the future i probably 
-        // needed to keep them in sync :)
-        for (i, obj) in self.objects.iter_mut().enumerate().rev() {
-            if !obj.is_visible(time, self.current_preempt, &self.current_hit_window) {
-                continue;
-            }
-
-            match &mut obj.kind {
-                ObjectKind::Slider(slider) => {
-                    self.osu_renderer.prepare_and_render_slider_texture(
-                        slider, 
-                    );
-                }
-                _ => {},
-            }
-
-
-            self.objects_render_queue.push(i);
-        }
-
-
-        self.osu_renderer.prepare_objects(
-            time, self.current_preempt, self.current_fadein,
-            &self.objects_render_queue, &self.objects,
-            &self.current_hit_window,
-        );
-
-        self.osu_renderer.prepare();
+        let frame = GameplayFrame {
+            time,
+            preempt: self.current_preempt,
+            fadein: self.current_fadein,
+            hit_window: &self.current_hit_window,
+        };
 
-        self.osu_renderer.write_buffers();
+        self.gameplay_renderer.prepare(&mut self.osu_renderer, &frame, &mut self.objects);
 
         // Render thingy
         let output = self.osu_renderer.get_graphics().get_current_texture().unwrap();
@@ -104,10 +83,7 @@ impl<'ows> OsuWasmState<'ows> {
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
-        self.osu_renderer.render_objects(
-            &view,
-            &self.objects_render_queue, &self.objects,
-        ).unwrap();
+        self.gameplay_renderer.render(&mut self.osu_renderer, &view, &self.objects).unwrap();
 
         output.present();
         
@@ -116,21 +92,21 @@ impl<'ows> OsuWasmState<'ows> {
     }
 }
 
-struct App<'app> {
+struct App {
     window: Option<Arc<Window>>,
     // Used as oneshot channel to initialize graphics in async manner
     proxy: Option<EventLoopProxy<AppEvents>>,
-    graphics: Option<Arc<Graphics<'app>>>,
-    osu_state: Option<OsuWasmState<'app>>,
+    graphics: Option<Arc<Graphics>>,
+    osu_state: Option<OsuWasmState>,
     last_ts: Instant,
 }
 
 enum AppEvents {
-    GraphicsInitialized(Arc<Graphics<'static>>, Arc<RwLock<SkinManager>>),
+    GraphicsInitialized(Arc<Graphics>, Arc<RwLock<SkinManager>>),
     Resize(PhysicalSize<u32>),
 }
 
-impl<'app> App<'app> {
+impl App {
     fn new(proxy: EventLoopProxy<AppEvents>) -> Self {
         Self {
             window: None,
@@ -142,7 +118,7 @@ impl<'app> App<'app> {
     }
 }
 
-async fn initialize_graphics<'a>(window: Arc<Window>) -> GraphicsInitialized<'a> {
+async fn initialize_graphics(window: Arc<Window>) -> GraphicsInitialized {
     let size = window.inner_size();
     let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
         backends: wgpu::Backends::GL,
@@ -180,6 +156,7 @@ async fn initialize_graphics<'a>(window: Arc<Window>) -> GraphicsInitialized<'a>
     info!("Initialized device and queue");
     
     GraphicsInitialized {
+        instance,
         surface,
         adapter,
         device,
@@ -188,7 +165,7 @@ async fn initialize_graphics<'a>(window: Arc<Window>) -> GraphicsInitialized<'a>
     }
 }
 
-async fn initialize_skin(graphics: &Graphics<'_>) -> SkinManager {
+async fn initialize_skin(graphics: &Graphics) -> SkinManager {
     let client = reqwest_wasm::Client::new();
 
     let hit_circle = Texture::from_bytes(
@@ -336,6 +313,7 @@ async fn initialize_skin(graphics: &Graphics<'_>) -> SkinManager {
     );
 
     SkinManager {
+        path: PathBuf::from("./skin"),
         ini: SkinIni::default(),
         hit_circle,
         hit_circle_overlay: empty,
@@ -348,7 +326,7 @@ async fn initialize_skin(graphics: &Graphics<'_>) -> SkinManager {
     }
 }
 
-impl<'app> ApplicationHandler<AppEvents> for App<'app> {
+impl ApplicationHandler<AppEvents> for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         let attrs = Window::default_attributes()
             .with_canvas(None);
@@ -430,8 +408,7 @@ impl<'app> ApplicationHandler<AppEvents> for App<'app> {
                     osu_renderer,
                     clock: Timer::new(),
                     objects: Vec::new(),
-                    objects_render_queue: Vec::new(),
-                    objects_jedgments_render_qeue: Vec::new(),
+                    gameplay_renderer: GameplayRenderer::new(false),
                     current_preempt: 0.0,
                     current_fadein: 0.0,
                     current_hit_window: HitWindow::from_od(5.0),
@@ -528,7 +505,7 @@ async fn main() {
     let hit_window = HitWindow::from_od(beatmap.overall_difficulty);
     let cs = beatmap.circle_size;
     let (preempt, fadein) = calculate_preempt_fadein(beatmap.approach_rate);
-    let mut our_objects = Object::from_rosu(&beatmap);
+    let mut our_objects = Object::from_rosu(&beatmap).expect("failed to convert test beatmap");
     osu_renderer.on_cs_change(cs);
 
     let mut objects_render_queue: Vec<usize> = Vec::new();